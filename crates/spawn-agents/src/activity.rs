@@ -0,0 +1,202 @@
+//! Git activity signals for search ranking
+//!
+//! One `git log` pass over the recent history of a workspace yields, per
+//! file, when it last changed, how often it changes and who changes it.
+//! Search results are then blended with that activity so files under active
+//! development rank above dead code with similar content.
+
+use chrono::{DateTime, TimeZone, Utc};
+use crate::vector_memory::SearchResult;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::warn;
+
+/// History considered when scoring activity
+const HISTORY_DAYS: u32 = 365;
+/// Age at which the recency signal has halved
+const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+/// How long a workspace's activity is reused before `git log` runs again
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// Default share of the final score taken by activity
+pub const DEFAULT_ACTIVITY_WEIGHT: f32 = 0.2;
+
+/// Recent git history of one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileActivity {
+    pub last_modified: DateTime<Utc>,
+    /// Commits touching the file within the history window
+    pub commits: u32,
+    /// Author with the most of those commits
+    pub owner: String,
+    #[serde(skip)]
+    authors: HashMap<String, u32>,
+}
+
+/// Per-file activity of a workspace, relative paths as keys
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceActivity {
+    files: HashMap<String, FileActivity>,
+    max_commits: u32,
+}
+
+impl WorkspaceActivity {
+    /// Read the workspace's recent history; empty if it isn't a git repository
+    pub async fn load(root: &Path) -> Self {
+        let since = format!("--since={}.days", HISTORY_DAYS);
+        let output = Command::new("git")
+            .args(["log", &since, "--no-merges", "--name-only", "--format=%x00%ct%x09%an"])
+            .current_dir(root)
+            .kill_on_drop(true)
+            .output()
+            .await;
+        match output {
+            Ok(output) if output.status.success() => Self::parse(&String::from_utf8_lossy(&output.stdout)),
+            Ok(output) => {
+                warn!(root = %root.display(), error = %String::from_utf8_lossy(&output.stderr).trim(), "git log failed");
+                Self::default()
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to run git");
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse `git log --name-only --format=%x00%ct%x09%an` output
+    fn parse(log: &str) -> Self {
+        let mut files: HashMap<String, FileActivity> = HashMap::new();
+        for commit in log.split('\0').filter(|c| !c.trim().is_empty()) {
+            let mut lines = commit.lines();
+            let Some((timestamp, author)) = lines.next().and_then(|h| h.split_once('\t')) else {
+                continue;
+            };
+            let Some(time) = timestamp.parse().ok().and_then(|t| Utc.timestamp_opt(t, 0).single()) else {
+                continue;
+            };
+            for path in lines.map(str::trim).filter(|l| !l.is_empty()) {
+                let entry = files.entry(path.to_string()).or_insert_with(|| FileActivity {
+                    last_modified: time,
+                    commits: 0,
+                    owner: String::new(),
+                    authors: HashMap::new(),
+                });
+                entry.last_modified = entry.last_modified.max(time);
+                entry.commits += 1;
+                *entry.authors.entry(author.to_string()).or_default() += 1;
+            }
+        }
+        for activity in files.values_mut() {
+            activity.owner = activity.authors.iter()
+                .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(author, _)| author.clone())
+                .unwrap_or_default();
+        }
+        let max_commits = files.values().map(|f| f.commits).max().unwrap_or(0);
+        Self { files, max_commits }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&FileActivity> {
+        self.files.get(path.trim_start_matches("./"))
+    }
+
+    /// 0.0 (untouched in the window) to 1.0 (changed just now, most churned file)
+    pub fn score(&self, path: &str, now: DateTime<Utc>) -> f32 {
+        let Some(activity) = self.get(path) else {
+            return 0.0;
+        };
+        let age_days = (now - activity.last_modified).num_seconds().max(0) as f32 / 86_400.0;
+        let recency = 0.5f32.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+        let frequency = if self.max_commits > 0 {
+            (1.0 + activity.commits as f32).ln() / (1.0 + self.max_commits as f32).ln()
+        } else {
+            0.0
+        };
+        0.6 * recency + 0.4 * frequency
+    }
+}
+
+/// Blend a relevance score with a file's activity; `weight` is clamped to 0..=1
+pub fn blend(relevance: f32, activity: f32, weight: f32) -> f32 {
+    let weight = weight.clamp(0.0, 1.0);
+    relevance * (1.0 - weight) + activity * weight
+}
+
+/// Re-rank code search results by relevance blended with git activity.
+///
+/// Each result's `metadata.file_path` (absolute or relative to `root`) is
+/// looked up; matches gain a `git` entry with the file's activity and every
+/// result an `activity_score`. `similarity` itself is left untouched.
+pub fn rank_by_activity(results: &mut [SearchResult], activity: &WorkspaceActivity, root: &Path, weight: f32) {
+    let now = Utc::now();
+    let mut scored: Vec<(f32, usize)> = Vec::with_capacity(results.len());
+    for (i, result) in results.iter_mut().enumerate() {
+        let path = result.metadata["file_path"].as_str().map(|p| {
+            Path::new(p).strip_prefix(root).unwrap_or(Path::new(p)).to_string_lossy().to_string()
+        });
+        let score = path.as_deref().map(|p| activity.score(p, now)).unwrap_or(0.0);
+        if let Some(meta) = result.metadata.as_object_mut() {
+            if let Some(file) = path.as_deref().and_then(|p| activity.get(p)) {
+                meta.insert("git".into(), serde_json::json!(file));
+            }
+            meta.insert("activity_score".into(), serde_json::json!(score));
+        }
+        scored.push((blend(result.similarity, score, weight), i));
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let order: Vec<SearchResult> = scored.iter().map(|&(_, i)| results[i].clone()).collect();
+    results.clone_from_slice(&order);
+}
+
+/// Workspace activity cached per root for a few minutes
+#[derive(Default)]
+pub struct ActivityCache {
+    entries: Mutex<HashMap<PathBuf, (Instant, Arc<WorkspaceActivity>)>>,
+}
+
+impl ActivityCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get(&self, root: &Path) -> Arc<WorkspaceActivity> {
+        if let Some((loaded, activity)) = self.entries.lock().unwrap().get(root) {
+            if loaded.elapsed() < CACHE_TTL {
+                return activity.clone();
+            }
+        }
+        let activity = Arc::new(WorkspaceActivity::load(root).await);
+        self.entries.lock().unwrap().insert(root.to_path_buf(), (Instant::now(), activity.clone()));
+        activity
+    }
+
+    /// Load `root`'s activity afresh on the next `get`
+    pub fn invalidate(&self, root: &Path) {
+        self.entries.lock().unwrap().remove(root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_churned_files_score_higher() {
+        let now = Utc::now().timestamp();
+        let log = format!(
+            "\0{}\tana\n\nsrc/hot.rs\n\0{}\tbo\n\nsrc/hot.rs\nsrc/cold.rs\n\0{}\tana\n\nsrc/hot.rs\n",
+            now - 3_600, now - 200 * 86_400, now - 86_400,
+        );
+        let activity = WorkspaceActivity::parse(&log);
+
+        let hot = activity.get("src/hot.rs").unwrap();
+        assert_eq!((hot.commits, hot.owner.as_str()), (3, "ana"));
+        let now = Utc::now();
+        assert!(activity.score("src/hot.rs", now) > activity.score("./src/cold.rs", now));
+        assert_eq!(activity.score("src/unknown.rs", now), 0.0);
+    }
+}
@@ -0,0 +1,90 @@
+//! Agent personas
+//!
+//! The built-in architect, coder and reviewer personas, plus any defined in a
+//! JSON file (an array of `Agent` objects). File entries replace built-ins
+//! with the same ID.
+
+use spawn_core::{Agent, AgentRegistry, Result, SpawnError};
+use std::path::Path;
+
+const ARCHITECT_PROMPT: &str = "You are a software architect. Study the existing code before \
+proposing changes, break the goal into small steps, and write down the plan and its trade-offs. \
+Do not edit files yourself.";
+
+const CODER_PROMPT: &str = "You are a senior software engineer. Make focused changes that follow \
+the conventions of the surrounding code, and build and test what you change.";
+
+const REVIEWER_PROMPT: &str = "You are a code reviewer. Read the changes carefully, run the tests \
+and linters, and report bugs, risky patterns and missing tests. Do not modify files.";
+
+/// Agents held in memory, in registration order
+#[derive(Debug, Clone, Default)]
+pub struct StaticAgentRegistry {
+    agents: Vec<Agent>,
+}
+
+impl StaticAgentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Architect, coder and reviewer personas
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Agent::new("architect", "architect", ARCHITECT_PROMPT)
+            .with_tools(["shell", "deps"]));
+        registry.register(Agent::new("coder", "coder", CODER_PROMPT));
+        registry.register(Agent::new("reviewer", "reviewer", REVIEWER_PROMPT)
+            .with_tools(["shell", "lint", "coverage", "deps"]));
+        registry
+    }
+
+    /// Add an agent, replacing any with the same ID
+    pub fn register(&mut self, agent: Agent) {
+        match self.agents.iter_mut().find(|a| a.id == agent.id) {
+            Some(existing) => *existing = agent,
+            None => self.agents.push(agent),
+        }
+    }
+
+    /// Register the agents defined in a JSON file
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SpawnError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let agents: Vec<Agent> = serde_json::from_str(&content)?;
+        let count = agents.len();
+        for agent in agents {
+            self.register(agent);
+        }
+        Ok(count)
+    }
+}
+
+impl AgentRegistry for StaticAgentRegistry {
+    fn get(&self, id_or_name: &str) -> Option<Agent> {
+        self.agents.iter()
+            .find(|a| a.id == id_or_name || a.name.eq_ignore_ascii_case(id_or_name))
+            .cloned()
+    }
+
+    fn list(&self) -> Vec<Agent> {
+        self.agents.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registering_replaces_by_id() {
+        let mut registry = StaticAgentRegistry::builtin();
+        registry.register(Agent::new("Reviewer", "reviewer", "Be terse.").with_model("cheap/model"));
+
+        assert_eq!(registry.list().len(), 3);
+        let reviewer = registry.get("reviewer").unwrap();
+        assert_eq!(reviewer.model.as_deref(), Some("cheap/model"));
+        assert!(reviewer.allows_tool("image_generate"));
+        assert!(!registry.get("architect").unwrap().allows_tool("lint"));
+    }
+}
@@ -0,0 +1,275 @@
+//! Prompt assembly
+//!
+//! Fits a mission's conversation into the model's context window. Messages
+//! are tagged with the section they belong to (`section` metadata); the
+//! prompt budget is split across sections by the configured weights, with
+//! whatever a section doesn't need going to the others. A section over its
+//! share is cut down: pinned sections (system, plan, retrieved) lose their
+//! tail, while conversation and tool results drop their oldest messages and
+//! keep the start and end of one that only partly fits. The pinned sections
+//! leading the prompt change little between steps, so the end of each is
+//! marked as a prompt cache breakpoint.
+
+use serde::Serialize;
+use spawn_ai::message_tokens;
+use spawn_core::{ChatMessage, ContextConfig, MessageContent};
+
+/// Below this, a partly fitting message is dropped rather than cut
+const MIN_PARTIAL_TOKENS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    /// System prompt and goal
+    System,
+    Plan,
+    /// Knowledge base chunks
+    Retrieved,
+    /// Model turns and anything untagged
+    Recent,
+    ToolResults,
+}
+
+impl Section {
+    const ALL: [Section; 5] = [Section::System, Section::Plan, Section::Retrieved, Section::Recent, Section::ToolResults];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Section::System => "system",
+            Section::Plan => "plan",
+            Section::Retrieved => "retrieved",
+            Section::Recent => "recent",
+            Section::ToolResults => "tool_results",
+        }
+    }
+
+    /// The section a message was tagged with; `Recent` if none
+    pub fn of(message: &ChatMessage) -> Section {
+        let tag = message.metadata.as_ref().and_then(|m| m["section"].as_str());
+        Self::ALL.into_iter().find(|s| Some(s.as_str()) == tag).unwrap_or(Section::Recent)
+    }
+
+    /// Pinned sections keep their start; the others keep their newest messages
+    pub(crate) fn keeps_newest(&self) -> bool {
+        matches!(self, Section::Recent | Section::ToolResults)
+    }
+
+    fn weight(&self, config: &ContextConfig) -> f32 {
+        let weights = &config.weights;
+        match self {
+            Section::System => weights.system,
+            Section::Plan => weights.plan,
+            Section::Retrieved => weights.retrieved,
+            Section::Recent => weights.recent,
+            Section::ToolResults => weights.tool_results,
+        }
+    }
+}
+
+/// Tag a message with the section it belongs to
+pub fn tagged(message: ChatMessage, section: Section) -> ChatMessage {
+    message.with_metadata("section", section.as_str())
+}
+
+/// How one section fared
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionUsage {
+    pub section: Section,
+    /// Tokens allotted
+    pub budget: usize,
+    /// Tokens used after truncation
+    pub tokens: usize,
+    /// Messages dropped or cut to fit
+    pub truncated: usize,
+}
+
+/// Token use of an assembled prompt, per section
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextBreakdown {
+    pub sections: Vec<SectionUsage>,
+    pub total: usize,
+    pub budget: usize,
+}
+
+impl std::fmt::Display for ContextBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Context {}/{} tokens:", self.total, self.budget)?;
+        for usage in self.sections.iter().filter(|u| u.tokens > 0 || u.truncated > 0) {
+            write!(f, " {} {}/{}", usage.section.as_str(), usage.tokens, usage.budget)?;
+            if usage.truncated > 0 {
+                write!(f, " ({} truncated)", usage.truncated)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct PromptAssembler {
+    config: ContextConfig,
+}
+
+impl PromptAssembler {
+    pub fn new(config: ContextConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ContextConfig {
+        &self.config
+    }
+
+    /// The messages to send to `model`, within the prompt budget as its
+    /// tokenizer counts, and what each section used
+    pub fn assemble(&self, messages: &[ChatMessage], model: &str) -> (Vec<ChatMessage>, ContextBreakdown) {
+        let budget = self.config.prompt_tokens();
+        let tokens: Vec<usize> = messages.iter().map(|m| message_tokens(m, model)).collect();
+        let demands: Vec<usize> = Section::ALL.iter()
+            .map(|s| (0..messages.len()).filter(|&j| Section::of(&messages[j]) == *s).map(|j| tokens[j]).sum())
+            .collect();
+        let weights: Vec<f32> = Section::ALL.iter().map(|s| s.weight(&self.config)).collect();
+        let allotted = allocate(budget, &demands, &weights);
+
+        let mut kept: Vec<Option<ChatMessage>> = vec![None; messages.len()];
+        let mut sections = Vec::with_capacity(Section::ALL.len());
+        for (i, section) in Section::ALL.into_iter().enumerate() {
+            let indices: Vec<usize> = (0..messages.len()).filter(|&j| Section::of(&messages[j]) == section).collect();
+            let (used, truncated) = fit(messages, &tokens, &indices, allotted[i], section.keeps_newest(), model, &mut kept);
+            sections.push(SectionUsage { section, budget: allotted[i], tokens: used, truncated });
+        }
+
+        let total = sections.iter().map(|s| s.tokens).sum();
+        let mut prompt: Vec<ChatMessage> = kept.into_iter().flatten().collect();
+        if self.config.prompt_caching {
+            mark_cache_breakpoints(&mut prompt);
+        }
+        (prompt, ContextBreakdown { sections, total, budget })
+    }
+}
+
+/// Flag the last message of each pinned section in the prompt's leading run
+/// of pinned messages
+fn mark_cache_breakpoints(prompt: &mut [ChatMessage]) {
+    let pinned = prompt.iter().take_while(|m| !Section::of(m).keeps_newest()).count();
+    for i in 0..pinned {
+        if i + 1 == pinned || Section::of(&prompt[i]) != Section::of(&prompt[i + 1]) {
+            prompt[i].cache_breakpoint = true;
+        }
+    }
+}
+
+/// Split `budget` by weight. Sections asking for less than their share get
+/// what they ask for and the remainder is shared out again among the rest.
+fn allocate(budget: usize, demands: &[usize], weights: &[f32]) -> Vec<usize> {
+    let mut allotted = vec![0; demands.len()];
+    let mut open: Vec<usize> = (0..demands.len()).filter(|&i| demands[i] > 0).collect();
+    let mut remaining = budget;
+    while !open.is_empty() {
+        let total_weight: f32 = open.iter().map(|&i| weights[i]).sum();
+        let share = |i: usize| if total_weight > 0.0 {
+            (remaining as f64 * f64::from(weights[i]) / f64::from(total_weight)) as usize
+        } else {
+            remaining / open.len()
+        };
+        let satisfied: Vec<usize> = open.iter().copied().filter(|&i| demands[i] <= share(i)).collect();
+        if satisfied.is_empty() {
+            for &i in &open {
+                allotted[i] = share(i);
+            }
+            break;
+        }
+        for &i in &satisfied {
+            allotted[i] = demands[i];
+            remaining -= demands[i];
+        }
+        open.retain(|i| !satisfied.contains(i));
+    }
+    allotted
+}
+
+/// Put as much of the section's messages into `kept` as `budget` allows,
+/// returning the tokens used and how many messages were dropped or cut
+fn fit(
+    messages: &[ChatMessage],
+    token_counts: &[usize],
+    indices: &[usize],
+    budget: usize,
+    keep_newest: bool,
+    model: &str,
+    kept: &mut [Option<ChatMessage>],
+) -> (usize, usize) {
+    let order: Vec<usize> = if keep_newest { indices.iter().rev().copied().collect() } else { indices.to_vec() };
+    let (mut used, mut truncated) = (0, 0);
+    for (n, &i) in order.iter().enumerate() {
+        let message = &messages[i];
+        let tokens = token_counts[i];
+        if used + tokens <= budget {
+            kept[i] = Some(message.clone());
+            used += tokens;
+            continue;
+        }
+        // Cut this one down if enough room is left, and drop the rest
+        let room = budget - used;
+        if room >= MIN_PARTIAL_TOKENS {
+            if let MessageContent::Text(text) = &message.content {
+                let mut cut = message.clone();
+                // Sized at about four characters per token
+                cut.content = MessageContent::Text(shorten(text, (room - 4) * 4, keep_newest));
+                used += message_tokens(&cut, model);
+                kept[i] = Some(cut);
+            }
+        }
+        truncated += order.len() - n;
+        break;
+    }
+    (used, truncated)
+}
+
+/// `text` cut to about `max_chars`: its start, or with `keep_ends` its start
+/// and end, around a note of how much was left out
+fn shorten(text: &str, max_chars: usize, keep_ends: bool) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let note = |omitted: usize| format!("\n[… {} tokens truncated …]\n", omitted.div_ceil(4));
+    // Leave room for the note itself
+    let keep = max_chars.saturating_sub(40).min(chars.len());
+    let omitted = chars.len() - keep;
+    if keep_ends {
+        let head = keep / 2;
+        let tail = keep - head;
+        let start: String = chars[..head].iter().collect();
+        let end: String = chars[chars.len() - tail..].iter().collect();
+        format!("{}{}{}", start, note(omitted), end)
+    } else {
+        let start: String = chars[..keep].iter().collect();
+        format!("{}{}", start, note(omitted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_passes_unused_share_on() {
+        // Section 0 needs little, so 1 and 2 split the rest 1:3
+        let allotted = allocate(1000, &[100, 5000, 5000], &[1.0, 1.0, 3.0]);
+        assert_eq!(allotted, vec![100, 225, 675]);
+        assert_eq!(allocate(1000, &[0, 10, 20], &[1.0, 1.0, 1.0]), vec![0, 10, 20]);
+    }
+
+    #[test]
+    fn test_assemble_drops_oldest_conversation() {
+        let assembler = PromptAssembler::new(ContextConfig { window_tokens: 400, reserve_tokens: 0, ..Default::default() });
+        let mut messages = vec![tagged(ChatMessage::system("You are an agent."), Section::System)];
+        for n in 0..10 {
+            messages.push(ChatMessage::assistant(format!("step {} {}", n, "lorem ipsum ".repeat(40))));
+        }
+
+        let (prompt, breakdown) = assembler.assemble(&messages, "openai/gpt-4o");
+        assert_eq!(prompt[0].content.text(), "You are an agent.");
+        assert!(prompt[0].cache_breakpoint && !prompt[1].cache_breakpoint);
+        assert_eq!(prompt.last().unwrap().content.text(), messages[10].content.text());
+        assert!(prompt.len() < messages.len());
+        assert!(breakdown.total <= 400);
+        let recent = breakdown.sections.iter().find(|s| s.section == Section::Recent).unwrap();
+        assert!(recent.truncated > 0);
+    }
+}
@@ -0,0 +1,148 @@
+//! Citations of retrieved knowledge base context
+//!
+//! Each documentation chunk put in front of the model is labelled with a
+//! marker (`[S1]`, `[S2]`, ...) and the model is asked to cite the markers it
+//! relied on. Markers in the final answer are mapped back to the document and
+//! the lines the chunk came from, so claims can be checked against the source.
+
+use spawn_core::{Citation, Result};
+use tracing::warn;
+
+use crate::memory::Database;
+use crate::vector_memory::SearchResult;
+
+/// Longest excerpt kept per citation
+const MAX_EXCERPT_CHARS: usize = 240;
+
+/// A chunk given to the model, with the marker it may cite it by
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub marker: String,
+    pub doc_id: String,
+    pub title: String,
+    pub filename: String,
+    pub text: String,
+}
+
+impl Source {
+    /// Label search results `S1`, `S2`, ... in order
+    pub fn from_results(results: Vec<SearchResult>) -> Vec<Self> {
+        results.into_iter()
+            .enumerate()
+            .map(|(i, r)| Source {
+                marker: format!("S{}", i + 1),
+                doc_id: r.metadata["doc_id"].as_str().unwrap_or_default().to_string(),
+                title: r.metadata["title"].as_str().unwrap_or("document").to_string(),
+                filename: r.metadata["filename"].as_str().unwrap_or_default().to_string(),
+                text: r.content_preview,
+            })
+            .collect()
+    }
+}
+
+/// The sources as a context message, each headed by its marker
+pub fn render(sources: &[Source]) -> String {
+    let sections: Vec<String> = sources.iter()
+        .map(|s| format!("[{}] From \"{}\" ({}):\n{}", s.marker, s.title, s.filename, s.text))
+        .collect();
+    format!(
+        "Project documentation relevant to this goal. Follow its conventions. \
+         When your answer relies on a section, cite its marker, e.g. [S1].\n\n{}",
+        sections.join("\n\n---\n\n")
+    )
+}
+
+/// Markers cited in `answer`, in order of first appearance. Accepts `[S1]`
+/// and grouped forms like `[S1, S3]`.
+pub fn cited_markers(answer: &str) -> Vec<String> {
+    let mut markers = Vec::new();
+    let mut rest = answer;
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else { break };
+        let group = &rest[..close];
+        let parts: Vec<&str> = group.split(',').map(str::trim).collect();
+        let all_markers = parts.iter().all(|p| {
+            p.len() > 1 && p.starts_with('S') && p[1..].chars().all(|c| c.is_ascii_digit())
+        });
+        if all_markers {
+            for part in parts {
+                if !markers.iter().any(|m| m == part) {
+                    markers.push(part.to_string());
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+    }
+    markers
+}
+
+/// 1-based line range of `chunk` within `content`. Chunks are prefixed with
+/// their nearest heading, which is skipped unless it is all there is.
+pub fn locate(content: &str, chunk: &str) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = chunk.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    let body = match lines.split_first() {
+        Some((first, rest)) if first.starts_with('#') && !rest.is_empty() => rest,
+        _ => &lines[..],
+    };
+    let (first, last) = (body.first()?, body.last()?);
+
+    let content: Vec<&str> = content.lines().map(str::trim).collect();
+    let start = content.iter().position(|l| !l.is_empty() && l.contains(first))?;
+    let end = content[start..].iter()
+        .position(|l| !l.is_empty() && l.contains(last))
+        .map_or(start, |i| start + i);
+    Some((start + 1, end + 1))
+}
+
+/// Citations for the markers `answer` used; unknown markers are dropped
+pub async fn resolve(db: &Database, sources: &[Source], answer: &str) -> Result<Vec<Citation>> {
+    let mut citations = Vec::new();
+    for marker in cited_markers(answer) {
+        let Some(source) = sources.iter().find(|s| s.marker == marker) else {
+            warn!(marker = %marker, "Answer cited a source it was not given");
+            continue;
+        };
+        let lines = db.get_document(&source.doc_id).await?
+            .and_then(|doc| locate(&doc.content, &source.text));
+
+        let mut excerpt = source.text.clone();
+        if excerpt.len() > MAX_EXCERPT_CHARS {
+            let mut cut = MAX_EXCERPT_CHARS;
+            while !excerpt.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            excerpt.truncate(cut);
+            excerpt.push_str("...");
+        }
+        citations.push(Citation {
+            marker,
+            doc_id: source.doc_id.clone(),
+            title: source.title.clone(),
+            filename: source.filename.clone(),
+            start_line: lines.map(|(start, _)| start),
+            end_line: lines.map(|(_, end)| end),
+            excerpt,
+        });
+    }
+    Ok(citations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cited_markers_accepts_groups_and_skips_other_brackets() {
+        let answer = "DONE: Used the retry policy [S2] and config layout [S1, S2]. See [docs] and [S].";
+        assert_eq!(cited_markers(answer), ["S2", "S1"]);
+    }
+
+    #[test]
+    fn test_locate_skips_heading_prefix() {
+        let content = "# Guide\n\nIntro.\n\n## Errors\n\nWrap errors in SpawnError.\nNever panic.\n\nOther.";
+        let chunk = "## Errors\n\nWrap errors in SpawnError.\nNever panic.";
+        assert_eq!(locate(content, chunk), Some((7, 8)));
+        assert_eq!(locate(content, "Not in the document"), None);
+    }
+}
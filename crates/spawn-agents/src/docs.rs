@@ -0,0 +1,187 @@
+//! Knowledge base documents
+//!
+//! Uploaded Markdown, PDF and HTML is reduced to plain text and split into
+//! heading-aware chunks sized for embedding.
+
+use spawn_core::{DocFormat, Result, SpawnError};
+
+/// Upper bound on chunk size; keeps each embedding focused on one topic
+pub const MAX_CHUNK_CHARS: usize = 1200;
+
+/// Plain text of an uploaded document
+pub fn extract_text(format: DocFormat, bytes: &[u8]) -> Result<String> {
+    let text = match format {
+        DocFormat::Markdown => String::from_utf8_lossy(bytes).into_owned(),
+        DocFormat::Html => html_to_text(&String::from_utf8_lossy(bytes)),
+        DocFormat::Pdf => {
+            // pdf-extract panics on some malformed files instead of erroring
+            let bytes = bytes.to_vec();
+            std::panic::catch_unwind(move || pdf_extract::extract_text_from_mem(&bytes))
+                .map_err(|_| SpawnError::Internal("Unreadable PDF".into()))?
+                .map_err(|e| SpawnError::Internal(format!("Failed to read PDF: {}", e)))?
+        }
+    };
+    Ok(text.replace("\r\n", "\n"))
+}
+
+/// Strip tags, keeping block structure and turning headings into `#` lines
+fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    let mut skip_until: Option<String> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skip_until.is_none() {
+            out.push_str(&decode_entities(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('>') else { break };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag.trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        if let Some(until) = &skip_until {
+            if closing && name == *until {
+                skip_until = None;
+            }
+            continue;
+        }
+
+        match name.as_str() {
+            "script" | "style" | "head" | "noscript" if !closing => skip_until = Some(name),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" if !closing => {
+                let level = name[1..].parse().unwrap_or(1);
+                out.push_str("\n\n");
+                out.push_str(&"#".repeat(level));
+                out.push(' ');
+            }
+            "li" if !closing => out.push_str("\n- "),
+            "p" | "div" | "section" | "article" | "pre" | "table" | "ul" | "ol"
+            | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => out.push_str("\n\n"),
+            "br" | "tr" => out.push('\n'),
+            _ => {}
+        }
+    }
+    if skip_until.is_none() {
+        out.push_str(&decode_entities(rest));
+    }
+
+    // Collapse the runs of blank lines left by nested blocks
+    let mut text = String::new();
+    let mut blank = 0;
+    for line in out.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !text.is_empty() {
+            text.push_str(if blank > 1 || line.starts_with('#') { "\n\n" } else { "\n" });
+        }
+        blank = 0;
+        text.push_str(line.trim_start());
+    }
+    text.push('\n');
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Split text into chunks of at most `max_chars`, breaking at headings and
+/// paragraphs. Each chunk is prefixed with its nearest heading so it still
+/// makes sense when retrieved on its own.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut heading = String::new();
+    let mut current = String::new();
+
+    let flush = |current: &mut String, chunks: &mut Vec<String>| {
+        let chunk = current.trim();
+        // A heading with nothing under it is not worth embedding
+        let heading_only = chunk.starts_with('#') && !chunk.contains('\n');
+        if !chunk.is_empty() && !heading_only {
+            chunks.push(chunk.to_string());
+        }
+        current.clear();
+    };
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if paragraph.starts_with('#') {
+            flush(&mut current, &mut chunks);
+            heading = paragraph.lines().next().unwrap_or_default().to_string();
+            current.push_str(paragraph);
+            continue;
+        }
+
+        if !current.is_empty() && current.len() + paragraph.len() + 2 > max_chars {
+            flush(&mut current, &mut chunks);
+        }
+        if current.is_empty() && !heading.is_empty() {
+            current.push_str(&heading);
+        }
+
+        for piece in split_long(paragraph, max_chars.saturating_sub(heading.len() + 2).max(1)) {
+            if !current.is_empty() && current != heading && current.len() + piece.len() + 2 > max_chars {
+                flush(&mut current, &mut chunks);
+                current.push_str(&heading);
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(piece);
+        }
+    }
+    flush(&mut current, &mut chunks);
+    chunks
+}
+
+/// Break a paragraph longer than `max_chars` at whitespace
+fn split_long(paragraph: &str, max_chars: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = paragraph;
+    while rest.len() > max_chars {
+        let mut cut = max_chars;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let cut = rest[..cut].rfind(char::is_whitespace).filter(|&i| i > 0).unwrap_or(cut);
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_keeps_headings_and_drops_scripts() {
+        let html = "<html><head><title>x</title></head><body><h2>Deploy</h2><p>Run <b>make</b> &amp; wait.</p><script>alert(1)</script></body></html>";
+        assert_eq!(html_to_text(html), "## Deploy\n\nRun make & wait.\n");
+    }
+
+    #[test]
+    fn chunks_carry_their_heading() {
+        let text = format!("# Setup\n\nInstall deps.\n\n# Style\n\n{}\n\n{}", "a ".repeat(40), "b ".repeat(40));
+        let chunks = chunk_text(&text, 100);
+        assert_eq!(chunks[0], "# Setup\n\nInstall deps.");
+        assert!(chunks[1].starts_with("# Style\n\na a"));
+        assert!(chunks[2].starts_with("# Style\n\nb b"));
+        assert!(chunks.iter().all(|c| c.len() <= 100));
+    }
+}
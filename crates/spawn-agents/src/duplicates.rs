@@ -0,0 +1,101 @@
+//! Duplicate mission detection
+//!
+//! Completed missions are embedded by goal; a new goal that lands close to
+//! one of them is flagged before it runs so the earlier result can be reused.
+//! Without a vector store, goals are compared by word overlap instead.
+
+use crate::memory::Database;
+use crate::vector_memory::{ContentType, VectorMemory};
+use serde::Serialize;
+use spawn_core::{LogStore, Mission, MissionFilter, MissionId, MissionStatus, Result};
+use std::collections::HashSet;
+
+/// Cosine similarity above which two goals are treated as the same task
+pub const EMBEDDING_THRESHOLD: f32 = 0.92;
+/// Word-overlap (Jaccard) threshold for the fallback comparison
+pub const LEXICAL_THRESHOLD: f32 = 0.8;
+/// Past missions compared by the fallback
+const LEXICAL_CANDIDATES: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarMission {
+    pub id: MissionId,
+    pub goal: String,
+    pub status: MissionStatus,
+    pub similarity: f32,
+    /// The earlier mission's `DONE:` summary
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Index a completed mission so later goals can be matched against it
+pub async fn remember(memory: &VectorMemory, mission: &Mission) -> Result<()> {
+    let metadata = serde_json::json!({ "mission_id": mission.id, "goal": mission.goal });
+    memory.store_embedding(ContentType::Mission, &mission.id, &mission.goal, metadata).await?;
+    Ok(())
+}
+
+/// Completed missions whose goal closely matches `goal`, most similar first
+pub async fn find_similar(
+    db: &Database,
+    logs: &dyn LogStore,
+    memory: Option<&VectorMemory>,
+    goal: &str,
+    limit: usize,
+) -> Result<Vec<SimilarMission>> {
+    let candidates: Vec<(MissionId, f32)> = match memory {
+        Some(memory) => memory.search(goal, Some(ContentType::Mission), limit as i32).await?
+            .into_iter()
+            .filter(|r| r.similarity >= EMBEDDING_THRESHOLD)
+            .filter_map(|r| r.metadata["mission_id"].as_str().map(|id| (MissionId::from(id), r.similarity)))
+            .collect(),
+        None => {
+            let filter = MissionFilter { status: Some(MissionStatus::Completed), ..Default::default() };
+            let mut scored: Vec<(MissionId, f32)> = db.list_missions_filtered(&filter).await?
+                .into_iter()
+                .take(LEXICAL_CANDIDATES)
+                .map(|m| {
+                    let similarity = goal_similarity(goal, &m.goal);
+                    (m.id, similarity)
+                })
+                .filter(|(_, similarity)| *similarity >= LEXICAL_THRESHOLD)
+                .collect();
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+            scored.truncate(limit);
+            scored
+        }
+    };
+
+    let mut similar = Vec::new();
+    for (id, similarity) in candidates {
+        // The embedding may outlive the mission row
+        let Some(mission) = db.get_mission(&id).await? else { continue };
+        if mission.status != MissionStatus::Completed {
+            continue;
+        }
+        similar.push(SimilarMission {
+            summary: logs.completion_summary(&mission.id).await?,
+            id: mission.id,
+            goal: mission.goal,
+            status: mission.status,
+            similarity,
+        });
+    }
+    Ok(similar)
+}
+
+/// Jaccard similarity of the goals' lowercase word sets
+pub fn goal_similarity(a: &str, b: &str) -> f32 {
+    let words = |text: &str| -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
@@ -0,0 +1,175 @@
+//! Mission cost estimates
+//!
+//! Before a mission runs, its step count and token use are predicted from
+//! completed missions: those with goals like the new one's when there are
+//! enough of them, the most recent ones otherwise. Priced for the model the
+//! mission would run on, that gives the likely cost range, so an expensive
+//! model can be weighed against a cheaper one before anything is spent.
+
+use serde::Serialize;
+use spawn_core::{ModelPrice, MissionUsage, TokenUsage};
+use std::collections::HashSet;
+
+/// Completed missions an estimate looks back over
+pub const HISTORY_MISSIONS: i64 = 200;
+/// Similar missions needed before the estimate is based on them alone
+const MIN_SIMILAR: usize = 5;
+/// Share of goal words two missions need in common to count as similar
+const MIN_GOAL_OVERLAP: f64 = 0.3;
+/// Percentiles of past missions bounding the range
+const LOW_PERCENTILE: f64 = 0.2;
+const HIGH_PERCENTILE: f64 = 0.8;
+/// Per step, when there is no history yet
+const DEFAULT_PROMPT_TOKENS_PER_STEP: u64 = 4_000;
+const DEFAULT_COMPLETION_TOKENS_PER_STEP: u64 = 600;
+const DEFAULT_MIN_STEPS: usize = 2;
+
+/// The likely span of a quantity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Range<T> {
+    pub low: T,
+    pub high: T,
+}
+
+/// What an estimate is based on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstimateBasis {
+    /// Completed missions with similar goals
+    Similar,
+    /// The most recent completed missions
+    Recent,
+    /// No history yet; fixed per-step figures
+    Default,
+}
+
+/// Predicted usage and cost of a mission before it runs
+#[derive(Debug, Clone, Serialize)]
+pub struct CostEstimate {
+    /// Model the mission's steps would run on
+    pub model: String,
+    pub basis: EstimateBasis,
+    /// Past missions the estimate comes from
+    pub sample_size: usize,
+    pub steps: Range<usize>,
+    pub prompt_tokens: Range<u64>,
+    pub completion_tokens: Range<u64>,
+    /// `None` when the model has no price
+    pub cost_usd: Option<Range<f64>>,
+}
+
+/// Estimate a mission with `goal` on `model`, capped at `max_steps`, from
+/// `history` (completed missions, any model)
+pub fn estimate(goal: &str, model: &str, price: Option<ModelPrice>, max_steps: usize, history: &[MissionUsage]) -> CostEstimate {
+    let words = goal_words(goal);
+    let similar: Vec<&MissionUsage> = history.iter()
+        .filter(|m| overlap(&words, &goal_words(&m.goal)) >= MIN_GOAL_OVERLAP)
+        .collect();
+    let (basis, sample): (EstimateBasis, Vec<&MissionUsage>) = if similar.len() >= MIN_SIMILAR {
+        (EstimateBasis::Similar, similar)
+    } else if !history.is_empty() {
+        (EstimateBasis::Recent, history.iter().collect())
+    } else {
+        (EstimateBasis::Default, Vec::new())
+    };
+
+    let max_steps = max_steps.max(1);
+    let (steps, prompt_tokens, completion_tokens) = if sample.is_empty() {
+        let steps = Range { low: DEFAULT_MIN_STEPS.min(max_steps), high: max_steps };
+        (
+            steps,
+            Range { low: steps.low as u64 * DEFAULT_PROMPT_TOKENS_PER_STEP, high: steps.high as u64 * DEFAULT_PROMPT_TOKENS_PER_STEP },
+            Range { low: steps.low as u64 * DEFAULT_COMPLETION_TOKENS_PER_STEP, high: steps.high as u64 * DEFAULT_COMPLETION_TOKENS_PER_STEP },
+        )
+    } else {
+        let steps = percentiles(sample.iter().map(|m| m.steps as u64).collect());
+        let mut prompt = percentiles(sample.iter().map(|m| m.prompt_tokens).collect());
+        let mut completion = percentiles(sample.iter().map(|m| m.completion_tokens).collect());
+        // The budget stops the mission before the longer runs got that far
+        let cap = max_steps as u64;
+        for range in [&mut prompt, &mut completion] {
+            if steps.low > cap {
+                range.low = range.low * cap / steps.low;
+            }
+            if steps.high > cap {
+                range.high = range.high * cap / steps.high;
+            }
+        }
+        let steps = Range { low: (steps.low as usize).clamp(1, max_steps), high: (steps.high as usize).clamp(1, max_steps) };
+        (steps, prompt, completion)
+    };
+
+    let cost = |prompt: u64, completion: u64| price.as_ref().map(|p| p.cost(&TokenUsage {
+        prompt_tokens: prompt.min(u32::MAX as u64) as u32,
+        completion_tokens: completion.min(u32::MAX as u64) as u32,
+        ..TokenUsage::default()
+    }));
+    let cost_usd = cost(prompt_tokens.low, completion_tokens.low)
+        .zip(cost(prompt_tokens.high, completion_tokens.high))
+        .map(|(low, high)| Range { low, high });
+
+    CostEstimate {
+        model: model.to_string(),
+        basis,
+        sample_size: sample.len(),
+        steps,
+        prompt_tokens,
+        completion_tokens,
+        cost_usd,
+    }
+}
+
+/// Low and high percentile of `values`, by nearest rank
+fn percentiles(mut values: Vec<u64>) -> Range<u64> {
+    values.sort_unstable();
+    let at = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+    Range { low: at(LOW_PERCENTILE), high: at(HIGH_PERCENTILE) }
+}
+
+/// Lowercase words of three letters or more
+fn goal_words(goal: &str) -> HashSet<String> {
+    goal.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard similarity of two word sets
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spawn_core::MissionId;
+
+    fn past(goal: &str, steps: usize, prompt_tokens: u64) -> MissionUsage {
+        MissionUsage { mission_id: MissionId::new(), goal: goal.into(), steps, prompt_tokens, completion_tokens: prompt_tokens / 10, cost_usd: 0.0 }
+    }
+
+    #[test]
+    fn test_estimates_from_similar_missions() {
+        let mut history: Vec<MissionUsage> = (1..=5).map(|i| past("Add a login page to the web app", i, i as u64 * 10_000)).collect();
+        history.extend((0..20).map(|_| past("Upgrade every dependency", 9, 500_000)));
+        let price = ModelPrice { prompt: 3.0, completion: 15.0, cached_prompt: None };
+
+        let similar = estimate("Add a signup page to the web app", "anthropic/claude-sonnet-4", Some(price), 10, &history);
+        assert_eq!(similar.basis, EstimateBasis::Similar);
+        assert_eq!(similar.sample_size, 5);
+        assert_eq!(similar.steps, Range { low: 2, high: 4 });
+        assert_eq!(similar.prompt_tokens, Range { low: 20_000, high: 40_000 });
+        let cost = similar.cost_usd.unwrap();
+        assert!((cost.low - (20_000.0 * 3.0 + 2_000.0 * 15.0) / 1_000_000.0).abs() < 1e-9);
+
+        let capped = estimate("Upgrade every dependency", "m", None, 3, &history);
+        assert_eq!(capped.steps.high, 3);
+        assert_eq!(capped.prompt_tokens.high, 500_000 * 3 / 9);
+        assert!(capped.cost_usd.is_none());
+        assert_eq!(estimate("anything", "m", None, 10, &[]).basis, EstimateBasis::Default);
+    }
+}
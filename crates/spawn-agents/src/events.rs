@@ -0,0 +1,65 @@
+//! In-process event bus for mission events
+//!
+//! Backed by a tokio broadcast channel. Subscribers that fall too far behind
+//! skip the events they missed rather than slowing the orchestrator down.
+
+use futures::StreamExt;
+use spawn_core::{EventBus, EventStream, MissionEvent};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Events buffered per subscriber before the slowest start losing them
+const CHANNEL_CAPACITY: usize = 1024;
+
+pub struct BroadcastEventBus {
+    sender: broadcast::Sender<MissionEvent>,
+}
+
+impl BroadcastEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl Default for BroadcastEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus for BroadcastEventBus {
+    fn publish(&self, event: MissionEvent) {
+        // No subscribers is not an error
+        let _ = self.sender.send(event);
+    }
+
+    fn subscribe(&self) -> EventStream {
+        futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => warn!(skipped, "Event subscriber lagged"),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let bus = BroadcastEventBus::new();
+        let mut events = bus.subscribe();
+        bus.publish(MissionEvent::StepStarted { mission_id: "m".into(), step: 1 });
+
+        let event = events.next().await.unwrap();
+        assert_eq!(event.mission_id(), "m");
+        assert!(!event.is_terminal());
+    }
+}
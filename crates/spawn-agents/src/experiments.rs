@@ -0,0 +1,145 @@
+//! A/B experiments over mission settings
+//!
+//! While an experiment is active, each new mission is assigned one of its
+//! variants (model, temperature, system prompt) by a stable hash of the
+//! mission ID, weighted by the variants' shares. Outcomes are aggregated per
+//! variant so model and prompt choices can be made on success rate, duration
+//! and LLM calls rather than impressions.
+
+use serde::Serialize;
+use spawn_core::{Experiment, ExperimentVariant, MissionStatus, Result, SpawnError};
+use std::collections::HashSet;
+
+use crate::memory::Database;
+
+/// How one variant's missions went
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantMetrics {
+    pub variant: String,
+    pub missions: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Completed out of finished missions; `None` until one finishes
+    pub success_rate: Option<f64>,
+    /// Mean creation-to-finish time of finished missions
+    pub avg_duration_secs: Option<f64>,
+    /// Mean LLM calls per finished mission, standing in for cost until token
+    /// usage is recorded
+    pub avg_steps: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentReport {
+    #[serde(flatten)]
+    pub experiment: Experiment,
+    pub results: Vec<VariantMetrics>,
+}
+
+/// Reject experiments that cannot assign missions sensibly
+pub fn validate(name: &str, variants: &[ExperimentVariant]) -> Result<()> {
+    let invalid = |message: String| Err(SpawnError::OrchestrationError(message));
+    if name.trim().is_empty() {
+        return invalid("Experiment name must not be empty".into());
+    }
+    if variants.is_empty() {
+        return invalid("Experiment needs at least one variant".into());
+    }
+    let mut names = HashSet::new();
+    for variant in variants {
+        if variant.name.trim().is_empty() || !names.insert(variant.name.as_str()) {
+            return invalid(format!("Variant names must be non-empty and unique ('{}')", variant.name));
+        }
+        if variant.weight == 0 {
+            return invalid(format!("Variant '{}' has weight 0", variant.name));
+        }
+        if variant.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            return invalid(format!("Variant '{}' temperature must be between 0 and 2", variant.name));
+        }
+    }
+    Ok(())
+}
+
+/// The variant a mission gets; the same mission always gets the same one
+pub fn assign<'a>(experiment: &'a Experiment, mission_id: &str) -> Option<&'a ExperimentVariant> {
+    let total: u64 = experiment.variants.iter().map(|v| u64::from(v.weight)).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut point = fnv1a(format!("{}:{}", experiment.id, mission_id).as_bytes()) % total;
+    experiment.variants.iter().find(|v| {
+        let weight = u64::from(v.weight);
+        if point < weight {
+            return true;
+        }
+        point -= weight;
+        false
+    })
+}
+
+/// Stable across builds and platforms, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x100000001b3))
+}
+
+/// Fill a variant's prompt template
+pub fn render_prompt(template: &str, persona: &str, tools: &str) -> String {
+    template.replace("{persona}", persona).replace("{tools}", tools)
+}
+
+/// Outcome metrics of each of the experiment's variants, in declaration order
+pub async fn report(db: &Database, experiment: Experiment) -> Result<ExperimentReport> {
+    let outcomes = db.experiment_outcomes(&experiment.id).await?;
+    let results = experiment.variants.iter()
+        .map(|variant| {
+            let mine: Vec<_> = outcomes.iter().filter(|o| o.variant == variant.name).collect();
+            let finished: Vec<_> = mine.iter().filter(|o| o.status.is_terminal()).collect();
+            let completed = finished.iter().filter(|o| o.status == MissionStatus::Completed).count();
+            let failed = finished.iter().filter(|o| o.status == MissionStatus::Failed).count();
+            let mean = |values: Vec<f64>| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+            VariantMetrics {
+                variant: variant.name.clone(),
+                missions: mine.len(),
+                completed,
+                failed,
+                success_rate: (!finished.is_empty()).then(|| completed as f64 / finished.len() as f64),
+                avg_duration_secs: mean(finished.iter().map(|o| o.duration.num_milliseconds() as f64 / 1000.0).collect()),
+                avg_steps: mean(finished.iter().map(|o| o.steps as f64).collect()),
+            }
+        })
+        .collect();
+    Ok(ExperimentReport { experiment, results })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, weight: u32) -> ExperimentVariant {
+        ExperimentVariant { name: name.into(), model: None, temperature: None, prompt_template: None, weight }
+    }
+
+    #[test]
+    fn test_assign_is_stable_and_follows_weights() {
+        let experiment = Experiment {
+            id: "exp".into(),
+            name: "temperature".into(),
+            variants: vec![variant("a", 3), variant("b", 1)],
+            active: true,
+            created_at: chrono::Utc::now(),
+        };
+        let picks: Vec<&str> = (0..400)
+            .map(|i| assign(&experiment, &format!("mission-{}", i)).unwrap().name.as_str())
+            .collect();
+        let a = picks.iter().filter(|&&p| p == "a").count();
+        assert!((250..350).contains(&a), "expected about 300 of 400 for a, got {}", a);
+        assert_eq!(assign(&experiment, "mission-7").unwrap().name, picks[7]);
+    }
+
+    #[test]
+    fn test_validate_rejects_duplicate_and_zero_weight_variants() {
+        assert!(validate("t", &[variant("a", 1), variant("b", 2)]).is_ok());
+        assert!(validate("t", &[variant("a", 1), variant("a", 1)]).is_err());
+        assert!(validate("t", &[variant("a", 0)]).is_err());
+        assert!(validate("t", &[]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Federated search across registered workspaces
+//!
+//! Each workspace is searched in parallel - a lexical scan of its files plus
+//! its knowledge base docs when a vector store is configured - and the hits
+//! are merged into one ranking, each tagged with the workspace it came from.
+
+use crate::ignore::IgnoreRules;
+use crate::vector_memory::VectorMemory;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use walkdir::WalkDir;
+
+/// Files larger than this are skipped (generated bundles, data dumps)
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Upper bound on files scanned per workspace
+const MAX_FILES: usize = 20_000;
+/// Hits kept per file so one noisy file cannot fill the ranking
+const MAX_HITS_PER_FILE: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FederatedHit {
+    pub workspace: String,
+    /// `code` for file matches, `doc` for knowledge base chunks
+    pub kind: &'static str,
+    /// Workspace-relative path, or the document title
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub snippet: String,
+    /// 0.0 - 1.0; comparable across workspaces
+    pub score: f32,
+}
+
+/// Search every workspace in parallel and merge the results by score
+pub async fn federated_search(
+    workspaces: &[(String, PathBuf)],
+    memory: Option<&VectorMemory>,
+    query: &str,
+    limit: usize,
+) -> Vec<FederatedHit> {
+    let searches = workspaces.iter().map(|(name, root)| async move {
+        let mut hits = Vec::new();
+
+        let (scan_root, scan_query) = (root.clone(), query.to_string());
+        match tokio::task::spawn_blocking(move || search_files(&scan_root, &scan_query, limit)).await {
+            Ok(matches) => hits.extend(matches.into_iter().map(|(path, line, snippet, score)| FederatedHit {
+                workspace: name.clone(),
+                kind: "code",
+                path,
+                line: Some(line),
+                snippet,
+                score,
+            })),
+            Err(e) => warn!(workspace = %name, error = %e, "Workspace scan failed"),
+        }
+
+        if let Some(memory) = memory {
+            match memory.search_docs(query, &root.display().to_string(), limit as i32).await {
+                Ok(results) => hits.extend(results.into_iter().map(|r| FederatedHit {
+                    workspace: name.clone(),
+                    kind: "doc",
+                    path: r.metadata["title"].as_str().unwrap_or("document").to_string(),
+                    line: None,
+                    snippet: r.content_preview,
+                    score: r.similarity.clamp(0.0, 1.0),
+                })),
+                Err(e) => warn!(workspace = %name, error = %e, "Workspace doc search failed"),
+            }
+        }
+
+        hits
+    });
+
+    let mut hits: Vec<FederatedHit> = futures::future::join_all(searches).await.into_iter().flatten().collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// Lexical scan of one workspace: `(path, line, snippet, score)`, best first
+pub fn search_files(root: &Path, query: &str, limit: usize) -> Vec<(String, usize, String, f32)> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let phrase = query.trim().to_lowercase();
+
+    let ignore = IgnoreRules::load(root);
+    let files = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !ignore.is_ignored(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.metadata().map(|m| m.len() <= MAX_FILE_BYTES).unwrap_or(false))
+        .take(MAX_FILES);
+
+    let mut hits = Vec::new();
+    for entry in files {
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue; // binary or not UTF-8
+        };
+        let path = entry.path().strip_prefix(root).unwrap_or(entry.path()).to_string_lossy().to_string();
+
+        let mut file_hits: Vec<(usize, String, f32)> = content.lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let score = score_line(line, &terms, &phrase);
+                (score > 0.0).then(|| (i + 1, line.trim().chars().take(200).collect(), score))
+            })
+            .collect();
+        file_hits.sort_by(|a, b| b.2.total_cmp(&a.2));
+        hits.extend(file_hits.into_iter()
+            .take(MAX_HITS_PER_FILE)
+            .map(|(line, snippet, score)| (path.clone(), line, snippet, score)));
+    }
+
+    hits.sort_by(|a, b| b.3.total_cmp(&a.3));
+    hits.truncate(limit);
+    hits
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| t.len() > 1)
+        .map(str::to_lowercase)
+        .collect();
+    terms.dedup();
+    terms
+}
+
+/// Fraction of query terms on the line, discounted unless the whole query appears
+fn score_line(line: &str, terms: &[String], phrase: &str) -> f32 {
+    let lower = line.to_lowercase();
+    let matched = terms.iter().filter(|t| lower.contains(t.as_str())).count();
+    if matched == 0 {
+        return 0.0;
+    }
+    let coverage = matched as f32 / terms.len() as f32;
+    if lower.contains(phrase) { coverage } else { coverage * 0.8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_phrase_outranks_scattered_terms() {
+        let terms = query_terms("retry policy");
+        let exact = score_line("let retry policy = default();", &terms, "retry policy");
+        let scattered = score_line("// policy for each retry", &terms, "retry policy");
+        let partial = score_line("retry();", &terms, "retry policy");
+        assert!(exact > scattered && scattered > partial);
+        assert_eq!(score_line("nothing here", &terms, "retry policy"), 0.0);
+    }
+}
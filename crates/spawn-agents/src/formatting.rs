@@ -0,0 +1,131 @@
+//! Formatting of agent-written files
+//!
+//! Before a file an agent wrote is saved, it is piped through the formatter
+//! for its extension so agent output matches the repository's style. The
+//! server config sets the defaults; a workspace's `.spawn-format.toml` (same
+//! keys as `[format]`) overrides them. A missing or failing formatter leaves
+//! the content as written.
+
+use spawn_core::{FormatConfig, Result, SpawnError};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Per-workspace overrides, at the workspace root
+pub const FORMAT_FILE: &str = ".spawn-format.toml";
+/// A formatter taking longer than this is abandoned
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Commands used when the config doesn't name one for an extension
+fn builtin_formatters() -> BTreeMap<String, Vec<String>> {
+    let prettier = || vec!["prettier".to_string(), "--stdin-filepath".to_string(), "{path}".to_string()];
+    let mut formatters = BTreeMap::new();
+    formatters.insert("rs".to_string(), vec!["rustfmt".into(), "--edition".into(), "2021".into(), "--emit".into(), "stdout".into()]);
+    for ext in ["ts", "tsx", "js", "jsx", "json", "css", "md"] {
+        formatters.insert(ext.to_string(), prettier());
+    }
+    formatters.insert("py".to_string(), vec!["black".into(), "--quiet".into(), "-".into()]);
+    formatters
+}
+
+/// The formatters in effect for one workspace
+pub struct Formatter {
+    root: PathBuf,
+    on_write: bool,
+    formatters: BTreeMap<String, Vec<String>>,
+}
+
+impl Formatter {
+    /// `defaults` overlaid with the workspace's `.spawn-format.toml`, if any
+    pub async fn for_workspace(root: &Path, defaults: &FormatConfig) -> Result<Self> {
+        let mut config = defaults.clone();
+        match tokio::fs::read_to_string(root.join(FORMAT_FILE)).await {
+            Ok(text) => {
+                let local: WorkspaceFormat = toml::from_str(&text)
+                    .map_err(|e| SpawnError::Config(format!("{}: {}", FORMAT_FILE, e)))?;
+                if let Some(on_write) = local.on_write {
+                    config.on_write = on_write;
+                }
+                config.formatters.extend(local.formatters);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(SpawnError::Internal(format!("Failed to read {}: {}", FORMAT_FILE, e))),
+        }
+
+        let mut formatters = builtin_formatters();
+        formatters.extend(config.formatters);
+        Ok(Self { root: root.to_path_buf(), on_write: config.on_write, formatters })
+    }
+
+    /// The command for `path`, with `{path}` filled in; none when formatting
+    /// is off or no formatter handles the extension
+    pub fn command(&self, path: &str) -> Option<Vec<String>> {
+        if !self.on_write {
+            return None;
+        }
+        let ext = Path::new(path).extension()?.to_str()?;
+        let command = self.formatters.get(ext).filter(|c| !c.is_empty())?;
+        Some(command.iter().map(|arg| arg.replace("{path}", path)).collect())
+    }
+
+    /// `content` formatted for `path` (relative to the workspace), or `None`
+    /// if nothing formats it
+    pub async fn format(&self, path: &str, content: &str) -> Result<Option<String>> {
+        let Some(command) = self.command(path) else {
+            return Ok(None);
+        };
+        let failed = |e: &dyn std::fmt::Display| SpawnError::ToolError(format!("{} failed on {}: {}", command[0], path, e));
+
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .current_dir(&self.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| failed(&e))?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = content.to_string();
+        // Written alongside reading the output, so large files can't deadlock
+        let feed = tokio::spawn(async move { stdin.write_all(input.as_bytes()).await });
+        let output = tokio::time::timeout(FORMAT_TIMEOUT, child.wait_with_output()).await
+            .map_err(|_| failed(&format!("timed out after {}s", FORMAT_TIMEOUT.as_secs())))?
+            .map_err(|e| failed(&e))?;
+        let _ = feed.await;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(failed(&stderr.lines().next().unwrap_or("no output")));
+        }
+        String::from_utf8(output.stdout).map(Some).map_err(|e| failed(&e))
+    }
+}
+
+/// `.spawn-format.toml`
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFormat {
+    on_write: Option<bool>,
+    #[serde(default)]
+    formatters: BTreeMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_format_pipes_through_command() {
+        let mut config = FormatConfig { on_write: true, ..Default::default() };
+        config.formatters.insert("txt".into(), vec!["tr".into(), "a-z".into(), "A-Z".into()]);
+        let formatter = Formatter::for_workspace(&std::env::temp_dir(), &config).await.unwrap();
+
+        assert_eq!(formatter.format("notes.txt", "hello\n").await.unwrap().as_deref(), Some("HELLO\n"));
+        assert_eq!(formatter.format("data.bin", "x").await.unwrap(), None);
+        assert_eq!(formatter.command("src/app.ts").unwrap(), vec!["prettier", "--stdin-filepath", "src/app.ts"]);
+    }
+}
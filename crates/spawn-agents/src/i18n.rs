@@ -0,0 +1,325 @@
+//! Localized prompts and messages
+//!
+//! The chat and mission system prompts and the readable text of API errors
+//! come from a built-in catalog per language. A locale without a catalog
+//! gets the English text plus an instruction to answer in its language, so
+//! agents still respond in it. The tool protocol (`TOOL:`, `ARGS:`, `DONE:`)
+//! stays English everywhere, since replies are parsed for it.
+
+/// Locale when neither the request, the user nor the workspace set one
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// The text of one language
+pub struct Catalog {
+    /// Primary language subtag
+    pub language: &'static str,
+    /// Persona of a mission without an agent
+    pub persona: &'static str,
+    /// Follows an agent's own persona
+    pub job: &'static str,
+    pub tools_heading: &'static str,
+    pub tool_usage: &'static str,
+    pub done_usage: &'static str,
+    pub done_placeholder: &'static str,
+    pub closing: &'static str,
+    /// Heads the operator's must and must-not rules
+    pub must_heading: &'static str,
+    pub must_not_heading: &'static str,
+    /// Label of the mission's goal message
+    pub goal: &'static str,
+    pub chat_system: &'static str,
+    /// Which language to answer in; empty for English
+    pub respond_in: &'static str,
+    /// Readable messages by `SpawnError::code`
+    pub errors: &'static [(&'static str, &'static str)],
+}
+
+const EN: Catalog = Catalog {
+    language: "en",
+    persona: "You are an autonomous AI agent. Your job is to accomplish the user's goal.",
+    job: "Your job is to accomplish the user's goal.",
+    tools_heading: "Available tools:",
+    tool_usage: "To use a tool, respond with:",
+    done_usage: "When the goal is complete, respond with:",
+    done_placeholder: "<summary of what was accomplished>",
+    closing: "Think step by step. Be concise.",
+    must_heading: "Always:",
+    must_not_heading: "Never:",
+    goal: "Goal",
+    chat_system: "You are a helpful coding assistant for spawn.new. Help users build software.",
+    respond_in: "",
+    errors: &[
+        ("rate_limited", "The model provider is rate limiting requests. Try again shortly."),
+        ("provider_unavailable", "The model provider is unavailable right now. Try again later."),
+        ("service_unavailable", "A service this needs is unavailable right now. Try again later."),
+        ("auth_failed", "The model provider rejected the server's credentials."),
+        ("budget_exceeded", "The spending limit was reached."),
+        ("context_overflow", "The conversation is too long for the model."),
+        ("cancelled", "The request was cancelled."),
+        ("locked", "Someone else is working on this right now."),
+        ("internal", "Something went wrong on the server."),
+    ],
+};
+
+const DE: Catalog = Catalog {
+    language: "de",
+    persona: "Du bist ein autonomer KI-Agent. Deine Aufgabe ist es, das Ziel des Nutzers zu erreichen.",
+    job: "Deine Aufgabe ist es, das Ziel des Nutzers zu erreichen.",
+    tools_heading: "Verfügbare Werkzeuge:",
+    tool_usage: "Um ein Werkzeug zu benutzen, antworte mit:",
+    done_usage: "Wenn das Ziel erreicht ist, antworte mit:",
+    done_placeholder: "<Zusammenfassung des Erreichten>",
+    closing: "Denke Schritt für Schritt. Fasse dich kurz.",
+    must_heading: "Immer:",
+    must_not_heading: "Niemals:",
+    goal: "Ziel",
+    chat_system: "Du bist ein hilfreicher Programmierassistent für spawn.new. Hilf Nutzern, Software zu bauen.",
+    respond_in: "Antworte immer auf Deutsch.",
+    errors: &[
+        ("rate_limited", "Der Modellanbieter drosselt Anfragen. Bitte versuche es gleich noch einmal."),
+        ("provider_unavailable", "Der Modellanbieter ist gerade nicht erreichbar. Bitte versuche es später erneut."),
+        ("service_unavailable", "Ein benötigter Dienst ist gerade nicht verfügbar. Bitte versuche es später erneut."),
+        ("auth_failed", "Der Modellanbieter hat die Zugangsdaten des Servers abgelehnt."),
+        ("budget_exceeded", "Das Ausgabenlimit wurde erreicht."),
+        ("context_overflow", "Die Unterhaltung ist zu lang für das Modell."),
+        ("cancelled", "Die Anfrage wurde abgebrochen."),
+        ("locked", "Gerade arbeitet jemand anderes daran."),
+        ("internal", "Auf dem Server ist ein Fehler aufgetreten."),
+    ],
+};
+
+const ES: Catalog = Catalog {
+    language: "es",
+    persona: "Eres un agente de IA autónomo. Tu trabajo es cumplir el objetivo del usuario.",
+    job: "Tu trabajo es cumplir el objetivo del usuario.",
+    tools_heading: "Herramientas disponibles:",
+    tool_usage: "Para usar una herramienta, responde con:",
+    done_usage: "Cuando el objetivo esté cumplido, responde con:",
+    done_placeholder: "<resumen de lo que se logró>",
+    closing: "Piensa paso a paso. Sé conciso.",
+    must_heading: "Siempre:",
+    must_not_heading: "Nunca:",
+    goal: "Objetivo",
+    chat_system: "Eres un asistente de programación útil para spawn.new. Ayuda a los usuarios a crear software.",
+    respond_in: "Responde siempre en español.",
+    errors: &[
+        ("rate_limited", "El proveedor del modelo está limitando las solicitudes. Inténtalo de nuevo en un momento."),
+        ("provider_unavailable", "El proveedor del modelo no está disponible ahora. Inténtalo más tarde."),
+        ("service_unavailable", "Un servicio necesario no está disponible ahora. Inténtalo más tarde."),
+        ("auth_failed", "El proveedor del modelo rechazó las credenciales del servidor."),
+        ("budget_exceeded", "Se alcanzó el límite de gasto."),
+        ("context_overflow", "La conversación es demasiado larga para el modelo."),
+        ("cancelled", "La solicitud fue cancelada."),
+        ("locked", "Otra persona está trabajando en esto ahora mismo."),
+        ("internal", "Algo salió mal en el servidor."),
+    ],
+};
+
+const FR: Catalog = Catalog {
+    language: "fr",
+    persona: "Tu es un agent IA autonome. Ta mission est d'atteindre l'objectif de l'utilisateur.",
+    job: "Ta mission est d'atteindre l'objectif de l'utilisateur.",
+    tools_heading: "Outils disponibles :",
+    tool_usage: "Pour utiliser un outil, réponds avec :",
+    done_usage: "Quand l'objectif est atteint, réponds avec :",
+    done_placeholder: "<résumé de ce qui a été accompli>",
+    closing: "Réfléchis étape par étape. Sois concis.",
+    must_heading: "Toujours :",
+    must_not_heading: "Jamais :",
+    goal: "Objectif",
+    chat_system: "Tu es un assistant de programmation pour spawn.new. Aide les utilisateurs à créer des logiciels.",
+    respond_in: "Réponds toujours en français.",
+    errors: &[
+        ("rate_limited", "Le fournisseur du modèle limite les requêtes. Réessaie dans un instant."),
+        ("provider_unavailable", "Le fournisseur du modèle est indisponible pour le moment. Réessaie plus tard."),
+        ("service_unavailable", "Un service nécessaire est indisponible pour le moment. Réessaie plus tard."),
+        ("auth_failed", "Le fournisseur du modèle a refusé les identifiants du serveur."),
+        ("budget_exceeded", "La limite de dépenses a été atteinte."),
+        ("context_overflow", "La conversation est trop longue pour le modèle."),
+        ("cancelled", "La requête a été annulée."),
+        ("locked", "Quelqu'un d'autre travaille dessus en ce moment."),
+        ("internal", "Une erreur s'est produite sur le serveur."),
+    ],
+};
+
+const PT: Catalog = Catalog {
+    language: "pt",
+    persona: "Você é um agente de IA autônomo. Seu trabalho é cumprir o objetivo do usuário.",
+    job: "Seu trabalho é cumprir o objetivo do usuário.",
+    tools_heading: "Ferramentas disponíveis:",
+    tool_usage: "Para usar uma ferramenta, responda com:",
+    done_usage: "Quando o objetivo estiver cumprido, responda com:",
+    done_placeholder: "<resumo do que foi feito>",
+    closing: "Pense passo a passo. Seja conciso.",
+    must_heading: "Sempre:",
+    must_not_heading: "Nunca:",
+    goal: "Objetivo",
+    chat_system: "Você é um assistente de programação para o spawn.new. Ajude os usuários a criar software.",
+    respond_in: "Responda sempre em português.",
+    errors: &[
+        ("rate_limited", "O provedor do modelo está limitando as requisições. Tente novamente em instantes."),
+        ("provider_unavailable", "O provedor do modelo está indisponível no momento. Tente mais tarde."),
+        ("service_unavailable", "Um serviço necessário está indisponível no momento. Tente mais tarde."),
+        ("auth_failed", "O provedor do modelo recusou as credenciais do servidor."),
+        ("budget_exceeded", "O limite de gastos foi atingido."),
+        ("context_overflow", "A conversa é longa demais para o modelo."),
+        ("cancelled", "A requisição foi cancelada."),
+        ("locked", "Outra pessoa está trabalhando nisso agora."),
+        ("internal", "Algo deu errado no servidor."),
+    ],
+};
+
+const JA: Catalog = Catalog {
+    language: "ja",
+    persona: "あなたは自律型のAIエージェントです。ユーザーの目標を達成することがあなたの仕事です。",
+    job: "ユーザーの目標を達成することがあなたの仕事です。",
+    tools_heading: "利用できるツール:",
+    tool_usage: "ツールを使うときは、次の形式で応答してください:",
+    done_usage: "目標を達成したら、次の形式で応答してください:",
+    done_placeholder: "<達成した内容の要約>",
+    closing: "段階的に考え、簡潔に答えてください。",
+    must_heading: "必ず守ること:",
+    must_not_heading: "してはいけないこと:",
+    goal: "目標",
+    chat_system: "あなたは spawn.new のコーディングアシスタントです。ユーザーのソフトウェア開発を手伝ってください。",
+    respond_in: "常に日本語で応答してください。",
+    errors: &[
+        ("rate_limited", "モデルプロバイダーがリクエストを制限しています。しばらくしてから再試行してください。"),
+        ("provider_unavailable", "モデルプロバイダーは現在利用できません。後でもう一度お試しください。"),
+        ("service_unavailable", "必要なサービスが現在利用できません。後でもう一度お試しください。"),
+        ("auth_failed", "モデルプロバイダーがサーバーの認証情報を拒否しました。"),
+        ("budget_exceeded", "利用上限に達しました。"),
+        ("context_overflow", "会話がモデルにとって長すぎます。"),
+        ("cancelled", "リクエストはキャンセルされました。"),
+        ("locked", "現在、他の人が作業中です。"),
+        ("internal", "サーバーでエラーが発生しました。"),
+    ],
+};
+
+const ZH: Catalog = Catalog {
+    language: "zh",
+    persona: "你是一个自主的 AI 智能体。你的任务是完成用户的目标。",
+    job: "你的任务是完成用户的目标。",
+    tools_heading: "可用工具:",
+    tool_usage: "要使用工具，请这样回复:",
+    done_usage: "目标完成后，请这样回复:",
+    done_placeholder: "<所完成工作的摘要>",
+    closing: "逐步思考，回答简洁。",
+    must_heading: "必须遵守:",
+    must_not_heading: "禁止事项:",
+    goal: "目标",
+    chat_system: "你是 spawn.new 的编程助手。帮助用户构建软件。",
+    respond_in: "请始终使用中文回复。",
+    errors: &[
+        ("rate_limited", "模型提供方正在限制请求，请稍后重试。"),
+        ("provider_unavailable", "模型提供方暂时不可用，请稍后再试。"),
+        ("service_unavailable", "所需的服务暂时不可用，请稍后再试。"),
+        ("auth_failed", "模型提供方拒绝了服务器的凭据。"),
+        ("budget_exceeded", "已达到支出上限。"),
+        ("context_overflow", "对话内容超出了模型的长度限制。"),
+        ("cancelled", "请求已取消。"),
+        ("locked", "其他人正在处理此内容。"),
+        ("internal", "服务器出现错误。"),
+    ],
+};
+
+const CATALOGS: &[&Catalog] = &[&EN, &DE, &ES, &FR, &PT, &JA, &ZH];
+
+/// Languages with a catalog
+pub fn supported() -> Vec<&'static str> {
+    CATALOGS.iter().map(|c| c.language).collect()
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// The catalog for `locale`'s language, or English
+pub fn catalog(locale: &str) -> &'static Catalog {
+    let language = language(locale).to_ascii_lowercase();
+    CATALOGS.iter().copied().find(|c| c.language == language).unwrap_or(&EN)
+}
+
+/// The instruction to answer in `locale`'s language; `None` for English
+fn respond_in(locale: &str) -> Option<String> {
+    let catalog = catalog(locale);
+    if !catalog.respond_in.is_empty() {
+        Some(catalog.respond_in.to_string())
+    } else if !language(locale).eq_ignore_ascii_case(DEFAULT_LOCALE) {
+        Some(format!("Always respond in the language of the locale '{}'.", locale))
+    } else {
+        None
+    }
+}
+
+/// `text` followed by the answer-language instruction, if any
+fn with_language(text: String, locale: &str) -> String {
+    match respond_in(locale) {
+        Some(instruction) => format!("{}\n\n{}", text, instruction),
+        None => text,
+    }
+}
+
+/// A mission's persona: the agent's own prompt, or the default one
+pub fn persona(locale: &str, agent_prompt: Option<&str>) -> String {
+    let catalog = catalog(locale);
+    match agent_prompt {
+        Some(prompt) => format!("{}\n\n{}", prompt, catalog.job),
+        None => catalog.persona.to_string(),
+    }
+}
+
+/// The catalog's mission prompt text, as the `text` variable of prompt
+/// templates (`text.tools_heading`, ...)
+pub fn prompt_text(locale: &str) -> serde_json::Value {
+    let c = catalog(locale);
+    serde_json::json!({
+        "tools_heading": c.tools_heading,
+        "tool_usage": c.tool_usage,
+        "done_usage": c.done_usage,
+        "done_placeholder": c.done_placeholder,
+        "closing": c.closing,
+        "must_heading": c.must_heading,
+        "must_not_heading": c.must_not_heading,
+    })
+}
+
+/// A prompt from a template, told which language to answer in
+pub fn localize_prompt(locale: &str, prompt: String) -> String {
+    with_language(prompt, locale)
+}
+
+/// The first user message of a mission
+pub fn goal_message(locale: &str, goal: &str) -> String {
+    format!("{}: {}", catalog(locale).goal, goal)
+}
+
+/// The system prompt for `/api/chat` and voice chat
+pub fn chat_prompt(locale: &str) -> String {
+    with_language(catalog(locale).chat_system.to_string(), locale)
+}
+
+/// A readable message for an error code, when the catalog has one
+pub fn error_message(locale: &str, code: &str) -> Option<&'static str> {
+    let find = |c: &'static Catalog| c.errors.iter().find(|(k, _)| *k == code).map(|(_, m)| *m);
+    find(catalog(locale)).or_else(|| find(&EN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_by_language() {
+        assert_eq!(catalog("pt-BR").language, "pt");
+        assert_eq!(catalog("ko").language, "en");
+        assert!(chat_prompt("de").ends_with("Antworte immer auf Deutsch."));
+        assert!(chat_prompt("ko").ends_with("locale 'ko'."));
+        assert_eq!(chat_prompt("en-GB"), EN.chat_system);
+        assert_eq!(goal_message("es", "fix it"), "Objetivo: fix it");
+        assert_eq!(prompt_text("fr")["must_not_heading"], "Jamais :");
+        assert_eq!(error_message("ja", "cancelled"), Some("リクエストはキャンセルされました。"));
+        assert_eq!(error_message("ko", "locked"), error_message("en", "locked"));
+        assert_eq!(error_message("de", "tool_error"), None);
+    }
+}
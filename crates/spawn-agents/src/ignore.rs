@@ -0,0 +1,161 @@
+//! Workspace ignore rules
+//!
+//! Which files the file tree, search, dependency scan and indexing skip. The
+//! built-in rules (dotfiles and build/dependency directories) come first,
+//! then the workspace's `.spawnignore` in gitignore syntax, so a workspace
+//! can add its own or re-include a default with `!`. As in git, the last
+//! matching rule wins and nothing under an ignored directory is seen.
+
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+use tracing::warn;
+
+pub const IGNORE_FILE: &str = ".spawnignore";
+
+/// Applied before the workspace's own rules
+pub const DEFAULT_RULES: &str = "\
+.*
+node_modules/
+target/
+__pycache__/
+dist/
+build/
+vendor/
+";
+
+struct Rule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+pub struct IgnoreRules {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// The defaults plus `root`'s `.spawnignore`, if it has one
+    pub fn load(root: &Path) -> Self {
+        let mut text = DEFAULT_RULES.to_string();
+        match std::fs::read_to_string(root.join(IGNORE_FILE)) {
+            Ok(local) => text.push_str(&local),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!(root = %root.display(), error = %e, "Failed to read {}", IGNORE_FILE),
+        }
+        Self::parse(root, &text)
+    }
+
+    /// Rules from gitignore-syntax `text`; lines that don't parse are skipped
+    pub fn parse(root: &Path, text: &str) -> Self {
+        let rules = text.lines().filter_map(|line| {
+            let rule = parse_rule(line);
+            if rule.is_none() && !line.trim().is_empty() && !line.starts_with('#') {
+                warn!(pattern = line, "Skipping invalid ignore pattern");
+            }
+            rule
+        }).collect();
+        Self { root: root.to_path_buf(), rules }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether `path` (absolute under the root, or relative to it) is
+    /// ignored, itself or through one of its directories
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let parts: Vec<&str> = relative.components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+        (1..=parts.len()).any(|n| self.matches(&parts[..n].join("/"), n < parts.len() || is_dir))
+    }
+
+    /// Last matching rule wins
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        self.rules.iter().rev()
+            .find(|r| (is_dir || !r.dir_only) && r.regex.is_match(relative))
+            .is_some_and(|r| !r.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    // A slash anywhere but the end ties the pattern to the root; otherwise
+    // it matches a name at any depth
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+    let prefix = if anchored { "^" } else { "^(?:.*/)?" };
+    let regex = Regex::new(&format!("{}{}$", prefix, glob_to_regex(pattern))).ok()?;
+    Some(Rule { regex, negated, dir_only })
+}
+
+/// `*` and `?` stay within one path segment, `**` crosses them
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let class = class.strip_prefix('!').map(|rest| format!("^{}", rest)).unwrap_or(class);
+                regex.push_str(&format!("[{}]", class));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_patterns() {
+        let rules = IgnoreRules::parse(Path::new("/ws"), &format!("{}*.log\n/secrets/\ndocs/**/draft-?.md\n!target/\n", DEFAULT_RULES));
+
+        assert!(rules.is_ignored(Path::new("/ws/node_modules"), true));
+        assert!(rules.is_ignored(Path::new("web/node_modules/react/index.js"), false));
+        assert!(rules.is_ignored(Path::new(".git"), true));
+        assert!(rules.is_ignored(Path::new("logs/app.log"), false));
+        assert!(rules.is_ignored(Path::new("secrets/key.pem"), false));
+        assert!(!rules.is_ignored(Path::new("src/secrets/mod.rs"), false));
+        assert!(rules.is_ignored(Path::new("docs/a/b/draft-1.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/draft-10.md"), false));
+        // Re-included by the workspace
+        assert!(!rules.is_ignored(Path::new("target/debug/app"), false));
+        // Directory rules don't match files of the same name
+        assert!(!rules.is_ignored(Path::new("build"), false));
+        assert!(!rules.is_ignored(Path::new("/ws/src/main.rs"), false));
+    }
+}
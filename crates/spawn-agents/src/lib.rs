@@ -3,11 +3,49 @@
 //! Contains the Orchestrator (agent loop), Memory (database), Tools,
 //! and Vector Memory for semantic search.
 
+pub mod activity;
+pub mod agents;
+pub mod assembler;
+pub mod citations;
+pub mod docs;
+pub mod duplicates;
+pub mod estimate;
+pub mod events;
+pub mod experiments;
+pub mod federation;
+pub mod formatting;
+pub mod i18n;
+pub mod ignore;
+pub mod locks;
+pub mod log_store;
 pub mod memory;
 pub mod orchestrator;
+pub mod plan;
+pub mod postmortem;
+pub mod preflight;
+pub mod privacy;
+pub mod processes;
+pub mod rerank;
+pub mod reservations;
+pub mod run_diff;
+pub mod scheduler;
+pub mod scratch;
+pub mod snapshots;
+pub mod supervisor;
+pub mod sync;
+pub mod templates;
+pub mod terminals;
 pub mod tools;
+pub mod transcript;
 pub mod vector_memory;
 
-pub use memory::Database;
-pub use orchestrator::Orchestrator;
-pub use vector_memory::{VectorMemory, SearchResult, CodeChunk, ContentType};
+pub use locks::WorkspaceLocks;
+pub use log_store::JsonlLogStore;
+pub use memory::{Database, ExperimentOutcome, IdempotentRequest, StepContext};
+pub use orchestrator::{Orchestrator, StepProgress};
+pub use processes::ProcessManager;
+pub use reservations::FileReservations;
+pub use scheduler::{MissionScheduler, QueueSnapshot};
+pub use snapshots::WorkspaceSnapshots;
+pub use terminals::MissionTerminals;
+pub use vector_memory::{VectorMemory, SearchResult, SearchExplain, CodeChunk, ContentType, PruneReport, ChatExport};
@@ -0,0 +1,170 @@
+//! Workspace locks
+//!
+//! Advisory locks, stored in the database so every node sees them, that keep
+//! conflicting mutations apart: two missions writing the same file, or two git
+//! operations on one repository. A held lock is refreshed in the background and
+//! released when its guard drops; one whose holder died expires after the TTL.
+
+use spawn_core::{LockConflict, LockInfo, LockPolicy, Result, SpawnError};
+use std::path::{Component, Path};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::memory::Database;
+
+/// Pause between attempts while waiting for a lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lock name for a file, relative to the workspace root
+pub fn file_resource(path: &str) -> String {
+    format!("file:{}", normalize(path))
+}
+
+/// Lock name for the git repository at `path`, relative to the workspace root
+pub fn git_resource(path: &str) -> String {
+    format!("git:{}", normalize(path))
+}
+
+/// `./src//a/../b.rs` and `src/b.rs` must name the same lock
+pub(crate) fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str().unwrap_or_default()),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    if parts.is_empty() { ".".to_string() } else { parts.join("/") }
+}
+
+pub struct WorkspaceLocks {
+    db: Arc<Database>,
+    policy: LockPolicy,
+}
+
+impl WorkspaceLocks {
+    pub fn new(db: Arc<Database>, policy: LockPolicy) -> Self {
+        Self { db, policy }
+    }
+
+    /// Take a lock, waiting or failing per the policy when it is held
+    pub async fn acquire(&self, resource: &str, holder: &str, purpose: &str) -> Result<LockGuard> {
+        let ttl = Duration::from_secs(self.policy.ttl_secs);
+        let ttl_chrono = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(self.policy.wait_secs);
+
+        loop {
+            let current = match self.db.try_lock(resource, holder, purpose, ttl_chrono).await? {
+                None => break,
+                Some(current) => current,
+            };
+            if self.policy.on_conflict == LockConflict::Fail || tokio::time::Instant::now() >= deadline {
+                return Err(SpawnError::Locked { resource: current.resource, holder: current.holder });
+            }
+            debug!(resource, holder = %current.holder, "Waiting for workspace lock");
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+
+        let refresher = tokio::spawn({
+            let (db, resource, holder) = (self.db.clone(), resource.to_string(), holder.to_string());
+            async move {
+                let mut interval = tokio::time::interval(ttl / 3);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = db.refresh_lock(&resource, &holder, ttl_chrono).await {
+                        warn!(resource, error = %e, "Failed to refresh workspace lock");
+                    }
+                }
+            }
+        });
+        Ok(LockGuard {
+            db: self.db.clone(),
+            resource: resource.to_string(),
+            holder: holder.to_string(),
+            refresher,
+        })
+    }
+
+    /// Take several locks, in a fixed order so two callers can't deadlock
+    pub async fn acquire_all(&self, resources: &[String], holder: &str, purpose: &str) -> Result<Vec<LockGuard>> {
+        let mut resources = resources.to_vec();
+        resources.sort();
+        resources.dedup();
+        let mut guards = Vec::with_capacity(resources.len());
+        for resource in &resources {
+            match self.acquire(resource, holder, purpose).await {
+                Ok(guard) => guards.push(guard),
+                Err(e) => {
+                    release_all(guards).await;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(guards)
+    }
+
+    pub async fn list(&self) -> Result<Vec<LockInfo>> {
+        self.db.list_locks().await
+    }
+}
+
+/// A held lock. `release` frees it before returning; dropping the guard
+/// frees it in the background.
+pub struct LockGuard {
+    db: Arc<Database>,
+    resource: String,
+    holder: String,
+    refresher: JoinHandle<()>,
+}
+
+impl LockGuard {
+    pub async fn release(mut self) {
+        self.refresher.abort();
+        let resource = std::mem::take(&mut self.resource);
+        if let Err(e) = self.db.unlock(&resource, &self.holder).await {
+            warn!(resource, error = %e, "Failed to release workspace lock");
+        }
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        self.refresher.abort();
+        if self.resource.is_empty() {
+            // Already released
+            return;
+        }
+        let (db, resource, holder) = (self.db.clone(), std::mem::take(&mut self.resource), std::mem::take(&mut self.holder));
+        tokio::spawn(async move {
+            if let Err(e) = db.unlock(&resource, &holder).await {
+                // It still expires after the TTL
+                warn!(resource, error = %e, "Failed to release workspace lock");
+            }
+        });
+    }
+}
+
+pub async fn release_all(guards: Vec<LockGuard>) {
+    for guard in guards {
+        guard.release().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_names_are_normalized() {
+        assert_eq!(file_resource("./src//a/../b.rs"), "file:src/b.rs");
+        assert_eq!(file_resource("src/b.rs"), "file:src/b.rs");
+        assert_eq!(git_resource(""), "git:.");
+        assert_eq!(git_resource("repos/app/"), "git:repos/app");
+    }
+}
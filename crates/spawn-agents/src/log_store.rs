@@ -0,0 +1,174 @@
+//! Mission log storage backends
+//!
+//! Logs go to the `mission_logs` table by default. Deployments producing a
+//! lot of trace output can write them to append-only JSON lines files
+//! instead, one per mission, and hand each finished file to a shell command
+//! (typically an `aws s3 cp`) for archiving.
+
+use async_trait::async_trait;
+use spawn_core::{
+    from_envelope, to_envelope, LogBackend, LogEntry, LogStorageConfig, LogStore, LogTier, MissionId, Result, SpawnError,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::memory::Database;
+
+/// The store `config` selects; the database one needs no setup
+pub async fn from_config(config: &LogStorageConfig, db: Arc<Database>) -> Result<Arc<dyn LogStore>> {
+    match config.backend {
+        LogBackend::Database => Ok(db),
+        LogBackend::Jsonl => {
+            let store = JsonlLogStore::new(&config.dir).await?;
+            Ok(Arc::new(match &config.ship_command {
+                Some(command) => store.with_ship_command(command.clone()),
+                None => store,
+            }))
+        }
+    }
+}
+
+#[async_trait]
+impl LogStore for Database {
+    async fn append(&self, entry: &LogEntry) -> Result<()> {
+        self.append_log(entry).await
+    }
+
+    async fn list(&self, mission_id: &MissionId) -> Result<Vec<LogEntry>> {
+        self.list_logs(mission_id).await
+    }
+
+    async fn list_tier(&self, mission_id: &MissionId, tier: LogTier) -> Result<Vec<LogEntry>> {
+        self.list_logs_tier(mission_id, tier).await
+    }
+
+    async fn prune_verbose(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        self.prune_verbose_logs(before).await
+    }
+
+    async fn completion_summary(&self, mission_id: &MissionId) -> Result<Option<String>> {
+        Database::completion_summary(self, mission_id).await
+    }
+
+    fn backend_name(&self) -> &str {
+        "database"
+    }
+}
+
+/// One `<mission id>.jsonl` file per mission
+pub struct JsonlLogStore {
+    dir: PathBuf,
+    ship_command: Option<String>,
+    /// Keeps concurrent appends from interleaving within a line
+    write: tokio::sync::Mutex<()>,
+}
+
+impl JsonlLogStore {
+    /// Creates `dir` if needed
+    pub async fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(&dir).await.map_err(|e| io_error(&dir, e))?;
+        Ok(Self { dir, ship_command: None, write: tokio::sync::Mutex::new(()) })
+    }
+
+    /// Run `command` through `sh -c` when a mission finishes, with `{file}`
+    /// and `{mission_id}` replaced
+    pub fn with_ship_command(mut self, command: impl Into<String>) -> Self {
+        self.ship_command = Some(command.into());
+        self
+    }
+
+    fn path(&self, mission_id: &str) -> Result<PathBuf> {
+        let safe = !mission_id.is_empty()
+            && mission_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !safe {
+            return Err(SpawnError::Internal(format!("Invalid mission id for log file: '{}'", mission_id)));
+        }
+        Ok(self.dir.join(format!("{}.jsonl", mission_id)))
+    }
+}
+
+#[async_trait]
+impl LogStore for JsonlLogStore {
+    async fn append(&self, entry: &LogEntry) -> Result<()> {
+        let path = self.path(&entry.mission_id)?;
+        let mut line = serde_json::to_string(&to_envelope(entry)?)?;
+        line.push('\n');
+
+        let _write = self.write.lock().await;
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await
+            .map_err(|e| io_error(&path, e))?;
+        file.write_all(line.as_bytes()).await.map_err(|e| io_error(&path, e))?;
+        Ok(())
+    }
+
+    async fn list(&self, mission_id: &MissionId) -> Result<Vec<LogEntry>> {
+        let path = self.path(mission_id)?;
+        let text = match tokio::fs::read_to_string(&path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&path, e)),
+        };
+        Ok(text.lines().filter_map(parse_line).collect())
+    }
+
+    async fn finish(&self, mission_id: &MissionId) -> Result<()> {
+        let Some(command) = &self.ship_command else {
+            return Ok(());
+        };
+        let path = self.path(mission_id)?;
+        if !path.exists() {
+            return Ok(());
+        }
+        let command = command
+            .replace("{file}", &path.display().to_string())
+            .replace("{mission_id}", mission_id);
+        let output = Command::new("sh").arg("-c").arg(&command).output().await
+            .map_err(|e| SpawnError::ToolError(format!("Failed to run log ship command: {}", e)))?;
+        if !output.status.success() {
+            return Err(SpawnError::ToolError(format!(
+                "Log ship command failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        info!(mission_id = %mission_id, "Mission log shipped");
+        Ok(())
+    }
+
+    fn backend_name(&self) -> &str {
+        "jsonl"
+    }
+}
+
+/// An entry from one line of a log file, whichever release wrote it. A line
+/// cut short by a crash mid-write is skipped.
+fn parse_line(line: &str) -> Option<LogEntry> {
+    from_envelope(serde_json::from_str(line).ok()?).ok()
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> SpawnError {
+    SpawnError::Internal(format!("Mission log {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_reads_old_and_new_entries() {
+        let entry = LogEntry::new("m1", "assistant", "DONE: ok");
+        let versioned = serde_json::to_string(&to_envelope(&entry).unwrap()).unwrap();
+        let bare = serde_json::to_string(&entry).unwrap();
+
+        for line in [versioned, bare] {
+            let parsed = parse_line(&line).unwrap();
+            assert_eq!((parsed.mission_id.as_str(), parsed.content.as_str()), ("m1", "DONE: ok"));
+        }
+        assert!(parse_line(r#"{"version":99,"payload":{}}"#).is_none());
+        assert!(parse_line(r#"{"mission_id":"m1","age"#).is_none());
+    }
+}
@@ -1,9 +1,24 @@
 //! Database layer for persistent memory
 
-use spawn_core::{Mission, MissionStatus, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::Serialize;
+use spawn_core::{
+    from_envelope, normalize_tags, to_envelope, AgentId, AuditEntry, ChatMessage, ChatResponse, Citation, ClusterNode, Diagnostic, DocFormat, Document, Experiment,
+    ExperimentVariant, FailureCategory, FileCoverage, FileReservation, HealthCheck, LocaleScope, LocaleSetting, LockInfo, LogEntry, LogTier, Mission, MissionFilter, MissionId, MissionStatus,
+    MissionUsage, PostMortem, ResponseStore, Result, SavedFilter, SessionKind, Severity, SpawnError, Task, TaskId, TaskStatus, TokenUsage, Workspace,
+};
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use sqlx::SqlitePool;
 use tracing::info;
 
+/// resource, holder, purpose, acquired_at, expires_at (locks), or path,
+/// owner, reason, created_at, expires_at (file reservations)
+type LockRow = (String, String, String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>);
+type OutcomeRow = (String, String, chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>, i64);
+/// request_hash, status, content_type, response
+type IdempotencyRow = (String, Option<i64>, Option<String>, Option<Vec<u8>>);
+
 pub struct Database {
     pool: SqlitePool,
 }
@@ -26,11 +41,13 @@ impl Database {
     pub async fn create_mission(&self, mission: &Mission) -> Result<()> {
         let status = serde_json::to_string(&mission.status)?;
         let context = serde_json::to_string(&mission.context)?;
+        let priority = serde_json::to_string(&mission.priority)?;
+        let budget = serde_json::to_string(&mission.budget)?;
         
         sqlx::query(
             r#"
-            INSERT INTO missions (id, goal, status, created_at, updated_at, context)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO missions (id, goal, status, created_at, updated_at, context, priority, budget)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(&mission.id)
@@ -39,90 +56,1615 @@ impl Database {
         .bind(mission.created_at)
         .bind(mission.updated_at)
         .bind(&context)
+        .bind(&priority)
+        .bind(&budget)
         .execute(&self.pool)
         .await?;
         
+        if !mission.tags.is_empty() {
+            self.set_mission_tags(&mission.id, &mission.tags).await?;
+        }
+        
         Ok(())
     }
     
     /// Get mission by ID
-    pub async fn get_mission(&self, id: &str) -> Result<Option<Mission>> {
+    pub async fn get_mission(&self, id: &MissionId) -> Result<Option<Mission>> {
         let row = sqlx::query_as::<_, MissionRow>(
-            "SELECT id, goal, status, created_at, updated_at, context FROM missions WHERE id = ?"
+            "SELECT id, goal, status, created_at, updated_at, context, priority, budget FROM missions WHERE id = ?"
         )
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
         
-        Ok(row.map(|r| r.into_mission()))
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let mut missions = vec![row.into_mission()];
+        self.attach_tags(&mut missions).await?;
+        Ok(missions.pop())
     }
     
-    /// Update mission status
-    pub async fn update_mission_status(&self, id: &str, status: MissionStatus) -> Result<()> {
+    /// Update mission status, rejecting changes the current status doesn't
+    /// allow (see `MissionStatus::can_transition_to`)
+    pub async fn update_mission_status(&self, id: &MissionId, status: MissionStatus) -> Result<()> {
         let status_str = serde_json::to_string(&status)?;
-        let now = chrono::Utc::now();
         
-        sqlx::query("UPDATE missions SET status = ?, updated_at = ? WHERE id = ?")
-            .bind(&status_str)
-            .bind(now)
+        loop {
+            let current: Option<String> = sqlx::query_scalar("SELECT status FROM missions WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+            let Some(current) = current else {
+                return Err(SpawnError::NotFound(format!("mission {}", id)));
+            };
+            let from: MissionStatus = serde_json::from_str(&current).map_err(|e| {
+                SpawnError::Internal(format!("mission {} has unreadable status {:?}: {}", id, current, e))
+            })?;
+            if !from.can_transition_to(&status) {
+                return Err(SpawnError::InvalidTransition { from, to: status });
+            }
+            
+            // Only if nobody changed it since we looked
+            let result = sqlx::query("UPDATE missions SET status = ?, updated_at = ? WHERE id = ? AND status = ?")
+                .bind(&status_str)
+                .bind(chrono::Utc::now())
+                .bind(id)
+                .bind(&current)
+                .execute(&self.pool)
+                .await?;
+            if result.rows_affected() > 0 {
+                return Ok(());
+            }
+        }
+    }
+    
+    /// List all missions
+    pub async fn list_missions(&self) -> Result<Vec<Mission>> {
+        self.list_missions_filtered(&MissionFilter::default()).await
+    }
+    
+    /// List missions matching a filter, newest first
+    pub async fn list_missions_filtered(&self, filter: &MissionFilter) -> Result<Vec<Mission>> {
+        let status = filter.status.as_ref().map(serde_json::to_string).transpose()?;
+        let query = filter.query.as_ref().map(|q| format!("%{}%", q.to_lowercase()));
+        let tags = normalize_tags(filter.tags.iter().cloned());
+        
+        let rows = sqlx::query_as::<_, MissionRow>(
+            r#"
+            SELECT id, goal, status, created_at, updated_at, context, priority, budget
+            FROM missions
+            WHERE (? IS NULL OR status = ?)
+              AND (? IS NULL OR LOWER(goal) LIKE ?)
+              AND (? = 0 OR id IN (
+                  SELECT mission_id FROM mission_tags
+                  WHERE tag IN (SELECT value FROM json_each(?))
+                  GROUP BY mission_id
+                  HAVING COUNT(*) = ?
+              ))
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(&status)
+        .bind(&status)
+        .bind(&query)
+        .bind(&query)
+        .bind(tags.len() as i64)
+        .bind(serde_json::to_string(&tags)?)
+        .bind(tags.len() as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut missions: Vec<Mission> = rows.into_iter().map(|r| r.into_mission()).collect();
+        self.attach_tags(&mut missions).await?;
+        Ok(missions)
+    }
+    
+    async fn attach_tags(&self, missions: &mut [Mission]) -> Result<()> {
+        if missions.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<&str> = missions.iter().map(|m| m.id.as_str()).collect();
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT mission_id, tag FROM mission_tags
+            WHERE mission_id IN (SELECT value FROM json_each(?))
+            ORDER BY tag
+            "#
+        )
+        .bind(serde_json::to_string(&ids)?)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut by_mission: HashMap<String, Vec<String>> = HashMap::new();
+        for (mission_id, tag) in rows {
+            by_mission.entry(mission_id).or_default().push(tag);
+        }
+        for mission in missions {
+            mission.tags = by_mission.remove(mission.id.as_str()).unwrap_or_default();
+        }
+        Ok(())
+    }
+    
+    /// Replace a mission's tags; returns the normalized set
+    pub async fn set_mission_tags(&self, mission_id: &MissionId, tags: &[String]) -> Result<Vec<String>> {
+        let tags = normalize_tags(tags.iter().cloned());
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query("DELETE FROM mission_tags WHERE mission_id = ?")
+            .bind(mission_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        for tag in &tags {
+            sqlx::query("INSERT INTO mission_tags (mission_id, tag) VALUES (?, ?)")
+                .bind(mission_id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(tags)
+    }
+    
+    /// All tags in use, with mission counts
+    pub async fn list_tags(&self) -> Result<Vec<(String, i64)>> {
+        let rows = sqlx::query_as(
+            "SELECT tag, COUNT(*) FROM mission_tags GROUP BY tag ORDER BY COUNT(*) DESC, tag"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Save a named filter
+    pub async fn create_saved_filter(&self, name: &str, filter: &MissionFilter) -> Result<SavedFilter> {
+        let saved = SavedFilter {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            filter: filter.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        
+        sqlx::query("INSERT INTO saved_filters (id, name, filter, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&saved.id)
+            .bind(&saved.name)
+            .bind(serde_json::to_string(&saved.filter)?)
+            .bind(saved.created_at)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(saved)
+    }
+    
+    /// Get a saved filter by ID or name
+    pub async fn get_saved_filter(&self, id_or_name: &str) -> Result<Option<SavedFilter>> {
+        let row = sqlx::query_as::<_, SavedFilterRow>(
+            "SELECT id, name, filter, created_at FROM saved_filters WHERE id = ? OR name = ?"
+        )
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|r| r.into_saved_filter()))
+    }
+    
+    pub async fn list_saved_filters(&self) -> Result<Vec<SavedFilter>> {
+        let rows = sqlx::query_as::<_, SavedFilterRow>(
+            "SELECT id, name, filter, created_at FROM saved_filters ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|r| r.into_saved_filter()).collect())
+    }
+    
+    /// Delete a saved filter; returns whether it existed
+    pub async fn delete_saved_filter(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM saved_filters WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await?;
         
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
     
-    /// List all missions
-    pub async fn list_missions(&self) -> Result<Vec<Mission>> {
-        let rows = sqlx::query_as::<_, MissionRow>(
-            "SELECT id, goal, status, created_at, updated_at, context FROM missions ORDER BY created_at DESC"
+    /// Start an experiment; it takes new missions until stopped
+    pub async fn create_experiment(&self, name: &str, variants: &[ExperimentVariant]) -> Result<Experiment> {
+        let experiment = Experiment {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            variants: variants.to_vec(),
+            active: true,
+            created_at: chrono::Utc::now(),
+        };
+        
+        sqlx::query("INSERT INTO experiments (id, name, variants, active, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&experiment.id)
+            .bind(&experiment.name)
+            .bind(serde_json::to_string(&experiment.variants)?)
+            .bind(experiment.active)
+            .bind(experiment.created_at)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(experiment)
+    }
+    
+    /// All experiments, newest first
+    pub async fn list_experiments(&self) -> Result<Vec<Experiment>> {
+        let rows = sqlx::query_as::<_, ExperimentRow>(
+            "SELECT id, name, variants, active, created_at FROM experiments ORDER BY created_at DESC"
         )
         .fetch_all(&self.pool)
         .await?;
         
-        Ok(rows.into_iter().map(|r| r.into_mission()).collect())
+        Ok(rows.into_iter().map(|r| r.into_experiment()).collect())
     }
     
-    /// Log a step in mission execution
-    pub async fn log_step(&self, mission_id: &str, agent: &str, content: &str) -> Result<()> {
-        let id = uuid::Uuid::new_v4().to_string();
-        let now = chrono::Utc::now();
+    /// Get an experiment by ID or name
+    pub async fn get_experiment(&self, id_or_name: &str) -> Result<Option<Experiment>> {
+        let row = sqlx::query_as::<_, ExperimentRow>(
+            "SELECT id, name, variants, active, created_at FROM experiments WHERE id = ? OR name = ?"
+        )
+        .bind(id_or_name)
+        .bind(id_or_name)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|r| r.into_experiment()))
+    }
+    
+    /// The experiment new missions are assigned to: the newest active one
+    pub async fn active_experiment(&self) -> Result<Option<Experiment>> {
+        let row = sqlx::query_as::<_, ExperimentRow>(
+            "SELECT id, name, variants, active, created_at FROM experiments WHERE active = 1 ORDER BY created_at DESC LIMIT 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|r| r.into_experiment()))
+    }
+    
+    /// Start or stop an experiment; returns whether it exists
+    pub async fn set_experiment_active(&self, id: &str, active: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE experiments SET active = ? WHERE id = ?")
+            .bind(active)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
         
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Record which variant a mission runs with
+    pub async fn assign_experiment(&self, mission_id: &MissionId, experiment_id: &str, variant: &str) -> Result<()> {
         sqlx::query(
-            "INSERT INTO mission_logs (id, mission_id, agent, content, created_at) VALUES (?, ?, ?, ?, ?)"
+            "INSERT OR REPLACE INTO mission_experiments (mission_id, experiment_id, variant, created_at) VALUES (?, ?, ?, ?)"
         )
-        .bind(&id)
         .bind(mission_id)
-        .bind(agent)
-        .bind(content)
-        .bind(now)
+        .bind(experiment_id)
+        .bind(variant)
+        .bind(chrono::Utc::now())
         .execute(&self.pool)
         .await?;
         
         Ok(())
     }
-}
-
-// Internal row type for SQLx
-#[derive(sqlx::FromRow)]
-struct MissionRow {
-    id: String,
-    goal: String,
-    status: String,
-    created_at: chrono::DateTime<chrono::Utc>,
-    updated_at: chrono::DateTime<chrono::Utc>,
-    context: String,
-}
-
-impl MissionRow {
-    fn into_mission(self) -> Mission {
-        Mission {
-            id: self.id,
-            goal: self.goal,
-            status: serde_json::from_str(&self.status).unwrap_or(MissionStatus::Pending),
-            created_at: self.created_at,
-            updated_at: self.updated_at,
-            context: serde_json::from_str(&self.context).unwrap_or(serde_json::json!({})),
+    
+    /// Every mission an experiment assigned, with how it went
+    pub async fn experiment_outcomes(&self, experiment_id: &str) -> Result<Vec<ExperimentOutcome>> {
+        let rows: Vec<OutcomeRow> = sqlx::query_as(
+            r#"
+            SELECT me.variant, m.status, m.created_at, m.updated_at,
+                   (SELECT COUNT(*) FROM step_contexts sc WHERE sc.mission_id = m.id)
+            FROM mission_experiments me
+            JOIN missions m ON m.id = me.mission_id
+            WHERE me.experiment_id = ?
+            "#
+        )
+        .bind(experiment_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(variant, status, created_at, updated_at, steps)| ExperimentOutcome {
+                variant,
+                status: serde_json::from_str(&status).unwrap_or(MissionStatus::Pending),
+                duration: updated_at - created_at,
+                steps,
+            })
+            .collect())
+    }
+    
+    /// Store (or replace) a mission's tasks
+    pub async fn save_tasks(&self, tasks: &[Task]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        for task in tasks {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO tasks (id, mission_id, title, status, depends_on, assigned_agent, result, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&task.id)
+            .bind(&task.mission_id)
+            .bind(&task.title)
+            .bind(serde_json::to_string(&task.status)?)
+            .bind(serde_json::to_string(&task.depends_on)?)
+            .bind(&task.assigned_agent)
+            .bind(&task.result)
+            .bind(task.created_at)
+            .bind(task.updated_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// A mission's tasks in creation order
+    pub async fn list_tasks(&self, mission_id: &MissionId) -> Result<Vec<Task>> {
+        let rows = sqlx::query_as::<_, TaskRow>(
+            r#"
+            SELECT id, mission_id, title, status, depends_on, assigned_agent, result, created_at, updated_at
+            FROM tasks
+            WHERE mission_id = ?
+            ORDER BY created_at, rowid
+            "#
+        )
+        .bind(mission_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|r| r.into_task()).collect())
+    }
+    
+    pub async fn update_task_status(&self, id: &TaskId, status: TaskStatus, result: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE tasks SET status = ?, result = COALESCE(?, result), updated_at = ? WHERE id = ?")
+            .bind(serde_json::to_string(&status)?)
+            .bind(result)
+            .bind(chrono::Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Register a workspace root under a name
+    pub async fn register_workspace(&self, name: &str, root: &str) -> Result<Workspace> {
+        let workspace = Workspace {
+            name: name.to_string(),
+            root: root.to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        
+        sqlx::query("INSERT INTO workspaces (name, root, created_at) VALUES (?, ?, ?)")
+            .bind(&workspace.name)
+            .bind(&workspace.root)
+            .bind(workspace.created_at)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(workspace)
+    }
+    
+    pub async fn list_workspaces(&self) -> Result<Vec<Workspace>> {
+        let rows: Vec<(String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+            "SELECT name, root, created_at FROM workspaces ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(name, root, created_at)| Workspace { name, root, created_at })
+            .collect())
+    }
+    
+    /// Unregister a workspace; returns whether it existed
+    pub async fn remove_workspace(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workspaces WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Set the locale of a workspace or user, replacing any earlier one
+    pub async fn set_locale(&self, scope: LocaleScope, id: &str, locale: &str) -> Result<LocaleSetting> {
+        let setting = LocaleSetting {
+            scope,
+            id: id.to_string(),
+            locale: locale.to_string(),
+            updated_at: chrono::Utc::now(),
+        };
+        
+        sqlx::query("INSERT OR REPLACE INTO locales (scope, id, locale, updated_at) VALUES (?, ?, ?, ?)")
+            .bind(scope.as_str())
+            .bind(&setting.id)
+            .bind(&setting.locale)
+            .bind(setting.updated_at)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(setting)
+    }
+    
+    pub async fn get_locale(&self, scope: LocaleScope, id: &str) -> Result<Option<String>> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT locale FROM locales WHERE scope = ? AND id = ?")
+            .bind(scope.as_str())
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(row.map(|(locale,)| locale))
+    }
+    
+    pub async fn list_locales(&self) -> Result<Vec<LocaleSetting>> {
+        let rows: Vec<(String, String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+            "SELECT scope, id, locale, updated_at FROM locales ORDER BY scope, id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|(scope, id, locale, updated_at)| Some(LocaleSetting {
+                scope: serde_json::from_value(serde_json::json!(scope)).ok()?,
+                id,
+                locale,
+                updated_at,
+            }))
+            .collect())
+    }
+    
+    /// Remove a workspace's or user's locale; returns whether it had one
+    pub async fn remove_locale(&self, scope: LocaleScope, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM locales WHERE scope = ? AND id = ?")
+            .bind(scope.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Record that a node is alive and where to reach it
+    pub async fn heartbeat_node(&self, id: &str, url: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cluster_nodes (id, url, heartbeat_at) VALUES (?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET url = excluded.url, heartbeat_at = excluded.heartbeat_at
+            "#
+        )
+        .bind(id)
+        .bind(url)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Nodes heard from since `since`
+    pub async fn list_nodes(&self, since: chrono::DateTime<chrono::Utc>) -> Result<Vec<ClusterNode>> {
+        let rows: Vec<(String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+            "SELECT id, url, heartbeat_at FROM cluster_nodes WHERE heartbeat_at >= ? ORDER BY id"
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(id, url, heartbeat_at)| ClusterNode { id, url, heartbeat_at })
+            .collect())
+    }
+    
+    /// Record `node_id` as the owner of a session, taking it over from any previous owner
+    pub async fn claim_session(&self, kind: SessionKind, session_id: &str, node_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO session_owners (kind, session_id, node_id, claimed_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(kind, session_id) DO UPDATE SET node_id = excluded.node_id, claimed_at = excluded.claimed_at
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(session_id)
+        .bind(node_id)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Drop a session's ownership, if `node_id` still holds it
+    pub async fn release_session(&self, kind: SessionKind, session_id: &str, node_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM session_owners WHERE kind = ? AND session_id = ? AND node_id = ?")
+            .bind(kind.as_str())
+            .bind(session_id)
+            .bind(node_id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Drop everything a node owned; returns how many sessions it held
+    pub async fn release_node_sessions(&self, node_id: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM session_owners WHERE node_id = ?")
+            .bind(node_id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// The node holding a session, with its last heartbeat
+    pub async fn session_owner(&self, kind: SessionKind, session_id: &str) -> Result<Option<ClusterNode>> {
+        let row: Option<(String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+            r#"
+            SELECT n.id, n.url, n.heartbeat_at FROM session_owners o
+            JOIN cluster_nodes n ON n.id = o.node_id
+            WHERE o.kind = ? AND o.session_id = ?
+            "#
+        )
+        .bind(kind.as_str())
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|(id, url, heartbeat_at)| ClusterNode { id, url, heartbeat_at }))
+    }
+    
+    /// Take a lock if it is free or expired. Returns the current holder
+    /// when someone else has it.
+    pub async fn try_lock(&self, resource: &str, holder: &str, purpose: &str, ttl: chrono::Duration) -> Result<Option<LockInfo>> {
+        loop {
+            let now = chrono::Utc::now();
+            let result = sqlx::query(
+                r#"
+                INSERT INTO workspace_locks (resource, holder, purpose, acquired_at, expires_at) VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(resource) DO UPDATE SET
+                    holder = excluded.holder, purpose = excluded.purpose,
+                    acquired_at = excluded.acquired_at, expires_at = excluded.expires_at
+                WHERE workspace_locks.expires_at < excluded.acquired_at
+                "#
+            )
+            .bind(resource)
+            .bind(holder)
+            .bind(purpose)
+            .bind(now)
+            .bind(now + ttl)
+            .execute(&self.pool)
+            .await?;
+            
+            if result.rows_affected() > 0 {
+                return Ok(None);
+            }
+            let row: Option<LockRow> = sqlx::query_as(
+                "SELECT resource, holder, purpose, acquired_at, expires_at FROM workspace_locks WHERE resource = ?"
+            )
+            .bind(resource)
+            .fetch_optional(&self.pool)
+            .await?;
+            
+            // None: released between the two queries, so try again
+            if let Some((resource, holder, purpose, acquired_at, expires_at)) = row {
+                return Ok(Some(LockInfo { resource, holder, purpose, acquired_at, expires_at }));
+            }
         }
     }
+    
+    /// Push back a held lock's expiry
+    pub async fn refresh_lock(&self, resource: &str, holder: &str, ttl: chrono::Duration) -> Result<()> {
+        sqlx::query("UPDATE workspace_locks SET expires_at = ? WHERE resource = ? AND holder = ?")
+            .bind(chrono::Utc::now() + ttl)
+            .bind(resource)
+            .bind(holder)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    pub async fn unlock(&self, resource: &str, holder: &str) -> Result<()> {
+        sqlx::query("DELETE FROM workspace_locks WHERE resource = ? AND holder = ?")
+            .bind(resource)
+            .bind(holder)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Unexpired locks, oldest first
+    pub async fn list_locks(&self) -> Result<Vec<LockInfo>> {
+        let rows: Vec<LockRow> = sqlx::query_as(
+            r#"
+            SELECT resource, holder, purpose, acquired_at, expires_at FROM workspace_locks
+            WHERE expires_at >= ?
+            ORDER BY acquired_at
+            "#
+        )
+        .bind(chrono::Utc::now())
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(resource, holder, purpose, acquired_at, expires_at)| LockInfo { resource, holder, purpose, acquired_at, expires_at })
+            .collect())
+    }
+    
+    /// Reserve a file if it is free, expired or already the owner's (which
+    /// renews it). Returns the current reservation when someone else has it.
+    pub async fn reserve_file(&self, reservation: &FileReservation) -> Result<Option<FileReservation>> {
+        loop {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO file_reservations (path, owner, reason, created_at, expires_at) VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(path) DO UPDATE SET
+                    owner = excluded.owner, reason = excluded.reason,
+                    created_at = CASE WHEN file_reservations.owner = excluded.owner
+                        THEN file_reservations.created_at ELSE excluded.created_at END,
+                    expires_at = excluded.expires_at
+                WHERE file_reservations.owner = excluded.owner OR file_reservations.expires_at < excluded.created_at
+                "#
+            )
+            .bind(&reservation.path)
+            .bind(&reservation.owner)
+            .bind(&reservation.reason)
+            .bind(reservation.created_at)
+            .bind(reservation.expires_at)
+            .execute(&self.pool)
+            .await?;
+            
+            if result.rows_affected() > 0 {
+                return Ok(None);
+            }
+            // None: released between the two queries, so try again
+            if let Some(current) = self.file_reservation(&reservation.path).await? {
+                return Ok(Some(current));
+            }
+        }
+    }
+    
+    /// The unexpired reservation on a file, if any
+    pub async fn file_reservation(&self, path: &str) -> Result<Option<FileReservation>> {
+        let row: Option<LockRow> = sqlx::query_as(
+            "SELECT path, owner, reason, created_at, expires_at FROM file_reservations WHERE path = ? AND expires_at >= ?"
+        )
+        .bind(path)
+        .bind(chrono::Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|(path, owner, reason, created_at, expires_at)| FileReservation { path, owner, reason, created_at, expires_at }))
+    }
+    
+    pub async fn release_file(&self, path: &str, owner: &str) -> Result<()> {
+        sqlx::query("DELETE FROM file_reservations WHERE path = ? AND owner = ?")
+            .bind(path)
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Drop every reservation `owner` holds; returns how many there were
+    pub async fn release_files_of(&self, owner: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM file_reservations WHERE owner = ?")
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Unexpired reservations, oldest first
+    pub async fn list_file_reservations(&self) -> Result<Vec<FileReservation>> {
+        let rows: Vec<LockRow> = sqlx::query_as(
+            r#"
+            SELECT path, owner, reason, created_at, expires_at FROM file_reservations
+            WHERE expires_at >= ?
+            ORDER BY created_at
+            "#
+        )
+        .bind(chrono::Utc::now())
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(path, owner, reason, created_at, expires_at)| FileReservation { path, owner, reason, created_at, expires_at })
+            .collect())
+    }
+    
+    /// Claim `key` for the request hashing to `request_hash`, or say where
+    /// the request that claimed it before stands. Keys older than `ttl` are
+    /// forgotten first, and claims without a response after `lease` are
+    /// taken to belong to a request that never finished.
+    pub async fn begin_idempotent(
+        &self,
+        key: &str,
+        request_hash: &str,
+        ttl: chrono::Duration,
+        lease: chrono::Duration,
+    ) -> Result<IdempotentRequest> {
+        let now = chrono::Utc::now();
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ? OR (status IS NULL AND created_at < ?)")
+            .bind(now - ttl)
+            .bind(now - lease)
+            .execute(&self.pool)
+            .await?;
+        
+        let claimed = sqlx::query("INSERT INTO idempotency_keys (key, request_hash, created_at) VALUES (?, ?, ?) ON CONFLICT(key) DO NOTHING")
+            .bind(key)
+            .bind(request_hash)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        if claimed.rows_affected() > 0 {
+            return Ok(IdempotentRequest::New);
+        }
+        
+        let row: Option<IdempotencyRow> = sqlx::query_as(
+            "SELECT request_hash, status, content_type, response FROM idempotency_keys WHERE key = ?"
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(match row {
+            // Abandoned between the two queries
+            None => IdempotentRequest::InProgress,
+            Some((hash, _, _, _)) if hash != request_hash => IdempotentRequest::Mismatch,
+            Some((_, None, _, _)) => IdempotentRequest::InProgress,
+            Some((_, Some(status), content_type, body)) => IdempotentRequest::Completed {
+                status: status as u16,
+                content_type,
+                body: body.unwrap_or_default(),
+            },
+        })
+    }
+    
+    /// Store the response to the request holding `key`, for replay
+    pub async fn finish_idempotent(&self, key: &str, status: u16, content_type: Option<&str>, body: &[u8]) -> Result<()> {
+        sqlx::query("UPDATE idempotency_keys SET status = ?, content_type = ?, response = ? WHERE key = ?")
+            .bind(i64::from(status))
+            .bind(content_type)
+            .bind(body)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Release `key` without a response, so a retry runs the request again
+    pub async fn abandon_idempotent(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = ? AND status IS NULL")
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Append an entry to the audit log
+    pub async fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        sqlx::query("INSERT INTO audit_log (id, action, subject, details, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&entry.id)
+            .bind(&entry.action)
+            .bind(&entry.subject)
+            .bind(serde_json::to_string(&entry.details)?)
+            .bind(entry.created_at)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Audit entries, newest first, optionally for one action
+    pub async fn list_audit(&self, action: Option<&str>, limit: i64) -> Result<Vec<AuditEntry>> {
+        let rows: Vec<(String, String, String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+            r#"
+            SELECT id, action, subject, details, created_at FROM audit_log
+            WHERE (? IS NULL OR action = ?)
+            ORDER BY created_at DESC
+            LIMIT ?
+            "#
+        )
+        .bind(action)
+        .bind(action)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(id, action, subject, details, created_at)| AuditEntry {
+                id,
+                action,
+                subject,
+                details: serde_json::from_str(&details).unwrap_or_default(),
+                created_at,
+            })
+            .collect())
+    }
+    
+    /// Log a step in mission execution
+    pub async fn log_step(&self, mission_id: &MissionId, agent: &str, content: &str) -> Result<()> {
+        self.append_log(&LogEntry::new(mission_id, agent, content)).await
+    }
+    
+    pub async fn append_log(&self, entry: &LogEntry) -> Result<()> {
+        let id = uuid::Uuid::new_v4().to_string();
+        
+        sqlx::query(
+            "INSERT INTO mission_logs (id, mission_id, agent, content, verbose, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&entry.mission_id)
+        .bind(&entry.agent)
+        .bind(&entry.content)
+        .bind(&entry.verbose)
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Summary from the mission's final `DONE:` response, if it completed
+    pub async fn completion_summary(&self, mission_id: &MissionId) -> Result<Option<String>> {
+        let content: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT content FROM mission_logs
+            WHERE mission_id = ? AND agent = 'assistant' AND content LIKE '%DONE:%'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(mission_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(content.and_then(|c| {
+            c.split_once("DONE:").map(|(_, summary)| summary.trim().to_string())
+        }))
+    }
+    
+    /// All log entries for a mission, oldest first
+    pub async fn list_logs(&self, mission_id: &MissionId) -> Result<Vec<LogEntry>> {
+        self.list_logs_tier(mission_id, LogTier::Verbose).await
+    }
+    
+    /// Log entries in one tier; the concise tier doesn't read `verbose` at all
+    pub async fn list_logs_tier(&self, mission_id: &MissionId, tier: LogTier) -> Result<Vec<LogEntry>> {
+        let verbose = match tier {
+            LogTier::Concise => "NULL",
+            LogTier::Verbose => "verbose",
+        };
+        let rows: Vec<(String, String, Option<String>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(&format!(
+            "SELECT agent, content, {} AS verbose, created_at FROM mission_logs WHERE mission_id = ? ORDER BY created_at",
+            verbose
+        ))
+        .bind(mission_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(agent, content, verbose, created_at)| LogEntry { mission_id: mission_id.clone(), agent, content, verbose, created_at })
+            .collect())
+    }
+    
+    /// A cached model response
+    pub async fn cached_response(&self, key: &str) -> Result<Option<ChatResponse>> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT response FROM llm_response_cache WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(|(response,)| serde_json::from_str(&response).map_err(SpawnError::from)).transpose()
+    }
+    
+    pub async fn cache_response(&self, key: &str, response: &ChatResponse) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO llm_response_cache (key, model, response) VALUES (?, ?, ?)")
+            .bind(key)
+            .bind(&response.model)
+            .bind(serde_json::to_string(response)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+    
+    /// Clear the verbose tier of log entries written before `before`
+    pub async fn prune_verbose_logs(&self, before: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query("UPDATE mission_logs SET verbose = NULL WHERE verbose IS NOT NULL AND created_at < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+    
+    /// Mission counts per status
+    pub async fn mission_status_counts(&self) -> Result<Vec<(MissionStatus, i64)>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT status, COUNT(*) FROM missions GROUP BY status"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .filter_map(|(status, count)| serde_json::from_str(&status).ok().map(|s| (s, count)))
+            .collect())
+    }
+    
+    /// Add one step's completion to a mission's usage
+    pub async fn add_mission_usage(&self, id: &MissionId, usage: &TokenUsage, cost_usd: f64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO mission_usage (mission_id, steps, prompt_tokens, completion_tokens, cost_usd, updated_at)
+            VALUES (?, 1, ?, ?, ?, ?)
+            ON CONFLICT(mission_id) DO UPDATE SET
+                steps = steps + 1,
+                prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                completion_tokens = completion_tokens + excluded.completion_tokens,
+                cost_usd = cost_usd + excluded.cost_usd,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(id)
+        .bind(i64::from(usage.prompt_tokens))
+        .bind(i64::from(usage.completion_tokens))
+        .bind(cost_usd)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Usage of the `limit` most recently completed missions, newest first
+    pub async fn completed_mission_usage(&self, limit: i64) -> Result<Vec<MissionUsage>> {
+        let rows: Vec<(MissionId, String, i64, i64, i64, f64)> = sqlx::query_as(
+            r#"
+            SELECT u.mission_id, m.goal, u.steps, u.prompt_tokens, u.completion_tokens, u.cost_usd
+            FROM mission_usage u JOIN missions m ON m.id = u.mission_id
+            WHERE m.status = ?
+            ORDER BY u.updated_at DESC
+            LIMIT ?
+            "#
+        )
+        .bind(serde_json::to_string(&MissionStatus::Completed)?)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(mission_id, goal, steps, prompt_tokens, completion_tokens, cost_usd)| MissionUsage {
+                mission_id,
+                goal,
+                steps: steps as usize,
+                prompt_tokens: prompt_tokens as u64,
+                completion_tokens: completion_tokens as u64,
+                cost_usd,
+            })
+            .collect())
+    }
+    
+    /// Store (or replace) a mission's post-mortem
+    pub async fn save_post_mortem(&self, post_mortem: &PostMortem) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO post_mortems (mission_id, category, summary, remediation, model, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&post_mortem.mission_id)
+        .bind(serde_json::to_string(&post_mortem.category)?)
+        .bind(&post_mortem.summary)
+        .bind(&post_mortem.remediation)
+        .bind(&post_mortem.model)
+        .bind(post_mortem.created_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    pub async fn get_post_mortem(&self, mission_id: &MissionId) -> Result<Option<PostMortem>> {
+        let row = sqlx::query_as::<_, PostMortemRow>(
+            "SELECT mission_id, category, summary, remediation, model, created_at FROM post_mortems WHERE mission_id = ?"
+        )
+        .bind(mission_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|r| r.into_post_mortem()))
+    }
+    
+    /// Record the knowledge base chunks a completed mission cited
+    pub async fn save_citations(&self, mission_id: &MissionId, citations: &[Citation]) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO mission_citations (mission_id, citations, created_at) VALUES (?, ?, ?)"
+        )
+        .bind(mission_id)
+        .bind(serde_json::to_string(citations)?)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Citations of a completed mission's answer; empty if it cited nothing
+    pub async fn list_citations(&self, mission_id: &MissionId) -> Result<Vec<Citation>> {
+        let citations: Option<String> = sqlx::query_scalar(
+            "SELECT citations FROM mission_citations WHERE mission_id = ?"
+        )
+        .bind(mission_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        match citations {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Failed-mission counts per failure category
+    pub async fn failure_category_counts(&self) -> Result<Vec<(FailureCategory, i64)>> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT category, COUNT(*) FROM post_mortems GROUP BY category ORDER BY COUNT(*) DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(category, count)| (serde_json::from_str(&category).unwrap_or(FailureCategory::Unknown), count))
+            .collect())
+    }
+    
+    /// Persist the exact message array sent to the LLM at a step
+    pub async fn save_step_context(
+        &self,
+        mission_id: &MissionId,
+        step: usize,
+        model: &str,
+        messages: &[ChatMessage],
+    ) -> Result<()> {
+        let json = serde_json::to_vec(&to_envelope(&messages.to_vec())?)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let compressed = encoder.write_all(&json)
+            .and_then(|_| encoder.finish())
+            .map_err(|e| SpawnError::Internal(format!("Failed to compress context: {}", e)))?;
+        
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO step_contexts (mission_id, step, model, message_count, messages, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(mission_id)
+        .bind(step as i64)
+        .bind(model)
+        .bind(messages.len() as i64)
+        .bind(compressed)
+        .bind(chrono::Utc::now())
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// What the model saw at a step
+    pub async fn get_step_context(&self, mission_id: &MissionId, step: usize) -> Result<Option<StepContext>> {
+        let row = sqlx::query_as::<_, StepContextRow>(
+            "SELECT step, model, messages, created_at FROM step_contexts WHERE mission_id = ? AND step = ?"
+        )
+        .bind(mission_id)
+        .bind(step as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        row.map(|r| r.into_step_context()).transpose()
+    }
+    
+    /// Number of recorded steps for a mission
+    pub async fn count_step_contexts(&self, mission_id: &MissionId) -> Result<i64> {
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM step_contexts WHERE mission_id = ?")
+            .bind(mission_id)
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(count)
+    }
+    
+    /// Replace the stored diagnostics from one source (clippy, eslint, ...)
+    /// under `paths`, the files and directories just linted; all of that
+    /// source's diagnostics when `paths` is empty
+    pub async fn replace_diagnostics(&self, source: &str, paths: &[String], diagnostics: &[Diagnostic]) -> Result<()> {
+        let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+        
+        if paths.is_empty() {
+            sqlx::query("DELETE FROM diagnostics WHERE source = ?")
+                .bind(source)
+                .execute(&mut *tx)
+                .await?;
+        }
+        // Prefixes, as the lint run itself filters its diagnostics to `paths`
+        for path in paths {
+            let prefix = path.trim_start_matches("./");
+            sqlx::query("DELETE FROM diagnostics WHERE source = ? AND substr(file, 1, length(?)) = ?")
+                .bind(source)
+                .bind(prefix)
+                .bind(prefix)
+                .execute(&mut *tx)
+                .await?;
+        }
+        
+        for d in diagnostics {
+            sqlx::query(
+                r#"
+                INSERT INTO diagnostics (id, source, file, line, col, end_line, end_col, severity, code, message, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(source)
+            .bind(&d.file)
+            .bind(d.line)
+            .bind(d.column)
+            .bind(d.end_line)
+            .bind(d.end_column)
+            .bind(serde_json::to_string(&d.severity)?)
+            .bind(&d.code)
+            .bind(&d.message)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// List stored diagnostics, optionally for a single file
+    pub async fn list_diagnostics(&self, file: Option<&str>) -> Result<Vec<Diagnostic>> {
+        let rows = sqlx::query_as::<_, DiagnosticRow>(
+            r#"
+            SELECT source, file, line, col, end_line, end_col, severity, code, message
+            FROM diagnostics
+            WHERE (? IS NULL OR file = ?)
+            ORDER BY file, line
+            "#
+        )
+        .bind(file)
+        .bind(file)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|r| r.into_diagnostic()).collect())
+    }
+    
+    /// Replace all stored coverage from one source (llvm-cov, istanbul, ...)
+    pub async fn replace_coverage(&self, source: &str, files: &[FileCoverage]) -> Result<()> {
+        let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query("DELETE FROM coverage WHERE source = ?")
+            .bind(source)
+            .execute(&mut *tx)
+            .await?;
+        
+        for f in files {
+            sqlx::query(
+                r#"
+                INSERT INTO coverage (file, source, lines_total, lines_covered, uncovered_lines, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&f.file)
+            .bind(source)
+            .bind(f.lines_total)
+            .bind(f.lines_covered)
+            .bind(serde_json::to_string(&f.uncovered_lines)?)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// List stored coverage, optionally for a single file
+    pub async fn list_coverage(&self, file: Option<&str>) -> Result<Vec<FileCoverage>> {
+        let rows = sqlx::query_as::<_, CoverageRow>(
+            r#"
+            SELECT file, source, lines_total, lines_covered, uncovered_lines
+            FROM coverage
+            WHERE (? IS NULL OR file = ?)
+            ORDER BY file
+            "#
+        )
+        .bind(file)
+        .bind(file)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|r| r.into_coverage()).collect())
+    }
+    
+    /// Store a knowledge base document
+    pub async fn save_document(&self, doc: &Document) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO documents (id, workspace, title, filename, format, content, chunk_count, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&doc.id)
+        .bind(&doc.workspace)
+        .bind(&doc.title)
+        .bind(&doc.filename)
+        .bind(serde_json::to_string(&doc.format)?)
+        .bind(&doc.content)
+        .bind(doc.chunk_count)
+        .bind(doc.created_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Get a document including its extracted text
+    pub async fn get_document(&self, id: &str) -> Result<Option<Document>> {
+        let row = sqlx::query_as::<_, DocumentRow>(
+            "SELECT id, workspace, title, filename, format, content, chunk_count, created_at FROM documents WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(row.map(|r| r.into_document()))
+    }
+    
+    /// List a workspace's documents (without their text)
+    pub async fn list_documents(&self, workspace: &str) -> Result<Vec<Document>> {
+        let rows = sqlx::query_as::<_, DocumentRow>(
+            r#"
+            SELECT id, workspace, title, filename, format, '' AS content, chunk_count, created_at
+            FROM documents
+            WHERE workspace = ?
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(workspace)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|r| r.into_document()).collect())
+    }
+    
+    /// Delete a document; returns whether it existed
+    pub async fn delete_document(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM documents WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for Database {
+    fn name(&self) -> &str {
+        "database"
+    }
+    
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(None)
+    }
+}
+
+#[async_trait::async_trait]
+impl ResponseStore for Database {
+    async fn get_response(&self, key: &str) -> Result<Option<ChatResponse>> {
+        self.cached_response(key).await
+    }
+    
+    async fn put_response(&self, key: &str, response: &ChatResponse) -> Result<()> {
+        self.cache_response(key, response).await
+    }
+}
+
+// Internal row type for SQLx
+#[derive(sqlx::FromRow)]
+struct MissionRow {
+    id: MissionId,
+    goal: String,
+    status: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    context: String,
+    priority: String,
+    budget: String,
+}
+
+impl MissionRow {
+    fn into_mission(self) -> Mission {
+        Mission {
+            id: self.id,
+            goal: self.goal,
+            status: serde_json::from_str(&self.status).unwrap_or(MissionStatus::Pending),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            context: serde_json::from_str(&self.context).unwrap_or_default(),
+            tags: Vec::new(),
+            priority: serde_json::from_str(&self.priority).unwrap_or_default(),
+            budget: serde_json::from_str(&self.budget).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PostMortemRow {
+    mission_id: MissionId,
+    category: String,
+    summary: String,
+    remediation: String,
+    model: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PostMortemRow {
+    fn into_post_mortem(self) -> PostMortem {
+        PostMortem {
+            mission_id: self.mission_id,
+            category: serde_json::from_str(&self.category).unwrap_or(FailureCategory::Unknown),
+            summary: self.summary,
+            remediation: self.remediation,
+            model: self.model,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Where a request carrying an idempotency key stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdempotentRequest {
+    /// The key is new and now held by this request, which should run
+    New,
+    /// The request that claimed the key hasn't finished
+    InProgress,
+    /// The key was claimed by a different request
+    Mismatch,
+    /// The request already ran; this is its response
+    Completed { status: u16, content_type: Option<String>, body: Vec<u8> },
+}
+
+/// The message array sent to the LLM at one orchestrator step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepContext {
+    pub step: usize,
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct StepContextRow {
+    step: i64,
+    model: String,
+    messages: Vec<u8>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl StepContextRow {
+    fn into_step_context(self) -> Result<StepContext> {
+        let mut json = Vec::new();
+        GzDecoder::new(self.messages.as_slice())
+            .read_to_end(&mut json)
+            .map_err(|e| SpawnError::Internal(format!("Corrupt step context: {}", e)))?;
+        Ok(StepContext {
+            step: self.step as usize,
+            model: self.model,
+            messages: from_envelope(serde_json::from_slice(&json)?)?,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// A mission assigned to an experiment variant
+#[derive(Debug, Clone)]
+pub struct ExperimentOutcome {
+    pub variant: String,
+    pub status: MissionStatus,
+    /// Creation to last status change
+    pub duration: chrono::Duration,
+    /// LLM calls made
+    pub steps: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ExperimentRow {
+    id: String,
+    name: String,
+    variants: String,
+    active: bool,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ExperimentRow {
+    fn into_experiment(self) -> Experiment {
+        Experiment {
+            id: self.id,
+            name: self.name,
+            variants: serde_json::from_str(&self.variants).unwrap_or_default(),
+            active: self.active,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SavedFilterRow {
+    id: String,
+    name: String,
+    filter: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SavedFilterRow {
+    fn into_saved_filter(self) -> SavedFilter {
+        SavedFilter {
+            id: self.id,
+            name: self.name,
+            filter: serde_json::from_str(&self.filter).unwrap_or_default(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DiagnosticRow {
+    source: String,
+    file: String,
+    line: u32,
+    col: Option<u32>,
+    end_line: Option<u32>,
+    end_col: Option<u32>,
+    severity: String,
+    code: Option<String>,
+    message: String,
+}
+
+impl DiagnosticRow {
+    fn into_diagnostic(self) -> Diagnostic {
+        Diagnostic {
+            file: self.file,
+            line: self.line,
+            column: self.col,
+            end_line: self.end_line,
+            end_column: self.end_col,
+            severity: serde_json::from_str(&self.severity).unwrap_or(Severity::Warning),
+            code: self.code,
+            message: self.message,
+            source: self.source,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CoverageRow {
+    file: String,
+    source: String,
+    lines_total: u32,
+    lines_covered: u32,
+    uncovered_lines: String,
+}
+
+impl CoverageRow {
+    fn into_coverage(self) -> FileCoverage {
+        FileCoverage {
+            file: self.file,
+            lines_total: self.lines_total,
+            lines_covered: self.lines_covered,
+            uncovered_lines: serde_json::from_str(&self.uncovered_lines).unwrap_or_default(),
+            source: self.source,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DocumentRow {
+    id: String,
+    workspace: String,
+    title: String,
+    filename: String,
+    format: String,
+    content: String,
+    chunk_count: u32,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl DocumentRow {
+    fn into_document(self) -> Document {
+        Document {
+            id: self.id,
+            workspace: self.workspace,
+            title: self.title,
+            filename: self.filename,
+            format: serde_json::from_str(&self.format).unwrap_or(DocFormat::Markdown),
+            content: self.content,
+            chunk_count: self.chunk_count,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TaskRow {
+    id: TaskId,
+    mission_id: MissionId,
+    title: String,
+    status: String,
+    depends_on: String,
+    assigned_agent: Option<AgentId>,
+    result: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl TaskRow {
+    fn into_task(self) -> Task {
+        Task {
+            id: self.id,
+            mission_id: self.mission_id,
+            title: self.title,
+            status: serde_json::from_str(&self.status).unwrap_or(TaskStatus::Pending),
+            depends_on: serde_json::from_str(&self.depends_on).unwrap_or_default(),
+            assigned_agent: self.assigned_agent,
+            result: self.result,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_mission_status_rejects_unknown_state() {
+        let path = std::env::temp_dir().join(format!("spawn-status-{}.db", uuid::Uuid::new_v4()));
+        let db = Database::connect(&format!("sqlite:{}?mode=rwc", path.display())).await.unwrap();
+
+        let missing = MissionId::new();
+        assert!(matches!(db.update_mission_status(&missing, MissionStatus::Running).await, Err(SpawnError::NotFound(_))));
+
+        let mission = Mission::new("ship it");
+        db.create_mission(&mission).await.unwrap();
+        db.update_mission_status(&mission.id, MissionStatus::Running).await.unwrap();
+        assert!(matches!(
+            db.update_mission_status(&mission.id, MissionStatus::Queued).await,
+            Err(SpawnError::InvalidTransition { from: MissionStatus::Running, to: MissionStatus::Queued })
+        ));
+
+        sqlx::query("UPDATE missions SET status = 'bogus' WHERE id = ?").bind(&mission.id).execute(&db.pool).await.unwrap();
+        assert!(matches!(db.update_mission_status(&mission.id, MissionStatus::Failed).await, Err(SpawnError::Internal(_))));
+
+        drop(db);
+        let _ = std::fs::remove_file(&path);
+    }
 }
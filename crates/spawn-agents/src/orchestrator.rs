@@ -1,28 +1,145 @@
 //! The Orchestrator - the brain that runs the think → act → reflect loop
 
+use crate::agents::StaticAgentRegistry;
+use crate::assembler::{tagged, PromptAssembler, Section};
+use crate::citations::{self, Source};
+use crate::duplicates;
+use crate::events::BroadcastEventBus;
+use crate::experiments;
+use crate::i18n::{self, DEFAULT_LOCALE};
+use crate::locks::{git_resource, release_all, WorkspaceLocks};
 use crate::memory::Database;
-use crate::tools::ToolRegistry;
-use spawn_core::{ChatMessage, LlmClient, Mission, MissionStatus, Result, SpawnError};
-use std::sync::Arc;
+use crate::postmortem::{self, DEFAULT_POSTMORTEM_MODEL};
+use crate::estimate::{self, CostEstimate};
+use crate::preflight::{self, Preflight, PreflightContext};
+use crate::rerank::{self, Reranker, DEFAULT_RERANK_CANDIDATES};
+use crate::reservations::{self, FileReservations};
+use crate::scheduler::MissionScheduler;
+use crate::scratch::{self, ScratchDirs};
+use crate::snapshots::WorkspaceSnapshots;
+use crate::supervisor::{Supervisor, WRAP_UP_PROMPT};
+use crate::terminals::MissionTerminals;
+use crate::tools::{FileStamps, ToolRegistry};
+use crate::vector_memory::VectorMemory;
+use futures::StreamExt;
+use serde::Serialize;
+use spawn_ai::{ContextManager, CostTracker, LlmSummarizer, MeteredClient, ModelRouter, PromptTemplates, MISSION_PROMPT};
+use spawn_core::{
+    Agent, AgentRegistry, Budget, BudgetUsage, CancellationToken, CapabilitySet, ChatMessage, ChatOptions, Citation, ContextConfig,
+    Conversation, EventBus, ExperimentVariant, LlmClient, LogEntry, LogStore, Mission, MissionEvent, MissionId, MissionStatus,
+    MissionHook, ProgressSender, Result, SchedulerConfig, Service, ServiceAvailability, SpawnError, TaskKind, TokenUsage, ToolPermission, ToolProgress,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tracing::{info, warn, error};
 
-const MAX_STEPS: usize = 10;
+/// Step ceiling for missions whose budget doesn't set one
+const DEFAULT_MAX_STEPS: usize = 10;
 const DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4-20250514";
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+/// Knowledge base chunks added to a mission's initial context
+const DOC_CONTEXT_CHUNKS: i32 = 4;
+/// Chunks less similar to the goal than this are left out
+const MIN_DOC_SIMILARITY: f32 = 0.3;
+/// Tool output longer than this is shortened in the concise log tier
+const CONCISE_LOG_CHARS: usize = 500;
+
+/// Output the model has produced so far in a mission's current step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepProgress {
+    pub step: usize,
+    pub output: String,
+}
 
 pub struct Orchestrator {
     db: Arc<Database>,
+    /// Where mission logs go; the database unless configured otherwise
+    logs: Arc<dyn LogStore>,
     llm: Arc<dyn LlmClient>,
     tools: ToolRegistry,
     model: String,
+    /// Generation parameters for mission steps
+    chat_options: ChatOptions,
+    /// Fits each step's prompt into the context window
+    assembler: PromptAssembler,
+    postmortem_model: String,
+    /// Per-task model rules, ahead of the models above
+    router: Arc<ModelRouter>,
+    /// Language of prompts for missions that don't set `context.locale`
+    locale: String,
+    /// The mission system prompt template and the rules it lists
+    prompts: Arc<PromptTemplates>,
+    /// Prices every completion; a mission's total counts against its budget
+    costs: Arc<CostTracker>,
+    snapshots: Option<Arc<WorkspaceSnapshots>>,
+    /// Workspace of missions whose context names none
+    workspace: Option<PathBuf>,
+    /// Keeps missions' file writes and git operations from colliding
+    locks: Option<Arc<WorkspaceLocks>>,
+    /// Keeps missions off files with unsaved editor changes, and reserves the
+    /// files they change until they end
+    reservations: Option<Arc<FileReservations>>,
+    /// Closes the terminals a mission opened once it ends
+    terminals: Option<Arc<MissionTerminals>>,
+    /// Per-mission `$MISSION_TMP` directories
+    scratch: Option<Arc<ScratchDirs>>,
+    /// Vector store and workspace key, for knowledge base retrieval and
+    /// duplicate detection
+    vector_memory: Option<(Arc<VectorMemory>, String)>,
+    /// Rescores knowledge base candidates before they enter the context
+    reranker: Option<Arc<dyn Reranker>>,
+    /// Personas a mission can run as (`context.agent`)
+    agents: Arc<dyn AgentRegistry>,
+    /// Capabilities no mission's tools may use, on top of each mission's
+    /// own `context.deny`
+    denied: CapabilitySet,
+    /// Optional services currently up; tools needing one that is down are
+    /// withheld from missions
+    services: Arc<ServiceAvailability>,
+    /// Where mission lifecycle events are published
+    events: Arc<dyn EventBus>,
+    /// Custom code run at mission start, each step, each tool call and the end
+    hooks: Vec<Arc<dyn MissionHook>>,
+    /// Hands out mission slots by priority
+    scheduler: MissionScheduler,
+    /// In-flight step output of running missions
+    progress: Mutex<HashMap<MissionId, StepProgress>>,
+    /// Cancellation tokens of running missions
+    running: Mutex<HashMap<MissionId, CancellationToken>>,
 }
 
 impl Orchestrator {
     pub fn new(db: Arc<Database>, llm: Arc<dyn LlmClient>) -> Self {
         Self {
+            logs: db.clone(),
             db,
             llm,
             tools: ToolRegistry::new(),
             model: DEFAULT_MODEL.to_string(),
+            chat_options: ChatOptions::new().with_temperature(DEFAULT_TEMPERATURE),
+            assembler: PromptAssembler::new(ContextConfig::default()),
+            postmortem_model: DEFAULT_POSTMORTEM_MODEL.to_string(),
+            router: Arc::new(ModelRouter::default()),
+            locale: DEFAULT_LOCALE.to_string(),
+            prompts: Arc::new(PromptTemplates::new()),
+            costs: Arc::new(CostTracker::default()),
+            snapshots: None,
+            workspace: None,
+            locks: None,
+            reservations: None,
+            terminals: None,
+            scratch: None,
+            vector_memory: None,
+            reranker: None,
+            agents: Arc::new(StaticAgentRegistry::builtin()),
+            denied: CapabilitySet::new(),
+            services: Arc::new(ServiceAvailability::new()),
+            events: Arc::new(BroadcastEventBus::new()),
+            hooks: Vec::new(),
+            scheduler: MissionScheduler::new(SchedulerConfig::default()),
+            progress: Mutex::new(HashMap::new()),
+            running: Mutex::new(HashMap::new()),
         }
     }
     
@@ -31,114 +148,960 @@ impl Orchestrator {
         self
     }
     
+    /// Model missions run on unless their context names another
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+    
+    /// Generation parameters for mission steps (temperature 0.7 by default)
+    pub fn with_chat_options(mut self, options: ChatOptions) -> Self {
+        self.chat_options = options;
+        self
+    }
+    
+    /// Fail a step's model call, so it can be retried or fall back, when it
+    /// takes longer than `timeout`; never cut short by default
+    pub fn with_request_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.chat_options.timeout_secs = Some(timeout.as_secs().max(1));
+        self
+    }
+    
+    /// Context window size and how it is shared between prompt sections
+    pub fn with_context_config(mut self, config: ContextConfig) -> Self {
+        self.assembler = PromptAssembler::new(config);
+        self
+    }
+    
+    /// Cheap model used to analyze failed missions
+    pub fn with_postmortem_model(mut self, model: impl Into<String>) -> Self {
+        self.postmortem_model = model.into();
+        self
+    }
+    
+    pub fn postmortem_model(&self) -> &str {
+        &self.postmortem_model
+    }
+    
+    /// Pick models per task by rule instead of always the defaults
+    pub fn with_router(mut self, router: Arc<ModelRouter>) -> Self {
+        self.router = router;
+        self
+    }
+    
+    /// Run `hook` in every mission, after the hooks added before it
+    pub fn with_hook(mut self, hook: Arc<dyn MissionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+    
+    /// Prompt missions in `locale` unless they ask for another language
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = locale.into();
+        self
+    }
+    
+    /// The model for a `task` call of `mission`: its own choice for the task,
+    /// then `chosen` (an agent's or experiment's), then the first routing
+    /// rule matching its spend so far, then `default`
+    fn route_model(&self, mission: &Mission, task: TaskKind, spent_usd: f64, chosen: Option<&str>, default: &str) -> String {
+        mission.context.models.get(&task).map(String::as_str)
+            .or(chosen)
+            .or_else(|| self.router.route(task, spent_usd))
+            .unwrap_or(default)
+            .to_string()
+    }
+    
+    /// Record spend in a shared tracker (built-in prices by default)
+    pub fn with_costs(mut self, costs: Arc<CostTracker>) -> Self {
+        self.costs = costs;
+        self
+    }
+    
+    pub fn costs(&self) -> &Arc<CostTracker> {
+        &self.costs
+    }
+    
     pub fn with_tools(mut self, tools: ToolRegistry) -> Self {
         self.tools = tools;
         self
     }
     
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+    
+    /// Snapshot the workspace before each mission's first tool call
+    pub fn with_snapshots(mut self, snapshots: Arc<WorkspaceSnapshots>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+    
+    /// Workspace of missions whose context names none, where the files their
+    /// tools change are looked for
+    pub fn with_workspace(mut self, root: impl Into<PathBuf>) -> Self {
+        self.workspace = Some(root.into());
+        self
+    }
+    
+    /// Lock the files and repository a tool call touches, under the mission's id
+    pub fn with_locks(mut self, locks: Arc<WorkspaceLocks>) -> Self {
+        self.locks = Some(locks);
+        self
+    }
+    
+    /// Draw on a workspace's knowledge base documents when planning, and
+    /// index completed missions for duplicate detection
+    pub fn with_vector_memory(mut self, memory: Arc<VectorMemory>, workspace: impl Into<String>) -> Self {
+        self.vector_memory = Some((memory, workspace.into()));
+        self
+    }
+    
+    /// Rerank a wider pool of knowledge base chunks when building context
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+    
+    /// Replace the built-in agent personas
+    pub fn with_agents(mut self, agents: Arc<dyn AgentRegistry>) -> Self {
+        self.agents = agents;
+        self
+    }
+    
+    /// Render mission system prompts from `prompts`' `mission` template;
+    /// shared, so templates and rules replaced there apply to the next mission
+    pub fn with_prompt_templates(mut self, prompts: Arc<PromptTemplates>) -> Self {
+        self.prompts = prompts;
+        self
+    }
+    
+    pub fn prompt_templates(&self) -> &Arc<PromptTemplates> {
+        &self.prompts
+    }
+    
+    pub fn agents(&self) -> &dyn AgentRegistry {
+        self.agents.as_ref()
+    }
+    
+    /// Respect and take file reservations
+    pub fn with_reservations(mut self, reservations: Arc<FileReservations>) -> Self {
+        self.reservations = Some(reservations);
+        self
+    }
+    
+    /// Close a mission's terminals when it ends, keeping their output in its log
+    pub fn with_terminals(mut self, terminals: Arc<MissionTerminals>) -> Self {
+        self.terminals = Some(terminals);
+        self
+    }
+    
+    /// Give each mission a scratch directory, `$MISSION_TMP` to its tools
+    pub fn with_scratch_dirs(mut self, scratch: Arc<ScratchDirs>) -> Self {
+        self.scratch = Some(scratch);
+        self
+    }
+    
+    /// Refuse tools needing any of these capabilities in every mission
+    pub fn with_denied_capabilities(mut self, denied: CapabilitySet) -> Self {
+        self.denied = denied;
+        self
+    }
+    
+    pub fn denied_capabilities(&self) -> &CapabilitySet {
+        &self.denied
+    }
+    
+    /// Withhold tools while services they require are down
+    pub fn with_services(mut self, services: Arc<ServiceAvailability>) -> Self {
+        self.services = services;
+        self
+    }
+    
+    /// Tools withheld right now, with the service each is missing
+    pub fn disabled_tools(&self) -> Vec<(String, Service)> {
+        self.tools.list().into_iter()
+            .filter_map(|t| self.services.missing(&t.requires).map(|s| (t.name, s)))
+            .collect()
+    }
+    
+    /// Publish mission events somewhere other than the in-process bus
+    pub fn with_event_bus(mut self, events: Arc<dyn EventBus>) -> Self {
+        self.events = events;
+        self
+    }
+    
+    pub fn events(&self) -> Arc<dyn EventBus> {
+        self.events.clone()
+    }
+    
+    /// Concurrency cap and preemption for missions (4 at once, no preemption by default)
+    pub fn with_scheduler(mut self, config: SchedulerConfig) -> Self {
+        self.scheduler = MissionScheduler::new(config);
+        self
+    }
+    
+    pub fn scheduler(&self) -> &MissionScheduler {
+        &self.scheduler
+    }
+    
+    /// Keep mission logs somewhere other than the database
+    pub fn with_log_store(mut self, logs: Arc<dyn LogStore>) -> Self {
+        self.logs = logs;
+        self
+    }
+    
+    pub fn log_store(&self) -> Arc<dyn LogStore> {
+        self.logs.clone()
+    }
+    
+    /// What would stop `mission` or make it misbehave, without running it;
+    /// `workspace` is where it runs unless its context names another
+    pub async fn preflight(&self, mission: &Mission, workspace: &std::path::Path) -> Preflight {
+        let with = PreflightContext {
+            tools: &self.tools,
+            agents: self.agents.as_ref(),
+            denied: &self.denied,
+            context: self.assembler.config(),
+            default_max_steps: DEFAULT_MAX_STEPS,
+        };
+        let mut report = preflight::preflight(mission, &with, workspace).await;
+        match self.estimate_cost(mission).await {
+            Ok(projected) => {
+                let over_budget = projected.cost_usd.zip(mission.budget.max_cost_usd)
+                    .filter(|(cost, max)| cost.high > *max);
+                if let Some((cost, max)) = over_budget {
+                    report.warnings.push(format!(
+                        "This mission may cost up to ${:.2} on {}; the budget of ${:.2} may stop it early",
+                        cost.high, projected.model, max
+                    ));
+                }
+                report.estimate.projected = Some(projected);
+            }
+            Err(e) => warn!(error = %e, "Failed to estimate mission cost"),
+        }
+        report
+    }
+    
+    /// Likely steps, tokens and cost of `mission` on the model its steps
+    /// would use, from completed missions
+    pub async fn estimate_cost(&self, mission: &Mission) -> Result<CostEstimate> {
+        let model = self.route_model(mission, TaskKind::Execution, 0.0, None, &self.model);
+        let history = self.db.completed_mission_usage(estimate::HISTORY_MISSIONS).await?;
+        let price = self.costs.pricing().price(&model);
+        let max_steps = mission.budget.max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+        Ok(estimate::estimate(&mission.goal, &model, price, max_steps, &history))
+    }
+    
     /// Run a mission through the agent loop
     pub async fn run_mission(&self, mission: Mission) -> Result<()> {
         info!(mission_id = %mission.id, goal = %mission.goal, "Starting mission");
         
         // Save mission to DB
         self.db.create_mission(&mission).await?;
+        self.events.publish(MissionEvent::Created { mission_id: mission.id.clone(), goal: mission.goal.clone() });
+        
+        let cancel = CancellationToken::new();
+        self.running.lock().unwrap().insert(mission.id.clone(), cancel.clone());
+        let result = self.run_scheduled(&mission, &cancel).await;
+        self.scheduler.release(&mission.id);
+        self.record_result(&mission.id).await;
+        self.progress.lock().unwrap().remove(&mission.id);
+        self.running.lock().unwrap().remove(&mission.id);
+        
+        let outcome = self.settle(&mission, result).await;
+        let status = match &outcome {
+            Ok(()) => MissionStatus::Completed,
+            Err(SpawnError::Cancelled) => MissionStatus::Cancelled,
+            Err(_) => MissionStatus::Failed,
+        };
+        for hook in &self.hooks {
+            hook.on_complete(&mission, &status, outcome.as_ref().err()).await;
+        }
+        if let Some(reservations) = &self.reservations {
+            if let Err(e) = reservations.release_all(&reservations::mission_owner(&mission.id)).await {
+                warn!(mission_id = %mission.id, error = %e, "Failed to release file reservations");
+            }
+        }
+        if let Some(terminals) = &self.terminals {
+            self.archive_terminals(&mission.id, terminals).await;
+        }
+        if let Err(e) = self.logs.finish(&mission.id).await {
+            warn!(mission_id = %mission.id, error = %e, "Failed to finish mission log");
+        }
+        outcome
+    }
+    
+    /// Record how the run ended
+    async fn settle(&self, mission: &Mission, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(SpawnError::Cancelled) => {
+                info!(mission_id = %mission.id, "Mission cancelled");
+                self.db.update_mission_status(&mission.id, MissionStatus::Cancelled).await?;
+                self.events.publish(MissionEvent::Cancelled { mission_id: mission.id.clone() });
+                Err(SpawnError::Cancelled)
+            }
+            Err(e) => {
+                self.db.update_mission_status(&mission.id, MissionStatus::Failed).await?;
+                self.events.publish(MissionEvent::Failed { mission_id: mission.id.clone(), error: e.to_string() });
+                self.post_mortem(mission, &e).await;
+                Err(e)
+            }
+        }
+    }
+    
+    /// Wait for a slot, then run
+    async fn run_scheduled(&self, mission: &Mission, cancel: &CancellationToken) -> Result<()> {
+        let ticket = self.scheduler.enqueue(&mission.id, mission.priority);
+        if let Some(position) = ticket.position() {
+            info!(mission_id = %mission.id, priority = mission.priority.as_str(), position, "Mission queued");
+            self.db.update_mission_status(&mission.id, MissionStatus::Queued).await?;
+            self.events.publish(MissionEvent::Queued { mission_id: mission.id.clone(), priority: mission.priority, position });
+        }
+        ticket.wait(cancel).await?;
+        
+        self.db.update_mission_status(&mission.id, MissionStatus::Running).await?;
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_start(mission).await {
+                warn!(mission_id = %mission.id, hook = hook.name(), error = %e, "Mission hook refused to start the mission");
+                return Err(e);
+            }
+        }
+        let supervisor = Supervisor::start(&mission.budget, cancel);
+        match self.run_loop(mission, cancel, &supervisor).await {
+            Err(SpawnError::Cancelled) if supervisor.timed_out() => {
+                let secs = mission.budget.hard_timeout_secs.unwrap_or_default();
+                warn!(mission_id = %mission.id, secs, "Mission hit its hard timeout");
+                Err(SpawnError::BudgetExceeded(format!("Hard timeout reached ({}s)", secs)))
+            }
+            result => result,
+        }
+    }
+    
+    /// At a step boundary, hand the slot over if a higher-priority mission
+    /// asked for it, and wait to get one back
+    async fn yield_if_preempted(&self, mission: &Mission, cancel: &CancellationToken) -> Result<()> {
+        let Some((by, ticket)) = self.scheduler.preempt(&mission.id) else {
+            return Ok(());
+        };
+        info!(mission_id = %mission.id, by = %by, "Mission preempted");
+        self.db.update_mission_status(&mission.id, MissionStatus::Paused).await?;
+        self.log(&mission.id, "scheduler", &format!("Paused to make room for mission {}", by)).await?;
+        self.events.publish(MissionEvent::Preempted { mission_id: mission.id.clone(), by });
+        
+        ticket.wait(cancel).await?;
+        
         self.db.update_mission_status(&mission.id, MissionStatus::Running).await?;
+        self.log(&mission.id, "scheduler", "Resumed").await?;
+        self.events.publish(MissionEvent::Resumed { mission_id: mission.id.clone() });
+        Ok(())
+    }
+    
+    async fn run_loop(&self, mission: &Mission, cancel: &CancellationToken, supervisor: &Supervisor) -> Result<()> {
+        let agent = match mission.context.agent.as_deref() {
+            Some(name) => Some(self.agents.get(name).ok_or_else(|| {
+                SpawnError::OrchestrationError(format!("Unknown agent '{}'", name))
+            })?),
+            None => None,
+        };
+        let variant = self.experiment_variant(&mission.id).await;
+        let chosen_model = variant.as_ref().and_then(|v| v.model.as_deref())
+            .or(agent.as_ref().and_then(|a| a.model.as_deref()));
+        let mut options = self.chat_options.clone();
+        if let Some(temperature) = variant.as_ref().and_then(|v| v.temperature) {
+            options = options.with_temperature(temperature);
+        }
         
         // Build initial context
-        let system_prompt = self.build_system_prompt();
-        let mut messages = vec![
-            ChatMessage::system(system_prompt),
-            ChatMessage::user(format!("Goal: {}", mission.goal)),
-        ];
+        let template = variant.as_ref().and_then(|v| v.prompt_template.as_deref());
+        let denied = self.denied.union(&mission.context.deny);
+        let locale = mission.context.locale.as_deref().unwrap_or(&self.locale);
+        let system_prompt = self.build_system_prompt(mission, agent.as_ref(), &denied, template, locale);
+        let mut conversation = Conversation::new(mission.id.as_str())
+            .with_model(self.route_model(mission, TaskKind::Planning, 0.0, chosen_model, &self.model))
+            .with_message(tagged(ChatMessage::system(system_prompt), Section::System))
+            .with_message(tagged(ChatMessage::user(i18n::goal_message(locale, &mission.goal)), Section::System));
+        if let Some(scratch) = &self.scratch {
+            let dir = scratch.create(&mission.id).await?;
+            conversation.append(tagged(ChatMessage::system(format!(
+                "Put intermediate files in your scratch directory, {} (${} in commands), not the workspace.",
+                dir.display(), scratch::ENV_VAR,
+            )), Section::System));
+        }
+        let sources = self.doc_context(&mission.goal).await;
+        if !sources.is_empty() {
+            conversation.insert_pinned(tagged(ChatMessage::system(citations::render(&sources)), Section::Retrieved));
+        }
+        if !mission.context.steps.is_empty() {
+            let plan: Vec<String> = mission.context.steps.iter().enumerate()
+                .map(|(i, step)| format!("{}. {}", i + 1, step))
+                .collect();
+            conversation.append(tagged(ChatMessage::user(format!("Plan:\n{}", plan.join("\n"))), Section::Plan));
+        }
+        
+        let budget = Budget {
+            max_steps: mission.budget.max_steps.or(Some(DEFAULT_MAX_STEPS)),
+            ..mission.budget
+        };
+        let mut usage = BudgetUsage::default();
+        let mut wrapping_up = false;
         
         // The Loop: Think → Act → Reflect
-        for step in 0..MAX_STEPS {
-            info!(mission_id = %mission.id, step = step, "Executing step");
+        for step in 0.. {
+            usage.steps = step;
+            if let Err(e) = budget.check(&usage, chrono::Utc::now()) {
+                warn!(mission_id = %mission.id, error = %e, "Mission hit its budget");
+                return Err(e);
+            }
+            if step > 0 {
+                self.yield_if_preempted(mission, cancel).await?;
+            }
+            if !wrapping_up && supervisor.wrap_up_requested() {
+                wrapping_up = true;
+                info!(mission_id = %mission.id, "Soft timeout reached; asking the mission to wrap up");
+                self.log(&mission.id, "supervisor", "Soft timeout reached; asked to wrap up").await?;
+                conversation.append(ChatMessage::user(WRAP_UP_PROMPT).with_metadata("step", step));
+            }
+            let task = if step == 0 { TaskKind::Planning } else { TaskKind::Execution };
+            let model = &self.route_model(mission, task, usage.cost_usd, chosen_model, &self.model);
+            info!(mission_id = %mission.id, step = step, model = %model, "Executing step");
+            self.events.publish(MissionEvent::StepStarted { mission_id: mission.id.clone(), step });
+            for hook in &self.hooks {
+                if let Err(e) = hook.on_step(mission, step).await {
+                    warn!(mission_id = %mission.id, hook = hook.name(), step, error = %e, "Mission hook stopped the mission");
+                    return Err(e);
+                }
+            }
+            
+            // Keep the conversation itself within the budget, not just each prompt
+            let pinned = conversation.messages.iter().take_while(|m| !Section::of(m).keeps_newest()).count();
+            let compaction = self.context_manager(mission, usage.cost_usd)
+                .compact(&mut conversation.messages, pinned, self.assembler.config().prompt_tokens(), model)
+                .await;
+            if !compaction.is_empty() {
+                info!(mission_id = %mission.id, summarized = compaction.summarized, dropped = compaction.dropped, "Compacted mission context");
+                self.log(&mission.id, "context", &compaction.to_string()).await?;
+            }
+            
+            let (prompt, breakdown) = self.assembler.assemble(&conversation.messages, model);
+            self.log(&mission.id, "context", &breakdown.to_string()).await?;
+            // Pinned sections alone can outgrow the window; better to stop
+            // here than pay for a call the provider will refuse
+            let limit = self.assembler.config().window_tokens
+                .saturating_sub(options.max_tokens.map_or(0, |t| t as usize));
+            let tokens = spawn_ai::estimate_tokens(&prompt, model);
+            if tokens > limit {
+                warn!(mission_id = %mission.id, tokens, limit, "Prompt exceeds the context window");
+                return Err(SpawnError::ContextOverflow { tokens, limit });
+            }
+            
+            // Record exactly what the model sees, for time-travel debugging
+            if let Err(e) = self.db.save_step_context(&mission.id, step, model, &prompt).await {
+                warn!(error = %e, "Failed to save step context");
+            }
             
             // 1. Think - ask LLM what to do
-            let response = match self.llm.chat(&self.model, &messages).await {
-                Ok(r) => r,
+            let response = match self.think(&mission.id, step, model, &prompt, &options, cancel).await {
+                Ok((r, tokens)) => {
+                    let cost = self.costs.record(mission.id.as_str(), model, &tokens);
+                    usage.tokens += u64::from(tokens.total_tokens);
+                    usage.cost_usd += cost;
+                    if let Err(e) = self.db.add_mission_usage(&mission.id, &tokens, cost).await {
+                        warn!(error = %e, "Failed to record mission usage");
+                    }
+                    r
+                }
                 Err(e) => {
                     error!(error = %e, "LLM call failed");
-                    self.db.update_mission_status(&mission.id, MissionStatus::Failed).await?;
                     return Err(e);
                 }
             };
             
-            // Log the response
-            self.db.log_step(&mission.id, "assistant", &response).await?;
-            messages.push(ChatMessage::assistant(&response));
+            // Log the action taken, keeping the full response in the verbose tier
+            self.log_tiered(&mission.id, "assistant", &concise_response(&response), &response).await?;
+            conversation.append(ChatMessage::assistant(&response).with_metadata("model", model).with_metadata("step", step));
             
             // 2. Check for completion
             if self.is_complete(&response) {
                 info!(mission_id = %mission.id, "Mission completed");
                 self.db.update_mission_status(&mission.id, MissionStatus::Completed).await?;
+                let summary = response.split_once("DONE:").map(|(_, s)| s.trim()).unwrap_or_default();
+                let citations = self.record_citations(&mission.id, &sources, &response).await;
+                self.events.publish(MissionEvent::Completed {
+                    mission_id: mission.id.clone(),
+                    summary: summary.to_string(),
+                    citations,
+                });
+                if let Some((memory, _)) = &self.vector_memory {
+                    if let Err(e) = duplicates::remember(memory, mission).await {
+                        warn!(error = %e, "Failed to index completed mission");
+                    }
+                }
                 return Ok(());
             }
             
             // 3. Act - parse and execute any tool calls
-            if let Some(tool_result) = self.execute_tools(&response).await? {
-                self.db.log_step(&mission.id, "tool", &tool_result).await?;
-                messages.push(ChatMessage::user(format!("Tool result: {}", tool_result)));
+            if response.contains("TOOL:") {
+                self.ensure_snapshot(&mission.id).await;
+            }
+            if let Some(tool_result) = self.execute_tools(mission, &response, agent.as_ref(), &denied, cancel).await? {
+                self.log_tiered(&mission.id, "tool", &shorten(&tool_result, CONCISE_LOG_CHARS), &tool_result).await?;
+                conversation.append(tagged(ChatMessage::user(format!("Tool result: {}", tool_result)), Section::ToolResults).with_metadata("step", step));
             }
         }
-        
-        // Hit max steps
-        warn!(mission_id = %mission.id, "Mission hit max steps");
-        self.db.update_mission_status(&mission.id, MissionStatus::Failed).await?;
-        Err(SpawnError::OrchestrationError("Max steps exceeded".into()))
+        unreachable!("the step budget always ends the loop")
+    }
+    
+    /// Abort a running mission's in-flight LLM call or tool execution, or take
+    /// a queued one out of the queue; it ends as `Cancelled`. Returns false if
+    /// the mission isn't running or queued.
+    pub fn cancel(&self, mission_id: &MissionId) -> bool {
+        match self.running.lock().unwrap().get(mission_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Partial model output for a running mission's current step
+    pub fn progress(&self, mission_id: &MissionId) -> Option<StepProgress> {
+        self.progress.lock().unwrap().get(mission_id).cloned()
     }
     
-    fn build_system_prompt(&self) -> String {
-        let tool_descriptions = self.tools.describe();
+    /// Stream one completion, publishing the partial output as it arrives
+    async fn think(
+        &self,
+        mission_id: &MissionId,
+        step: usize,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<(String, TokenUsage)> {
+        let mut stream = self.llm.chat_stream(model, messages, options, cancel).await?;
+        let mut output = String::new();
+        let mut usage = TokenUsage::default();
+        self.progress.lock().unwrap()
+            .insert(mission_id.clone(), StepProgress { step, output: String::new() });
         
-        format!(r#"You are an autonomous AI agent. Your job is to accomplish the user's goal.
-
-Available tools:
-{tool_descriptions}
-
-To use a tool, respond with:
-TOOL: <tool_name>
-ARGS: <json_arguments>
-
-When the goal is complete, respond with:
-DONE: <summary of what was accomplished>
-
-Think step by step. Be concise."#)
+        while let Some(delta) = cancel.run_until_cancelled(stream.next()).await
+            .ok_or(SpawnError::Cancelled)?
+        {
+            let delta = delta?;
+            output.push_str(&delta.content);
+            if let Some(tokens) = delta.usage {
+                usage = tokens;
+            }
+            if let Some(progress) = self.progress.lock().unwrap().get_mut(mission_id) {
+                progress.output.clone_from(&output);
+            }
+        }
+        Ok((output, usage))
+    }
+    
+    /// Compacts a mission's conversation, summarizing on the summarization
+    /// route unless that's turned off
+    fn context_manager(&self, mission: &Mission, spent: f64) -> ContextManager {
+        let manager = ContextManager::new();
+        if !self.assembler.config().summarize {
+            return manager;
+        }
+        let llm = MeteredClient::new(self.llm.clone(), self.costs.clone(), mission.id.as_str());
+        let model = self.route_model(mission, TaskKind::Summarization, spent, None, &self.model);
+        manager.with_summarizer(Arc::new(LlmSummarizer::new(Arc::new(llm), model)))
+    }
+    
+    /// Classify a failure and store the analysis on the mission
+    async fn post_mortem(&self, mission: &Mission, error: &SpawnError) {
+        let logs = self.logs.list(&mission.id).await.unwrap_or_default();
+        let llm = MeteredClient::new(self.llm.clone(), self.costs.clone(), mission.id.as_str());
+        let spent = self.costs.scope(mission.id.as_str()).cost_usd;
+        let model = self.route_model(mission, TaskKind::Postmortem, spent, None, &self.postmortem_model);
+        let analysis = postmortem::analyze(&llm, &model, mission, &logs, error).await;
+        info!(mission_id = %mission.id, category = ?analysis.category, "Post-mortem recorded");
+        if let Err(e) = self.db.save_post_mortem(&analysis).await {
+            warn!(error = %e, "Failed to save post-mortem");
+        }
+    }
+    
+    /// Take the pre-mission snapshot once, before anything can write
+    async fn ensure_snapshot(&self, mission_id: &MissionId) {
+        let Some(snapshots) = &self.snapshots else {
+            return;
+        };
+        if snapshots.get(mission_id).await.is_some() {
+            return;
+        }
+        let guard = match &self.locks {
+            Some(locks) => match locks.acquire(&git_resource(""), mission_id, "snapshot").await {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    warn!(mission_id = %mission_id, error = %e, "Workspace snapshot skipped; rollback unavailable");
+                    return;
+                }
+            },
+            None => None,
+        };
+        let snapshot = snapshots.snapshot(mission_id).await;
+        if let Some(guard) = guard {
+            guard.release().await;
+        }
+        match snapshot {
+            Ok(commit) => {
+                if let Err(e) = self.log(mission_id, "snapshot", &commit).await {
+                    warn!(error = %e, "Failed to log snapshot");
+                }
+            }
+            Err(e) => warn!(mission_id = %mission_id, error = %e, "Workspace snapshot failed; rollback unavailable"),
+        }
+    }
+    
+    /// Commit the tree a mission that touched the workspace left behind, so
+    /// its run can be compared with others
+    async fn record_result(&self, mission_id: &MissionId) {
+        let Some(snapshots) = &self.snapshots else {
+            return;
+        };
+        if snapshots.get(mission_id).await.is_none() {
+            return;
+        }
+        let guard = match &self.locks {
+            Some(locks) => match locks.acquire(&git_resource(""), mission_id, "snapshot").await {
+                Ok(guard) => Some(guard),
+                Err(e) => {
+                    warn!(mission_id = %mission_id, error = %e, "Mission result not recorded");
+                    return;
+                }
+            },
+            None => None,
+        };
+        if let Err(e) = snapshots.record_result(mission_id).await {
+            warn!(mission_id = %mission_id, error = %e, "Mission result not recorded");
+        }
+        if let Some(guard) = guard {
+            guard.release().await;
+        }
+    }
+    
+    /// Project documentation relevant to the goal, if a knowledge base is configured
+    async fn doc_context(&self, goal: &str) -> Vec<Source> {
+        let Some((memory, workspace)) = self.vector_memory.as_ref().filter(|_| self.services.is_up(Service::VectorStore)) else {
+            return Vec::new();
+        };
+        let pool = if self.reranker.is_some() { DEFAULT_RERANK_CANDIDATES } else { DOC_CONTEXT_CHUNKS };
+        let results = match memory.search_docs(goal, workspace, pool).await {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "Knowledge base search failed");
+                return Vec::new();
+            }
+        };
+        let mut results: Vec<_> = results.into_iter()
+            .filter(|r| r.similarity >= MIN_DOC_SIMILARITY)
+            .collect();
+        if let Some(reranker) = &self.reranker {
+            match rerank::rerank(reranker.as_ref(), goal, results.clone(), DOC_CONTEXT_CHUNKS as usize).await {
+                Ok(reranked) => results = reranked,
+                Err(e) => {
+                    warn!(error = %e, "Reranking failed; using vector order");
+                    results.truncate(DOC_CONTEXT_CHUNKS as usize);
+                }
+            }
+        }
+        Source::from_results(results)
+    }
+    
+    /// Assign the mission a variant of the active experiment, if there is one
+    async fn experiment_variant(&self, mission_id: &MissionId) -> Option<ExperimentVariant> {
+        let experiment = match self.db.active_experiment().await {
+            Ok(experiment) => experiment?,
+            Err(e) => {
+                warn!(error = %e, "Failed to load active experiment");
+                return None;
+            }
+        };
+        let variant = experiments::assign(&experiment, mission_id)?.clone();
+        info!(mission_id = %mission_id, experiment = %experiment.name, variant = %variant.name, "Experiment variant assigned");
+        if let Err(e) = self.db.assign_experiment(mission_id, &experiment.id, &variant.name).await {
+            warn!(error = %e, "Failed to record experiment assignment");
+        }
+        Some(variant)
+    }
+    
+    /// Map the sources cited in the final answer back to their documents and store them
+    async fn record_citations(&self, mission_id: &MissionId, sources: &[Source], answer: &str) -> Vec<Citation> {
+        if sources.is_empty() {
+            return Vec::new();
+        }
+        let citations = match citations::resolve(&self.db, sources, answer).await {
+            Ok(citations) => citations,
+            Err(e) => {
+                warn!(error = %e, "Failed to resolve citations");
+                return Vec::new();
+            }
+        };
+        if let Err(e) = self.db.save_citations(mission_id, &citations).await {
+            warn!(error = %e, "Failed to save citations");
+        }
+        citations
+    }
+    
+    /// The default system prompt in `locale`, or `template` filled in
+    fn build_system_prompt(
+        &self,
+        mission: &Mission,
+        agent: Option<&Agent>,
+        denied: &CapabilitySet,
+        template: Option<&str>,
+        locale: &str,
+    ) -> String {
+        let tool_descriptions = self.tools.describe_allowed(|name| {
+            agent.is_none_or(|a| a.allows_tool(name))
+                && self.tools.capabilities(name).intersection(denied).is_empty()
+                && self.services.missing(&self.tools.requires(name)).is_none()
+        });
+        let persona = i18n::persona(locale, agent.map(|a| a.system_prompt.as_str()));
+        if let Some(template) = template {
+            return i18n::localize_prompt(locale, experiments::render_prompt(template, &persona, &tool_descriptions));
+        }
+        let vars = serde_json::json!({
+            "persona": persona,
+            "tools": tool_descriptions,
+            "workspace": mission.context.workspace,
+            "goal": mission.goal,
+            "locale": locale,
+            "text": i18n::prompt_text(locale),
+        });
+        match self.prompts.render(MISSION_PROMPT, &vars) {
+            Ok(prompt) => i18n::localize_prompt(locale, prompt),
+            Err(e) => {
+                warn!(mission_id = %mission.id, error = %e, "Failed to render the mission prompt; using the built-in one");
+                let prompt = PromptTemplates::new().render(MISSION_PROMPT, &vars).unwrap_or_default();
+                i18n::localize_prompt(locale, prompt)
+            }
+        }
+    }
+    
+    /// Append to the mission log and publish the line
+    async fn log(&self, mission_id: &MissionId, agent: &str, content: &str) -> Result<()> {
+        self.log_tiered(mission_id, agent, content, content).await
+    }
+    
+    /// Log `concise`, storing `verbose` alongside when it says more; events
+    /// carry the concise form
+    async fn log_tiered(&self, mission_id: &MissionId, agent: &str, content: &str, verbose: &str) -> Result<()> {
+        self.logs.append(&LogEntry::new(mission_id, agent, content).with_verbose(verbose)).await?;
+        self.events.publish(MissionEvent::LogLine {
+            mission_id: mission_id.clone(),
+            agent: agent.to_string(),
+            content: content.to_string(),
+        });
+        Ok(())
+    }
+    
+    /// Reap a finished mission's terminals into its log. Logged without an
+    /// event, since the mission's last event has already gone out.
+    async fn archive_terminals(&self, mission_id: &MissionId, terminals: &MissionTerminals) {
+        let archived = match terminals.reap(mission_id).await {
+            Ok(archived) => archived,
+            Err(e) => {
+                warn!(mission_id = %mission_id, error = %e, "Failed to reap mission terminals");
+                return;
+            }
+        };
+        for terminal in archived {
+            info!(mission_id = %mission_id, terminal = %terminal.name, "Closed mission terminal");
+            if let Err(e) = self.logs.append(&LogEntry::new(mission_id, terminal.log_agent(), &terminal.buffer)).await {
+                warn!(mission_id = %mission_id, error = %e, "Failed to archive terminal buffer");
+            }
+        }
+    }
+    
+    /// Record a running tool's progress in the mission log and publish it
+    async fn tool_progress(&self, mission_id: &MissionId, tool: &str, progress: ToolProgress) {
+        let line = match progress.fraction {
+            Some(fraction) => format!("{} ({:.0}%): {}", tool, fraction * 100.0, progress.message),
+            None => format!("{}: {}", tool, progress.message),
+        };
+        if let Err(e) = self.logs.append(&LogEntry::new(mission_id, "progress", line.as_str())).await {
+            warn!(error = %e, "Failed to log tool progress");
+        }
+        self.events.publish(MissionEvent::ToolProgress {
+            mission_id: mission_id.clone(),
+            tool: tool.to_string(),
+            progress,
+        });
     }
     
     fn is_complete(&self, response: &str) -> bool {
         response.contains("DONE:")
     }
     
-    async fn execute_tools(&self, response: &str) -> Result<Option<String>> {
-        // Simple parsing - look for TOOL: and ARGS:
-        if !response.contains("TOOL:") {
+    async fn execute_tools(
+        &self,
+        mission: &Mission,
+        response: &str,
+        agent: Option<&Agent>,
+        denied: &CapabilitySet,
+        cancel: &CancellationToken,
+    ) -> Result<Option<String>> {
+        let Some((tool_name, args)) = parse_tool_call(response) else {
             return Ok(None);
+        };
+        let mission_id = &mission.id;
+        
+        if let Some(agent) = agent.filter(|a| !a.allows_tool(tool_name)) {
+            return Ok(Some(format!("Tool '{}' is not available to the {} agent", tool_name, agent.name)));
+        }
+        let refused = self.tools.capabilities(tool_name).intersection(denied);
+        if !refused.is_empty() {
+            return Ok(Some(format!("Tool '{}' not run: this mission denies {}", tool_name, refused)));
+        }
+        // Went down since the prompt was built; the model can do without it
+        if let Some(service) = self.services.missing(&self.tools.requires(tool_name)) {
+            return Ok(Some(format!("Tool '{}' not run: {}", tool_name, SpawnError::ServiceUnavailable(service))));
+        }
+        for hook in &self.hooks {
+            if let Err(e) = hook.on_tool_call(mission, tool_name, &args).await {
+                info!(mission_id = %mission_id, hook = hook.name(), tool = tool_name, error = %e, "Mission hook refused a tool call");
+                return Ok(Some(format!("Tool '{}' not run: {}", tool_name, e)));
+            }
         }
         
-        // Extract tool name
-        let tool_line = response.lines()
-            .find(|l| l.starts_with("TOOL:"))
-            .map(|l| l.trim_start_matches("TOOL:").trim());
+        let resources = self.tools.locks(tool_name, &args);
+        let files: Vec<String> = resources.iter()
+            .filter_map(|r| r.strip_prefix("file:"))
+            .map(str::to_string)
+            .collect();
+        if let Some(reservations) = &self.reservations {
+            for file in &files {
+                match reservations.check(file, &reservations::mission_owner(mission_id), false).await {
+                    Ok(()) => {}
+                    Err(e @ SpawnError::Reserved { .. }) => return Ok(Some(format!("Tool '{}' not run: {}", tool_name, e))),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
         
-        let Some(tool_name) = tool_line else {
-            return Ok(None);
+        let guards = match &self.locks {
+            Some(locks) => match locks.acquire_all(&resources, mission_id, tool_name).await {
+                Ok(guards) => guards,
+                // Let the model work on something else and come back
+                Err(e @ SpawnError::Locked { .. }) => return Ok(Some(format!("Tool '{}' not run: {}", tool_name, e))),
+                Err(e) => return Err(e),
+            },
+            None => Vec::new(),
         };
         
-        // Extract args
-        let args_line = response.lines()
-            .find(|l| l.starts_with("ARGS:"))
-            .map(|l| l.trim_start_matches("ARGS:").trim())
-            .unwrap_or("{}");
-        
-        let args: serde_json::Value = serde_json::from_str(args_line)
-            .unwrap_or(serde_json::json!({}));
+        // A tool running commands can change any file without naming it, so
+        // the workspace is compared before and after
+        let capabilities = self.tools.capabilities(tool_name);
+        let stamps = match mission.context.workspace.as_ref().or(self.workspace.as_ref()) {
+            Some(root) if capabilities.contains(ToolPermission::ProcessSpawn) || capabilities.contains(ToolPermission::FilesystemWrite) => {
+                Some(FileStamps::take(root).await)
+            }
+            _ => None,
+        };
         
         // Execute
         info!(tool = tool_name, "Executing tool");
-        let result = self.tools.execute(tool_name, args).await?;
+        let (progress, mut updates) = ProgressSender::channel();
+        let run = self.tools.execute_with_progress(tool_name, args, &progress, cancel);
+        let scratch_dir = self.scratch.as_ref().map(|s| s.path(mission_id));
+        let run = async move {
+            match scratch_dir {
+                Some(dir) => scratch::scope(dir, run).await,
+                None => run.await,
+            }
+        };
+        tokio::pin!(run);
+        let result = loop {
+            tokio::select! {
+                result = &mut run => break result,
+                Some(update) = updates.next() => self.tool_progress(mission_id, tool_name, update).await,
+            }
+        };
+        // Sent just before the tool returned
+        while let Ok(Some(update)) = updates.try_next() {
+            self.tool_progress(mission_id, tool_name, update).await;
+        }
+        release_all(guards).await;
+        for hook in &self.hooks {
+            hook.on_tool_result(mission, tool_name, &result).await;
+        }
+        let mut written = result.as_ref().map(|r| self.tools.written_files(tool_name, r)).unwrap_or_default();
+        if let Some(stamps) = stamps {
+            written.extend(stamps.changed().await);
+            written.sort();
+            written.dedup();
+        }
+        if let Some(reservations) = self.reservations.as_ref().filter(|_| result.is_ok()) {
+            let changed: Vec<String> = files.iter().chain(&written).cloned().collect();
+            if let Err(e) = reservations.reserve_for_mission(&changed, mission_id, tool_name).await {
+                warn!(mission_id = %mission_id, error = %e, "Failed to reserve changed files");
+            }
+        }
+        self.events.publish(MissionEvent::ToolExecuted {
+            mission_id: mission_id.clone(),
+            tool: tool_name.to_string(),
+            success: result.as_ref().is_ok_and(|r| r["success"].as_bool() != Some(false)),
+        });
+        if !written.is_empty() {
+            self.events.publish(MissionEvent::FilesChanged {
+                mission_id: mission_id.clone(),
+                tool: tool_name.to_string(),
+                paths: written,
+            });
+        }
+        if let Some(scratch) = &self.scratch {
+            scratch.check_quota(mission_id).await?;
+        }
+        if let Err(e @ SpawnError::ServiceUnavailable(service)) = &result {
+            // Withhold its other tools until the next probe finds it back
+            self.services.set(*service, false);
+            return Ok(Some(format!("Tool '{}' failed: {}", tool_name, e)));
+        }
+        let result = result?;
         
         Ok(Some(serde_json::to_string_pretty(&result)?))
     }
 }
+
+/// The action in a model response, for the concise log tier: its `DONE:`
+/// summary or tool call, else its first line
+fn concise_response(response: &str) -> String {
+    if let Some((_, summary)) = response.split_once("DONE:") {
+        return format!("DONE: {}", summary.trim());
+    }
+    if let Some((tool, args)) = parse_tool_call(response) {
+        return format!("TOOL: {}\nARGS: {}", tool, args);
+    }
+    shorten(response.trim().lines().next().unwrap_or_default(), CONCISE_LOG_CHARS)
+}
+
+/// `text` cut to `max` characters, saying how much was left out
+fn shorten(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}… ({} more characters)", &text[..end], text[end..].chars().count()),
+        None => text.to_string(),
+    }
+}
+
+/// The `TOOL:` / `ARGS:` call in a model response, if any; unparseable
+/// arguments become `{}`
+pub fn parse_tool_call(response: &str) -> Option<(&str, serde_json::Value)> {
+    // Simple parsing - look for TOOL: and ARGS:
+    let tool_name = response.lines()
+        .find(|l| l.starts_with("TOOL:"))
+        .map(|l| l.trim_start_matches("TOOL:").trim())?;
+    
+    let args_line = response.lines()
+        .find(|l| l.starts_with("ARGS:"))
+        .map(|l| l.trim_start_matches("ARGS:").trim())
+        .unwrap_or("{}");
+    let args = serde_json::from_str(args_line).unwrap_or(serde_json::json!({}));
+    
+    Some((tool_name, args))
+}
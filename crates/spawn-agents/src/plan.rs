@@ -0,0 +1,119 @@
+//! Mission plans as task DAGs
+//!
+//! Validation (unknown dependencies, cycles) and scheduling helpers over a
+//! mission's tasks.
+
+use spawn_core::{Result, SpawnError, Task, TaskId, TaskStatus};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Check a plan and return its task IDs in a valid execution order.
+///
+/// Fails if a task depends on one outside the plan or the dependencies
+/// form a cycle.
+pub fn validate(tasks: &[Task]) -> Result<Vec<TaskId>> {
+    let ids: HashSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    if ids.len() != tasks.len() {
+        return Err(SpawnError::OrchestrationError("Duplicate task IDs in plan".into()));
+    }
+    for task in tasks {
+        if let Some(missing) = task.depends_on.iter().find(|d| !ids.contains(d.as_str())) {
+            return Err(SpawnError::OrchestrationError(format!(
+                "Task '{}' depends on unknown task '{}'", task.title, missing
+            )));
+        }
+    }
+
+    // Kahn's algorithm, keeping plan order among tasks that are ready together
+    let mut remaining: HashMap<&str, usize> = tasks.iter()
+        .map(|t| (t.id.as_str(), t.depends_on.len()))
+        .collect();
+    let mut queue: VecDeque<&str> = tasks.iter()
+        .filter(|t| t.depends_on.is_empty())
+        .map(|t| t.id.as_str())
+        .collect();
+    let mut order = Vec::with_capacity(tasks.len());
+
+    while let Some(id) = queue.pop_front() {
+        order.push(TaskId::from(id));
+        for task in tasks.iter().filter(|t| t.depends_on.iter().any(|d| d == id)) {
+            let count = remaining.get_mut(task.id.as_str()).expect("task id indexed above");
+            *count -= 1;
+            if *count == 0 {
+                queue.push_back(&task.id);
+            }
+        }
+    }
+
+    if order.len() != tasks.len() {
+        let stuck: Vec<&str> = tasks.iter()
+            .filter(|t| !order.contains(&t.id))
+            .map(|t| t.title.as_str())
+            .collect();
+        return Err(SpawnError::OrchestrationError(format!(
+            "Plan has a dependency cycle involving: {}", stuck.join(", ")
+        )));
+    }
+    Ok(order)
+}
+
+/// Pending tasks whose dependencies have all completed
+pub fn ready(tasks: &[Task]) -> Vec<&Task> {
+    let completed: HashSet<&str> = tasks.iter()
+        .filter(|t| t.status == TaskStatus::Completed)
+        .map(|t| t.id.as_str())
+        .collect();
+    tasks.iter()
+        .filter(|t| t.status == TaskStatus::Pending && t.depends_on.iter().all(|d| completed.contains(d.as_str())))
+        .collect()
+}
+
+/// Pending tasks that can never run because something they depend on
+/// (directly or transitively) failed or was skipped
+pub fn blocked(tasks: &[Task]) -> Vec<&Task> {
+    let mut dead: HashSet<&str> = tasks.iter()
+        .filter(|t| matches!(t.status, TaskStatus::Failed | TaskStatus::Skipped))
+        .map(|t| t.id.as_str())
+        .collect();
+    loop {
+        let newly: Vec<&str> = tasks.iter()
+            .filter(|t| t.status == TaskStatus::Pending && !dead.contains(t.id.as_str()))
+            .filter(|t| t.depends_on.iter().any(|d| dead.contains(d.as_str())))
+            .map(|t| t.id.as_str())
+            .collect();
+        if newly.is_empty() {
+            break;
+        }
+        dead.extend(newly);
+    }
+    tasks.iter()
+        .filter(|t| t.status == TaskStatus::Pending && dead.contains(t.id.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders_and_schedules_a_dag() {
+        let schema = Task::new("m", "schema");
+        let api = Task::new("m", "api").depends_on([schema.id.clone()]);
+        let ui = Task::new("m", "ui").depends_on([api.id.clone()]);
+        let mut tasks = vec![ui, api, schema];
+
+        let order = validate(&tasks).unwrap();
+        assert_eq!(order, vec![tasks[2].id.clone(), tasks[1].id.clone(), tasks[0].id.clone()]);
+        assert_eq!(ready(&tasks)[0].title, "schema");
+
+        tasks[2].status = TaskStatus::Failed;
+        assert_eq!(blocked(&tasks).len(), 2);
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut a = Task::new("m", "a");
+        let b = Task::new("m", "b").depends_on([a.id.clone()]);
+        a.depends_on = vec![b.id.clone()];
+        assert!(validate(&[a, b]).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Post-mortems for failed missions
+//!
+//! A cheap model reads the mission log and classifies the failure; if that
+//! call fails too (e.g. during a provider outage) the error itself is
+//! classified heuristically, so every failed mission gets a post-mortem.
+
+use chrono::Utc;
+use spawn_core::{CancellationToken, ChatMessage, ChatOptions, FailureCategory, LlmClient, LogEntry, Mission, PostMortem, SpawnError};
+use tracing::warn;
+
+pub const DEFAULT_POSTMORTEM_MODEL: &str = "openai/gpt-4o-mini";
+
+/// Log characters sent to the model; the tail is kept since failures happen late
+const MAX_LOG_CHARS: usize = 12_000;
+
+/// Classification from the error alone
+pub fn classify_error(error: &SpawnError) -> FailureCategory {
+    match error {
+        SpawnError::ProviderError(_)
+        | SpawnError::RateLimited { .. }
+        | SpawnError::ProviderUnavailable(_) => FailureCategory::ProviderOutage,
+        SpawnError::BudgetExceeded(_) | SpawnError::ContextOverflow { .. } => FailureCategory::Budget,
+        SpawnError::ToolError(_) => FailureCategory::ToolError,
+        SpawnError::OrchestrationError(msg) if msg.contains("Max steps") => FailureCategory::Budget,
+        _ => FailureCategory::Unknown,
+    }
+}
+
+fn default_remediation(category: FailureCategory) -> &'static str {
+    match category {
+        FailureCategory::ToolError => "Check the failing tool call's arguments and environment, then retry.",
+        FailureCategory::BadPlan => "Narrow the goal or add constraints and context before retrying.",
+        FailureCategory::Budget => "Split the goal into smaller missions or raise the step budget.",
+        FailureCategory::ProviderOutage => "Retry once the provider recovers, or switch to a fallback model.",
+        FailureCategory::Unknown => "Inspect the mission log for the last successful step.",
+    }
+}
+
+/// Post-mortem built without a model
+pub fn heuristic(mission: &Mission, error: &SpawnError) -> PostMortem {
+    let category = classify_error(error);
+    PostMortem {
+        mission_id: mission.id.clone(),
+        category,
+        summary: error.to_string(),
+        remediation: default_remediation(category).to_string(),
+        model: None,
+        created_at: Utc::now(),
+    }
+}
+
+/// Analyze a failed mission's log with a model, falling back to the heuristic
+pub async fn analyze(
+    llm: &dyn LlmClient,
+    model: &str,
+    mission: &Mission,
+    logs: &[LogEntry],
+    error: &SpawnError,
+) -> PostMortem {
+    let fallback = heuristic(mission, error);
+    // Asking a down provider to explain its own outage only adds latency
+    if fallback.category == FailureCategory::ProviderOutage {
+        return fallback;
+    }
+
+    let mut log = logs.iter()
+        .map(|e| format!("[{}] {}", e.agent, e.content))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if log.len() > MAX_LOG_CHARS {
+        let mut start = log.len() - MAX_LOG_CHARS;
+        while !log.is_char_boundary(start) {
+            start += 1;
+        }
+        log = format!("...{}", &log[start..]);
+    }
+
+    let messages = vec![
+        ChatMessage::system(
+            r#"You analyze failed runs of an autonomous coding agent. Reply with JSON only:
+{"category": "tool_error" | "bad_plan" | "budget" | "provider_outage" | "unknown",
+ "summary": "<one or two sentences on what went wrong>",
+ "remediation": "<one concrete change to make before retrying>"}"#,
+        ),
+        ChatMessage::user(format!(
+            "Goal: {}\nFinal error: {}\nHeuristic category: {:?}\n\nLog:\n{}",
+            mission.goal, error, fallback.category, log
+        )),
+    ];
+
+    // Runs after the mission has ended, so it is never cancelled with it
+    let options = ChatOptions::new().with_temperature(0.2);
+    let response = match llm.chat(model, &messages, &options, &CancellationToken::new()).await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(mission_id = %mission.id, error = %e, "Post-mortem analysis failed");
+            return fallback;
+        }
+    };
+
+    match parse_analysis(&response) {
+        Some((category, summary, remediation)) => PostMortem {
+            category,
+            summary,
+            remediation,
+            model: Some(model.to_string()),
+            ..fallback
+        },
+        None => {
+            warn!(mission_id = %mission.id, "Unparseable post-mortem response");
+            fallback
+        }
+    }
+}
+
+/// Pull the JSON object out of a model response (tolerates code fences)
+fn parse_analysis(response: &str) -> Option<(FailureCategory, String, String)> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    let json: serde_json::Value = serde_json::from_str(response.get(start..=end)?).ok()?;
+    let category = serde_json::from_value(json["category"].clone()).unwrap_or(FailureCategory::Unknown);
+    let summary = json["summary"].as_str()?.trim().to_string();
+    let remediation = json["remediation"].as_str().unwrap_or_default().trim().to_string();
+    Some((category, summary, remediation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fenced_analysis() {
+        let response = "```json\n{\"category\": \"bad_plan\", \"summary\": \"Edited the wrong file.\", \"remediation\": \"Point at src/app.rs.\"}\n```";
+        let (category, summary, remediation) = parse_analysis(response).unwrap();
+        assert_eq!(category, FailureCategory::BadPlan);
+        assert_eq!(summary, "Edited the wrong file.");
+        assert_eq!(remediation, "Point at src/app.rs.");
+    }
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(classify_error(&SpawnError::OrchestrationError("Max steps exceeded".into())), FailureCategory::Budget);
+        assert_eq!(classify_error(&SpawnError::BudgetExceeded("Max steps exceeded".into())), FailureCategory::Budget);
+        assert_eq!(classify_error(&SpawnError::from_status(429, "slow down", None)), FailureCategory::ProviderOutage);
+        assert_eq!(classify_error(&SpawnError::ProviderError("503".into())), FailureCategory::ProviderOutage);
+    }
+}
@@ -0,0 +1,186 @@
+//! Pre-flight checks for a mission
+//!
+//! Everything that can be known to go wrong before a mission spends tokens:
+//! an unknown agent or tool, a budget that can't be met, a workspace that
+//! isn't there or can't be written. Errors mean the mission would fail;
+//! warnings mean it would run, but probably not as intended.
+
+use crate::estimate::CostEstimate;
+use crate::tools::ToolRegistry;
+use serde::Serialize;
+use spawn_core::{AgentRegistry, Budget, CapabilitySet, ContextConfig, Mission};
+use std::path::Path;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Preflight {
+    /// No errors: the mission can start
+    pub ok: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub estimate: Estimate,
+}
+
+/// The most a mission can use, from its budget and the context window
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Estimate {
+    pub max_steps: usize,
+    /// Every step sending a full prompt, unless the budget caps it lower
+    pub max_tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+    /// Likely steps, tokens and cost, from past missions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected: Option<CostEstimate>,
+}
+
+/// What a pre-flight check needs to know about the orchestrator
+pub struct PreflightContext<'a> {
+    pub tools: &'a ToolRegistry,
+    pub agents: &'a dyn AgentRegistry,
+    /// Capabilities denied to every mission
+    pub denied: &'a CapabilitySet,
+    pub context: &'a ContextConfig,
+    /// Steps allowed when the budget sets no limit
+    pub default_max_steps: usize,
+}
+
+/// Check `mission` against the orchestrator's tools, agents and policy and
+/// against the workspace it would run in
+pub async fn preflight(mission: &Mission, with: &PreflightContext<'_>, workspace: &Path) -> Preflight {
+    let mut report = Preflight::default();
+    if mission.goal.trim().is_empty() {
+        report.errors.push("Goal is empty".into());
+    }
+    check_tools(mission, with, &mut report);
+    check_budget(&mission.budget, with.context, chrono::Utc::now(), &mut report);
+    check_workspace(mission.context.workspace.as_deref().unwrap_or(workspace), &mut report).await;
+
+    let max_steps = mission.budget.max_steps.unwrap_or(with.default_max_steps);
+    let per_step = with.context.window_tokens as u64;
+    report.estimate = Estimate {
+        max_steps,
+        max_tokens: mission.budget.max_tokens.map_or(per_step * max_steps as u64, |max| max.min(per_step * max_steps as u64)),
+        max_cost_usd: mission.budget.max_cost_usd,
+        projected: None,
+    };
+    report.ok = report.errors.is_empty();
+    report
+}
+
+/// The agent exists, and the tools the mission asks for (`context.tools`)
+/// exist, are allowed to the agent and aren't denied
+fn check_tools(mission: &Mission, with: &PreflightContext<'_>, report: &mut Preflight) {
+    let agent = match mission.context.agent.as_deref() {
+        Some(name) => match with.agents.get(name) {
+            Some(agent) => Some(agent),
+            None => {
+                report.errors.push(format!("Unknown agent '{}'", name));
+                None
+            }
+        },
+        None => None,
+    };
+    if let Some(agent) = &agent {
+        for tool in agent.allowed_tools.iter().filter(|t| !with.tools.contains(t)) {
+            report.warnings.push(format!("Agent '{}' allows tool '{}', which isn't registered", agent.name, tool));
+        }
+    }
+
+    let denied = with.denied.union(&mission.context.deny);
+    for tool in mission.context.get::<Vec<String>>("tools").unwrap_or_default() {
+        if !with.tools.contains(&tool) {
+            report.errors.push(format!("Unknown tool '{}'", tool));
+            continue;
+        }
+        if let Some(agent) = agent.as_ref().filter(|a| !a.allows_tool(&tool)) {
+            report.errors.push(format!("Tool '{}' is not available to the {} agent", tool, agent.name));
+        }
+        let refused = with.tools.capabilities(&tool).intersection(&denied);
+        if !refused.is_empty() {
+            report.warnings.push(format!("Tool '{}' will be refused: this mission denies {}", tool, refused));
+        }
+    }
+}
+
+/// Limits that stop the mission before it starts, or before its first step
+fn check_budget(budget: &Budget, context: &ContextConfig, now: chrono::DateTime<chrono::Utc>, report: &mut Preflight) {
+    if budget.max_steps == Some(0) {
+        report.errors.push("max_steps is 0".into());
+    }
+    match budget.max_tokens {
+        Some(0) => report.errors.push("max_tokens is 0".into()),
+        Some(max) if max < context.window_tokens as u64 => report.warnings.push(format!(
+            "max_tokens ({}) is less than one full prompt ({} tokens); the mission may stop after its first step",
+            max, context.window_tokens
+        )),
+        _ => {}
+    }
+    if budget.max_cost_usd.is_some_and(|max| max <= 0.0) {
+        report.errors.push("max_cost_usd must be positive".into());
+    }
+    if let Some(deadline) = budget.deadline.filter(|deadline| *deadline <= now) {
+        report.errors.push(format!("Deadline {} has already passed", deadline.to_rfc3339()));
+    }
+    if budget.hard_timeout_secs == Some(0) {
+        report.errors.push("hard_timeout_secs is 0".into());
+    }
+    if let (Some(soft), Some(hard)) = (budget.soft_timeout_secs, budget.hard_timeout_secs) {
+        if soft >= hard {
+            report.warnings.push(format!(
+                "soft_timeout_secs ({}) is not below hard_timeout_secs ({}); the mission will be stopped without a wrap-up",
+                soft, hard
+            ));
+        }
+    }
+}
+
+/// The workspace exists and can be written; not being a git repository only
+/// costs rollback and the git tools
+async fn check_workspace(dir: &Path, report: &mut Preflight) {
+    if !dir.is_dir() {
+        report.errors.push(format!("Workspace {} does not exist", dir.display()));
+        return;
+    }
+    let probe = dir.join(format!(".spawn-preflight-{}", uuid::Uuid::new_v4().simple()));
+    match tokio::fs::write(&probe, b"").await {
+        Ok(()) => {
+            let _ = tokio::fs::remove_file(&probe).await;
+        }
+        Err(e) => report.errors.push(format!("Workspace {} is not writable: {}", dir.display(), e)),
+    }
+    let in_repo = Command::new("git").args(["rev-parse", "--is-inside-work-tree"]).current_dir(dir)
+        .output().await
+        .is_ok_and(|o| o.status.success());
+    if !in_repo {
+        report.warnings.push(format!("Workspace {} is not a git repository; rollback and git tools won't work", dir.display()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_budget() {
+        let now = chrono::Utc::now();
+        let context = ContextConfig::default();
+        let mut report = Preflight::default();
+        let budget = Budget {
+            max_steps: Some(0),
+            max_tokens: Some(100),
+            max_cost_usd: Some(1.0),
+            deadline: Some(now - chrono::Duration::minutes(1)),
+            ..Default::default()
+        };
+
+        check_budget(&budget, &context, now, &mut report);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[1].contains("already passed"));
+        assert_eq!(report.warnings.len(), 1);
+
+        let mut report = Preflight::default();
+        check_budget(&Budget::default(), &context, now, &mut report);
+        assert!(report.errors.is_empty() && report.warnings.is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+//! PII scrubbing for conversation memory
+//!
+//! Applied to chat messages before they are embedded and stored when
+//! `PII_SCRUBBING` is enabled. Matches are replaced with a placeholder so the
+//! surrounding text still reads naturally when retrieved as context.
+
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap()
+});
+
+/// Provider API keys and tokens with recognizable prefixes
+static SECRET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\b(?:sk-(?:or-|proj-|ant-)?[A-Za-z0-9_-]{20,}",
+        r"|AKIA[0-9A-Z]{16}",
+        r"|gh[pousr]_[A-Za-z0-9]{36,}",
+        r"|xox[abpr]-[A-Za-z0-9-]{10,}",
+        r"|eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,})",
+    )).unwrap()
+});
+
+static SSN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap());
+
+/// 13-19 digits, optionally grouped by spaces or dashes; confirmed with Luhn
+static CARD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap());
+
+/// International or separated phone numbers; bare digit runs are left alone
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[ .-]?)?\(?\b\d{3}\)?[ .-]\d{3}[ .-]\d{4}\b|\+\d{7,14}\b").unwrap()
+});
+
+/// Redact personal data and credentials from text
+pub fn scrub_pii(text: &str) -> String {
+    let text = SECRET.replace_all(text, "[SECRET]");
+    let text = EMAIL.replace_all(&text, "[EMAIL]");
+    let text = SSN.replace_all(&text, "[SSN]");
+    let text = CARD.replace_all(&text, |caps: &Captures| {
+        if luhn_valid(&caps[0]) { "[CARD]".to_string() } else { caps[0].to_string() }
+    });
+    PHONE.replace_all(&text, "[PHONE]").into_owned()
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() < 13 {
+        return false;
+    }
+    let sum: u32 = digits.iter().rev().enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_contact_details_and_keys() {
+        let text = "Mail jane.doe@example.com or call +1 415-555-0100, key sk-or-v1abcdefghijklmnopqrstuvwx";
+        assert_eq!(scrub_pii(text), "Mail [EMAIL] or call [PHONE], key [SECRET]");
+    }
+
+    #[test]
+    fn only_luhn_valid_numbers_are_cards() {
+        assert_eq!(scrub_pii("card 4111 1111 1111 1111"), "card [CARD]");
+        assert_eq!(scrub_pii("build 1234567890123"), "build 1234567890123");
+    }
+}
@@ -0,0 +1,337 @@
+//! Process manager for long-running dev servers and watchers
+//!
+//! Tracks processes started on behalf of the agent (or the user), the ports
+//! they listen on, and the tail of their output.
+
+use crate::tools::within_workspace;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use spawn_core::{Result, SpawnError};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+const OUTPUT_LINES: usize = 500;
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessStatus {
+    Running,
+    Exited,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub cwd: PathBuf,
+    pub port: Option<u16>,
+    pub pid: Option<u32>,
+    pub status: ProcessStatus,
+    pub exit_code: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub restarts: u32,
+    /// Extra environment, kept for restarts
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartProcess {
+    pub name: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    /// Port the process is expected to listen on; detected from output if omitted
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+struct ManagedProcess {
+    info: ProcessInfo,
+    child: Option<Child>,
+    output: Arc<Mutex<VecDeque<String>>>,
+    detected_port: Arc<Mutex<Option<u16>>>,
+}
+
+pub struct ProcessManager {
+    workspace_root: PathBuf,
+    processes: RwLock<HashMap<String, ManagedProcess>>,
+}
+
+impl ProcessManager {
+    pub fn new(workspace_root: PathBuf) -> Self {
+        Self {
+            workspace_root,
+            processes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// `cwd`s of started processes are relative to this
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    /// Start a new managed process
+    pub async fn start(&self, req: StartProcess) -> Result<ProcessInfo> {
+        let cwd = match req.cwd {
+            Some(p) => within_workspace(&self.workspace_root, &p)
+                .ok_or_else(|| SpawnError::ToolError("cwd must be inside the workspace".into()))?,
+            None => self.workspace_root.clone(),
+        };
+
+        let mut info = ProcessInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: req.name,
+            command: req.command,
+            cwd,
+            port: req.port,
+            pid: None,
+            status: ProcessStatus::Running,
+            exit_code: None,
+            started_at: Utc::now(),
+            restarts: 0,
+            env: req.env,
+        };
+
+        let output = Arc::new(Mutex::new(VecDeque::with_capacity(OUTPUT_LINES)));
+        let detected_port = Arc::new(Mutex::new(None));
+        let child = spawn_child(&info, output.clone(), detected_port.clone())?;
+        info.pid = child.id();
+
+        info!(id = %info.id, name = %info.name, command = %info.command, "Started process");
+
+        self.processes.write().await.insert(info.id.clone(), ManagedProcess {
+            info: info.clone(),
+            child: Some(child),
+            output,
+            detected_port,
+        });
+        Ok(info)
+    }
+
+    /// List all managed processes with refreshed status
+    pub async fn list(&self) -> Vec<ProcessInfo> {
+        let mut processes = self.processes.write().await;
+        let mut list = Vec::with_capacity(processes.len());
+        for process in processes.values_mut() {
+            process.refresh().await;
+            list.push(process.info.clone());
+        }
+        list.sort_by_key(|p| p.started_at);
+        list
+    }
+
+    pub async fn get(&self, id: &str) -> Option<ProcessInfo> {
+        let mut processes = self.processes.write().await;
+        let process = processes.get_mut(id)?;
+        process.refresh().await;
+        Some(process.info.clone())
+    }
+
+    /// Find the running process listening on `port`
+    pub async fn by_port(&self, port: u16) -> Option<ProcessInfo> {
+        self.list().await
+            .into_iter()
+            .find(|p| p.port == Some(port) && p.status == ProcessStatus::Running)
+    }
+
+    /// Last `lines` lines of combined stdout/stderr
+    pub async fn output(&self, id: &str, lines: usize) -> Option<Vec<String>> {
+        let processes = self.processes.read().await;
+        let output = processes.get(id)?.output.lock().await;
+        Some(output.iter().rev().take(lines).rev().cloned().collect())
+    }
+
+    /// Stop a process (SIGTERM to its process group, SIGKILL after a grace period)
+    pub async fn stop(&self, id: &str) -> Result<ProcessInfo> {
+        let exit_code = match self.take_child(id).await? {
+            Some(mut child) => {
+                terminate(&mut child).await;
+                Some(child.wait().await.ok().and_then(|s| s.code()))
+            }
+            None => None,
+        };
+
+        let mut processes = self.processes.write().await;
+        let process = processes.get_mut(id)
+            .ok_or_else(|| SpawnError::ToolError(format!("Process not found: {}", id)))?;
+        if let Some(exit_code) = exit_code {
+            process.info.exit_code = exit_code;
+        }
+        process.info.status = ProcessStatus::Stopped;
+        process.info.pid = None;
+
+        info!(id = %id, name = %process.info.name, "Stopped process");
+        Ok(process.info.clone())
+    }
+
+    /// Stop (if running) and start the same command again
+    pub async fn restart(&self, id: &str) -> Result<ProcessInfo> {
+        if let Some(mut child) = self.take_child(id).await? {
+            terminate(&mut child).await;
+            let _ = child.wait().await;
+        }
+
+        let mut processes = self.processes.write().await;
+        let process = processes.get_mut(id)
+            .ok_or_else(|| SpawnError::ToolError(format!("Process not found: {}", id)))?;
+        // Started again by a concurrent restart while this one was stopping it
+        if process.child.is_some() {
+            return Ok(process.info.clone());
+        }
+        process.output.lock().await.clear();
+        let child = spawn_child(&process.info, process.output.clone(), process.detected_port.clone())?;
+        process.info.pid = child.id();
+        process.info.status = ProcessStatus::Running;
+        process.info.exit_code = None;
+        process.info.started_at = Utc::now();
+        process.info.restarts += 1;
+        process.child = Some(child);
+
+        info!(id = %id, name = %process.info.name, restarts = process.info.restarts, "Restarted process");
+        Ok(process.info.clone())
+    }
+
+    /// Take a process's child, so it can be stopped without holding the
+    /// lock every other lookup needs through the grace period
+    async fn take_child(&self, id: &str) -> Result<Option<Child>> {
+        let mut processes = self.processes.write().await;
+        let process = processes.get_mut(id)
+            .ok_or_else(|| SpawnError::ToolError(format!("Process not found: {}", id)))?;
+        Ok(process.child.take())
+    }
+
+    /// Stop a process and forget about it
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        self.stop(id).await?;
+        self.processes.write().await.remove(id);
+        Ok(())
+    }
+}
+
+impl ManagedProcess {
+    async fn refresh(&mut self) {
+        if self.info.port.is_none() {
+            self.info.port = *self.detected_port.lock().await;
+        }
+        if let Some(child) = self.child.as_mut() {
+            if let Ok(Some(status)) = child.try_wait() {
+                self.info.status = ProcessStatus::Exited;
+                self.info.exit_code = status.code();
+                self.info.pid = None;
+                self.child = None;
+            }
+        }
+    }
+}
+
+fn spawn_child(
+    info: &ProcessInfo,
+    output: Arc<Mutex<VecDeque<String>>>,
+    detected_port: Arc<Mutex<Option<u16>>>,
+) -> Result<Child> {
+    let mut cmd = Command::new("bash");
+    cmd.arg("-c")
+        .arg(&info.command)
+        .current_dir(&info.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .envs(&info.env);
+    if let Some(port) = info.port {
+        cmd.env("PORT", port.to_string());
+    }
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    let mut child = cmd.spawn()
+        .map_err(|e| SpawnError::ToolError(format!("Failed to start '{}': {}", info.command, e)))?;
+
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(capture(stdout, output.clone(), detected_port.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(capture(stderr, output, detected_port));
+    }
+    Ok(child)
+}
+
+async fn capture(
+    stream: impl AsyncRead + Unpin,
+    output: Arc<Mutex<VecDeque<String>>>,
+    detected_port: Arc<Mutex<Option<u16>>>,
+) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Some(port) = detect_port(&line) {
+            detected_port.lock().await.get_or_insert(port);
+        }
+        let mut output = output.lock().await;
+        if output.len() >= OUTPUT_LINES {
+            output.pop_front();
+        }
+        output.push_back(line);
+    }
+}
+
+async fn terminate(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // Signal the whole group so `npm run dev` style wrappers take their children down too
+        let _ = Command::new("kill")
+            .args(["-TERM", "--", &format!("-{}", pid)])
+            .status()
+            .await;
+        if tokio::time::timeout(STOP_GRACE, child.wait()).await.is_ok() {
+            return;
+        }
+        warn!(pid, "Process did not exit after SIGTERM, killing");
+    }
+    let _ = child.kill().await;
+}
+
+/// Find a listening port announced in a line of output
+/// ("Local: http://localhost:5173/", "listening on 0.0.0.0:3000", "port 8080")
+pub fn detect_port(line: &str) -> Option<u16> {
+    let lower = line.to_lowercase();
+    for marker in ["localhost:", "127.0.0.1:", "0.0.0.0:", "[::]:", "port "] {
+        let mut rest = lower.as_str();
+        while let Some(i) = rest.find(marker) {
+            rest = &rest[i + marker.len()..];
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(port) = digits.parse::<u16>() {
+                if port >= 1024 {
+                    return Some(port);
+                }
+            }
+        }
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_start_keeps_cwd_in_workspace() {
+        let manager = ProcessManager::new(std::env::temp_dir().join("spawn-processes"));
+        for cwd in ["../../etc", "sub/../../..", "/etc"] {
+            let req = StartProcess { name: "escape".into(), command: "true".into(), cwd: Some(cwd.into()), port: None, env: HashMap::new() };
+            assert!(matches!(manager.start(req).await, Err(SpawnError::ToolError(_))), "{}", cwd);
+        }
+        assert!(manager.list().await.is_empty());
+    }
+}
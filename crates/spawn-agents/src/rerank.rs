@@ -0,0 +1,247 @@
+//! Second-stage reranking of vector search results
+//!
+//! Vector retrieval is cheap but coarse, so callers fetch a wide candidate
+//! pool (50 by default) and let a reranker rescore it before keeping the top
+//! few. Two rerankers are available: an LLM judge, and a cross-encoder served
+//! over HTTP (text-embeddings-inference `/rerank`, or a Cohere/Jina-style API).
+
+use crate::vector_memory::SearchResult;
+use serde::{Deserialize, Serialize};
+use spawn_core::{CancellationToken, ChatMessage, ChatOptions, LlmClient, Result, SpawnError};
+use std::sync::Arc;
+
+/// Candidates fetched from the vector store before reranking
+pub const DEFAULT_RERANK_CANDIDATES: i32 = 50;
+/// Cheap model used as the LLM judge
+pub const DEFAULT_RERANK_MODEL: &str = "openai/gpt-4o-mini";
+/// Passage characters shown to the LLM judge
+const MAX_PASSAGE_CHARS: usize = 800;
+
+/// Which reranker a request asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RerankMode {
+    Llm,
+    CrossEncoder,
+}
+
+impl std::fmt::Display for RerankMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RerankMode::Llm => write!(f, "llm"),
+            RerankMode::CrossEncoder => write!(f, "cross_encoder"),
+        }
+    }
+}
+
+impl std::str::FromStr for RerankMode {
+    type Err = SpawnError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "llm" => Ok(Self::Llm),
+            "cross_encoder" | "cross-encoder" => Ok(Self::CrossEncoder),
+            other => Err(SpawnError::Internal(format!("Unknown rerank mode '{}'", other))),
+        }
+    }
+}
+
+/// Scores passages for relevance to a query; higher is more relevant
+#[async_trait::async_trait]
+pub trait Reranker: Send + Sync {
+    /// One score per passage, in order
+    async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>>;
+
+    fn name(&self) -> &str;
+}
+
+/// The rerankers a server has configured, selectable per request
+#[derive(Clone, Default)]
+pub struct Rerankers {
+    pub llm: Option<Arc<dyn Reranker>>,
+    pub cross_encoder: Option<Arc<dyn Reranker>>,
+}
+
+impl Rerankers {
+    pub fn get(&self, mode: RerankMode) -> Option<Arc<dyn Reranker>> {
+        match mode {
+            RerankMode::Llm => self.llm.clone(),
+            RerankMode::CrossEncoder => self.cross_encoder.clone(),
+        }
+    }
+}
+
+/// Rescore candidates and keep the best `top_n`
+pub async fn rerank(
+    reranker: &dyn Reranker,
+    query: &str,
+    mut candidates: Vec<SearchResult>,
+    top_n: usize,
+) -> Result<Vec<SearchResult>> {
+    if candidates.is_empty() {
+        return Ok(candidates);
+    }
+    let passages: Vec<&str> = candidates.iter().map(|c| c.content_preview.as_str()).collect();
+    let scores = reranker.score(query, &passages).await?;
+    if scores.len() != candidates.len() {
+        return Err(SpawnError::Internal(format!(
+            "{} reranker returned {} scores for {} passages", reranker.name(), scores.len(), candidates.len()
+        )));
+    }
+
+    for (candidate, score) in candidates.iter_mut().zip(scores) {
+        candidate.rerank_score = Some(score);
+    }
+    // Vector similarity breaks ties between equally rated passages
+    candidates.sort_by(|a, b| {
+        b.rerank_score.unwrap_or(0.0).total_cmp(&a.rerank_score.unwrap_or(0.0))
+            .then(b.similarity.total_cmp(&a.similarity))
+    });
+    candidates.truncate(top_n);
+    Ok(candidates)
+}
+
+/// LLM-as-judge: rates every passage 0-10 in a single call
+pub struct LlmReranker {
+    llm: Arc<dyn LlmClient>,
+    model: String,
+}
+
+impl LlmReranker {
+    pub fn new(llm: Arc<dyn LlmClient>) -> Self {
+        Self { llm, model: DEFAULT_RERANK_MODEL.to_string() }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Reranker for LlmReranker {
+    async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        let numbered = passages.iter()
+            .enumerate()
+            .map(|(i, p)| format!("[{}] {}", i + 1, p.chars().take(MAX_PASSAGE_CHARS).collect::<String>()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let messages = vec![
+            ChatMessage::system(format!(
+                "You rate how useful each passage is for answering a search query, from 0 (irrelevant) \
+                 to 10 (directly answers it). Reply with a JSON array of exactly {} numbers, one per \
+                 passage in order, and nothing else.",
+                passages.len()
+            )),
+            ChatMessage::user(format!("Query: {}\n\nPassages:\n{}", query, numbered)),
+        ];
+
+        // Scores should not depend on the sampling draw
+        let options = ChatOptions::new().with_temperature(0.0);
+        let response = self.llm.chat(&self.model, &messages, &options, &CancellationToken::new()).await?;
+        let scores = parse_llm_scores(&response)
+            .ok_or_else(|| SpawnError::ProviderError("Unparseable rerank response".into()))?;
+        Ok(scores.into_iter().map(|s| (s / 10.0).clamp(0.0, 1.0)).collect())
+    }
+
+    fn name(&self) -> &str {
+        "llm"
+    }
+}
+
+/// Pull the score array out of a judge response (tolerates code fences)
+fn parse_llm_scores(response: &str) -> Option<Vec<f32>> {
+    let start = response.find('[')?;
+    let end = response.rfind(']')?;
+    serde_json::from_str(response.get(start..=end)?).ok()
+}
+
+/// Cross-encoder behind an HTTP rerank endpoint
+pub struct CrossEncoderReranker {
+    client: reqwest::Client,
+    url: String,
+    model: Option<String>,
+    api_key: Option<String>,
+}
+
+impl CrossEncoderReranker {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { client: reqwest::Client::new(), url: url.into(), model: None, api_key: None }
+    }
+
+    /// Model name, for hosted APIs that serve several
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Reranker for CrossEncoderReranker {
+    async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        let mut body = serde_json::json!({
+            "query": query,
+            "texts": passages,
+            "documents": passages,
+        });
+        if let Some(model) = &self.model {
+            body["model"] = serde_json::json!(model);
+        }
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await
+            .map_err(|e| SpawnError::ProviderError(format!("Rerank request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(SpawnError::ProviderError(format!("Rerank endpoint returned {}", response.status())));
+        }
+        let value: serde_json::Value = response.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Invalid rerank response: {}", e)))?;
+        parse_cross_encoder_scores(&value, passages.len())
+            .ok_or_else(|| SpawnError::ProviderError("Unrecognized rerank response".into()))
+    }
+
+    fn name(&self) -> &str {
+        "cross_encoder"
+    }
+}
+
+/// Scores in passage order from `[{index, score}]` (TEI) or
+/// `{results: [{index, relevance_score}]}` (Cohere/Jina)
+fn parse_cross_encoder_scores(value: &serde_json::Value, count: usize) -> Option<Vec<f32>> {
+    let results = value.as_array().or_else(|| value["results"].as_array())?;
+    let mut scores = vec![0.0; count];
+    for result in results {
+        let index = result["index"].as_u64()? as usize;
+        let score = result["score"].as_f64().or_else(|| result["relevance_score"].as_f64())?;
+        *scores.get_mut(index)? = score as f32;
+    }
+    Some(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_cross_encoder_formats() {
+        let tei = serde_json::json!([{ "index": 1, "score": 0.9 }, { "index": 0, "score": 0.2 }]);
+        assert_eq!(parse_cross_encoder_scores(&tei, 2), Some(vec![0.2, 0.9]));
+
+        let cohere = serde_json::json!({ "results": [{ "index": 0, "relevance_score": 0.5 }] });
+        assert_eq!(parse_cross_encoder_scores(&cohere, 2), Some(vec![0.5, 0.0]));
+        assert_eq!(parse_cross_encoder_scores(&cohere, 0), None);
+    }
+
+    #[test]
+    fn parses_fenced_llm_scores() {
+        assert_eq!(parse_llm_scores("```json\n[7, 0, 10]\n```"), Some(vec![7.0, 0.0, 10.0]));
+    }
+}
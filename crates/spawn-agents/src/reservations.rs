@@ -0,0 +1,88 @@
+//! File reservations
+//!
+//! Soft claims that keep the agent from clobbering work in progress. The
+//! editor reserves a file while it has unsaved changes to it; a mission
+//! reserves the files its tools change until it ends. Anyone else writing a
+//! reserved file is refused unless they force it. Unlike workspace locks,
+//! which only serialize individual writes, a reservation lasts across them.
+
+use chrono::Utc;
+use spawn_core::{FileReservation, MissionId, Result, SpawnError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::locks::normalize;
+use crate::memory::Database;
+
+/// How long an editor reservation lasts unless renewed
+pub const DEFAULT_RESERVATION_TTL: Duration = Duration::from_secs(300);
+/// Missions release theirs when they end; this only covers a crashed node
+const MISSION_RESERVATION_TTL: Duration = Duration::from_secs(6 * 3600);
+
+/// Reservation owner for a mission's pending changes
+pub fn mission_owner(mission_id: &MissionId) -> String {
+    format!("mission:{}", mission_id)
+}
+
+pub struct FileReservations {
+    db: Arc<Database>,
+}
+
+impl FileReservations {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+    
+    /// Reserve `path` for `owner`, or renew the owner's reservation; fails
+    /// with `SpawnError::Reserved` when someone else holds it
+    pub async fn reserve(&self, path: &str, owner: &str, reason: &str, ttl: Duration) -> Result<FileReservation> {
+        let now = Utc::now();
+        let reservation = FileReservation {
+            path: normalize(path),
+            owner: owner.to_string(),
+            reason: reason.to_string(),
+            created_at: now,
+            expires_at: now + chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX),
+        };
+        match self.db.reserve_file(&reservation).await? {
+            None => Ok(reservation),
+            Some(current) => Err(SpawnError::Reserved { path: current.path, owner: current.owner }),
+        }
+    }
+    
+    /// Mark files a mission changed as pending until it ends
+    pub async fn reserve_for_mission(&self, paths: &[String], mission_id: &MissionId, tool: &str) -> Result<()> {
+        let owner = mission_owner(mission_id);
+        for path in paths {
+            self.reserve(path, &owner, &format!("changed by {}", tool), MISSION_RESERVATION_TTL).await?;
+        }
+        Ok(())
+    }
+    
+    pub async fn release(&self, path: &str, owner: &str) -> Result<()> {
+        self.db.release_file(&normalize(path), owner).await
+    }
+    
+    /// Drop all of `owner`'s reservations, e.g. when its mission ends
+    pub async fn release_all(&self, owner: &str) -> Result<u64> {
+        self.db.release_files_of(owner).await
+    }
+    
+    /// Whether `writer` may write `path`: yes if it is unreserved, reserved
+    /// by `writer`, or `force` is set; otherwise `SpawnError::Reserved`
+    pub async fn check(&self, path: &str, writer: &str, force: bool) -> Result<()> {
+        if force {
+            return Ok(());
+        }
+        match self.db.file_reservation(&normalize(path)).await? {
+            Some(current) if current.owner != writer => {
+                Err(SpawnError::Reserved { path: current.path, owner: current.owner })
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    pub async fn list(&self) -> Result<Vec<FileReservation>> {
+        self.db.list_file_reservations().await
+    }
+}
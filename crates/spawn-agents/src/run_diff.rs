@@ -0,0 +1,195 @@
+//! Run comparison
+//!
+//! Lines two mission runs up side by side - typically a mission and its rerun
+//! after a prompt or model tweak - and diffs their plans, tool calls and the
+//! files each left changed.
+
+use serde::Serialize;
+use spawn_core::{LogStore, Mission, MissionId, MissionStatus, Result};
+use std::collections::BTreeMap;
+
+use crate::memory::Database;
+use crate::orchestrator::parse_tool_call;
+use crate::snapshots::{ChangeKind, WorkspaceSnapshots};
+
+/// Longest patch included per file
+const MAX_PATCH_CHARS: usize = 20_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub mission_id: MissionId,
+    pub goal: String,
+    pub status: MissionStatus,
+    /// Model of the first step, if one ran
+    pub model: Option<String>,
+    pub steps: i64,
+}
+
+/// One line of a sequence diff
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", content = "line", rename_all = "snake_case")]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// How each run changed the file; `None` if it left it alone
+    pub base: Option<ChangeKind>,
+    pub other: Option<ChangeKind>,
+    /// Both runs left the file with the same content
+    pub identical: bool,
+    /// From the base run's version of the file to the other's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDiff {
+    pub base: RunSummary,
+    pub other: RunSummary,
+    pub plan: Vec<DiffLine>,
+    pub tool_calls: Vec<DiffLine>,
+    /// `None` unless both runs touched the workspace and recorded their result
+    pub files: Option<Vec<FileDiff>>,
+}
+
+/// Compare `other` against `base`
+pub async fn compare(
+    db: &Database,
+    logs: &dyn LogStore,
+    snapshots: &WorkspaceSnapshots,
+    base: &Mission,
+    other: &Mission,
+) -> Result<RunDiff> {
+    Ok(RunDiff {
+        base: summary(db, base).await?,
+        other: summary(db, other).await?,
+        plan: diff_lines(&plan(db, base).await?, &plan(db, other).await?),
+        tool_calls: diff_lines(&tool_calls(logs, &base.id).await?, &tool_calls(logs, &other.id).await?),
+        files: files(snapshots, &base.id, &other.id).await?,
+    })
+}
+
+async fn summary(db: &Database, mission: &Mission) -> Result<RunSummary> {
+    Ok(RunSummary {
+        mission_id: mission.id.clone(),
+        goal: mission.goal.clone(),
+        status: mission.status.clone(),
+        model: db.get_step_context(&mission.id, 0).await?.map(|c| c.model),
+        steps: db.count_step_contexts(&mission.id).await?,
+    })
+}
+
+/// The steps the mission was started with, else its task titles
+async fn plan(db: &Database, mission: &Mission) -> Result<Vec<String>> {
+    if !mission.context.steps.is_empty() {
+        return Ok(mission.context.steps.clone());
+    }
+    Ok(db.list_tasks(&mission.id).await?.into_iter().map(|t| t.title).collect())
+}
+
+/// Each tool call as `name {args}`, in order
+async fn tool_calls(logs: &dyn LogStore, mission_id: &MissionId) -> Result<Vec<String>> {
+    Ok(logs.list(mission_id).await?
+        .iter()
+        .filter(|e| e.agent == "assistant")
+        .filter_map(|e| parse_tool_call(&e.content))
+        .map(|(name, args)| format!("{} {}", name, args))
+        .collect())
+}
+
+async fn files(snapshots: &WorkspaceSnapshots, base: &str, other: &str) -> Result<Option<Vec<FileDiff>>> {
+    let (Some(base_changes), Some(other_changes)) = (snapshots.changes(base).await?, snapshots.changes(other).await?) else {
+        return Ok(None);
+    };
+    let (Some(base_result), Some(other_result)) = (snapshots.get_result(base).await, snapshots.get_result(other).await) else {
+        return Ok(None);
+    };
+
+    let mut paths: BTreeMap<String, (Option<ChangeKind>, Option<ChangeKind>)> = BTreeMap::new();
+    for change in base_changes {
+        paths.entry(change.path).or_default().0 = Some(change.kind);
+    }
+    for change in other_changes {
+        paths.entry(change.path).or_default().1 = Some(change.kind);
+    }
+
+    let mut diffs = Vec::with_capacity(paths.len());
+    for (path, (base, other)) in paths {
+        let identical = snapshots.same_file(&base_result, &other_result, &path).await;
+        let patch = if identical {
+            None
+        } else {
+            let mut patch = snapshots.file_patch(&base_result, &other_result, &path).await?;
+            if patch.len() > MAX_PATCH_CHARS {
+                let mut cut = MAX_PATCH_CHARS;
+                while !patch.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                patch.truncate(cut);
+                patch.push_str("\n... (truncated)");
+            }
+            Some(patch)
+        };
+        diffs.push(FileDiff { path, base, other, identical, patch });
+    }
+    Ok(Some(diffs))
+}
+
+/// Line diff of two sequences by longest common subsequence
+pub fn diff_lines(a: &[String], b: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j]: common subsequence length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::with_capacity(n.max(m));
+    while i < n && j < m {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Same(a[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(a[i].clone()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j].clone()));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().cloned().map(DiffLine::Removed));
+    lines.extend(b[j..].iter().cloned().map(DiffLine::Added));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff_lines_keeps_common_subsequence() {
+        let a = strings(&["read", "edit a", "test"]);
+        let b = strings(&["read", "edit b", "test", "commit"]);
+        assert_eq!(diff_lines(&a, &b), vec![
+            DiffLine::Same("read".into()),
+            DiffLine::Removed("edit a".into()),
+            DiffLine::Added("edit b".into()),
+            DiffLine::Same("test".into()),
+            DiffLine::Added("commit".into()),
+        ]);
+        assert!(diff_lines(&[], &[]).is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! Mission scheduler
+//!
+//! Caps how many missions run at once on this node. The rest wait in a queue
+//! ordered by priority, then arrival. With preemption on, a waiting mission can
+//! ask a lower-priority running one to pause at its next step boundary and hand
+//! over its slot; the paused mission rejoins the queue ahead of later arrivals
+//! of its priority.
+
+use serde::Serialize;
+use spawn_core::{CancellationToken, MissionId, MissionPriority, Result, SchedulerConfig, SpawnError};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+/// A mission holding or waiting for a slot
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub mission_id: MissionId,
+    pub priority: MissionPriority,
+    /// Set on a running mission that has been asked to pause for this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preempted_by: Option<MissionId>,
+}
+
+/// Running and waiting missions, in scheduling order
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub max_concurrent: usize,
+    pub preemption: bool,
+    pub running: Vec<QueueEntry>,
+    pub waiting: Vec<QueueEntry>,
+}
+
+struct Slot {
+    priority: MissionPriority,
+    seq: u64,
+}
+
+struct Waiter {
+    mission_id: MissionId,
+    priority: MissionPriority,
+    seq: u64,
+    wake: oneshot::Sender<()>,
+}
+
+impl Waiter {
+    fn order(&self) -> (Reverse<MissionPriority>, u64) {
+        (Reverse(self.priority), self.seq)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    running: HashMap<MissionId, Slot>,
+    /// Highest priority first, then by arrival
+    waiting: Vec<Waiter>,
+    /// Running missions asked to pause, and the waiting mission they pause for
+    yield_to: HashMap<MissionId, MissionId>,
+    next_seq: u64,
+}
+
+pub struct MissionScheduler {
+    config: SchedulerConfig,
+    state: Mutex<State>,
+}
+
+impl MissionScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self { config, state: Mutex::new(State::default()) }
+    }
+
+    /// Join the queue; the ticket resolves once the mission has a slot
+    pub fn enqueue(&self, mission_id: &str, priority: MissionPriority) -> Ticket<'_> {
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        self.push(&mut state, mission_id, priority, seq)
+    }
+
+    fn push(&self, state: &mut State, mission_id: &str, priority: MissionPriority, seq: u64) -> Ticket<'_> {
+        let (wake, rx) = oneshot::channel();
+        let waiter = Waiter { mission_id: mission_id.into(), priority, seq, wake };
+        let at = state.waiting.partition_point(|w| w.order() <= waiter.order());
+        state.waiting.insert(at, waiter);
+        self.rebalance(state);
+
+        let position = state.waiting.iter().position(|w| w.mission_id == mission_id);
+        Ticket { scheduler: self, mission_id: Some(mission_id.into()), position, rx: Some(rx) }
+    }
+
+    /// At a step boundary: if a higher-priority mission asked for this one's
+    /// slot, give it up and queue again. Returns who it yielded to.
+    pub fn preempt(&self, mission_id: &str) -> Option<(MissionId, Ticket<'_>)> {
+        let mut state = self.state.lock().unwrap();
+        let by = state.yield_to.remove(mission_id)?;
+        let slot = state.running.remove(mission_id)?;
+        Some((by, self.push(&mut state, mission_id, slot.priority, slot.seq)))
+    }
+
+    /// Free a finished mission's slot (or its place in the queue)
+    pub fn release(&self, mission_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.running.remove(mission_id);
+        state.waiting.retain(|w| w.mission_id != mission_id);
+        state.yield_to.retain(|victim, by| victim != mission_id && by != mission_id);
+        self.rebalance(&mut state);
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let state = self.state.lock().unwrap();
+        let mut running: Vec<(&MissionId, &Slot)> = state.running.iter().collect();
+        running.sort_by_key(|(_, slot)| (Reverse(slot.priority), slot.seq));
+        QueueSnapshot {
+            max_concurrent: self.config.max_concurrent,
+            preemption: self.config.preemption,
+            running: running.into_iter()
+                .map(|(id, slot)| QueueEntry {
+                    mission_id: id.clone(),
+                    priority: slot.priority,
+                    preempted_by: state.yield_to.get(id).cloned(),
+                })
+                .collect(),
+            waiting: state.waiting.iter()
+                .map(|w| QueueEntry { mission_id: w.mission_id.clone(), priority: w.priority, preempted_by: None })
+                .collect(),
+        }
+    }
+
+    /// Fill free slots from the front of the queue, then, if preemption is on,
+    /// ask lower-priority running missions to make room for those still waiting
+    fn rebalance(&self, state: &mut State) {
+        while state.running.len() < self.config.max_concurrent && !state.waiting.is_empty() {
+            let waiter = state.waiting.remove(0);
+            // It got a slot without anyone pausing for it
+            state.yield_to.retain(|_, by| *by != waiter.mission_id);
+            state.running.insert(waiter.mission_id, Slot { priority: waiter.priority, seq: waiter.seq });
+            let _ = waiter.wake.send(());
+        }
+
+        if !self.config.preemption {
+            return;
+        }
+        for waiter in &state.waiting {
+            if state.yield_to.values().any(|by| *by == waiter.mission_id) {
+                continue;
+            }
+            // The lowest-priority, most recently started mission pauses first
+            let victim = state.running.iter()
+                .filter(|(id, slot)| slot.priority < waiter.priority && !state.yield_to.contains_key(*id))
+                .min_by_key(|(_, slot)| (slot.priority, Reverse(slot.seq)))
+                .map(|(id, _)| id.clone());
+            match victim {
+                Some(victim) => {
+                    state.yield_to.insert(victim, waiter.mission_id.clone());
+                }
+                // Nobody further back outranks anything still running either
+                None => break,
+            }
+        }
+    }
+}
+
+/// A mission's place in the queue. Dropping it before it resolves gives the
+/// place up.
+pub struct Ticket<'a> {
+    scheduler: &'a MissionScheduler,
+    /// Taken once the mission has its slot; the caller releases it
+    mission_id: Option<MissionId>,
+    position: Option<usize>,
+    rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Ticket<'_> {
+    /// Missions ahead in the queue, or `None` if a slot was free
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Wait for the slot
+    pub async fn wait(mut self, cancel: &CancellationToken) -> Result<()> {
+        if self.position.is_some() {
+            let rx = self.rx.take().expect("ticket waited on twice");
+            match cancel.run_until_cancelled(rx).await {
+                Some(Ok(())) => {}
+                _ => return Err(SpawnError::Cancelled),
+            }
+        }
+        self.rx = None;
+        self.mission_id = None;
+        Ok(())
+    }
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        if let Some(mission_id) = self.mission_id.take() {
+            self.scheduler.release(&mission_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(max_concurrent: usize, preemption: bool) -> MissionScheduler {
+        MissionScheduler::new(SchedulerConfig { max_concurrent, preemption })
+    }
+
+    #[test]
+    fn test_queue_orders_by_priority_then_arrival() {
+        let s = scheduler(1, false);
+        let a = s.enqueue("a", MissionPriority::Normal);
+        let b = s.enqueue("b", MissionPriority::Low);
+        let c = s.enqueue("c", MissionPriority::High);
+        let d = s.enqueue("d", MissionPriority::High);
+        assert_eq!(a.position(), None);
+        assert_eq!((b.position(), c.position(), d.position()), (Some(0), Some(0), Some(1)));
+
+        let waiting: Vec<_> = s.snapshot().waiting.into_iter().map(|e| e.mission_id).collect();
+        assert_eq!(waiting, ["c", "d", "b"]);
+        assert!(s.preempt("a").is_none());
+
+        s.release("a");
+        let running: Vec<_> = s.snapshot().running.into_iter().map(|e| e.mission_id).collect();
+        assert_eq!(running, ["c"]);
+        drop((b, c, d));
+    }
+
+    #[test]
+    fn test_preemption_pauses_lowest_priority_and_requeues_it_first() {
+        let s = scheduler(2, true);
+        let _a = s.enqueue("a", MissionPriority::Low);
+        let _b = s.enqueue("b", MissionPriority::Normal);
+        let _late = s.enqueue("late", MissionPriority::Low);
+        let _u = s.enqueue("u", MissionPriority::Urgent);
+
+        assert!(s.preempt("b").is_none());
+        let (by, ticket) = s.preempt("a").expect("low-priority mission should yield");
+        assert_eq!(by, "u");
+        // `u` took the slot; `a` keeps its original place ahead of `late`
+        assert_eq!(ticket.position(), Some(0));
+        let running: Vec<_> = s.snapshot().running.into_iter().map(|e| e.mission_id).collect();
+        assert_eq!(running, ["u", "b"]);
+    }
+}
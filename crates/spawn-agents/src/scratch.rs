@@ -0,0 +1,162 @@
+//! Per-mission scratch directories
+//!
+//! Each mission gets a directory of its own under the scratch root for
+//! intermediate artifacts, so they stay out of the workspace. While one of
+//! its tool calls runs, the directory is the task's `MISSION_TMP`: processes
+//! tools start get it as `$MISSION_TMP`. A mission whose directory outgrows
+//! the quota is stopped. Directories stay after the mission ends, for
+//! inspection, until retention removes them.
+
+use spawn_core::{MissionId, MissionTmpConfig, Result, SpawnError};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::process::Command;
+use tracing::info;
+
+/// Environment variable naming the mission's scratch directory
+pub const ENV_VAR: &str = "MISSION_TMP";
+
+tokio::task_local! {
+    static MISSION_TMP: PathBuf;
+}
+
+/// Run `f` with `dir` as the current mission's scratch directory
+pub async fn scope<F: Future>(dir: PathBuf, f: F) -> F::Output {
+    MISSION_TMP.scope(dir, f).await
+}
+
+/// The scratch directory of the mission whose tool call is running, if any
+pub fn current() -> Option<PathBuf> {
+    MISSION_TMP.try_with(|dir| dir.clone()).ok()
+}
+
+/// Give a command the current mission's `$MISSION_TMP`
+pub fn apply_env(cmd: &mut Command) -> &mut Command {
+    if let Some(dir) = current() {
+        cmd.env(ENV_VAR, dir);
+    }
+    cmd
+}
+
+/// `$MISSION_TMP` and `${MISSION_TMP}` in `arg` replaced by the current
+/// mission's scratch directory, for commands run without a shell
+pub fn expand(arg: &str) -> String {
+    match current() {
+        Some(dir) => {
+            let dir = dir.to_string_lossy();
+            arg.replace("${MISSION_TMP}", &dir).replace("$MISSION_TMP", &dir)
+        }
+        None => arg.to_string(),
+    }
+}
+
+pub struct ScratchDirs {
+    root: PathBuf,
+    quota_bytes: u64,
+    retention: Duration,
+}
+
+impl ScratchDirs {
+    pub fn new(config: &MissionTmpConfig) -> Self {
+        Self {
+            root: config.dir.clone().unwrap_or_else(|| std::env::temp_dir().join("spawn-missions")),
+            quota_bytes: config.quota_mb.saturating_mul(1024 * 1024),
+            retention: Duration::from_secs(config.retention_hours.saturating_mul(3600)),
+        }
+    }
+
+    pub fn path(&self, mission_id: &MissionId) -> PathBuf {
+        self.root.join(mission_id.as_str())
+    }
+
+    /// The mission's directory, created if it doesn't exist yet
+    pub async fn create(&self, mission_id: &MissionId) -> Result<PathBuf> {
+        let dir = self.path(mission_id);
+        tokio::fs::create_dir_all(&dir).await
+            .map_err(|e| SpawnError::Internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+        Ok(dir)
+    }
+
+    /// Fail with `BudgetExceeded` if the mission's directory is over quota
+    pub async fn check_quota(&self, mission_id: &MissionId) -> Result<()> {
+        let dir = self.path(mission_id);
+        let used = tokio::task::spawn_blocking(move || dir_size(&dir)).await
+            .map_err(|e| SpawnError::Internal(e.to_string()))?;
+        if used > self.quota_bytes {
+            return Err(SpawnError::BudgetExceeded(format!(
+                "${} holds {} MB, over its {} MB quota", ENV_VAR, used / (1024 * 1024), self.quota_bytes / (1024 * 1024)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Remove mission directories untouched for longer than the retention
+    /// period, returning how many went
+    pub async fn prune(&self) -> Result<usize> {
+        let (root, retention) = (self.root.clone(), self.retention);
+        let removed = tokio::task::spawn_blocking(move || prune_older_than(&root, retention)).await
+            .map_err(|e| SpawnError::Internal(e.to_string()))?;
+        if removed > 0 {
+            info!(removed, "Removed expired mission scratch directories");
+        }
+        Ok(removed)
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Newest modification time of anything in `dir`
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .max()
+}
+
+fn prune_older_than(root: &Path, retention: Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return 0;
+    };
+    let cutoff = SystemTime::now() - retention;
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() && last_modified(path).is_none_or(|modified| modified < cutoff))
+        .filter(|path| std::fs::remove_dir_all(path).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_quota_and_task_local_env() {
+        let root = std::env::temp_dir().join(format!("spawn-scratch-{}", uuid::Uuid::new_v4()));
+        let scratch = ScratchDirs::new(&MissionTmpConfig { dir: Some(root.clone()), quota_mb: 1, retention_hours: 24 });
+        let mission = MissionId::from("m1");
+        let dir = scratch.create(&mission).await.unwrap();
+
+        let expanded = scope(dir.clone(), async { expand("$MISSION_TMP/out.txt") }).await;
+        assert_eq!(expanded, format!("{}/out.txt", dir.display()));
+        assert_eq!(expand("$MISSION_TMP"), "$MISSION_TMP");
+
+        std::fs::write(dir.join("small"), vec![0u8; 1024]).unwrap();
+        assert!(scratch.check_quota(&mission).await.is_ok());
+        std::fs::write(dir.join("big"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+        assert!(matches!(scratch.check_quota(&mission).await, Err(SpawnError::BudgetExceeded(_))));
+
+        assert_eq!(prune_older_than(&root, Duration::ZERO), 1);
+        assert!(!dir.exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+//! Workspace snapshots for mission rollback and run comparison
+//!
+//! Before a mission first touches the workspace, the working tree (tracked
+//! and untracked, minus ignored files) is committed to a private ref
+//! `refs/spawn/snapshots/<mission_id>` using a temporary index, so neither
+//! HEAD, the branch nor the user's staging area are affected. When the mission
+//! ends, the tree it left behind is committed the same way under
+//! `refs/spawn/results/<mission_id>`, so runs can be compared.
+
+use serde::Serialize;
+use spawn_core::{Result, SpawnError};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::info;
+
+const REF_PREFIX: &str = "refs/spawn/snapshots";
+const RESULT_REF_PREFIX: &str = "refs/spawn/results";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub mission_id: String,
+    pub commit: String,
+    /// Files that differed from the snapshot and were restored
+    pub restored: Vec<String>,
+    /// Files created after the snapshot and removed
+    pub removed: Vec<String>,
+}
+
+/// How a mission changed a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A file a mission changed, between its snapshot and its result
+#[derive(Debug, Clone, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+pub struct WorkspaceSnapshots {
+    root: PathBuf,
+}
+
+impl WorkspaceSnapshots {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn ref_name(mission_id: &str) -> String {
+        format!("{}/{}", REF_PREFIX, mission_id)
+    }
+
+    fn result_ref_name(mission_id: &str) -> String {
+        format!("{}/{}", RESULT_REF_PREFIX, mission_id)
+    }
+
+    fn temp_index(&self, mission_id: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("spawn-snapshot-{}.index", mission_id))
+    }
+
+    async fn git(&self, args: &[&str], index: Option<&Path>) -> Result<String> {
+        let mut cmd = Command::new("git");
+        cmd.args(args)
+            .current_dir(&self.root)
+            .env("GIT_AUTHOR_NAME", "spawn")
+            .env("GIT_AUTHOR_EMAIL", "spawn@localhost")
+            .env("GIT_COMMITTER_NAME", "spawn")
+            .env("GIT_COMMITTER_EMAIL", "spawn@localhost");
+        if let Some(index) = index {
+            cmd.env("GIT_INDEX_FILE", index);
+        }
+
+        let output = cmd.output().await
+            .map_err(|e| SpawnError::ToolError(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(SpawnError::ToolError(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    async fn resolve(&self, ref_name: &str) -> Option<String> {
+        let spec = format!("{}^{{commit}}", ref_name);
+        self.git(&["rev-parse", "--verify", "--quiet", &spec], None).await.ok()
+    }
+
+    /// Snapshot commit for a mission, if one was taken
+    pub async fn get(&self, mission_id: &str) -> Option<String> {
+        self.resolve(&Self::ref_name(mission_id)).await
+    }
+
+    /// Commit of the tree a mission left behind, if it was recorded
+    pub async fn get_result(&self, mission_id: &str) -> Option<String> {
+        self.resolve(&Self::result_ref_name(mission_id)).await
+    }
+
+    /// Commit the working tree under `ref_name` without touching HEAD or the index
+    async fn commit_worktree(&self, mission_id: &str, ref_name: &str, message: &str) -> Result<String> {
+        let index = self.temp_index(mission_id);
+        let result = async {
+            self.git(&["add", "-A"], Some(&index)).await?;
+            let tree = self.git(&["write-tree"], Some(&index)).await?;
+
+            let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+            let head = self.git(&["rev-parse", "--verify", "--quiet", "HEAD"], None).await.ok();
+            if let Some(head) = head.as_deref() {
+                args.extend(["-p", head]);
+            }
+            let commit = self.git(&args, None).await?;
+            self.git(&["update-ref", ref_name, &commit], None).await?;
+            Ok(commit)
+        }.await;
+        let _ = tokio::fs::remove_file(&index).await;
+        result
+    }
+
+    /// Snapshot the workspace for a mission; returns the snapshot commit.
+    /// Idempotent: an existing snapshot is kept.
+    pub async fn snapshot(&self, mission_id: &str) -> Result<String> {
+        if let Some(commit) = self.get(mission_id).await {
+            return Ok(commit);
+        }
+        self.git(&["rev-parse", "--git-dir"], None).await
+            .map_err(|_| SpawnError::ToolError("Workspace is not a git repository".into()))?;
+
+        let message = format!("spawn: snapshot before mission {}", mission_id);
+        let result = self.commit_worktree(mission_id, &Self::ref_name(mission_id), &message).await;
+
+        if let Ok(commit) = &result {
+            info!(mission_id, commit = %commit, "Workspace snapshot taken");
+        }
+        result
+    }
+
+    /// Record the tree a mission left behind. Only missions with a snapshot
+    /// get one, so `changes` always has both ends.
+    pub async fn record_result(&self, mission_id: &str) -> Result<Option<String>> {
+        if self.get(mission_id).await.is_none() {
+            return Ok(None);
+        }
+        let message = format!("spawn: result of mission {}", mission_id);
+        self.commit_worktree(mission_id, &Self::result_ref_name(mission_id), &message).await.map(Some)
+    }
+
+    /// Files a mission changed, or `None` without both a snapshot and a result
+    pub async fn changes(&self, mission_id: &str) -> Result<Option<Vec<FileChange>>> {
+        let (Some(before), Some(after)) = (self.get(mission_id).await, self.get_result(mission_id).await) else {
+            return Ok(None);
+        };
+        let output = self.git(&["diff", "--name-status", "--no-renames", &before, &after], None).await?;
+        let changes = output.lines()
+            .filter_map(|line| {
+                let (status, path) = line.split_once('\t')?;
+                let kind = match status {
+                    "A" => ChangeKind::Added,
+                    "D" => ChangeKind::Deleted,
+                    _ => ChangeKind::Modified,
+                };
+                Some(FileChange { path: path.to_string(), kind })
+            })
+            .collect();
+        Ok(Some(changes))
+    }
+
+    /// Whether `path` has the same content (or is equally absent) in two commits
+    pub async fn same_file(&self, a: &str, b: &str, path: &str) -> bool {
+        let blob = |commit: &str| format!("{}:{}", commit, path);
+        let (a, b) = (
+            self.git(&["rev-parse", "--verify", "--quiet", &blob(a)], None).await.ok(),
+            self.git(&["rev-parse", "--verify", "--quiet", &blob(b)], None).await.ok(),
+        );
+        a == b
+    }
+
+    /// Unified diff of `path` from commit `a` to commit `b`
+    pub async fn file_patch(&self, a: &str, b: &str, path: &str) -> Result<String> {
+        self.git(&["diff", a, b, "--", path], None).await
+    }
+
+    /// Restore the working tree to a mission's snapshot. HEAD and the index
+    /// are left alone, so commits made by the mission remain in history.
+    pub async fn restore(&self, mission_id: &str) -> Result<RestoreReport> {
+        let commit = self.get(mission_id).await
+            .ok_or_else(|| SpawnError::ToolError(format!("No snapshot for mission '{}'", mission_id)))?;
+
+        let index = self.temp_index(mission_id);
+        let result = async {
+            // Stage the current tree in the temporary index and diff it against the snapshot
+            self.git(&["add", "-A"], Some(&index)).await?;
+            let added = self.git(&["diff-index", "--cached", "--name-only", "--diff-filter=A", &commit], Some(&index)).await?;
+            let changed = self.git(&["diff-index", "--cached", "--name-only", "--diff-filter=MDT", &commit], Some(&index)).await?;
+
+            let removed: Vec<String> = added.lines().map(String::from).collect();
+            for file in &removed {
+                let _ = tokio::fs::remove_file(self.root.join(file)).await;
+            }
+
+            self.git(&["read-tree", &commit], Some(&index)).await?;
+            self.git(&["checkout-index", "--all", "--force"], Some(&index)).await?;
+
+            Ok(RestoreReport {
+                mission_id: mission_id.to_string(),
+                commit: commit.clone(),
+                restored: changed.lines().map(String::from).collect(),
+                removed,
+            })
+        }.await;
+        let _ = tokio::fs::remove_file(&index).await;
+
+        if let Ok(report) = &result {
+            info!(
+                mission_id,
+                restored = report.restored.len(),
+                removed = report.removed.len(),
+                "Workspace rolled back"
+            );
+        }
+        result
+    }
+
+    /// Drop a mission's snapshot and result refs
+    pub async fn discard(&self, mission_id: &str) -> Result<()> {
+        self.git(&["update-ref", "-d", &Self::ref_name(mission_id)], None).await?;
+        if self.get_result(mission_id).await.is_some() {
+            self.git(&["update-ref", "-d", &Self::result_ref_name(mission_id)], None).await?;
+        }
+        Ok(())
+    }
+}
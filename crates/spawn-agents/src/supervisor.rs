@@ -0,0 +1,85 @@
+//! Mission timeouts
+//!
+//! A supervisor task runs beside each mission and watches its wall-clock
+//! time. At the soft timeout it asks the mission to wrap up, which the agent
+//! loop turns into a "summarize progress and stop" prompt at the next step;
+//! at the hard timeout it cancels the mission, so in-flight model calls and
+//! tools are aborted and the usual cleanup runs.
+
+use spawn_core::{Budget, CancellationToken};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+
+/// Sent to the model once the soft timeout has passed
+pub const WRAP_UP_PROMPT: &str = "Time is almost up. Stop starting new work: summarize what you have done and what remains, then finish with DONE: and that summary.";
+
+pub struct Supervisor {
+    wrap_up: CancellationToken,
+    timed_out: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl Supervisor {
+    /// Start timing a mission now; `cancel` is its cancellation token
+    pub fn start(budget: &Budget, cancel: &CancellationToken) -> Self {
+        let start = Instant::now();
+        let soft = budget.soft_timeout_secs.map(|secs| start + Duration::from_secs(secs));
+        let hard = budget.hard_timeout_secs.map(|secs| start + Duration::from_secs(secs));
+        let (wrap_up, timed_out) = (CancellationToken::new(), Arc::new(AtomicBool::new(false)));
+
+        let task = tokio::spawn({
+            let (wrap_up, timed_out, cancel) = (wrap_up.clone(), timed_out.clone(), cancel.clone());
+            async move {
+                // A soft timeout at or after the hard one would never be seen
+                if let Some(soft) = soft.filter(|soft| hard.is_none_or(|hard| *soft < hard)) {
+                    sleep_until(soft).await;
+                    wrap_up.cancel();
+                }
+                if let Some(hard) = hard {
+                    sleep_until(hard).await;
+                    timed_out.store(true, Ordering::SeqCst);
+                    cancel.cancel();
+                }
+            }
+        });
+        Self { wrap_up, timed_out, task }
+    }
+
+    /// The soft timeout has passed
+    pub fn wrap_up_requested(&self) -> bool {
+        self.wrap_up.is_cancelled()
+    }
+
+    /// The hard timeout cancelled the mission
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_soft_then_hard_timeout() {
+        let cancel = CancellationToken::new();
+        let budget = Budget { soft_timeout_secs: Some(60), hard_timeout_secs: Some(120), ..Default::default() };
+        let supervisor = Supervisor::start(&budget, &cancel);
+
+        tokio::time::sleep(Duration::from_secs(90)).await;
+        assert!(supervisor.wrap_up_requested());
+        assert!(!cancel.is_cancelled());
+
+        cancel.cancelled().await;
+        assert!(supervisor.timed_out());
+    }
+}
@@ -0,0 +1,132 @@
+//! Differential workspace sync
+//!
+//! A frontend keeping its own copy of the workspace sends a manifest of
+//! `path -> sha256` for what it holds and gets back only what differs: files
+//! that are new or changed, with their content, and paths that are gone.
+//! Batches are capped in size; a truncated batch is followed up by sending
+//! the updated manifest again. Binary and oversized files come without
+//! content, to be fetched through the files API.
+
+use crate::ignore::IgnoreRules;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Files larger than this are listed but their content is left out
+pub const MAX_INLINE_BYTES: u64 = 1024 * 1024;
+/// Default cap on inlined content per batch
+pub const DEFAULT_BATCH_BYTES: u64 = 4 * 1024 * 1024;
+/// Upper bound on files compared per request
+const MAX_FILES: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncedFile {
+    /// Workspace-relative, `/`-separated
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    /// Left out for binary and oversized files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncBatch {
+    pub changed: Vec<SyncedFile>,
+    /// Manifest paths no longer in the workspace
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    /// More changes are pending; sync again with the updated manifest
+    pub truncated: bool,
+}
+
+pub fn hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Compare the workspace at `root` with `manifest`, inlining at most
+/// `max_batch_bytes` of content
+pub fn diff(root: &Path, manifest: &HashMap<String, String>, max_batch_bytes: u64) -> SyncBatch {
+    let ignore = IgnoreRules::load(root);
+    let files = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !ignore.is_ignored(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .take(MAX_FILES);
+
+    // Sorted so a truncated batch is picked up where it left off
+    let mut present = BTreeMap::new();
+    for entry in files {
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        let path = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        present.insert(path, entry.into_path());
+    }
+
+    let mut batch = SyncBatch {
+        deleted: manifest.keys().filter(|path| !present.contains_key(*path)).cloned().collect(),
+        ..SyncBatch::default()
+    };
+    batch.deleted.sort();
+
+    let mut inlined = 0;
+    for (path, full_path) in present {
+        let Ok(bytes) = std::fs::read(&full_path) else {
+            continue; // removed or unreadable since the walk
+        };
+        let hash = hash(&bytes);
+        if manifest.get(&path) == Some(&hash) {
+            batch.unchanged += 1;
+            continue;
+        }
+        let size = bytes.len() as u64;
+        let content = (size <= MAX_INLINE_BYTES).then(|| String::from_utf8(bytes).ok()).flatten();
+        let content_bytes = content.as_ref().map_or(0, |c| c.len() as u64);
+        // Always send at least one file, however large the cap
+        if inlined + content_bytes > max_batch_bytes && !batch.changed.is_empty() {
+            batch.truncated = true;
+            break;
+        }
+        inlined += content_bytes;
+        batch.changed.push(SyncedFile { path, hash, size, content });
+    }
+    batch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_against_manifest() {
+        let root = std::env::temp_dir().join(format!("spawn-sync-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(root.join("README.md"), "hello").unwrap();
+        std::fs::write(root.join("logo.png"), [0xff, 0xfe, 0x00]).unwrap();
+        std::fs::create_dir_all(root.join("node_modules")).unwrap();
+        std::fs::write(root.join("node_modules/x.js"), "ignored").unwrap();
+
+        let manifest = HashMap::from([
+            ("src/main.rs".to_string(), hash(b"fn main() {}")),
+            ("README.md".to_string(), hash(b"old")),
+            ("gone.txt".to_string(), hash(b"gone")),
+        ]);
+        let batch = diff(&root, &manifest, DEFAULT_BATCH_BYTES);
+        assert_eq!(batch.unchanged, 1);
+        assert_eq!(batch.deleted, vec!["gone.txt"]);
+        let changed: Vec<_> = batch.changed.iter().map(|f| (f.path.as_str(), f.content.as_deref())).collect();
+        assert_eq!(changed, vec![("README.md", Some("hello")), ("logo.png", None)]);
+        assert!(!batch.truncated);
+
+        let first = diff(&root, &HashMap::new(), 1);
+        assert!(first.truncated);
+        assert_eq!(first.changed.len(), 1);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! Workspace templates
+//!
+//! A template is a directory (or a git repository holding one) with a
+//! `spawn-template.toml` manifest and a `files/` tree. Applying it copies the
+//! files into a workspace, writes the template's tool policy to
+//! `.spawn-policy.toml`, and hands back the missions it suggests running
+//! first. The policy keeps applying to every mission run in that workspace.
+//!
+//! ```toml
+//! name = "rust-service"
+//! description = "Axum service with CI"
+//!
+//! [policy]
+//! deny = ["git_push"]
+//!
+//! [[missions]]
+//! goal = "Add a health check endpoint"
+//! agent = "builder"
+//! budget = { max_steps = 20 }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use spawn_core::{Budget, CapabilitySet, Mission, MissionContext, Result, SpawnError};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use walkdir::WalkDir;
+
+pub const MANIFEST_FILE: &str = "spawn-template.toml";
+/// Copied into the workspace as-is
+pub const FILES_DIR: &str = "files";
+/// The tool policy, at the workspace root
+pub const POLICY_FILE: &str = ".spawn-policy.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub policy: WorkspacePolicy,
+    /// Suggested first missions, in order
+    #[serde(default)]
+    pub missions: Vec<TemplateMission>,
+}
+
+/// What missions in a workspace may do, on top of the server's own policy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacePolicy {
+    /// Capabilities no tool may use in this workspace
+    pub deny: CapabilitySet,
+}
+
+impl WorkspacePolicy {
+    /// The workspace's `.spawn-policy.toml`; an empty policy if it has none
+    pub fn load(workspace: &Path) -> Result<Self> {
+        match std::fs::read_to_string(workspace.join(POLICY_FILE)) {
+            Ok(text) => toml::from_str(&text).map_err(|e| SpawnError::Config(format!("{}: {}", POLICY_FILE, e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(SpawnError::Internal(format!("Failed to read {}: {}", POLICY_FILE, e))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateMission {
+    pub goal: String,
+    #[serde(default)]
+    pub agent: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub budget: Budget,
+}
+
+impl TemplateMission {
+    /// The mission, set to run in `workspace`
+    pub fn to_mission(&self, workspace: &Path) -> Mission {
+        let context = MissionContext {
+            workspace: Some(workspace.to_path_buf()),
+            agent: self.agent.clone(),
+            steps: self.steps.clone(),
+            tags: self.tags.clone(),
+            ..Default::default()
+        };
+        Mission::new(&self.goal).with_context(context).with_budget(self.budget)
+    }
+}
+
+/// What applying a template did, with paths relative to the workspace
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Applied {
+    pub written: Vec<String>,
+    /// Already in the workspace and left alone
+    pub skipped: Vec<String>,
+}
+
+/// A template ready to apply
+pub struct Template {
+    root: PathBuf,
+    pub manifest: TemplateManifest,
+    /// Our clone of a remote template, removed on drop
+    checkout: Option<PathBuf>,
+}
+
+impl Template {
+    /// Open a template directory, or clone a git URL (`https://`, `ssh://`,
+    /// `git@...`) to a temporary directory first
+    pub async fn fetch(source: &str) -> Result<Self> {
+        if !(source.contains("://") || source.starts_with("git@")) {
+            return Self::open(Path::new(source), None);
+        }
+        let checkout = std::env::temp_dir().join(format!("spawn-template-{}", uuid::Uuid::new_v4().simple()));
+        let output = Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", "--", source])
+            .arg(&checkout)
+            .output().await
+            .map_err(|e| SpawnError::ToolError(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            let _ = tokio::fs::remove_dir_all(&checkout).await;
+            return Err(SpawnError::ToolError(format!(
+                "Failed to clone template {}: {}", source, String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Self::open(&checkout, Some(checkout.clone()))
+    }
+
+    fn open(root: &Path, checkout: Option<PathBuf>) -> Result<Self> {
+        let text = std::fs::read_to_string(root.join(MANIFEST_FILE))
+            .map_err(|e| SpawnError::Config(format!("{} has no readable {}: {}", root.display(), MANIFEST_FILE, e)))?;
+        let manifest = toml::from_str(&text)
+            .map_err(|e| SpawnError::Config(format!("{}: {}", MANIFEST_FILE, e)))?;
+        Ok(Self { root: root.to_path_buf(), manifest, checkout })
+    }
+
+    /// Copy the template's files into `workspace` (created if missing) and
+    /// write its policy. Existing files are kept unless `overwrite` is set.
+    pub async fn apply(&self, workspace: &Path, overwrite: bool) -> Result<Applied> {
+        let io_error = |e: std::io::Error| SpawnError::Internal(format!("Failed to apply template {}: {}", self.manifest.name, e));
+        tokio::fs::create_dir_all(workspace).await.map_err(io_error)?;
+
+        let files = self.root.join(FILES_DIR);
+        let mut applied = Applied::default();
+        for entry in WalkDir::new(&files).min_depth(1).sort_by_file_name() {
+            let entry = entry.map_err(|e| SpawnError::Internal(format!("Failed to read template files: {}", e)))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&files).unwrap_or(entry.path());
+            let target = workspace.join(relative);
+            let name = relative.to_string_lossy().to_string();
+            if target.exists() && !overwrite {
+                applied.skipped.push(name);
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+            }
+            tokio::fs::copy(entry.path(), &target).await.map_err(io_error)?;
+            applied.written.push(name);
+        }
+
+        if self.manifest.policy != WorkspacePolicy::default() {
+            let policy = toml::to_string(&self.manifest.policy)
+                .map_err(|e| SpawnError::Internal(format!("Failed to write {}: {}", POLICY_FILE, e)))?;
+            tokio::fs::write(workspace.join(POLICY_FILE), policy).await.map_err(io_error)?;
+            applied.written.push(POLICY_FILE.to_string());
+        }
+        Ok(applied)
+    }
+
+    /// The template's suggested missions, set to run in `workspace`
+    pub fn missions(&self, workspace: &Path) -> Vec<Mission> {
+        self.manifest.missions.iter().map(|m| m.to_mission(workspace)).collect()
+    }
+}
+
+impl Drop for Template {
+    fn drop(&mut self) {
+        if let Some(checkout) = &self.checkout {
+            let _ = std::fs::remove_dir_all(checkout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply_template() {
+        let dir = std::env::temp_dir().join(format!("spawn-template-test-{}", uuid::Uuid::new_v4().simple()));
+        let (source, workspace) = (dir.join("template"), dir.join("workspace"));
+        std::fs::create_dir_all(source.join("files/src")).unwrap();
+        std::fs::write(source.join(MANIFEST_FILE), "name = \"t\"\n[policy]\ndeny = [\"git_push\"]\n[[missions]]\ngoal = \"Add CI\"\n").unwrap();
+        std::fs::write(source.join("files/src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(source.join("files/README.md"), "template\n").unwrap();
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("README.md"), "mine\n").unwrap();
+
+        let template = Template::fetch(source.to_str().unwrap()).await.unwrap();
+        let applied = template.apply(&workspace, false).await.unwrap();
+        assert_eq!(applied.written, vec!["src/main.rs", POLICY_FILE]);
+        assert_eq!(applied.skipped, vec!["README.md"]);
+        assert_eq!(std::fs::read_to_string(workspace.join("README.md")).unwrap(), "mine\n");
+        assert_eq!(WorkspacePolicy::load(&workspace).unwrap(), template.manifest.policy);
+        assert_eq!(template.missions(&workspace)[0].context.workspace.as_deref(), Some(workspace.as_path()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Terminals owned by missions
+//!
+//! Terminal sessions live in terminal-app. One created with a `mission_id`
+//! belongs to that mission: when the mission ends its sessions are closed
+//! and the last of their output is kept in the mission log.
+
+use serde::Deserialize;
+use spawn_core::{HealthCheck, MissionId, Result, SpawnError};
+
+/// Buffer lines kept from each reaped session
+pub const ARCHIVED_LINES: usize = 1000;
+
+/// A closed session and the output it left
+#[derive(Debug, Clone)]
+pub struct ArchivedTerminal {
+    pub name: String,
+    pub buffer: String,
+}
+
+impl ArchivedTerminal {
+    /// Mission log agent the buffer is filed under
+    pub fn log_agent(&self) -> String {
+        format!("terminal:{}", self.name)
+    }
+}
+
+#[derive(Deserialize)]
+struct SessionList {
+    terminals: Vec<SessionInfo>,
+}
+
+#[derive(Deserialize)]
+struct SessionInfo {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Buffer {
+    lines: Vec<String>,
+}
+
+/// terminal-app client for a mission's sessions
+pub struct MissionTerminals {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl MissionTerminals {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self { client: reqwest::Client::new(), base_url: base_url.into() }
+    }
+
+    /// Close every session the mission owns, returning their final buffers.
+    /// A session whose buffer can't be read is still closed.
+    pub async fn reap(&self, mission_id: &MissionId) -> Result<Vec<ArchivedTerminal>> {
+        let list = match self.client.get(format!("{}/api/terminals?mission_id={}", self.base_url, mission_id)).send().await {
+            Ok(response) => parse::<SessionList>(response).await?,
+            // terminal-app isn't running, so there is nothing to reap
+            Err(e) if e.is_connect() => return Ok(Vec::new()),
+            Err(e) => return Err(SpawnError::ToolError(format!("Terminal API unavailable: {}", e))),
+        };
+        let mut archived = Vec::with_capacity(list.terminals.len());
+        for session in list.terminals {
+            let buffer = self.get::<Buffer>(&format!("/api/terminals/{}/buffer?lines={}", session.id, ARCHIVED_LINES)).await
+                .map(|b| b.lines.join("\n"))
+                .unwrap_or_else(|e| format!("[buffer unavailable: {}]", e));
+            self.client.delete(format!("{}/api/terminals/{}", self.base_url, session.id))
+                .send().await
+                .map_err(|e| SpawnError::ToolError(format!("Failed to close terminal '{}': {}", session.name, e)))?;
+            archived.push(ArchivedTerminal { name: session.name, buffer });
+        }
+        Ok(archived)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let response = self.client.get(format!("{}{}", self.base_url, path)).send().await
+            .map_err(|e| SpawnError::ToolError(format!("Terminal API unavailable: {}", e)))?;
+        parse(response).await
+    }
+}
+
+/// terminal-app, which hosts the sessions
+#[async_trait::async_trait]
+impl HealthCheck for MissionTerminals {
+    fn name(&self) -> &str {
+        "terminals"
+    }
+
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        let list: SessionList = self.get("/api/terminals").await?;
+        Ok(Some(serde_json::json!({ "sessions": list.terminals.len() })))
+    }
+}
+
+async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    if !response.status().is_success() {
+        return Err(SpawnError::ToolError(format!("Terminal API returned {}", response.status())));
+    }
+    response.json().await
+        .map_err(|e| SpawnError::ToolError(format!("Invalid terminal API response: {}", e)))
+}
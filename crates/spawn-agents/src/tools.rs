@@ -1,11 +1,46 @@
 //! Tools - capabilities the agent can use
 
+pub mod cargo;
+pub mod clipboard;
+pub mod coverage;
+pub mod deps;
+pub mod image;
+pub mod lint;
+pub mod npm;
+pub mod process;
+
 use async_trait::async_trait;
-use spawn_core::{Result, SpawnError, Tool};
-use std::collections::HashMap;
-use std::process::Command;
+use serde::Serialize;
+use spawn_core::{CancellationToken, CapabilitySet, ProgressSender, Result, Service, SpawnError, Tool, ToolPermission};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::Command;
 use tracing::{info, warn};
 
+pub use cargo::CargoTool;
+pub use clipboard::ClipboardTool;
+pub use coverage::CoverageTool;
+pub use deps::DepsTool;
+pub use image::ImageGenerateTool;
+pub use lint::LintTool;
+pub use npm::NpmScriptTool;
+pub use process::ProcessTool;
+
+/// A registered tool, as described to clients
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema for the arguments
+    pub parameters: serde_json::Value,
+    pub capabilities: CapabilitySet,
+    /// Services it is withheld without
+    pub requires: Vec<Service>,
+}
+
 /// Registry of available tools
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn Tool>>,
@@ -29,17 +64,202 @@ impl ToolRegistry {
     }
     
     pub fn describe(&self) -> String {
+        self.describe_allowed(|_| true)
+    }
+    
+    /// Describe only the tools `allowed` accepts by name
+    pub fn describe_allowed(&self, allowed: impl Fn(&str) -> bool) -> String {
         self.tools.values()
+            .filter(|t| allowed(t.name()))
             .map(|t| format!("- {}: {}", t.name(), t.description()))
             .collect::<Vec<_>>()
             .join("\n")
     }
     
-    pub async fn execute(&self, name: &str, args: serde_json::Value) -> Result<serde_json::Value> {
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+    
+    /// Every tool, by name
+    pub fn list(&self) -> Vec<ToolInfo> {
+        let mut tools: Vec<ToolInfo> = self.tools.values()
+            .map(|t| ToolInfo {
+                name: t.name().to_string(),
+                description: t.description().to_string(),
+                parameters: t.parameters(),
+                capabilities: t.capabilities(),
+                requires: t.requires().to_vec(),
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+    
+    /// What a tool may do to the host (see `Tool::capabilities`); none for
+    /// unknown tools
+    pub fn capabilities(&self, name: &str) -> CapabilitySet {
+        self.tools.get(name).map(|t| t.capabilities()).unwrap_or_default()
+    }
+    
+    /// Services a tool can't work without (see `Tool::requires`); none for
+    /// unknown tools
+    pub fn requires(&self, name: &str) -> Vec<Service> {
+        self.tools.get(name).map(|t| t.requires().to_vec()).unwrap_or_default()
+    }
+    
+    /// Workspace locks a call needs (see `Tool::locks`); none for unknown tools
+    pub fn locks(&self, name: &str, args: &serde_json::Value) -> Vec<String> {
+        self.tools.get(name).map(|t| t.locks(args)).unwrap_or_default()
+    }
+    
+    /// Files a successful call wrote (see `Tool::written_files`)
+    pub fn written_files(&self, name: &str, result: &serde_json::Value) -> Vec<String> {
+        self.tools.get(name).map(|t| t.written_files(result)).unwrap_or_default()
+    }
+    
+    /// Run a tool; if `cancel` fires first the call is dropped (killing any
+    /// child process it spawned) and `SpawnError::Cancelled` returned
+    pub async fn execute(&self, name: &str, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value> {
+        let tool = self.tools.get(name)
+            .ok_or_else(|| SpawnError::ToolError(format!("Unknown tool: {}", name)))?;
+        
+        cancel.run_until_cancelled(tool.execute(args, cancel)).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+    
+    /// `execute`, with the tool's progress updates sent to `progress`
+    pub async fn execute_with_progress(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
         let tool = self.tools.get(name)
             .ok_or_else(|| SpawnError::ToolError(format!("Unknown tool: {}", name)))?;
         
-        tool.execute(args).await
+        cancel.run_until_cancelled(tool.execute_with_progress(args, progress, cancel)).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+}
+
+/// `path` joined onto `workspace`, or `None` when it is absolute or climbs
+/// out with `..`; `Path::starts_with` alone doesn't resolve `..`
+pub fn within_workspace(workspace: &Path, path: &str) -> Option<PathBuf> {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        .then(|| workspace.join(path))
+}
+
+/// Write a file, creating its directory. The content goes to a temporary
+/// file next to it that is then renamed over it, so a reader sees the old
+/// file or the new one, never a partly written one, and sees the new one
+/// as soon as this returns.
+pub async fn write_synced(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    tokio::fs::create_dir_all(parent).await?;
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let tmp = parent.join(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4()));
+    let written = async {
+        let mut file = tokio::fs::File::create(&tmp).await?;
+        file.write_all(data).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp, path).await
+    };
+    if let Err(e) = written.await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Files stamped at most; past this a workspace is only partly compared
+const MAX_STAMPED_FILES: usize = 50_000;
+
+/// Size and modification time of each workspace file outside the ignore
+/// rules, to find the files a tool changed when it can't name them itself
+/// (a shell command, a build script)
+pub struct FileStamps {
+    root: PathBuf,
+    files: HashMap<String, (u64, Option<SystemTime>)>,
+}
+
+impl FileStamps {
+    pub async fn take(root: &Path) -> Self {
+        let root = root.to_path_buf();
+        let files = tokio::task::spawn_blocking({
+            let root = root.clone();
+            move || stamp(&root)
+        }).await.unwrap_or_default();
+        Self { root, files }
+    }
+
+    /// Workspace-relative paths of the files added, modified or removed
+    /// since the stamps were taken, sorted
+    pub async fn changed(&self) -> Vec<String> {
+        let now = FileStamps::take(&self.root).await.files;
+        let changed: BTreeSet<&String> = now.iter()
+            .filter(|(path, stamp)| self.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path)
+            .chain(self.files.keys().filter(|path| !now.contains_key(*path)))
+            .collect();
+        changed.into_iter().cloned().collect()
+    }
+}
+
+fn stamp(root: &Path) -> HashMap<String, (u64, Option<SystemTime>)> {
+    let ignore = crate::ignore::IgnoreRules::load(root);
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !ignore.is_ignored(e.path(), e.file_type().is_dir()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .take(MAX_STAMPED_FILES)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let relative = entry.path().strip_prefix(root).ok()?;
+            let path = relative.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((path, (metadata.len(), metadata.modified().ok())))
+        })
+        .collect()
+}
+
+/// Run a command to completion like `Command::output`, reporting each line it
+/// prints as progress
+pub async fn output_with_progress(cmd: &mut Command, progress: &ProgressSender) -> std::io::Result<Output> {
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let (stdout, stderr, status) = tokio::try_join!(
+        forward_lines(child.stdout.take(), progress),
+        forward_lines(child.stderr.take(), progress),
+        child.wait(),
+    )?;
+    Ok(Output { status, stdout, stderr })
+}
+
+/// Collect a stream, sending each non-blank line to `progress`
+async fn forward_lines(stream: Option<impl AsyncRead + Unpin>, progress: &ProgressSender) -> std::io::Result<Vec<u8>> {
+    let Some(stream) = stream else {
+        return Ok(Vec::new());
+    };
+    let mut reader = BufReader::new(stream);
+    let mut collected = Vec::new();
+    loop {
+        let start = collected.len();
+        if reader.read_until(b'\n', &mut collected).await? == 0 {
+            return Ok(collected);
+        }
+        let line = String::from_utf8_lossy(&collected[start..]);
+        if !line.trim().is_empty() {
+            progress.message(line.trim_end());
+        }
     }
 }
 
@@ -62,6 +282,10 @@ impl Tool for EchoTool {
     
     fn description(&self) -> &str { "Echo back the input message" }
     
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+    }
+    
     fn parameters(&self) -> serde_json::Value {
         serde_json::json!({
             "type": "object",
@@ -72,7 +296,7 @@ impl Tool for EchoTool {
         })
     }
     
-    async fn execute(&self, args: serde_json::Value) -> Result<serde_json::Value> {
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
         let message = args["message"].as_str().unwrap_or("(empty)");
         Ok(serde_json::json!({ "echo": message }))
     }
@@ -108,6 +332,12 @@ impl Tool for ShellTool {
     
     fn description(&self) -> &str { "Execute safe shell commands (ls, cat, grep, etc.)" }
     
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::ProcessSpawn)
+    }
+    
     fn parameters(&self) -> serde_json::Value {
         serde_json::json!({
             "type": "object",
@@ -123,7 +353,7 @@ impl Tool for ShellTool {
         })
     }
     
-    async fn execute(&self, args: serde_json::Value) -> Result<serde_json::Value> {
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
         let cmd = args["command"].as_str()
             .ok_or_else(|| SpawnError::ToolError("Missing command".into()))?;
         
@@ -137,14 +367,16 @@ impl Tool for ShellTool {
         
         let cmd_args: Vec<String> = args["args"]
             .as_array()
-            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(crate::scratch::expand)).collect())
             .unwrap_or_default();
         
         info!(command = cmd, args = ?cmd_args, "Executing shell command");
         
-        let output = Command::new(cmd)
+        let output = crate::scratch::apply_env(&mut Command::new(cmd))
             .args(&cmd_args)
+            .kill_on_drop(true)
             .output()
+            .await
             .map_err(|e| SpawnError::ToolError(format!("Exec failed: {}", e)))?;
         
         Ok(serde_json::json!({
@@ -154,3 +386,29 @@ impl Tool for ShellTool {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_synced_is_read_back() {
+        let dir = std::env::temp_dir().join(format!("spawn-write-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("src/new/file.rs");
+
+        write_synced(&path, b"fn main() {}").await.unwrap();
+        assert_eq!(tokio::fs::read_to_string(&path).await.unwrap(), "fn main() {}");
+        write_synced(&path, b"fn main() { run() }").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fn main() { run() }");
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[test]
+    fn test_within_workspace() {
+        let workspace = Path::new("/work/app");
+        assert_eq!(within_workspace(workspace, "./src/lib.rs"), Some(workspace.join("./src/lib.rs")));
+        assert_eq!(within_workspace(workspace, "sub/../../etc"), None);
+        assert_eq!(within_workspace(workspace, "/etc"), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Cargo tool - build, check, test and clippy with structured results
+//!
+//! Compiler output is read from `--message-format=json`, so errors come back
+//! as file/line/severity diagnostics rather than as raw text cut off at some
+//! length. Test failures are taken from libtest's report. The summary puts
+//! errors first, which is what a mission fixing Rust code needs to see.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use spawn_core::{CancellationToken, CapabilitySet, Diagnostic, ProgressSender, Result, Severity, SpawnError, Tool, ToolPermission};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::memory::Database;
+use crate::scratch;
+use crate::tools::lint::{parse_cargo_messages, summarize};
+
+/// Diagnostics listed in the summary
+const SUMMARY_DIAGNOSTICS: usize = 20;
+/// Lines of a failed test's output kept
+const TEST_OUTPUT_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CargoCommand {
+    Build,
+    Check,
+    Test,
+    Clippy,
+}
+
+impl CargoCommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CargoCommand::Build => "build",
+            CargoCommand::Check => "check",
+            CargoCommand::Test => "test",
+            CargoCommand::Clippy => "clippy",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        [CargoCommand::Build, CargoCommand::Check, CargoCommand::Test, CargoCommand::Clippy]
+            .into_iter()
+            .find(|c| c.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedTest {
+    pub name: String,
+    /// Last lines of what it printed, panic message included
+    pub output: String,
+}
+
+/// How a cargo run went
+#[derive(Debug, Clone, Serialize)]
+pub struct CargoReport {
+    pub command: CargoCommand,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Errors first, then by file and line
+    pub diagnostics: Vec<Diagnostic>,
+    pub tests_passed: usize,
+    pub failed_tests: Vec<FailedTest>,
+    /// Compact text for prompts
+    pub summary: String,
+}
+
+/// Run `cargo <command> --message-format=json` in `root` with `extra` arguments
+/// (a package filter, a test name, ...)
+pub async fn run(root: &Path, command: CargoCommand, extra: &[String], cancel: &CancellationToken) -> Result<CargoReport> {
+    let mut cmd = Command::new("cargo");
+    cmd.args([command.name(), "--message-format=json"]).current_dir(root).kill_on_drop(true);
+    if command == CargoCommand::Test {
+        // Flags after `--` belong to the test binaries, which must not see ours
+        let (before, after) = match extra.iter().position(|a| a == "--") {
+            Some(i) => (&extra[..i], &extra[i + 1..]),
+            None => (extra, &[][..]),
+        };
+        cmd.args(before).arg("--").arg("--color=never").args(after);
+    } else {
+        cmd.args(extra);
+    }
+    // Backtraces would push the panic message out of the kept output
+    cmd.env("RUST_BACKTRACE", "0");
+    scratch::apply_env(&mut cmd);
+
+    let output = tokio::select! {
+        output = cmd.output() => output.map_err(|e| SpawnError::ToolError(format!("Failed to run cargo: {}", e)))?,
+        _ = cancel.cancelled() => return Err(SpawnError::Cancelled),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let source = if command == CargoCommand::Clippy { "clippy" } else { "rustc" };
+    let mut diagnostics = parse_cargo_messages(&stdout, root, source);
+    // The same message comes once per target that compiles the file
+    diagnostics.sort_by(|a, b| {
+        (a.severity != Severity::Error, &a.file, a.line, &a.message).cmp(&(b.severity != Severity::Error, &b.file, b.line, &b.message))
+    });
+    diagnostics.dedup_by(|a, b| (&a.file, a.line, &a.message) == (&b.file, b.line, &b.message));
+    // Notes and help only make sense next to the error they belong to
+    diagnostics.retain(|d| d.severity != Severity::Info);
+
+    let (tests_passed, failed_tests) = parse_test_report(&stdout);
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+    let mut report = CargoReport {
+        command,
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        errors,
+        warnings,
+        diagnostics,
+        tests_passed,
+        failed_tests,
+        summary: String::new(),
+    };
+    // Failing before compiling anything (bad manifest, unknown package)
+    // leaves only cargo's own error lines to go on
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let cargo_errors = if !report.success && report.errors == 0 && report.failed_tests.is_empty() {
+        stderr.lines().filter(|l| l.starts_with("error")).collect::<Vec<_>>().join("\n")
+    } else {
+        String::new()
+    };
+    report.summary = render_summary(&report, &cargo_errors);
+    info!(command = command.name(), success = report.success, errors, warnings, failed = report.failed_tests.len(), "Cargo finished");
+    Ok(report)
+}
+
+/// Passed count and failures from libtest's plain-text report
+fn parse_test_report(stdout: &str) -> (usize, Vec<FailedTest>) {
+    let mut passed = 0;
+    let mut outputs: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in stdout.lines().filter(|l| !l.starts_with('{')) {
+        if let Some(name) = line.strip_prefix("---- ").and_then(|l| l.strip_suffix(" stdout ----")) {
+            outputs.entry(name.to_string()).or_default();
+            current = Some(name.to_string());
+        } else if line == "failures:" || line.starts_with("test result:") {
+            current = None;
+            if let Some(counts) = line.strip_prefix("test result:").and_then(|r| r.split(';').next()) {
+                // "FAILED. 2 passed"
+                passed += counts.split_whitespace().rev().nth(1).and_then(|n| n.parse::<usize>().ok()).unwrap_or(0);
+            }
+        } else if let Some(name) = &current {
+            outputs.entry(name.clone()).or_default().push(line);
+        } else if let Some(name) = line.strip_prefix("test ").and_then(|l| l.strip_suffix(" ... FAILED")) {
+            outputs.entry(name.to_string()).or_default();
+        }
+    }
+    (passed, outputs.into_iter().map(|(name, lines)| failed_test(name, &lines)).collect())
+}
+
+fn failed_test(name: String, lines: &[&str]) -> FailedTest {
+    let lines: Vec<&str> = lines.iter().copied().filter(|l| !l.trim().is_empty()).collect();
+    FailedTest { name, output: lines[lines.len().saturating_sub(TEST_OUTPUT_LINES)..].join("\n") }
+}
+
+fn render_summary(report: &CargoReport, cargo_errors: &str) -> String {
+    let mut summary = format!("cargo {}: {}", report.command.name(), if report.success { "ok" } else { "FAILED" });
+    summary.push_str(&format!(" ({} errors, {} warnings", report.errors, report.warnings));
+    if report.command == CargoCommand::Test {
+        summary.push_str(&format!(", {} tests passed, {} failed", report.tests_passed, report.failed_tests.len()));
+    }
+    summary.push(')');
+    if !report.diagnostics.is_empty() {
+        summary.push('\n');
+        summary.push_str(&summarize(&report.diagnostics, SUMMARY_DIAGNOSTICS));
+    }
+    for test in &report.failed_tests {
+        summary.push_str(&format!("\nFAILED {}\n{}", test.name, test.output));
+    }
+    if !cargo_errors.is_empty() {
+        summary.push('\n');
+        summary.push_str(cargo_errors);
+    }
+    summary
+}
+
+// ============================================
+// Tool
+// ============================================
+
+pub struct CargoTool {
+    root: PathBuf,
+    db: Option<Arc<Database>>,
+}
+
+impl CargoTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), db: None }
+    }
+
+    /// Persist diagnostics so the editor and API see the agent's builds
+    pub fn with_database(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for CargoTool {
+    fn name(&self) -> &str { "cargo" }
+
+    fn description(&self) -> &str {
+        "Run cargo build/check/test/clippy and return compiler errors as file/line diagnostics plus failed tests, with a compact summary"
+    }
+
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::ProcessSpawn)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "enum": ["build", "check", "test", "clippy"] },
+                "package": { "type": "string", "description": "Only this workspace package (-p)" },
+                "args": { "type": "array", "items": { "type": "string" }, "description": "Extra cargo arguments; for test, a name filter or arguments after --" }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value> {
+        self.execute_with_progress(args, &ProgressSender::default(), cancel).await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
+        let name = args["command"].as_str().ok_or_else(|| SpawnError::ToolError("Missing command".into()))?;
+        let command = CargoCommand::parse(name)
+            .ok_or_else(|| SpawnError::ToolError(format!("Unknown cargo command: {}", name)))?;
+        let mut extra: Vec<String> = Vec::new();
+        if let Some(package) = args["package"].as_str() {
+            extra.extend(["-p".to_string(), package.to_string()]);
+        }
+        extra.extend(args["args"].as_array()
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(scratch::expand)).collect::<Vec<_>>())
+            .unwrap_or_default());
+
+        progress.message(format!("Running cargo {}", command.name()));
+        let report = run(&self.root, command, &extra, cancel).await?;
+        progress.message(format!("cargo {}: {} errors, {} warnings", command.name(), report.errors, report.warnings));
+        if let Some(db) = &self.db {
+            let source = if command == CargoCommand::Clippy { "clippy" } else { "rustc" };
+            db.replace_diagnostics(source, &[], &report.diagnostics).await?;
+        }
+        Ok(serde_json::to_value(report)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_libtest_failures() {
+        let stdout = "\
+{\"reason\":\"compiler-artifact\"}
+running 3 tests
+test tests::adds ... ok
+test tests::subtracts ... FAILED
+test tests::divides ... ok
+
+failures:
+
+---- tests::subtracts stdout ----
+thread 'tests::subtracts' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+
+failures:
+    tests::subtracts
+
+test result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let (passed, failed) = parse_test_report(stdout);
+        assert_eq!(passed, 2);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].name, "tests::subtracts");
+        assert!(failed[0].output.contains("left: 1"));
+    }
+}
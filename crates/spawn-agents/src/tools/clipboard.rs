@@ -0,0 +1,89 @@
+//! Clipboard tool - share text with the user's terminals and editor
+//!
+//! Talks to the terminal server, which owns the workspace clipboard.
+
+use async_trait::async_trait;
+use spawn_core::{CancellationToken, CapabilitySet, Result, Service, SpawnError, Tool, ToolPermission};
+
+pub struct ClipboardTool {
+    terminal_api: String,
+    client: reqwest::Client,
+}
+
+impl ClipboardTool {
+    pub fn new(terminal_api: impl Into<String>) -> Self {
+        Self {
+            terminal_api: terminal_api.into().trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+        let res = request.send().await.map_err(|e| {
+            tracing::warn!(error = %e, "Terminal server unreachable");
+            SpawnError::ServiceUnavailable(Service::Terminal)
+        })?;
+        let status = res.status();
+        let body: serde_json::Value = res.json().await.unwrap_or(serde_json::Value::Null);
+        if !status.is_success() {
+            let message = body["error"].as_str().unwrap_or("request failed").to_string();
+            return Err(SpawnError::ToolError(format!("Clipboard {}: {}", status, message)));
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl Tool for ClipboardTool {
+    fn name(&self) -> &str { "clipboard" }
+
+    fn description(&self) -> &str {
+        "Read or write the workspace clipboard, or paste it into a named terminal"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new().with(ToolPermission::Network)
+    }
+
+    fn requires(&self) -> &[Service] {
+        &[Service::Terminal]
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["get", "set", "paste"] },
+                "text": { "type": "string", "description": "Text to copy (set) or paste (default: clipboard)" },
+                "terminal": { "type": "string", "description": "Terminal name (paste)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
+        match args["action"].as_str().unwrap_or_default() {
+            "get" => self.send(self.client.get(format!("{}/api/clipboard", self.terminal_api))).await,
+            "set" => {
+                let text = args["text"].as_str()
+                    .ok_or_else(|| SpawnError::ToolError("Missing text".into()))?;
+                self.send(self.client
+                    .put(format!("{}/api/clipboard", self.terminal_api))
+                    .json(&serde_json::json!({ "text": text, "source": "agent" }))).await
+            }
+            "paste" => {
+                let name = args["terminal"].as_str()
+                    .ok_or_else(|| SpawnError::ToolError("Missing terminal".into()))?;
+                let session = self.send(self.client
+                    .get(format!("{}/api/terminals/by-name/{}", self.terminal_api, name))).await?;
+                let id = session["id"].as_str()
+                    .ok_or_else(|| SpawnError::ToolError(format!("Terminal '{}' not found", name)))?;
+                self.send(self.client
+                    .post(format!("{}/api/terminals/{}/paste", self.terminal_api, id))
+                    .json(&serde_json::json!({ "text": args["text"].as_str() }))).await?;
+                Ok(serde_json::json!({ "success": true }))
+            }
+            other => Err(SpawnError::ToolError(format!("Unknown action: {}", other))),
+        }
+    }
+}
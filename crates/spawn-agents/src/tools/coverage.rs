@@ -0,0 +1,296 @@
+//! Coverage tool - run tests with coverage and find untested code
+//!
+//! All supported runners are asked for LCOV output, so one parser covers
+//! cargo-llvm-cov, istanbul (nyc) and coverage.py.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use spawn_core::{CancellationToken, CapabilitySet, FileCoverage, ProgressSender, Result, SpawnError, Tool, ToolPermission, ToolProgress};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::memory::Database;
+use crate::tools::output_with_progress;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoverageRunner {
+    LlvmCov,
+    Istanbul,
+    #[serde(rename = "coverage.py")]
+    CoveragePy,
+}
+
+impl CoverageRunner {
+    /// Runner for a language name (rust, typescript, python, ...)
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language.to_lowercase().as_str() {
+            "rust" | "rs" => Some(CoverageRunner::LlvmCov),
+            "javascript" | "typescript" | "js" | "ts" => Some(CoverageRunner::Istanbul),
+            "python" | "py" => Some(CoverageRunner::CoveragePy),
+            _ => None,
+        }
+    }
+
+    /// Runners that apply to a project, based on its manifests
+    pub fn detect(root: &Path) -> Vec<Self> {
+        let mut runners = Vec::new();
+        if root.join("Cargo.toml").exists() {
+            runners.push(CoverageRunner::LlvmCov);
+        }
+        if root.join("package.json").exists() {
+            runners.push(CoverageRunner::Istanbul);
+        }
+        if root.join("pyproject.toml").exists() || root.join("requirements.txt").exists() {
+            runners.push(CoverageRunner::CoveragePy);
+        }
+        runners
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoverageRunner::LlvmCov => "llvm-cov",
+            CoverageRunner::Istanbul => "istanbul",
+            CoverageRunner::CoveragePy => "coverage.py",
+        }
+    }
+
+    /// Where the runner writes its LCOV report, relative to the root
+    fn report_path(&self) -> &'static str {
+        match self {
+            CoverageRunner::LlvmCov => "target/lcov.info",
+            CoverageRunner::Istanbul => "coverage/lcov.info",
+            CoverageRunner::CoveragePy => "coverage.lcov",
+        }
+    }
+
+    fn command(&self) -> Command {
+        let script = match self {
+            CoverageRunner::LlvmCov => "cargo llvm-cov --workspace --lcov --output-path target/lcov.info",
+            CoverageRunner::Istanbul => "npx --no-install nyc --reporter=lcovonly npm test",
+            CoverageRunner::CoveragePy => "coverage run -m pytest && coverage lcov -o coverage.lcov",
+        };
+        let mut cmd = Command::new("bash");
+        cmd.args(["-c", script]);
+        crate::scratch::apply_env(&mut cmd);
+        cmd
+    }
+}
+
+/// Run the test suite with coverage in `root` and parse the LCOV report.
+/// The runner's output is sent to `progress` line by line.
+pub async fn run(root: &Path, runner: CoverageRunner, progress: &ProgressSender) -> Result<Vec<FileCoverage>> {
+    let output = output_with_progress(runner.command().current_dir(root), progress)
+        .await
+        .map_err(|e| SpawnError::ToolError(format!("Failed to run {}: {}", runner.name(), e)))?;
+
+    let report = root.join(runner.report_path());
+    let lcov = match tokio::fs::read_to_string(&report).await {
+        Ok(s) => s,
+        Err(_) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!(runner = runner.name(), stderr = %stderr, "Coverage run produced no report");
+            return Err(SpawnError::ToolError(format!(
+                "{} produced no report at {}: {}",
+                runner.name(), report.display(), stderr.trim()
+            )));
+        }
+    };
+
+    if !output.status.success() {
+        // Failing tests still produce usable coverage
+        warn!(runner = runner.name(), "Tests failed during coverage run");
+    }
+
+    let files = parse_lcov(&lcov, root, runner.name());
+    info!(runner = runner.name(), files = files.len(), "Coverage collected");
+    Ok(files)
+}
+
+/// Parse an LCOV tracefile into per-file line coverage
+pub fn parse_lcov(lcov: &str, root: &Path, source: &str) -> Vec<FileCoverage> {
+    let mut files = Vec::new();
+    let mut current: Option<FileCoverage> = None;
+
+    for line in lcov.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("SF:") {
+            let file = Path::new(path)
+                .strip_prefix(root)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| path.trim_start_matches("./").to_string());
+            current = Some(FileCoverage {
+                file,
+                lines_total: 0,
+                lines_covered: 0,
+                uncovered_lines: Vec::new(),
+                source: source.to_string(),
+            });
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let (Some(cov), Some((line_no, hits))) = (current.as_mut(), parse_da(data)) else {
+                continue;
+            };
+            cov.lines_total += 1;
+            if hits > 0 {
+                cov.lines_covered += 1;
+            } else {
+                cov.uncovered_lines.push(line_no);
+            }
+        } else if line == "end_of_record" {
+            files.extend(current.take());
+        }
+    }
+    files.extend(current);
+
+    for f in &mut files {
+        f.uncovered_lines.sort_unstable();
+        f.uncovered_lines.dedup();
+    }
+    files
+}
+
+/// `DA:<line>,<hits>[,<checksum>]`
+fn parse_da(data: &str) -> Option<(u32, u64)> {
+    let mut parts = data.split(',');
+    let line = parts.next()?.parse().ok()?;
+    // llvm-cov can emit hit counts that overflow to negative values
+    let hits = parts.next()?.parse::<i64>().ok()?.max(0) as u64;
+    Some((line, hits))
+}
+
+/// Collapse sorted line numbers into ranges ("3-7, 12")
+pub fn line_ranges(lines: &[u32]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = lines.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap_or(end);
+        }
+        ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+    }
+    ranges.join(", ")
+}
+
+// ============================================
+// Tool
+// ============================================
+
+pub struct CoverageTool {
+    root: PathBuf,
+    db: Arc<Database>,
+}
+
+impl CoverageTool {
+    pub fn new(root: impl Into<PathBuf>, db: Arc<Database>) -> Self {
+        Self { root: root.into(), db }
+    }
+}
+
+#[async_trait]
+impl Tool for CoverageTool {
+    fn name(&self) -> &str { "coverage" }
+
+    fn description(&self) -> &str {
+        "Run tests with coverage (cargo-llvm-cov/istanbul/coverage.py) or list the least-covered files and their untested lines"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::ProcessSpawn)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["run", "uncovered"] },
+                "language": { "type": "string", "description": "rust, typescript, javascript or python (default: detect)" },
+                "file": { "type": "string", "description": "Only this file (uncovered)" },
+                "limit": { "type": "integer", "description": "Max files to return (uncovered, default 10)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value> {
+        self.execute_with_progress(args, &ProgressSender::default(), cancel).await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        _cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
+        match args["action"].as_str().unwrap_or("uncovered") {
+            "run" => {
+                let runners = match args["language"].as_str() {
+                    Some(lang) => vec![CoverageRunner::for_language(lang)
+                        .ok_or_else(|| SpawnError::ToolError(format!("No coverage runner for language '{}'", lang)))?],
+                    None => CoverageRunner::detect(&self.root),
+                };
+
+                let mut summary = Vec::new();
+                let count = runners.len();
+                for (i, runner) in runners.into_iter().enumerate() {
+                    progress.send(ToolProgress::new(format!("Running {}", runner.name())).with_fraction(i as f32 / count as f32));
+                    let files = run(&self.root, runner, progress).await?;
+                    self.db.replace_coverage(runner.name(), &files).await?;
+                    let total: u32 = files.iter().map(|f| f.lines_total).sum();
+                    let covered: u32 = files.iter().map(|f| f.lines_covered).sum();
+                    summary.push(serde_json::json!({
+                        "runner": runner.name(),
+                        "files": files.len(),
+                        "lines_total": total,
+                        "lines_covered": covered,
+                    }));
+                }
+                Ok(serde_json::json!({ "runs": summary }))
+            }
+            "uncovered" => {
+                let limit = args["limit"].as_u64().unwrap_or(10) as usize;
+                let mut files = self.db.list_coverage(args["file"].as_str()).await?;
+                files.retain(|f| !f.uncovered_lines.is_empty());
+                files.sort_by(|a, b| a.percent().total_cmp(&b.percent()));
+
+                let result: Vec<_> = files.iter()
+                    .take(limit)
+                    .map(|f| serde_json::json!({
+                        "file": f.file,
+                        "percent": (f.percent() * 10.0).round() / 10.0,
+                        "uncovered": line_ranges(&f.uncovered_lines),
+                    }))
+                    .collect();
+                Ok(serde_json::json!({ "files": result }))
+            }
+            other => Err(SpawnError::ToolError(format!("Unknown action: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lcov_records() {
+        let lcov = "SF:/work/src/lib.rs\nDA:1,3\nDA:2,0\nDA:3,0\nDA:5,1\nend_of_record\nSF:src/main.rs\nDA:1,0\nend_of_record\n";
+        let files = parse_lcov(lcov, Path::new("/work"), "llvm-cov");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file, "src/lib.rs");
+        assert_eq!(files[0].lines_total, 4);
+        assert_eq!(files[0].lines_covered, 2);
+        assert_eq!(files[0].uncovered_lines, vec![2, 3]);
+        assert_eq!(files[1].percent(), 0.0);
+    }
+
+    #[test]
+    fn collapses_line_ranges() {
+        assert_eq!(line_ranges(&[2, 3, 4, 7, 9, 10]), "2-4, 7, 9-10");
+        assert_eq!(line_ranges(&[]), "");
+    }
+}
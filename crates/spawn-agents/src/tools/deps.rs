@@ -0,0 +1,461 @@
+//! Dependency and manifest analysis
+//!
+//! Parses Cargo.toml, package.json and pyproject.toml files in the workspace,
+//! looks up the latest published versions and known advisories (OSV).
+
+use crate::ignore::IgnoreRules;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use spawn_core::{CancellationToken, CapabilitySet, Result, SpawnError, Tool, ToolPermission};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+const MAX_DEPTH: usize = 3;
+const REGISTRY_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    PyPI,
+}
+
+impl Ecosystem {
+    /// Ecosystem name as used by the OSV API
+    fn osv_name(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "crates.io",
+            Ecosystem::Npm => "npm",
+            Ecosystem::PyPI => "PyPI",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub summary: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+    pub name: String,
+    pub ecosystem: Ecosystem,
+    pub kind: DependencyKind,
+    /// Manifest path relative to the workspace root
+    pub manifest: String,
+    /// Declared version requirement (None for path/git/workspace dependencies)
+    pub requirement: Option<String>,
+    pub latest: Option<String>,
+    pub outdated: Option<bool>,
+    pub advisories: Vec<Advisory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepsReport {
+    pub manifests: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub outdated: usize,
+    pub vulnerable: usize,
+}
+
+/// The directory `path` names under `workspace`, or the workspace itself.
+///
+/// Rejects absolute paths and `..` components, so the result can't escape
+/// the workspace.
+pub fn scan_root(workspace: &Path, path: Option<&str>) -> Result<PathBuf> {
+    let Some(path) = path else {
+        return Ok(workspace.to_path_buf());
+    };
+    super::within_workspace(workspace, path).ok_or_else(|| SpawnError::ToolError("Path must be inside the workspace".into()))
+}
+
+/// Scan `root` for manifests and build a dependency report.
+///
+/// With `check_registry` set, each dependency is looked up in its registry
+/// and in OSV; otherwise only declared dependencies are reported.
+pub async fn analyze(root: &Path, check_registry: bool) -> Result<DepsReport> {
+    let manifests = find_manifests(root);
+    let mut dependencies = Vec::new();
+
+    for manifest in &manifests {
+        let content = tokio::fs::read_to_string(manifest).await
+            .map_err(|e| SpawnError::ToolError(format!("Failed to read {}: {}", manifest.display(), e)))?;
+        let relative = manifest.strip_prefix(root).unwrap_or(manifest).to_string_lossy().to_string();
+
+        let parsed = match manifest.file_name().and_then(|n| n.to_str()) {
+            Some("Cargo.toml") => parse_cargo(&content, &relative)?,
+            Some("package.json") => parse_package_json(&content, &relative)?,
+            Some("pyproject.toml") => parse_pyproject(&content, &relative)?,
+            _ => continue,
+        };
+        dependencies.extend(parsed);
+    }
+
+    if check_registry {
+        let client = reqwest::Client::builder()
+            .user_agent("spawn (https://spawn.new)")
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| SpawnError::ToolError(format!("HTTP client error: {}", e)))?;
+
+        dependencies = stream::iter(dependencies)
+            .map(|dep| {
+                let client = client.clone();
+                async move { enrich(&client, dep).await }
+            })
+            .buffered(REGISTRY_CONCURRENCY)
+            .collect()
+            .await;
+    }
+
+    let outdated = dependencies.iter().filter(|d| d.outdated == Some(true)).count();
+    let vulnerable = dependencies.iter().filter(|d| !d.advisories.is_empty()).count();
+    info!(manifests = manifests.len(), dependencies = dependencies.len(), outdated, vulnerable, "Analyzed dependencies");
+
+    Ok(DepsReport {
+        manifests: manifests.iter()
+            .map(|m| m.strip_prefix(root).unwrap_or(m).to_string_lossy().to_string())
+            .collect(),
+        dependencies,
+        outdated,
+        vulnerable,
+    })
+}
+
+// ============================================
+// Manifest discovery & parsing
+// ============================================
+
+fn find_manifests(root: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    collect_manifests(root, 0, &IgnoreRules::load(root), &mut found);
+    found.sort();
+    found
+}
+
+fn collect_manifests(dir: &Path, depth: usize, ignore: &IgnoreRules, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let is_dir = path.is_dir();
+        if ignore.is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            if depth < MAX_DEPTH {
+                collect_manifests(&path, depth + 1, ignore, found);
+            }
+        } else if matches!(name.as_str(), "Cargo.toml" | "package.json" | "pyproject.toml") {
+            found.push(path);
+        }
+    }
+}
+
+fn parse_cargo(content: &str, manifest: &str) -> Result<Vec<Dependency>> {
+    let doc: toml::Value = toml::from_str(content)
+        .map_err(|e| SpawnError::ToolError(format!("Invalid {}: {}", manifest, e)))?;
+
+    let sections = [
+        (doc.get("dependencies"), DependencyKind::Normal),
+        (doc.get("dev-dependencies"), DependencyKind::Dev),
+        (doc.get("build-dependencies"), DependencyKind::Build),
+        (doc.get("workspace").and_then(|w| w.get("dependencies")), DependencyKind::Normal),
+    ];
+
+    let mut deps = Vec::new();
+    for (table, kind) in sections {
+        let Some(table) = table.and_then(|t| t.as_table()) else {
+            continue;
+        };
+        for (name, spec) in table {
+            // Path, git and workspace-inherited deps have no registry version
+            let requirement = match spec {
+                toml::Value::String(v) => Some(v.clone()),
+                toml::Value::Table(t) if t.contains_key("path") || t.contains_key("git") => None,
+                toml::Value::Table(t) => t.get("version").and_then(|v| v.as_str()).map(String::from),
+                _ => None,
+            };
+            let name = spec.get("package")
+                .and_then(|p| p.as_str())
+                .unwrap_or(name)
+                .to_string();
+            deps.push(Dependency::declared(name, Ecosystem::Cargo, kind, manifest, requirement));
+        }
+    }
+    Ok(deps)
+}
+
+fn parse_package_json(content: &str, manifest: &str) -> Result<Vec<Dependency>> {
+    let doc: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| SpawnError::ToolError(format!("Invalid {}: {}", manifest, e)))?;
+
+    let mut deps = Vec::new();
+    for (section, kind) in [("dependencies", DependencyKind::Normal), ("devDependencies", DependencyKind::Dev)] {
+        let Some(table) = doc[section].as_object() else {
+            continue;
+        };
+        for (name, spec) in table {
+            // Skip workspace:, file:, git and URL specifiers
+            let requirement = spec.as_str()
+                .filter(|v| !v.contains(':') && !v.contains('/'))
+                .map(String::from);
+            deps.push(Dependency::declared(name.clone(), Ecosystem::Npm, kind, manifest, requirement));
+        }
+    }
+    Ok(deps)
+}
+
+fn parse_pyproject(content: &str, manifest: &str) -> Result<Vec<Dependency>> {
+    let doc: toml::Value = toml::from_str(content)
+        .map_err(|e| SpawnError::ToolError(format!("Invalid {}: {}", manifest, e)))?;
+
+    let mut deps = Vec::new();
+
+    // PEP 621: [project] dependencies = ["requests>=2.31", ...]
+    if let Some(list) = doc.get("project").and_then(|p| p.get("dependencies")).and_then(|d| d.as_array()) {
+        for spec in list.iter().filter_map(|s| s.as_str()) {
+            let (name, requirement) = split_pep508(spec);
+            deps.push(Dependency::declared(name, Ecosystem::PyPI, DependencyKind::Normal, manifest, requirement));
+        }
+    }
+
+    // Poetry: [tool.poetry.dependencies] requests = "^2.31"
+    let poetry = doc.get("tool").and_then(|t| t.get("poetry"));
+    for (section, kind) in [("dependencies", DependencyKind::Normal), ("dev-dependencies", DependencyKind::Dev)] {
+        let Some(table) = poetry.and_then(|p| p.get(section)).and_then(|d| d.as_table()) else {
+            continue;
+        };
+        for (name, spec) in table.iter().filter(|(n, _)| n.as_str() != "python") {
+            let requirement = match spec {
+                toml::Value::String(v) => Some(v.clone()),
+                toml::Value::Table(t) => t.get("version").and_then(|v| v.as_str()).map(String::from),
+                _ => None,
+            };
+            deps.push(Dependency::declared(name.clone(), Ecosystem::PyPI, kind, manifest, requirement));
+        }
+    }
+    Ok(deps)
+}
+
+/// Split a PEP 508 requirement ("requests[socks]>=2.31; python_version>'3.8'")
+/// into name and version specifier
+fn split_pep508(spec: &str) -> (String, Option<String>) {
+    let spec = spec.split(';').next().unwrap_or(spec).trim();
+    let end = spec.find(|c: char| "<>=!~[ (".contains(c)).unwrap_or(spec.len());
+    let name = spec[..end].trim().to_string();
+    let rest = spec[end..].trim_start_matches(|c: char| c != '[' && !"<>=!~".contains(c));
+    let rest = match rest.find(']') {
+        Some(i) if rest.starts_with('[') => &rest[i + 1..],
+        _ => rest,
+    };
+    let requirement = rest.trim().trim_matches(|c| c == '(' || c == ')').trim();
+    (name, (!requirement.is_empty()).then(|| requirement.to_string()))
+}
+
+impl Dependency {
+    fn declared(
+        name: String,
+        ecosystem: Ecosystem,
+        kind: DependencyKind,
+        manifest: &str,
+        requirement: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            ecosystem,
+            kind,
+            manifest: manifest.to_string(),
+            requirement,
+            latest: None,
+            outdated: None,
+            advisories: Vec::new(),
+        }
+    }
+}
+
+// ============================================
+// Registry & advisory lookups
+// ============================================
+
+async fn enrich(client: &reqwest::Client, mut dep: Dependency) -> Dependency {
+    let Some(requirement) = dep.requirement.clone() else {
+        return dep;
+    };
+
+    dep.latest = match latest_version(client, &dep).await {
+        Ok(v) => v,
+        Err(e) => {
+            debug!(name = %dep.name, error = %e, "Registry lookup failed");
+            None
+        }
+    };
+    dep.outdated = dep.latest.as_deref().map(|latest| is_outdated(&requirement, latest));
+
+    if let Some(version) = base_version(&requirement) {
+        match query_osv(client, &dep, &version).await {
+            Ok(advisories) => dep.advisories = advisories,
+            Err(e) => debug!(name = %dep.name, error = %e, "OSV lookup failed"),
+        }
+    }
+    dep
+}
+
+async fn latest_version(client: &reqwest::Client, dep: &Dependency) -> std::result::Result<Option<String>, reqwest::Error> {
+    let (url, pointer) = match dep.ecosystem {
+        Ecosystem::Cargo => (format!("https://crates.io/api/v1/crates/{}", dep.name), "/crate/max_stable_version"),
+        Ecosystem::Npm => (format!("https://registry.npmjs.org/{}/latest", dep.name), "/version"),
+        Ecosystem::PyPI => (format!("https://pypi.org/pypi/{}/json", dep.name), "/info/version"),
+    };
+    let json: serde_json::Value = client.get(url).send().await?.error_for_status()?.json().await?;
+    Ok(json.pointer(pointer).and_then(|v| v.as_str()).map(String::from))
+}
+
+async fn query_osv(client: &reqwest::Client, dep: &Dependency, version: &str) -> std::result::Result<Vec<Advisory>, reqwest::Error> {
+    let json: serde_json::Value = client
+        .post("https://api.osv.dev/v1/query")
+        .json(&serde_json::json!({
+            "version": version,
+            "package": { "name": dep.name, "ecosystem": dep.ecosystem.osv_name() },
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(json["vulns"].as_array()
+        .map(|vulns| vulns.iter().map(|v| Advisory {
+            id: v["id"].as_str().unwrap_or_default().to_string(),
+            summary: v["summary"].as_str().map(String::from),
+            aliases: v["aliases"].as_array()
+                .map(|a| a.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+        }).collect())
+        .unwrap_or_default())
+}
+
+/// Extract the lowest version named by a requirement ("^1.2" → "1.2", ">=2.31,<3" → "2.31")
+fn base_version(requirement: &str) -> Option<String> {
+    let start = requirement.find(|c: char| c.is_ascii_digit())?;
+    let version: String = requirement[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-')
+        .collect();
+    Some(version.trim_end_matches('.').to_string())
+}
+
+/// A requirement is outdated when the latest release is outside its
+/// compatible range (major, or minor for 0.x versions)
+fn is_outdated(requirement: &str, latest: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.split('.')
+            .map(|p| p.chars().take_while(|c| c.is_ascii_digit()).collect::<String>())
+            .map_while(|p| p.parse().ok())
+            .collect()
+    };
+
+    let Some(declared) = base_version(requirement).map(|v| parse(&v)) else {
+        return false;
+    };
+    let latest = parse(latest);
+
+    match (declared.first(), latest.first()) {
+        (Some(0), Some(0)) => declared.get(1).unwrap_or(&0) < latest.get(1).unwrap_or(&0),
+        (Some(d), Some(l)) => d < l,
+        _ => false,
+    }
+}
+
+// ============================================
+// Tool
+// ============================================
+
+/// Dependency analysis tool: declared vs latest versions plus advisories
+pub struct DepsTool {
+    root: PathBuf,
+}
+
+impl DepsTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl Tool for DepsTool {
+    fn name(&self) -> &str { "deps" }
+
+    fn description(&self) -> &str {
+        "Analyze Cargo.toml/package.json/pyproject.toml dependencies: declared, outdated, and known advisories"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::Network)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Subdirectory to scan (default: workspace root)" },
+                "check_registry": { "type": "boolean", "description": "Look up latest versions and advisories (default: true)" }
+            }
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
+        let root = scan_root(&self.root, args["path"].as_str())?;
+        let check_registry = args["check_registry"].as_bool().unwrap_or(true);
+
+        let report = analyze(&root, check_registry).await?;
+        Ok(serde_json::to_value(report)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_outdated() {
+        assert!(!is_outdated("1.0", "1.0.219"));
+        assert!(is_outdated("0.7", "0.8.1"));
+        assert!(is_outdated("^1.2.3", "2.0.0"));
+        assert!(!is_outdated(">=2.31,<3", "2.32.3"));
+    }
+
+    #[test]
+    fn test_split_pep508() {
+        assert_eq!(split_pep508("requests>=2.31"), ("requests".into(), Some(">=2.31".into())));
+        assert_eq!(split_pep508("uvicorn[standard] ==0.30; python_version>'3.8'"), ("uvicorn".into(), Some("==0.30".into())));
+        assert_eq!(split_pep508("rich"), ("rich".into(), None));
+    }
+
+    #[test]
+    fn test_scan_root_stays_in_workspace() {
+        let workspace = Path::new("/work/app");
+        assert_eq!(scan_root(workspace, None).unwrap(), workspace);
+        assert_eq!(scan_root(workspace, Some("crates/core")).unwrap(), workspace.join("crates/core"));
+        assert!(scan_root(workspace, Some("sub/../../etc")).is_err());
+        assert!(scan_root(workspace, Some("/etc")).is_err());
+    }
+}
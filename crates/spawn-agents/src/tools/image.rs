@@ -0,0 +1,182 @@
+//! Image generation tool - logos, icons and placeholder art for the workspace
+
+use async_trait::async_trait;
+use spawn_ai::{GeneratedImage, OpenRouterClient};
+use spawn_core::{CancellationToken, CapabilitySet, Result, Service, SpawnError, Tool, ToolPermission};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::tools::write_synced;
+
+const DEFAULT_IMAGE_MODEL: &str = "google/gemini-2.5-flash-image-preview";
+
+pub struct ImageGenerateTool {
+    client: Arc<OpenRouterClient>,
+    root: PathBuf,
+    model: String,
+}
+
+impl ImageGenerateTool {
+    pub fn new(client: Arc<OpenRouterClient>, root: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            root: root.into(),
+            model: DEFAULT_IMAGE_MODEL.to_string(),
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Web projects serve static files from `public/`; otherwise use `assets/`
+    fn default_dir(&self) -> &'static str {
+        if self.root.join("public").is_dir() {
+            "public/assets"
+        } else {
+            "assets"
+        }
+    }
+}
+
+/// Workspace-relative directory, rejecting absolute paths and `..`
+fn safe_relative(dir: &str) -> Result<PathBuf> {
+    let path = Path::new(dir);
+    if path.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(SpawnError::ToolError(format!("Invalid directory: {}", dir)));
+    }
+    Ok(path.to_path_buf())
+}
+
+/// File-name stem from a prompt ("A minimal fox logo" -> "a-minimal-fox-logo")
+fn slugify(text: &str) -> String {
+    let slug: String = text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|s| !s.is_empty()).take(6).collect::<Vec<_>>().join("-");
+    if slug.is_empty() { "image".to_string() } else { slug }
+}
+
+#[async_trait]
+impl Tool for ImageGenerateTool {
+    fn name(&self) -> &str { "image_generate" }
+
+    fn description(&self) -> &str {
+        "Generate an image (logo, icon, placeholder) from a prompt and save it into the workspace assets directory"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemWrite)
+            .with(ToolPermission::Network)
+    }
+
+    fn requires(&self) -> &[Service] {
+        &[Service::Provider]
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "prompt": { "type": "string", "description": "What the image should show" },
+                "name": { "type": "string", "description": "File name without extension (default: from prompt)" },
+                "dir": { "type": "string", "description": "Directory relative to the workspace (default: public/assets or assets)" },
+                "model": { "type": "string", "description": "Image model on OpenRouter" }
+            },
+            "required": ["prompt"]
+        })
+    }
+
+    /// The first image's file under every extension the model may pick,
+    /// and its metadata sidecar
+    fn locks(&self, args: &serde_json::Value) -> Vec<String> {
+        let Some(prompt) = args["prompt"].as_str() else {
+            return Vec::new();
+        };
+        let dir = args["dir"].as_str().unwrap_or(self.default_dir());
+        let stem = slugify(args["name"].as_str().unwrap_or(prompt));
+        GeneratedImage::EXTENSIONS.iter().chain(&["json"])
+            .map(|ext| crate::locks::file_resource(&format!("{}/{}.{}", dir, stem, ext)))
+            .collect()
+    }
+
+    fn written_files(&self, result: &serde_json::Value) -> Vec<String> {
+        result["files"].as_array().into_iter().flatten()
+            .flat_map(|f| [&f["path"], &f["metadata"]])
+            .filter_map(|p| p.as_str().map(str::to_string))
+            .collect()
+    }
+
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
+        let prompt = args["prompt"].as_str()
+            .ok_or_else(|| SpawnError::ToolError("Missing prompt".into()))?;
+        let model = args["model"].as_str().unwrap_or(&self.model);
+        let dir = safe_relative(args["dir"].as_str().unwrap_or(self.default_dir()))?;
+        let stem = slugify(args["name"].as_str().unwrap_or(prompt));
+
+        let images = self.client.generate_image(model, prompt).await?;
+
+        tokio::fs::create_dir_all(self.root.join(&dir)).await
+            .map_err(|e| SpawnError::ToolError(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+        let mut files = Vec::new();
+        for (i, image) in images.iter().enumerate() {
+            let name = if i == 0 { stem.clone() } else { format!("{}-{}", stem, i + 1) };
+            let path = dir.join(format!("{}.{}", name, image.extension()));
+            write_synced(&self.root.join(&path), &image.data).await
+                .map_err(|e| SpawnError::ToolError(format!("Failed to write {}: {}", path.display(), e)))?;
+
+            // Sidecar metadata so assets can be traced back and regenerated
+            let metadata = serde_json::json!({
+                "file": path.file_name().map(|n| n.to_string_lossy().to_string()),
+                "prompt": prompt,
+                "model": model,
+                "mime_type": image.mime_type,
+                "bytes": image.data.len(),
+                "created_at": chrono::Utc::now().to_rfc3339(),
+            });
+            let metadata_path = dir.join(format!("{}.json", name));
+            write_synced(&self.root.join(&metadata_path), &serde_json::to_vec_pretty(&metadata)?).await
+                .map_err(|e| SpawnError::ToolError(format!("Failed to write {}: {}", metadata_path.display(), e)))?;
+
+            info!(path = %path.display(), bytes = image.data.len(), "Image generated");
+            files.push(serde_json::json!({
+                "path": path.to_string_lossy(),
+                "metadata": metadata_path.to_string_lossy(),
+                // URL the app can reference when served from public/
+                "url": path.strip_prefix("public").ok().map(|p| format!("/{}", p.to_string_lossy())),
+            }));
+        }
+
+        Ok(serde_json::json!({ "model": model, "files": files }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_written_files_from_result() {
+        let tool = ImageGenerateTool::new(Arc::new(OpenRouterClient::new("key")), "/tmp");
+        let result = serde_json::json!({
+            "model": "m",
+            "files": [{ "path": "public/assets/logo.png", "metadata": "public/assets/logo.json", "url": "/assets/logo.png" }],
+        });
+        assert_eq!(tool.written_files(&result), vec!["public/assets/logo.png", "public/assets/logo.json"]);
+        assert!(tool.written_files(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_locks_output_files() {
+        let tool = ImageGenerateTool::new(Arc::new(OpenRouterClient::new("key")), "/tmp");
+        let locks = tool.locks(&serde_json::json!({ "prompt": "A logo", "dir": "./img" }));
+        assert!(locks.contains(&crate::locks::file_resource("img/a-logo.png")));
+        assert!(locks.contains(&crate::locks::file_resource("img/a-logo.svg")));
+        assert!(locks.contains(&crate::locks::file_resource("img/a-logo.json")));
+    }
+}
@@ -0,0 +1,374 @@
+//! Lint tool - clippy / eslint / ruff as structured diagnostics
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use spawn_core::{CancellationToken, CapabilitySet, Diagnostic, ProgressSender, Result, Severity, SpawnError, Tool, ToolPermission, ToolProgress};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::memory::Database;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Linter {
+    Clippy,
+    Eslint,
+    Ruff,
+}
+
+impl Linter {
+    /// Linter for an editor language name (rust, typescript, python, ...)
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Linter::Clippy),
+            "javascript" | "typescript" | "js" | "ts" | "jsx" | "tsx" => Some(Linter::Eslint),
+            "python" | "py" => Some(Linter::Ruff),
+            _ => None,
+        }
+    }
+
+    /// Linters that apply to a project, based on its manifests
+    pub fn detect(root: &Path) -> Vec<Self> {
+        let mut linters = Vec::new();
+        if root.join("Cargo.toml").exists() {
+            linters.push(Linter::Clippy);
+        }
+        if root.join("package.json").exists() {
+            linters.push(Linter::Eslint);
+        }
+        if root.join("pyproject.toml").exists() || root.join("requirements.txt").exists() {
+            linters.push(Linter::Ruff);
+        }
+        linters
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Linter::Clippy => "clippy",
+            Linter::Eslint => "eslint",
+            Linter::Ruff => "ruff",
+        }
+    }
+
+    fn command(&self, paths: &[String]) -> Command {
+        let mut cmd = match self {
+            Linter::Clippy => {
+                let mut c = Command::new("cargo");
+                c.args(["clippy", "--message-format=json", "--quiet"]);
+                c
+            }
+            Linter::Eslint => {
+                let mut c = Command::new("npx");
+                c.args(["--no-install", "eslint", "--format", "json"]);
+                if paths.is_empty() {
+                    c.arg(".");
+                }
+                c
+            }
+            Linter::Ruff => {
+                let mut c = Command::new("ruff");
+                c.args(["check", "--output-format=json", "--exit-zero"]);
+                if paths.is_empty() {
+                    c.arg(".");
+                }
+                c
+            }
+        };
+        // clippy lints the whole crate; path filtering happens after parsing
+        if *self != Linter::Clippy {
+            cmd.args(paths);
+        }
+        crate::scratch::apply_env(&mut cmd);
+        cmd
+    }
+
+    fn parse(&self, stdout: &str, root: &Path) -> Vec<Diagnostic> {
+        match self {
+            Linter::Clippy => parse_cargo_messages(stdout, root, "clippy"),
+            Linter::Eslint => parse_eslint(stdout, root),
+            Linter::Ruff => parse_ruff(stdout, root),
+        }
+    }
+}
+
+/// Run a linter in `root`, optionally restricted to `paths`
+pub async fn run(root: &Path, linter: Linter, paths: &[String]) -> Result<Vec<Diagnostic>> {
+    let output = linter.command(paths)
+        .current_dir(root)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| SpawnError::ToolError(format!("Failed to run {}: {}", linter.name(), e)))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut diagnostics = linter.parse(&stdout, root);
+
+    if diagnostics.is_empty() && !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.trim().is_empty() {
+            warn!(linter = linter.name(), stderr = %stderr, "Linter failed without diagnostics");
+            return Err(SpawnError::ToolError(format!("{} failed: {}", linter.name(), stderr.trim())));
+        }
+    }
+
+    if !paths.is_empty() {
+        diagnostics.retain(|d| paths.iter().any(|p| d.file.starts_with(p.trim_start_matches("./"))));
+    }
+    diagnostics.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+
+    info!(linter = linter.name(), count = diagnostics.len(), "Lint finished");
+    Ok(diagnostics)
+}
+
+fn relative(root: &Path, file: &str) -> String {
+    Path::new(file)
+        .strip_prefix(root)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file.trim_start_matches("./").to_string())
+}
+
+/// Parse `cargo ... --message-format=json` output
+pub fn parse_cargo_messages(stdout: &str, root: &Path, source: &str) -> Vec<Diagnostic> {
+    stdout.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["reason"] == "compiler-message")
+        .filter_map(|msg| {
+            let message = &msg["message"];
+            let severity = match message["level"].as_str()? {
+                "error" | "error: internal compiler error" => Severity::Error,
+                "warning" => Severity::Warning,
+                "note" | "help" => Severity::Info,
+                _ => return None,
+            };
+            let span = message["spans"].as_array()?
+                .iter()
+                .find(|s| s["is_primary"].as_bool() == Some(true))?;
+            Some(Diagnostic {
+                file: relative(root, span["file_name"].as_str()?),
+                line: span["line_start"].as_u64()? as u32,
+                column: span["column_start"].as_u64().map(|c| c as u32),
+                end_line: span["line_end"].as_u64().map(|l| l as u32),
+                end_column: span["column_end"].as_u64().map(|c| c as u32),
+                severity,
+                code: message["code"]["code"].as_str().map(String::from),
+                message: message["message"].as_str().unwrap_or_default().to_string(),
+                source: source.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_eslint(stdout: &str, root: &Path) -> Vec<Diagnostic> {
+    let Ok(files) = serde_json::from_str::<Vec<serde_json::Value>>(stdout) else {
+        return Vec::new();
+    };
+    files.iter()
+        .flat_map(|file| {
+            let path = relative(root, file["filePath"].as_str().unwrap_or_default());
+            file["messages"].as_array().cloned().unwrap_or_default()
+                .into_iter()
+                .map(move |m| Diagnostic {
+                    file: path.clone(),
+                    line: m["line"].as_u64().unwrap_or(1) as u32,
+                    column: m["column"].as_u64().map(|c| c as u32),
+                    end_line: m["endLine"].as_u64().map(|l| l as u32),
+                    end_column: m["endColumn"].as_u64().map(|c| c as u32),
+                    severity: if m["severity"].as_u64() == Some(2) { Severity::Error } else { Severity::Warning },
+                    code: m["ruleId"].as_str().map(String::from),
+                    message: m["message"].as_str().unwrap_or_default().to_string(),
+                    source: "eslint".to_string(),
+                })
+        })
+        .collect()
+}
+
+fn parse_ruff(stdout: &str, root: &Path) -> Vec<Diagnostic> {
+    let Ok(items) = serde_json::from_str::<Vec<serde_json::Value>>(stdout) else {
+        return Vec::new();
+    };
+    items.iter()
+        .map(|d| {
+            let code = d["code"].as_str().map(String::from);
+            // Syntax errors have no rule code
+            let severity = if code.is_none() { Severity::Error } else { Severity::Warning };
+            Diagnostic {
+                file: relative(root, d["filename"].as_str().unwrap_or_default()),
+                line: d["location"]["row"].as_u64().unwrap_or(1) as u32,
+                column: d["location"]["column"].as_u64().map(|c| c as u32),
+                end_line: d["end_location"]["row"].as_u64().map(|l| l as u32),
+                end_column: d["end_location"]["column"].as_u64().map(|c| c as u32),
+                severity,
+                code,
+                message: d["message"].as_str().unwrap_or_default().to_string(),
+                source: "ruff".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Render diagnostics as a compact list for prompts
+pub fn summarize(diagnostics: &[Diagnostic], limit: usize) -> String {
+    let mut lines: Vec<String> = diagnostics.iter()
+        .take(limit)
+        .map(|d| format!(
+            "{}:{}: {:?} [{}] {}",
+            d.file, d.line, d.severity,
+            d.code.as_deref().unwrap_or(&d.source),
+            d.message
+        ))
+        .collect();
+    if diagnostics.len() > limit {
+        lines.push(format!("... and {} more", diagnostics.len() - limit));
+    }
+    lines.join("\n")
+}
+
+// ============================================
+// Tool
+// ============================================
+
+pub struct LintTool {
+    root: PathBuf,
+    db: Option<Arc<Database>>,
+}
+
+impl LintTool {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), db: None }
+    }
+
+    /// Persist diagnostics so the editor and API see the agent's lint runs
+    pub fn with_database(mut self, db: Arc<Database>) -> Self {
+        self.db = Some(db);
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for LintTool {
+    fn name(&self) -> &str { "lint" }
+
+    fn description(&self) -> &str {
+        "Run the project's linter (clippy/eslint/ruff) and return file/line/severity diagnostics"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::ProcessSpawn)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "language": { "type": "string", "description": "rust, typescript, javascript or python (default: detect)" },
+                "paths": { "type": "array", "items": { "type": "string" }, "description": "Restrict to these files/directories" }
+            }
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value> {
+        self.execute_with_progress(args, &ProgressSender::default(), cancel).await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        _cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
+        let linters = match args["language"].as_str() {
+            Some(lang) => vec![Linter::for_language(lang)
+                .ok_or_else(|| SpawnError::ToolError(format!("No linter for language '{}'", lang)))?],
+            None => Linter::detect(&self.root),
+        };
+        let paths: Vec<String> = args["paths"].as_array()
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+
+        let mut diagnostics = Vec::new();
+        let total = linters.len();
+        for (i, linter) in linters.into_iter().enumerate() {
+            progress.send(ToolProgress::new(format!("Running {}", linter.name())).with_fraction(i as f32 / total as f32));
+            let found = run(&self.root, linter, &paths).await?;
+            progress.message(format!("{}: {} diagnostics", linter.name(), found.len()));
+            if let Some(db) = &self.db {
+                db.replace_diagnostics(linter.name(), &paths, &found).await?;
+            }
+            diagnostics.extend(found);
+        }
+
+        let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+        Ok(serde_json::json!({
+            "total": diagnostics.len(),
+            "errors": count(Severity::Error),
+            "warnings": count(Severity::Warning),
+            "diagnostics": diagnostics,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_linter_output() {
+        let root = Path::new("/work/app");
+
+        let clippy = r#"{"reason":"compiler-artifact"}
+{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `x`","code":{"code":"unused_variables"},"spans":[{"is_primary":false,"file_name":"src/other.rs","line_start":1},{"is_primary":true,"file_name":"/work/app/src/main.rs","line_start":3,"line_end":3,"column_start":9,"column_end":10}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":null,"spans":[{"is_primary":true,"file_name":"src/lib.rs","line_start":7,"line_end":7,"column_start":5,"column_end":8}]}}"#;
+        let found = Linter::Clippy.parse(clippy, root);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].file.as_str(), found[0].line, found[0].column), ("src/main.rs", 3, Some(9)));
+        assert_eq!((found[0].severity, found[0].code.as_deref()), (Severity::Warning, Some("unused_variables")));
+        assert_eq!((found[1].file.as_str(), found[1].severity, found[1].code.as_deref()), ("src/lib.rs", Severity::Error, None));
+
+        let eslint = r#"[{"filePath":"/work/app/src/index.ts","messages":[
+            {"ruleId":"no-unused-vars","severity":1,"message":"'a' is unused","line":2,"column":7,"endLine":2,"endColumn":8},
+            {"ruleId":null,"severity":2,"message":"Parsing error","line":5,"column":1}]}]"#;
+        let found = Linter::Eslint.parse(eslint, root);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].file.as_str(), found[0].severity, found[0].end_column), ("src/index.ts", Severity::Warning, Some(8)));
+        assert_eq!((found[1].severity, found[1].code.as_deref(), found[1].source.as_str()), (Severity::Error, None, "eslint"));
+
+        let ruff = r#"[{"code":"F401","message":"`os` imported but unused","filename":"/work/app/app/main.py","location":{"row":1,"column":8},"end_location":{"row":1,"column":10}},
+            {"code":null,"message":"SyntaxError: invalid syntax","filename":"./app/broken.py","location":{"row":4,"column":1}}]"#;
+        let found = Linter::Ruff.parse(ruff, root);
+        assert_eq!((found[0].file.as_str(), found[0].line, found[0].severity), ("app/main.py", 1, Severity::Warning));
+        assert_eq!((found[1].file.as_str(), found[1].severity, found[1].end_line), ("app/broken.py", Severity::Error, None));
+
+        assert!(Linter::Eslint.parse("not json", root).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_linting_some_paths_keeps_other_diagnostics() {
+        let path = std::env::temp_dir().join(format!("spawn-lint-{}.db", uuid::Uuid::new_v4()));
+        let db = Database::connect(&format!("sqlite:{}?mode=rwc", path.display())).await.unwrap();
+        let diagnostic = |file: &str| Diagnostic {
+            file: file.to_string(),
+            line: 1,
+            column: None,
+            end_line: None,
+            end_column: None,
+            severity: Severity::Warning,
+            code: None,
+            message: "unused".to_string(),
+            source: "eslint".to_string(),
+        };
+        db.replace_diagnostics("eslint", &[], &[diagnostic("src/a.ts"), diagnostic("src/b.ts"), diagnostic("lib/c.ts")]).await.unwrap();
+        db.replace_diagnostics("eslint", &["./src/a.ts".to_string()], &[]).await.unwrap();
+        let files = |d: Vec<Diagnostic>| d.into_iter().map(|d| d.file).collect::<Vec<_>>();
+        assert_eq!(files(db.list_diagnostics(None).await.unwrap()), ["lib/c.ts", "src/b.ts"]);
+
+        db.replace_diagnostics("eslint", &["src".to_string()], &[diagnostic("src/d.ts")]).await.unwrap();
+        assert_eq!(files(db.list_diagnostics(None).await.unwrap()), ["lib/c.ts", "src/d.ts"]);
+        db.replace_diagnostics("eslint", &[], &[]).await.unwrap();
+        assert!(db.list_diagnostics(None).await.unwrap().is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,285 @@
+//! npm script tool - list and run a package's scripts
+//!
+//! Most scaffolded web projects are driven through `package.json` scripts.
+//! Scripts run with the package manager the project's lockfile points to.
+//! A finite script (`build`, `test`, `lint`) runs to completion with its
+//! output streamed as progress. A long-running one (`dev`, `watch`) is started
+//! as a managed process, so its output and port can be followed afterwards.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use spawn_core::{CancellationToken, CapabilitySet, ProgressSender, Result, SpawnError, Tool, ToolPermission};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::processes::{ProcessInfo, ProcessManager, StartProcess};
+use crate::scratch;
+use crate::tools::output_with_progress;
+
+/// Output lines kept in a run's result
+const OUTPUT_TAIL_LINES: usize = 50;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+impl PackageManager {
+    /// From the lockfile in `root`; npm when there is none
+    pub fn detect(root: &Path) -> Self {
+        if root.join("pnpm-lock.yaml").exists() {
+            PackageManager::Pnpm
+        } else if root.join("yarn.lock").exists() {
+            PackageManager::Yarn
+        } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+            PackageManager::Bun
+        } else {
+            PackageManager::Npm
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// Arguments after the package manager's name that run `script`
+    fn run_args(&self, script: &str, args: &[String]) -> Vec<String> {
+        let mut all = vec!["run".to_string(), script.to_string()];
+        if !args.is_empty() {
+            all.push("--".to_string());
+            all.extend(args.iter().cloned());
+        }
+        all
+    }
+}
+
+/// A package's scripts, by name
+pub fn scripts(root: &Path) -> Result<BTreeMap<String, String>> {
+    let path = root.join("package.json");
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| SpawnError::ToolError(format!("Failed to read {}: {}", path.display(), e)))?;
+    let manifest: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| SpawnError::ToolError(format!("Invalid package.json: {}", e)))?;
+    Ok(manifest["scripts"].as_object()
+        .map(|scripts| scripts.iter()
+            .filter_map(|(name, command)| Some((name.clone(), command.as_str()?.to_string())))
+            .collect())
+        .unwrap_or_default())
+}
+
+/// How a finished script run went
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptRun {
+    pub script: String,
+    /// The command line that ran it (`pnpm run build`)
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Last lines of combined stdout and stderr
+    pub output: Vec<String>,
+}
+
+/// Where and how scripts of one package run
+pub struct ScriptRunner {
+    root: PathBuf,
+    manager: PackageManager,
+    scripts: BTreeMap<String, String>,
+}
+
+impl ScriptRunner {
+    /// The package at `root`; fails without a readable `package.json`
+    pub fn load(root: &Path) -> Result<Self> {
+        Ok(Self { root: root.to_path_buf(), manager: PackageManager::detect(root), scripts: scripts(root)? })
+    }
+
+    /// The package in `dir` under `workspace`, or at its root
+    pub fn in_workspace(workspace: &Path, dir: Option<&str>) -> Result<Self> {
+        let Some(dir) = dir else {
+            return Self::load(workspace);
+        };
+        let root = super::within_workspace(workspace, dir)
+            .ok_or_else(|| SpawnError::ToolError("dir must be inside the workspace".into()))?;
+        Self::load(&root)
+    }
+
+    pub fn package_manager(&self) -> PackageManager {
+        self.manager
+    }
+
+    pub fn scripts(&self) -> &BTreeMap<String, String> {
+        &self.scripts
+    }
+
+    fn check(&self, script: &str) -> Result<()> {
+        if self.scripts.contains_key(script) {
+            return Ok(());
+        }
+        Err(SpawnError::ToolError(format!(
+            "No script '{}' in package.json. Available: {}",
+            script, self.scripts.keys().cloned().collect::<Vec<_>>().join(", ")
+        )))
+    }
+
+    /// Run `script` to completion, sending its output to `progress` line by line
+    pub async fn run(&self, script: &str, args: &[String], progress: &ProgressSender, cancel: &CancellationToken) -> Result<ScriptRun> {
+        self.check(script)?;
+        let run_args = self.manager.run_args(script, args);
+        let mut cmd = Command::new(self.manager.name());
+        cmd.args(&run_args).current_dir(&self.root);
+        scratch::apply_env(&mut cmd);
+
+        info!(script, package_manager = self.manager.name(), "Running package script");
+        let started = Instant::now();
+        let output = tokio::select! {
+            output = output_with_progress(&mut cmd, progress) => output
+                .map_err(|e| SpawnError::ToolError(format!("Failed to run {}: {}", self.manager.name(), e)))?,
+            _ = cancel.cancelled() => return Err(SpawnError::Cancelled),
+        };
+
+        let combined = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        let lines: Vec<&str> = combined.lines().filter(|l| !l.trim().is_empty()).collect();
+        Ok(ScriptRun {
+            script: script.to_string(),
+            command: format!("{} {}", self.manager.name(), run_args.join(" ")),
+            exit_code: output.status.code(),
+            success: output.status.success(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            output: lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].iter().map(|l| l.to_string()).collect(),
+        })
+    }
+
+    /// Start `script` as a managed process and return without waiting for it
+    pub async fn start(&self, processes: &ProcessManager, script: &str, args: &[String], port: Option<u16>) -> Result<ProcessInfo> {
+        self.check(script)?;
+        let mut command = vec![self.manager.name().to_string()];
+        command.extend(self.manager.run_args(script, args).iter().map(|a| shell_quote(a)));
+        let cwd = self.root.strip_prefix(processes.workspace_root()).unwrap_or(Path::new(""));
+        processes.start(StartProcess {
+            name: format!("{} {}", self.manager.name(), script),
+            command: command.join(" "),
+            cwd: Some(cwd.to_string_lossy().into_owned()).filter(|c| !c.is_empty()),
+            port,
+            env: scratch::current()
+                .map(|dir| HashMap::from([(scratch::ENV_VAR.to_string(), dir.to_string_lossy().into_owned())]))
+                .unwrap_or_default(),
+        }).await
+    }
+}
+
+/// `arg` as one word for `bash -c`
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+// ============================================
+// Tool
+// ============================================
+
+pub struct NpmScriptTool {
+    root: PathBuf,
+    processes: Arc<ProcessManager>,
+}
+
+impl NpmScriptTool {
+    pub fn new(root: impl Into<PathBuf>, processes: Arc<ProcessManager>) -> Self {
+        Self { root: root.into(), processes }
+    }
+}
+
+#[async_trait]
+impl Tool for NpmScriptTool {
+    fn name(&self) -> &str { "npm_script" }
+
+    fn description(&self) -> &str {
+        "List package.json scripts, run one to completion (build, test, lint) or start a long-running one (dev, watch) as a managed process"
+    }
+
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new()
+            .with(ToolPermission::FilesystemRead)
+            .with(ToolPermission::ProcessSpawn)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["list", "run", "start"] },
+                "script": { "type": "string", "description": "Script name (run/start)" },
+                "args": { "type": "array", "items": { "type": "string" }, "description": "Extra arguments passed to the script" },
+                "dir": { "type": "string", "description": "Package directory relative to the workspace (default: root)" },
+                "port": { "type": "integer", "description": "Port a started script will listen on" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value> {
+        self.execute_with_progress(args, &ProgressSender::default(), cancel).await
+    }
+
+    async fn execute_with_progress(
+        &self,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
+        let runner = ScriptRunner::in_workspace(&self.root, args["dir"].as_str())?;
+        let script = || args["script"].as_str()
+            .ok_or_else(|| SpawnError::ToolError("Missing script".into()));
+        let script_args: Vec<String> = args["args"].as_array()
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(scratch::expand)).collect())
+            .unwrap_or_default();
+
+        match args["action"].as_str().unwrap_or("list") {
+            "list" => Ok(serde_json::json!({
+                "package_manager": runner.package_manager(),
+                "scripts": runner.scripts(),
+            })),
+            "run" => Ok(serde_json::to_value(runner.run(script()?, &script_args, progress, cancel).await?)?),
+            "start" => {
+                let port = args["port"].as_u64().and_then(|p| u16::try_from(p).ok());
+                Ok(serde_json::to_value(runner.start(&self.processes, script()?, &script_args, port).await?)?)
+            }
+            other => Err(SpawnError::ToolError(format!("Unknown action: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_scripts_and_package_manager() {
+        let root = std::env::temp_dir().join(format!("spawn-npm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("package.json"), r#"{"scripts": {"build": "vite build", "dev": "vite"}}"#).unwrap();
+        std::fs::write(root.join("pnpm-lock.yaml"), "").unwrap();
+
+        let runner = ScriptRunner::load(&root).unwrap();
+        assert_eq!(runner.package_manager(), PackageManager::Pnpm);
+        assert_eq!(runner.scripts().keys().collect::<Vec<_>>(), vec!["build", "dev"]);
+        assert!(runner.check("deploy").is_err());
+        assert_eq!(PackageManager::Npm.run_args("test", &["--watch=false".into()]), vec!["run", "test", "--", "--watch=false"]);
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,81 @@
+//! Process tool - start and manage dev servers / watchers
+
+use async_trait::async_trait;
+use spawn_core::{CancellationToken, CapabilitySet, Result, SpawnError, Tool, ToolPermission};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::processes::{ProcessManager, StartProcess};
+
+pub struct ProcessTool {
+    manager: Arc<ProcessManager>,
+}
+
+impl ProcessTool {
+    pub fn new(manager: Arc<ProcessManager>) -> Self {
+        Self { manager }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessTool {
+    fn name(&self) -> &str { "process" }
+
+    fn description(&self) -> &str {
+        "Start, list, stop, restart long-running processes (dev servers, watchers) and read their output"
+    }
+    
+    fn capabilities(&self) -> CapabilitySet {
+        CapabilitySet::new().with(ToolPermission::ProcessSpawn)
+    }
+
+    fn parameters(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": { "type": "string", "enum": ["start", "list", "stop", "restart", "logs"] },
+                "id": { "type": "string", "description": "Process id (stop/restart/logs)" },
+                "name": { "type": "string", "description": "Label for a new process" },
+                "command": { "type": "string", "description": "Shell command to start" },
+                "cwd": { "type": "string", "description": "Working directory relative to the workspace" },
+                "port": { "type": "integer", "description": "Port the process will listen on" },
+                "lines": { "type": "integer", "description": "Number of output lines (logs)" }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value, _cancel: &CancellationToken) -> Result<serde_json::Value> {
+        let action = args["action"].as_str()
+            .ok_or_else(|| SpawnError::ToolError("Missing action".into()))?;
+        let id = || args["id"].as_str()
+            .ok_or_else(|| SpawnError::ToolError(format!("'{}' requires an id", action)));
+
+        match action {
+            "start" => {
+                let command = args["command"].as_str()
+                    .ok_or_else(|| SpawnError::ToolError("Missing command".into()))?;
+                let info = self.manager.start(StartProcess {
+                    name: args["name"].as_str().unwrap_or(command).to_string(),
+                    command: command.to_string(),
+                    cwd: args["cwd"].as_str().map(String::from),
+                    port: args["port"].as_u64().and_then(|p| u16::try_from(p).ok()),
+                    env: crate::scratch::current()
+                        .map(|dir| HashMap::from([(crate::scratch::ENV_VAR.to_string(), dir.to_string_lossy().into_owned())]))
+                        .unwrap_or_default(),
+                }).await?;
+                Ok(serde_json::to_value(info)?)
+            }
+            "list" => Ok(serde_json::to_value(self.manager.list().await)?),
+            "stop" => Ok(serde_json::to_value(self.manager.stop(id()?).await?)?),
+            "restart" => Ok(serde_json::to_value(self.manager.restart(id()?).await?)?),
+            "logs" => {
+                let lines = args["lines"].as_u64().unwrap_or(50) as usize;
+                let output = self.manager.output(id()?, lines).await
+                    .ok_or_else(|| SpawnError::ToolError("Process not found".into()))?;
+                Ok(serde_json::json!({ "output": output }))
+            }
+            other => Err(SpawnError::ToolError(format!("Unknown action: {}", other))),
+        }
+    }
+}
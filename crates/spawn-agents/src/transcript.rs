@@ -0,0 +1,110 @@
+//! Markdown rendering of chat sessions
+//!
+//! Turns a stored conversation into a transcript suitable for pasting into a
+//! PR description or a doc. User and assistant turns are rendered as prose;
+//! tool calls and tool results are folded into `<details>` blocks so they
+//! don't drown out the conversation.
+
+use crate::vector_memory::StoredChatMessage;
+
+/// Tool output longer than this is cut in the transcript
+const MAX_TOOL_OUTPUT_CHARS: usize = 4000;
+
+/// Render a session's messages, oldest first, as Markdown
+pub fn render_markdown(session_id: &str, messages: &[StoredChatMessage]) -> String {
+    let mut out = format!("# Chat transcript `{}`\n", session_id);
+    if let (Some(first), Some(last)) = (messages.first(), messages.last()) {
+        out.push_str(&format!(
+            "\n_{} messages, {} – {}_\n",
+            messages.len(),
+            first.created_at.format("%Y-%m-%d %H:%M UTC"),
+            last.created_at.format("%Y-%m-%d %H:%M UTC"),
+        ));
+    }
+
+    for message in messages {
+        match message.role.as_str() {
+            "system" => continue,
+            "tool" => out.push_str(&details("Tool result", &message.content)),
+            role => {
+                out.push_str(&format!("\n## {}\n\n{}\n", heading(role), message.content.trim()));
+                for call in message.tool_calls.as_array().into_iter().flatten() {
+                    out.push_str(&tool_call(call));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn heading(role: &str) -> String {
+    let mut chars = role.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Message".to_string(),
+    }
+}
+
+/// A tool call as `{name, arguments}` or OpenAI's `{function: {name, arguments}}`
+fn tool_call(call: &serde_json::Value) -> String {
+    let function = call.get("function").unwrap_or(call);
+    let name = function["name"].as_str().unwrap_or("tool");
+    let arguments = match &function["arguments"] {
+        // OpenAI sends arguments as a JSON-encoded string
+        serde_json::Value::String(s) => serde_json::from_str::<serde_json::Value>(s)
+            .ok()
+            .and_then(|v| serde_json::to_string_pretty(&v).ok())
+            .unwrap_or_else(|| s.clone()),
+        serde_json::Value::Null => String::new(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    };
+    details(&format!("Tool call: `{}`", name), &arguments)
+}
+
+fn details(summary: &str, body: &str) -> String {
+    let mut body = body.trim().to_string();
+    if body.chars().count() > MAX_TOOL_OUTPUT_CHARS {
+        body = body.chars().take(MAX_TOOL_OUTPUT_CHARS).collect::<String>() + "\n… (truncated)";
+    }
+    // A fence longer than any backtick run inside keeps the block intact
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("\n<details>\n<summary>{}</summary>\n\n{}\n{}\n{}\n\n</details>\n", summary, fence, body, fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str, tool_calls: serde_json::Value) -> StoredChatMessage {
+        StoredChatMessage {
+            id: String::new(),
+            session_id: "s1".into(),
+            role: role.into(),
+            content: content.into(),
+            tool_calls,
+            metadata: serde_json::json!({}),
+            created_at: chrono::Utc::now(),
+            embedding: None,
+        }
+    }
+
+    #[test]
+    fn collapses_tool_calls_and_results() {
+        let messages = vec![
+            message("system", "secret prompt", serde_json::json!([])),
+            message("user", "List files", serde_json::json!([])),
+            message("assistant", "Checking.", serde_json::json!([
+                { "function": { "name": "shell", "arguments": "{\"command\":\"ls\"}" } }
+            ])),
+            message("tool", "a.rs\n```\nb.rs", serde_json::json!([])),
+        ];
+        let md = render_markdown("s1", &messages);
+
+        assert!(!md.contains("secret prompt"));
+        assert!(md.contains("## User\n\nList files"));
+        assert!(md.contains("<summary>Tool call: `shell`</summary>"));
+        assert!(md.contains("\"command\": \"ls\""));
+        assert!(md.contains("````\na.rs\n```\nb.rs\n````"));
+    }
+}
@@ -3,7 +3,9 @@
 //! Provides embedding-based search over code, chat history, and mission context.
 
 use serde::{Deserialize, Serialize};
-use spawn_core::Result;
+use spawn_core::{ChatMessage, Conversation, DataSubject, Document, EmbeddingClient, HealthCheck, Result, RetentionPolicy, Role, ToolCall};
+use std::path::Path;
+use std::sync::Arc;
 use tracing::warn;
 
 #[cfg(feature = "postgres")]
@@ -15,6 +17,26 @@ use tracing::info;
 #[cfg(feature = "postgres")]
 use sqlx::PgPool;
 
+#[cfg(feature = "postgres")]
+use crate::privacy::scrub_pii;
+
+#[cfg(feature = "postgres")]
+use crate::ignore::IgnoreRules;
+
+/// Language name for a file extension, as clients index files with
+#[cfg(feature = "postgres")]
+fn language_of(extension: &str) -> String {
+    match extension {
+        "rs" => "rust",
+        "ts" | "tsx" => "typescript",
+        "js" | "jsx" | "mjs" => "javascript",
+        "py" => "python",
+        "md" => "markdown",
+        other => other,
+    }
+    .to_string()
+}
+
 /// Embedding dimensions (OpenAI text-embedding-3-small)
 pub const EMBEDDING_DIMENSIONS: usize = 1536;
 
@@ -26,6 +48,7 @@ pub enum ContentType {
     Chat,
     Mission,
     File,
+    Doc,
 }
 
 impl std::fmt::Display for ContentType {
@@ -35,6 +58,7 @@ impl std::fmt::Display for ContentType {
             ContentType::Chat => write!(f, "chat"),
             ContentType::Mission => write!(f, "mission"),
             ContentType::File => write!(f, "file"),
+            ContentType::Doc => write!(f, "doc"),
         }
     }
 }
@@ -59,20 +83,129 @@ pub struct SearchResult {
     pub content_preview: String,
     pub similarity: f32,
     pub metadata: serde_json::Value,
+    /// Relevance assigned by a rerank pass, when one ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_score: Option<f32>,
+}
+
+/// Diagnostics for one search, returned by `VectorMemory::explain`
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchExplain {
+    pub query: String,
+    pub embedding_model: String,
+    pub embedding_dimensions: usize,
+    /// L2 norm of the query embedding; far from 1.0 suggests a bad embedding
+    pub embedding_norm: f32,
+    pub filters: ExplainFilters,
+    /// Rows the filters left to rank
+    pub rows_considered: i64,
+    /// Nearest rows, including some past `limit` that a normal search would drop
+    pub candidates: Vec<ExplainCandidate>,
+    pub timings: ExplainTimings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainFilters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub limit: i32,
+    pub candidate_pool: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainCandidate {
+    pub rank: usize,
+    pub id: String,
+    pub content_type: String,
+    pub content_preview: String,
+    /// Raw pgvector cosine distance (`<=>`); similarity is `1 - distance`
+    pub distance: f32,
+    pub similarity: f32,
+    /// Whether a search with the same limit would have returned it
+    pub returned: bool,
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExplainTimings {
+    pub embed_ms: u64,
+    pub count_ms: u64,
+    pub query_ms: u64,
+    pub total_ms: u64,
+}
+/// Candidate pool fetched by `explain`, as a multiple of the search limit
+/// How many extra neighbours `explain` fetches beyond the search limit
+#[cfg(feature = "postgres")]
+const EXPLAIN_POOL_FACTOR: i32 = 5;
+
+/// A chat message as stored, for data subject exports
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredChatMessage {
+    pub id: String,
+    pub session_id: String,
+    pub role: String,
+    pub content: String,
+    pub tool_calls: serde_json::Value,
+    pub metadata: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+impl StoredChatMessage {
+    /// The message as the rest of the system handles it
+    pub fn to_message(&self) -> ChatMessage {
+        let role: Role = serde_json::from_value(serde_json::json!(self.role)).unwrap_or(Role::User);
+        let mut message = ChatMessage::new(role, self.content.as_str()).with_created_at(self.created_at);
+        message.tool_calls = serde_json::from_value::<Vec<ToolCall>>(self.tool_calls.clone()).ok().filter(|c| !c.is_empty());
+        message.tool_call_id = self.metadata["tool_call_id"].as_str().map(String::from);
+        if self.metadata.as_object().is_some_and(|m| !m.is_empty()) {
+            message.metadata = Some(self.metadata.clone());
+        }
+        message
+    }
+}
+
+/// An embedding row as stored, for data subject exports
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredEmbedding {
+    pub id: String,
+    pub content_type: String,
+    pub content_id: String,
+    pub content_preview: String,
+    pub metadata: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Everything stored about a data subject's conversations
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChatExport {
+    pub messages: Vec<StoredChatMessage>,
+    pub embeddings: Vec<StoredEmbedding>,
+}
+
+/// Rows removed by a retention pass or an erasure request
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    pub chat_messages: u64,
+    pub embeddings: u64,
 }
 
 /// Vector memory store backed by PostgreSQL + pgvector
 #[cfg(feature = "postgres")]
 pub struct VectorMemory {
     pool: PgPool,
-    embedding_api_key: String,
+    embedder: Arc<dyn EmbeddingClient>,
     embedding_model: String,
+    retention: RetentionPolicy,
 }
 
 #[cfg(feature = "postgres")]
 impl VectorMemory {
-    /// Create a new vector memory store
-    pub async fn connect(database_url: &str, api_key: &str) -> Result<Self> {
+    /// Create a new vector memory store, embedding with `embedder`
+    pub async fn connect(database_url: &str, embedder: Arc<dyn EmbeddingClient>) -> Result<Self> {
         info!("Connecting to PostgreSQL with pgvector");
         let pool = PgPool::connect(database_url).await?;
 
@@ -87,36 +220,35 @@ impl VectorMemory {
 
         Ok(Self {
             pool,
-            embedding_api_key: api_key.to_string(),
+            embedder,
             embedding_model: "openai/text-embedding-3-small".to_string(),
+            retention: RetentionPolicy::default(),
         })
     }
 
-    /// Generate embedding for text using OpenRouter
-    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let client = reqwest::Client::new();
-
-        let response = client
-            .post("https://openrouter.ai/api/v1/embeddings")
-            .header("Authorization", format!("Bearer {}", self.embedding_api_key))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "model": self.embedding_model,
-                "input": text
-            }))
-            .send()
-            .await?;
+    /// Embed with `model` instead of `openai/text-embedding-3-small`
+    pub fn with_embedding_model(mut self, model: impl Into<String>) -> Self {
+        self.embedding_model = model.into();
+        self
+    }
 
-        let data: serde_json::Value = response.json().await?;
+    /// Apply retention limits and PII scrubbing to conversation memory
+    pub fn with_retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = policy;
+        self
+    }
 
-        let embedding = data["data"][0]["embedding"]
-            .as_array()
-            .ok_or_else(|| spawn_core::SpawnError::Other("Invalid embedding response".to_string()))?
-            .iter()
-            .filter_map(|v| v.as_f64().map(|f| f as f32))
-            .collect();
+    /// Conversation content is scrubbed when enabled; code and docs are stored as-is
+    fn scrub<'a>(&self, content_type: &ContentType, content: &'a str) -> std::borrow::Cow<'a, str> {
+        match content_type {
+            ContentType::Chat | ContentType::Mission if self.retention.scrub_pii => scrub_pii(content).into(),
+            _ => content.into(),
+        }
+    }
 
-        Ok(embedding)
+    /// Generate embedding for text with the embedding model
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embedder.embed(&self.embedding_model, text).await
     }
 
     /// Compute content hash for deduplication
@@ -134,6 +266,7 @@ impl VectorMemory {
         content: &str,
         metadata: serde_json::Value,
     ) -> Result<String> {
+        let content = &*self.scrub(&content_type, content);
         let hash = Self::content_hash(content);
         let preview = content.chars().take(500).collect::<String>();
         let embedding = self.embed(content).await?;
@@ -165,6 +298,10 @@ impl VectorMemory {
     /// Store a code chunk with embedding
     pub async fn store_code_chunk(&self, chunk: &CodeChunk) -> Result<String> {
         let embedding = self.embed(&chunk.content).await?;
+        self.insert_code_chunk(chunk, &embedding).await
+    }
+
+    async fn insert_code_chunk(&self, chunk: &CodeChunk, embedding: &[f32]) -> Result<String> {
         let embedding_str = format!("[{}]",
             embedding.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(","));
 
@@ -202,18 +339,24 @@ impl VectorMemory {
     pub async fn store_chat(
         &self,
         session_id: &str,
+        user_id: Option<&str>,
         role: &str,
         content: &str,
         tool_calls: Vec<serde_json::Value>,
     ) -> Result<String> {
+        let content = &*self.scrub(&ContentType::Chat, content);
+        let metadata = match user_id {
+            Some(user_id) => serde_json::json!({ "user_id": user_id }),
+            None => serde_json::json!({}),
+        };
         let embedding = self.embed(content).await?;
         let embedding_str = format!("[{}]",
             embedding.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(","));
 
         let id: (uuid::Uuid,) = sqlx::query_as(
             r#"
-            INSERT INTO chat_history (session_id, role, content, embedding, tool_calls)
-            VALUES ($1, $2, $3, $4::vector, $5)
+            INSERT INTO chat_history (session_id, role, content, embedding, tool_calls, metadata)
+            VALUES ($1, $2, $3, $4::vector, $5, $6)
             RETURNING id
             "#
         )
@@ -222,6 +365,7 @@ impl VectorMemory {
         .bind(content)
         .bind(&embedding_str)
         .bind(serde_json::json!(tool_calls))
+        .bind(metadata)
         .fetch_one(&self.pool)
         .await?;
 
@@ -264,9 +408,84 @@ impl VectorMemory {
             content_preview: preview,
             similarity: sim,
             metadata: meta,
+            rerank_score: None,
         }).collect())
     }
 
+    /// Run `search` with diagnostics: the query embedding's norm, raw
+    /// distances for a wider pool of neighbours, and where the time went
+    pub async fn explain(
+        &self,
+        query: &str,
+        content_type: Option<ContentType>,
+        limit: i32,
+    ) -> Result<SearchExplain> {
+        let started = std::time::Instant::now();
+        let query_embedding = self.embed(query).await?;
+        let embed_ms = started.elapsed().as_millis() as u64;
+        let embedding_norm = query_embedding.iter().map(|f| f * f).sum::<f32>().sqrt();
+        let embedding_str = format!("[{}]",
+            query_embedding.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(","));
+
+        let type_filter = content_type.map(|t| t.to_string());
+        let candidate_pool = limit.max(1) * EXPLAIN_POOL_FACTOR;
+
+        let counted = std::time::Instant::now();
+        let (rows_considered,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM embeddings WHERE ($1::text IS NULL OR content_type = $1)"
+        )
+        .bind(&type_filter)
+        .fetch_one(&self.pool)
+        .await?;
+        let count_ms = counted.elapsed().as_millis() as u64;
+
+        let queried = std::time::Instant::now();
+        let rows: Vec<(uuid::Uuid, String, String, f32, serde_json::Value)> = sqlx::query_as(
+            r#"
+            SELECT id, content_type, content_preview,
+                   (embedding <=> $1::vector)::real as distance,
+                   metadata
+            FROM embeddings
+            WHERE ($2::text IS NULL OR content_type = $2)
+            ORDER BY embedding <=> $1::vector
+            LIMIT $3
+            "#
+        )
+        .bind(&embedding_str)
+        .bind(&type_filter)
+        .bind(candidate_pool)
+        .fetch_all(&self.pool)
+        .await?;
+        let query_ms = queried.elapsed().as_millis() as u64;
+
+        let candidates = rows.into_iter().enumerate().map(|(i, (id, ct, preview, distance, meta))| ExplainCandidate {
+            rank: i + 1,
+            id: id.to_string(),
+            content_type: ct,
+            content_preview: preview,
+            distance,
+            similarity: 1.0 - distance,
+            returned: (i as i32) < limit,
+            metadata: meta,
+        }).collect();
+
+        Ok(SearchExplain {
+            query: query.to_string(),
+            embedding_model: self.embedding_model.clone(),
+            embedding_dimensions: query_embedding.len(),
+            embedding_norm,
+            filters: ExplainFilters { content_type: type_filter, limit, candidate_pool },
+            rows_considered,
+            candidates,
+            timings: ExplainTimings {
+                embed_ms,
+                count_ms,
+                query_ms,
+                total_ms: started.elapsed().as_millis() as u64,
+            },
+        })
+    }
+
     /// Search code specifically
     pub async fn search_code(
         &self,
@@ -295,13 +514,19 @@ impl VectorMemory {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows.into_iter().map(|(id, path, content, start, end, sim, meta)| SearchResult {
-            id: id.to_string(),
-            content_type: "code".to_string(),
-            content_preview: format!("{}:{}-{}\n{}", path, start, end,
-                content.chars().take(200).collect::<String>()),
-            similarity: sim,
-            metadata: meta,
+        Ok(rows.into_iter().map(|(id, path, content, start, end, sim, mut meta)| {
+            if let Some(meta) = meta.as_object_mut() {
+                meta.insert("file_path".into(), serde_json::json!(path));
+            }
+            SearchResult {
+                id: id.to_string(),
+                content_type: "code".to_string(),
+                content_preview: format!("{}:{}-{}\n{}", path, start, end,
+                    content.chars().take(200).collect::<String>()),
+                similarity: sim,
+                metadata: meta,
+                rerank_score: None,
+            }
         }).collect())
     }
 
@@ -339,10 +564,12 @@ impl VectorMemory {
             content_preview: content.chars().take(300).collect(),
             similarity: sim,
             metadata: meta,
+            rerank_score: None,
         }).collect())
     }
 
-    /// Index an entire file by chunking it intelligently
+    /// Index an entire file by chunking it intelligently, replacing the
+    /// chunks it had
     pub async fn index_file(&self, file_path: &str, content: &str, language: &str) -> Result<usize> {
         // Simple line-based chunking for now
         // TODO: Use tree-sitter for AST-based chunking
@@ -350,7 +577,7 @@ impl VectorMemory {
         let chunk_size = 50;  // lines per chunk
         let overlap = 10;     // overlap between chunks
 
-        let mut chunks_indexed = 0;
+        let mut chunks = Vec::new();
         let mut i = 0;
 
         while i < lines.len() {
@@ -358,7 +585,7 @@ impl VectorMemory {
             let chunk_content = lines[i..end].join("\n");
 
             if !chunk_content.trim().is_empty() {
-                let chunk = CodeChunk {
+                chunks.push(CodeChunk {
                     file_path: file_path.to_string(),
                     language: language.to_string(),
                     chunk_type: "block".to_string(),
@@ -366,18 +593,305 @@ impl VectorMemory {
                     start_line: (i + 1) as i32,
                     end_line: end as i32,
                     content: chunk_content,
-                };
-
-                self.store_code_chunk(&chunk).await?;
-                chunks_indexed += 1;
+                });
             }
 
             i += chunk_size - overlap;
         }
 
+        // One round of embedding requests for the whole file
+        let contents: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        let embeddings = self.embedder.embed_batch(&self.embedding_model, &contents).await?;
+        self.delete_file(file_path).await?;
+        for (chunk, embedding) in chunks.iter().zip(&embeddings) {
+            self.insert_code_chunk(chunk, embedding).await?;
+        }
+        let chunks_indexed = chunks.len();
+
         info!(file = file_path, chunks = chunks_indexed, "Indexed file");
         Ok(chunks_indexed)
     }
+
+    /// Remove a file's chunks
+    pub async fn delete_file(&self, file_path: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM code_chunks WHERE file_path = $1")
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Bring the indexed ones among `paths` (relative to `root`) up to date
+    /// with the files on disk, dropping those since removed or ignored.
+    /// Files that aren't indexed stay that way. Returns the files updated.
+    pub async fn refresh_files(&self, root: &Path, paths: &[String]) -> Result<usize> {
+        let indexed: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT file_path FROM code_chunks WHERE file_path = ANY($1)")
+            .bind(paths)
+            .fetch_all(&self.pool)
+            .await?;
+        let ignore = IgnoreRules::load(root);
+        for (path,) in &indexed {
+            let full_path = root.join(path);
+            match tokio::fs::read_to_string(&full_path).await {
+                Ok(content) if !ignore.is_ignored(&full_path, false) => {
+                    let language = Path::new(path).extension().map(|e| language_of(&e.to_string_lossy())).unwrap_or_default();
+                    self.index_file(path, &content, &language).await?;
+                }
+                _ => {
+                    self.delete_file(path).await?;
+                }
+            }
+        }
+        Ok(indexed.len())
+    }
+
+    /// Delete conversation memory beyond the retention limits
+    pub async fn prune(&self) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+
+        if let Some(days) = self.retention.max_age_days {
+            report.chat_messages += sqlx::query(
+                "DELETE FROM chat_history WHERE created_at < NOW() - make_interval(days => $1)"
+            )
+            .bind(days as i32)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+            report.embeddings += sqlx::query(
+                r#"
+                DELETE FROM embeddings
+                WHERE content_type IN ('chat', 'mission')
+                  AND updated_at < NOW() - make_interval(days => $1)
+                "#
+            )
+            .bind(days as i32)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        }
+
+        if let Some(max) = self.retention.max_messages_per_session {
+            let mut tx = self.pool.begin().await?;
+            let pruned: Vec<String> = sqlx::query_scalar(
+                r#"
+                DELETE FROM chat_history WHERE id IN (
+                    SELECT id FROM (
+                        SELECT id, ROW_NUMBER() OVER (PARTITION BY session_id ORDER BY created_at DESC) AS rn
+                        FROM chat_history
+                    ) ranked
+                    WHERE rn > $1
+                )
+                RETURNING id::text
+                "#
+            )
+            .bind(max as i64)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            // Embeddings of the dropped messages go with them
+            report.embeddings += sqlx::query("DELETE FROM embeddings WHERE content_type = 'chat' AND content_id = ANY($1)")
+                .bind(&pruned)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            tx.commit().await?;
+            report.chat_messages += pruned.len() as u64;
+        }
+
+        if report.chat_messages + report.embeddings > 0 {
+            info!(chat_messages = report.chat_messages, embeddings = report.embeddings, "Pruned conversation memory");
+        }
+        Ok(report)
+    }
+
+    /// All chat messages and embeddings stored for a data subject; raw
+    /// vectors are included only when asked for
+    pub async fn export_chat(&self, subject: &DataSubject, include_vectors: bool) -> Result<ChatExport> {
+        let (session, user) = subject_filter(subject);
+
+        let messages: Vec<(uuid::Uuid, String, String, String, serde_json::Value, serde_json::Value, chrono::DateTime<chrono::Utc>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, session_id, role, content, tool_calls, metadata, created_at,
+                   CASE WHEN $3 THEN embedding::text END
+            FROM chat_history
+            WHERE ($1::text IS NULL OR session_id = $1)
+              AND ($2::text IS NULL OR metadata->>'user_id' = $2)
+            ORDER BY created_at
+            "#
+        )
+        .bind(session)
+        .bind(user)
+        .bind(include_vectors)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let embeddings: Vec<(uuid::Uuid, String, String, String, serde_json::Value, chrono::DateTime<chrono::Utc>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, content_type, content_id, content_preview, metadata, created_at,
+                   CASE WHEN $3 THEN embedding::text END
+            FROM embeddings
+            WHERE ($1::text IS NULL OR metadata->>'session_id' = $1)
+              AND ($2::text IS NULL OR metadata->>'user_id' = $2)
+            ORDER BY created_at
+            "#
+        )
+        .bind(session)
+        .bind(user)
+        .bind(include_vectors)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ChatExport {
+            messages: messages.into_iter()
+                .map(|(id, session_id, role, content, tool_calls, metadata, created_at, vector)| StoredChatMessage {
+                    id: id.to_string(),
+                    session_id,
+                    role,
+                    content,
+                    tool_calls,
+                    metadata,
+                    created_at,
+                    embedding: vector.as_deref().and_then(parse_vector),
+                })
+                .collect(),
+            embeddings: embeddings.into_iter()
+                .map(|(id, content_type, content_id, content_preview, metadata, created_at, vector)| StoredEmbedding {
+                    id: id.to_string(),
+                    content_type,
+                    content_id,
+                    content_preview,
+                    metadata,
+                    created_at,
+                    embedding: vector.as_deref().and_then(parse_vector),
+                })
+                .collect(),
+        })
+    }
+
+    /// Hard-delete a data subject's chat messages and embeddings, vectors included
+    pub async fn delete_chat(&self, subject: &DataSubject) -> Result<PruneReport> {
+        let (session, user) = subject_filter(subject);
+        let mut tx = self.pool.begin().await?;
+
+        let chat_messages = sqlx::query(
+            r#"
+            DELETE FROM chat_history
+            WHERE ($1::text IS NULL OR session_id = $1)
+              AND ($2::text IS NULL OR metadata->>'user_id' = $2)
+            "#
+        )
+        .bind(session)
+        .bind(user)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let embeddings = sqlx::query(
+            r#"
+            DELETE FROM embeddings
+            WHERE ($1::text IS NULL OR metadata->>'session_id' = $1)
+              AND ($2::text IS NULL OR metadata->>'user_id' = $2)
+            "#
+        )
+        .bind(session)
+        .bind(user)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        info!(subject = %subject, chat_messages, embeddings, "Erased conversation memory");
+        Ok(PruneReport { chat_messages, embeddings })
+    }
+
+    /// Embed a knowledge base document's chunks as `doc` content
+    pub async fn index_document(&self, doc: &Document, chunks: &[String]) -> Result<usize> {
+        for (i, chunk) in chunks.iter().enumerate() {
+            let metadata = serde_json::json!({
+                "workspace": doc.workspace,
+                "title": doc.title,
+                "filename": doc.filename,
+                "chunk": i,
+                // Previews are cut at 500 chars; retrieval needs the whole chunk
+                "text": chunk,
+            });
+            self.store_embedding(ContentType::Doc, &doc.id, chunk, metadata).await?;
+        }
+
+        info!(doc = %doc.id, chunks = chunks.len(), "Indexed document");
+        Ok(chunks.len())
+    }
+
+    /// Remove a document's chunks
+    pub async fn delete_document(&self, doc_id: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM embeddings WHERE content_type = 'doc' AND content_id = $1")
+            .bind(doc_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Document chunks in a workspace most relevant to a query; each result's
+    /// `metadata.doc_id` names the document it came from
+    pub async fn search_docs(&self, query: &str, workspace: &str, limit: i32) -> Result<Vec<SearchResult>> {
+        let query_embedding = self.embed(query).await?;
+        let embedding_str = format!("[{}]",
+            query_embedding.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(","));
+
+        let rows: Vec<(uuid::Uuid, String, String, f32, serde_json::Value)> = sqlx::query_as(
+            r#"
+            SELECT id, content_id, content_preview,
+                   1 - (embedding <=> $1::vector) as similarity,
+                   metadata
+            FROM embeddings
+            WHERE content_type = 'doc' AND metadata->>'workspace' = $2
+            ORDER BY embedding <=> $1::vector
+            LIMIT $3
+            "#
+        )
+        .bind(&embedding_str)
+        .bind(workspace)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id, doc_id, preview, sim, mut meta)| {
+            let text = meta.as_object_mut()
+                .and_then(|m| m.remove("text"))
+                .and_then(|t| t.as_str().map(String::from))
+                .unwrap_or(preview);
+            meta["doc_id"] = serde_json::Value::String(doc_id);
+            SearchResult {
+                id: id.to_string(),
+                content_type: "doc".to_string(),
+                content_preview: text,
+                similarity: sim,
+                metadata: meta,
+                rerank_score: None,
+            }
+        }).collect())
+    }
+}
+
+/// `(session_id, user_id)` bind values selecting a data subject's rows
+#[cfg(feature = "postgres")]
+fn subject_filter(subject: &DataSubject) -> (Option<&str>, Option<&str>) {
+    match subject {
+        DataSubject::Session(id) => (Some(id), None),
+        DataSubject::User(id) => (None, Some(id)),
+    }
+}
+
+/// Parse pgvector's text form (`[0.1,0.2,...]`)
+#[cfg(feature = "postgres")]
+fn parse_vector(text: &str) -> Option<Vec<f32>> {
+    text.trim().trim_start_matches('[').trim_end_matches(']')
+        .split(',')
+        .map(|v| v.trim().parse().ok())
+        .collect()
 }
 
 // Stub implementation when postgres feature is not enabled
@@ -386,15 +900,41 @@ pub struct VectorMemory;
 
 #[cfg(not(feature = "postgres"))]
 impl VectorMemory {
-    pub async fn connect(_database_url: &str, _api_key: &str) -> Result<Self> {
+    pub async fn connect(_database_url: &str, _embedder: Arc<dyn EmbeddingClient>) -> Result<Self> {
         warn!("Vector memory requires 'postgres' feature. Using stub implementation.");
         Ok(Self)
     }
 
+    pub fn with_embedding_model(self, _model: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn with_retention(self, _policy: RetentionPolicy) -> Self {
+        self
+    }
+
+    pub async fn prune(&self) -> Result<PruneReport> {
+        Ok(PruneReport::default())
+    }
+
+    pub async fn store_embedding(
+        &self,
+        _content_type: ContentType,
+        _content_id: &str,
+        _content: &str,
+        _metadata: serde_json::Value,
+    ) -> Result<String> {
+        Ok(String::new())
+    }
+
     pub async fn search(&self, _query: &str, _content_type: Option<ContentType>, _limit: i32) -> Result<Vec<SearchResult>> {
         Ok(vec![])
     }
 
+    pub async fn explain(&self, _query: &str, _content_type: Option<ContentType>, _limit: i32) -> Result<SearchExplain> {
+        Err(spawn_core::SpawnError::Internal("Search explain requires 'postgres' feature".into()))
+    }
+
     pub async fn search_code(&self, _query: &str, _language: Option<&str>, _limit: i32) -> Result<Vec<SearchResult>> {
         Ok(vec![])
     }
@@ -404,9 +944,18 @@ impl VectorMemory {
         Ok(0)
     }
 
+    pub async fn delete_file(&self, _file_path: &str) -> Result<u64> {
+        Ok(0)
+    }
+
+    pub async fn refresh_files(&self, _root: &Path, _paths: &[String]) -> Result<usize> {
+        Ok(0)
+    }
+
     pub async fn store_chat(
         &self,
         _session_id: &str,
+        _user_id: Option<&str>,
         _role: &str,
         _content: &str,
         _tool_calls: Vec<serde_json::Value>,
@@ -423,6 +972,63 @@ impl VectorMemory {
     ) -> Result<Vec<SearchResult>> {
         Ok(vec![])
     }
+
+    pub async fn export_chat(&self, _subject: &DataSubject, _include_vectors: bool) -> Result<ChatExport> {
+        Ok(ChatExport::default())
+    }
+
+    pub async fn delete_chat(&self, _subject: &DataSubject) -> Result<PruneReport> {
+        Ok(PruneReport::default())
+    }
+
+    pub async fn index_document(&self, _doc: &Document, _chunks: &[String]) -> Result<usize> {
+        warn!("index_document requires 'postgres' feature");
+        Ok(0)
+    }
+
+    pub async fn delete_document(&self, _doc_id: &str) -> Result<u64> {
+        Ok(0)
+    }
+
+    pub async fn search_docs(&self, _query: &str, _workspace: &str, _limit: i32) -> Result<Vec<SearchResult>> {
+        Ok(vec![])
+    }
+}
+
+impl VectorMemory {
+    /// A chat session's stored messages, oldest first
+    pub async fn conversation(&self, session_id: &str) -> Result<Conversation> {
+        let export = self.export_chat(&DataSubject::Session(session_id.to_string()), false).await?;
+        let mut conversation = Conversation::new(session_id);
+        conversation.messages = export.messages.iter().map(StoredChatMessage::to_message).collect();
+        Ok(conversation)
+    }
+
+    /// Store one message of a chat session
+    pub async fn store_message(&self, session_id: &str, user_id: Option<&str>, message: &ChatMessage) -> Result<String> {
+        let tool_calls = message.tool_calls.iter().flatten()
+            .map(serde_json::to_value)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        self.store_chat(session_id, user_id, message.role.as_str(), &message.content.text(), tool_calls).await
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthCheck for VectorMemory {
+    fn name(&self) -> &str {
+        "vector_memory"
+    }
+
+    #[cfg(feature = "postgres")]
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        let (vectors,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM embeddings").fetch_one(&self.pool).await?;
+        Ok(Some(serde_json::json!({ "embeddings": vectors })))
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        Err(spawn_core::SpawnError::Internal("Vector memory requires 'postgres' feature".into()))
+    }
 }
 
 #[cfg(all(test, feature = "postgres"))]
@@ -0,0 +1,154 @@
+//! Azure OpenAI client
+//!
+//! Azure serves each model from a named deployment:
+//! `{endpoint}/openai/deployments/{deployment}/chat/completions?api-version=...`,
+//! authenticated with an `api-key` header. Model ids of the form
+//! `azure/<deployment>` pick the deployment; any other id goes to the
+//! configured default one. Bodies and streams are the OpenAI format.
+
+use crate::openrouter::{parse_completion, request_body, sse_deltas};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use spawn_core::{
+    CancellationToken, ChatMessage, ChatOptions, ChatResponse, ChatStream, HttpTimeouts, LlmClient, Result, SpawnError,
+};
+use tracing::{debug, error};
+
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+pub struct AzureOpenAiClient {
+    /// `https://<resource>.openai.azure.com`
+    endpoint: String,
+    api_key: String,
+    deployment: String,
+    api_version: String,
+    client: Client,
+}
+
+impl AzureOpenAiClient {
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>, deployment: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            api_key: api_key.into(),
+            deployment: deployment.into(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+            client: crate::http_client(HttpTimeouts::default()),
+        }
+    }
+
+    pub fn with_api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = version.into();
+        self
+    }
+
+    /// Connect and read timeouts for this provider's requests
+    pub fn with_timeouts(mut self, timeouts: HttpTimeouts) -> Self {
+        self.client = crate::http_client(timeouts);
+        self
+    }
+
+    fn deployment<'a>(&'a self, model: &'a str) -> &'a str {
+        model.strip_prefix("azure/").unwrap_or(&self.deployment)
+    }
+
+    fn completions_url(&self, deployment: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint, deployment, self.api_version
+        )
+    }
+
+    async fn send(&self, deployment: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let res = self.client
+            .post(self.completions_url(deployment))
+            .header("api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, deployment, error = %err, "Azure OpenAI API error");
+            return Err(err);
+        }
+        Ok(res)
+    }
+
+    async fn complete(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        let deployment = self.deployment(model);
+        debug!(deployment, message_count = messages.len(), ?options, "Sending Azure OpenAI chat request");
+        let res = self.send(deployment, &request_body(deployment, messages, options)?).await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        parse_completion(&json, deployment)
+    }
+}
+
+#[async_trait]
+impl LlmClient for AzureOpenAiClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        cancel.run_until_cancelled(crate::Deadline::start(options).run(self.complete(model, messages, options))).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let deployment = self.deployment(model);
+        let mut body = request_body(deployment, messages, options)?;
+        body["stream"] = json!(true);
+        body["stream_options"] = json!({ "include_usage": true });
+        debug!(deployment, message_count = messages.len(), ?options, "Sending streaming Azure OpenAI chat request");
+        let deadline = crate::Deadline::start(options);
+        let res = cancel.run_until_cancelled(deadline.run(self.send(deployment, &body))).await
+            .ok_or(SpawnError::Cancelled)??;
+        Ok(deadline.stream(sse_deltas(res.bytes_stream())))
+    }
+
+    /// Lists the resource's models; no tokens spent
+    async fn health_check(&self, _model: &str, cancel: &CancellationToken) -> Result<()> {
+        let request = self.client
+            .get(format!("{}/openai/models?api-version={}", self.endpoint, self.api_version))
+            .header("api-key", &self.api_key)
+            .send();
+        let res = cancel.run_until_cancelled(request).await
+            .ok_or(SpawnError::Cancelled)?
+            .map_err(crate::request_error)?;
+        if !res.status().is_success() {
+            return Err(crate::api_error(res).await);
+        }
+        Ok(())
+    }
+
+    fn provider_name(&self) -> &str {
+        "azure"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployment_url() {
+        let client = AzureOpenAiClient::new("https://corp.openai.azure.com/", "key", "gpt-4o")
+            .with_api_version("2024-06-01");
+        assert_eq!(
+            client.completions_url(client.deployment("anthropic/claude-sonnet-4")),
+            "https://corp.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(client.deployment("azure/gpt-4o-mini"), "gpt-4o-mini");
+    }
+}
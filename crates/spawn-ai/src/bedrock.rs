@@ -0,0 +1,425 @@
+//! AWS Bedrock client
+//!
+//! Talks to the Bedrock runtime's Converse API (`/model/{id}/converse` and
+//! `/converse-stream`), which takes one request shape for every model family
+//! Bedrock hosts (Anthropic, Llama, Mistral, ...). Requests are signed with
+//! SigV4. Model ids may carry a `bedrock/` prefix
+//! (`bedrock/anthropic.claude-3-5-sonnet-20241022-v2:0`), which is dropped
+//! before they are sent. Streams arrive in AWS's binary event-stream framing.
+
+use crate::sigv4::{self, AwsCredentials, SignableRequest};
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::json;
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, ContentPart, HttpTimeouts, LlmClient, MessageContent,
+    Result, Role, SpawnError, TokenUsage,
+};
+use tracing::{debug, error};
+
+/// SigV4 signing name of both the runtime and the control plane
+const SERVICE: &str = "bedrock";
+
+pub struct BedrockClient {
+    region: String,
+    credentials: AwsCredentials,
+    /// Overrides `https://bedrock-runtime.{region}.amazonaws.com`, e.g. for a
+    /// VPC endpoint
+    endpoint: Option<String>,
+    client: Client,
+}
+
+impl BedrockClient {
+    pub fn new(region: impl Into<String>, credentials: AwsCredentials) -> Self {
+        Self { region: region.into(), credentials, endpoint: None, client: crate::http_client(HttpTimeouts::default()) }
+    }
+
+    pub fn with_endpoint(mut self, url: impl Into<String>) -> Self {
+        self.endpoint = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Connect and read timeouts for this provider's requests
+    pub fn with_timeouts(mut self, timeouts: HttpTimeouts) -> Self {
+        self.client = crate::http_client(timeouts);
+        self
+    }
+
+    fn runtime_url(&self) -> String {
+        self.endpoint.clone().unwrap_or_else(|| format!("https://bedrock-runtime.{}.amazonaws.com", self.region))
+    }
+
+    /// `/model/{id}/{action}`, the id percent-encoded (its `:` included)
+    fn model_path(model: &str, action: &str) -> String {
+        format!("/model/{}/{}", sigv4::uri_encode(model.strip_prefix("bedrock/").unwrap_or(model)), action)
+    }
+
+    /// A SigV4-signed request to `base` + `path`
+    fn signed(&self, method: reqwest::Method, base: &str, path: &str, query: &str, body: Vec<u8>) -> Result<reqwest::RequestBuilder> {
+        let url = reqwest::Url::parse(&format!("{}{}", base, path))
+            .map_err(|e| SpawnError::Config(format!("Invalid Bedrock endpoint '{}': {}", base, e)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(SpawnError::Config(format!("Invalid Bedrock endpoint '{}'", base))),
+        };
+        let mut headers = vec![("host".to_string(), host)];
+        if !body.is_empty() {
+            headers.push(("content-type".to_string(), "application/json".to_string()));
+        }
+        let request = SignableRequest { method: method.as_str(), path, query, headers: headers.clone(), payload: &body };
+        let signature = sigv4::sign(&request, &self.credentials, &self.region, SERVICE, chrono::Utc::now());
+
+        let full_url = if query.is_empty() { url.to_string() } else { format!("{}?{}", url, query) };
+        let mut builder = self.client.request(method, full_url);
+        for (name, value) in headers.into_iter().filter(|(name, _)| name != "host").chain(signature) {
+            builder = builder.header(name, value);
+        }
+        Ok(builder.body(body))
+    }
+
+    async fn send(&self, model: &str, action: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let path = Self::model_path(model, action);
+        let res = self.signed(reqwest::Method::POST, &self.runtime_url(), &path, "", serde_json::to_vec(body)?)?
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, model, error = %err, "Bedrock API error");
+            return Err(err);
+        }
+        Ok(res)
+    }
+
+    async fn complete(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        debug!(model = model, message_count = messages.len(), ?options, "Sending Bedrock chat request");
+        let res = self.send(model, "converse", &request_body(model, messages, options)).await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        parse_response(&json, model)
+    }
+}
+
+/// Converse request body. System messages go to `system`; tool results are
+/// user turns; consecutive messages of one role become one turn, as the API
+/// wants them alternating. Cache breakpoints become cache points on
+/// Anthropic models.
+fn request_body(model: &str, messages: &[ChatMessage], options: &ChatOptions) -> serde_json::Value {
+    let caching = model.contains("anthropic.");
+    let cache_point = json!({ "cachePoint": { "type": "default" } });
+    let mut system = Vec::new();
+    let mut turns: Vec<serde_json::Value> = Vec::new();
+    for message in messages {
+        let mut content = wire_content(&message.content);
+        if caching && message.cache_breakpoint {
+            content.push(cache_point.clone());
+        }
+        if message.role == Role::System {
+            system.extend(content);
+            continue;
+        }
+        let role = if message.role == Role::Assistant { "assistant" } else { "user" };
+        match turns.last_mut() {
+            Some(turn) if turn["role"] == role => {
+                if let Some(existing) = turn["content"].as_array_mut() {
+                    existing.extend(content);
+                }
+            }
+            _ => turns.push(json!({ "role": role, "content": content })),
+        }
+    }
+
+    let mut inference = serde_json::Map::new();
+    if let Some(max_tokens) = options.max_tokens {
+        inference.insert("maxTokens".into(), json!(max_tokens));
+    }
+    if let Some(temperature) = options.temperature {
+        inference.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(top_p) = options.top_p {
+        inference.insert("topP".into(), json!(top_p));
+    }
+    if !options.stop.is_empty() {
+        inference.insert("stopSequences".into(), json!(options.stop));
+    }
+    let mut body = json!({ "messages": turns, "inferenceConfig": inference });
+    if !system.is_empty() {
+        body["system"] = json!(system);
+    }
+    body
+}
+
+/// Text blocks, and images as bytes; images by URL can't be passed and are
+/// left out
+fn wire_content(content: &MessageContent) -> Vec<serde_json::Value> {
+    match content {
+        MessageContent::Text(text) => vec![json!({ "text": text })],
+        MessageContent::Parts(parts) => parts.iter()
+            .filter_map(|part| {
+                let (mime_type, data) = match part {
+                    ContentPart::Text { text } => return Some(json!({ "text": text })),
+                    ContentPart::ImageBase64 { mime_type, data } => (mime_type.as_str(), data.as_str()),
+                    ContentPart::ImageUrl { url, .. } => url.strip_prefix("data:")?.split_once(";base64,")?,
+                };
+                let format = mime_type.strip_prefix("image/").unwrap_or(mime_type);
+                Some(json!({ "image": { "format": format, "source": { "bytes": data } } }))
+            })
+            .collect(),
+    }
+}
+
+/// Bedrock's stop reasons in the OpenAI vocabulary the rest of Spawn uses
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "end_turn" | "stop_sequence" => "stop".to_string(),
+        "max_tokens" => "length".to_string(),
+        "guardrail_intervened" | "content_filtered" => "content_filter".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_usage(usage: &serde_json::Value) -> Option<TokenUsage> {
+    usage.is_object().then(|| {
+        let count = |key: &str| usage[key].as_u64().unwrap_or(0) as u32;
+        let cached_tokens = count("cacheReadInputTokens");
+        // Bedrock counts cached prompt tokens apart from the rest
+        let prompt_tokens = count("inputTokens") + cached_tokens + count("cacheWriteInputTokens");
+        let completion_tokens = count("outputTokens");
+        TokenUsage { prompt_tokens, completion_tokens, total_tokens: prompt_tokens + completion_tokens, cached_tokens }
+    })
+}
+
+fn parse_response(json: &serde_json::Value, model: &str) -> Result<ChatResponse> {
+    let content = json["output"]["message"]["content"].as_array()
+        .map(|blocks| blocks.iter().filter_map(|b| b["text"].as_str()).collect::<String>())
+        .ok_or_else(|| SpawnError::ProviderError("No content in response".into()))?;
+    Ok(ChatResponse {
+        content,
+        usage: parse_usage(&json["usage"]).unwrap_or_default(),
+        finish_reason: json["stopReason"].as_str().map(finish_reason),
+        model: model.to_string(),
+    })
+}
+
+/// One event-stream message: its headers and payload
+struct Frame {
+    headers: Vec<(String, String)>,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Take the first complete message off `buffer`. The framing is: total
+/// length, headers length and prelude CRC (4 bytes each), headers, payload,
+/// message CRC. The CRCs aren't checked; TLS already guards the bytes.
+fn next_frame(buffer: &mut Vec<u8>) -> Result<Option<Frame>> {
+    let read_u32 = |bytes: &[u8], at: usize| u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+    if buffer.len() < 12 {
+        return Ok(None);
+    }
+    let total = read_u32(buffer, 0);
+    let headers_len = read_u32(buffer, 4);
+    if total < 16 + headers_len {
+        return Err(SpawnError::ProviderError("Malformed Bedrock event stream".into()));
+    }
+    if buffer.len() < total {
+        return Ok(None);
+    }
+    let message: Vec<u8> = buffer.drain(..total).collect();
+    let mut headers = Vec::new();
+    let mut at = 12;
+    let headers_end = 12 + headers_len;
+    let malformed = || SpawnError::ProviderError("Malformed Bedrock event stream header".into());
+    while at < headers_end {
+        let name_len = message[at] as usize;
+        let name = String::from_utf8_lossy(message.get(at + 1..at + 1 + name_len).ok_or_else(malformed)?).to_string();
+        at += 1 + name_len;
+        let kind = *message.get(at).ok_or_else(malformed)?;
+        at += 1;
+        // Only strings matter here; the others are skipped by their size
+        let size = match kind {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => {
+                let len = u16::from_be_bytes([*message.get(at).ok_or_else(malformed)?, *message.get(at + 1).ok_or_else(malformed)?]) as usize;
+                at += 2;
+                len
+            }
+            _ => return Err(malformed()),
+        };
+        let value = message.get(at..at + size).ok_or_else(malformed)?;
+        if kind == 7 {
+            headers.push((name, String::from_utf8_lossy(value).to_string()));
+        }
+        at += size;
+    }
+    Ok(Some(Frame { headers, payload: message[headers_end..total - 4].to_vec() }))
+}
+
+/// The delta in one stream message, if it carries one
+fn parse_frame(frame: &Frame) -> Option<Result<ChatDelta>> {
+    let payload: serde_json::Value = match serde_json::from_slice(&frame.payload) {
+        Ok(payload) => payload,
+        Err(e) => return Some(Err(SpawnError::ProviderError(format!("Invalid stream event: {}", e)))),
+    };
+    if frame.header(":message-type") == Some("exception") {
+        let status = match frame.header(":exception-type") {
+            Some("throttlingException") => 429,
+            Some("serviceUnavailableException" | "internalServerException" | "modelStreamErrorException") => 503,
+            _ => 400,
+        };
+        return Some(Err(SpawnError::from_status(status, payload["message"].as_str().unwrap_or_default(), None)));
+    }
+    let delta = match frame.header(":event-type")? {
+        "contentBlockDelta" => ChatDelta {
+            content: payload["delta"]["text"].as_str().unwrap_or_default().to_string(),
+            finish_reason: None,
+            usage: None,
+        },
+        "messageStop" => ChatDelta { content: String::new(), finish_reason: payload["stopReason"].as_str().map(finish_reason), usage: None },
+        "metadata" => ChatDelta { content: String::new(), finish_reason: None, usage: parse_usage(&payload["usage"]) },
+        _ => return None,
+    };
+    Some(Ok(delta))
+}
+
+#[async_trait]
+impl LlmClient for BedrockClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        cancel.run_until_cancelled(crate::Deadline::start(options).run(self.complete(model, messages, options))).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        debug!(model = model, message_count = messages.len(), ?options, "Sending streaming Bedrock chat request");
+        let body = request_body(model, messages, options);
+        let deadline = crate::Deadline::start(options);
+        let res = cancel.run_until_cancelled(deadline.run(self.send(model, "converse-stream", &body))).await
+            .ok_or(SpawnError::Cancelled)??;
+
+        let frames = res.bytes_stream().scan(Vec::new(), |pending: &mut Vec<u8>, chunk| {
+            let frames = match chunk {
+                Ok(chunk) => {
+                    pending.extend_from_slice(&chunk);
+                    let mut frames = Vec::new();
+                    loop {
+                        match next_frame(pending) {
+                            Ok(Some(frame)) => frames.push(Ok(frame)),
+                            Ok(None) => break,
+                            Err(e) => {
+                                frames.push(Err(e));
+                                pending.clear();
+                                break;
+                            }
+                        }
+                    }
+                    frames
+                }
+                Err(e) => vec![Err(SpawnError::ProviderUnavailable(format!("Stream interrupted: {}", e)))],
+            };
+            futures::future::ready(Some(futures::stream::iter(frames)))
+        });
+        Ok(deadline.stream(frames
+            .flatten()
+            .filter_map(|frame| futures::future::ready(match frame {
+                Ok(frame) => parse_frame(&frame),
+                Err(e) => Some(Err(e)),
+            }))
+            .boxed()))
+    }
+
+    /// Lists foundation models on the control plane; no tokens spent
+    async fn health_check(&self, _model: &str, cancel: &CancellationToken) -> Result<()> {
+        let base = format!("https://bedrock.{}.amazonaws.com", self.region);
+        let request = self.signed(reqwest::Method::GET, &base, "/foundation-models", "byOutputModality=TEXT", Vec::new())?.send();
+        let res = cancel.run_until_cancelled(request).await
+            .ok_or(SpawnError::Cancelled)?
+            .map_err(crate::request_error)?;
+        if !res.status().is_success() {
+            return Err(crate::api_error(res).await);
+        }
+        Ok(())
+    }
+
+    fn provider_name(&self) -> &str {
+        "bedrock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An event-stream message with string headers
+    fn frame(headers: &[(&str, &str)], payload: &str) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for (name, value) in headers {
+            encoded.push(name.len() as u8);
+            encoded.extend_from_slice(name.as_bytes());
+            encoded.push(7);
+            encoded.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            encoded.extend_from_slice(value.as_bytes());
+        }
+        let total = 16 + encoded.len() + payload.len();
+        let mut message = Vec::new();
+        message.extend_from_slice(&(total as u32).to_be_bytes());
+        message.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&encoded);
+        message.extend_from_slice(payload.as_bytes());
+        message.extend_from_slice(&[0; 4]);
+        message
+    }
+
+    #[test]
+    fn test_request_body_and_event_stream() {
+        let model = "bedrock/anthropic.claude-3-5-sonnet-20241022-v2:0";
+        assert_eq!(BedrockClient::model_path(model, "converse"), "/model/anthropic.claude-3-5-sonnet-20241022-v2%3A0/converse");
+        let messages = [
+            ChatMessage::system("Be brief.").with_cache_breakpoint(),
+            ChatMessage::user("hi"),
+            ChatMessage::user("still there?"),
+        ];
+        let body = request_body(model, &messages, &ChatOptions::new().with_max_tokens(64));
+        assert_eq!(body["system"], json!([{ "text": "Be brief." }, { "cachePoint": { "type": "default" } }]));
+        assert_eq!(body["messages"], json!([{ "role": "user", "content": [{ "text": "hi" }, { "text": "still there?" }] }]));
+        assert_eq!(body["inferenceConfig"]["maxTokens"], 64);
+
+        let mut buffer = frame(&[(":event-type", "contentBlockDelta"), (":message-type", "event")], r#"{"delta":{"text":"Hel"}}"#);
+        buffer.extend(frame(&[(":event-type", "metadata"), (":message-type", "event")], r#"{"usage":{"inputTokens":10,"outputTokens":2}}"#));
+        let split = buffer.split_off(buffer.len() - 5);
+        let first = next_frame(&mut buffer).unwrap().unwrap();
+        assert_eq!(parse_frame(&first).unwrap().unwrap().content, "Hel");
+        assert!(next_frame(&mut buffer).unwrap().is_none());
+        buffer.extend(split);
+        let usage = parse_frame(&next_frame(&mut buffer).unwrap().unwrap()).unwrap().unwrap().usage.unwrap();
+        assert_eq!(usage.total_tokens, 12);
+
+        let mut throttled = frame(&[(":message-type", "exception"), (":exception-type", "throttlingException")], r#"{"message":"slow down"}"#);
+        let error = parse_frame(&next_frame(&mut throttled).unwrap().unwrap()).unwrap().unwrap_err();
+        assert!(matches!(error, SpawnError::RateLimited { .. }));
+    }
+}
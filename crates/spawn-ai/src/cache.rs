@@ -0,0 +1,288 @@
+//! Response caching
+//!
+//! Requests at temperature 0 should get the same answer every time, so the
+//! answer to one is kept under a hash of the model, messages and options and
+//! served again for an identical request, without calling the provider. That
+//! makes re-running a mission during development close to free. Responses
+//! live in an in-memory LRU and, optionally, a `ResponseStore` that outlives
+//! the process. Served responses report no token use, since none was spent.
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, LlmClient, ResponseStore, Result, TokenUsage,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Least recently used entries go first once `capacity` is reached
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (u64, ChatResponse)>,
+    /// Keys by last use
+    order: BTreeMap<u64, String>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, tick: 0, entries: HashMap::new(), order: BTreeMap::new() }
+    }
+
+    fn get(&mut self, key: &str) -> Option<ChatResponse> {
+        self.tick += 1;
+        let (used, response) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(response.clone())
+    }
+
+    fn put(&mut self, key: String, response: ChatResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((used, _)) = self.entries.insert(key.clone(), (self.tick, response)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key);
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Cached responses, in memory and optionally in a durable store
+pub struct ResponseCache {
+    memory: Mutex<Lru>,
+    store: Option<Arc<dyn ResponseStore>>,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { memory: Mutex::new(Lru::new(capacity)), store: None }
+    }
+
+    /// Also keep responses in `store`, and look there on a memory miss
+    pub fn with_store(mut self, store: Arc<dyn ResponseStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    async fn get(&self, key: &str) -> Option<ChatResponse> {
+        if let Some(response) = self.memory.lock().unwrap().get(key) {
+            return Some(response);
+        }
+        let store = self.store.as_ref()?;
+        match store.get_response(key).await {
+            Ok(Some(response)) => {
+                self.memory.lock().unwrap().put(key.to_string(), response.clone());
+                Some(response)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!(error = %e, "Failed to read cached response");
+                None
+            }
+        }
+    }
+
+    async fn put(&self, key: String, response: ChatResponse) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.put_response(&key, &response).await {
+                warn!(error = %e, "Failed to store cached response");
+            }
+        }
+        self.memory.lock().unwrap().put(key, response);
+    }
+}
+
+/// Only deterministic requests are worth answering from the cache
+fn cacheable(options: &ChatOptions) -> bool {
+    options.temperature == Some(0.0)
+}
+
+/// Everything that shapes the answer; timestamps and metadata don't
+pub(crate) fn request_json(model: &str, messages: &[ChatMessage], options: &ChatOptions) -> serde_json::Value {
+    let messages: Vec<serde_json::Value> = messages.iter()
+        .map(|m| json!({
+            "role": m.role,
+            "content": m.content,
+            "name": m.name,
+            "tool_calls": m.tool_calls,
+            "tool_call_id": m.tool_call_id,
+        }))
+        .collect();
+    json!({ "model": model, "messages": messages, "options": options })
+}
+
+/// Hash of `request_json`
+pub(crate) fn cache_key(model: &str, messages: &[ChatMessage], options: &ChatOptions) -> String {
+    format!("{:x}", Sha256::digest(request_json(model, messages, options).to_string().as_bytes()))
+}
+
+/// An `LlmClient` answering repeated temperature-0 requests from a
+/// `ResponseCache`; everything else goes straight through
+pub struct CachingClient {
+    inner: Arc<dyn LlmClient>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachingClient {
+    pub fn new(inner: Arc<dyn LlmClient>, cache: ResponseCache) -> Self {
+        Self { inner, cache: Arc::new(cache) }
+    }
+}
+
+#[async_trait]
+impl LlmClient for CachingClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        if !cacheable(options) {
+            return self.inner.chat_with_usage(model, messages, options, cancel).await;
+        }
+        let key = cache_key(model, messages, options);
+        if let Some(cached) = self.cache.get(&key).await {
+            debug!(model, key = %key, "Serving cached response");
+            return Ok(ChatResponse { usage: TokenUsage::default(), ..cached });
+        }
+        let response = self.inner.chat_with_usage(model, messages, options, cancel).await?;
+        self.cache.put(key, response.clone()).await;
+        Ok(response)
+    }
+
+    /// A hit comes back as one delta; a miss streams as usual and is cached
+    /// once the stream has run to the end without error
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        if !cacheable(options) {
+            return self.inner.chat_stream(model, messages, options, cancel).await;
+        }
+        let key = cache_key(model, messages, options);
+        if let Some(cached) = self.cache.get(&key).await {
+            debug!(model, key = %key, "Serving cached response");
+            let delta = ChatDelta { content: cached.content, finish_reason: cached.finish_reason, usage: Some(TokenUsage::default()) };
+            return Ok(futures::stream::once(async move { Ok(delta) }).boxed());
+        }
+
+        let stream = self.inner.chat_stream(model, messages, options, cancel).await?;
+        let collected = Arc::new(Mutex::new(Some(ChatResponse {
+            content: String::new(),
+            usage: TokenUsage::default(),
+            finish_reason: None,
+            model: model.to_string(),
+        })));
+        let record = {
+            let (collected, cache) = (collected.clone(), self.cache.clone());
+            async move {
+                let response = collected.lock().unwrap().take();
+                if let Some(response) = response.filter(|r| r.finish_reason.is_some()) {
+                    cache.put(key, response).await;
+                }
+                None::<Result<ChatDelta>>
+            }
+        };
+        Ok(stream
+            .inspect(move |delta| {
+                let mut collected = collected.lock().unwrap();
+                match (delta, collected.as_mut()) {
+                    (Ok(delta), Some(response)) => {
+                        response.content.push_str(&delta.content);
+                        response.finish_reason = delta.finish_reason.clone().or(response.finish_reason.take());
+                        if let Some(usage) = delta.usage {
+                            response.usage = usage;
+                        }
+                    }
+                    // A failed stream isn't cached
+                    (Err(_), _) => *collected = None,
+                    (Ok(_), None) => {}
+                }
+            })
+            .chain(futures::stream::once(record).filter_map(futures::future::ready))
+            .boxed())
+    }
+
+    async fn health_check(&self, model: &str, cancel: &CancellationToken) -> Result<()> {
+        self.inner.health_check(model, cancel).await
+    }
+
+    fn supports_json_schema(&self, model: &str) -> bool {
+        self.inner.supports_json_schema(model)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct CountingClient(AtomicU32);
+
+    #[async_trait]
+    impl LlmClient for CountingClient {
+        async fn chat_with_usage(&self, model: &str, _: &[ChatMessage], _: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                content: format!("answer {}", n),
+                usage: TokenUsage { prompt_tokens: 10, completion_tokens: 2, total_tokens: 12, cached_tokens: 0 },
+                finish_reason: Some("stop".into()),
+                model: model.to_string(),
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeats_deterministic_requests_from_cache() {
+        let inner = Arc::new(CountingClient(AtomicU32::new(0)));
+        let client = CachingClient::new(inner.clone(), ResponseCache::new(10));
+        let cancel = CancellationToken::new();
+        let deterministic = ChatOptions::new().with_temperature(0.0);
+        let messages = [ChatMessage::user("What is 2 + 2?")];
+
+        let first = client.chat_with_usage("m", &messages, &deterministic, &cancel).await.unwrap();
+        let again = [ChatMessage::user("What is 2 + 2?").with_metadata("step", 3)];
+        let second = client.chat_with_usage("m", &again, &deterministic, &cancel).await.unwrap();
+        assert_eq!(second.content, first.content);
+        assert_eq!(second.usage.total_tokens, 0);
+
+        let creative = ChatOptions::new().with_temperature(0.7);
+        client.chat_with_usage("m", &messages, &creative, &cancel).await.unwrap();
+        client.chat_with_usage("other", &messages, &deterministic, &cancel).await.unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let response = |content: &str| ChatResponse { content: content.into(), usage: TokenUsage::default(), finish_reason: None, model: "m".into() };
+        let mut lru = Lru::new(2);
+        lru.put("a".into(), response("a"));
+        lru.put("b".into(), response("b"));
+        lru.get("a");
+        lru.put("c".into(), response("c"));
+        assert!(lru.get("b").is_none());
+        assert!(lru.get("a").is_some() && lru.get("c").is_some());
+    }
+}
@@ -0,0 +1,153 @@
+//! Circuit breaker
+//!
+//! Tracks the outcomes of a provider's recent requests and probes. Once too
+//! many in the window have failed the circuit opens and the provider is
+//! skipped outright, so requests go straight to a fallback instead of each
+//! waiting out a timeout. After a cool-down one trial request is let
+//! through (half-open); its outcome closes the circuit or opens it again.
+
+use spawn_core::{CircuitState, SpawnError};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// When a circuit opens and for how long
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Share of failed outcomes in the window that opens the circuit
+    pub failure_rate: f64,
+    /// Outcomes in the window before the rate counts
+    pub min_requests: usize,
+    pub window: Duration,
+    /// How long an open circuit waits before letting a trial through
+    pub open_for: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            min_requests: 5,
+            window: Duration::from_secs(60),
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether an error says something about the provider's health, rather
+/// than about the request or the caller
+pub fn is_provider_failure(e: &SpawnError) -> bool {
+    matches!(e, SpawnError::ProviderUnavailable(_) | SpawnError::RateLimited { .. } | SpawnError::AuthFailed(_))
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    /// Recent outcomes, oldest first; true for success
+    outcomes: VecDeque<(Instant, bool)>,
+    state: CircuitState,
+    opened_at: Option<Instant>,
+    /// When the half-open trial in flight started; one that never reports
+    /// back (cancelled, say) stops counting after `open_for`
+    trial: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self { config, outcomes: VecDeque::new(), state: CircuitState::Closed, opened_at: None, trial: None }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// Failed share of the outcomes in the window
+    pub fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        self.outcomes.iter().filter(|(_, ok)| !ok).count() as f64 / self.outcomes.len() as f64
+    }
+
+    /// Whether a request may go to the provider now; after the cool-down an
+    /// open circuit lets one trial through
+    pub fn allow(&mut self, now: Instant) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open if self.opened_at.is_some_and(|at| now.duration_since(at) >= self.config.open_for) => {
+                self.state = CircuitState::HalfOpen;
+                self.trial = Some(now);
+                true
+            }
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if self.trial.is_none_or(|at| now.duration_since(at) >= self.config.open_for) => {
+                self.trial = Some(now);
+                true
+            }
+            CircuitState::HalfOpen => false,
+        }
+    }
+
+    /// Record an outcome, returning the new state if it changed
+    pub fn record(&mut self, now: Instant, success: bool) -> Option<CircuitState> {
+        let before = self.state;
+        self.outcomes.push_back((now, success));
+        while self.outcomes.front().is_some_and(|(at, _)| now.duration_since(*at) > self.config.window) {
+            self.outcomes.pop_front();
+        }
+        match (self.state, success) {
+            (CircuitState::HalfOpen, true) => {
+                self.state = CircuitState::Closed;
+                self.outcomes.clear();
+            }
+            (CircuitState::HalfOpen, false) => self.open(now),
+            (CircuitState::Closed, false)
+                if self.outcomes.len() >= self.config.min_requests && self.failure_rate() >= self.config.failure_rate =>
+            {
+                self.open(now)
+            }
+            _ => {}
+        }
+        self.trial = None;
+        (self.state != before).then_some(self.state)
+    }
+
+    /// A passing health probe: an open circuit needn't wait out the rest of
+    /// its cool-down before the next trial
+    pub fn probe_passed(&mut self) {
+        if self.state == CircuitState::Open {
+            self.state = CircuitState::HalfOpen;
+            self.trial = None;
+        }
+    }
+
+    fn open(&mut self, now: Instant) {
+        self.state = CircuitState::Open;
+        self.opened_at = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_and_recovers() {
+        let config = CircuitBreakerConfig { min_requests: 4, ..CircuitBreakerConfig::default() };
+        let mut breaker = CircuitBreaker::new(config);
+        let start = Instant::now();
+        breaker.record(start, true);
+        breaker.record(start, false);
+        assert_eq!(breaker.record(start, false), None);
+        assert_eq!(breaker.record(start, false), Some(CircuitState::Open));
+        assert!(!breaker.allow(start + Duration::from_secs(1)));
+
+        let later = start + config.open_for;
+        assert!(breaker.allow(later));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Only one trial at a time
+        assert!(!breaker.allow(later));
+        assert_eq!(breaker.record(later, true), Some(CircuitState::Closed));
+        assert!(breaker.allow(later));
+        assert_eq!(breaker.failure_rate(), 0.0);
+    }
+}
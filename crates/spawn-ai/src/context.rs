@@ -0,0 +1,184 @@
+//! Context compaction
+//!
+//! A long-running conversation outgrows any context window. `ContextManager`
+//! keeps it under a token budget: the leading pinned messages (system prompt,
+//! goal, plan) and the newest turns stay as they are, and the turns between
+//! are replaced by an LLM summary of them, or dropped oldest first when there
+//! is no summarizer or it fails. An earlier summary is folded into the next.
+
+use crate::tokens::{estimate_tokens, message_tokens};
+use serde::Serialize;
+use spawn_core::{ChatMessage, Result, Role, Summarizer};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Newest messages kept verbatim by default
+pub const DEFAULT_KEEP_RECENT: usize = 6;
+
+/// What a compaction did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Compaction {
+    /// Messages replaced by a summary
+    pub summarized: usize,
+    /// Messages removed outright
+    pub dropped: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl Compaction {
+    pub fn is_empty(&self) -> bool {
+        self.summarized == 0 && self.dropped == 0
+    }
+}
+
+impl std::fmt::Display for Compaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Compacted context from {} to {} tokens", self.tokens_before, self.tokens_after)?;
+        if self.summarized > 0 {
+            write!(f, ", {} messages summarized", self.summarized)?;
+        }
+        if self.dropped > 0 {
+            write!(f, ", {} messages dropped", self.dropped)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ContextManager {
+    summarizer: Option<Arc<dyn Summarizer>>,
+    keep_recent: usize,
+}
+
+impl Default for ContextManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextManager {
+    /// Drops old turns; see `with_summarizer` to summarize them instead
+    pub fn new() -> Self {
+        Self { summarizer: None, keep_recent: DEFAULT_KEEP_RECENT }
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Newest messages never compacted, so recent tool results stay intact
+    pub fn with_keep_recent(mut self, keep_recent: usize) -> Self {
+        self.keep_recent = keep_recent;
+        self
+    }
+
+    /// Bring `messages` within `budget` tokens for `model`, leaving the first
+    /// `pinned` and the newest `keep_recent` alone. When those alone are over
+    /// budget the result still is; it's for the caller to cut further.
+    pub async fn compact(&self, messages: &mut Vec<ChatMessage>, pinned: usize, budget: usize, model: &str) -> Compaction {
+        let tokens_before = estimate_tokens(messages, model);
+        let mut compaction = Compaction { tokens_before, tokens_after: tokens_before, ..Compaction::default() };
+        if tokens_before <= budget {
+            return compaction;
+        }
+        let pinned = pinned.min(messages.len());
+        let mut end = messages.len().saturating_sub(self.keep_recent).max(pinned);
+        // Tool results stay with the call they answer
+        while end > pinned && end < messages.len() && messages[end].role == Role::Tool {
+            end -= 1;
+        }
+        if end == pinned {
+            return compaction;
+        }
+
+        if let Some(summarizer) = &self.summarizer {
+            match summarize(summarizer.as_ref(), &messages[pinned..end]).await {
+                Ok(summary) => {
+                    messages.splice(pinned..end, [summary]);
+                    compaction.summarized = end - pinned;
+                    compaction.tokens_after = estimate_tokens(messages, model);
+                    return compaction;
+                }
+                Err(e) => warn!(error = %e, "Failed to summarize old context; dropping it instead"),
+            }
+        }
+
+        let mut tokens = tokens_before;
+        let mut drop_to = pinned;
+        while drop_to < end && tokens > budget {
+            tokens -= message_tokens(&messages[drop_to], model);
+            drop_to += 1;
+        }
+        while drop_to < end && messages[drop_to].role == Role::Tool {
+            tokens -= message_tokens(&messages[drop_to], model);
+            drop_to += 1;
+        }
+        messages.drain(pinned..drop_to);
+        compaction.dropped = drop_to - pinned;
+        compaction.tokens_after = tokens;
+        compaction
+    }
+}
+
+/// The summary message standing in for `messages`, in the form
+/// `Conversation::summarize` leaves
+async fn summarize(summarizer: &dyn Summarizer, messages: &[ChatMessage]) -> Result<ChatMessage> {
+    let summary = summarizer.summarize(messages).await?;
+    Ok(ChatMessage::system(format!("Summary of the earlier conversation:\n{}", summary))
+        .with_metadata("summarized", messages.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use spawn_core::SpawnError;
+
+    struct FixedSummarizer(Option<&'static str>);
+
+    #[async_trait]
+    impl Summarizer for FixedSummarizer {
+        async fn summarize(&self, _: &[ChatMessage]) -> Result<String> {
+            self.0.map(String::from).ok_or_else(|| SpawnError::ProviderUnavailable("down".into()))
+        }
+    }
+
+    fn conversation() -> Vec<ChatMessage> {
+        let mut messages = vec![ChatMessage::system("You are an agent."), ChatMessage::user("Goal: tidy the repo")];
+        for step in 0..10 {
+            messages.push(ChatMessage::assistant(format!("Step {}: {}", step, "thinking ".repeat(50))));
+            messages.push(ChatMessage::user(format!("Tool result: {}", "output ".repeat(50))));
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_summarizes_middle_turns() {
+        let mut messages = conversation();
+        let newest = messages[messages.len() - 4..].to_vec();
+        let manager = ContextManager::new().with_summarizer(Arc::new(FixedSummarizer(Some("- listed files"))));
+        let compaction = manager.with_keep_recent(4).compact(&mut messages, 2, 400, "gpt-4o").await;
+        assert_eq!(compaction.summarized, 16);
+        assert_eq!(messages.len(), 2 + 1 + 4);
+        assert!(messages[2].content.text().contains("- listed files"));
+        assert_eq!(messages[3..].iter().map(|m| m.content.text()).collect::<Vec<_>>(), newest.iter().map(|m| m.content.text()).collect::<Vec<_>>());
+        assert!(compaction.tokens_after < compaction.tokens_before);
+    }
+
+    #[tokio::test]
+    async fn test_drops_oldest_when_summarizer_fails() {
+        let mut messages = conversation();
+        let manager = ContextManager::new().with_summarizer(Arc::new(FixedSummarizer(None)));
+        let compaction = manager.compact(&mut messages, 2, 1_000, "gpt-4o").await;
+        assert_eq!(compaction.summarized, 0);
+        assert!(compaction.dropped > 0);
+        assert!(compaction.tokens_after <= 1_000);
+        assert_eq!(compaction.tokens_after, estimate_tokens(&messages, "gpt-4o"));
+        assert_eq!(messages[0].content.text(), "You are an agent.");
+        assert_eq!(messages.len(), 22 - compaction.dropped);
+
+        let untouched = manager.compact(&mut messages, 2, 1_000, "gpt-4o").await;
+        assert!(untouched.is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Cost tracking
+//!
+//! Every completion's token counts are priced from a per-model table and
+//! added up per scope (a mission, the chat endpoints, ...), per model and
+//! overall. The orchestrator checks a mission's total against its budget;
+//! the admin panel shows the rest.
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Serialize;
+use spawn_core::{
+    CancellationToken, ChatMessage, ChatOptions, ChatResponse, ChatStream, LlmClient, ModelPrice, Result, TokenUsage,
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// USD per million prompt/completion/cached prompt tokens, as listed by OpenRouter
+const BUILTIN_PRICES: &[(&str, f64, f64, Option<f64>)] = &[
+    ("anthropic/claude-opus-4", 15.0, 75.0, Some(1.5)),
+    ("anthropic/claude-sonnet-4", 3.0, 15.0, Some(0.3)),
+    ("anthropic/claude-3.7-sonnet", 3.0, 15.0, Some(0.3)),
+    ("anthropic/claude-3.5-sonnet", 3.0, 15.0, Some(0.3)),
+    ("anthropic/claude-3.5-haiku", 0.8, 4.0, Some(0.08)),
+    ("openai/gpt-4o", 2.5, 10.0, Some(1.25)),
+    ("openai/gpt-4o-mini", 0.15, 0.6, Some(0.075)),
+    ("openai/gpt-4.1", 2.0, 8.0, Some(0.5)),
+    ("openai/gpt-4.1-mini", 0.4, 1.6, Some(0.1)),
+    ("openai/text-embedding-3-small", 0.02, 0.0, None),
+    ("google/gemini-2.5-pro", 1.25, 10.0, Some(0.31)),
+    ("google/gemini-2.5-flash", 0.3, 2.5, Some(0.075)),
+    ("x-ai/grok-3", 3.0, 15.0, Some(0.75)),
+];
+
+/// Prices by model id
+#[derive(Debug, Clone)]
+pub struct PricingTable {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl PricingTable {
+    /// The built-in prices, with `overrides` added or replacing them
+    pub fn new(overrides: BTreeMap<String, ModelPrice>) -> Self {
+        let mut prices: BTreeMap<String, ModelPrice> = BUILTIN_PRICES.iter()
+            .map(|&(model, prompt, completion, cached_prompt)| (model.to_string(), ModelPrice { prompt, completion, cached_prompt }))
+            .collect();
+        prices.extend(overrides);
+        Self { prices }
+    }
+
+    /// The price of `model`, or of the longest model id it starts with, so
+    /// dated ids (`...-20250514`) find their family. `:free` variants cost
+    /// nothing.
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        if model.ends_with(":free") {
+            return Some(ModelPrice::default());
+        }
+        let model = model.split(':').next().unwrap_or(model);
+        self.prices.get(model).copied().or_else(|| {
+            self.prices.iter()
+                .filter(|(id, _)| model.starts_with(id.as_str()))
+                .max_by_key(|(id, _)| id.len())
+                .map(|(_, price)| *price)
+        })
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        Self::new(BTreeMap::new())
+    }
+}
+
+/// Requests, tokens and spend added up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CostTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Prompt tokens served from the provider's prompt cache
+    pub cached_tokens: u64,
+    pub cost_usd: f64,
+    /// Requests to models with no known price, counted at no cost
+    pub unpriced_requests: u64,
+}
+
+impl CostTotals {
+    fn add(&mut self, usage: &TokenUsage, cost: Option<f64>) {
+        self.requests += 1;
+        self.prompt_tokens += u64::from(usage.prompt_tokens);
+        self.completion_tokens += u64::from(usage.completion_tokens);
+        self.cached_tokens += u64::from(usage.cached_tokens);
+        match cost {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+/// Everything recorded so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostSummary {
+    pub total: CostTotals,
+    pub by_model: BTreeMap<String, CostTotals>,
+    pub by_scope: BTreeMap<String, CostTotals>,
+}
+
+/// Running cost totals, shared by everything that calls a model
+#[derive(Default)]
+pub struct CostTracker {
+    pricing: PricingTable,
+    summary: Mutex<CostSummary>,
+}
+
+impl CostTracker {
+    pub fn new(pricing: PricingTable) -> Self {
+        Self { pricing, summary: Mutex::default() }
+    }
+
+    pub fn pricing(&self) -> &PricingTable {
+        &self.pricing
+    }
+
+    /// Add one completion to `scope`'s totals, returning its cost (zero when
+    /// the model has no price)
+    pub fn record(&self, scope: &str, model: &str, usage: &TokenUsage) -> f64 {
+        let cost = self.pricing.price(model).map(|price| price.cost(usage));
+        if cost.is_none() {
+            debug!(model, "No price for model; counting its usage at no cost");
+        }
+        let mut summary = self.summary.lock().unwrap();
+        summary.total.add(usage, cost);
+        summary.by_model.entry(model.to_string()).or_default().add(usage, cost);
+        summary.by_scope.entry(scope.to_string()).or_default().add(usage, cost);
+        cost.unwrap_or(0.0)
+    }
+
+    /// Totals for one scope; zero if nothing was recorded under it
+    pub fn scope(&self, scope: &str) -> CostTotals {
+        self.summary.lock().unwrap().by_scope.get(scope).copied().unwrap_or_default()
+    }
+
+    pub fn summary(&self) -> CostSummary {
+        self.summary.lock().unwrap().clone()
+    }
+}
+
+/// An `LlmClient` recording every completion it serves under one scope.
+/// Health probes aren't recorded.
+pub struct MeteredClient {
+    inner: Arc<dyn LlmClient>,
+    costs: Arc<CostTracker>,
+    scope: String,
+}
+
+impl MeteredClient {
+    pub fn new(inner: Arc<dyn LlmClient>, costs: Arc<CostTracker>, scope: impl Into<String>) -> Self {
+        Self { inner, costs, scope: scope.into() }
+    }
+}
+
+#[async_trait]
+impl LlmClient for MeteredClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        let response = self.inner.chat_with_usage(model, messages, options, cancel).await?;
+        self.costs.record(&self.scope, model, &response.usage);
+        Ok(response)
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let stream = self.inner.chat_stream(model, messages, options, cancel).await?;
+        let (costs, scope, model) = (self.costs.clone(), self.scope.clone(), model.to_string());
+        Ok(stream
+            .inspect(move |delta| {
+                if let Some(usage) = delta.as_ref().ok().and_then(|d| d.usage.as_ref()) {
+                    costs.record(&scope, &model, usage);
+                }
+            })
+            .boxed())
+    }
+
+    async fn health_check(&self, model: &str, cancel: &CancellationToken) -> Result<()> {
+        self.inner.health_check(model, cancel).await
+    }
+
+    fn supports_json_schema(&self, model: &str) -> bool {
+        self.inner.supports_json_schema(model)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_prices_usage() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("local/model".to_string(), ModelPrice { prompt: 1.0, completion: 2.0, cached_prompt: None });
+        let costs = CostTracker::new(PricingTable::new(overrides));
+        let usage = TokenUsage { prompt_tokens: 1_000_000, completion_tokens: 500_000, total_tokens: 1_500_000, cached_tokens: 0 };
+
+        assert_eq!(costs.record("m1", "local/model", &usage), 2.0);
+        assert_eq!(costs.record("m1", "anthropic/claude-sonnet-4-20250514", &usage), 10.5);
+        assert_eq!(costs.record("m2", "meta-llama/llama-3-8b-instruct:free", &usage), 0.0);
+        assert_eq!(costs.record("m2", "unknown/model", &usage), 0.0);
+
+        assert_eq!(costs.scope("m1").cost_usd, 12.5);
+        assert_eq!(costs.scope("m2").unpriced_requests, 1);
+        let summary = costs.summary();
+        assert_eq!(summary.total.requests, 4);
+        assert_eq!(summary.by_model["local/model"].prompt_tokens, 1_000_000);
+
+        let cached = TokenUsage { cached_tokens: 800_000, ..usage };
+        assert!((costs.record("m3", "anthropic/claude-sonnet-4", &cached) - 8.34).abs() < 1e-9);
+        assert_eq!(costs.scope("m3").cached_tokens, 800_000);
+    }
+}
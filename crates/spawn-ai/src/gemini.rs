@@ -0,0 +1,315 @@
+//! Google Gemini client
+//!
+//! Talks to the Generative Language API directly (`models/{model}:generateContent`
+//! and `:streamGenerateContent`), authenticated with an `x-goog-api-key`
+//! header. Model ids may carry OpenRouter's `google/` prefix
+//! (`google/gemini-2.5-pro`), which is dropped before they are sent. System
+//! messages become the system instruction and assistant turns the `model`
+//! role; safety settings apply to every request.
+
+use crate::openrouter::sse_stream;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, ContentPart, HttpTimeouts, LlmClient,
+    MessageContent, ResponseFormat, Result, Role, SpawnError, TokenUsage,
+};
+use tracing::{debug, error};
+
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+pub struct GeminiClient {
+    base_url: String,
+    api_key: String,
+    /// `(HARM_CATEGORY_*, threshold)` pairs; the API's defaults when empty
+    safety_settings: Vec<(String, String)>,
+    client: Client,
+}
+
+impl GeminiClient {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self { base_url: DEFAULT_BASE_URL.to_string(), api_key: api_key.into(), safety_settings: Vec::new(), client: crate::http_client(HttpTimeouts::default()) }
+    }
+
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Block `category` (`harassment`, `hate_speech`, `sexually_explicit`,
+    /// `dangerous_content`, `civic_integrity`, or the full `HARM_CATEGORY_*`
+    /// name) at `threshold` (`block_none`, `block_only_high`,
+    /// `block_medium_and_above`, `block_low_and_above` or `off`)
+    pub fn with_safety_setting(mut self, category: &str, threshold: &str) -> Self {
+        let category = category.trim().to_uppercase().replace('-', "_");
+        let category = if category.starts_with("HARM_CATEGORY_") { category } else { format!("HARM_CATEGORY_{}", category) };
+        self.safety_settings.retain(|(c, _)| *c != category);
+        self.safety_settings.push((category, threshold.trim().to_uppercase()));
+        self
+    }
+
+    /// Connect and read timeouts for this provider's requests
+    pub fn with_timeouts(mut self, timeouts: HttpTimeouts) -> Self {
+        self.client = crate::http_client(timeouts);
+        self
+    }
+
+    fn model_url(&self, model: &str, method: &str) -> String {
+        format!("{}/models/{}:{}", self.base_url, model.strip_prefix("google/").unwrap_or(model), method)
+    }
+
+    fn request_body(&self, messages: &[ChatMessage], options: &ChatOptions) -> serde_json::Value {
+        let system: Vec<serde_json::Value> = messages.iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| json!({ "text": m.content.text() }))
+            .collect();
+        // Consecutive messages of one role become one turn
+        let mut contents: Vec<serde_json::Value> = Vec::new();
+        for message in messages.iter().filter(|m| m.role != Role::System) {
+            let role = if message.role == Role::Assistant { "model" } else { "user" };
+            let parts = wire_parts(&message.content);
+            match contents.last_mut() {
+                Some(turn) if turn["role"] == role => {
+                    if let Some(existing) = turn["parts"].as_array_mut() {
+                        existing.extend(parts);
+                    }
+                }
+                _ => contents.push(json!({ "role": role, "parts": parts })),
+            }
+        }
+
+        let mut body = json!({ "contents": contents, "generationConfig": generation_config(options) });
+        if !system.is_empty() {
+            body["systemInstruction"] = json!({ "parts": system });
+        }
+        if !self.safety_settings.is_empty() {
+            body["safetySettings"] = self.safety_settings.iter()
+                .map(|(category, threshold)| json!({ "category": category, "threshold": threshold }))
+                .collect();
+        }
+        body
+    }
+
+    async fn send(&self, url: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let res = self.client
+            .post(url)
+            .header("x-goog-api-key", &self.api_key)
+            .json(body)
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, error = %err, "Gemini API error");
+            return Err(err);
+        }
+        Ok(res)
+    }
+
+    async fn complete(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        debug!(model = model, message_count = messages.len(), ?options, "Sending Gemini chat request");
+        let res = self.send(&self.model_url(model, "generateContent"), &self.request_body(messages, options)).await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        let delta = parse_response(&json)?;
+        Ok(ChatResponse {
+            content: delta.content,
+            usage: delta.usage.unwrap_or_default(),
+            finish_reason: delta.finish_reason,
+            model: json["modelVersion"].as_str().unwrap_or(model).to_string(),
+        })
+    }
+}
+
+/// Text inline, images as base64 data or by URI
+fn wire_parts(content: &MessageContent) -> Vec<serde_json::Value> {
+    match content {
+        MessageContent::Text(text) => vec![json!({ "text": text })],
+        MessageContent::Parts(parts) => parts.iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => json!({ "text": text }),
+                ContentPart::ImageBase64 { mime_type, data } => json!({ "inlineData": { "mimeType": mime_type, "data": data } }),
+                ContentPart::ImageUrl { url, .. } => match url.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+                    Some((mime_type, data)) => json!({ "inlineData": { "mimeType": mime_type, "data": data } }),
+                    None => json!({ "fileData": { "fileUri": url } }),
+                },
+            })
+            .collect(),
+    }
+}
+
+fn generation_config(options: &ChatOptions) -> serde_json::Value {
+    let mut config = serde_json::Map::new();
+    if let Some(temperature) = options.temperature {
+        config.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(max_tokens) = options.max_tokens {
+        config.insert("maxOutputTokens".into(), json!(max_tokens));
+    }
+    if let Some(top_p) = options.top_p {
+        config.insert("topP".into(), json!(top_p));
+    }
+    if !options.stop.is_empty() {
+        config.insert("stopSequences".into(), json!(options.stop));
+    }
+    if let Some(seed) = options.seed {
+        config.insert("seed".into(), json!(seed));
+    }
+    match &options.response_format {
+        Some(ResponseFormat::JsonObject) => {
+            config.insert("responseMimeType".into(), json!("application/json"));
+        }
+        Some(ResponseFormat::JsonSchema { json_schema }) => {
+            config.insert("responseMimeType".into(), json!("application/json"));
+            config.insert("responseJsonSchema".into(), json_schema.schema.clone());
+        }
+        None => {}
+    }
+    serde_json::Value::Object(config)
+}
+
+/// Gemini's finish reasons in the OpenAI vocabulary the rest of Spawn uses
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "STOP" => "stop".to_string(),
+        "MAX_TOKENS" => "length".to_string(),
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" | "IMAGE_SAFETY" => "content_filter".to_string(),
+        other => other.to_lowercase(),
+    }
+}
+
+/// One `GenerateContentResponse`: the whole reply, or one event of a
+/// stream. Token counts are running totals, so the last event's are final.
+fn parse_response(json: &serde_json::Value) -> Result<ChatDelta> {
+    if let Some(message) = json["error"]["message"].as_str() {
+        return Err(SpawnError::ProviderError(message.to_string()));
+    }
+    if let Some(reason) = json["promptFeedback"]["blockReason"].as_str() {
+        return Err(SpawnError::ProviderError(format!("Prompt blocked by Gemini safety settings: {}", reason)));
+    }
+    let candidate = &json["candidates"][0];
+    let content: String = candidate["content"]["parts"].as_array()
+        .map(|parts| parts.iter()
+            // Thought summaries aren't part of the reply
+            .filter(|p| p["thought"].as_bool() != Some(true))
+            .filter_map(|p| p["text"].as_str())
+            .collect())
+        .unwrap_or_default();
+    let metadata = &json["usageMetadata"];
+    let usage = metadata.is_object().then(|| {
+        let count = |key: &str| metadata[key].as_u64().unwrap_or(0) as u32;
+        let prompt_tokens = count("promptTokenCount");
+        // Thinking tokens are billed as output
+        let completion_tokens = count("candidatesTokenCount") + count("thoughtsTokenCount");
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cached_tokens: count("cachedContentTokenCount"),
+        }
+    });
+    Ok(ChatDelta {
+        content,
+        finish_reason: candidate["finishReason"].as_str().map(finish_reason),
+        usage,
+    })
+}
+
+/// One SSE event of `streamGenerateContent?alt=sse`; the stream just ends
+fn parse_event(data: &str) -> Option<Result<ChatDelta>> {
+    Some(serde_json::from_str(data)
+        .map_err(|e| SpawnError::ProviderError(format!("Invalid stream event: {}", e)))
+        .and_then(|json| parse_response(&json)))
+}
+
+#[async_trait]
+impl LlmClient for GeminiClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        cancel.run_until_cancelled(crate::Deadline::start(options).run(self.complete(model, messages, options))).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        debug!(model = model, message_count = messages.len(), ?options, "Sending streaming Gemini chat request");
+        let url = format!("{}?alt=sse", self.model_url(model, "streamGenerateContent"));
+        let body = self.request_body(messages, options);
+        let deadline = crate::Deadline::start(options);
+        let res = cancel.run_until_cancelled(deadline.run(self.send(&url, &body))).await
+            .ok_or(SpawnError::Cancelled)??;
+        Ok(deadline.stream(sse_stream(res.bytes_stream(), parse_event)))
+    }
+
+    /// Lists models; no tokens spent
+    async fn health_check(&self, _model: &str, cancel: &CancellationToken) -> Result<()> {
+        let request = self.client
+            .get(format!("{}/models?pageSize=1", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .send();
+        let res = cancel.run_until_cancelled(request).await
+            .ok_or(SpawnError::Cancelled)?
+            .map_err(crate::request_error)?;
+        if !res.status().is_success() {
+            return Err(crate::api_error(res).await);
+        }
+        Ok(())
+    }
+
+    /// `responseJsonSchema` holds every current Gemini model to a schema
+    fn supports_json_schema(&self, _model: &str) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &str {
+        "gemini"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_body_and_response() {
+        let client = GeminiClient::new("key").with_safety_setting("harassment", "block_only_high");
+        assert_eq!(
+            client.model_url("google/gemini-2.5-flash", "generateContent"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent"
+        );
+        let messages = [
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("hi"),
+            ChatMessage::user("are you there?"),
+            ChatMessage::assistant("Yes."),
+        ];
+        let body = client.request_body(&messages, &ChatOptions::new().with_max_tokens(64));
+        assert_eq!(body["systemInstruction"], json!({ "parts": [{ "text": "Be brief." }] }));
+        assert_eq!(body["contents"][0], json!({ "role": "user", "parts": [{ "text": "hi" }, { "text": "are you there?" }] }));
+        assert_eq!(body["contents"][1]["role"], "model");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["safetySettings"], json!([{ "category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH" }]));
+
+        let response = json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hel" }, { "text": "lo" }] }, "finishReason": "MAX_TOKENS" }],
+            "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 5, "thoughtsTokenCount": 3, "totalTokenCount": 18 },
+        });
+        let delta = parse_response(&response).unwrap();
+        assert_eq!(delta.content, "Hello");
+        assert_eq!(delta.finish_reason.as_deref(), Some("length"));
+        assert_eq!(delta.usage.unwrap().total_tokens, 18);
+        assert!(parse_response(&json!({ "promptFeedback": { "blockReason": "SAFETY" } })).is_err());
+    }
+}
@@ -1,27 +1,151 @@
 //! spawn-ai: The speech center
 //! 
-//! LLM provider adapters, routing, and cost tracking.
-//! Currently supports OpenRouter (which proxies to everything).
+//! LLM provider adapters, fallback, model routing, response caching, token
+//! counting, context compaction, structured output, cost tracking, prompt
+//! templates, usage metrics, and record/replay of exchanges for tests.
+//! Supports OpenRouter (which proxies to everything), Azure OpenAI
+//! deployments, AWS Bedrock, Google Gemini, and local models through Ollama.
 
+mod azure;
+mod bedrock;
+mod cache;
+mod circuit;
+mod context;
+mod cost;
+mod gemini;
+mod manager;
+mod metrics;
+mod ollama;
 mod openrouter;
+mod prompt;
+mod rate_limit;
+mod replay;
+mod retry;
+mod router;
+mod sigv4;
+mod speech;
+mod structured;
+mod summarize;
+mod tokens;
 
-pub use openrouter::OpenRouterClient;
+pub use azure::AzureOpenAiClient;
+pub use bedrock::BedrockClient;
+pub use cache::{CachingClient, ResponseCache};
+pub use circuit::{CircuitBreaker, CircuitBreakerConfig};
+pub use context::{Compaction, ContextManager, DEFAULT_KEEP_RECENT};
+pub use cost::{CostSummary, CostTotals, CostTracker, MeteredClient, PricingTable};
+pub use gemini::GeminiClient;
+pub use manager::{Candidate, ProviderManager};
+pub use metrics::{LatencySummary, ModelUsage, TokenTotals, UsageMetrics, UsageSnapshot, LATENCY_SAMPLES};
+pub use ollama::{OllamaClient, OllamaModel};
+pub use openrouter::{GeneratedImage, OpenRouterClient, OpenRouterModel};
+pub use prompt::{PromptTemplates, DEFAULT_MISSION_PROMPT, MISSION_PROMPT};
+pub use replay::{Exchange, RecordingClient, ReplayClient};
+pub use router::ModelRouter;
+pub use sigv4::AwsCredentials;
+pub use speech::{OpenAiSpeechClient, WhisperClient};
+pub use structured::{StructuredChat, MAX_REPAIR_ATTEMPTS};
+pub use summarize::LlmSummarizer;
+pub use tokens::{count_tokens, estimate_tokens, message_tokens};
 
-use spawn_core::{LlmClient, Result};
-use std::sync::Arc;
+use futures::StreamExt;
+use spawn_core::{ChatOptions, ChatStream, HttpTimeouts, Result, SpawnError};
+use std::future::Future;
+use std::time::Duration;
 
-/// Provider manager for load balancing / fallback
-pub struct ProviderManager {
-    primary: Arc<dyn LlmClient>,
-    // TODO: Add fallback providers, cost tracking, rate limiting
+/// Classify a provider's non-success response, honouring `Retry-After` (seconds)
+async fn api_error(res: reqwest::Response) -> SpawnError {
+    let status = res.status().as_u16();
+    let retry_after = res.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs);
+    let body = res.text().await.unwrap_or_default();
+    SpawnError::from_status(status, body, retry_after)
 }
 
-impl ProviderManager {
-    pub fn new(primary: Arc<dyn LlmClient>) -> Self {
-        Self { primary }
+/// A request that got no response: transient unless it was never sendable
+fn request_error(e: reqwest::Error) -> SpawnError {
+    if e.is_timeout() || e.is_connect() || e.is_request() {
+        SpawnError::ProviderUnavailable(format!("Request failed: {}", e))
+    } else {
+        SpawnError::ProviderError(format!("Request failed: {}", e))
     }
-    
-    pub fn client(&self) -> &Arc<dyn LlmClient> {
-        &self.primary
+}
+
+/// An HTTP client with `timeouts`, for one provider
+fn http_client(timeouts: HttpTimeouts) -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(timeouts.connect)
+        .read_timeout(timeouts.read)
+        .build()
+        .expect("HTTP client should build with the default TLS backend")
+}
+
+/// The end of a call's `ChatOptions::timeout_secs`, counted from when the
+/// call starts
+#[derive(Clone, Copy)]
+struct Deadline(Option<(tokio::time::Instant, Duration)>);
+
+impl Deadline {
+    fn start(options: &ChatOptions) -> Self {
+        Self(options.timeout_secs.map(Duration::from_secs).map(|limit| (tokio::time::Instant::now() + limit, limit)))
+    }
+
+    /// `request`'s result, or a transient error once the deadline passes
+    async fn run<T>(self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.0 {
+            Some((at, limit)) => tokio::time::timeout_at(at, request).await.unwrap_or_else(|_| Err(timed_out(limit))),
+            None => request.await,
+        }
+    }
+
+    /// `stream` up to the deadline, then the same error as `run`
+    fn stream(self, stream: ChatStream) -> ChatStream {
+        let Some((at, limit)) = self.0 else {
+            return stream;
+        };
+        futures::stream::unfold(Some(stream), move |stream| async move {
+            let mut stream = stream?;
+            match tokio::time::timeout_at(at, stream.next()).await {
+                Ok(Some(delta)) => Some((delta, Some(stream))),
+                Ok(None) => None,
+                Err(_) => Some((Err(timed_out(limit)), None)),
+            }
+        })
+        .boxed()
+    }
+}
+
+fn timed_out(limit: Duration) -> SpawnError {
+    SpawnError::ProviderUnavailable(format!("No response within {}s", limit.as_secs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spawn_core::ChatDelta;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_cuts_off_slow_calls_and_streams() {
+        let options = ChatOptions::new().with_timeout_secs(5);
+        let slow = Deadline::start(&options).run(async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        assert!(matches!(slow.await, Err(SpawnError::ProviderUnavailable(_))));
+        assert_eq!(Deadline::start(&ChatOptions::new()).run(async { Ok(1) }).await.unwrap(), 1);
+
+        let deltas = futures::stream::iter(0..10)
+            .then(|i| async move {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                Ok(ChatDelta { content: i.to_string(), ..ChatDelta::default() })
+            })
+            .boxed();
+        let deltas: Vec<Result<ChatDelta>> = Deadline::start(&options).stream(deltas).collect().await;
+        assert_eq!(deltas.len(), 3);
+        assert!(deltas[..2].iter().all(Result::is_ok));
+        assert!(matches!(deltas[2], Err(SpawnError::ProviderUnavailable(_))));
     }
 }
@@ -0,0 +1,467 @@
+//! Provider manager
+//!
+//! Holds the LLM providers in order of preference and probes them in the
+//! background, so requests go to the first provider passing its probes
+//! instead of discovering an outage mid-mission. Each provider also has a
+//! circuit breaker fed by its requests and probes: while its circuit is open
+//! it is skipped, and a request failing on the provider side moves on to the
+//! next one. Requests to a provider with a rate limit wait for their turn.
+//! The manager also carries the deployment's `ModelRouter`, for callers
+//! choosing a model per task, and counts every call in `UsageMetrics`.
+//! `chat_race` and `chat_best_of` send one request to several models (or
+//! the same model several times) at once, for steps like planning where one
+//! good answer out of a few is worth the extra calls.
+
+use crate::circuit::{self, CircuitBreaker, CircuitBreakerConfig};
+use crate::metrics::{UsageMetrics, UsageSnapshot};
+use crate::rate_limit::RateLimiter;
+use crate::router::ModelRouter;
+use async_trait::async_trait;
+use futures::StreamExt;
+use spawn_core::{
+    CancellationToken, ChatMessage, ChatOptions, ChatResponse, ChatStream, CircuitState, LlmClient, ProviderHealth, RateLimit,
+    Result, SpawnError, TokenUsage,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Model asked for a one-token completion by providers without a cheaper probe
+const DEFAULT_PROBE_MODEL: &str = "openai/gpt-4o-mini";
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Provider {
+    client: Arc<dyn LlmClient>,
+    health: Mutex<ProviderHealth>,
+    breaker: Mutex<CircuitBreaker>,
+    limiter: RateLimiter,
+}
+
+impl Provider {
+    fn new(client: Arc<dyn LlmClient>, breaker: CircuitBreakerConfig, limit: RateLimit) -> Self {
+        let health = Mutex::new(ProviderHealth::new(client.provider_name()));
+        let breaker = Mutex::new(CircuitBreaker::new(breaker));
+        Self { client, health, breaker, limiter: RateLimiter::new(limit) }
+    }
+
+    /// Whether its circuit lets a request through now
+    fn allow(&self) -> bool {
+        self.breaker.lock().unwrap().allow(Instant::now())
+    }
+
+    /// Feed an outcome to the circuit breaker, logging when it trips or resets
+    fn record(&self, success: bool) {
+        let changed = self.breaker.lock().unwrap().record(Instant::now(), success);
+        match changed {
+            Some(CircuitState::Open) => warn!(provider = self.client.provider_name(), "Circuit opened; skipping provider"),
+            Some(CircuitState::Closed) => info!(provider = self.client.provider_name(), "Circuit closed; provider back in use"),
+            _ => {}
+        }
+    }
+
+    /// `result` of a request, recorded if it says anything about the provider
+    fn outcome<T>(&self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.record(true),
+            Err(e) if circuit::is_provider_failure(e) => self.record(false),
+            Err(_) => {}
+        }
+    }
+}
+
+/// One answer from `ProviderManager::chat_best_of` and how it scored
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub response: ChatResponse,
+    pub score: f64,
+}
+
+/// Provider manager for load balancing / fallback
+pub struct ProviderManager {
+    /// In order of preference; the first is the primary
+    providers: Vec<Provider>,
+    probe_model: String,
+    probe_timeout: Duration,
+    /// Applied to providers added later as well as those already there
+    breaker: CircuitBreakerConfig,
+    rate_limits: HashMap<String, RateLimit>,
+    router: Arc<ModelRouter>,
+    metrics: Arc<UsageMetrics>,
+}
+
+impl ProviderManager {
+    pub fn new(primary: Arc<dyn LlmClient>) -> Self {
+        Self {
+            providers: vec![Provider::new(primary, CircuitBreakerConfig::default(), RateLimit::default())],
+            probe_model: DEFAULT_PROBE_MODEL.to_string(),
+            probe_timeout: DEFAULT_PROBE_TIMEOUT,
+            breaker: CircuitBreakerConfig::default(),
+            rate_limits: HashMap::new(),
+            router: Arc::new(ModelRouter::default()),
+            metrics: Arc::new(UsageMetrics::new()),
+        }
+    }
+
+    /// Used while every provider added before it is failing its probes
+    pub fn with_fallback(mut self, client: Arc<dyn LlmClient>) -> Self {
+        let limit = self.rate_limits.get(client.provider_name()).copied().unwrap_or_default();
+        self.providers.push(Provider::new(client, self.breaker, limit));
+        self
+    }
+
+    /// Queue requests to the provider named `provider` beyond `limit`,
+    /// whether it was added already or is added later
+    pub fn with_rate_limit(mut self, provider: &str, limit: RateLimit) -> Self {
+        for p in self.providers.iter_mut().filter(|p| p.client.provider_name() == provider) {
+            p.limiter = RateLimiter::new(limit);
+        }
+        self.rate_limits.insert(provider.to_string(), limit);
+        self
+    }
+
+    pub fn with_probe_model(mut self, model: impl Into<String>) -> Self {
+        self.probe_model = model.into();
+        self
+    }
+
+    pub fn with_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    /// When providers' circuits open, for providers added before or after
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        for provider in &self.providers {
+            *provider.breaker.lock().unwrap() = CircuitBreaker::new(config);
+        }
+        self.breaker = config;
+        self
+    }
+
+    pub fn with_router(mut self, router: ModelRouter) -> Self {
+        self.router = Arc::new(router);
+        self
+    }
+
+    pub fn router(&self) -> &Arc<ModelRouter> {
+        &self.router
+    }
+
+    /// Calls, errors, tokens and latency per provider and model so far
+    pub fn metrics(&self) -> UsageSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Ask each of `models` at once (a model listed twice is sampled twice)
+    /// and return the first answer to arrive; the others are dropped. Fails
+    /// with the last error if every one does.
+    pub async fn chat_race(
+        &self,
+        models: &[&str],
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        if models.is_empty() {
+            return Err(SpawnError::Internal("chat_race needs at least one model".into()));
+        }
+        let requests = models.iter().map(|model| self.chat_with_usage(model, messages, options, cancel));
+        futures::future::select_ok(requests).await.map(|(response, _)| response)
+    }
+
+    /// Ask each of `models` at once and return every answer with its
+    /// `score`, best first; failed requests are left out unless all fail
+    pub async fn chat_best_of(
+        &self,
+        models: &[&str],
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+        score: impl Fn(&ChatResponse) -> f64,
+    ) -> Result<Vec<Candidate>> {
+        let requests = models.iter().map(|model| self.chat_with_usage(model, messages, options, cancel));
+        let mut last_error = None;
+        let mut candidates = Vec::new();
+        for result in futures::future::join_all(requests).await {
+            match result {
+                Ok(response) => candidates.push(Candidate { score: score(&response), response }),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        if candidates.is_empty() {
+            return Err(last_error.unwrap_or_else(|| SpawnError::Internal("chat_best_of needs at least one model".into())));
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+
+    /// The first provider passing its probes with its circuit closed, or
+    /// the primary if none is
+    pub fn client(&self) -> &Arc<dyn LlmClient> {
+        let provider = self.providers.iter()
+            .find(|p| p.health.lock().unwrap().healthy && p.breaker.lock().unwrap().state() == CircuitState::Closed)
+            .unwrap_or(&self.providers[0]);
+        &provider.client
+    }
+
+    /// Providers to try a request on, in order: those passing their probes,
+    /// then those failing them. Each still has to pass `Provider::allow`.
+    fn candidates(&self) -> Vec<&Provider> {
+        let (mut healthy, failing): (Vec<&Provider>, Vec<&Provider>) = self.providers.iter()
+            .partition(|p| p.health.lock().unwrap().healthy);
+        healthy.extend(failing);
+        healthy
+    }
+
+    fn all_open() -> SpawnError {
+        SpawnError::ProviderUnavailable("Every provider's circuit is open after repeated failures".into())
+    }
+
+    /// Latest probe outcome and circuit of each provider, in order of preference
+    pub fn health(&self) -> Vec<ProviderHealth> {
+        self.providers.iter()
+            .map(|p| {
+                let breaker = p.breaker.lock().unwrap();
+                ProviderHealth {
+                    circuit: breaker.state(),
+                    failure_rate: breaker.failure_rate(),
+                    ..p.health.lock().unwrap().clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Probe every provider once, concurrently
+    pub async fn check_all(&self) {
+        futures::future::join_all(self.providers.iter().map(|p| self.check(p))).await;
+    }
+
+    async fn check(&self, provider: &Provider) {
+        let started = Instant::now();
+        let cancel = CancellationToken::new();
+        let probe = provider.client.health_check(&self.probe_model, &cancel);
+        let outcome = match tokio::time::timeout(self.probe_timeout, probe).await {
+            Ok(result) => result,
+            Err(_) => Err(SpawnError::ProviderUnavailable(format!(
+                "Health probe timed out after {}s", self.probe_timeout.as_secs()
+            ))),
+        };
+
+        match &outcome {
+            Ok(()) => provider.breaker.lock().unwrap().probe_passed(),
+            Err(_) => provider.record(false),
+        }
+        let mut health = provider.health.lock().unwrap();
+        health.checked_at = Some(chrono::Utc::now());
+        health.latency_ms = Some(started.elapsed().as_millis() as u64);
+        match outcome {
+            Ok(()) => {
+                if !health.healthy {
+                    info!(provider = %health.provider, "LLM provider passing health probes again");
+                }
+                health.healthy = true;
+                health.error = None;
+                health.consecutive_failures = 0;
+            }
+            Err(e) => {
+                if health.healthy {
+                    warn!(provider = %health.provider, error = %e, "LLM provider failing health probes");
+                }
+                health.healthy = false;
+                health.error = Some(e.to_string());
+                health.consecutive_failures += 1;
+            }
+        }
+    }
+
+    /// Probe all providers every `interval`, starting now
+    pub fn spawn_health_checks(self: &Arc<Self>, interval: Duration) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.check_all().await;
+            }
+        });
+    }
+}
+
+/// Requests go to the first candidate provider once its rate limit lets
+/// them through, moving on to the next when one fails on the provider side;
+/// health probes skip the queue
+#[async_trait]
+impl LlmClient for ProviderManager {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        let mut last_error = None;
+        for provider in self.candidates().into_iter().filter(|p| p.allow()) {
+            let _permit = provider.limiter.acquire(cancel).await?;
+            let started = Instant::now();
+            let result = provider.client.chat_with_usage(model, messages, options, cancel).await;
+            self.metrics.record(provider.client.provider_name(), model, started.elapsed(), result.as_ref().map(|r| &r.usage));
+            provider.outcome(&result);
+            match result {
+                Err(e) if circuit::is_provider_failure(&e) => {
+                    warn!(provider = provider.client.provider_name(), error = %e, "Provider failed; trying the next one");
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(Self::all_open))
+    }
+
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let mut last_error = None;
+        for provider in self.candidates().into_iter().filter(|p| p.allow()) {
+            let permit = provider.limiter.acquire(cancel).await?;
+            let started = Instant::now();
+            let result = provider.client.chat_stream(model, messages, options, cancel).await;
+            if let Err(e) = &result {
+                self.metrics.record(provider.client.provider_name(), model, started.elapsed(), Err(e));
+            }
+            provider.outcome(&result);
+            match result {
+                // In flight until the caller is done with the stream
+                Ok(stream) => {
+                    // Counted when the stream fails or runs to the end; one
+                    // dropped part-way isn't
+                    let usage = Arc::new(Mutex::new(Some(TokenUsage::default())));
+                    let (name, model) = (provider.client.provider_name().to_string(), model.to_string());
+                    let finish = {
+                        let (usage, metrics, name, model) = (usage.clone(), self.metrics.clone(), name.clone(), model.clone());
+                        async move {
+                            if let Some(total) = usage.lock().unwrap().take() {
+                                metrics.record(&name, &model, started.elapsed(), Ok(&total));
+                            }
+                            None
+                        }
+                    };
+                    let metrics = self.metrics.clone();
+                    return Ok(stream
+                        .inspect(move |delta| {
+                            let _ = &permit;
+                            let mut usage = usage.lock().unwrap();
+                            match (delta, usage.as_mut()) {
+                                (Ok(delta), Some(total)) => if let Some(u) = delta.usage {
+                                    *total = u;
+                                },
+                                (Err(e), Some(_)) => {
+                                    *usage = None;
+                                    metrics.record(&name, &model, started.elapsed(), Err(e));
+                                }
+                                _ => {}
+                            }
+                        })
+                        .chain(futures::stream::once(finish).filter_map(futures::future::ready))
+                        .boxed());
+                }
+                Err(e) if circuit::is_provider_failure(&e) => {
+                    warn!(provider = provider.client.provider_name(), error = %e, "Provider failed; trying the next one");
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(Self::all_open))
+    }
+
+    async fn health_check(&self, model: &str, cancel: &CancellationToken) -> Result<()> {
+        self.client().health_check(model, cancel).await
+    }
+
+    fn supports_json_schema(&self, model: &str) -> bool {
+        self.client().supports_json_schema(model)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.client().provider_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spawn_core::TokenUsage;
+
+    /// Answers with the model's name, slowly for `slow`, and fails `broken`
+    struct ModelsClient;
+
+    #[async_trait]
+    impl LlmClient for ModelsClient {
+        async fn chat_with_usage(&self, model: &str, _: &[ChatMessage], _: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            match model {
+                "broken" => return Err(SpawnError::ProviderError("bad request".into())),
+                "slow" => tokio::time::sleep(Duration::from_millis(200)).await,
+                _ => {}
+            }
+            Ok(ChatResponse {
+                content: format!("answer from {}", model),
+                usage: TokenUsage::default(),
+                finish_reason: Some("stop".into()),
+                model: model.to_string(),
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "models"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_races_and_ranks_parallel_samples() {
+        let manager = ProviderManager::new(Arc::new(ModelsClient));
+        let (messages, options, cancel) = ([ChatMessage::user("Plan it")], ChatOptions::new(), CancellationToken::new());
+
+        let first = manager.chat_race(&["slow", "broken", "fast"], &messages, &options, &cancel).await.unwrap();
+        assert_eq!(first.model, "fast");
+        assert!(manager.chat_race(&["broken"], &messages, &options, &cancel).await.is_err());
+
+        let ranked = manager
+            .chat_best_of(&["fast", "broken", "slow"], &messages, &options, &cancel, |r| if r.model == "slow" { 0.9 } else { 0.4 })
+            .await
+            .unwrap();
+        let models: Vec<&str> = ranked.iter().map(|c| c.response.model.as_str()).collect();
+        assert_eq!(models, ["slow", "fast"]);
+        assert_eq!(ranked[0].score, 0.9);
+    }
+
+    /// Fails every request and probe on the provider side
+    struct DownClient;
+
+    #[async_trait]
+    impl LlmClient for DownClient {
+        async fn chat_with_usage(&self, _: &str, _: &[ChatMessage], _: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            Err(SpawnError::ProviderUnavailable("503".into()))
+        }
+
+        fn provider_name(&self) -> &str {
+            "down"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settings_reach_providers_added_later() {
+        let trip_at_once = CircuitBreakerConfig { min_requests: 1, ..CircuitBreakerConfig::default() };
+        let manager = ProviderManager::new(Arc::new(ModelsClient))
+            .with_circuit_breaker(trip_at_once)
+            .with_fallback(Arc::new(DownClient));
+        manager.check_all().await;
+        let health = manager.health();
+        assert_eq!(health[0].circuit, CircuitState::Closed);
+        assert_eq!(health[1].circuit, CircuitState::Open);
+    }
+}
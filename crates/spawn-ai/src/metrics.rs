@@ -0,0 +1,209 @@
+//! Usage metrics
+//!
+//! Every call the provider manager sends is counted per provider and model:
+//! calls, errors by code, tokens, and latency. Latency percentiles are over
+//! the most recent `LATENCY_SAMPLES` calls of each model, so they follow
+//! current behaviour rather than the whole uptime. A snapshot serializes to
+//! JSON or renders in the Prometheus text format.
+
+use serde::Serialize;
+use spawn_core::{SpawnError, TokenUsage};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latencies kept per model for percentiles
+pub const LATENCY_SAMPLES: usize = 1000;
+
+#[derive(Default)]
+struct Stats {
+    calls: u64,
+    errors: BTreeMap<&'static str, u64>,
+    tokens: TokenTotals,
+    latency_total: Duration,
+    latencies: VecDeque<Duration>,
+}
+
+/// Counters per provider and model
+#[derive(Default)]
+pub struct UsageMetrics {
+    models: Mutex<BTreeMap<(String, String), Stats>>,
+}
+
+impl UsageMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One call to `model` on `provider` that took `latency`. Cancelled
+    /// calls aren't counted: they say nothing about the model.
+    pub fn record(&self, provider: &str, model: &str, latency: Duration, outcome: std::result::Result<&TokenUsage, &SpawnError>) {
+        if matches!(outcome, Err(SpawnError::Cancelled)) {
+            return;
+        }
+        let mut models = self.models.lock().unwrap();
+        let stats = models.entry((provider.to_string(), model.to_string())).or_default();
+        stats.calls += 1;
+        match outcome {
+            Ok(usage) => stats.tokens.add(usage),
+            Err(e) => *stats.errors.entry(e.code()).or_default() += 1,
+        }
+        stats.latency_total += latency;
+        if stats.latencies.len() == LATENCY_SAMPLES {
+            stats.latencies.pop_front();
+        }
+        stats.latencies.push_back(latency);
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let models = self.models.lock().unwrap();
+        let models = models.iter()
+            .map(|((provider, model), stats)| {
+                let mut latencies: Vec<Duration> = stats.latencies.iter().copied().collect();
+                latencies.sort();
+                let errors: u64 = stats.errors.values().sum();
+                ModelUsage {
+                    provider: provider.clone(),
+                    model: model.clone(),
+                    calls: stats.calls,
+                    errors,
+                    error_rate: if stats.calls == 0 { 0.0 } else { errors as f64 / stats.calls as f64 },
+                    errors_by_code: stats.errors.iter().map(|(code, n)| (code.to_string(), *n)).collect(),
+                    tokens: stats.tokens,
+                    latency_ms: LatencySummary {
+                        p50: percentile(&latencies, 0.5),
+                        p90: percentile(&latencies, 0.9),
+                        p99: percentile(&latencies, 0.99),
+                        max: latencies.last().map_or(0, |l| l.as_millis() as u64),
+                        total: stats.latency_total.as_millis() as u64,
+                    },
+                }
+            })
+            .collect();
+        UsageSnapshot { models }
+    }
+}
+
+/// Nearest-rank percentile of sorted `latencies`, in milliseconds
+fn percentile(latencies: &[Duration], p: f64) -> u64 {
+    if latencies.is_empty() {
+        return 0;
+    }
+    let rank = ((p * latencies.len() as f64).ceil() as usize).clamp(1, latencies.len());
+    latencies[rank - 1].as_millis() as u64
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TokenTotals {
+    pub prompt: u64,
+    pub completion: u64,
+    /// Prompt tokens served from the provider's prompt cache
+    pub cached: u64,
+}
+
+impl TokenTotals {
+    fn add(&mut self, usage: &TokenUsage) {
+        self.prompt += u64::from(usage.prompt_tokens);
+        self.completion += u64::from(usage.completion_tokens);
+        self.cached += u64::from(usage.cached_tokens);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LatencySummary {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+    /// Over every call counted, for averages
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub model: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    /// By `SpawnError::code`
+    pub errors_by_code: BTreeMap<String, u64>,
+    pub tokens: TokenTotals,
+    pub latency_ms: LatencySummary,
+}
+
+/// Usage per provider and model, ordered by provider, then model
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageSnapshot {
+    pub models: Vec<ModelUsage>,
+}
+
+impl UsageSnapshot {
+    /// The Prometheus text exposition format, metrics prefixed `spawn_llm_`
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let labels = |m: &ModelUsage| format!("provider=\"{}\",model=\"{}\"", escape(&m.provider), escape(&m.model));
+        let mut family = |name: &str, kind: &str, help: &str, lines: &mut dyn Iterator<Item = String>| {
+            let _ = writeln!(out, "# HELP spawn_llm_{} {}\n# TYPE spawn_llm_{} {}", name, help, name, kind);
+            for line in lines {
+                let _ = writeln!(out, "spawn_llm_{}", line);
+            }
+        };
+        family("calls_total", "counter", "Model calls sent to providers.", &mut self.models.iter()
+            .map(|m| format!("calls_total{{{}}} {}", labels(m), m.calls)));
+        family("errors_total", "counter", "Model calls that failed, by error code.", &mut self.models.iter()
+            .flat_map(|m| m.errors_by_code.iter().map(move |(code, n)| format!("errors_total{{{},code=\"{}\"}} {}", labels(m), code, n))));
+        family("tokens_total", "counter", "Tokens used, by kind.", &mut self.models.iter()
+            .flat_map(|m| [("prompt", m.tokens.prompt), ("completion", m.tokens.completion), ("cached", m.tokens.cached)]
+                .map(|(kind, n)| format!("tokens_total{{{},kind=\"{}\"}} {}", labels(m), kind, n))));
+        family("latency_seconds", "summary", "Call latency over recent calls.", &mut self.models.iter()
+            .flat_map(|m| {
+                let latency = m.latency_ms;
+                [("0.5", latency.p50), ("0.9", latency.p90), ("0.99", latency.p99)]
+                    .map(|(q, ms)| format!("latency_seconds{{{},quantile=\"{}\"}} {}", labels(m), q, ms as f64 / 1000.0))
+                    .into_iter()
+                    .chain([
+                        format!("latency_seconds_sum{{{}}} {}", labels(m), latency.total as f64 / 1000.0),
+                        format!("latency_seconds_count{{{}}} {}", labels(m), m.calls),
+                    ])
+            }));
+        out
+    }
+}
+
+/// A label value as Prometheus quotes it
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_calls_per_model() {
+        let metrics = UsageMetrics::new();
+        let usage = TokenUsage { prompt_tokens: 100, completion_tokens: 20, total_tokens: 120, cached_tokens: 40 };
+        for ms in 1..=10 {
+            metrics.record("openrouter", "gpt-4o", Duration::from_millis(ms * 100), Ok(&usage));
+        }
+        metrics.record("openrouter", "gpt-4o", Duration::from_millis(50), Err(&SpawnError::ProviderUnavailable("down".into())));
+        metrics.record("openrouter", "gpt-4o", Duration::from_millis(50), Err(&SpawnError::Cancelled));
+        metrics.record("ollama", "llama3", Duration::from_millis(10), Ok(&usage));
+
+        let snapshot = metrics.snapshot();
+        let gpt = snapshot.models.iter().find(|m| m.model == "gpt-4o").unwrap();
+        assert_eq!((gpt.calls, gpt.errors), (11, 1));
+        assert_eq!(gpt.errors_by_code["provider_unavailable"], 1);
+        assert_eq!((gpt.tokens.prompt, gpt.tokens.completion, gpt.tokens.cached), (1000, 200, 400));
+        assert_eq!((gpt.latency_ms.p50, gpt.latency_ms.p99, gpt.latency_ms.max), (500, 1000, 1000));
+        assert_eq!(snapshot.models[0].provider, "ollama");
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("spawn_llm_calls_total{provider=\"openrouter\",model=\"gpt-4o\"} 11\n"));
+        assert!(text.contains("spawn_llm_errors_total{provider=\"openrouter\",model=\"gpt-4o\",code=\"provider_unavailable\"} 1\n"));
+        assert!(text.contains("spawn_llm_latency_seconds{provider=\"ollama\",model=\"llama3\",quantile=\"0.5\"} 0.01\n"));
+        assert!(text.contains("# TYPE spawn_llm_latency_seconds summary\n"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Ollama client, for models running locally
+//!
+//! Talks to Ollama's native API (`/api/chat`, `/api/tags`). Model ids may
+//! carry an `ollama/` prefix (`ollama/llama3.1`), which is dropped before
+//! they are sent.
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, ContentPart, HttpTimeouts, LlmClient,
+    MessageContent, ResponseFormat, Result, SpawnError, TokenUsage,
+};
+use tracing::{debug, error};
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+pub struct OllamaClient {
+    base_url: String,
+    /// How long Ollama keeps the model loaded after a request (`5m`, `1h`,
+    /// `-1` for ever); Ollama's default when unset
+    keep_alive: Option<String>,
+    client: Client,
+}
+
+/// A model pulled into the local Ollama
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+}
+
+impl OllamaClient {
+    pub fn new() -> Self {
+        Self { base_url: DEFAULT_BASE_URL.to_string(), keep_alive: None, client: crate::http_client(HttpTimeouts::default()) }
+    }
+
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: impl Into<String>) -> Self {
+        self.keep_alive = Some(keep_alive.into());
+        self
+    }
+
+    /// Connect and read timeouts for this provider's requests
+    pub fn with_timeouts(mut self, timeouts: HttpTimeouts) -> Self {
+        self.client = crate::http_client(timeouts);
+        self
+    }
+
+    /// The models available locally
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>> {
+        #[derive(Deserialize)]
+        struct Tags {
+            models: Vec<OllamaModel>,
+        }
+        let res = self.client
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+        if !res.status().is_success() {
+            return Err(crate::api_error(res).await);
+        }
+        let tags: Tags = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        Ok(tags.models)
+    }
+
+    fn request_body(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions, stream: bool) -> serde_json::Value {
+        let mut body = json!({
+            "model": model.strip_prefix("ollama/").unwrap_or(model),
+            "messages": messages.iter().map(wire_message).collect::<Vec<_>>(),
+            "stream": stream,
+            "options": wire_options(options),
+        });
+        match &options.response_format {
+            Some(ResponseFormat::JsonObject) => body["format"] = json!("json"),
+            Some(ResponseFormat::JsonSchema { json_schema }) => body["format"] = json_schema.schema.clone(),
+            None => {}
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            // Durations are strings; a bare number is seconds
+            body["keep_alive"] = keep_alive.parse::<i64>().map(|secs| json!(secs)).unwrap_or_else(|_| json!(keep_alive));
+        }
+        body
+    }
+
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response> {
+        let res = self.client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(body)
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, error = %err, "Ollama API error");
+            return Err(err);
+        }
+        Ok(res)
+    }
+
+    async fn complete(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        debug!(model = model, message_count = messages.len(), "Sending Ollama chat request");
+        let res = self.send(&self.request_body(model, messages, options, false)).await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        let delta = parse_chunk(&json)?;
+        Ok(ChatResponse {
+            content: delta.content,
+            usage: delta.usage.unwrap_or_default(),
+            finish_reason: delta.finish_reason,
+            model: json["model"].as_str().unwrap_or(model).to_string(),
+        })
+    }
+}
+
+impl Default for OllamaClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ollama takes text content plus a list of base64 images; images by URL
+/// can't be passed and are left out
+fn wire_message(message: &ChatMessage) -> serde_json::Value {
+    let role = serde_json::to_value(&message.role).unwrap_or(json!("user"));
+    match &message.content {
+        MessageContent::Text(text) => json!({ "role": role, "content": text }),
+        MessageContent::Parts(parts) => {
+            let text: Vec<&str> = parts.iter()
+                .filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            let images: Vec<&str> = parts.iter()
+                .filter_map(|part| match part {
+                    ContentPart::ImageBase64 { data, .. } => Some(data.as_str()),
+                    _ => None,
+                })
+                .collect();
+            json!({ "role": role, "content": text.join("\n"), "images": images })
+        }
+    }
+}
+
+fn wire_options(options: &ChatOptions) -> serde_json::Value {
+    let mut wire = serde_json::Map::new();
+    if let Some(temperature) = options.temperature {
+        wire.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(max_tokens) = options.max_tokens {
+        wire.insert("num_predict".into(), json!(max_tokens));
+    }
+    if let Some(top_p) = options.top_p {
+        wire.insert("top_p".into(), json!(top_p));
+    }
+    if !options.stop.is_empty() {
+        wire.insert("stop".into(), json!(options.stop));
+    }
+    if let Some(seed) = options.seed {
+        wire.insert("seed".into(), json!(seed));
+    }
+    serde_json::Value::Object(wire)
+}
+
+/// One response object: the whole reply, or one line of a stream. Token
+/// counts come with the last (`done`) one.
+fn parse_chunk(json: &serde_json::Value) -> Result<ChatDelta> {
+    if let Some(message) = json["error"].as_str() {
+        return Err(SpawnError::ProviderError(message.to_string()));
+    }
+    let done = json["done"].as_bool().unwrap_or(false);
+    let usage = done.then(|| {
+        let prompt_tokens = json["prompt_eval_count"].as_u64().unwrap_or(0) as u32;
+        let completion_tokens = json["eval_count"].as_u64().unwrap_or(0) as u32;
+        TokenUsage { prompt_tokens, completion_tokens, total_tokens: prompt_tokens + completion_tokens, ..Default::default() }
+    });
+    Ok(ChatDelta {
+        content: json["message"]["content"].as_str().unwrap_or_default().to_string(),
+        finish_reason: done.then(|| json["done_reason"].as_str().unwrap_or("stop").to_string()),
+        usage,
+    })
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        cancel.run_until_cancelled(crate::Deadline::start(options).run(self.complete(model, messages, options))).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+
+    /// Newline-delimited JSON, one object per delta
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        debug!(model = model, message_count = messages.len(), "Sending streaming Ollama chat request");
+        let body = self.request_body(model, messages, options, true);
+        let deadline = crate::Deadline::start(options);
+        let res = cancel.run_until_cancelled(deadline.run(self.send(&body))).await
+            .ok_or(SpawnError::Cancelled)??;
+
+        let lines = res.bytes_stream().scan(Vec::new(), |pending: &mut Vec<u8>, chunk| {
+            let lines = match chunk {
+                Ok(chunk) => {
+                    pending.extend_from_slice(&chunk);
+                    let mut lines = Vec::new();
+                    while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=end).collect();
+                        lines.push(Ok(line));
+                    }
+                    lines
+                }
+                Err(e) => vec![Err(SpawnError::ProviderUnavailable(format!("Stream interrupted: {}", e)))],
+            };
+            futures::future::ready(Some(futures::stream::iter(lines)))
+        });
+        Ok(deadline.stream(lines
+            .flatten()
+            .filter_map(|line| futures::future::ready(match line {
+                Ok(line) if line.iter().all(u8::is_ascii_whitespace) => None,
+                Ok(line) => Some(serde_json::from_slice(&line)
+                    .map_err(|e| SpawnError::ProviderError(format!("Invalid stream line: {}", e)))
+                    .and_then(|json| parse_chunk(&json))),
+                Err(e) => Some(Err(e)),
+            }))
+            .boxed()))
+    }
+
+    /// Lists the local models; no generation needed
+    async fn health_check(&self, _model: &str, cancel: &CancellationToken) -> Result<()> {
+        cancel.run_until_cancelled(self.list_models()).await
+            .ok_or(SpawnError::Cancelled)??;
+        Ok(())
+    }
+
+    /// `format` takes a schema since Ollama 0.5
+    fn supports_json_schema(&self, _model: &str) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &str {
+        "ollama"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_body_and_final_chunk() {
+        let client = OllamaClient::new().with_keep_alive("-1");
+        let options = ChatOptions::new().with_max_tokens(64).with_temperature(0.5);
+        let body = client.request_body("ollama/llama3.1", &[ChatMessage::user("hi")], &options, false);
+        assert_eq!(body["model"], "llama3.1");
+        assert_eq!(body["options"]["num_predict"], 64);
+        assert_eq!(body["keep_alive"], -1);
+        assert_eq!(body["messages"][0], json!({ "role": "user", "content": "hi" }));
+
+        let done = json!({
+            "model": "llama3.1", "message": { "role": "assistant", "content": "" },
+            "done": true, "done_reason": "length", "prompt_eval_count": 12, "eval_count": 64,
+        });
+        let delta = parse_chunk(&done).unwrap();
+        assert_eq!(delta.finish_reason.as_deref(), Some("length"));
+        assert_eq!(delta.usage.unwrap().total_tokens, 76);
+    }
+}
@@ -1,25 +1,55 @@
 //! OpenRouter API client
+//!
+//! Prompt caching: OpenAI-style models cache long prompt prefixes on their
+//! own. Anthropic and Gemini models only cache up to a `cache_control`
+//! marker, which is put on the text of messages flagged as cache
+//! breakpoints. Either way the cached part comes back as
+//! `prompt_tokens_details.cached_tokens`.
 
 use async_trait::async_trait;
+use base64::Engine;
+use futures::StreamExt;
 use reqwest::Client;
 use serde_json::json;
-use spawn_core::{ChatMessage, LlmClient, Result, SpawnError};
+use serde::Serialize;
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, ContentPart, EmbeddingClient, HealthCheck, HttpTimeouts,
+    LlmClient, MessageContent, ModelPrice, Result, RetryPolicy, SpawnError, TokenUsage,
+};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tracing::{debug, error};
 
+const COMPLETIONS_URL: &str = "https://openrouter.ai/api/v1/chat/completions";
+const EMBEDDINGS_URL: &str = "https://openrouter.ai/api/v1/embeddings";
+const MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+/// How long a fetched model catalog is served before it is fetched again
+const CATALOG_TTL: Duration = Duration::from_secs(3600);
+/// Inputs sent per embeddings request
+const EMBEDDING_BATCH_SIZE: usize = 64;
+/// Anthropic accepts at most this many `cache_control` markers per request
+const MAX_CACHE_BREAKPOINTS: usize = 4;
+
 pub struct OpenRouterClient {
     api_key: String,
     client: Client,
     site_url: String,
     site_name: String,
+    /// Applied to every chat, image and embeddings request
+    retry: RetryPolicy,
+    /// The last model catalog fetched, and when
+    catalog: RwLock<Option<(Instant, Arc<Vec<OpenRouterModel>>)>>,
 }
 
 impl OpenRouterClient {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             api_key: api_key.into(),
-            client: Client::new(),
+            client: crate::http_client(HttpTimeouts::default()),
             site_url: "https://spawn.new".to_string(),
             site_name: "Spawn".to_string(),
+            retry: RetryPolicy::default(),
+            catalog: RwLock::new(None),
         }
     }
     
@@ -28,52 +58,477 @@ impl OpenRouterClient {
         self.site_name = name.into();
         self
     }
+    
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    
+    /// Connect and read timeouts for this provider's requests
+    pub fn with_timeouts(mut self, timeouts: HttpTimeouts) -> Self {
+        self.client = crate::http_client(timeouts);
+        self
+    }
 }
 
-#[async_trait]
-impl LlmClient for OpenRouterClient {
-    async fn chat(&self, model: &str, messages: &[ChatMessage]) -> Result<String> {
-        debug!(model = model, message_count = messages.len(), "Sending chat request");
+/// Messages in the OpenAI wire format; multimodal content becomes
+/// `[{"type": "text"}, {"type": "image_url"}]` parts, with inline images sent
+/// as data URLs. Timestamps and metadata stay local. With `cache_control`,
+/// the last few cache breakpoints get a marker on their final text part.
+pub(crate) fn wire_messages(messages: &[ChatMessage], cache_control: bool) -> Result<Vec<serde_json::Value>> {
+    let marked: Vec<usize> = match cache_control {
+        true => messages.iter().enumerate().filter(|(_, m)| m.cache_breakpoint).map(|(i, _)| i).collect(),
+        false => Vec::new(),
+    };
+    let marked = &marked[marked.len().saturating_sub(MAX_CACHE_BREAKPOINTS)..];
+    messages.iter()
+        .enumerate()
+        .map(|(i, message)| {
+            let mut value = serde_json::to_value(message)?;
+            if let Some(fields) = value.as_object_mut() {
+                fields.remove("created_at");
+                fields.remove("metadata");
+                fields.remove("cache_breakpoint");
+            }
+            if let MessageContent::Parts(parts) = &message.content {
+                value["content"] = parts.iter().map(wire_part).collect();
+            }
+            if marked.contains(&i) {
+                mark_cache_control(&mut value["content"]);
+            }
+            Ok(value)
+        })
+        .collect()
+}
+
+/// Put an ephemeral `cache_control` marker on the last text of `content`
+fn mark_cache_control(content: &mut serde_json::Value) {
+    if let Some(text) = content.as_str() {
+        *content = json!([{ "type": "text", "text": text }]);
+    }
+    let last_text = content.as_array_mut()
+        .and_then(|parts| parts.iter_mut().rev().find(|part| part["type"] == "text"));
+    if let Some(part) = last_text {
+        part["cache_control"] = json!({ "type": "ephemeral" });
+    }
+}
+
+/// Models OpenRouter can hold to a `json_schema` response format
+fn supports_structured_outputs(model: &str) -> bool {
+    let structured = ["openai/gpt-4o", "openai/gpt-4.1", "openai/gpt-5", "openai/o3", "openai/o4", "google/gemini"];
+    structured.iter().any(|prefix| model.starts_with(prefix))
+}
+
+/// Models that cache only what is marked with `cache_control`
+fn uses_cache_control(model: &str) -> bool {
+    model.starts_with("anthropic/") || model.starts_with("google/gemini")
+}
+
+fn wire_part(part: &ContentPart) -> serde_json::Value {
+    match part {
+        ContentPart::Text { text } => json!({ "type": "text", "text": text }),
+        ContentPart::ImageUrl { url, detail } => {
+            let mut image_url = json!({ "url": url });
+            if let Some(detail) = detail {
+                image_url["detail"] = json!(detail);
+            }
+            json!({ "type": "image_url", "image_url": image_url })
+        }
+        ContentPart::ImageBase64 { mime_type, data } => json!({
+            "type": "image_url",
+            "image_url": { "url": format!("data:{};base64,{}", mime_type, data) },
+        }),
+    }
+}
+
+/// An image returned by an image-capable model
+#[derive(Debug, Clone)]
+pub struct GeneratedImage {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl GeneratedImage {
+    /// Every extension `extension` can return
+    pub const EXTENSIONS: [&'static str; 5] = ["png", "jpg", "webp", "gif", "svg"];
+
+    /// File extension for the image's MIME type
+    pub fn extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/jpeg" => "jpg",
+            "image/webp" => "webp",
+            "image/gif" => "gif",
+            "image/svg+xml" => "svg",
+            _ => "png",
+        }
+    }
+
+    /// Decode a `data:<mime>;base64,<data>` URL
+    fn from_data_url(url: &str) -> Option<Self> {
+        let (header, payload) = url.strip_prefix("data:")?.split_once(',')?;
+        let mime_type = header.strip_suffix(";base64")?.to_string();
+        let data = base64::engine::general_purpose::STANDARD.decode(payload).ok()?;
+        Some(Self { mime_type, data })
+    }
+}
+
+impl OpenRouterClient {
+    /// Generate images with an image-capable model (e.g. `google/gemini-2.5-flash-image-preview`)
+    pub async fn generate_image(&self, model: &str, prompt: &str) -> Result<Vec<GeneratedImage>> {
+        debug!(model = model, "Sending image generation request");
         
         let body = json!({
             "model": model,
-            "messages": messages,
-            "temperature": 0.7,
+            "messages": [{ "role": "user", "content": prompt }],
+            "modalities": ["image", "text"],
         });
 
+        let res = self.send(&body).await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+
+        let images: Vec<GeneratedImage> = json["choices"][0]["message"]["images"]
+            .as_array()
+            .map(|arr| arr.iter()
+                .filter_map(|img| img["image_url"]["url"].as_str())
+                .filter_map(GeneratedImage::from_data_url)
+                .collect())
+            .unwrap_or_default();
+
+        if images.is_empty() {
+            return Err(SpawnError::ProviderError(format!("Model {} returned no images", model)));
+        }
+        Ok(images)
+    }
+}
+
+/// A model in OpenRouter's catalog
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenRouterModel {
+    pub id: String,
+    pub name: String,
+    /// Prompt and completion tokens together
+    pub context_length: Option<u32>,
+    pub max_completion_tokens: Option<u32>,
+    /// Unset for routers whose price depends on the model they pick
+    pub pricing: Option<ModelPrice>,
+    /// Takes images as input
+    pub vision: bool,
+    pub tools: bool,
+    /// Enforces `ResponseFormat::JsonSchema`
+    pub structured_outputs: bool,
+    pub reasoning: bool,
+}
+
+impl OpenRouterModel {
+    /// One `data` entry of `/api/v1/models`
+    fn parse(json: &serde_json::Value) -> Option<Self> {
+        let parameters: Vec<&str> = json["supported_parameters"].as_array()
+            .map(|params| params.iter().filter_map(|p| p.as_str()).collect())
+            .unwrap_or_default();
+        // Per token, as decimal strings; negative for variable prices
+        let per_million = |price: &serde_json::Value| {
+            price.as_str().and_then(|p| p.parse::<f64>().ok()).filter(|p| *p >= 0.0).map(|p| p * 1_000_000.0)
+        };
+        let pricing = &json["pricing"];
+        let tokens = |value: &serde_json::Value| value.as_u64().map(|n| n.min(u64::from(u32::MAX)) as u32);
+        Some(Self {
+            id: json["id"].as_str()?.to_string(),
+            name: json["name"].as_str().unwrap_or_default().to_string(),
+            context_length: tokens(&json["context_length"]).or(tokens(&json["top_provider"]["context_length"])),
+            max_completion_tokens: tokens(&json["top_provider"]["max_completion_tokens"]),
+            pricing: per_million(&pricing["prompt"]).zip(per_million(&pricing["completion"]))
+                .map(|(prompt, completion)| ModelPrice { prompt, completion, cached_prompt: per_million(&pricing["input_cache_read"]) }),
+            vision: json["architecture"]["input_modalities"].as_array()
+                .is_some_and(|modalities| modalities.iter().any(|m| m == "image")),
+            tools: parameters.contains(&"tools"),
+            structured_outputs: parameters.contains(&"structured_outputs"),
+            reasoning: parameters.contains(&"reasoning"),
+        })
+    }
+}
+
+impl OpenRouterClient {
+    /// The models OpenRouter serves, fetched at most once an hour
+    pub async fn list_models(&self) -> Result<Arc<Vec<OpenRouterModel>>> {
+        if let Some((fetched, models)) = self.catalog.read().unwrap().as_ref() {
+            if fetched.elapsed() < CATALOG_TTL {
+                return Ok(models.clone());
+            }
+        }
+        self.refresh_models().await
+    }
+
+    /// Fetch the catalog now, whatever is cached
+    pub async fn refresh_models(&self) -> Result<Arc<Vec<OpenRouterModel>>> {
+        let res = crate::retry::with_retry(&self.retry, "openrouter", || async {
+            let res = self.client.get(MODELS_URL).send().await.map_err(crate::request_error)?;
+            if !res.status().is_success() {
+                return Err(crate::api_error(res).await);
+            }
+            Ok(res)
+        })
+        .await?;
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+        let models: Vec<OpenRouterModel> = json["data"].as_array()
+            .ok_or_else(|| SpawnError::ProviderError("Invalid models response".into()))?
+            .iter()
+            .filter_map(OpenRouterModel::parse)
+            .collect();
+        debug!(count = models.len(), "Fetched OpenRouter model catalog");
+        let models = Arc::new(models);
+        *self.catalog.write().unwrap() = Some((Instant::now(), models.clone()));
+        Ok(models)
+    }
+
+    /// A model from the cached catalog, if one has been fetched
+    pub fn model_info(&self, id: &str) -> Option<OpenRouterModel> {
+        let catalog = self.catalog.read().unwrap();
+        catalog.as_ref()?.1.iter().find(|m| m.id == id).cloned()
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for OpenRouterClient {
+    /// Sent in batches of `EMBEDDING_BATCH_SIZE`, each retried on its own
+    async fn embed_batch(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        debug!(model = model, count = texts.len(), "Sending embeddings request");
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBEDDING_BATCH_SIZE) {
+            let body = json!({ "model": model, "input": batch });
+            let res = crate::retry::with_retry(&self.retry, "openrouter", || self.post(EMBEDDINGS_URL, &body)).await?;
+            let json: serde_json::Value = res.json().await
+                .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+            embeddings.extend(parse_embeddings(&json, batch.len())?);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Vectors from an embeddings response, put back in input order
+fn parse_embeddings(json: &serde_json::Value, expected: usize) -> Result<Vec<Vec<f32>>> {
+    let mut data: Vec<&serde_json::Value> = json["data"].as_array()
+        .map(|arr| arr.iter().collect())
+        .unwrap_or_default();
+    if data.len() != expected {
+        return Err(SpawnError::ProviderError(format!("Expected {} embeddings, got {}", expected, data.len())));
+    }
+    data.sort_by_key(|item| item["index"].as_u64());
+    data.iter()
+        .map(|item| item["embedding"].as_array()
+            .map(|values| values.iter().filter_map(|v| v.as_f64().map(|f| f as f32)).collect())
+            .ok_or_else(|| SpawnError::ProviderError("Invalid embedding response".into())))
+        .collect()
+}
+
+#[async_trait]
+impl LlmClient for OpenRouterClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        // Dropping the request future aborts the HTTP call
+        cancel.run_until_cancelled(crate::Deadline::start(options).run(self.complete(model, messages, options))).await
+            .unwrap_or(Err(SpawnError::Cancelled))
+    }
+    
+    /// Server-sent events, as they arrive; the last delta carries the usage
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let mut body = request_body(model, messages, options)?;
+        body["stream"] = json!(true);
+        body["stream_options"] = json!({ "include_usage": true });
+        debug!(model = model, message_count = messages.len(), ?options, "Sending streaming chat request");
+        let deadline = crate::Deadline::start(options);
+        let res = cancel.run_until_cancelled(deadline.run(self.send(&body))).await
+            .ok_or(SpawnError::Cancelled)??;
+        Ok(deadline.stream(sse_deltas(res.bytes_stream())))
+    }
+    
+    /// A models listing: reaches the API without spending tokens
+    async fn health_check(&self, _model: &str, cancel: &CancellationToken) -> Result<()> {
+        let request = self.client
+            .get(MODELS_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send();
+        let res = cancel.run_until_cancelled(request).await
+            .ok_or(SpawnError::Cancelled)?
+            .map_err(crate::request_error)?;
+        if !res.status().is_success() {
+            return Err(crate::api_error(res).await);
+        }
+        Ok(())
+    }
+    
+    /// What the catalog says once it has been fetched, a list of model
+    /// families until then
+    fn supports_json_schema(&self, model: &str) -> bool {
+        match self.model_info(model) {
+            Some(info) => info.structured_outputs,
+            None => supports_structured_outputs(model),
+        }
+    }
+    
+    fn provider_name(&self) -> &str {
+        "openrouter"
+    }
+}
+
+/// The same probe the provider manager runs, on demand
+#[async_trait]
+impl HealthCheck for OpenRouterClient {
+    fn name(&self) -> &str {
+        "openrouter"
+    }
+    
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        LlmClient::health_check(self, "", &CancellationToken::new()).await?;
+        Ok(None)
+    }
+}
+
+/// A chat completions request body
+pub(crate) fn request_body(model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<serde_json::Value> {
+    let mut body = json!({
+        "model": model,
+        "messages": wire_messages(messages, uses_cache_control(model))?,
+    });
+    // Field names match the OpenAI API; unset ones are left out
+    if let (Some(body), serde_json::Value::Object(options)) = (body.as_object_mut(), serde_json::to_value(options)?) {
+        body.extend(options);
+    }
+    Ok(body)
+}
+
+/// Completion deltas from an SSE body. Events may be split across chunks;
+/// comment lines (`: OPENROUTER PROCESSING`) are keep-alives.
+pub(crate) fn sse_deltas<B: AsRef<[u8]>>(bytes: impl futures::Stream<Item = reqwest::Result<B>> + Send + 'static) -> ChatStream {
+    sse_stream(bytes, parse_event)
+}
+
+/// Deltas from an SSE body, `parse` turning each `data:` payload into one;
+/// the stream ends where `parse` returns `None`
+pub(crate) fn sse_stream<B: AsRef<[u8]>>(
+    bytes: impl futures::Stream<Item = reqwest::Result<B>> + Send + 'static,
+    parse: fn(&str) -> Option<Result<ChatDelta>>,
+) -> ChatStream {
+    let lines = bytes.scan(Vec::new(), |pending: &mut Vec<u8>, chunk| {
+        let lines = match chunk {
+            Ok(chunk) => {
+                pending.extend_from_slice(chunk.as_ref());
+                let mut lines = Vec::new();
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    lines.push(Ok(String::from_utf8_lossy(&line).trim_end().to_string()));
+                }
+                lines
+            }
+            Err(e) => vec![Err(SpawnError::ProviderUnavailable(format!("Stream interrupted: {}", e)))],
+        };
+        futures::future::ready(Some(futures::stream::iter(lines)))
+    })
+    .flatten();
+
+    lines
+        .filter_map(move |line| futures::future::ready(match line {
+            Ok(line) => line.strip_prefix("data:").map(|data| parse(data.trim())),
+            Err(e) => Some(Some(Err(e))),
+        }))
+        .take_while(|event| futures::future::ready(event.is_some()))
+        .filter_map(futures::future::ready)
+        .boxed()
+}
+
+/// One `data:` payload: a delta, an error, or `None` for `[DONE]`
+fn parse_event(data: &str) -> Option<Result<ChatDelta>> {
+    if data == "[DONE]" {
+        return None;
+    }
+    let json: serde_json::Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(e) => return Some(Err(SpawnError::ProviderError(format!("Invalid stream event: {}", e)))),
+    };
+    if let Some(message) = json["error"]["message"].as_str() {
+        return Some(Err(SpawnError::ProviderError(message.to_string())));
+    }
+    let choice = &json["choices"][0];
+    Some(Ok(ChatDelta {
+        content: choice["delta"]["content"].as_str().unwrap_or_default().to_string(),
+        finish_reason: choice["finish_reason"].as_str().map(String::from),
+        usage: parse_usage(&json["usage"]),
+    }))
+}
+
+/// Token counts from a `usage` object, with the cached part of the prompt
+fn parse_usage(usage: &serde_json::Value) -> Option<TokenUsage> {
+    let mut parsed: TokenUsage = serde_json::from_value(usage.clone()).ok()?;
+    parsed.cached_tokens = usage["prompt_tokens_details"]["cached_tokens"].as_u64().unwrap_or(0) as u32;
+    Some(parsed)
+}
+
+impl OpenRouterClient {
+    /// POST a chat completions request, turning error statuses into errors
+    /// and retrying transient ones
+    async fn send(&self, body: &serde_json::Value) -> Result<reqwest::Response> {
+        crate::retry::with_retry(&self.retry, "openrouter", || self.post(COMPLETIONS_URL, body)).await
+    }
+    
+    async fn post(&self, url: &str, body: &serde_json::Value) -> Result<reqwest::Response> {
         let res = self.client
-            .post("https://openrouter.ai/api/v1/chat/completions")
+            .post(url)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("HTTP-Referer", &self.site_url)
             .header("X-Title", &self.site_name)
-            .json(&body)
+            .json(body)
             .send()
             .await
-            .map_err(|e| SpawnError::ProviderError(format!("Request failed: {}", e)))?;
+            .map_err(crate::request_error)?;
 
         let status = res.status();
         if !status.is_success() {
-            let err_text = res.text().await.unwrap_or_default();
-            error!(status = %status, error = %err_text, "OpenRouter API error");
-            return Err(SpawnError::ProviderError(format!(
-                "API error {}: {}", status, err_text
-            )));
+            let err = crate::api_error(res).await;
+            error!(status = %status, code = err.code(), error = %err, "OpenRouter API error");
+            return Err(err);
         }
+        Ok(res)
+    }
+
+    async fn complete(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        debug!(model = model, message_count = messages.len(), ?options, "Sending chat request");
+        let res = self.send(&request_body(model, messages, options)?).await?;
 
         let json: serde_json::Value = res.json().await
             .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
-
-        json["choices"][0]["message"]["content"]
-            .as_str()
-            .map(|s| s.to_string())
-            .ok_or_else(|| SpawnError::ProviderError("No content in response".into()))
-    }
-    
-    fn provider_name(&self) -> &str {
-        "openrouter"
+        parse_completion(&json, model)
     }
 }
 
+/// A chat completions response, OpenAI style
+pub(crate) fn parse_completion(json: &serde_json::Value, model: &str) -> Result<ChatResponse> {
+    let content = json["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| SpawnError::ProviderError("No content in response".into()))?;
+    let usage = parse_usage(&json["usage"]).unwrap_or_default();
+    debug!(model = model, usage = ?usage, "Chat completion received");
+
+    Ok(ChatResponse {
+        content,
+        usage,
+        finish_reason: json["choices"][0]["finish_reason"].as_str().map(String::from),
+        model: json["model"].as_str().unwrap_or(model).to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,4 +539,99 @@ mod tests {
             .with_site_info("https://test.com", "Test");
         assert_eq!(client.provider_name(), "openrouter");
     }
+    
+    #[test]
+    fn test_parse_catalog_model() {
+        let model = OpenRouterModel::parse(&json!({
+            "id": "anthropic/claude-sonnet-4",
+            "name": "Anthropic: Claude Sonnet 4",
+            "context_length": 200000,
+            "architecture": { "input_modalities": ["image", "text"], "output_modalities": ["text"] },
+            "pricing": { "prompt": "0.000003", "completion": "0.000015", "input_cache_read": "0.0000003" },
+            "top_provider": { "context_length": 200000, "max_completion_tokens": 64000 },
+            "supported_parameters": ["max_tokens", "tools", "reasoning"],
+        })).unwrap();
+        assert_eq!(model.context_length, Some(200_000));
+        assert_eq!(model.max_completion_tokens, Some(64_000));
+        let pricing = model.pricing.unwrap();
+        assert!((pricing.prompt - 3.0).abs() < 1e-9 && (pricing.completion - 15.0).abs() < 1e-9);
+        assert!((pricing.cached_prompt.unwrap() - 0.3).abs() < 1e-9);
+        assert!(model.vision && model.tools && model.reasoning && !model.structured_outputs);
+
+        let router = OpenRouterModel::parse(&json!({ "id": "openrouter/auto", "pricing": { "prompt": "-1", "completion": "-1" } })).unwrap();
+        assert!(router.pricing.is_none() && router.context_length.is_none());
+    }
+
+    #[test]
+    fn test_decode_image_data_url() {
+        let image = GeneratedImage::from_data_url("data:image/png;base64,aGVsbG8=").unwrap();
+        assert_eq!(image.mime_type, "image/png");
+        assert_eq!(image.data, b"hello");
+        assert_eq!(image.extension(), "png");
+        assert!(GeneratedImage::from_data_url("https://example.com/a.png").is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_sse_deltas() {
+        let body = concat!(
+            ": OPENROUTER PROCESSING\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        // Split mid-event, as the network may
+        let (a, b) = body.split_at(40);
+        let chunks: Vec<reqwest::Result<&str>> = vec![Ok(a), Ok(b)];
+        let deltas: Vec<ChatDelta> = sse_deltas(futures::stream::iter(chunks))
+            .map(|d| d.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(deltas.iter().map(|d| d.content.as_str()).collect::<String>(), "Hello");
+        assert_eq!(deltas[1].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(deltas.last().unwrap().usage.as_ref().unwrap().total_tokens, 5);
+    }
+    
+    #[test]
+    fn test_embeddings_in_input_order() {
+        let json = json!({ "data": [
+            { "index": 1, "embedding": [0.5, 0.25] },
+            { "index": 0, "embedding": [1.0, 0.0] },
+        ]});
+        assert_eq!(parse_embeddings(&json, 2).unwrap(), vec![vec![1.0, 0.0], vec![0.5, 0.25]]);
+        assert!(parse_embeddings(&json, 3).is_err());
+    }
+    
+    #[test]
+    fn test_multimodal_wire_format() {
+        let message = ChatMessage::user(vec![
+            ContentPart::text("What is wrong here?"),
+            ContentPart::image_base64("image/png", "aGVsbG8="),
+        ]);
+        let wire = wire_messages(&[message, ChatMessage::system("plain").with_metadata("model", "m")], false).unwrap();
+        assert_eq!(wire[0]["content"][0], json!({ "type": "text", "text": "What is wrong here?" }));
+        assert_eq!(wire[0]["content"][1]["image_url"]["url"], "data:image/png;base64,aGVsbG8=");
+        assert_eq!(wire[1]["content"], "plain");
+        assert!(wire[1].get("metadata").is_none() && wire[1].get("created_at").is_none());
+    }
+    
+    #[test]
+    fn test_cache_control_on_breakpoints() {
+        let messages = [ChatMessage::system("You are an agent").with_cache_breakpoint(), ChatMessage::user("Step 3")];
+        let body = request_body("anthropic/claude-sonnet-4", &messages, &ChatOptions::new()).unwrap();
+        assert_eq!(body["messages"][0]["content"], json!([
+            { "type": "text", "text": "You are an agent", "cache_control": { "type": "ephemeral" } }
+        ]));
+        assert_eq!(body["messages"][1]["content"], "Step 3");
+        assert!(body["messages"][0].get("cache_breakpoint").is_none());
+        let body = request_body("openai/gpt-4o", &messages, &ChatOptions::new()).unwrap();
+        assert_eq!(body["messages"][0]["content"], "You are an agent");
+
+        let usage = parse_usage(&json!({
+            "prompt_tokens": 1200, "completion_tokens": 30, "total_tokens": 1230,
+            "prompt_tokens_details": { "cached_tokens": 1024 },
+        })).unwrap();
+        assert_eq!(usage.cached_tokens, 1024);
+    }
 }
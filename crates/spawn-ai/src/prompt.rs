@@ -0,0 +1,169 @@
+//! Prompt templates
+//!
+//! System prompts are MiniJinja templates (`{{ persona }}`,
+//! `{% for rule in must %}`), kept by name and rendered with the caller's
+//! variables plus globals shared by every prompt, such as the operator's
+//! must/must-not rules. Templates and globals can be replaced while the
+//! server runs; the next render uses them.
+
+use minijinja::{Environment, Value};
+use serde::Serialize;
+use spawn_core::{Result, SpawnError};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Template name of the mission system prompt
+pub const MISSION_PROMPT: &str = "mission";
+
+/// The mission system prompt unless one is configured. Besides the
+/// variables it uses, missions render with `workspace`, `locale` and `goal`.
+pub const DEFAULT_MISSION_PROMPT: &str = "\
+{{ persona }}
+
+{{ text.tools_heading }}
+{{ tools }}
+
+{{ text.tool_usage }}
+TOOL: <tool_name>
+ARGS: <json_arguments>
+
+{{ text.done_usage }}
+DONE: {{ text.done_placeholder }}
+{% if must %}
+
+{{ text.must_heading }}
+{% for rule in must %}
+- {{ rule }}
+{% endfor %}
+{% endif %}
+{% if must_not %}
+
+{{ text.must_not_heading }}
+{% for rule in must_not %}
+- {{ rule }}
+{% endfor %}
+{% endif %}
+
+{{ text.closing }}";
+
+/// Named templates and the globals they render with
+pub struct PromptTemplates {
+    templates: RwLock<BTreeMap<String, String>>,
+    globals: RwLock<BTreeMap<String, Value>>,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PromptTemplates {
+    /// The built-in mission prompt and no globals
+    pub fn new() -> Self {
+        let templates = BTreeMap::from([(MISSION_PROMPT.to_string(), DEFAULT_MISSION_PROMPT.to_string())]);
+        Self { templates: RwLock::new(templates), globals: RwLock::new(BTreeMap::new()) }
+    }
+
+    /// Templates from a JSON object of name to template, over the built-in
+    /// ones; a file that doesn't exist leaves just those
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let prompts = Self::new();
+        let path = path.as_ref();
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(prompts),
+            Err(e) => return Err(SpawnError::Config(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        let templates: BTreeMap<String, String> = serde_json::from_str(&json)
+            .map_err(|e| SpawnError::Config(format!("{}: {}", path.display(), e)))?;
+        for (name, template) in templates {
+            prompts.set(&name, template)
+                .map_err(|e| SpawnError::Config(format!("{}: {}", path.display(), e)))?;
+        }
+        Ok(prompts)
+    }
+
+    /// Add or replace the template `name`, once it compiles
+    pub fn set(&self, name: &str, template: impl Into<String>) -> Result<()> {
+        let template = template.into();
+        compile_check(name, &template)?;
+        self.templates.write().unwrap().insert(name.to_string(), template);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.templates.read().unwrap().get(name).cloned()
+    }
+
+    /// A variable every template sees, unless the render's own variables
+    /// have one by that name
+    pub fn set_global(&self, name: &str, value: impl Serialize) {
+        self.globals.write().unwrap().insert(name.to_string(), Value::from_serialize(value));
+    }
+
+    /// Render the template `name` with `vars`, a struct or map
+    pub fn render(&self, name: &str, vars: impl Serialize) -> Result<String> {
+        let template = self.get(name)
+            .ok_or_else(|| SpawnError::Internal(format!("No prompt template '{}'", name)))?;
+        self.render_str(name, &template, vars)
+    }
+
+    fn render_str(&self, name: &str, template: &str, vars: impl Serialize) -> Result<String> {
+        let mut env = environment();
+        for (global, value) in self.globals.read().unwrap().iter() {
+            env.add_global(global.clone(), value.clone());
+        }
+        env.add_template(name, template).map_err(|e| template_error(name, e))?;
+        env.get_template(name)
+            .and_then(|t| t.render(vars))
+            .map_err(|e| template_error(name, e))
+    }
+}
+
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    // Block tags take their line with them, so loops don't leave blank lines
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Chainable);
+    env
+}
+
+fn compile_check(name: &str, template: &str) -> Result<()> {
+    environment().template_from_named_str(name, template).map(|_| ()).map_err(|e| template_error(name, e))
+}
+
+fn template_error(name: &str, e: minijinja::Error) -> SpawnError {
+    SpawnError::Config(format!("Prompt template '{}': {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_renders_mission_prompt_with_rules() {
+        let prompts = PromptTemplates::new();
+        let vars = json!({
+            "persona": "You are an agent.",
+            "tools": "- shell: Run a command",
+            "text": { "tools_heading": "Available tools:", "closing": "Be concise.", "must_heading": "Always:", "must_not_heading": "Never:" },
+        });
+        let plain = prompts.render(MISSION_PROMPT, &vars).unwrap();
+        assert!(plain.starts_with("You are an agent.\n\nAvailable tools:\n- shell: Run a command\n"));
+        assert!(plain.ends_with("\n\nBe concise."));
+        assert!(!plain.contains("Always:"));
+
+        prompts.set_global("must", ["Use relative paths"]);
+        prompts.set_global("must_not", ["sudo commands"]);
+        let ruled = prompts.render(MISSION_PROMPT, &vars).unwrap();
+        assert!(ruled.contains("\n\nAlways:\n- Use relative paths\n\nNever:\n- sudo commands\n\nBe concise."), "{}", ruled);
+
+        assert!(prompts.set(MISSION_PROMPT, "{% for %}").is_err());
+        prompts.set(MISSION_PROMPT, "{{ persona }} in {{ workspace or 'no workspace' }}").unwrap();
+        assert_eq!(prompts.render(MISSION_PROMPT, &vars).unwrap(), "You are an agent. in no workspace");
+    }
+}
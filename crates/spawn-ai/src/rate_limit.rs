@@ -0,0 +1,94 @@
+//! Client-side rate limiting
+//!
+//! A token bucket for requests per minute and a semaphore for requests in
+//! flight, so a fast mission loop queues behind a provider's limits instead
+//! of being refused by them.
+
+use spawn_core::{CancellationToken, RateLimit, Result, SpawnError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+/// Refills continuously at `per_sec`, holding at most `capacity` tokens
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(requests_per_minute: u32, now: Instant) -> Self {
+        let capacity = f64::from(requests_per_minute.max(1));
+        Self { capacity, tokens: capacity, per_sec: capacity / 60.0, updated: now }
+    }
+
+    /// Take a token, or say how long until one is available
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.per_sec))
+        }
+    }
+}
+
+pub(crate) struct RateLimiter {
+    /// Locked while waiting for a token, so waiters are served in order
+    bucket: Option<Mutex<Bucket>>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            bucket: limit.requests_per_minute.map(|rpm| Mutex::new(Bucket::new(rpm, Instant::now()))),
+            in_flight: limit.max_concurrent.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+        }
+    }
+
+    /// Wait for a turn to send a request. The permit, if any, counts the
+    /// request as in flight until it is dropped.
+    pub(crate) async fn acquire(&self, cancel: &CancellationToken) -> Result<Option<OwnedSemaphorePermit>> {
+        if let Some(bucket) = &self.bucket {
+            let mut bucket = cancel.run_until_cancelled(bucket.lock()).await.ok_or(SpawnError::Cancelled)?;
+            while let Some(wait) = bucket.take(Instant::now()) {
+                cancel.run_until_cancelled(tokio::time::sleep(wait)).await.ok_or(SpawnError::Cancelled)?;
+            }
+        }
+        match &self.in_flight {
+            Some(in_flight) => {
+                let permit = cancel.run_until_cancelled(in_flight.clone().acquire_owned()).await
+                    .ok_or(SpawnError::Cancelled)?
+                    .expect("the semaphore is never closed");
+                Ok(Some(permit))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2, start);
+
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), Some(Duration::from_secs(30)));
+        assert_eq!(bucket.take(start + Duration::from_secs(30)), None);
+        // Refills never exceed the burst size
+        let later = start + Duration::from_secs(600);
+        assert_eq!(bucket.take(later), None);
+        assert_eq!(bucket.take(later), None);
+        assert!(bucket.take(later).is_some());
+    }
+}
@@ -0,0 +1,253 @@
+//! Recorded LLM exchanges
+//!
+//! `RecordingClient` passes requests through to a real provider and writes
+//! each request with its response to a JSON fixture file; `ReplayClient`
+//! answers from such a file without any provider, so tests of missions and
+//! endpoints run the same way every time and need no API key. Requests are
+//! matched on everything that shapes the answer (model, messages, options),
+//! as the response cache matches them.
+
+use crate::cache::{cache_key, request_json};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use spawn_core::{
+    CancellationToken, ChatDelta, ChatMessage, ChatOptions, ChatResponse, ChatStream, LlmClient, Result, SpawnError, TokenUsage,
+};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// One recorded request and the response it got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Hash of `request`, what replays are matched on
+    pub key: String,
+    /// Model, messages and options, for whoever reads the fixture
+    pub request: serde_json::Value,
+    pub response: ChatResponse,
+}
+
+/// Records every successful exchange with `inner` to a fixture file,
+/// rewritten after each one; failed requests aren't recorded
+pub struct RecordingClient {
+    inner: Arc<dyn LlmClient>,
+    path: PathBuf,
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+}
+
+impl RecordingClient {
+    /// Start a new recording at `path`, replacing any file there once the
+    /// first exchange is recorded
+    pub fn new(inner: Arc<dyn LlmClient>, path: impl Into<PathBuf>) -> Self {
+        Self { inner, path: path.into(), exchanges: Arc::new(Mutex::new(Vec::new())) }
+    }
+
+    /// What has been recorded so far
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+}
+
+fn record(exchanges: &Mutex<Vec<Exchange>>, path: &Path, exchange: Exchange) -> Result<()> {
+    let mut exchanges = exchanges.lock().unwrap();
+    exchanges.push(exchange);
+    let json = serde_json::to_string_pretty(&*exchanges)?;
+    std::fs::write(path, json)
+        .map_err(|e| SpawnError::Internal(format!("Failed to write fixture {}: {}", path.display(), e)))
+}
+
+#[async_trait]
+impl LlmClient for RecordingClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        let response = self.inner.chat_with_usage(model, messages, options, cancel).await?;
+        let exchange = Exchange {
+            key: cache_key(model, messages, options),
+            request: request_json(model, messages, options),
+            response: response.clone(),
+        };
+        record(&self.exchanges, &self.path, exchange)?;
+        Ok(response)
+    }
+
+    /// Streams as usual; the exchange is recorded once the stream has run
+    /// to the end without error, and a failed write ends it with the error
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let stream = self.inner.chat_stream(model, messages, options, cancel).await?;
+        let collected = Arc::new(Mutex::new(Some(Exchange {
+            key: cache_key(model, messages, options),
+            request: request_json(model, messages, options),
+            response: ChatResponse { content: String::new(), usage: TokenUsage::default(), finish_reason: None, model: model.to_string() },
+        })));
+        let finish = {
+            let (collected, exchanges, path) = (collected.clone(), self.exchanges.clone(), self.path.clone());
+            async move {
+                let exchange = collected.lock().unwrap().take();
+                let exchange = exchange.filter(|e| e.response.finish_reason.is_some())?;
+                record(&exchanges, &path, exchange).err().map(Err)
+            }
+        };
+        Ok(stream
+            .inspect(move |delta| {
+                let mut collected = collected.lock().unwrap();
+                match (delta, collected.as_mut()) {
+                    (Ok(delta), Some(exchange)) => {
+                        let response = &mut exchange.response;
+                        response.content.push_str(&delta.content);
+                        response.finish_reason = delta.finish_reason.clone().or(response.finish_reason.take());
+                        if let Some(usage) = delta.usage {
+                            response.usage = usage;
+                        }
+                    }
+                    (Err(_), _) => *collected = None,
+                    (Ok(_), None) => {}
+                }
+            })
+            .chain(futures::stream::once(finish).filter_map(futures::future::ready))
+            .boxed())
+    }
+
+    async fn health_check(&self, model: &str, cancel: &CancellationToken) -> Result<()> {
+        self.inner.health_check(model, cancel).await
+    }
+
+    fn supports_json_schema(&self, model: &str) -> bool {
+        self.inner.supports_json_schema(model)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+/// Answers from recorded exchanges. A request recorded more than once gets
+/// its responses in recorded order, the last one repeating; one never
+/// recorded is an error naming its key.
+pub struct ReplayClient {
+    responses: Mutex<HashMap<String, VecDeque<ChatResponse>>>,
+}
+
+impl ReplayClient {
+    pub fn new(exchanges: impl IntoIterator<Item = Exchange>) -> Self {
+        let mut responses: HashMap<String, VecDeque<ChatResponse>> = HashMap::new();
+        for exchange in exchanges {
+            responses.entry(exchange.key).or_default().push_back(exchange.response);
+        }
+        Self { responses: Mutex::new(responses) }
+    }
+
+    /// A fixture file written by `RecordingClient`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| SpawnError::Internal(format!("Failed to read fixture {}: {}", path.display(), e)))?;
+        let exchanges: Vec<Exchange> = serde_json::from_str(&json)?;
+        Ok(Self::new(exchanges))
+    }
+
+    fn response(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions) -> Result<ChatResponse> {
+        let key = cache_key(model, messages, options);
+        let mut responses = self.responses.lock().unwrap();
+        let recorded = responses.get_mut(&key)
+            .ok_or_else(|| SpawnError::ProviderError(format!("No recorded response for this {} request (key {})", model, key)))?;
+        match recorded.len() {
+            1 => Ok(recorded[0].clone()),
+            _ => Ok(recorded.pop_front().expect("recorded responses are never empty")),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for ReplayClient {
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        _cancel: &CancellationToken,
+    ) -> Result<ChatResponse> {
+        self.response(model, messages, options)
+    }
+
+    /// The recorded response as one delta
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        _cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let response = self.response(model, messages, options)?;
+        let delta = ChatDelta { content: response.content, finish_reason: response.finish_reason, usage: Some(response.usage) };
+        Ok(futures::stream::once(async move { Ok(delta) }).boxed())
+    }
+
+    async fn health_check(&self, _model: &str, _cancel: &CancellationToken) -> Result<()> {
+        Ok(())
+    }
+
+    fn provider_name(&self) -> &str {
+        "replay"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct CountingClient(AtomicU32);
+
+    #[async_trait]
+    impl LlmClient for CountingClient {
+        async fn chat_with_usage(&self, model: &str, _: &[ChatMessage], _: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            let n = self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                content: format!("answer {}", n),
+                usage: TokenUsage { prompt_tokens: 10, completion_tokens: 2, total_tokens: 12, cached_tokens: 0 },
+                finish_reason: Some("stop".into()),
+                model: model.to_string(),
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_recorded_exchanges() {
+        let path = std::env::temp_dir().join(format!("spawn-fixture-{}.json", std::process::id()));
+        let cancel = CancellationToken::new();
+        let options = ChatOptions::new();
+        let question = [ChatMessage::user("What is 2 + 2?")];
+        let other = [ChatMessage::user("And 3 + 3?")];
+
+        let recorder = RecordingClient::new(Arc::new(CountingClient(AtomicU32::new(0))), &path);
+        recorder.chat_with_usage("gpt-4o", &question, &options, &cancel).await.unwrap();
+        recorder.chat_with_usage("gpt-4o", &question, &options, &cancel).await.unwrap();
+        let streamed: Vec<_> = recorder.chat_stream("gpt-4o", &other, &options, &cancel).await.unwrap().collect().await;
+        assert_eq!(streamed.len(), 1);
+        assert_eq!(recorder.exchanges().len(), 3);
+
+        let replay = ReplayClient::load(&path).unwrap();
+        for expected in ["answer 0", "answer 1", "answer 1"] {
+            assert_eq!(replay.chat("gpt-4o", &question, &options, &cancel).await.unwrap(), expected);
+        }
+        assert_eq!(replay.chat_with_usage("gpt-4o", &other, &options, &cancel).await.unwrap().usage.total_tokens, 12);
+        assert!(replay.chat("gpt-4o-mini", &other, &options, &cancel).await.is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,105 @@
+//! Retrying failed provider requests
+//!
+//! Each try runs in a `provider_request` span carrying the attempt number.
+//! Retryable errors (see `SpawnError::is_retryable`) wait out the provider's
+//! `Retry-After` when it sent one (capped at `max_backoff`), and otherwise an exponential backoff with
+//! jitter, so clients throttled together don't come back together.
+
+use spawn_core::{Result, RetryPolicy};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::{warn, Instrument};
+
+/// Run `request` until it succeeds, fails for good, or `policy` runs out of
+/// attempts; the last error is returned
+pub(crate) async fn with_retry<T, F, Fut>(policy: &RetryPolicy, provider: &str, mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let span = tracing::debug_span!("provider_request", provider, attempt);
+        let err = match request().instrument(span).await {
+            Ok(value) => return Ok(value),
+            Err(e) if e.is_retryable() && attempt < policy.max_attempts => e,
+            Err(e) => return Err(e),
+        };
+        let wait = match err.retry_after() {
+            Some(after) => after.min(policy.max_backoff),
+            None => backoff(policy, attempt, jitter()),
+        };
+        warn!(provider, attempt, max_attempts = policy.max_attempts, wait_ms = wait.as_millis() as u64, error = %err, "Provider request failed; retrying");
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// Wait after failed try `attempt`: the initial backoff doubled per earlier
+/// try, capped, then scaled to between half and all of that by `jitter`
+/// (0.0 to 1.0)
+fn backoff(policy: &RetryPolicy, attempt: u32, jitter: f64) -> Duration {
+    let exponential = policy.initial_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    exponential.min(policy.max_backoff).mul_f64(0.5 + jitter / 2.0)
+}
+
+/// A random fraction in 0.0..1.0
+fn jitter() -> f64 {
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spawn_core::SpawnError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy { max_attempts: 5, initial_backoff: Duration::from_millis(100), max_backoff: Duration::from_millis(300) };
+        assert_eq!(backoff(&policy, 1, 1.0), Duration::from_millis(100));
+        assert_eq!(backoff(&policy, 2, 1.0), Duration::from_millis(200));
+        assert_eq!(backoff(&policy, 3, 1.0), Duration::from_millis(300));
+        assert_eq!(backoff(&policy, 2, 0.0), Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_transient_errors_only() {
+        let policy = RetryPolicy::default();
+        let calls = AtomicU32::new(0);
+        let result = with_retry(&policy, "test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(SpawnError::RateLimited { message: "slow down".into(), retry_after: Some(Duration::from_secs(2)) }),
+                1 => Err(SpawnError::ProviderUnavailable("502".into())),
+                _ => Ok("done"),
+            }
+        }).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        calls.store(0, Ordering::SeqCst);
+        let result: Result<()> = with_retry(&policy, "test", || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(SpawnError::AuthFailed("bad key".into()))
+        }).await;
+        assert!(matches!(result, Err(SpawnError::AuthFailed(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_caps_server_retry_after() {
+        let policy = RetryPolicy { max_attempts: 2, initial_backoff: Duration::from_millis(100), max_backoff: Duration::from_secs(5) };
+        let calls = AtomicU32::new(0);
+        let started = tokio::time::Instant::now();
+        let result = with_retry(&policy, "test", || async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(SpawnError::RateLimited { message: "slow down".into(), retry_after: Some(Duration::from_secs(3600)) }),
+                _ => Ok("done"),
+            }
+        }).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,51 @@
+//! Model routing
+//!
+//! Declarative rules from `[[models.routes]]` pick the model for a call by
+//! what it is for (planning, summarization, embeddings, ...) and, optionally,
+//! how much the mission has spent so far, so expensive models can be kept
+//! for the work that needs them. Rules are tried in order and the first
+//! match wins; with none matching the caller's default model is used.
+
+use spawn_core::{RoutingRule, TaskKind};
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelRouter {
+    rules: Vec<RoutingRule>,
+}
+
+impl ModelRouter {
+    pub fn new(rules: Vec<RoutingRule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn rules(&self) -> &[RoutingRule] {
+        &self.rules
+    }
+
+    /// The model for a `task` call by a mission that has spent `spent_usd`,
+    /// if a rule picks one
+    pub fn route(&self, task: TaskKind, spent_usd: f64) -> Option<&str> {
+        self.rules.iter()
+            .find(|rule| rule.matches(task, spent_usd))
+            .map(|rule| rule.model.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rule = |task, model: &str, max_spent_usd| RoutingRule { task, model: model.into(), max_spent_usd };
+        let router = ModelRouter::new(vec![
+            rule(TaskKind::Execution, "anthropic/claude-sonnet-4", Some(1.0)),
+            rule(TaskKind::Execution, "openai/gpt-4o-mini", None),
+            rule(TaskKind::Summarization, "openai/gpt-4o-mini", None),
+        ]);
+        assert_eq!(router.route(TaskKind::Execution, 0.5), Some("anthropic/claude-sonnet-4"));
+        assert_eq!(router.route(TaskKind::Execution, 1.5), Some("openai/gpt-4o-mini"));
+        assert_eq!(router.route(TaskKind::Summarization, 9.0), Some("openai/gpt-4o-mini"));
+        assert_eq!(router.route(TaskKind::Planning, 0.0), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! Speech adapters for OpenAI-compatible audio APIs
+//! (OpenAI, Groq, local whisper.cpp / faster-whisper servers)
+
+use async_trait::async_trait;
+use reqwest::{multipart, Client};
+use spawn_core::{Result, SpawnError, SpeechAudio, SpeechConfig, SpeechToText, TextToSpeech};
+use tracing::{debug, error};
+
+pub struct WhisperClient {
+    api_url: String,
+    api_key: String,
+    model: String,
+    client: Client,
+}
+
+impl WhisperClient {
+    pub fn new(config: &SpeechConfig) -> Self {
+        Self {
+            api_url: config.api_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            client: Client::new(),
+        }
+    }
+}
+
+/// File name with an extension matching the MIME type; providers sniff the
+/// format from it
+fn audio_filename(mime_type: &str) -> &'static str {
+    match mime_type.split(';').next().unwrap_or_default() {
+        "audio/mpeg" | "audio/mp3" => "audio.mp3",
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "audio.wav",
+        "audio/ogg" => "audio.ogg",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "audio.m4a",
+        "audio/flac" => "audio.flac",
+        _ => "audio.webm",
+    }
+}
+
+#[async_trait]
+impl SpeechToText for WhisperClient {
+    async fn transcribe(&self, audio: Vec<u8>, mime_type: &str, language: Option<&str>) -> Result<String> {
+        debug!(model = %self.model, bytes = audio.len(), "Sending transcription request");
+
+        let file = multipart::Part::bytes(audio)
+            .file_name(audio_filename(mime_type))
+            .mime_str(mime_type)
+            .map_err(|e| SpawnError::ProviderError(format!("Invalid audio type: {}", e)))?;
+        let mut form = multipart::Form::new()
+            .text("model", self.model.clone())
+            .part("file", file);
+        if let Some(language) = language {
+            form = form.text("language", language.to_string());
+        }
+
+        let res = self.client
+            .post(format!("{}/audio/transcriptions", self.api_url))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, code = err.code(), error = %err, "Transcription API error");
+            return Err(err);
+        }
+
+        let json: serde_json::Value = res.json().await
+            .map_err(|e| SpawnError::ProviderError(format!("Parse error: {}", e)))?;
+
+        json["text"]
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .ok_or_else(|| SpawnError::ProviderError("No text in transcription".into()))
+    }
+
+    fn provider_name(&self) -> &str {
+        "whisper"
+    }
+}
+
+pub struct OpenAiSpeechClient {
+    api_url: String,
+    api_key: String,
+    model: String,
+    voice: String,
+    client: Client,
+}
+
+impl OpenAiSpeechClient {
+    pub fn new(config: &SpeechConfig) -> Self {
+        Self {
+            api_url: config.api_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            voice: config.voice.clone().unwrap_or_else(|| "alloy".to_string()),
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for OpenAiSpeechClient {
+    async fn synthesize(&self, text: &str) -> Result<SpeechAudio> {
+        debug!(model = %self.model, voice = %self.voice, chars = text.len(), "Sending speech request");
+
+        let res = self.client
+            .post(format!("{}/audio/speech", self.api_url))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "model": self.model,
+                "voice": self.voice,
+                "input": text,
+                "response_format": "mp3",
+            }))
+            .send()
+            .await
+            .map_err(crate::request_error)?;
+
+        let status = res.status();
+        if !status.is_success() {
+            let err = crate::api_error(res).await;
+            error!(status = %status, code = err.code(), error = %err, "Speech API error");
+            return Err(err);
+        }
+
+        let data = res.bytes().await
+            .map_err(|e| SpawnError::ProviderError(format!("Read error: {}", e)))?;
+
+        Ok(SpeechAudio {
+            mime_type: "audio/mpeg".to_string(),
+            data: data.to_vec(),
+        })
+    }
+
+    fn provider_name(&self) -> &str {
+        "openai-tts"
+    }
+}
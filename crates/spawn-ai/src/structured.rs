@@ -0,0 +1,202 @@
+//! Structured output
+//!
+//! `chat_structured` asks for a reply that deserializes into a Rust type.
+//! The type's JSON schema goes into `response_format` for models that can be
+//! held to one, and into the prompt for the rest. Either way the reply is
+//! validated against the schema; one that fails is sent back with what was
+//! wrong with it, and the model gets another try.
+
+use async_trait::async_trait;
+use jsonschema::JSONSchema;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use spawn_core::{CancellationToken, ChatMessage, ChatOptions, JsonSchemaFormat, LlmClient, ResponseFormat, Result, SpawnError};
+use tracing::warn;
+
+/// Corrections asked for after the first reply
+pub const MAX_REPAIR_ATTEMPTS: usize = 2;
+/// Validation errors quoted back to the model
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Chat for typed replies; implemented for every `LlmClient`
+#[async_trait]
+pub trait StructuredChat {
+    /// A reply validated against `T`'s JSON schema and deserialized into it
+    async fn chat_structured<T: DeserializeOwned + JsonSchema + Send>(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<T>;
+}
+
+#[async_trait]
+impl<C: LlmClient + ?Sized> StructuredChat for C {
+    async fn chat_structured<T: DeserializeOwned + JsonSchema + Send>(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<T> {
+        let schema = serde_json::to_value(schemars::schema_for!(T))?;
+        let validator = JSONSchema::compile(&schema)
+            .map_err(|e| SpawnError::Internal(format!("Invalid schema for {}: {}", T::schema_name(), e)))?;
+
+        let mut messages = messages.to_vec();
+        let mut options = options.clone();
+        // Providers only take an object at the top of a response schema
+        if self.supports_json_schema(model) && schema["type"] == "object" {
+            options.response_format = Some(ResponseFormat::JsonSchema {
+                json_schema: JsonSchemaFormat { name: schema_name::<T>(), schema: provider_schema(&schema), strict: false },
+            });
+        } else {
+            messages.push(ChatMessage::system(format!(
+                "Reply with only a JSON value matching this JSON schema, without code fences or commentary:\n{}",
+                schema
+            )));
+        }
+
+        let mut attempt = 0;
+        loop {
+            let reply = self.chat(model, &messages, &options, cancel).await?;
+            let problems = match parse_structured(&reply, &validator) {
+                Ok(value) => return Ok(value),
+                Err(problems) => problems.join("\n- "),
+            };
+            if attempt == MAX_REPAIR_ATTEMPTS {
+                return Err(SpawnError::ProviderError(format!(
+                    "{} gave no valid {} in {} attempts:\n- {}", model, T::schema_name(), attempt + 1, problems
+                )));
+            }
+            attempt += 1;
+            warn!(model, attempt, errors = %problems, "Structured reply failed validation, asking for a repair");
+            messages.push(ChatMessage::assistant(reply));
+            messages.push(ChatMessage::user(format!(
+                "That reply doesn't match the schema:\n- {}\n\nReply again with only the corrected JSON.",
+                problems
+            )));
+        }
+    }
+}
+
+/// `T`'s schema name in the characters providers accept
+fn schema_name<T: JsonSchema>() -> String {
+    let name: String = T::schema_name().chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .take(64)
+        .collect();
+    if name.is_empty() { "response".to_string() } else { name }
+}
+
+/// The schema without the `$schema` draft marker, which providers reject
+fn provider_schema(schema: &serde_json::Value) -> serde_json::Value {
+    let mut schema = schema.clone();
+    if let Some(fields) = schema.as_object_mut() {
+        fields.remove("$schema");
+    }
+    schema
+}
+
+/// The JSON in a reply, tolerating code fences and text around it
+fn extract_json(reply: &str) -> &str {
+    let trimmed = reply.trim();
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return trimmed;
+    }
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    }
+}
+
+/// `reply` as a `T`, or what is wrong with it
+fn parse_structured<T: DeserializeOwned>(reply: &str, validator: &JSONSchema) -> std::result::Result<T, Vec<String>> {
+    let value: serde_json::Value = serde_json::from_str(extract_json(reply))
+        .map_err(|e| vec![format!("not valid JSON: {}", e)])?;
+    if let Err(errors) = validator.validate(&value) {
+        return Err(errors
+            .take(MAX_REPORTED_ERRORS)
+            .map(|e| match e.instance_path.to_string() {
+                path if path.is_empty() => e.to_string(),
+                path => format!("{}: {}", path, e),
+            })
+            .collect());
+    }
+    serde_json::from_value(value).map_err(|e| vec![e.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use spawn_core::{ChatResponse, TokenUsage};
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+    struct Step {
+        title: String,
+        minutes: u32,
+    }
+
+    /// Replies in turn, recording what each call was sent
+    struct ScriptedClient {
+        replies: Mutex<Vec<&'static str>>,
+        calls: Mutex<Vec<(Vec<ChatMessage>, ChatOptions)>>,
+    }
+
+    #[async_trait]
+    impl LlmClient for ScriptedClient {
+        async fn chat_with_usage(&self, model: &str, messages: &[ChatMessage], options: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            self.calls.lock().unwrap().push((messages.to_vec(), options.clone()));
+            Ok(ChatResponse {
+                content: self.replies.lock().unwrap().remove(0).to_string(),
+                usage: TokenUsage::default(),
+                finish_reason: Some("stop".into()),
+                model: model.to_string(),
+            })
+        }
+
+        fn supports_json_schema(&self, model: &str) -> bool {
+            model == "schema"
+        }
+
+        fn provider_name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repairs_invalid_reply() {
+        let client = ScriptedClient {
+            replies: Mutex::new(vec![r#"{"title": "Write tests", "minutes": "ten"}"#, "```json\n{\"title\": \"Write tests\", \"minutes\": 10}\n```"]),
+            calls: Mutex::new(Vec::new()),
+        };
+        let messages = [ChatMessage::user("Plan the next step")];
+        let step: Step = client.chat_structured("schema", &messages, &ChatOptions::new(), &CancellationToken::new()).await.unwrap();
+        assert_eq!(step, Step { title: "Write tests".into(), minutes: 10 });
+
+        let calls = client.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(calls[0].1.response_format, Some(ResponseFormat::JsonSchema { .. })));
+        let repair = calls[1].0.last().unwrap().content.text();
+        assert!(repair.contains("/minutes"), "{}", repair);
+    }
+
+    #[tokio::test]
+    async fn test_schema_in_prompt_without_support() {
+        let client = ScriptedClient {
+            replies: Mutex::new(vec!["nope", "still nope", "no", "unused"]),
+            calls: Mutex::new(Vec::new()),
+        };
+        let result = client.chat_structured::<Step>("plain", &[ChatMessage::user("Plan")], &ChatOptions::new(), &CancellationToken::new()).await;
+        assert!(result.is_err());
+        let calls = client.calls.lock().unwrap();
+        assert_eq!(calls.len(), MAX_REPAIR_ATTEMPTS + 1);
+        assert!(calls[0].1.response_format.is_none());
+        assert!(calls[0].0[1].content.text().contains("\"minutes\""));
+    }
+}
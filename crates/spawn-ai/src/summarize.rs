@@ -0,0 +1,47 @@
+//! Conversation summaries by an LLM, for `Conversation::summarize`
+
+use async_trait::async_trait;
+use spawn_core::{CancellationToken, ChatMessage, ChatOptions, LlmClient, Result, Summarizer};
+use std::sync::Arc;
+
+/// Message text beyond this is cut before summarizing
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+const SUMMARY_PROMPT: &str = "Summarize the conversation below for whoever continues it. \
+Keep decisions, facts learned, files touched and open questions; drop pleasantries. \
+Reply with the summary only, as short bullet points.";
+
+pub struct LlmSummarizer {
+    llm: Arc<dyn LlmClient>,
+    model: String,
+}
+
+impl LlmSummarizer {
+    pub fn new(llm: Arc<dyn LlmClient>, model: impl Into<String>) -> Self {
+        Self { llm, model: model.into() }
+    }
+}
+
+#[async_trait]
+impl Summarizer for LlmSummarizer {
+    async fn summarize(&self, messages: &[ChatMessage]) -> Result<String> {
+        let transcript: Vec<String> = messages.iter()
+            .map(|m| {
+                let mut text = m.content.text();
+                if text.len() > MAX_MESSAGE_CHARS {
+                    let mut end = MAX_MESSAGE_CHARS;
+                    while !text.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    text.truncate(end);
+                    text.push_str(" …");
+                }
+                format!("[{}] {}", m.role.as_str(), text)
+            })
+            .collect();
+        let prompt = [ChatMessage::system(SUMMARY_PROMPT), ChatMessage::user(transcript.join("\n\n"))];
+        let options = ChatOptions::new().with_temperature(0.0);
+        let summary = self.llm.chat(&self.model, &prompt, &options, &CancellationToken::new()).await?;
+        Ok(summary.trim().to_string())
+    }
+}
@@ -0,0 +1,82 @@
+//! Token counting
+//!
+//! Counts prompt tokens with OpenAI's BPE vocabularies (o200k for the GPT-4o
+//! generation and later, cl100k otherwise), so a request can be trimmed or
+//! refused before it is paid for. Other model families tokenize differently;
+//! for them cl100k gives a close estimate rather than an exact count.
+
+use spawn_core::{ChatMessage, ContentPart, MessageContent};
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+/// Role and framing around each message
+const TOKENS_PER_MESSAGE: usize = 4;
+/// The reply is primed with the assistant role
+const TOKENS_PER_REPLY: usize = 3;
+/// An image at low detail; higher detail costs more, but the size isn't known here
+const TOKENS_PER_IMAGE: usize = 85;
+
+fn cl100k() -> &'static CoreBPE {
+    static BPE: OnceLock<CoreBPE> = OnceLock::new();
+    BPE.get_or_init(|| tiktoken_rs::cl100k_base().expect("cl100k vocabulary is built in"))
+}
+
+fn o200k() -> &'static CoreBPE {
+    static BPE: OnceLock<CoreBPE> = OnceLock::new();
+    BPE.get_or_init(|| tiktoken_rs::o200k_base().expect("o200k vocabulary is built in"))
+}
+
+/// The vocabulary `model` uses, or the closest one we have
+fn encoding(model: &str) -> &'static CoreBPE {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let o200k_family = ["gpt-4o", "gpt-4.1", "gpt-4.5", "gpt-5", "o1", "o3", "o4", "chatgpt-4o"];
+    if o200k_family.iter().any(|prefix| name.starts_with(prefix)) {
+        o200k()
+    } else {
+        cl100k()
+    }
+}
+
+/// Tokens in `text` for `model`
+pub fn count_tokens(text: &str, model: &str) -> usize {
+    encoding(model).encode_ordinary(text).len()
+}
+
+/// Tokens one message takes up in a prompt for `model`
+pub fn message_tokens(message: &ChatMessage, model: &str) -> usize {
+    let bpe = encoding(model);
+    let content = match &message.content {
+        MessageContent::Text(text) => bpe.encode_ordinary(text).len(),
+        MessageContent::Parts(parts) => parts.iter()
+            .map(|part| match part {
+                ContentPart::Text { text } => bpe.encode_ordinary(text).len(),
+                _ => TOKENS_PER_IMAGE,
+            })
+            .sum(),
+    };
+    let name = message.name.as_deref().map_or(0, |name| bpe.encode_ordinary(name).len());
+    let tool_calls = message.tool_calls.as_ref()
+        .map_or(0, |calls| bpe.encode_ordinary(&serde_json::to_string(calls).unwrap_or_default()).len());
+    TOKENS_PER_MESSAGE + content + name + tool_calls
+}
+
+/// Prompt tokens `messages` will cost with `model`, for checking a request
+/// against the context window before sending it
+pub fn estimate_tokens(messages: &[ChatMessage], model: &str) -> usize {
+    messages.iter().map(|m| message_tokens(m, model)).sum::<usize>() + TOKENS_PER_REPLY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_with_model_vocabulary() {
+        assert_eq!(count_tokens("hello world", "openai/gpt-4o-mini"), 2);
+        assert_eq!(count_tokens("hello world", "anthropic/claude-sonnet-4"), 2);
+        let messages = [ChatMessage::system("You are terse."), ChatMessage::user("hello world")];
+        let expected = message_tokens(&messages[0], "gpt-4o") + message_tokens(&messages[1], "gpt-4o") + TOKENS_PER_REPLY;
+        assert_eq!(estimate_tokens(&messages, "gpt-4o"), expected);
+        assert_eq!(message_tokens(&messages[1], "gpt-4o"), TOKENS_PER_MESSAGE + 2);
+    }
+}
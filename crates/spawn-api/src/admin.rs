@@ -1,96 +1,228 @@
 //! Admin API endpoints for the control panel
 //!
-//! Provides system status, prompt management, and configuration APIs.
+//! Provides system status, prompt management, A/B experiments, and
+//! configuration APIs.
 
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use spawn_agents::experiments;
+use spawn_core::{ExperimentVariant, HealthState, HealthStatus, ProviderHealth};
 use std::fs;
-use std::path::Path;
 
-use crate::AppState;
+use crate::{error, AppState};
 
 // ============================================
 // Status Endpoint
 // ============================================
 
-#[derive(Debug, Serialize)]
-pub struct ServiceStatus {
-    pub name: String,
-    pub status: String,
-    pub latency_ms: Option<u64>,
-    pub details: Option<serde_json::Value>,
-}
-
 #[derive(Debug, Serialize)]
 pub struct SystemStatus {
-    pub rust_api: ServiceStatus,
-    pub sandbox: ServiceStatus,
-    pub openrouter: ServiceStatus,
-    pub database: ServiceStatus,
+    pub rust_api: HealthStatus,
+    pub sandbox: HealthStatus,
+    pub openrouter: HealthStatus,
+    pub database: HealthStatus,
+    /// Every subsystem probed, including the ones above
+    pub checks: Vec<HealthStatus>,
+    /// Latest health probe of each LLM provider, in order of preference
+    pub providers: Vec<ProviderHealth>,
 }
 
 pub async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
-    // Check sandbox health
-    let sandbox_status = check_sandbox_health().await;
-
-    // Database is healthy if we got here (connection works)
-    let db_status = ServiceStatus {
-        name: "database".to_string(),
-        status: "online".to_string(),
-        latency_ms: None,
-        details: None,
-    };
+    let checks = state.health.check_all().await;
+    let find = |name: &str| checks.iter().find(|c| c.name == name).cloned()
+        .unwrap_or_else(|| HealthStatus::offline(name, "not configured"));
+
+    let mut database = find("database");
+    if let Some(details) = database.details.get_or_insert_with(|| serde_json::json!({})).as_object_mut() {
+        details.insert("mission_logs".into(), state.logs.backend_name().into());
+    }
 
     let status = SystemStatus {
-        rust_api: ServiceStatus {
+        rust_api: HealthStatus {
             name: "rust_api".to_string(),
-            status: "online".to_string(),
+            status: HealthState::Online,
             latency_ms: Some(0),
             details: Some(serde_json::json!({
                 "version": "0.1.0",
                 "workspace": state.workspace_root.display().to_string(),
             })),
         },
-        sandbox: sandbox_status,
-        openrouter: ServiceStatus {
-            name: "openrouter".to_string(),
-            status: if std::env::var("OPENROUTER_API_KEY").is_ok() { "configured" } else { "not_configured" }.to_string(),
-            latency_ms: None,
-            details: None,
-        },
-        database: db_status,
+        sandbox: find("sandbox"),
+        openrouter: find("openrouter"),
+        database,
+        providers: state.providers.health(),
+        checks,
     };
 
     (StatusCode::OK, Json(status))
 }
 
-async fn check_sandbox_health() -> ServiceStatus {
-    let sandbox_url = std::env::var("SANDBOX_ENDPOINT")
-        .unwrap_or_else(|_| "http://localhost:3080".to_string());
-
-    let start = std::time::Instant::now();
-    match reqwest::get(format!("{}/health", sandbox_url)).await {
-        Ok(res) if res.status().is_success() => {
-            let latency = start.elapsed().as_millis() as u64;
-            let details = res.json::<serde_json::Value>().await.ok();
-            ServiceStatus {
-                name: "sandbox".to_string(),
-                status: "online".to_string(),
-                latency_ms: Some(latency),
-                details,
-            }
+// ============================================
+// Stats Endpoint
+// ============================================
+
+/// Mission counts by status and failed missions by post-mortem category
+pub async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
+    let statuses = match state.db.mission_status_counts().await {
+        Ok(counts) => counts,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    };
+    let failures = match state.db.failure_category_counts().await {
+        Ok(counts) => counts,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    };
+
+    let missions: Vec<serde_json::Value> = statuses.into_iter()
+        .map(|(status, count)| serde_json::json!({ "status": status, "count": count }))
+        .collect();
+    let failures: Vec<serde_json::Value> = failures.into_iter()
+        .map(|(category, count)| serde_json::json!({ "category": category, "count": count }))
+        .collect();
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "missions": missions,
+        "failures": failures,
+    }))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    #[serde(default = "default_audit_limit")]
+    pub limit: i64,
+}
+
+fn default_audit_limit() -> i64 {
+    100
+}
+
+/// Recent audit log entries (data exports, erasure receipts)
+pub async fn get_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    match state.db.list_audit(query.action.as_deref(), query.limit).await {
+        Ok(entries) => (StatusCode::OK, Json(serde_json::json!({ "entries": entries }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+/// `GET /api/admin/providers` - latest health probe of each LLM provider
+pub async fn get_providers(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({ "providers": state.providers.health() }))
+}
+
+/// `GET /api/admin/costs` - spend since startup: overall, per model and per
+/// scope (mission id, or `chat`)
+pub async fn get_costs(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.costs.summary())
+}
+
+/// `GET /api/admin/usage` - calls, errors, tokens and latency per provider
+/// and model since startup
+pub async fn get_usage(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.providers.metrics())
+}
+
+/// `GET /metrics` - the same in the Prometheus text format, when enabled
+pub async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.providers.metrics().to_prometheus())
+}
+
+/// `GET /api/admin/locks` - workspace locks currently held, across all nodes
+pub async fn get_locks(State(state): State<AppState>) -> impl IntoResponse {
+    match state.locks.list().await {
+        Ok(locks) => (StatusCode::OK, Json(serde_json::json!({ "locks": locks }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+// ============================================
+// Experiments Endpoints
+// ============================================
+
+#[derive(Debug, Deserialize)]
+pub struct CreateExperimentRequest {
+    pub name: String,
+    pub variants: Vec<ExperimentVariant>,
+}
+
+/// `GET /api/admin/experiments` - every experiment with outcome metrics per variant
+pub async fn list_experiments(State(state): State<AppState>) -> impl IntoResponse {
+    let experiments = match state.db.list_experiments().await {
+        Ok(experiments) => experiments,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let mut reports = Vec::with_capacity(experiments.len());
+    for experiment in experiments {
+        match experiments::report(&state.db, experiment).await {
+            Ok(report) => reports.push(report),
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         }
-        _ => ServiceStatus {
-            name: "sandbox".to_string(),
-            status: "offline".to_string(),
-            latency_ms: None,
-            details: None,
-        },
+    }
+    (StatusCode::OK, Json(serde_json::json!({ "experiments": reports }))).into_response()
+}
+
+/// `POST /api/admin/experiments` - start assigning new missions to the variants.
+/// The newest active experiment takes new missions.
+pub async fn create_experiment(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateExperimentRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = experiments::validate(&payload.name, &payload.variants) {
+        return error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string());
+    }
+    match state.db.get_experiment(&payload.name).await {
+        Ok(Some(_)) => return error(StatusCode::CONFLICT, format!("Experiment '{}' already exists", payload.name)),
+        Ok(None) => {}
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    match state.db.create_experiment(payload.name.trim(), &payload.variants).await {
+        Ok(experiment) => (StatusCode::CREATED, Json(experiment)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `GET /api/admin/experiments/:id` - one experiment (by ID or name) with its metrics
+pub async fn get_experiment(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let experiment = match state.db.get_experiment(&id).await {
+        Ok(Some(experiment)) => experiment,
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Experiment '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match experiments::report(&state.db, experiment).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/admin/experiments/:id/stop` - stop assigning missions; results are kept
+pub async fn stop_experiment(state: State<AppState>, id: Path<String>) -> Response {
+    set_experiment_active(state, id, false).await
+}
+
+/// `POST /api/admin/experiments/:id/start` - resume assigning missions
+pub async fn start_experiment(state: State<AppState>, id: Path<String>) -> Response {
+    set_experiment_active(state, id, true).await
+}
+
+async fn set_experiment_active(State(state): State<AppState>, Path(id): Path<String>, active: bool) -> Response {
+    let experiment = match state.db.get_experiment(&id).await {
+        Ok(Some(experiment)) => experiment,
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Experiment '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match state.db.set_experiment_active(&experiment.id, active).await {
+        Ok(_) => (StatusCode::OK, Json(serde_json::json!({ "id": experiment.id, "active": active }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
     }
 }
 
@@ -102,18 +234,33 @@ async fn check_sandbox_health() -> ServiceStatus {
 pub struct SystemPrompts {
     pub chat: String,
     pub agent: String,
+    /// Mission system prompt template; see `spawn_ai::PromptTemplates`
+    #[serde(default = "default_mission_prompt")]
+    pub mission: String,
 }
 
-const PROMPTS_FILE: &str = "config/prompts.json";
+fn default_mission_prompt() -> String {
+    spawn_ai::DEFAULT_MISSION_PROMPT.to_string()
+}
+
+pub(crate) const PROMPTS_FILE: &str = "config/prompts.json";
 
 pub async fn get_prompts() -> impl IntoResponse {
     let prompts = load_prompts();
     (StatusCode::OK, Json(prompts))
 }
 
-pub async fn save_prompts(Json(prompts): Json<SystemPrompts>) -> impl IntoResponse {
+/// Templates must compile; missions started after this use them
+pub async fn save_prompts(State(state): State<AppState>, Json(prompts): Json<SystemPrompts>) -> impl IntoResponse {
+    let templates = state.orchestrator.prompt_templates();
+    for (name, template) in [("chat", &prompts.chat), ("agent", &prompts.agent), (spawn_ai::MISSION_PROMPT, &prompts.mission)] {
+        if let Err(e) = templates.set(name, template.as_str()) {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"success": false, "error": e.to_string()})));
+        }
+    }
+
     // Ensure config directory exists
-    if let Some(parent) = Path::new(PROMPTS_FILE).parent() {
+    if let Some(parent) = std::path::Path::new(PROMPTS_FILE).parent() {
         let _ = fs::create_dir_all(parent);
     }
 
@@ -144,6 +291,7 @@ Guidelines:
 - Be proactive and complete tasks autonomously
 - Test your work by running commands
 - Report progress and results clearly"#.to_string(),
+            mission: default_mission_prompt(),
         })
 }
 
@@ -167,9 +315,12 @@ pub async fn get_config() -> impl IntoResponse {
     (StatusCode::OK, Json(config))
 }
 
-pub async fn save_config(Json(config): Json<SpawnConfig>) -> impl IntoResponse {
+/// The rules apply to missions started after this
+pub async fn save_config(State(state): State<AppState>, Json(config): Json<SpawnConfig>) -> impl IntoResponse {
+    apply_rules(state.orchestrator.prompt_templates(), &config);
+
     // Ensure config directory exists
-    if let Some(parent) = Path::new(CONFIG_FILE).parent() {
+    if let Some(parent) = std::path::Path::new(CONFIG_FILE).parent() {
         let _ = fs::create_dir_all(parent);
     }
 
@@ -182,7 +333,13 @@ pub async fn save_config(Json(config): Json<SpawnConfig>) -> impl IntoResponse {
     }
 }
 
-fn load_config() -> SpawnConfig {
+/// Make the must/must-not rules the `must` and `must_not` of every prompt
+pub(crate) fn apply_rules(templates: &spawn_ai::PromptTemplates, config: &SpawnConfig) {
+    templates.set_global("must", &config.must_rules);
+    templates.set_global("must_not", &config.must_not_rules);
+}
+
+pub(crate) fn load_config() -> SpawnConfig {
     fs::read_to_string(CONFIG_FILE)
         .ok()
         .and_then(|s| serde_json::from_str(&s).ok())
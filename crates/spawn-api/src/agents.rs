@@ -0,0 +1,28 @@
+//! Agent persona endpoints
+//!
+//! Lists the personas missions can run as; pass one's ID as `agent` when
+//! creating a mission.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{error, AppState};
+
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    let agents = state.orchestrator.agents().list();
+    Json(serde_json::json!({ "agents": agents }))
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.orchestrator.agents().get(&id) {
+        Some(agent) => (StatusCode::OK, Json(agent)).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("Agent '{}' not found", id)),
+    }
+}
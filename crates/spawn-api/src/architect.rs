@@ -6,14 +6,27 @@
 use axum::{
     extract::{Query, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use spawn_agents::locks::{file_resource, git_resource};
+use spawn_agents::formatting::Formatter;
+use spawn_agents::ignore::IgnoreRules;
+use spawn_agents::tools::write_synced;
+use spawn_core::{MissionId, Service, SpawnError};
 
-use crate::AppState;
+use crate::{error, spawn_error, AppState};
 
-const TERMINAL_API: &str = "http://localhost:3001";
+pub const TERMINAL_API: &str = "http://localhost:3001";
+
+/// terminal-app didn't answer: mark it down until the next probe and give
+/// the 503 naming it
+fn terminal_unreachable(state: &AppState, e: reqwest::Error) -> Response {
+    tracing::warn!(error = %e, "terminal-app unreachable");
+    state.services.set(Service::Terminal, false);
+    spawn_error(&SpawnError::ServiceUnavailable(Service::Terminal), serde_json::json!({}))
+}
 
 // ============================================
 // Tool Execution API
@@ -98,8 +111,12 @@ pub async fn exec_command(
 pub struct CreateTerminalRequest {
     pub name: String,
     pub cwd: Option<String>,
+    /// Mission that owns the terminal; it is closed and its output archived
+    /// when the mission ends
+    pub mission_id: Option<MissionId>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub struct TerminalInfo {
     pub id: String,
@@ -111,7 +128,10 @@ pub struct TerminalInfo {
 pub async fn create_terminal(
     State(state): State<AppState>,
     Json(req): Json<CreateTerminalRequest>,
-) -> impl IntoResponse {
+) -> Response {
+    if let Err(e) = state.services.require(Service::Terminal) {
+        return spawn_error(&e, serde_json::json!({}));
+    }
     let client = reqwest::Client::new();
     let cwd = req.cwd
         .map(|p| state.workspace_root.join(p))
@@ -122,6 +142,7 @@ pub async fn create_terminal(
         .json(&serde_json::json!({
             "name": req.name,
             "cwd": cwd.to_string_lossy(),
+            "mission_id": req.mission_id,
         }))
         .send()
         .await
@@ -131,11 +152,7 @@ pub async fn create_terminal(
             let body: serde_json::Value = resp.json().await.unwrap_or_default();
             (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK), Json(body)).into_response()
         }
-        Err(e) => {
-            (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
-                "error": format!("Terminal API unavailable: {}", e)
-            }))).into_response()
-        }
+        Err(e) => terminal_unreachable(&state, e),
     }
 }
 
@@ -147,8 +164,12 @@ pub struct TerminalExecRequest {
 
 /// Execute command in a named terminal
 pub async fn terminal_exec(
+    State(state): State<AppState>,
     Json(req): Json<TerminalExecRequest>,
-) -> impl IntoResponse {
+) -> Response {
+    if let Err(e) = state.services.require(Service::Terminal) {
+        return spawn_error(&e, serde_json::json!({}));
+    }
     let client = reqwest::Client::new();
 
     match client
@@ -162,11 +183,7 @@ pub async fn terminal_exec(
             let body: serde_json::Value = resp.json().await.unwrap_or_default();
             (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK), Json(body)).into_response()
         }
-        Err(e) => {
-            (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
-                "error": format!("Terminal API unavailable: {}", e)
-            }))).into_response()
-        }
+        Err(e) => terminal_unreachable(&state, e),
     }
 }
 
@@ -178,8 +195,12 @@ pub struct TerminalBufferQuery {
 
 /// Get terminal output buffer
 pub async fn terminal_buffer(
+    State(state): State<AppState>,
     Query(query): Query<TerminalBufferQuery>,
-) -> impl IntoResponse {
+) -> Response {
+    if let Err(e) = state.services.require(Service::Terminal) {
+        return spawn_error(&e, serde_json::json!({}));
+    }
     let client = reqwest::Client::new();
 
     // First get terminal by name
@@ -189,11 +210,7 @@ pub async fn terminal_buffer(
         .await
     {
         Ok(r) => r,
-        Err(e) => {
-            return (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
-                "error": format!("Terminal API unavailable: {}", e)
-            }))).into_response();
-        }
+        Err(e) => return terminal_unreachable(&state, e),
     };
 
     if !term_resp.status().is_success() {
@@ -216,16 +233,74 @@ pub async fn terminal_buffer(
             let body: serde_json::Value = resp.json().await.unwrap_or_default();
             (StatusCode::OK, Json(body)).into_response()
         }
-        Err(e) => {
-            (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
-                "error": format!("Terminal API unavailable: {}", e)
-            }))).into_response()
+        Err(e) => terminal_unreachable(&state, e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TerminalSnippetRequest {
+    pub name: String,
+    pub snippet: String,
+    #[serde(default)]
+    pub params: std::collections::HashMap<String, String>,
+}
+
+/// URL on the terminal API with each of `segments` percent-encoded
+fn terminal_url(segments: &[&str]) -> String {
+    let mut url = reqwest::Url::parse(TERMINAL_API).expect("TERMINAL_API is a valid URL");
+    url.path_segments_mut().expect("TERMINAL_API has a path").pop_if_empty().extend(segments);
+    url.to_string()
+}
+
+/// Run a saved snippet in a named terminal
+pub async fn terminal_snippet(
+    State(state): State<AppState>,
+    Json(req): Json<TerminalSnippetRequest>,
+) -> Response {
+    if let Err(e) = state.services.require(Service::Terminal) {
+        return spawn_error(&e, serde_json::json!({}));
+    }
+    let client = reqwest::Client::new();
+
+    // First get terminal by name
+    let term_resp = match client
+        .get(terminal_url(&["api", "terminals", "by-name", &req.name]))
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return terminal_unreachable(&state, e),
+    };
+
+    if !term_resp.status().is_success() {
+        return error(StatusCode::NOT_FOUND, format!("Terminal '{}' not found", req.name));
+    }
+
+    let term_info: serde_json::Value = term_resp.json().await.unwrap_or_default();
+    let Some(term_id) = term_info["id"].as_str().filter(|id| !id.is_empty()) else {
+        return error(StatusCode::BAD_GATEWAY, format!("Terminal API returned no id for '{}'", req.name));
+    };
+
+    match client
+        .post(terminal_url(&["api", "terminals", term_id, "snippets", &req.snippet, "run"]))
+        .json(&serde_json::json!({ "params": req.params }))
+        .send()
+        .await
+    {
+        Ok(resp) => {
+            let status = resp.status();
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            (StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::OK), Json(body)).into_response()
         }
+        Err(e) => terminal_unreachable(&state, e),
     }
 }
 
 /// List all terminal sessions
-pub async fn list_terminals() -> impl IntoResponse {
+pub async fn list_terminals(State(state): State<AppState>) -> Response {
+    if let Err(e) = state.services.require(Service::Terminal) {
+        return spawn_error(&e, serde_json::json!({}));
+    }
     let client = reqwest::Client::new();
 
     match client.get(format!("{}/api/terminals", TERMINAL_API)).send().await {
@@ -233,11 +308,7 @@ pub async fn list_terminals() -> impl IntoResponse {
             let body: serde_json::Value = resp.json().await.unwrap_or_default();
             (StatusCode::OK, Json(body)).into_response()
         }
-        Err(e) => {
-            (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
-                "error": format!("Terminal API unavailable: {}", e)
-            }))).into_response()
-        }
+        Err(e) => terminal_unreachable(&state, e),
     }
 }
 
@@ -290,6 +361,16 @@ pub async fn read_file(
 pub struct WriteFileRequest {
     pub path: String,
     pub content: String,
+    /// Who is writing (`editor:<session>`); their own reservation doesn't
+    /// block them and is released once the file is saved
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Write even if someone else has the file reserved
+    #[serde(default)]
+    pub force: bool,
+    /// Save exactly as given, skipping the workspace's formatter
+    #[serde(default)]
+    pub raw: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -297,6 +378,11 @@ pub struct WriteFileResponse {
     pub success: bool,
     pub path: String,
     pub error: Option<String>,
+    /// The content was run through a formatter before saving
+    pub formatted: bool,
+    /// Saved, but not as cleanly as asked (e.g. the formatter failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
 /// Write file contents
@@ -304,6 +390,13 @@ pub async fn write_file(
     State(state): State<AppState>,
     Json(req): Json<WriteFileRequest>,
 ) -> impl IntoResponse {
+    if let Err(response) = state.check_reservation(&req.path, req.owner.as_deref(), req.force).await {
+        return response;
+    }
+    let _lock = match state.lock(file_resource(&req.path), "write_file").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
     let path = state.workspace_root.join(&req.path);
 
     // Ensure parent directory exists
@@ -313,16 +406,38 @@ pub async fn write_file(
                 success: false,
                 path: req.path,
                 error: Some(format!("Failed to create directory: {}", e)),
+                formatted: false,
+                warning: None,
             })).into_response();
         }
     }
 
-    match tokio::fs::write(&path, &req.content).await {
+    let (content, warning) = if req.raw {
+        (None, None)
+    } else {
+        match format_content(&state, &req.path, &req.content).await {
+            Ok(formatted) => (formatted, None),
+            Err(e) => {
+                tracing::warn!(path = %req.path, error = %e, "Formatting failed; saving as written");
+                (None, Some(e.to_string()))
+            }
+        }
+    };
+    let formatted = content.is_some();
+
+    match write_synced(&path, content.as_deref().unwrap_or(&req.content).as_bytes()).await {
         Ok(_) => {
+            if let Some(owner) = &req.owner {
+                if let Err(e) = state.reservations.release(&req.path, owner).await {
+                    tracing::warn!(error = %e, "Failed to release file reservation");
+                }
+            }
             (StatusCode::OK, Json(WriteFileResponse {
                 success: true,
                 path: req.path,
                 error: None,
+                formatted,
+                warning,
             })).into_response()
         }
         Err(e) => {
@@ -330,14 +445,24 @@ pub async fn write_file(
                 success: false,
                 path: req.path,
                 error: Some(e.to_string()),
+                formatted: false,
+                warning,
             })).into_response()
         }
     }
 }
 
+/// `content` as the workspace's formatter for `path` leaves it, or `None`
+/// if formatting is off or nothing handles the file type
+async fn format_content(state: &AppState, path: &str, content: &str) -> spawn_core::Result<Option<String>> {
+    Formatter::for_workspace(&state.workspace_root, &state.format).await?
+        .format(path, content).await
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListFilesRequest {
     pub path: Option<String>,
+    #[allow(dead_code)]
     pub recursive: Option<bool>,
 }
 
@@ -366,11 +491,15 @@ pub async fn list_files(
         .unwrap_or_else(|| state.workspace_root.clone());
 
     let mut files = Vec::new();
+    let ignore = IgnoreRules::load(&state.workspace_root);
 
     match tokio::fs::read_dir(&path).await {
         Ok(mut entries) => {
             while let Ok(Some(entry)) = entries.next_entry().await {
                 let metadata = entry.metadata().await.ok();
+                if ignore.is_ignored(&entry.path(), metadata.as_ref().is_some_and(|m| m.is_dir())) {
+                    continue;
+                }
                 files.push(FileEntry {
                     name: entry.file_name().to_string_lossy().to_string(),
                     path: entry.path().strip_prefix(&state.workspace_root)
@@ -409,7 +538,7 @@ pub struct ChatToMissionRequest {
 
 #[derive(Debug, Serialize)]
 pub struct ChatToMissionResponse {
-    pub mission_id: Option<String>,
+    pub mission_id: Option<MissionId>,
     pub analysis: String,
     pub suggested_steps: Vec<String>,
 }
@@ -419,7 +548,7 @@ pub async fn chat_to_mission(
     State(state): State<AppState>,
     Json(req): Json<ChatToMissionRequest>,
 ) -> impl IntoResponse {
-    use spawn_core::Mission;
+    use spawn_core::{Mission, MissionContext};
 
     // Simple extraction - use the message as the goal directly
     // For more sophisticated analysis, could call sandbox's Grok via API
@@ -431,20 +560,12 @@ pub async fn chat_to_mission(
     ];
 
     let mission_id = if req.create_mission {
-        let mut mission = Mission::new(&goal);
-        mission.context = serde_json::json!({
-            "original_message": req.message,
-            "steps": steps,
-        });
+        let mission = Mission::new(&goal)
+            .with_context(MissionContext::new().with_steps(steps.clone()).with("original_message", &req.message));
         let id = mission.id.clone();
 
         // Start the mission
-        let orchestrator = state.orchestrator.clone();
-        tokio::spawn(async move {
-            if let Err(e) = orchestrator.run_mission(mission).await {
-                tracing::error!(error = %e, "Mission failed");
-            }
-        });
+        state.spawn_mission(mission).await;
 
         Some(id)
     } else {
@@ -579,7 +700,7 @@ pub async fn git_clone(
     }
 
     let target_dir = req.target.unwrap_or_else(|| {
-        req.repo.split('/').last().unwrap_or("repo").replace(".git", "")
+        req.repo.split('/').next_back().unwrap_or("repo").replace(".git", "")
     });
 
     let mut args = vec!["clone".to_string()];
@@ -590,6 +711,11 @@ pub async fn git_clone(
     args.push(repo_url);
     args.push(target_dir.clone());
 
+    let _lock = match state.lock(git_resource(&target_dir), "git_clone").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
+
     let output = tokio::process::Command::new("git")
         .args(&args)
         .current_dir(&state.workspace_root)
@@ -630,6 +756,10 @@ pub async fn git_commit(
     State(state): State<AppState>,
     Json(req): Json<GitCommitRequest>,
 ) -> impl IntoResponse {
+    let _lock = match state.lock(git_resource(&req.path), "git_commit").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
     let repo_path = state.workspace_root.join(&req.path);
 
     // Stage files
@@ -713,6 +843,11 @@ pub async fn git_push(
         }))).into_response(),
     };
 
+    let _lock = match state.lock(git_resource(&req.path), "git_push").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
+
     let mut args = vec!["push".to_string()];
     if let Some(ref branch) = req.branch {
         args.push("origin".to_string());
@@ -763,6 +898,10 @@ pub async fn git_pull(
     State(state): State<AppState>,
     Json(req): Json<GitPullRequest>,
 ) -> impl IntoResponse {
+    let _lock = match state.lock(git_resource(&req.path), "git_pull").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
     let repo_path = state.workspace_root.join(&req.path);
 
     let mut args = vec!["pull".to_string()];
@@ -824,6 +963,7 @@ pub async fn status(State(state): State<AppState>) -> impl IntoResponse {
         .send()
         .await
         .is_ok();
+    state.services.set(Service::Terminal, terminal_connected);
 
     // Get terminal count
     let active_terminals = if terminal_connected {
@@ -0,0 +1,149 @@
+//! Self-description of this deployment
+//!
+//! `GET /api/capabilities` reports what missions and clients can use here:
+//! the registered tools with their argument schemas and whether policy lets
+//! missions run them, the models in use and the providers behind them, the
+//! agent personas, which optional subsystems are enabled and which
+//! services are up, and the workspaces. External orchestrators and the frontend read it to adapt to
+//! the deployment instead of assuming a feature set.
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use spawn_agents::templates::WorkspacePolicy;
+use spawn_agents::tools::ToolInfo;
+use spawn_core::{AgentId, CapabilitySet, CircuitState, RoutingRule, Service};
+
+use crate::workspaces::DEFAULT_WORKSPACE;
+use crate::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub version: &'static str,
+    pub tools: Vec<ToolCapability>,
+    /// Capabilities no mission may use, whatever its own policy
+    pub denied_capabilities: CapabilitySet,
+    pub models: Models,
+    /// Persona IDs to pass as a mission's `agent`
+    pub agents: Vec<AgentId>,
+    pub features: Features,
+    /// Optional services and whether they are up right now
+    pub services: Vec<ServiceInfo>,
+    pub workspaces: Vec<WorkspaceInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolCapability {
+    #[serde(flatten)]
+    pub tool: ToolInfo,
+    /// Policy lets missions in the default workspace run it
+    pub allowed: bool,
+    /// A service it needs that is down; missions aren't offered it meanwhile
+    pub missing_capability: Option<Service>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Models {
+    pub mission: String,
+    pub chat: String,
+    pub postmortem: String,
+    /// Per-task rules picking models ahead of the defaults above
+    pub routes: Vec<RoutingRule>,
+    /// LLM providers in order of preference, with their health
+    pub providers: Vec<ProviderInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderInfo {
+    pub name: String,
+    pub healthy: bool,
+    /// Open while recent failures have it skipped
+    pub circuit: CircuitState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceInfo {
+    pub name: Service,
+    pub available: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Features {
+    /// Knowledge base, semantic search and conversation memory
+    pub vector_memory: bool,
+    pub speech_to_text: bool,
+    pub text_to_speech: bool,
+    /// Cross-encoder search reranking (the LLM reranker is always there)
+    pub cross_encoder_rerank: bool,
+    pub cluster: bool,
+    /// Where mission logs are kept
+    pub log_backend: String,
+    /// Interactive terminal connections allowed at once that are free
+    pub terminal_slots: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub root: String,
+    /// Capabilities its `.spawn-policy.toml` denies
+    pub deny: CapabilitySet,
+}
+
+pub async fn get(State(state): State<AppState>) -> impl IntoResponse {
+    let orchestrator = &state.orchestrator;
+    let default_policy = WorkspacePolicy::load(&state.workspace_root).unwrap_or_default();
+    let denied = orchestrator.denied_capabilities().union(&default_policy.deny);
+    let tools = orchestrator.tools().list().into_iter()
+        .map(|tool| ToolCapability {
+            allowed: tool.capabilities.intersection(&denied).is_empty(),
+            missing_capability: state.services.missing(&tool.requires),
+            tool,
+        })
+        .collect();
+
+    let mut workspaces = vec![WorkspaceInfo {
+        name: DEFAULT_WORKSPACE.to_string(),
+        root: state.workspace_root.display().to_string(),
+        deny: default_policy.deny,
+    }];
+    match state.db.list_workspaces().await {
+        Ok(registered) => workspaces.extend(registered.into_iter().map(|w| WorkspaceInfo {
+            deny: WorkspacePolicy::load(std::path::Path::new(&w.root)).unwrap_or_default().deny,
+            name: w.name,
+            root: w.root,
+        })),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": e.to_string() }))).into_response();
+        }
+    }
+
+    let capabilities = Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        tools,
+        denied_capabilities: orchestrator.denied_capabilities().clone(),
+        models: Models {
+            mission: orchestrator.model().to_string(),
+            chat: state.chat_model.clone(),
+            postmortem: orchestrator.postmortem_model().to_string(),
+            routes: state.providers.router().rules().to_vec(),
+            providers: state.providers.health().into_iter()
+                .map(|p| ProviderInfo { name: p.provider, healthy: p.healthy, circuit: p.circuit })
+                .collect(),
+        },
+        agents: orchestrator.agents().list().into_iter().map(|a| a.id).collect(),
+        features: Features {
+            vector_memory: state.vector_memory.is_some(),
+            speech_to_text: state.stt.is_some(),
+            text_to_speech: state.tts.is_some(),
+            cross_encoder_rerank: state.rerankers.cross_encoder.is_some(),
+            cluster: state.cluster.is_some(),
+            log_backend: state.logs.backend_name().to_string(),
+            terminal_slots: state.terminal_slots.available_permits(),
+        },
+        services: Service::ALL.into_iter()
+            .map(|name| ServiceInfo { name, available: state.services.is_up(name) })
+            .collect(),
+        workspaces,
+    };
+    (StatusCode::OK, Json(capabilities)).into_response()
+}
@@ -0,0 +1,215 @@
+//! Cluster mode
+//!
+//! With `cluster.advertise_url` set, several spawn-api instances can share one
+//! database behind a load balancer. Running missions and resumable WebSocket
+//! sessions live in the memory of the node that started them; ownership is
+//! recorded in the database, and a request for a session that lands on another
+//! node is proxied (or redirected) to its owner.
+
+use axum::{
+    body::Body,
+    extract::{ws::WebSocketUpgrade, FromRequestParts, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use spawn_agents::Database;
+use spawn_core::{Affinity, ClusterConfig, ClusterNode, SessionKind};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::{error, preview, AppState};
+
+/// How often a node refreshes its heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// A node not heard from for this long is presumed gone, along with its sessions
+const NODE_TTL: Duration = Duration::from_secs(30);
+/// Set on proxied requests so the owner serves them instead of routing again
+const FORWARDED_BY: &str = "x-spawn-forwarded-by";
+
+pub struct Cluster {
+    pub node_id: String,
+    pub url: String,
+    affinity: Affinity,
+    db: Arc<Database>,
+}
+
+impl Cluster {
+    pub fn new(config: &ClusterConfig, db: Arc<Database>) -> Self {
+        Self {
+            node_id: config.node_id.clone(),
+            url: config.advertise_url.clone(),
+            affinity: config.affinity,
+            db,
+        }
+    }
+
+    /// Announce this node and keep its heartbeat fresh.
+    ///
+    /// Sessions recorded under this node id belonged to a previous process and
+    /// died with it, so they are released first.
+    pub async fn join(self: &Arc<Self>) -> spawn_core::Result<()> {
+        let stale = self.db.release_node_sessions(&self.node_id).await?;
+        if stale > 0 {
+            info!(stale, "Released sessions left by this node's previous run");
+        }
+        self.db.heartbeat_node(&self.node_id, &self.url).await?;
+
+        let cluster = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = cluster.db.heartbeat_node(&cluster.node_id, &cluster.url).await {
+                    warn!(error = %e, "Cluster heartbeat failed");
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Record this node as the session's owner
+    pub async fn claim(&self, kind: SessionKind, id: &str) {
+        if let Err(e) = self.db.claim_session(kind, id, &self.node_id).await {
+            warn!(kind = kind.as_str(), id, error = %e, "Failed to record session owner");
+        }
+    }
+
+    pub async fn release(&self, kind: SessionKind, id: &str) {
+        if let Err(e) = self.db.release_session(kind, id, &self.node_id).await {
+            warn!(kind = kind.as_str(), id, error = %e, "Failed to release session");
+        }
+    }
+
+    /// The session's owner, if that is another node that is still alive
+    async fn remote_owner(&self, kind: SessionKind, id: &str) -> Option<ClusterNode> {
+        let owner = match self.db.session_owner(kind, id).await {
+            Ok(owner) => owner?,
+            Err(e) => {
+                // Serving locally beats failing the request
+                warn!(error = %e, "Session owner lookup failed");
+                return None;
+            }
+        };
+        let alive = chrono::Utc::now().signed_duration_since(owner.heartbeat_at)
+            .to_std()
+            .map_or(true, |age| age < NODE_TTL);
+        (owner.id != self.node_id && alive).then_some(owner)
+    }
+}
+
+/// Middleware: send requests for another node's session to that node
+pub async fn route_to_owner(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(cluster) = state.cluster.clone() else {
+        return next.run(req).await;
+    };
+    if req.headers().contains_key(FORWARDED_BY) {
+        return next.run(req).await;
+    }
+    let Some((kind, id)) = session_of(req.uri()) else {
+        return next.run(req).await;
+    };
+    let Some(owner) = cluster.remote_owner(kind, &id).await else {
+        return next.run(req).await;
+    };
+
+    let path = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let url = format!("{}{}", owner.url, path);
+    debug!(kind = kind.as_str(), id, owner = %owner.id, "Routing request to session owner");
+    match cluster.affinity {
+        Affinity::Redirect => (StatusCode::TEMPORARY_REDIRECT, [(header::LOCATION, url)]).into_response(),
+        Affinity::Proxy => proxy(&state, &cluster, req, url).await,
+    }
+}
+
+/// The node-bound session a request addresses, if any
+fn session_of(uri: &Uri) -> Option<(SessionKind, String)> {
+    let Query(query) = Query::<HashMap<String, String>>::try_from_uri(uri).ok()?;
+    let param = |name: &str| query.get(name).filter(|v| !v.is_empty()).cloned();
+
+    match uri.path() {
+        "/ws/terminal" => Some((SessionKind::Terminal, param("resume")?)),
+        "/ws/events" => param("resume")
+            .map(|token| (SessionKind::Events, token))
+            .or_else(|| Some((SessionKind::Mission, param("mission_id")?))),
+        "/api/events" => Some((SessionKind::Mission, param("mission_id")?)),
+        path => {
+            let (id, _) = path.strip_prefix("/api/missions/")?.split_once('/')?;
+            (!matches!(id, "tags" | "filters")).then(|| (SessionKind::Mission, id.to_string()))
+        }
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build cluster HTTP client")
+    })
+}
+
+async fn proxy(state: &AppState, cluster: &Cluster, req: Request, url: String) -> Response {
+    let mut forwarded = HeaderMap::new();
+    if let Ok(node) = HeaderValue::from_str(&cluster.node_id) {
+        forwarded.insert(FORWARDED_BY, node);
+    }
+
+    if preview::is_websocket_upgrade(req.headers()) {
+        let (mut parts, _) = req.into_parts();
+        let ws = match WebSocketUpgrade::from_request_parts(&mut parts, state).await {
+            Ok(ws) => ws,
+            Err(rejection) => return rejection.into_response(),
+        };
+        // http -> ws, https -> wss
+        let url = url.replacen("http", "ws", 1);
+        return preview::proxy_websocket(ws, &parts.headers, forwarded, url).await;
+    }
+
+    let (parts, body) = req.into_parts();
+    let mut upstream = client()
+        .request(parts.method, &url)
+        .body(reqwest::Body::wrap_stream(body.into_data_stream()));
+    for (name, value) in parts.headers.iter() {
+        if name != header::HOST && !preview::HOP_BY_HOP.contains(&name.as_str()) {
+            upstream = upstream.header(name, value);
+        }
+    }
+
+    let resp = match upstream.headers(forwarded).send().await {
+        Ok(r) => r,
+        Err(e) => {
+            return error(StatusCode::BAD_GATEWAY, format!("Owning node unavailable: {}", e));
+        }
+    };
+
+    let mut builder = Response::builder().status(resp.status());
+    for (name, value) in resp.headers().iter() {
+        if !preview::HOP_BY_HOP.contains(&name.as_str()) {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
+        .body(Body::from_stream(resp.bytes_stream()))
+        .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response())
+}
+
+/// `GET /api/cluster/nodes` - this node and the live nodes sharing its database
+pub async fn nodes(State(state): State<AppState>) -> Response {
+    let Some(cluster) = &state.cluster else {
+        return Json(serde_json::json!({ "enabled": false, "node_id": null, "nodes": [] })).into_response();
+    };
+    let since = chrono::Utc::now() - chrono::Duration::from_std(NODE_TTL).unwrap_or_default();
+    match state.db.list_nodes(since).await {
+        Ok(nodes) => Json(serde_json::json!({
+            "enabled": true,
+            "node_id": cluster.node_id,
+            "nodes": nodes,
+        })).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
@@ -0,0 +1,85 @@
+//! Coverage endpoints
+//!
+//! Per-file test coverage collected by cargo-llvm-cov, istanbul or
+//! coverage.py runs.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::tools::coverage::{self, CoverageRunner};
+use spawn_core::ProgressSender;
+
+use crate::{error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct CoverageQuery {
+    pub file: Option<String>,
+}
+
+/// Stored coverage, optionally for a single file
+pub async fn get_coverage(
+    State(state): State<AppState>,
+    Query(query): Query<CoverageQuery>,
+) -> impl IntoResponse {
+    let files = match state.db.list_coverage(query.file.as_deref()).await {
+        Ok(f) => f,
+        Err(e) => {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+
+    let lines_total: u32 = files.iter().map(|f| f.lines_total).sum();
+    let lines_covered: u32 = files.iter().map(|f| f.lines_covered).sum();
+    let percent = if lines_total == 0 { 0.0 } else { lines_covered as f64 * 100.0 / lines_total as f64 };
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "lines_total": lines_total,
+        "lines_covered": lines_covered,
+        "percent": percent,
+        "files": files,
+    }))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunCoverageRequest {
+    pub language: Option<String>,
+}
+
+/// Run tests with coverage and store the results
+pub async fn run_coverage(
+    State(state): State<AppState>,
+    Json(req): Json<RunCoverageRequest>,
+) -> impl IntoResponse {
+    let runners = match req.language.as_deref() {
+        Some(lang) => match CoverageRunner::for_language(lang) {
+            Some(r) => vec![r],
+            None => {
+                return error(StatusCode::BAD_REQUEST, format!("No coverage runner for language '{}'", lang));
+            }
+        },
+        None => CoverageRunner::detect(&state.workspace_root),
+    };
+
+    let mut files = Vec::new();
+    for runner in &runners {
+        let found = match coverage::run(&state.workspace_root, *runner, &ProgressSender::default()).await {
+            Ok(f) => f,
+            Err(e) => {
+                return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+            }
+        };
+        if let Err(e) = state.db.replace_coverage(runner.name(), &found).await {
+            tracing::error!(error = %e, "Failed to store coverage");
+        }
+        files.extend(found);
+    }
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "runners": runners,
+        "files": files,
+    }))).into_response()
+}
@@ -0,0 +1,43 @@
+//! Dependency analysis endpoint
+//!
+//! Structured view of declared, outdated, and vulnerable dependencies.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::tools::deps;
+
+use crate::{error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct DepsQuery {
+    pub path: Option<String>,
+    #[serde(default = "default_check_registry")]
+    pub check_registry: bool,
+}
+
+fn default_check_registry() -> bool {
+    true
+}
+
+/// Analyze workspace manifests
+pub async fn analyze(
+    State(state): State<AppState>,
+    Query(query): Query<DepsQuery>,
+) -> impl IntoResponse {
+    let root = match deps::scan_root(&state.workspace_root, query.path.as_deref()) {
+        Ok(root) => root,
+        Err(_) => return error(StatusCode::FORBIDDEN, "Access denied"),
+    };
+
+    match deps::analyze(&root, query.check_registry).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "error": format!("Dependency analysis failed: {}", e)
+        }))).into_response(),
+    }
+}
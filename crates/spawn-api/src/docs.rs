@@ -0,0 +1,196 @@
+//! Knowledge base endpoints
+//!
+//! Project docs (conventions, runbooks, specs) are uploaded per workspace,
+//! chunked and embedded so missions can retrieve the relevant parts.
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::docs::{chunk_text, extract_text, MAX_CHUNK_CHARS};
+use spawn_core::{DocFormat, Document};
+
+use crate::search::RerankPlan;
+use crate::{error, spawn_error, AppState};
+
+/// Upload limit for a single document
+pub const MAX_DOC_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct WorkspaceQuery {
+    /// Defaults to the server's workspace root
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DocSearchQuery {
+    pub q: String,
+    pub workspace: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i32,
+    /// `llm` or `cross_encoder` to rescore a wider candidate pool
+    pub rerank: Option<String>,
+    pub candidates: Option<i32>,
+}
+
+fn default_limit() -> i32 {
+    5
+}
+
+fn workspace_key(state: &AppState, workspace: Option<String>) -> String {
+    workspace.unwrap_or_else(|| state.workspace_root.display().to_string())
+}
+
+/// List documents in a workspace
+pub async fn list_docs(
+    State(state): State<AppState>,
+    Query(query): Query<WorkspaceQuery>,
+) -> impl IntoResponse {
+    let workspace = workspace_key(&state, query.workspace);
+    match state.db.list_documents(&workspace).await {
+        Ok(docs) => (StatusCode::OK, Json(serde_json::json!({
+            "workspace": workspace,
+            "documents": docs,
+        }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Upload a Markdown, PDF or HTML document.
+///
+/// Multipart fields: `file`, optional `title` and `workspace`.
+pub async fn upload_doc(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut file: Option<(String, Option<String>, Vec<u8>)> = None;
+    let mut title: Option<String> = None;
+    let mut workspace: Option<String> = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                let filename = field.file_name().unwrap_or("document.md").to_string();
+                let mime = field.content_type().map(String::from);
+                match field.bytes().await {
+                    Ok(bytes) => file = Some((filename, mime, bytes.to_vec())),
+                    Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+                }
+            }
+            "title" => title = field.text().await.ok().filter(|t| !t.trim().is_empty()),
+            "workspace" => workspace = field.text().await.ok().filter(|w| !w.is_empty()),
+            _ => {}
+        }
+    }
+
+    let Some((filename, mime, bytes)) = file.filter(|(_, _, b)| !b.is_empty()) else {
+        return error(StatusCode::BAD_REQUEST, "Missing 'file' field");
+    };
+    let Some(format) = DocFormat::detect(&filename, mime.as_deref()) else {
+        return error(StatusCode::UNSUPPORTED_MEDIA_TYPE, "Supported formats: Markdown, PDF, HTML");
+    };
+
+    let content = match tokio::task::spawn_blocking(move || extract_text(format, &bytes)).await {
+        Ok(Ok(text)) if !text.trim().is_empty() => text,
+        Ok(Ok(_)) => return error(StatusCode::UNPROCESSABLE_ENTITY, "Document contains no text"),
+        Ok(Err(e)) => return error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let chunks = chunk_text(&content, MAX_CHUNK_CHARS);
+
+    let title = title.unwrap_or_else(|| filename.rsplit_once('.').map_or(filename.as_str(), |(stem, _)| stem).to_string());
+    let mut doc = Document::new(workspace_key(&state, workspace), title, filename, format, content);
+
+    // Stored either way; retrieval only works once the chunks are embedded,
+    // and `chunk_count` only counts chunks that were
+    let indexed = match &state.vector_memory {
+        Some(memory) => match memory.index_document(&doc, &chunks).await {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::warn!(doc = %doc.id, error = %e, "Failed to embed document");
+                let _ = memory.delete_document(&doc.id).await;
+                0
+            }
+        },
+        None => 0,
+    };
+    doc.chunk_count = indexed as u32;
+    if let Err(e) = state.db.save_document(&doc).await {
+        if let Some(memory) = &state.vector_memory {
+            let _ = memory.delete_document(&doc.id).await;
+        }
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+
+    (StatusCode::CREATED, Json(serde_json::json!({
+        "document": Document { content: String::new(), ..doc },
+        "chunks_indexed": indexed,
+    }))).into_response()
+}
+
+/// A document with its extracted text
+pub async fn get_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.db.get_document(&id).await {
+        Ok(Some(doc)) => (StatusCode::OK, Json(doc)).into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, format!("Document '{}' not found", id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Delete a document and its embeddings
+pub async fn delete_doc(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.db.delete_document(&id).await {
+        Ok(true) => {}
+        Ok(false) => return error(StatusCode::NOT_FOUND, format!("Document '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    if let Some(memory) = &state.vector_memory {
+        if let Err(e) = memory.delete_document(&id).await {
+            tracing::warn!(doc = %id, error = %e, "Failed to delete document embeddings");
+        }
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Document chunks most relevant to a query
+pub async fn search_docs(
+    State(state): State<AppState>,
+    Query(query): Query<DocSearchQuery>,
+) -> impl IntoResponse {
+    let memory = match state.vector_memory() {
+        Ok(memory) => memory,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
+    let plan = match RerankPlan::new(&state, query.rerank.as_deref(), query.candidates, query.limit) {
+        Ok(plan) => plan,
+        Err((status, message)) => return error(status, message),
+    };
+    let workspace = workspace_key(&state, query.workspace);
+    let results = match memory.search_docs(&query.q, &workspace, plan.fetch).await {
+        Ok(results) => plan.apply(&query.q, results).await,
+        Err(e) => Err(e),
+    };
+    match results {
+        Ok(results) => (StatusCode::OK, Json(serde_json::json!({
+            "query": query.q,
+            "total": results.len(),
+            "results": results,
+        }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("Search failed: {}", e)),
+    }
+}
@@ -0,0 +1,190 @@
+//! Mission event streams
+//!
+//! Forwards the orchestrator's event bus to clients, as Server-Sent Events
+//! or over a WebSocket, optionally narrowed to a single mission. WebSocket
+//! clients that negotiate a schema version get numbered `event` messages,
+//! can change their mission filter with `subscribe` and can resume after a
+//! dropped connection; others get bare events.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use spawn_core::{EventStream, MissionEvent, MissionId, SessionKind, WsChannel, WsClientMessage, WsSequenced, WsServerMessage};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::resume::{ReplayChannel, ResumeQuery};
+use crate::{ws_protocol, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Only this mission's events
+    pub mission_id: Option<MissionId>,
+}
+
+/// Subscribe to the bus, keeping only the requested mission's events
+fn subscribe(state: &AppState, mission_id: Option<MissionId>) -> EventStream {
+    state.orchestrator.events().subscribe()
+        .filter(move |event| {
+            let keep = mission_id.as_deref().is_none_or(|id| event.mission_id() == id);
+            async move { keep }
+        })
+        .boxed()
+}
+
+/// `GET /api/events` - Server-Sent Events, one `MissionEvent` per message
+pub async fn sse(
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+) -> impl IntoResponse {
+    let stream = subscribe(&state, query.mission_id).map(|event| {
+        let kind = event_type(&event);
+        Ok::<_, Infallible>(Event::default().event(kind).json_data(&event).unwrap_or_default())
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// A filtered subscription to the bus, buffered so a client can resume it
+pub struct EventSession {
+    mission_id: Arc<Mutex<Option<MissionId>>>,
+    output: Arc<ReplayChannel>,
+    pump: JoinHandle<()>,
+}
+
+impl EventSession {
+    fn start(mut events: EventStream, mission_id: Option<MissionId>) -> Self {
+        let mission_id = Arc::new(Mutex::new(mission_id));
+        let output = Arc::new(ReplayChannel::new());
+        let pump = tokio::spawn({
+            let (mission_id, output) = (mission_id.clone(), output.clone());
+            async move {
+                while let Some(event) = events.next().await {
+                    let wanted = mission_id.lock().unwrap().as_deref().is_none_or(|id| event.mission_id() == id);
+                    if wanted {
+                        output.publish(WsServerMessage::Event { event });
+                    }
+                }
+            }
+        });
+        Self { mission_id, output, pump }
+    }
+}
+
+impl Drop for EventSession {
+    fn drop(&mut self) {
+        self.pump.abort();
+    }
+}
+
+/// `GET /ws/events` - the same events as JSON text frames
+pub async fn ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+    Query(resume): Query<ResumeQuery>,
+) -> impl IntoResponse {
+    let resumed = resume.resume.and_then(|token| {
+        let (session, generation) = state.event_sessions.attach(&token)?;
+        Some((token, session, generation))
+    });
+    // Subscribe before the upgrade so nothing published meanwhile is missed
+    let fresh = resumed.is_none().then(|| EventSession::start(state.orchestrator.events().subscribe(), query.mission_id));
+    let last_seq = resume.last_seq.unwrap_or(0);
+    ws_protocol::offer(ws).on_upgrade(move |socket| async move {
+        let version = ws_protocol::negotiated(&socket);
+        let is_resume = resumed.is_some();
+        let (session, registration, last_seq) = match (resumed, fresh) {
+            (Some((token, session, generation)), _) => (session, Some((token, generation)), last_seq),
+            (None, fresh) => {
+                let session = Arc::new(fresh.expect("started when not resuming"));
+                let registration = version.map(|_| state.event_sessions.insert(session.clone()));
+                if let (Some((token, _)), Some(cluster)) = (&registration, &state.cluster) {
+                    cluster.claim(SessionKind::Events, token).await;
+                }
+                (session, registration, 0)
+            }
+        };
+        let welcome = version.map(|version| WsServerMessage::Welcome {
+            version,
+            channel: WsChannel::Events,
+            resume_token: registration.as_ref().map(|(token, _)| token.clone()),
+            resumed: is_resume,
+        });
+        forward(socket, &session, welcome, last_seq).await;
+        if let Some((token, generation)) = registration {
+            state.event_sessions.detach(&token, generation);
+        }
+    })
+}
+
+async fn forward(socket: WebSocket, session: &EventSession, welcome: Option<WsServerMessage>, last_seq: u64) {
+    let typed = welcome.is_some();
+    let (mut sender, mut receiver) = socket.split();
+    if let Some(welcome) = welcome {
+        let _ = sender.send(ws_protocol::encode(&welcome)).await;
+    }
+    let (mut events, gap_from) = session.output.attach(last_seq);
+    if let (Some(oldest), true) = (gap_from, typed) {
+        let gap = WsServerMessage::error("replay_gap", format!("Events before seq {} are no longer available", oldest));
+        let _ = sender.send(ws_protocol::encode(&gap)).await;
+    }
+    loop {
+        let frame = tokio::select! {
+            frame = events.next() => match (typed, frame.message) {
+                (true, message) => ws_protocol::encode(&WsSequenced { seq: frame.seq, message }),
+                (false, WsServerMessage::Event { event }) => Message::Text(serde_json::to_string(&event).unwrap_or_default()),
+                (false, _) => continue,
+            },
+            incoming = receiver.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(Message::Text(text))) if typed => {
+                    match serde_json::from_str::<WsClientMessage>(&text) {
+                        Ok(WsClientMessage::Subscribe { mission_id }) => {
+                            *session.mission_id.lock().unwrap() = mission_id;
+                            continue;
+                        }
+                        Ok(WsClientMessage::Ping) => ws_protocol::encode(&WsServerMessage::Pong),
+                        Ok(WsClientMessage::Input { .. }) => ws_protocol::encode(
+                            &WsServerMessage::error("unsupported", "input is only valid on /ws/terminal"),
+                        ),
+                        Err(e) => ws_protocol::encode(&WsServerMessage::error("invalid_message", e.to_string())),
+                    }
+                }
+                _ => continue,
+            },
+        };
+        if sender.send(frame).await.is_err() {
+            break;
+        }
+    }
+    debug!("Event WebSocket closed");
+}
+
+/// SSE event name, matching the JSON `type` tag
+fn event_type(event: &MissionEvent) -> &'static str {
+    match event {
+        MissionEvent::Created { .. } => "created",
+        MissionEvent::Queued { .. } => "queued",
+        MissionEvent::Preempted { .. } => "preempted",
+        MissionEvent::Resumed { .. } => "resumed",
+        MissionEvent::StepStarted { .. } => "step_started",
+        MissionEvent::ToolProgress { .. } => "tool_progress",
+        MissionEvent::ToolExecuted { .. } => "tool_executed",
+        MissionEvent::FilesChanged { .. } => "files_changed",
+        MissionEvent::LogLine { .. } => "log_line",
+        MissionEvent::Completed { .. } => "completed",
+        MissionEvent::Failed { .. } => "failed",
+        MissionEvent::Cancelled { .. } => "cancelled",
+    }
+}
@@ -3,16 +3,20 @@
 //! REST endpoints for file explorer functionality
 
 use axum::{
-    body::Body,
     extract::{Path, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::fs;
-use tracing::{debug, error, info};
+use spawn_agents::ignore::IgnoreRules;
+use spawn_agents::locks::file_resource;
+use spawn_agents::sync::{self, DEFAULT_BATCH_BYTES};
+use spawn_agents::tools::write_synced;
+use tracing::{debug, error, info, warn};
 
 use crate::AppState;
 
@@ -35,6 +39,23 @@ pub struct FileNode {
 #[derive(Debug, Deserialize)]
 pub struct WriteFileRequest {
     pub content: String,
+    /// Who is writing (`editor:<session>`); their own reservation doesn't
+    /// block them and is released once the file is saved
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Write even if someone else has the file reserved
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    /// What the client holds: workspace-relative path -> sha256 (hex)
+    #[serde(default)]
+    pub manifest: HashMap<String, String>,
+    /// Cap on file content returned in one batch
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
 }
 
 // ============================================
@@ -45,7 +66,7 @@ pub struct WriteFileRequest {
 pub async fn list_files(State(state): State<AppState>) -> impl IntoResponse {
     info!("📂 Listing files in workspace");
 
-    match build_file_tree(&state.workspace_root, 0, 3).await {
+    match build_file_tree(&state.workspace_root, 0, 3, &IgnoreRules::load(&state.workspace_root)).await {
         Ok(tree) => (StatusCode::OK, Json(tree)).into_response(),
         Err(e) => {
             error!("Failed to list files: {}", e);
@@ -74,7 +95,7 @@ pub async fn read_file(
 
     if file_path.is_dir() {
         // Return directory listing as JSON
-        match build_file_tree(&file_path, 0, 2).await {
+        match build_file_tree(&file_path, 0, 2, &IgnoreRules::load(&state.workspace_root)).await {
             Ok(tree) => return (StatusCode::OK, Json(tree)).into_response(),
             Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
         }
@@ -83,7 +104,7 @@ pub async fn read_file(
     // Read file content
     match fs::read_to_string(&file_path).await {
         Ok(content) => (StatusCode::OK, content).into_response(),
-        Err(e) => {
+        Err(_) => {
             // Try reading as binary
             match fs::read(&file_path).await {
                 Ok(bytes) => {
@@ -120,6 +141,14 @@ pub async fn write_file(
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     }
 
+    if let Err(response) = state.check_reservation(&path, payload.owner.as_deref(), payload.force).await {
+        return response;
+    }
+    let _lock = match state.lock(file_resource(&path), "write_file").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
+
     // Create parent directories if needed
     if let Some(parent) = file_path.parent() {
         if let Err(e) = fs::create_dir_all(parent).await {
@@ -128,9 +157,16 @@ pub async fn write_file(
     }
 
     // Write file
-    match fs::write(&file_path, &payload.content).await {
+    // Synced so the editor and indexer see it as soon as we return
+    match write_synced(&file_path, payload.content.as_bytes()).await {
         Ok(_) => {
             info!("✅ File written: {:?}", file_path);
+            if let Some(owner) = &payload.owner {
+                // Saved, so nothing is pending any more
+                if let Err(e) = state.reservations.release(&path, owner).await {
+                    warn!(error = %e, "Failed to release file reservation");
+                }
+            }
             (
                 StatusCode::OK,
                 Json(serde_json::json!({ "success": true, "path": path })),
@@ -141,15 +177,32 @@ pub async fn write_file(
     }
 }
 
+/// Files that differ from the client's manifest, and paths deleted since
+pub async fn sync_files(
+    State(state): State<AppState>,
+    Json(req): Json<SyncRequest>,
+) -> impl IntoResponse {
+    let root = state.workspace_root.clone();
+    let max_bytes = req.max_bytes.unwrap_or(DEFAULT_BATCH_BYTES);
+    match tokio::task::spawn_blocking(move || sync::diff(&root, &req.manifest, max_bytes)).await {
+        Ok(batch) => {
+            debug!(changed = batch.changed.len(), deleted = batch.deleted.len(), truncated = batch.truncated, "🔁 Workspace sync");
+            (StatusCode::OK, Json(batch)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 // ============================================
 // Helpers
 // ============================================
 
-/// Build file tree recursively
+/// Build file tree recursively, leaving out what the workspace ignores
 async fn build_file_tree(
     path: &PathBuf,
     depth: usize,
     max_depth: usize,
+    ignore: &IgnoreRules,
 ) -> anyhow::Result<Vec<FileNode>> {
     let mut nodes = Vec::new();
 
@@ -159,18 +212,11 @@ async fn build_file_tree(
         let entry_path = entry.path();
         let file_name = entry.file_name().to_string_lossy().to_string();
 
-        // Skip hidden files and common ignored directories
-        if file_name.starts_with('.') 
-            || file_name == "node_modules"
-            || file_name == "target"
-            || file_name == "__pycache__"
-            || file_name == ".git"
-        {
-            continue;
-        }
-
         let metadata = entry.metadata().await?;
         let is_dir = metadata.is_dir();
+        if ignore.is_ignored(&entry_path, is_dir) {
+            continue;
+        }
 
         let relative_path = entry_path
             .strip_prefix(path.ancestors().last().unwrap_or(path))
@@ -179,7 +225,7 @@ async fn build_file_tree(
             .to_string();
 
         let children = if is_dir && depth < max_depth {
-            Some(Box::pin(build_file_tree(&entry_path, depth + 1, max_depth)).await?)
+            Some(Box::pin(build_file_tree(&entry_path, depth + 1, max_depth, ignore)).await?)
         } else if is_dir {
             Some(vec![]) // Indicate it has children but don't load them
         } else {
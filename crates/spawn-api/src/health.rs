@@ -0,0 +1,90 @@
+//! Subsystem health
+//!
+//! Every subsystem spawn depends on implements `HealthCheck`; `/health` and
+//! the admin status page probe them all at once.
+
+use async_trait::async_trait;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use spawn_core::{HealthCheck, HealthStatus, Result, SpawnError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::AppState;
+
+/// A probe slower than this counts as offline
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The subsystems to probe
+pub struct HealthChecks {
+    checks: Vec<Arc<dyn HealthCheck>>,
+}
+
+impl HealthChecks {
+    pub fn new() -> Self {
+        Self { checks: Vec::new() }
+    }
+
+    pub fn with(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Probe everything concurrently, in registration order
+    pub async fn check_all(&self) -> Vec<HealthStatus> {
+        futures::future::join_all(self.checks.iter().map(|check| async move {
+            tokio::time::timeout(PROBE_TIMEOUT, check.check()).await
+                .unwrap_or_else(|_| HealthStatus::offline(check.name(), format!("No answer within {}s", PROBE_TIMEOUT.as_secs())))
+        }))
+        .await
+    }
+}
+
+impl Default for HealthChecks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A service with a `GET /health` endpoint; whatever JSON it returns is
+/// passed on as details
+pub struct HttpHealthCheck {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpHealthCheck {
+    pub fn new(name: impl Into<String>, base_url: &str) -> Self {
+        Self { name: name.into(), url: format!("{}/health", base_url), client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for HttpHealthCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn probe(&self) -> Result<Option<serde_json::Value>> {
+        let response = self.client.get(&self.url).send().await
+            .map_err(|e| SpawnError::Internal(format!("{} unreachable: {}", self.name, e)))?;
+        if !response.status().is_success() {
+            return Err(SpawnError::Internal(format!("{} returned {}", self.name, response.status())));
+        }
+        Ok(response.json().await.ok())
+    }
+}
+
+/// `GET /health` - every subsystem's status; 503 if the database is down,
+/// since nothing works without it
+pub async fn health(State(state): State<AppState>) -> impl IntoResponse {
+    let checks = state.health.check_all().await;
+    let database_up = checks.iter().any(|c| c.name == "database" && c.is_online());
+    let status = match (database_up, checks.iter().all(HealthStatus::is_online)) {
+        (false, _) => "down",
+        (true, true) => "ok",
+        (true, false) => "degraded",
+    };
+    let code = if database_up { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(serde_json::json!({ "status": status, "checks": checks })))
+}
@@ -0,0 +1,238 @@
+//! Idempotency keys
+//!
+//! A POST sent with an `Idempotency-Key` header runs once: a retry with the
+//! same key and the same request gets the stored response back instead of
+//! starting a second mission, write or commit. Server errors aren't stored,
+//! so those can be retried, and streamed responses are passed through.
+//! Keys are per client: a request's `Authorization` credential, when it
+//! has one, is part of the stored key.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use spawn_agents::{Database, IdempotentRequest};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{error, AppState};
+
+pub const HEADER: &str = "idempotency-key";
+/// Set on a stored response that is being replayed
+const REPLAYED: &str = "idempotent-replayed";
+/// How long a key is remembered
+const KEY_TTL_HOURS: i64 = 24;
+/// How long a claim without a response holds, should the server stop
+/// before the request finishes
+const CLAIM_LEASE_MINUTES: i64 = 10;
+const MAX_KEY_LEN: usize = 255;
+/// Larger requests or responses aren't made idempotent
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+pub async fn idempotent(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    respond(&state.db, req, |req| next.run(req)).await
+}
+
+/// The middleware around `run`, the rest of the stack
+async fn respond<F, Fut>(db: &Arc<Database>, req: Request, run: F) -> Response
+where
+    F: FnOnce(Request) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let key = match req.headers().get(HEADER) {
+        Some(key) if req.method() == Method::POST => key.to_str().unwrap_or_default().to_string(),
+        _ => return run(req).await,
+    };
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return error(StatusCode::BAD_REQUEST, "Idempotency-Key must be 1 to 255 visible ASCII characters");
+    }
+    let key = match req.headers().get(header::AUTHORIZATION) {
+        Some(credential) => format!("{:x}:{}", Sha256::digest(credential.as_bytes()), key),
+        None => key,
+    };
+
+    let (parts, body) = req.into_parts();
+    let Ok(body) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return error(StatusCode::PAYLOAD_TOO_LARGE, "Request too large to make idempotent");
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str());
+    hasher.update(parts.uri.path_and_query().map_or(parts.uri.path(), |p| p.as_str()));
+    hasher.update(&body);
+    let hash = format!("{:x}", hasher.finalize());
+
+    let (ttl, lease) = (chrono::Duration::hours(KEY_TTL_HOURS), chrono::Duration::minutes(CLAIM_LEASE_MINUTES));
+    let claim = match db.begin_idempotent(&key, &hash, ttl, lease).await {
+        Ok(IdempotentRequest::New) => Claim { db: db.clone(), key: Some(key) },
+        Ok(IdempotentRequest::InProgress) => {
+            return error(StatusCode::CONFLICT, "A request with this Idempotency-Key is still in progress");
+        }
+        Ok(IdempotentRequest::Mismatch) => {
+            return error(StatusCode::UNPROCESSABLE_ENTITY, "This Idempotency-Key was used for a different request");
+        }
+        Ok(IdempotentRequest::Completed { status, content_type, body }) => {
+            let mut response = (StatusCode::from_u16(status).unwrap_or(StatusCode::OK), body).into_response();
+            if let Some(value) = content_type.and_then(|t| HeaderValue::from_str(&t).ok()) {
+                response.headers_mut().insert(header::CONTENT_TYPE, value);
+            }
+            response.headers_mut().insert(REPLAYED, HeaderValue::from_static("true"));
+            return response;
+        }
+        Err(e) => {
+            // Better to risk a duplicate than to refuse the request
+            warn!(error = %e, "Idempotency store unavailable; running request without it");
+            return run(Request::from_parts(parts, Body::from(body))).await;
+        }
+    };
+
+    let response = run(Request::from_parts(parts, Body::from(body))).await;
+    let content_type = response.headers().get(header::CONTENT_TYPE)
+        .and_then(|t| t.to_str().ok())
+        .map(str::to_string);
+    let streamed = content_type.as_deref().is_some_and(|t| t.starts_with("text/event-stream"));
+    if response.status().is_server_error() || streamed {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read response: {}", e)),
+    };
+    match db.finish_idempotent(claim.key(), parts.status.as_u16(), content_type.as_deref(), &body).await {
+        Ok(()) => claim.keep(),
+        Err(e) => warn!(error = %e, "Failed to store idempotent response"),
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// A key claimed for a running request. Released when dropped unless its
+/// response was stored, so a retry runs the request again after a server
+/// error, or after the client went away and the request was dropped.
+struct Claim {
+    db: Arc<Database>,
+    key: Option<String>,
+}
+
+impl Claim {
+    fn key(&self) -> &str {
+        self.key.as_deref().unwrap_or_default()
+    }
+
+    fn keep(mut self) {
+        self.key = None;
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else { return };
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            if let Err(e) = db.abandon_idempotent(&key).await {
+                warn!(error = %e, "Failed to release idempotency key");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn request(key: &str, credential: Option<&str>, body: &'static str) -> Request {
+        let mut builder = Request::builder().method(Method::POST).uri("/api/missions").header(HEADER, key);
+        if let Some(credential) = credential {
+            builder = builder.header(header::AUTHORIZATION, credential);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    /// Sends `req` to a handler answering `status` with its run count
+    async fn send(db: &Arc<Database>, runs: &AtomicU32, req: Request, status: StatusCode) -> Response {
+        respond(db, req, |_| async move {
+            let n = runs.fetch_add(1, Ordering::SeqCst) + 1;
+            (status, Json(serde_json::json!({ "run": n }))).into_response()
+        })
+        .await
+    }
+
+    async fn body(response: Response) -> String {
+        String::from_utf8(to_bytes(response.into_body(), MAX_BODY_BYTES).await.unwrap().to_vec()).unwrap()
+    }
+
+    /// Gives a released claim's spawned cleanup time to run
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    #[tokio::test]
+    async fn test_runs_once_per_key() {
+        let path = std::env::temp_dir().join(format!("spawn-idempotency-{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::connect(&format!("sqlite:{}?mode=rwc", path.display())).await.unwrap());
+        let runs = AtomicU32::new(0);
+
+        let first = send(&db, &runs, request("a", None, "{}"), StatusCode::CREATED).await;
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert_eq!(body(first).await, r#"{"run":1}"#);
+
+        let replayed = send(&db, &runs, request("a", None, "{}"), StatusCode::CREATED).await;
+        assert_eq!(replayed.status(), StatusCode::CREATED);
+        assert_eq!(replayed.headers()[REPLAYED], "true");
+        assert_eq!(body(replayed).await, r#"{"run":1}"#);
+
+        let mismatch = send(&db, &runs, request("a", None, r#"{"goal":"other"}"#), StatusCode::CREATED).await;
+        assert_eq!(mismatch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let mut other_query = request("a", None, "{}");
+        *other_query.uri_mut() = "/api/missions?start=false".parse().unwrap();
+        let mismatch = send(&db, &runs, other_query, StatusCode::CREATED).await;
+        assert_eq!(mismatch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // Another client's key of the same name is its own
+        let other_client = send(&db, &runs, request("a", Some("Bearer other"), "{}"), StatusCode::CREATED).await;
+        assert_eq!(body(other_client).await, r#"{"run":2}"#);
+
+        let hash = format!("{:x}", Sha256::digest(b"POST/api/missions{}"));
+        let lease = chrono::Duration::minutes(CLAIM_LEASE_MINUTES);
+        db.begin_idempotent("busy", &hash, chrono::Duration::hours(1), lease).await.unwrap();
+        let in_progress = send(&db, &runs, request("busy", None, "{}"), StatusCode::CREATED).await;
+        assert_eq!(in_progress.status(), StatusCode::CONFLICT);
+        // A claim past its lease is taken over
+        db.begin_idempotent("stale", &hash, chrono::Duration::hours(1), lease).await.unwrap();
+        let reclaimed = db.begin_idempotent("stale", &hash, chrono::Duration::hours(1), chrono::Duration::zero()).await.unwrap();
+        assert!(matches!(reclaimed, IdempotentRequest::New));
+
+        let failed = send(&db, &runs, request("b", None, "{}"), StatusCode::INTERNAL_SERVER_ERROR).await;
+        assert_eq!(failed.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        settle().await;
+        let retried = send(&db, &runs, request("b", None, "{}"), StatusCode::CREATED).await;
+        assert_eq!(body(retried).await, r#"{"run":4}"#);
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_releases_key_when_client_disconnects() {
+        let path = std::env::temp_dir().join(format!("spawn-idempotency-{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::connect(&format!("sqlite:{}?mode=rwc", path.display())).await.unwrap());
+        let runs = AtomicU32::new(0);
+
+        // The request future is dropped mid-handler, as axum does on disconnect
+        let hung = respond(&db, request("c", None, "{}"), |_| std::future::pending());
+        assert!(tokio::time::timeout(Duration::from_millis(100), hung).await.is_err());
+        settle().await;
+
+        let retried = send(&db, &runs, request("c", None, "{}"), StatusCode::CREATED).await;
+        assert_eq!(retried.status(), StatusCode::CREATED);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,132 @@
+//! Lint endpoints
+//!
+//! Runs clippy/eslint/ruff, stores diagnostics for editor squiggles, and can
+//! turn them into a fix-lint mission for the agent.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use spawn_agents::tools::lint::{self, Linter};
+use spawn_core::{Diagnostic, Mission, MissionContext, Severity};
+
+use crate::{error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct LintRequest {
+    pub language: Option<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintResponse {
+    pub linters: Vec<Linter>,
+    pub total: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Run linters and store their diagnostics
+pub async fn run_lint(
+    State(state): State<AppState>,
+    Json(req): Json<LintRequest>,
+) -> impl IntoResponse {
+    let linters = match req.language.as_deref() {
+        Some(lang) => match Linter::for_language(lang) {
+            Some(l) => vec![l],
+            None => {
+                return error(StatusCode::BAD_REQUEST, format!("No linter for language '{}'", lang));
+            }
+        },
+        None => Linter::detect(&state.workspace_root),
+    };
+
+    let mut diagnostics = Vec::new();
+    for linter in &linters {
+        let found = match lint::run(&state.workspace_root, *linter, &req.paths).await {
+            Ok(d) => d,
+            Err(e) => {
+                return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+            }
+        };
+        if let Err(e) = state.db.replace_diagnostics(linter.name(), &req.paths, &found).await {
+            tracing::error!(error = %e, "Failed to store diagnostics");
+        }
+        diagnostics.extend(found);
+    }
+
+    (StatusCode::OK, Json(LintResponse {
+        linters,
+        total: diagnostics.len(),
+        diagnostics,
+    })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiagnosticsQuery {
+    pub file: Option<String>,
+}
+
+/// Stored diagnostics (editor squiggles), optionally for a single file
+pub async fn diagnostics(
+    State(state): State<AppState>,
+    Query(query): Query<DiagnosticsQuery>,
+) -> impl IntoResponse {
+    match state.db.list_diagnostics(query.file.as_deref()).await {
+        Ok(diagnostics) => (StatusCode::OK, Json(serde_json::json!({
+            "total": diagnostics.len(),
+            "diagnostics": diagnostics,
+        }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FixLintRequest {
+    pub file: Option<String>,
+    /// Include warnings, not just errors
+    #[serde(default = "default_include_warnings")]
+    pub include_warnings: bool,
+}
+
+fn default_include_warnings() -> bool {
+    true
+}
+
+/// Start a mission that fixes the stored diagnostics
+pub async fn fix(
+    State(state): State<AppState>,
+    Json(req): Json<FixLintRequest>,
+) -> impl IntoResponse {
+    let diagnostics: Vec<Diagnostic> = match state.db.list_diagnostics(req.file.as_deref()).await {
+        Ok(d) => d.into_iter()
+            .filter(|d| d.severity == Severity::Error || (req.include_warnings && d.severity == Severity::Warning))
+            .collect(),
+        Err(e) => {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+
+    if diagnostics.is_empty() {
+        return (StatusCode::OK, Json(serde_json::json!({
+            "mission_id": null,
+            "message": "No diagnostics to fix",
+        }))).into_response();
+    }
+
+    let mission = Mission::new(format!(
+        "Fix the following lint diagnostics, then run the lint tool again to confirm they are gone:\n{}",
+        lint::summarize(&diagnostics, 50)
+    )).with_context(MissionContext::new().with("kind", "fix_lint").with("diagnostics", diagnostics.len()));
+    let mission_id = mission.id.clone();
+
+    state.spawn_mission(mission).await;
+
+    (StatusCode::ACCEPTED, Json(serde_json::json!({
+        "mission_id": mission_id,
+        "diagnostics": diagnostics.len(),
+    }))).into_response()
+}
@@ -0,0 +1,115 @@
+//! Locale settings
+//!
+//! Workspaces and users can each set the language agents prompt in and
+//! error messages come back in. A request's own `locale` wins, then the
+//! user's setting, then the workspace's, then the server's `locale`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::i18n;
+use spawn_core::{normalize_locale, LocaleScope};
+
+use crate::workspaces::DEFAULT_WORKSPACE;
+use crate::{error, spawn_error, AppState};
+
+/// Who a request is for, to pick its language
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LocaleRequest {
+    /// Language for this request only, as a BCP 47 tag
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Registered workspace name (default: the server's workspace)
+    #[serde(default)]
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLocaleRequest {
+    pub locale: String,
+}
+
+/// The locale for `req`; an error message if its own `locale` isn't a
+/// language tag. Stored settings that can't be read are skipped.
+pub async fn resolve(state: &AppState, req: &LocaleRequest) -> Result<String, String> {
+    if let Some(tag) = &req.locale {
+        return normalize_locale(tag).ok_or_else(|| format!("'{}' is not a language tag like en or pt-BR", tag));
+    }
+    let workspace = req.workspace.as_deref().unwrap_or(DEFAULT_WORKSPACE);
+    let candidates = req.user_id.as_deref().map(|user| (LocaleScope::User, user))
+        .into_iter()
+        .chain([(LocaleScope::Workspace, workspace)]);
+    for (scope, id) in candidates {
+        match state.db.get_locale(scope, id).await {
+            Ok(Some(locale)) => return Ok(locale),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, scope = scope.as_str(), id, "Failed to read locale setting"),
+        }
+    }
+    Ok(state.locale.clone())
+}
+
+/// Name of the workspace at `root`: a registered one, else the default
+pub async fn workspace_name(state: &AppState, root: Option<&std::path::Path>) -> String {
+    let Some(root) = root.filter(|r| *r != state.workspace_root) else {
+        return DEFAULT_WORKSPACE.to_string();
+    };
+    state.db.list_workspaces().await.unwrap_or_default()
+        .into_iter()
+        .find(|w| std::path::Path::new(&w.root) == root)
+        .map(|w| w.name)
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+/// `GET /api/locales` - the default, the languages with a catalog and
+/// every stored setting
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    match state.db.list_locales().await {
+        Ok(settings) => (StatusCode::OK, Json(serde_json::json!({
+            "default": state.locale,
+            "supported": i18n::supported(),
+            "settings": settings,
+        }))).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
+
+/// `PUT /api/locales/:scope/:id` - set a workspace's or user's locale
+pub async fn set(
+    State(state): State<AppState>,
+    Path((scope, id)): Path<(LocaleScope, String)>,
+    Json(req): Json<SetLocaleRequest>,
+) -> impl IntoResponse {
+    let Some(locale) = normalize_locale(&req.locale) else {
+        return error(StatusCode::BAD_REQUEST, format!("'{}' is not a language tag like en or pt-BR", req.locale));
+    };
+    if scope == LocaleScope::Workspace && id != DEFAULT_WORKSPACE {
+        match state.db.list_workspaces().await {
+            Ok(workspaces) if workspaces.iter().any(|w| w.name == id) => {}
+            Ok(_) => return error(StatusCode::NOT_FOUND, format!("Workspace '{}' not found", id)),
+            Err(e) => return spawn_error(&e, serde_json::json!({})),
+        }
+    }
+    match state.db.set_locale(scope, &id, &locale).await {
+        Ok(setting) => (StatusCode::OK, Json(setting)).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
+
+/// `DELETE /api/locales/:scope/:id` - fall back to the next setting
+pub async fn remove(
+    State(state): State<AppState>,
+    Path((scope, id)): Path<(LocaleScope, String)>,
+) -> impl IntoResponse {
+    match state.db.remove_locale(scope, &id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, format!("No locale set for {} '{}'", scope.as_str(), id)),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
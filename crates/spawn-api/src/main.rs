@@ -7,20 +7,64 @@ mod files;
 mod admin;
 mod architect;
 mod search;
+mod deps;
+mod processes;
+mod preview;
+mod lint;
+mod coverage;
+mod npm;
+mod voice;
+mod missions;
+mod docs;
+mod workspaces;
+mod agents;
+mod privacy;
+mod events;
+mod transcripts;
+mod ws_protocol;
+mod resume;
+mod cluster;
+mod reservations;
+mod health;
+mod idempotency;
+mod capabilities;
+mod locales;
+mod services;
+mod models;
 
 use axum::{
     body::Body,
-    extract::State,
+    extract::{DefaultBodyLimit, Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{any, delete, get, post, put},
     Json, Router,
 };
-use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use spawn_agents::{Database, Orchestrator};
-use spawn_ai::OpenRouterClient;
-use spawn_core::{ChatMessage, Config, LlmClient, Mission};
+use resume::{ResumeSessions, RESUME_GRACE};
+use spawn_agents::activity::ActivityCache;
+use spawn_agents::agents::StaticAgentRegistry;
+use spawn_agents::duplicates::{self, SimilarMission};
+use spawn_agents::i18n;
+use spawn_agents::rerank::{CrossEncoderReranker, LlmReranker, Reranker, Rerankers};
+use spawn_agents::tools::{CargoTool, ClipboardTool, CoverageTool, DepsTool, ImageGenerateTool, LintTool, NpmScriptTool, ProcessTool, ToolRegistry};
+use spawn_agents::locks::{git_resource, LockGuard};
+use spawn_agents::log_store;
+use spawn_agents::scratch::ScratchDirs;
+use spawn_agents::estimate::CostEstimate;
+use spawn_agents::templates::{self, Template, WorkspacePolicy};
+use spawn_agents::{Database, FileReservations, MissionTerminals, Orchestrator, ProcessManager, VectorMemory, WorkspaceLocks, WorkspaceSnapshots};
+use spawn_ai::{
+    AwsCredentials, AzureOpenAiClient, BedrockClient, CachingClient, CircuitBreakerConfig, CostTracker, GeminiClient, MeteredClient, ModelRouter, OllamaClient, OpenAiSpeechClient, OpenRouterClient, PricingTable,
+    PromptTemplates, ProviderManager, ResponseCache, WhisperClient,
+};
+use spawn_core::{
+    Budget, CancellationToken, ChatOptions, Config, Conversation, EmbeddingClient, FormatConfig, LlmClient, LogEntry, LogStore, Mission, MissionContext, MissionId, MissionPriority,
+    Service, ServiceAvailability, SessionKind, SpawnError, SpeechToText, TaskKind, TextToSpeech,
+};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
@@ -28,6 +72,11 @@ use tower_http::trace::TraceLayer;
 use tower_http::services::ServeDir;
 use tracing::{info, Level};
 
+/// How often conversation memory is pruned
+const RETENTION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+/// How often detached WebSocket sessions are checked for expiry
+const RESUME_REAP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
 // ============================================
 // App State
 // ============================================
@@ -36,13 +85,148 @@ use tracing::{info, Level};
 pub struct AppState {
     pub orchestrator: Arc<Orchestrator>,
     pub db: Arc<Database>,
+    /// Mission logs, in the database or wherever `log_storage` points
+    pub logs: Arc<dyn LogStore>,
     pub workspace_root: std::path::PathBuf,
+    pub processes: Arc<ProcessManager>,
+    pub snapshots: Arc<WorkspaceSnapshots>,
+    /// Advisory locks shared with missions and other nodes
+    pub locks: Arc<WorkspaceLocks>,
+    /// Files with unsaved editor changes or pending mission changes
+    pub reservations: Arc<FileReservations>,
+    pub stt: Option<Arc<dyn SpeechToText>>,
+    pub tts: Option<Arc<dyn TextToSpeech>>,
+    pub vector_memory: Option<Arc<VectorMemory>>,
+    pub rerankers: Rerankers,
+    /// Git activity per workspace, for code search ranking
+    pub activity: Arc<ActivityCache>,
+    /// Client and model for `/api/chat` and voice chat
+    pub llm: Arc<dyn LlmClient>,
+    /// The providers behind `llm`, with their health
+    pub providers: Arc<ProviderManager>,
+    /// For its model catalog, whether or not it serves chat
+    pub openrouter: Arc<OpenRouterClient>,
+    /// Spend per mission, per model and on chat (scope `chat`)
+    pub costs: Arc<CostTracker>,
+    pub chat_model: String,
+    /// Generation parameters for chat, under the ones a request sets
+    pub chat_options: ChatOptions,
+    /// Language for workspaces and users without a locale setting
+    pub locale: String,
+    /// One permit per allowed concurrent terminal connection
+    pub terminal_slots: Arc<tokio::sync::Semaphore>,
+    /// Shells and event subscriptions awaiting or serving a resumable client
+    pub terminal_sessions: Arc<ResumeSessions<terminal::ShellSession>>,
+    pub event_sessions: Arc<ResumeSessions<events::EventSession>>,
+    /// Set in cluster mode; records which node owns each session
+    pub cluster: Option<Arc<cluster::Cluster>>,
+    /// Subsystems reported by `/health` and the admin status page
+    pub health: Arc<health::HealthChecks>,
+    /// Optional services currently up, shared with the orchestrator
+    pub services: Arc<ServiceAvailability>,
+    /// Formatting of files agents write, before workspace overrides
+    pub format: FormatConfig,
+}
+
+impl AppState {
+    /// Take a workspace lock for the rest of an API request, or the response
+    /// to give up with
+    pub async fn lock(&self, resource: String, purpose: &str) -> Result<LockGuard, Response> {
+        let holder = format!("api:{}", uuid::Uuid::new_v4().simple());
+        self.locks.acquire(&resource, &holder, purpose).await
+            .map_err(|e| spawn_error(&e, serde_json::json!({})))
+    }
+    
+    /// The knowledge base's vector store; `SpawnError::ServiceUnavailable`
+    /// when it isn't configured or is down
+    pub fn vector_memory(&self) -> spawn_core::Result<Arc<VectorMemory>> {
+        self.services.require(Service::VectorStore)?;
+        self.vector_memory.clone().ok_or(SpawnError::ServiceUnavailable(Service::VectorStore))
+    }
+    
+    /// Go ahead with a write to `path` by `writer` (the editor session, or
+    /// `api` when unnamed), or the 409 to give up with when someone else has
+    /// the file reserved and `force` isn't set
+    pub async fn check_reservation(&self, path: &str, writer: Option<&str>, force: bool) -> Result<(), Response> {
+        self.reservations.check(path, writer.unwrap_or("api"), force).await.map_err(|e| match &e {
+            SpawnError::Reserved { path, owner } => spawn_error(&e, serde_json::json!({ "path": path, "owner": owner })),
+            _ => spawn_error(&e, serde_json::json!({})),
+        })
+    }
+    
+    /// Run a mission in the background, under its workspace's policy. This
+    /// node owns it until it ends, so in cluster mode ownership is recorded
+    /// before the caller hands out its id.
+    pub async fn spawn_mission(&self, mut mission: Mission) {
+        let mission_id = mission.id.clone();
+        let workspace = mission.context.workspace.clone().unwrap_or_else(|| self.workspace_root.clone());
+        match WorkspacePolicy::load(&workspace) {
+            Ok(policy) => mission.context.deny = mission.context.deny.union(&policy.deny),
+            Err(e) => tracing::warn!(mission_id = %mission_id, error = %e, "Ignoring unreadable workspace policy"),
+        }
+        if let Some(cluster) = &self.cluster {
+            cluster.claim(SessionKind::Mission, &mission_id).await;
+        }
+
+        let orchestrator = self.orchestrator.clone();
+        let cluster = self.cluster.clone();
+        tokio::spawn(async move {
+            match orchestrator.run_mission(mission).await {
+                Ok(()) | Err(SpawnError::Cancelled) => {}
+                Err(e) => tracing::error!(mission_id = %mission_id, error = %e, "Mission failed"),
+            }
+            if let Some(cluster) = cluster {
+                cluster.release(SessionKind::Mission, &mission_id).await;
+            }
+        });
+    }
 }
 
 // ============================================
 // Main
 // ============================================
 
+/// `spawn init --template <path|url> [--overwrite] [dir]`: set a workspace
+/// up from a template without starting the server
+async fn init_workspace(args: &[String]) -> anyhow::Result<()> {
+    let mut template = None;
+    let mut overwrite = false;
+    let mut dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--template" | "-t" => template = args.next(),
+            "--overwrite" => overwrite = true,
+            _ if dir.is_none() && !arg.starts_with('-') => dir = Some(arg),
+            _ => anyhow::bail!("Unexpected argument '{}'", arg),
+        }
+    }
+    let Some(source) = template else {
+        anyhow::bail!("Usage: spawn init --template <path|url> [--overwrite] [dir]");
+    };
+    let root = dir.map(std::path::PathBuf::from).unwrap_or(std::env::current_dir()?);
+
+    let template = Template::fetch(source).await?;
+    let applied = template.apply(&root, overwrite).await?;
+    println!("Applied template '{}' to {}", template.manifest.name, root.display());
+    for path in &applied.written {
+        println!("  wrote   {}", path);
+    }
+    for path in &applied.skipped {
+        println!("  kept    {} (exists; --overwrite replaces it)", path);
+    }
+    if !template.manifest.missions.is_empty() {
+        println!("Suggested missions (POST /api/workspaces/init with start_missions runs them):");
+        for mission in &template.manifest.missions {
+            println!("  - {}", mission.goal);
+        }
+    }
+    if !template.manifest.policy.deny.is_empty() {
+        println!("Missions in this workspace may not use: {} (see {})", template.manifest.policy.deny, templates::POLICY_FILE);
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Load .env
@@ -53,6 +237,11 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(Level::DEBUG)
         .init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("init") {
+        return init_workspace(&args[1..]).await;
+    }
+
     info!("🚀 Starting Spawn API");
 
     // Load config
@@ -63,50 +252,448 @@ async fn main() -> anyhow::Result<()> {
     info!("📦 Database connected");
 
     // Init LLM client
-    let llm = Arc::new(OpenRouterClient::new(&config.openrouter_api_key));
+    let openrouter_config = config.providers.get("openrouter").cloned().unwrap_or_default();
+    let openrouter = Arc::new(
+        OpenRouterClient::new(&config.openrouter_api_key)
+            .with_retry_policy(openrouter_config.retry_policy())
+            .with_timeouts(openrouter_config.timeouts()),
+    );
+    let embedder: Arc<dyn EmbeddingClient> = openrouter.clone();
+    let ollama = config.providers.get("ollama").map(|provider| {
+        let mut client = OllamaClient::new().with_timeouts(provider.timeouts());
+        if let Some(url) = &provider.base_url {
+            client = client.with_base_url(url);
+        }
+        if let Some(keep_alive) = &provider.keep_alive {
+            client = client.with_keep_alive(keep_alive);
+        }
+        Arc::new(client) as Arc<dyn LlmClient>
+    });
+    // Config validation guarantees these are set
+    let azure = config.providers.get("azure").map(|provider| {
+        let mut client = AzureOpenAiClient::new(
+            provider.base_url.clone().unwrap_or_default(),
+            provider.api_key.clone().unwrap_or_default(),
+            provider.deployment.clone().unwrap_or_default(),
+        )
+        .with_timeouts(provider.timeouts());
+        if let Some(version) = &provider.api_version {
+            client = client.with_api_version(version);
+        }
+        Arc::new(client) as Arc<dyn LlmClient>
+    });
+    let gemini = config.providers.get("gemini").map(|provider| {
+        let mut client = GeminiClient::new(provider.api_key.clone().unwrap_or_default()).with_timeouts(provider.timeouts());
+        if let Some(url) = &provider.base_url {
+            client = client.with_base_url(url);
+        }
+        for (category, threshold) in &provider.safety_settings {
+            client = client.with_safety_setting(category, threshold);
+        }
+        Arc::new(client) as Arc<dyn LlmClient>
+    });
+    let bedrock = match config.providers.get("bedrock") {
+        Some(provider) => {
+            let credentials = match (&provider.access_key_id, &provider.secret_access_key) {
+                (Some(access_key_id), Some(secret_access_key)) => Some(AwsCredentials {
+                    access_key_id: access_key_id.clone(),
+                    secret_access_key: secret_access_key.clone(),
+                    session_token: provider.session_token.clone(),
+                }),
+                _ => AwsCredentials::from_env(),
+            };
+            let credentials = credentials.ok_or_else(|| SpawnError::Config(
+                "providers.bedrock: no credentials; set access_key_id and secret_access_key, or AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY".into(),
+            ))?;
+            let mut client = BedrockClient::new(provider.region.clone().unwrap_or_default(), credentials)
+                .with_timeouts(provider.timeouts());
+            if let Some(url) = &provider.base_url {
+                client = client.with_endpoint(url);
+            }
+            Some(Arc::new(client) as Arc<dyn LlmClient>)
+        }
+        None => None,
+    };
+    // Local models first, then Azure, Bedrock and Gemini; OpenRouter, if it
+    // has a key, backs them up
+    let openrouter_fallback = (!config.openrouter_api_key.is_empty()).then(|| openrouter.clone() as Arc<dyn LlmClient>);
+    let openrouter_in_chain = openrouter_fallback.is_some();
+    let mut clients = ollama.into_iter().chain(azure).chain(bedrock).chain(gemini).chain(openrouter_fallback);
+    let primary = clients.next().expect("config requires at least one LLM provider");
+    let mut providers = clients.fold(ProviderManager::new(primary), ProviderManager::with_fallback)
+        .with_probe_timeout(std::time::Duration::from_secs(config.provider_health.timeout_secs))
+        .with_circuit_breaker(CircuitBreakerConfig {
+            failure_rate: f64::from(config.provider_health.failure_rate_percent) / 100.0,
+            min_requests: config.provider_health.min_requests as usize,
+            window: std::time::Duration::from_secs(config.provider_health.window_secs),
+            open_for: std::time::Duration::from_secs(config.provider_health.open_secs),
+        });
+    if let Some(model) = &config.models.chat {
+        providers = providers.with_probe_model(model);
+    }
+    for (name, provider) in &config.providers {
+        providers = providers.with_rate_limit(name, provider.rate_limit());
+    }
+    if !config.models.routes.is_empty() {
+        info!(rules = config.models.routes.len(), "🧭 Model routing rules loaded");
+        providers = providers.with_router(ModelRouter::new(config.models.routes.clone()));
+    }
+    let providers = Arc::new(providers);
+    if config.provider_health.interval_secs > 0 {
+        providers.spawn_health_checks(std::time::Duration::from_secs(config.provider_health.interval_secs));
+    }
+    let mut llm: Arc<dyn LlmClient> = providers.clone();
+    if config.response_cache.enabled {
+        let mut cache = ResponseCache::new(config.response_cache.capacity);
+        if config.response_cache.persist {
+            cache = cache.with_store(db.clone());
+        }
+        llm = Arc::new(CachingClient::new(llm, cache));
+        info!(capacity = config.response_cache.capacity, persist = config.response_cache.persist, "🗃️ Response cache enabled");
+    }
+    let costs = Arc::new(CostTracker::new(PricingTable::new(config.pricing.clone())));
     info!("🤖 LLM client initialized");
 
-    // Init orchestrator
-    let orchestrator = Arc::new(Orchestrator::new(db.clone(), llm));
+    // Optional speech-to-text for voice input
+    let stt: Option<Arc<dyn SpeechToText>> = config.stt.as_ref()
+        .map(|c| Arc::new(WhisperClient::new(c)) as Arc<dyn SpeechToText>);
+    if stt.is_some() {
+        info!("🎙️ Speech-to-text enabled");
+    }
+    let tts: Option<Arc<dyn TextToSpeech>> = config.tts.as_ref()
+        .map(|c| Arc::new(OpenAiSpeechClient::new(c)) as Arc<dyn TextToSpeech>);
+    if tts.is_some() {
+        info!("🔊 Text-to-speech enabled");
+    }
 
     // Workspace root for file operations
-    let workspace_root = std::env::var("WORKSPACE_ROOT")
-        .map(std::path::PathBuf::from)
-        .unwrap_or_else(|_| std::env::current_dir().unwrap());
+    let workspace_root = config.workspace_root.clone()
+        .unwrap_or_else(|| std::env::current_dir().unwrap());
     
     info!("📂 Workspace: {:?}", workspace_root);
 
+    // Managed dev servers / watchers
+    let processes = Arc::new(ProcessManager::new(workspace_root.clone()));
+
+    // Init orchestrator
+    let mut tools = ToolRegistry::new();
+    tools.register(Box::new(DepsTool::new(workspace_root.clone())));
+    tools.register(Box::new(ProcessTool::new(processes.clone())));
+    tools.register(Box::new(NpmScriptTool::new(workspace_root.clone(), processes.clone())));
+    tools.register(Box::new(LintTool::new(workspace_root.clone()).with_database(db.clone())));
+    tools.register(Box::new(CargoTool::new(workspace_root.clone()).with_database(db.clone())));
+    tools.register(Box::new(CoverageTool::new(workspace_root.clone(), db.clone())));
+    tools.register(Box::new(ImageGenerateTool::new(openrouter.clone(), workspace_root.clone())));
+    tools.register(Box::new(ClipboardTool::new(architect::TERMINAL_API)));
+    let snapshots = Arc::new(WorkspaceSnapshots::new(workspace_root.clone()));
+    let locks = Arc::new(WorkspaceLocks::new(db.clone(), config.locks.clone()));
+    let reservations = Arc::new(FileReservations::new(db.clone()));
+
+    // Optional pgvector store for the knowledge base
+    let vector_memory = match std::env::var("POSTGRES_URL") {
+        Ok(url) => match VectorMemory::connect(&url, embedder).await {
+            Ok(vm) => {
+                info!("📚 Knowledge base enabled");
+                let mut vm = vm.with_retention(config.retention.clone());
+                if let Some(model) = providers.router().route(TaskKind::Embedding, 0.0) {
+                    vm = vm.with_embedding_model(model);
+                }
+                Some(Arc::new(vm))
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Vector store unavailable; knowledge base retrieval disabled");
+                None
+            }
+        },
+        Err(_) => None,
+    };
+
+    // Scheduled conversation memory cleanup
+    if let Some(vm) = vector_memory.clone().filter(|_| config.retention.prunes()) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = vm.prune().await {
+                    tracing::warn!(error = %e, "Retention cleanup failed");
+                }
+            }
+        });
+        info!("🧹 Retention cleanup scheduled");
+    }
+
+    // Search rerankers: an LLM judge always, a cross-encoder when RERANK_URL is set
+    let mut llm_reranker = LlmReranker::new(llm.clone());
+    if let Some(model) = providers.router().route(TaskKind::Rerank, 0.0).or(config.models.rerank.as_deref()) {
+        llm_reranker = llm_reranker.with_model(model);
+    }
+    let cross_encoder = std::env::var("RERANK_URL").ok().map(|url| {
+        let mut reranker = CrossEncoderReranker::new(url);
+        if let Ok(key) = std::env::var("RERANK_API_KEY") {
+            reranker = reranker.with_api_key(key);
+        }
+        Arc::new(reranker) as Arc<dyn Reranker>
+    });
+    let rerankers = Rerankers {
+        llm: Some(Arc::new(llm_reranker)),
+        cross_encoder: cross_encoder.clone(),
+    };
+
+    // Agent personas: built-ins plus any defined in AGENTS_FILE
+    let mut agents = StaticAgentRegistry::builtin();
+    if let Ok(path) = std::env::var("AGENTS_FILE") {
+        match agents.load_file(std::path::Path::new(&path)) {
+            Ok(count) => info!("🎭 Loaded {} agents from {}", count, path),
+            Err(e) => tracing::warn!(error = %e, "Failed to load agents file"),
+        }
+    }
+
+    let logs = log_store::from_config(&config.log_storage, db.clone()).await?;
+    info!(backend = logs.backend_name(), "📜 Mission log storage");
+
+    // Verbose step logs are dropped sooner than the concise ones
+    if let Some(days) = config.retention.verbose_log_days {
+        let logs = logs.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                match logs.prune_verbose(chrono::Utc::now() - chrono::Duration::days(days as i64)).await {
+                    Ok(0) => {}
+                    Ok(trimmed) => info!(trimmed, "Pruned verbose mission logs"),
+                    Err(e) => tracing::warn!(error = %e, "Verbose log cleanup failed"),
+                }
+            }
+        });
+    }
+
+    // Per-mission $MISSION_TMP directories, removed once past retention
+    let scratch = Arc::new(ScratchDirs::new(&config.mission_tmp));
+    {
+        let scratch = scratch.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = scratch.prune().await {
+                    tracing::warn!(error = %e, "Mission scratch cleanup failed");
+                }
+            }
+        });
+    }
+
+    let terminals = Arc::new(MissionTerminals::new(architect::TERMINAL_API));
+    let services = Arc::new(ServiceAvailability::new());
+    // Unconfigured rather than failing; nothing to announce
+    services.set(Service::VectorStore, vector_memory.is_some());
+    let probes = services::ServiceProbes {
+        vector_store: vector_memory.clone().map(|vm| vm as Arc<dyn spawn_core::HealthCheck>),
+        terminal: terminals.clone(),
+        providers: providers.clone(),
+    };
+    probes.refresh(&services).await;
+    probes.spawn(services.clone());
+    let mut orchestrator = Orchestrator::new(db.clone(), llm.clone())
+        .with_log_store(logs.clone())
+        .with_tools(tools)
+        .with_snapshots(snapshots.clone())
+        .with_workspace(workspace_root.clone())
+        .with_locks(locks.clone())
+        .with_reservations(reservations.clone())
+        .with_terminals(terminals.clone())
+        .with_scratch_dirs(scratch)
+        .with_services(services.clone())
+        .with_costs(costs.clone())
+        .with_router(providers.router().clone())
+        .with_locale(&config.locale)
+        .with_agents(Arc::new(agents))
+        .with_scheduler(config.scheduler.clone())
+        .with_context_config(config.context.clone());
+    if let Some(model) = &config.models.mission {
+        orchestrator = orchestrator.with_model(model);
+    }
+    if let Some(model) = &config.models.postmortem {
+        orchestrator = orchestrator.with_postmortem_model(model);
+    }
+    let prompts = Arc::new(PromptTemplates::load(admin::PROMPTS_FILE)?);
+    admin::apply_rules(&prompts, &admin::load_config());
+    orchestrator = orchestrator.with_prompt_templates(prompts);
+    if let Some(secs) = config.models.request_timeout_secs {
+        orchestrator = orchestrator.with_request_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(vm) = &vector_memory {
+        orchestrator = orchestrator.with_vector_memory(vm.clone(), workspace_root.display().to_string());
+    }
+    if let Some(reranker) = cross_encoder {
+        orchestrator = orchestrator.with_reranker(reranker);
+    }
+    let orchestrator = Arc::new(orchestrator);
+
+    // Cluster mode: share sessions with other nodes on the same database
+    let cluster = match &config.cluster {
+        Some(cluster_config) => {
+            let cluster = Arc::new(cluster::Cluster::new(cluster_config, db.clone()));
+            cluster.join().await?;
+            info!(node = %cluster.node_id, url = %cluster.url, "🛰️ Cluster mode enabled");
+            Some(cluster)
+        }
+        None => None,
+    };
+
+    let sandbox_url = std::env::var("SANDBOX_ENDPOINT").unwrap_or_else(|_| "http://localhost:3080".to_string());
+    let mut health = health::HealthChecks::new()
+        .with(db.clone())
+        .with(terminals)
+        .with(Arc::new(health::HttpHealthCheck::new("sandbox", &sandbox_url)));
+    // Without a key OpenRouter serves no requests, so it can't be unhealthy
+    if openrouter_in_chain {
+        health = health.with(openrouter.clone());
+    }
+    if let Some(vm) = &vector_memory {
+        health = health.with(vm.clone());
+    }
+
     // Build state
+    let chat_model = providers.router().route(TaskKind::Chat, 0.0)
+        .or(config.models.chat.as_deref())
+        .unwrap_or(CHAT_MODEL)
+        .to_string();
+    let mut chat_options = chat_options();
+    if let Some(secs) = config.models.request_timeout_secs {
+        chat_options = chat_options.with_timeout_secs(secs);
+    }
     let state = AppState {
         orchestrator,
         db,
+        logs,
         workspace_root,
+        processes,
+        snapshots,
+        locks,
+        reservations,
+        stt,
+        tts,
+        vector_memory,
+        rerankers,
+        activity: Arc::new(ActivityCache::new()),
+        llm: Arc::new(MeteredClient::new(llm, costs.clone(), "chat")),
+        providers,
+        openrouter,
+        costs,
+        chat_model,
+        chat_options,
+        locale: config.locale.clone(),
+        terminal_slots: Arc::new(tokio::sync::Semaphore::new(config.terminal.max_sessions)),
+        terminal_sessions: Arc::new(ResumeSessions::new()),
+        event_sessions: Arc::new(ResumeSessions::new()),
+        cluster,
+        health: Arc::new(health),
+        services,
+        format: config.format.clone(),
     };
+    search::spawn_refresh_on_change(state.clone());
+
+    // Release WebSocket sessions nobody came back for
+    tokio::spawn({
+        let (terminals, events) = (state.terminal_sessions.clone(), state.event_sessions.clone());
+        let cluster = state.cluster.clone();
+        async move {
+            let mut interval = tokio::time::interval(RESUME_REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let reaped = [
+                    (SessionKind::Terminal, terminals.reap(RESUME_GRACE)),
+                    (SessionKind::Events, events.reap(RESUME_GRACE)),
+                ];
+                let count: usize = reaped.iter().map(|(_, tokens)| tokens.len()).sum();
+                if count > 0 {
+                    tracing::debug!(reaped = count, "Expired detached WebSocket sessions");
+                }
+                let Some(cluster) = &cluster else {
+                    continue;
+                };
+                for (kind, tokens) in &reaped {
+                    for token in tokens {
+                        cluster.release(*kind, token).await;
+                    }
+                }
+            }
+        }
+    });
 
     // Build router
     let app = Router::new()
         // Health & Info
         .route("/", get(root))
-        .route("/health", get(health))
+        .route("/health", get(health::health))
         // Terminal WebSocket
         .route("/ws/terminal", get(terminal::ws_handler))
+        .route("/ws/events", get(events::ws))
+        .route("/api/events", get(events::sse))
         // File operations
         .route("/api/files", get(files::list_files))
+        .route("/api/files/sync", post(files::sync_files))
         .route("/api/files/*path", get(files::read_file))
         .route("/api/files/*path", post(files::write_file))
+        .route("/api/reservations", get(reservations::list))
+        .route("/api/reservations", post(reservations::reserve))
+        .route("/api/reservations", delete(reservations::release))
         // Missions (agent orchestration)
         .route("/api/missions", post(create_mission))
         .route("/api/missions", get(list_missions))
+        .route("/api/capabilities", get(capabilities::get))
+        .route("/api/models", get(models::list))
+        .route("/api/agents", get(agents::list))
+        .route("/api/agents/:id", get(agents::get))
+        .route("/api/missions/validate", post(validate_mission))
+        .route("/api/missions/queue", get(missions::queue))
+        .route("/api/missions/tags", get(missions::list_tags))
+        .route("/api/missions/filters", get(missions::list_filters))
+        .route("/api/missions/filters", post(missions::create_filter))
+        .route("/api/missions/filters/:id", delete(missions::delete_filter))
+        .route("/api/missions/:id/tags", put(missions::set_tags))
+        .route("/api/missions/:id/rollback", post(rollback_mission))
+        .route("/api/missions/:id/cancel", post(missions::cancel))
+        .route("/api/missions/:id/progress", get(missions::progress))
+        .route("/api/missions/:id/postmortem", get(missions::post_mortem))
+        .route("/api/missions/:id/citations", get(missions::citations))
+        .route("/api/missions/:id/logs", get(missions::logs))
+        .route("/api/missions/:id/tasks", get(missions::list_tasks))
+        .route("/api/missions/:id/steps/:n/context", get(missions::step_context))
+        .route("/api/missions/:id/compare/:other", get(missions::compare))
+        .route("/api/missions/:id/summary.audio", get(voice::mission_summary_audio))
         // Chat (for AI assistant)
         .route("/api/chat", post(chat))
+        .route(
+            "/api/chat/voice",
+            post(voice::chat_voice).layer(DefaultBodyLimit::max(voice::MAX_AUDIO_BYTES)),
+        )
         // Chat stream proxy to sandbox (Grok with tools)
         .route("/api/chat/stream", post(chat_stream_proxy))
+        .route("/api/chat/sessions/:id", get(transcripts::get_session))
+        .route("/api/chat/sessions/:id/transcript", get(transcripts::get_transcript))
         // Admin API endpoints
+        .route("/api/cluster/nodes", get(cluster::nodes))
         .route("/api/admin/status", get(admin::get_status))
+        .route("/api/admin/stats", get(admin::get_stats))
+        .route("/api/admin/audit", get(admin::get_audit))
+        .route("/api/admin/locks", get(admin::get_locks))
+        .route("/api/admin/providers", get(admin::get_providers))
+        .route("/api/admin/costs", get(admin::get_costs))
+        .route("/api/admin/usage", get(admin::get_usage))
+        .route("/api/admin/experiments", get(admin::list_experiments))
+        .route("/api/admin/experiments", post(admin::create_experiment))
+        .route("/api/admin/experiments/:id", get(admin::get_experiment))
+        .route("/api/admin/experiments/:id/stop", post(admin::stop_experiment))
+        .route("/api/admin/experiments/:id/start", post(admin::start_experiment))
         .route("/api/admin/prompts", get(admin::get_prompts))
         .route("/api/admin/prompts", post(admin::save_prompts))
         .route("/api/admin/config", get(admin::get_config))
         .route("/api/admin/config", post(admin::save_config))
+        .merge(match config.metrics.prometheus {
+            true => Router::new().route("/metrics", get(admin::prometheus_metrics)),
+            false => Router::new(),
+        })
         // ARCHITECT API - Rust-native tool execution
         .route("/api/architect/status", get(architect::status))
         .route("/api/architect/exec", post(architect::exec_command))
@@ -117,6 +704,7 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/architect/terminal/exec", post(architect::terminal_exec))
         .route("/api/architect/terminal/buffer", get(architect::terminal_buffer))
         .route("/api/architect/terminal/list", get(architect::list_terminals))
+        .route("/api/architect/terminal/snippet", post(architect::terminal_snippet))
         .route("/api/architect/mission", post(architect::chat_to_mission))
         // Git operations
         .route("/api/architect/git/status", post(architect::git_status))
@@ -124,13 +712,56 @@ async fn main() -> anyhow::Result<()> {
         .route("/api/architect/git/commit", post(architect::git_commit))
         .route("/api/architect/git/push", post(architect::git_push))
         .route("/api/architect/git/pull", post(architect::git_pull))
+        // Knowledge base
+        .route("/api/docs", get(docs::list_docs))
+        .route(
+            "/api/docs",
+            post(docs::upload_doc).layer(DefaultBodyLimit::max(docs::MAX_DOC_BYTES)),
+        )
+        .route("/api/docs/search", get(docs::search_docs))
+        .route("/api/docs/:id", get(docs::get_doc))
+        .route("/api/docs/:id", delete(docs::delete_doc))
+
         // Semantic Search API (pgvector)
         .route("/api/search", get(search::search))
         .route("/api/search/code", get(search::search_code))
+        .route("/api/search/explain", get(search::explain))
         .route("/api/search/index", post(search::index_file))
         .route("/api/search/chat", post(search::store_chat))
         .route("/api/search/context", get(search::get_chat_context))
         .route("/api/search/status", get(search::search_status))
+        .route("/api/search/federated", get(workspaces::federated))
+        .route("/api/privacy/export", get(privacy::export))
+        .route("/api/privacy/data", delete(privacy::erase))
+
+        .route("/api/workspaces", get(workspaces::list))
+        .route("/api/workspaces", post(workspaces::register))
+        .route("/api/workspaces/init", post(workspaces::init))
+        .route("/api/workspaces/:name", delete(workspaces::remove))
+        .route("/api/locales", get(locales::list))
+        .route("/api/locales/:scope/:id", put(locales::set))
+        .route("/api/locales/:scope/:id", delete(locales::remove))
+        // Dependency analysis
+        .route("/api/deps", get(deps::analyze))
+        // Managed processes & preview proxy
+        .route("/api/processes", get(processes::list))
+        .route("/api/processes", post(processes::start))
+        .route("/api/processes/:id", get(processes::get))
+        .route("/api/processes/:id", delete(processes::remove))
+        .route("/api/processes/:id/stop", post(processes::stop))
+        .route("/api/processes/:id/restart", post(processes::restart))
+        .route("/api/processes/:id/logs", get(processes::logs))
+        .route("/preview/:port", any(preview::proxy_root))
+        .route("/preview/:port/*path", any(preview::proxy))
+        // Lint diagnostics
+        .route("/api/lint", post(lint::run_lint))
+        .route("/api/lint/diagnostics", get(lint::diagnostics))
+        .route("/api/lint/fix", post(lint::fix))
+        // Test coverage
+        .route("/api/coverage", get(coverage::get_coverage))
+        .route("/api/coverage/run", post(coverage::run_coverage))
+        .route("/api/npm/scripts", get(npm::list))
+        .route("/api/npm/run", post(npm::run))
         // Serve static UIs
         .nest_service("/admin", ServeDir::new("web/admin"))
         .nest_service("/sandbox", ServeDir::new("web/sandbox"))
@@ -138,6 +769,8 @@ async fn main() -> anyhow::Result<()> {
         // Serve static frontend (in production)
         .fallback_service(ServeDir::new("web/dist"))
         // Middleware
+        .layer(axum::middleware::from_fn_with_state(state.clone(), idempotency::idempotent))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), cluster::route_to_owner))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -165,61 +798,189 @@ async fn root() -> &'static str {
     "🧠 Spawn API v0.1.0"
 }
 
-async fn health() -> &'static str {
-    "OK"
-}
-
 // --- Missions ---
 
 #[derive(Debug, Deserialize)]
 struct CreateMissionRequest {
     goal: String,
     #[serde(default)]
-    context: serde_json::Value,
+    context: MissionContext,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Run even if a near-identical mission already completed
+    #[serde(default)]
+    force: bool,
+    /// Persona to run as (see `GET /api/agents`)
+    #[serde(default)]
+    agent: Option<String>,
+    /// `low`, `normal` (default), `high` or `urgent`
+    #[serde(default)]
+    priority: MissionPriority,
+    /// `max_steps`, `max_tokens`, `max_cost_usd`, `deadline`,
+    /// `soft_timeout_secs` and `hard_timeout_secs`, all optional
+    #[serde(default)]
+    budget: Budget,
+    /// `locale`, `user_id` and `workspace`, for the language the agent uses
+    /// when `context.locale` isn't set
+    #[serde(flatten)]
+    locale: locales::LocaleRequest,
 }
 
 #[derive(Debug, Serialize)]
 struct CreateMissionResponse {
-    mission_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mission_id: Option<MissionId>,
     status: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    similar_missions: Vec<SimilarMission>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    /// Likely steps, tokens and cost of the started mission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimate: Option<CostEstimate>,
 }
 
+/// Past missions checked for duplicates before a new one starts
+const DUPLICATE_CANDIDATES: usize = 3;
+
 async fn create_mission(
     State(state): State<AppState>,
     Json(payload): Json<CreateMissionRequest>,
 ) -> impl IntoResponse {
-    let mut mission = Mission::new(&payload.goal);
-    mission.context = payload.context;
+    let agent = match payload.agent.as_deref() {
+        Some(name) => match state.orchestrator.agents().get(name) {
+            Some(agent) => Some(agent),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(CreateMissionResponse {
+                        mission_id: None,
+                        status: "error".to_string(),
+                        similar_missions: Vec::new(),
+                        message: Some(format!("Unknown agent '{}'", name)),
+                        estimate: None,
+                    }),
+                );
+            }
+        },
+        None => None,
+    };
 
-    let mission_id = mission.id.clone();
+    if !payload.force {
+        match duplicates::find_similar(&state.db, state.logs.as_ref(), state.vector_memory.as_deref(), &payload.goal, DUPLICATE_CANDIDATES).await {
+            Ok(similar) if !similar.is_empty() => {
+                let best = &similar[0];
+                let message = format!(
+                    "This looks like mission {} which already did: {}. Send \"force\": true to run it again.",
+                    best.id,
+                    best.summary.as_deref().unwrap_or(&best.goal),
+                );
+                return (
+                    StatusCode::OK,
+                    Json(CreateMissionResponse {
+                        mission_id: None,
+                        status: "duplicate".to_string(),
+                        similar_missions: similar,
+                        message: Some(message),
+                        estimate: None,
+                    }),
+                );
+            }
+            Ok(_) => {}
+            // Detection is advisory; never block a mission on it
+            Err(e) => tracing::warn!(error = %e, "Duplicate mission check failed"),
+        }
+    }
 
-    // Spawn background task to run the mission
-    let orchestrator = state.orchestrator.clone();
-    tokio::spawn(async move {
-        if let Err(e) = orchestrator.run_mission(mission).await {
-            tracing::error!(error = %e, "Mission failed");
+    let mut context = payload.context;
+    if let Some(agent) = agent {
+        context.agent = Some(agent.id.into_string());
+    }
+    let mut locale = payload.locale;
+    locale.locale = context.locale.take().or(locale.locale);
+    if locale.workspace.is_none() {
+        locale.workspace = Some(locales::workspace_name(&state, context.workspace.as_deref()).await);
+    }
+    context.locale = match locales::resolve(&state, &locale).await {
+        Ok(locale) => Some(locale),
+        Err(message) => return (
+            StatusCode::BAD_REQUEST,
+            Json(CreateMissionResponse {
+                mission_id: None,
+                status: "error".to_string(),
+                similar_missions: Vec::new(),
+                message: Some(message),
+                estimate: None,
+            }),
+        ),
+    };
+    let mission = Mission::new(&payload.goal)
+        .with_tags(payload.tags)
+        .with_context(context)
+        .with_budget(payload.budget)
+        .with_priority(payload.priority);
+
+    let mission_id = mission.id.clone();
+    let estimate = match state.orchestrator.estimate_cost(&mission).await {
+        Ok(estimate) => Some(estimate),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to estimate mission cost");
+            None
         }
-    });
+    };
+
+    state.spawn_mission(mission).await;
 
     (
         StatusCode::ACCEPTED,
         Json(CreateMissionResponse {
-            mission_id,
+            mission_id: Some(mission_id),
             status: "started".to_string(),
+            similar_missions: Vec::new(),
+            message: None,
+            estimate,
         }),
     )
 }
 
+/// `POST /api/missions/validate` - check a mission request the way
+/// `POST /api/missions` would take it, without starting anything
+async fn validate_mission(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateMissionRequest>,
+) -> impl IntoResponse {
+    let mut context = payload.context;
+    if payload.agent.is_some() {
+        context.agent = payload.agent;
+    }
+    let mission = Mission::new(&payload.goal)
+        .with_tags(payload.tags)
+        .with_context(context)
+        .with_budget(payload.budget)
+        .with_priority(payload.priority);
+    Json(state.orchestrator.preflight(&mission, &state.workspace_root).await)
+}
+
 #[derive(Debug, Serialize)]
 struct MissionSummary {
-    id: String,
+    id: MissionId,
     goal: String,
     status: String,
+    priority: MissionPriority,
     created_at: String,
+    tags: Vec<String>,
 }
 
-async fn list_missions(State(state): State<AppState>) -> impl IntoResponse {
-    match state.db.list_missions().await {
+async fn list_missions(
+    State(state): State<AppState>,
+    Query(query): Query<missions::ListMissionsQuery>,
+) -> impl IntoResponse {
+    let filter = match missions::resolve_filter(&state, query).await {
+        Ok(f) => f,
+        Err(response) => return response,
+    };
+
+    match state.db.list_missions_filtered(&filter).await {
         Ok(missions) => {
             let summaries: Vec<MissionSummary> = missions
                 .into_iter()
@@ -227,20 +988,109 @@ async fn list_missions(State(state): State<AppState>) -> impl IntoResponse {
                     id: m.id,
                     goal: m.goal,
                     status: format!("{:?}", m.status).to_lowercase(),
+                    priority: m.priority,
                     created_at: m.created_at.to_rfc3339(),
+                    tags: m.tags,
                 })
                 .collect();
             (StatusCode::OK, Json(summaries)).into_response()
         }
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
     }
 }
 
+/// Restore the workspace to its state before the mission ran
+async fn rollback_mission(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    match state.db.get_mission(&id).await {
+        Ok(Some(m)) if !m.status.is_terminal() => {
+            return (StatusCode::CONFLICT, Json(serde_json::json!({
+                "error": "Mission is still running"
+            }))).into_response();
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "error": format!("Mission '{}' not found", id)
+            }))).into_response();
+        }
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    }
+
+    let _lock = match state.lock(git_resource(""), "rollback").await {
+        Ok(guard) => guard,
+        Err(response) => return response,
+    };
+
+    if state.snapshots.get(&id).await.is_none() {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "error": "No snapshot for this mission (it made no tool calls, or the workspace is not a git repository)"
+        }))).into_response();
+    }
+
+    match state.snapshots.restore(&id).await {
+        Ok(report) => {
+            if let Err(e) = state.logs.append(&LogEntry::new(id.as_str(), "rollback", report.commit.as_str())).await {
+                tracing::warn!(error = %e, "Failed to log rollback");
+            }
+            (StatusCode::OK, Json(report)).into_response()
+        }
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
+
+/// Plain JSON error, `{"error": message}`, for failures that aren't a `SpawnError`
+pub(crate) fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+/// JSON error for a `SpawnError`: its HTTP status plus the stable `code` and
+/// `retryable` fields, merged into `body`
+fn spawn_error(e: &SpawnError, mut body: serde_json::Value) -> Response {
+    let status = StatusCode::from_u16(e.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("error".into(), e.to_string().into());
+        fields.insert("code".into(), e.code().into());
+        fields.insert("retryable".into(), e.is_retryable().into());
+        if let Some(service) = e.missing_capability() {
+            fields.insert("missing_capability".into(), service.as_str().into());
+        }
+    }
+    let mut response = (status, Json(body)).into_response();
+    if let Some(after) = e.retry_after() {
+        response.headers_mut().insert(axum::http::header::RETRY_AFTER, after.as_secs().into());
+    }
+    response
+}
+
+/// `spawn_error` plus a readable `message` in `locale`, when the catalog
+/// has one for the error's code
+fn localized_error(e: &SpawnError, locale: &str, mut body: serde_json::Value) -> Response {
+    if let (Some(fields), Some(message)) = (body.as_object_mut(), i18n::error_message(locale, e.code())) {
+        fields.insert("message".into(), message.into());
+    }
+    spawn_error(e, body)
+}
+
 // --- Chat ---
 
 #[derive(Debug, Deserialize)]
 struct ChatRequest {
     message: String,
+    /// Screenshots etc. for vision models, as http(s) or `data:` URLs
+    #[serde(default)]
+    images: Vec<String>,
+    /// Respond with server-sent events carrying deltas as they arrive
+    #[serde(default)]
+    stream: bool,
+    /// Generation parameters; unset fields keep the chat defaults
+    #[serde(default)]
+    options: ChatOptions,
+    /// `locale`, `user_id` and `workspace`, for the reply's language
+    #[serde(flatten)]
+    locale: locales::LocaleRequest,
 }
 
 #[derive(Debug, Serialize)]
@@ -251,32 +1101,107 @@ struct ChatResponse {
 async fn chat(
     State(state): State<AppState>,
     Json(payload): Json<ChatRequest>,
-) -> impl IntoResponse {
-    use spawn_core::ChatMessage;
-
-    // Simple single-turn chat
-    let messages = vec![
-        ChatMessage::system("You are a helpful coding assistant for spawn.new. Help users build software."),
-        ChatMessage::user(&payload.message),
-    ];
-
-    // Get LLM from orchestrator (TODO: expose this better)
-    let llm = spawn_ai::OpenRouterClient::new(
-        std::env::var("OPENROUTER_API_KEY").unwrap_or_default(),
-    );
+) -> Response {
+    let locale = match locales::resolve(&state, &payload.locale).await {
+        Ok(locale) => locale,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": message }))).into_response(),
+    };
+    if let Err(e) = state.services.require(Service::Provider) {
+        return localized_error(&e, &locale, serde_json::json!({}));
+    }
+    let conversation = chat_conversation(&state, &payload.message, &payload.images, &locale);
+    let options = payload.options.or(&state.chat_options);
+    if payload.stream {
+        return chat_sse(&state, &conversation.messages, &options, &locale).await.into_response();
+    }
 
-    match llm.chat("anthropic/claude-sonnet-4-20250514", &messages).await {
+    match state.llm.chat(&state.chat_model, &conversation.messages, &options, &CancellationToken::new()).await {
         Ok(response) => (StatusCode::OK, Json(ChatResponse { response })).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ChatResponse {
-                response: format!("Error: {}", e),
-            }),
-        )
-            .into_response(),
+        // `response` kept for clients that only render that field
+        Err(e) => {
+            let message = i18n::error_message(&locale, e.code()).map_or_else(|| e.to_string(), String::from);
+            localized_error(&e, &locale, serde_json::json!({ "response": format!("Error: {}", message) }))
+        }
     }
 }
 
+/// Chat model when `models.chat` isn't configured
+const CHAT_MODEL: &str = "anthropic/claude-sonnet-4-20250514";
+
+/// Generation defaults for chat endpoints
+fn chat_options() -> ChatOptions {
+    ChatOptions::new().with_temperature(0.7)
+}
+
+/// A new single-turn chat: the system prompt in `locale` and the user's message
+fn chat_conversation(state: &AppState, message: &str, images: &[String], locale: &str) -> Conversation {
+    use spawn_core::{ChatMessage, ContentPart, MessageContent};
+
+    let content = if images.is_empty() {
+        MessageContent::from(message)
+    } else {
+        std::iter::once(ContentPart::text(message))
+            .chain(images.iter().map(ContentPart::image_url))
+            .collect::<Vec<_>>()
+            .into()
+    };
+
+    Conversation::new(uuid::Uuid::new_v4().to_string())
+        .with_model(&state.chat_model)
+        .with_message(ChatMessage::system(i18n::chat_prompt(locale)))
+        .with_message(ChatMessage::user(content))
+}
+
+/// Simple single-turn chat, shared by text and voice input.
+///
+/// Chat handlers pass a fresh cancellation token: axum drops the handler
+/// future, and with it the provider call, when the client disconnects.
+async fn chat_reply(state: &AppState, message: &str, locale: &str) -> spawn_core::Result<String> {
+    state.services.require(Service::Provider)?;
+    let conversation = chat_conversation(state, message, &[], locale);
+    state.llm.chat(&state.chat_model, &conversation.messages, &state.chat_options, &CancellationToken::new()).await
+}
+
+/// Single-turn chat as SSE: `delta` events, then `done` (or `error`)
+async fn chat_sse(
+    state: &AppState,
+    messages: &[spawn_core::ChatMessage],
+    options: &ChatOptions,
+    locale: &str,
+) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    use futures::StreamExt;
+
+    let locale = locale.to_string();
+    let error_event = move |e: SpawnError| {
+        Event::default().data(serde_json::json!({
+            "type": "error",
+            "message": i18n::error_message(&locale, e.code()).map_or_else(|| e.to_string(), String::from),
+            "error": e.to_string(),
+            "code": e.code(),
+            "retryable": e.is_retryable(),
+        }).to_string())
+    };
+
+    let events = match state.llm.chat_stream(&state.chat_model, messages, options, &CancellationToken::new()).await {
+        Ok(stream) => stream
+            .map(move |delta| Ok(match delta {
+                Ok(delta) => Event::default().data(serde_json::json!({
+                    "type": "delta",
+                    "content": delta.content,
+                    "finish_reason": delta.finish_reason,
+                }).to_string()),
+                Err(e) => error_event(e),
+            }))
+            .chain(futures::stream::once(async {
+                Ok(Event::default().data(r#"{"type":"done"}"#))
+            }))
+            .boxed(),
+        Err(e) => futures::stream::once(async move { Ok(error_event(e)) }).boxed(),
+    };
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
 // --- Chat Stream Proxy (routes to sandbox server for Grok + tools) ---
 
 #[derive(Debug, Deserialize)]
@@ -0,0 +1,283 @@
+//! Mission organization endpoints
+//!
+//! Tags on missions, tag-based listing filters, saved filter definitions, the
+//! scheduling queue, the per-step LLM context recorded by the orchestrator,
+//! step logs, citations in mission answers, and comparison of two runs.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::{plan, run_diff};
+use spawn_core::{normalize_tags, LogTier, MissionFilter, MissionId, MissionStatus};
+
+use crate::{error, AppState};
+
+/// Query string for `GET /api/missions`
+#[derive(Debug, Deserialize)]
+pub struct ListMissionsQuery {
+    pub status: Option<String>,
+    /// Comma-separated; missions must carry all of them
+    pub tags: Option<String>,
+    pub q: Option<String>,
+    /// Saved filter ID or name; explicit parameters narrow it further
+    pub filter: Option<String>,
+}
+
+/// Build the effective filter from query parameters and an optional saved filter
+pub async fn resolve_filter(state: &AppState, query: ListMissionsQuery) -> Result<MissionFilter, Response> {
+    let mut filter = match query.filter.as_deref() {
+        Some(name) => match state.db.get_saved_filter(name).await {
+            Ok(Some(saved)) => saved.filter,
+            Ok(None) => return Err(error(StatusCode::NOT_FOUND, format!("Filter '{}' not found", name))),
+            Err(e) => return Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        },
+        None => MissionFilter::default(),
+    };
+
+    if let Some(status) = query.status {
+        let parsed: MissionStatus = serde_json::from_value(serde_json::Value::String(status.clone()))
+            .map_err(|_| error(StatusCode::BAD_REQUEST, format!("Unknown status '{}'", status)))?;
+        filter.status = Some(parsed);
+    }
+    if let Some(tags) = query.tags {
+        filter.tags.extend(tags.split(',').map(String::from));
+    }
+    if let Some(q) = query.q.filter(|q| !q.is_empty()) {
+        filter.query = Some(q);
+    }
+    Ok(filter)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTagsRequest {
+    pub tags: Vec<String>,
+}
+
+/// Replace a mission's tags
+pub async fn set_tags(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+    Json(req): Json<SetTagsRequest>,
+) -> impl IntoResponse {
+    match state.db.get_mission(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+
+    match state.db.set_mission_tags(&id, &req.tags).await {
+        Ok(tags) => (StatusCode::OK, Json(serde_json::json!({
+            "mission_id": id,
+            "tags": tags,
+        }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// All tags in use, with mission counts
+pub async fn list_tags(State(state): State<AppState>) -> impl IntoResponse {
+    match state.db.list_tags().await {
+        Ok(tags) => {
+            let tags: Vec<_> = tags.into_iter()
+                .map(|(tag, count)| serde_json::json!({ "tag": tag, "count": count }))
+                .collect();
+            (StatusCode::OK, Json(serde_json::json!({ "tags": tags }))).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub async fn list_filters(State(state): State<AppState>) -> impl IntoResponse {
+    match state.db.list_saved_filters().await {
+        Ok(filters) => (StatusCode::OK, Json(serde_json::json!({ "filters": filters }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFilterRequest {
+    pub name: String,
+    #[serde(flatten)]
+    pub filter: MissionFilter,
+}
+
+/// Save a named filter
+pub async fn create_filter(
+    State(state): State<AppState>,
+    Json(mut req): Json<CreateFilterRequest>,
+) -> impl IntoResponse {
+    req.filter.tags = normalize_tags(req.filter.tags);
+    let name = req.name.trim();
+    if name.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "Filter name is required");
+    }
+    if let Ok(Some(_)) = state.db.get_saved_filter(name).await {
+        return error(StatusCode::CONFLICT, format!("Filter '{}' already exists", name));
+    }
+
+    match state.db.create_saved_filter(name, &req.filter).await {
+        Ok(saved) => (StatusCode::CREATED, Json(saved)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub async fn delete_filter(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    match state.db.delete_saved_filter(&id).await {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, format!("Filter '{}' not found", id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// The exact message array the model saw at step `n` (0-based)
+pub async fn step_context(
+    State(state): State<AppState>,
+    Path((id, step)): Path<(MissionId, usize)>,
+) -> impl IntoResponse {
+    match state.db.get_step_context(&id, step).await {
+        Ok(Some(context)) => {
+            let total_steps = state.db.count_step_contexts(&id).await.unwrap_or_default();
+            (StatusCode::OK, Json(serde_json::json!({
+                "mission_id": id,
+                "total_steps": total_steps,
+                "context": context,
+            }))).into_response()
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, format!("No context recorded for step {} of mission '{}'", step, id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// How mission `other` differed from mission `id`: plan, tool calls, and
+/// the files each run left changed
+pub async fn compare(
+    State(state): State<AppState>,
+    Path((id, other)): Path<(MissionId, MissionId)>,
+) -> impl IntoResponse {
+    let mut missions = Vec::with_capacity(2);
+    for id in [&id, &other] {
+        match state.db.get_mission(id).await {
+            Ok(Some(mission)) => missions.push(mission),
+            Ok(None) => return error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+    match run_diff::compare(&state.db, state.logs.as_ref(), &state.snapshots, &missions[0], &missions[1]).await {
+        Ok(diff) => (StatusCode::OK, Json(diff)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Failure classification and remediation hint for a failed mission
+pub async fn post_mortem(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    match state.db.get_post_mortem(&id).await {
+        Ok(Some(post_mortem)) => (StatusCode::OK, Json(post_mortem)).into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, format!("No post-mortem for mission '{}'", id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Query string for `GET /api/missions/:id/logs`
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    #[serde(default)]
+    pub tier: LogTier,
+}
+
+/// The mission's step logs: the action taken and shortened tool output
+/// (`tier=concise`, the default), or with full responses and output as well
+/// (`tier=verbose`) while retention keeps them
+pub async fn logs(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+    Query(query): Query<LogsQuery>,
+) -> impl IntoResponse {
+    match state.db.get_mission(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    match state.logs.list_tier(&id, query.tier).await {
+        Ok(logs) => (StatusCode::OK, Json(serde_json::json!({ "mission_id": id, "tier": query.tier, "logs": logs }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Knowledge base sources the mission's final answer cited, with the lines they came from
+pub async fn citations(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    match state.db.get_mission(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    match state.db.list_citations(&id).await {
+        Ok(citations) => (StatusCode::OK, Json(serde_json::json!({ "citations": citations }))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// The mission's plan as tasks in execution order, with those ready to run
+pub async fn list_tasks(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    let tasks = match state.db.list_tasks(&id).await {
+        Ok(tasks) => tasks,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let order = match plan::validate(&tasks) {
+        Ok(order) => order,
+        Err(e) => return error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+    };
+    let ready: Vec<&str> = plan::ready(&tasks).iter().map(|t| t.id.as_str()).collect();
+    (StatusCode::OK, Json(serde_json::json!({
+        "mission_id": id,
+        "tasks": tasks,
+        "order": order,
+        "ready": ready,
+    }))).into_response()
+}
+
+/// Abort a running mission; it ends with status `cancelled`
+pub async fn cancel(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    if state.orchestrator.cancel(&id) {
+        return (StatusCode::ACCEPTED, Json(serde_json::json!({ "mission_id": id, "status": "cancelling" }))).into_response();
+    }
+    match state.db.get_mission(&id).await {
+        Ok(Some(mission)) => error(StatusCode::CONFLICT, format!("Mission '{}' is not running ({:?})", id, mission.status)),
+        Ok(None) => error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Missions holding a slot on this node and those queued for one
+pub async fn queue(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.orchestrator.scheduler().snapshot())
+}
+
+/// Output streamed so far in a running mission's current step
+pub async fn progress(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    match state.orchestrator.progress(&id) {
+        Some(progress) => (StatusCode::OK, Json(progress)).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("Mission '{}' is not running", id)),
+    }
+}
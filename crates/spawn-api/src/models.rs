@@ -0,0 +1,32 @@
+//! Model catalog endpoint
+//!
+//! The models OpenRouter serves, with their context window, prices per
+//! million tokens and capabilities (vision, tools, structured outputs,
+//! reasoning), for choosing mission and chat models. The catalog is cached
+//! for an hour; `?refresh=true` fetches it again.
+
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{spawn_error, AppState};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ModelsQuery {
+    refresh: bool,
+}
+
+pub async fn list(State(state): State<AppState>, Query(query): Query<ModelsQuery>) -> Response {
+    let models = match query.refresh {
+        true => state.openrouter.refresh_models().await,
+        false => state.openrouter.list_models().await,
+    };
+    match models {
+        Ok(models) => Json(serde_json::json!({ "models": models })).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
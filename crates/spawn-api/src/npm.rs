@@ -0,0 +1,104 @@
+//! Package script endpoints
+//!
+//! `package.json` scripts of the workspace (or a package in it), and running
+//! them: to completion with the output streamed as SSE, or in the background
+//! as a managed process.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use spawn_agents::tools::npm::ScriptRunner;
+use spawn_core::{CancellationToken, ProgressSender};
+
+use crate::{error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct ScriptsQuery {
+    /// Package directory relative to the workspace
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunScriptRequest {
+    pub script: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub dir: Option<String>,
+    /// Start as a managed process instead of waiting for it to finish
+    #[serde(default)]
+    pub background: bool,
+    /// Port a background script will listen on
+    pub port: Option<u16>,
+}
+
+/// The package's scripts and the package manager that runs them
+pub async fn list(
+    State(state): State<AppState>,
+    Query(query): Query<ScriptsQuery>,
+) -> Response {
+    match ScriptRunner::in_workspace(&state.workspace_root, query.dir.as_deref()) {
+        Ok(runner) => (StatusCode::OK, Json(serde_json::json!({
+            "package_manager": runner.package_manager(),
+            "scripts": runner.scripts(),
+        }))).into_response(),
+        Err(e) => error(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
+
+/// Run a script. In the foreground the response is SSE: an `output` event
+/// per line, then `result` (exit code, duration, output tail) or `error`.
+/// The script is stopped if the client goes away.
+pub async fn run(
+    State(state): State<AppState>,
+    Json(req): Json<RunScriptRequest>,
+) -> Response {
+    let runner = match ScriptRunner::in_workspace(&state.workspace_root, req.dir.as_deref()) {
+        Ok(runner) => runner,
+        Err(e) => return error(StatusCode::NOT_FOUND, e.to_string()),
+    };
+    if !runner.scripts().contains_key(&req.script) {
+        return error(StatusCode::NOT_FOUND, format!("No script '{}' in package.json", req.script));
+    }
+
+    if req.background {
+        return match runner.start(&state.processes, &req.script, &req.args, req.port).await {
+            Ok(info) => (StatusCode::CREATED, Json(info)).into_response(),
+            Err(e) => error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+    }
+
+    let cancel = CancellationToken::new();
+    let (progress, updates) = ProgressSender::channel();
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            let result = runner.run(&req.script, &req.args, &progress, &cancel).await;
+            let _ = done_tx.send(result);
+        });
+    }
+
+    let guard = cancel.drop_guard();
+    let output = updates.map(|update| serde_json::json!({ "type": "output", "line": update.message }));
+    let result = futures::stream::once(async move {
+        match done_rx.await {
+            Ok(Ok(run)) => serde_json::json!({ "type": "result", "result": run }),
+            Ok(Err(e)) => serde_json::json!({ "type": "error", "message": e.to_string() }),
+            Err(_) => serde_json::json!({ "type": "error", "message": "Script run was aborted" }),
+        }
+    });
+    let events = output.chain(result).map(move |event| {
+        // Dropped with the stream, which stops the script
+        let _ = &guard;
+        Ok::<_, std::convert::Infallible>(Event::default().data(event.to_string()))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
@@ -0,0 +1,278 @@
+//! Preview proxy
+//!
+//! Reverse-proxies `/preview/:port/*path` to dev servers started through the
+//! process manager, including WebSocket upgrades (Vite/webpack HMR), so built
+//! apps can be previewed without exposing extra ports.
+
+use axum::{
+    body::Body,
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        FromRequestParts, Path, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tokio_tungstenite::tungstenite::{
+    self,
+    client::IntoClientRequest,
+    protocol::{frame::coding::CloseCode, CloseFrame as UpstreamCloseFrame},
+};
+use tracing::{debug, warn};
+
+use crate::{error, AppState};
+
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Hop-by-hop headers that must not be forwarded in either direction
+pub(crate) const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            // Redirects are rewritten and passed through to the browser
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build preview HTTP client")
+    })
+}
+
+/// Proxy the root of a preview (`/preview/:port`)
+pub async fn proxy_root(
+    State(state): State<AppState>,
+    Path(port): Path<u16>,
+    req: Request,
+) -> Response {
+    forward(state, port, String::new(), req).await
+}
+
+/// Proxy a path under a preview (`/preview/:port/*path`)
+pub async fn proxy(
+    State(state): State<AppState>,
+    Path(params): Path<HashMap<String, String>>,
+    req: Request,
+) -> Response {
+    let Some(port) = params.get("port").and_then(|p| p.parse::<u16>().ok()) else {
+        return (StatusCode::BAD_REQUEST, "Invalid port").into_response();
+    };
+    let path = params.get("path").cloned().unwrap_or_default();
+    forward(state, port, path, req).await
+}
+
+async fn forward(state: AppState, port: u16, path: String, req: Request) -> Response {
+    // Only ports owned by managed processes are reachable
+    if state.processes.by_port(port).await.is_none() {
+        return error(StatusCode::NOT_FOUND, format!("No running process on port {}", port));
+    }
+
+    let query = req.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
+    let target = format!("127.0.0.1:{}/{}{}", port, path, query);
+
+    if is_websocket_upgrade(req.headers()) {
+        let (mut parts, _) = req.into_parts();
+        let ws = match WebSocketUpgrade::from_request_parts(&mut parts, &state).await {
+            Ok(ws) => ws,
+            Err(rejection) => return rejection.into_response(),
+        };
+        let forwarded = forwarded_headers(&parts.headers, port);
+        return proxy_websocket(ws, &parts.headers, forwarded, format!("ws://{}", target)).await;
+    }
+
+    proxy_http(req, port, format!("http://{}", target)).await
+}
+
+// ============================================
+// HTTP
+// ============================================
+
+async fn proxy_http(req: Request, port: u16, url: String) -> Response {
+    let (parts, body) = req.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(b) => b,
+        Err(e) => return (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response(),
+    };
+
+    let mut upstream = client().request(parts.method, &url).body(body);
+    for (name, value) in parts.headers.iter() {
+        if name != header::HOST && !HOP_BY_HOP.contains(&name.as_str()) {
+            upstream = upstream.header(name, value);
+        }
+    }
+    upstream = upstream.headers(forwarded_headers(&parts.headers, port));
+
+    let resp = match upstream.send().await {
+        Ok(r) => r,
+        Err(e) => {
+            return error(StatusCode::BAD_GATEWAY, format!("Preview server unavailable: {}", e));
+        }
+    };
+
+    let mut builder = Response::builder().status(resp.status());
+    for (name, value) in resp.headers().iter() {
+        if HOP_BY_HOP.contains(&name.as_str()) {
+            continue;
+        }
+        if name == header::LOCATION {
+            if let Some(location) = value.to_str().ok().and_then(|l| rewrite_location(l, port)) {
+                builder = builder.header(name, location);
+                continue;
+            }
+        }
+        builder = builder.header(name, value);
+    }
+    builder
+        .body(Body::from_stream(resp.bytes_stream()))
+        .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response())
+}
+
+/// Host rewriting: the dev server sees itself as `localhost:PORT`, with the
+/// original host and prefix available in the X-Forwarded-* headers
+fn forwarded_headers(original: &HeaderMap, port: u16) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(host) = HeaderValue::from_str(&format!("localhost:{}", port)) {
+        headers.insert(header::HOST, host);
+    }
+    if let Some(host) = original.get(header::HOST) {
+        headers.insert("x-forwarded-host", host.clone());
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static("http"));
+    if let Ok(prefix) = HeaderValue::from_str(&format!("/preview/{}", port)) {
+        headers.insert("x-forwarded-prefix", prefix);
+    }
+    headers
+}
+
+/// Map redirects back under the preview prefix
+fn rewrite_location(location: &str, port: u16) -> Option<String> {
+    let prefix = format!("/preview/{}", port);
+    for origin in [format!("http://localhost:{}", port), format!("http://127.0.0.1:{}", port)] {
+        if let Some(rest) = location.strip_prefix(&origin) {
+            return Some(format!("{}{}", prefix, if rest.is_empty() { "/" } else { rest }));
+        }
+    }
+    if location.starts_with('/') && !location.starts_with("//") && !location.starts_with(&prefix) {
+        return Some(format!("{}{}", prefix, location));
+    }
+    None
+}
+
+// ============================================
+// WebSocket
+// ============================================
+
+pub(crate) fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    headers.get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("websocket"))
+        .unwrap_or(false)
+}
+
+/// Accept `ws` and relay it to `url`, passing along the client's subprotocols
+pub(crate) async fn proxy_websocket(ws: WebSocketUpgrade, headers: &HeaderMap, forwarded: HeaderMap, url: String) -> Response {
+    let protocols: Vec<String> = headers.get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+        .unwrap_or_default();
+
+    let mut request = match url.as_str().into_client_request() {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    request.headers_mut().extend(forwarded);
+    if let Some(value) = headers.get(header::SEC_WEBSOCKET_PROTOCOL) {
+        request.headers_mut().insert(header::SEC_WEBSOCKET_PROTOCOL, value.clone());
+    }
+
+    // Connect upstream before accepting so failures surface as HTTP errors
+    let upstream = match tokio_tungstenite::connect_async(request).await {
+        Ok((stream, _)) => stream,
+        Err(e) => {
+            return error(StatusCode::BAD_GATEWAY, format!("Upstream WebSocket unavailable: {}", e));
+        }
+    };
+
+    debug!(url = %url, "Proxying WebSocket");
+    ws.protocols(protocols)
+        .on_upgrade(move |socket| pump(socket, upstream))
+}
+
+async fn pump(
+    client: WebSocket,
+    upstream: tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) {
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+
+    let to_upstream = async {
+        while let Some(Ok(msg)) = client_rx.next().await {
+            let close = matches!(msg, Message::Close(_));
+            if upstream_tx.send(to_upstream_message(msg)).await.is_err() || close {
+                break;
+            }
+        }
+    };
+
+    let to_client = async {
+        while let Some(msg) = upstream_rx.next().await {
+            let msg = match msg {
+                Ok(m) => m,
+                Err(e) => {
+                    warn!(error = %e, "Upstream WebSocket error");
+                    break;
+                }
+            };
+            let Some(msg) = to_client_message(msg) else {
+                continue;
+            };
+            let close = matches!(msg, Message::Close(_));
+            if client_tx.send(msg).await.is_err() || close {
+                break;
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = to_upstream => {}
+        _ = to_client => {}
+    }
+}
+
+fn to_upstream_message(msg: Message) -> tungstenite::Message {
+    match msg {
+        Message::Text(t) => tungstenite::Message::Text(t),
+        Message::Binary(b) => tungstenite::Message::Binary(b),
+        Message::Ping(p) => tungstenite::Message::Ping(p),
+        Message::Pong(p) => tungstenite::Message::Pong(p),
+        Message::Close(frame) => tungstenite::Message::Close(frame.map(|f| UpstreamCloseFrame {
+            code: CloseCode::from(f.code),
+            reason: f.reason,
+        })),
+    }
+}
+
+fn to_client_message(msg: tungstenite::Message) -> Option<Message> {
+    Some(match msg {
+        tungstenite::Message::Text(t) => Message::Text(t),
+        tungstenite::Message::Binary(b) => Message::Binary(b),
+        tungstenite::Message::Ping(p) => Message::Ping(p),
+        tungstenite::Message::Pong(p) => Message::Pong(p),
+        tungstenite::Message::Close(frame) => Message::Close(frame.map(|f| CloseFrame {
+            code: f.code.into(),
+            reason: f.reason,
+        })),
+        tungstenite::Message::Frame(_) => return None,
+    })
+}
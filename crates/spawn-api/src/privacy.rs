@@ -0,0 +1,98 @@
+//! Data subject request endpoints
+//!
+//! Export and hard-delete the chat history and embeddings stored for a chat
+//! session or a user. Both are recorded in the audit log; the deletion's audit
+//! entry doubles as the receipt returned to the caller.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_core::{AuditEntry, DataSubject};
+
+use crate::{error, spawn_error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct SubjectQuery {
+    pub session_id: Option<String>,
+    pub user_id: Option<String>,
+    /// Include raw embedding vectors in an export
+    #[serde(default)]
+    pub include_vectors: bool,
+}
+
+impl SubjectQuery {
+    fn subject(&self) -> Option<DataSubject> {
+        match (&self.session_id, &self.user_id) {
+            (Some(id), None) if !id.trim().is_empty() => Some(DataSubject::Session(id.clone())),
+            (None, Some(id)) if !id.trim().is_empty() => Some(DataSubject::User(id.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Everything stored for a session or user
+pub async fn export(
+    State(state): State<AppState>,
+    Query(query): Query<SubjectQuery>,
+) -> impl IntoResponse {
+    let Some(subject) = query.subject() else {
+        return error(StatusCode::BAD_REQUEST, "Specify exactly one of session_id or user_id");
+    };
+    let memory = match state.vector_memory() {
+        Ok(memory) => memory,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
+
+    let data = match memory.export_chat(&subject, query.include_vectors).await {
+        Ok(data) => data,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Export failed: {}", e)),
+    };
+    let entry = AuditEntry::new("chat_export", subject.to_string(), serde_json::json!({
+        "chat_messages": data.messages.len(),
+        "embeddings": data.embeddings.len(),
+        "include_vectors": query.include_vectors,
+    }));
+    if let Err(e) = state.db.record_audit(&entry).await {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record audit entry: {}", e));
+    }
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "subject": subject,
+        "exported_at": entry.created_at,
+        "messages": data.messages,
+        "embeddings": data.embeddings,
+    }))).into_response()
+}
+
+/// Hard-delete everything stored for a session or user, returning a receipt
+pub async fn erase(
+    State(state): State<AppState>,
+    Query(query): Query<SubjectQuery>,
+) -> impl IntoResponse {
+    let Some(subject) = query.subject() else {
+        return error(StatusCode::BAD_REQUEST, "Specify exactly one of session_id or user_id");
+    };
+    let memory = match state.vector_memory() {
+        Ok(memory) => memory,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
+
+    let deleted = match memory.delete_chat(&subject).await {
+        Ok(deleted) => deleted,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("Deletion failed: {}", e)),
+    };
+    let receipt = AuditEntry::new("chat_delete", subject.to_string(), serde_json::json!({
+        "chat_messages": deleted.chat_messages,
+        "embeddings": deleted.embeddings,
+    }));
+    if let Err(e) = state.db.record_audit(&receipt).await {
+        // The data is already gone; still hand back the receipt
+        tracing::error!(receipt = %receipt.id, error = %e, "Failed to record deletion receipt");
+    }
+
+    (StatusCode::OK, Json(receipt)).into_response()
+}
@@ -0,0 +1,98 @@
+//! Process management endpoints
+//!
+//! Dev servers and watchers started by the agent or the user.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::processes::StartProcess;
+
+use crate::{error, AppState};
+
+/// List managed processes
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    let processes = state.processes.list().await;
+    (StatusCode::OK, Json(serde_json::json!({
+        "count": processes.len(),
+        "processes": processes,
+    })))
+}
+
+/// Start a managed process
+pub async fn start(
+    State(state): State<AppState>,
+    Json(req): Json<StartProcess>,
+) -> impl IntoResponse {
+    match state.processes.start(req).await {
+        Ok(info) => (StatusCode::CREATED, Json(info)).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Get a single process
+pub async fn get(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.processes.get(&id).await {
+        Some(info) => (StatusCode::OK, Json(info)).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("Process '{}' not found", id)),
+    }
+}
+
+/// Stop a process
+pub async fn stop(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.processes.stop(&id).await {
+        Ok(info) => (StatusCode::OK, Json(info)).into_response(),
+        Err(_) => error(StatusCode::NOT_FOUND, format!("Process '{}' not found", id)),
+    }
+}
+
+/// Restart a process
+pub async fn restart(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.processes.restart(&id).await {
+        Ok(info) => (StatusCode::OK, Json(info)).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Stop and remove a process
+pub async fn remove(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.processes.remove(&id).await {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({ "success": true }))).into_response(),
+        Err(_) => error(StatusCode::NOT_FOUND, format!("Process '{}' not found", id)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    pub lines: Option<usize>,
+}
+
+/// Tail of a process's output
+pub async fn logs(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> impl IntoResponse {
+    match state.processes.output(&id, query.lines.unwrap_or(100)).await {
+        Some(lines) => (StatusCode::OK, Json(serde_json::json!({
+            "total": lines.len(),
+            "lines": lines,
+        }))).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("Process '{}' not found", id)),
+    }
+}
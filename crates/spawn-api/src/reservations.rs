@@ -0,0 +1,68 @@
+//! File reservation endpoints
+//!
+//! The editor reserves a file while it has unsaved changes, renewing the
+//! reservation as long as they stay unsaved; missions reserve files their
+//! tools change. Writes to a file reserved by someone else get a 409 naming
+//! the owner unless they pass `force`.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::reservations::DEFAULT_RESERVATION_TTL;
+use std::time::Duration;
+
+use crate::{spawn_error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct ReserveRequest {
+    pub path: String,
+    /// `editor:<session>` for the editor
+    pub owner: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// Lifetime unless renewed (default 300)
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseQuery {
+    pub path: String,
+    pub owner: String,
+}
+
+/// List reservations in force
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    match state.reservations.list().await {
+        Ok(reservations) => (StatusCode::OK, Json(serde_json::json!({ "reservations": reservations }))).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
+
+/// Reserve a file, or renew a reservation the caller already holds
+pub async fn reserve(
+    State(state): State<AppState>,
+    Json(req): Json<ReserveRequest>,
+) -> impl IntoResponse {
+    let ttl = req.ttl_secs.map(Duration::from_secs).unwrap_or(DEFAULT_RESERVATION_TTL);
+    let reason = req.reason.as_deref().unwrap_or("unsaved changes");
+    match state.reservations.reserve(&req.path, &req.owner, reason, ttl).await {
+        Ok(reservation) => (StatusCode::OK, Json(reservation)).into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
+
+/// Drop a reservation, e.g. when the editor discards its changes
+pub async fn release(
+    State(state): State<AppState>,
+    Query(query): Query<ReleaseQuery>,
+) -> impl IntoResponse {
+    match state.reservations.release(&query.path, &query.owner).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => spawn_error(&e, serde_json::json!({})),
+    }
+}
@@ -0,0 +1,172 @@
+//! Resumable WebSocket sessions
+//!
+//! A negotiated connection's output is numbered and kept in a bounded replay
+//! buffer that outlives the socket for a grace period. Reconnecting with
+//! `?resume=<token>&last_seq=<n>` reattaches to the same session and replays
+//! everything after `n`, so a dropped connection loses neither mission events
+//! nor terminal output.
+
+use serde::Deserialize;
+use spawn_core::{WsSequenced, WsServerMessage};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// How long a disconnected session waits to be resumed
+pub const RESUME_GRACE: Duration = Duration::from_secs(120);
+/// Frames kept per session for replay
+const REPLAY_CAPACITY: usize = 1000;
+
+/// Query string of a resuming upgrade request
+#[derive(Debug, Default, Deserialize)]
+pub struct ResumeQuery {
+    pub resume: Option<String>,
+    /// Last `seq` the client received; everything after it is replayed
+    pub last_seq: Option<u64>,
+}
+
+#[derive(Default)]
+struct ReplayBuffer {
+    frames: VecDeque<WsSequenced>,
+    last_seq: u64,
+}
+
+/// Numbered output of one session: a replay buffer plus a live feed
+pub struct ReplayChannel {
+    buffer: Mutex<ReplayBuffer>,
+    live: broadcast::Sender<WsSequenced>,
+}
+
+impl ReplayChannel {
+    pub fn new() -> Self {
+        let (live, _) = broadcast::channel(256);
+        Self { buffer: Mutex::new(ReplayBuffer::default()), live }
+    }
+
+    pub fn publish(&self, message: WsServerMessage) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.last_seq += 1;
+        let frame = WsSequenced { seq: buffer.last_seq, message };
+        if buffer.frames.len() == REPLAY_CAPACITY {
+            buffer.frames.pop_front();
+        }
+        buffer.frames.push_back(frame.clone());
+        // Sent under the buffer lock so `attach` sees each frame exactly once
+        let _ = self.live.send(frame);
+    }
+
+    /// Every frame after `last_seq`, replayed from the buffer and then live.
+    ///
+    /// The second value is set when frames after `last_seq` were already
+    /// evicted: the seq of the oldest frame that is still available.
+    pub fn attach(self: &Arc<Self>, last_seq: u64) -> (Subscription, Option<u64>) {
+        let buffer = self.buffer.lock().unwrap();
+        let live = self.live.subscribe();
+        let oldest = buffer.frames.front().map(|f| f.seq);
+        let gap_from = oldest.filter(|&oldest| oldest > last_seq + 1);
+        let pending = buffer.frames.iter().filter(|f| f.seq > last_seq).cloned().collect();
+        (Subscription { channel: self.clone(), live, pending, last_seq }, gap_from)
+    }
+}
+
+/// One socket's view of a `ReplayChannel`
+pub struct Subscription {
+    channel: Arc<ReplayChannel>,
+    live: broadcast::Receiver<WsSequenced>,
+    pending: VecDeque<WsSequenced>,
+    last_seq: u64,
+}
+
+impl Subscription {
+    /// The next frame, in order and without gaps while the buffer holds them
+    pub async fn next(&mut self) -> WsSequenced {
+        loop {
+            if let Some(frame) = self.pending.pop_front() {
+                self.last_seq = frame.seq;
+                return frame;
+            }
+            match self.live.recv().await {
+                Ok(frame) if frame.seq > self.last_seq => {
+                    self.last_seq = frame.seq;
+                    return frame;
+                }
+                Ok(_) => {}
+                // Fell behind the live feed; catch up from the buffer
+                Err(RecvError::Lagged(_)) => {
+                    let (caught_up, _) = self.channel.attach(self.last_seq);
+                    *self = caught_up;
+                }
+                Err(RecvError::Closed) => std::future::pending().await,
+            }
+        }
+    }
+}
+
+impl Default for ReplayChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Entry<S> {
+    session: Arc<S>,
+    /// Bumped on every attach, so a stale socket's detach is ignored
+    generation: u64,
+    detached_at: Option<Instant>,
+}
+
+/// Sessions by resume token
+pub struct ResumeSessions<S> {
+    entries: Mutex<HashMap<String, Entry<S>>>,
+}
+
+impl<S> ResumeSessions<S> {
+    pub fn new() -> Self {
+        Self { entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Register a freshly attached session, returning its token and generation
+    pub fn insert(&self, session: Arc<S>) -> (String, u64) {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.entries.lock().unwrap().insert(token.clone(), Entry { session, generation: 0, detached_at: None });
+        (token, 0)
+    }
+
+    /// Take over a session; any socket still attached is superseded
+    pub fn attach(&self, token: &str) -> Option<(Arc<S>, u64)> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(token)?;
+        entry.generation += 1;
+        entry.detached_at = None;
+        Some((entry.session.clone(), entry.generation))
+    }
+
+    /// Start the grace period, unless a newer socket has attached since
+    pub fn detach(&self, token: &str, generation: u64) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(token) {
+            if entry.generation == generation {
+                entry.detached_at = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Drop sessions detached for longer than `grace`, returning their tokens
+    pub fn reap(&self, grace: Duration) -> Vec<String> {
+        let mut entries = self.entries.lock().unwrap();
+        let expired: Vec<String> = entries.iter()
+            .filter(|(_, e)| e.detached_at.is_some_and(|at| at.elapsed() >= grace))
+            .map(|(token, _)| token.clone())
+            .collect();
+        for token in &expired {
+            entries.remove(token);
+        }
+        expired
+    }
+}
+
+impl<S> Default for ResumeSessions<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
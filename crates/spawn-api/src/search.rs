@@ -9,10 +9,15 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use spawn_agents::{ContentType, SearchResult, VectorMemory};
+use spawn_agents::activity::{rank_by_activity, DEFAULT_ACTIVITY_WEIGHT};
+use spawn_agents::ignore::IgnoreRules;
+use spawn_agents::rerank::{self, RerankMode, Reranker, DEFAULT_RERANK_CANDIDATES};
+use futures::StreamExt;
+use spawn_agents::{ContentType, SearchResult};
+use spawn_core::{MissionEvent, Service};
 use std::sync::Arc;
 
-use crate::AppState;
+use crate::{spawn_error, AppState};
 
 // ============================================
 // Search API Types
@@ -25,6 +30,12 @@ pub struct SearchQuery {
     pub content_type: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: i32,
+    /// `llm` or `cross_encoder` to rescore a wider candidate pool
+    #[serde(default)]
+    pub rerank: Option<String>,
+    /// Candidates fetched for reranking (default 50)
+    #[serde(default)]
+    pub candidates: Option<i32>,
 }
 
 fn default_limit() -> i32 {
@@ -45,6 +56,9 @@ pub struct CodeSearchQuery {
     pub language: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: i32,
+    /// Share of the ranking given to git activity (0 disables; default 0.2)
+    #[serde(default)]
+    pub activity_weight: Option<f32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,11 +73,17 @@ pub struct IndexFileResponse {
     pub success: bool,
     pub chunks_indexed: usize,
     pub file_path: String,
+    /// Left out by the workspace's ignore rules
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub ignored: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct StoreChatRequest {
     pub session_id: String,
+    /// Lets the user's data be exported or erased across sessions
+    #[serde(default)]
+    pub user_id: Option<String>,
     pub role: String,
     pub content: String,
     #[serde(default)]
@@ -89,42 +109,80 @@ fn default_context_limit() -> i32 {
     5
 }
 
+/// How many results to fetch, and the reranker that cuts them down to the
+/// requested limit, for a request's `rerank` parameter
+pub(crate) struct RerankPlan {
+    pub fetch: i32,
+    limit: i32,
+    reranker: Option<Arc<dyn Reranker>>,
+}
+
+impl RerankPlan {
+    pub fn new(
+        state: &AppState,
+        rerank: Option<&str>,
+        candidates: Option<i32>,
+        limit: i32,
+    ) -> Result<Self, (StatusCode, String)> {
+        let Some(mode) = rerank else {
+            return Ok(Self { fetch: limit, limit, reranker: None });
+        };
+        let mode: RerankMode = mode.parse().map_err(|e: spawn_core::SpawnError| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        let reranker = state.rerankers.get(mode).ok_or_else(|| {
+            (StatusCode::SERVICE_UNAVAILABLE, format!("Reranker '{}' is not configured", mode))
+        })?;
+        Ok(Self {
+            fetch: candidates.unwrap_or(DEFAULT_RERANK_CANDIDATES).max(limit),
+            limit,
+            reranker: Some(reranker),
+        })
+    }
+
+    pub async fn apply(&self, query: &str, results: Vec<SearchResult>) -> spawn_core::Result<Vec<SearchResult>> {
+        match &self.reranker {
+            Some(reranker) => rerank::rerank(reranker.as_ref(), query, results, self.limit.max(0) as usize).await,
+            None => Ok(results),
+        }
+    }
+}
+
+fn parse_content_type(name: &str) -> Option<ContentType> {
+    match name {
+        "code" => Some(ContentType::Code),
+        "chat" => Some(ContentType::Chat),
+        "mission" => Some(ContentType::Mission),
+        "file" => Some(ContentType::File),
+        "doc" => Some(ContentType::Doc),
+        _ => None,
+    }
+}
+
 // ============================================
 // Search Handlers
 // ============================================
 
 /// General semantic search across all content types
 pub async fn search(
+    State(state): State<AppState>,
     Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let pg_url = std::env::var("POSTGRES_URL").ok();
-
-    // Check if PostgreSQL is configured
-    let Some(pg_url) = pg_url else {
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
-            "error": "Vector search requires PostgreSQL with pgvector. Set POSTGRES_URL env var."
-        }))).into_response();
+    let plan = match RerankPlan::new(&state, query.rerank.as_deref(), query.candidates, query.limit) {
+        Ok(plan) => plan,
+        Err((status, message)) => return (status, Json(serde_json::json!({ "error": message }))).into_response(),
     };
 
-    let vector_memory = match VectorMemory::connect(&pg_url, &api_key).await {
+    let vector_memory = match state.vector_memory() {
         Ok(vm) => vm,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to connect to vector store: {}", e)
-            }))).into_response();
-        }
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
     };
 
-    let content_type = query.content_type.as_deref().and_then(|t| match t {
-        "code" => Some(ContentType::Code),
-        "chat" => Some(ContentType::Chat),
-        "mission" => Some(ContentType::Mission),
-        "file" => Some(ContentType::File),
-        _ => None,
-    });
+    let content_type = query.content_type.as_deref().and_then(parse_content_type);
 
-    match vector_memory.search(&query.q, content_type, query.limit).await {
+    let results = match vector_memory.search(&query.q, content_type, plan.fetch).await {
+        Ok(results) => plan.apply(&query.q, results).await,
+        Err(e) => Err(e),
+    };
+    match results {
         Ok(results) => {
             let total = results.len();
             (StatusCode::OK, Json(SearchResponse {
@@ -141,30 +199,57 @@ pub async fn search(
     }
 }
 
-/// Search code specifically with language filtering
-pub async fn search_code(
-    Query(query): Query<CodeSearchQuery>,
+/// Debug a search: query embedding norm, raw distances for the nearest
+/// candidates (including ones just past the limit), filters and timings
+pub async fn explain(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let pg_url = std::env::var("POSTGRES_URL").ok();
+    let vector_memory = match state.vector_memory() {
+        Ok(vm) => vm,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
 
-    let Some(pg_url) = pg_url else {
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
-            "error": "Vector search requires PostgreSQL with pgvector. Set POSTGRES_URL env var."
-        }))).into_response();
+    let content_type = match query.content_type.as_deref() {
+        Some(name) => match parse_content_type(name) {
+            Some(t) => Some(t),
+            None => return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "error": format!("Unknown content type '{}'", name)
+            }))).into_response(),
+        },
+        None => None,
     };
 
-    let vector_memory = match VectorMemory::connect(&pg_url, &api_key).await {
-        Ok(vm) => vm,
+    match vector_memory.explain(&query.q, content_type, query.limit).await {
+        Ok(explain) => (StatusCode::OK, Json(explain)).into_response(),
         Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to connect to vector store: {}", e)
-            }))).into_response();
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "error": format!("Search explain failed: {}", e)
+            }))).into_response()
         }
+    }
+}
+
+/// Search code specifically with language filtering
+///
+/// Results are re-ranked with the workspace's git activity, so recently and
+/// frequently changed files come before stale ones.
+pub async fn search_code(
+    State(state): State<AppState>,
+    Query(query): Query<CodeSearchQuery>,
+) -> impl IntoResponse {
+    let vector_memory = match state.vector_memory() {
+        Ok(vm) => vm,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
     };
 
     match vector_memory.search_code(&query.q, query.language.as_deref(), query.limit).await {
-        Ok(results) => {
+        Ok(mut results) => {
+            let weight = query.activity_weight.unwrap_or(DEFAULT_ACTIVITY_WEIGHT);
+            if weight > 0.0 {
+                let activity = state.activity.get(&state.workspace_root).await;
+                rank_by_activity(&mut results, &activity, &state.workspace_root, weight);
+            }
             let total = results.len();
             (StatusCode::OK, Json(SearchResponse {
                 query: query.q,
@@ -180,31 +265,49 @@ pub async fn search_code(
     }
 }
 
-/// Index a file for semantic search
+/// Keep search in step with the files missions' tools change in the
+/// server's workspace: those already indexed are indexed again (or dropped
+/// if removed), and the workspace's git activity is reloaded
+pub fn spawn_refresh_on_change(state: AppState) {
+    let mut events = state.orchestrator.events().subscribe();
+    tokio::spawn(async move {
+        while let Some(event) = events.next().await {
+            let MissionEvent::FilesChanged { mission_id, paths, .. } = event else { continue };
+            let workspace = match state.db.get_mission(&mission_id).await {
+                Ok(Some(mission)) => mission.context.workspace,
+                _ => None,
+            };
+            if workspace.is_some_and(|w| w != state.workspace_root) {
+                continue;
+            }
+            state.activity.invalidate(&state.workspace_root);
+            if let Some(vm) = &state.vector_memory {
+                if let Err(e) = vm.refresh_files(&state.workspace_root, &paths).await {
+                    tracing::warn!(mission_id = %mission_id, error = %e, "Failed to re-index changed files");
+                }
+            }
+        }
+    });
+}
+
+/// Index a file for semantic search; ignored files are accepted but not
+/// indexed
 pub async fn index_file(
     State(state): State<AppState>,
     Json(req): Json<IndexFileRequest>,
 ) -> impl IntoResponse {
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let pg_url = std::env::var("POSTGRES_URL").ok();
-
-    let Some(pg_url) = pg_url else {
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(IndexFileResponse {
-            success: false,
+    if IgnoreRules::load(&state.workspace_root).is_ignored(std::path::Path::new(&req.file_path), false) {
+        return (StatusCode::OK, Json(IndexFileResponse {
+            success: true,
             chunks_indexed: 0,
             file_path: req.file_path,
+            ignored: true,
         })).into_response();
-    };
+    }
 
-    let vector_memory = match VectorMemory::connect(&pg_url, &api_key).await {
+    let vector_memory = match state.vector_memory() {
         Ok(vm) => vm,
-        Err(_) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(IndexFileResponse {
-                success: false,
-                chunks_indexed: 0,
-                file_path: req.file_path,
-            })).into_response();
-        }
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
     };
 
     match vector_memory.index_file(&req.file_path, &req.content, &req.language).await {
@@ -213,6 +316,7 @@ pub async fn index_file(
                 success: true,
                 chunks_indexed: chunks,
                 file_path: req.file_path,
+                ignored: false,
             })).into_response()
         }
         Err(_) => {
@@ -220,36 +324,25 @@ pub async fn index_file(
                 success: false,
                 chunks_indexed: 0,
                 file_path: req.file_path,
+                ignored: false,
             })).into_response()
         }
     }
 }
 
 /// Store chat message with embedding for context retrieval
+///
+/// Uses the shared store so the retention policy (PII scrubbing) applies.
 pub async fn store_chat(
+    State(state): State<AppState>,
     Json(req): Json<StoreChatRequest>,
 ) -> impl IntoResponse {
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let pg_url = std::env::var("POSTGRES_URL").ok();
-
-    let Some(pg_url) = pg_url else {
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(StoreChatResponse {
-            success: false,
-            id: String::new(),
-        })).into_response();
-    };
-
-    let vector_memory = match VectorMemory::connect(&pg_url, &api_key).await {
+    let vector_memory = match state.vector_memory() {
         Ok(vm) => vm,
-        Err(_) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(StoreChatResponse {
-                success: false,
-                id: String::new(),
-            })).into_response();
-        }
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
     };
 
-    match vector_memory.store_chat(&req.session_id, &req.role, &req.content, req.tool_calls).await {
+    match vector_memory.store_chat(&req.session_id, req.user_id.as_deref(), &req.role, &req.content, req.tool_calls).await {
         Ok(id) => {
             (StatusCode::OK, Json(StoreChatResponse {
                 success: true,
@@ -267,24 +360,12 @@ pub async fn store_chat(
 
 /// Get relevant chat context for a query (RAG-style retrieval)
 pub async fn get_chat_context(
+    State(state): State<AppState>,
     Query(query): Query<ChatContextQuery>,
 ) -> impl IntoResponse {
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-    let pg_url = std::env::var("POSTGRES_URL").ok();
-
-    let Some(pg_url) = pg_url else {
-        return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
-            "error": "Vector search requires PostgreSQL with pgvector. Set POSTGRES_URL env var."
-        }))).into_response();
-    };
-
-    let vector_memory = match VectorMemory::connect(&pg_url, &api_key).await {
+    let vector_memory = match state.vector_memory() {
         Ok(vm) => vm,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to connect to vector store: {}", e)
-            }))).into_response();
-        }
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
     };
 
     match vector_memory.get_chat_context(&query.q, query.session_id.as_deref(), query.limit).await {
@@ -305,19 +386,12 @@ pub async fn get_chat_context(
 }
 
 /// Get search system status
-pub async fn search_status() -> impl IntoResponse {
-    let pg_url = std::env::var("POSTGRES_URL").ok();
-    let api_key = std::env::var("OPENROUTER_API_KEY").unwrap_or_default();
-
-    let pg_available = if let Some(ref url) = pg_url {
-        VectorMemory::connect(url, &api_key).await.is_ok()
-    } else {
-        false
-    };
+pub async fn search_status(State(state): State<AppState>) -> impl IntoResponse {
+    let pg_available = state.vector_memory.is_some() && state.services.is_up(Service::VectorStore);
 
     (StatusCode::OK, Json(serde_json::json!({
         "vector_search_available": pg_available,
-        "postgres_configured": pg_url.is_some(),
+        "postgres_configured": std::env::var("POSTGRES_URL").is_ok(),
         "embedding_model": "openai/text-embedding-3-small",
         "embedding_dimensions": 1536,
     }))).into_response()
@@ -0,0 +1,69 @@
+//! Optional service availability
+//!
+//! The knowledge base and conversation memory need PostgreSQL with pgvector,
+//! named terminals need terminal-app, and chat needs an LLM provider. These
+//! are probed in the background. While one is down, the endpoints that need
+//! it answer 503 with its name in `missing_capability` (see
+//! `AppState::require`), and missions aren't offered the tools that need it.
+
+use spawn_ai::ProviderManager;
+use spawn_core::{HealthCheck, Service, ServiceAvailability};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often the services are probed
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// A probe slower than this counts as down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to probe for each service
+pub struct ServiceProbes {
+    /// `None` when no vector store is configured, which leaves it down for good
+    pub vector_store: Option<Arc<dyn HealthCheck>>,
+    pub terminal: Arc<dyn HealthCheck>,
+    /// Up while any provider passes its own health probes with its circuit
+    /// not open
+    pub providers: Arc<ProviderManager>,
+}
+
+impl ServiceProbes {
+    /// Probe every service once and record the results
+    pub async fn refresh(&self, services: &ServiceAvailability) {
+        let (vector_store, terminal) = tokio::join!(
+            async {
+                match &self.vector_store {
+                    Some(check) => probe(check.as_ref()).await,
+                    None => false,
+                }
+            },
+            probe(self.terminal.as_ref()),
+        );
+        let provider = self.providers.health().iter().any(|p| p.is_available());
+        for (service, up) in [(Service::VectorStore, vector_store), (Service::Terminal, terminal), (Service::Provider, provider)] {
+            if services.set(service, up) {
+                if up {
+                    info!(service = service.as_str(), "Service back up; re-enabling what depends on it");
+                } else {
+                    warn!(service = service.as_str(), "Service down; disabling what depends on it");
+                }
+            }
+        }
+    }
+
+    /// Refresh `services` every `PROBE_INTERVAL` from now on
+    pub fn spawn(self, services: Arc<ServiceAvailability>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + PROBE_INTERVAL, PROBE_INTERVAL);
+            loop {
+                ticker.tick().await;
+                self.refresh(&services).await;
+            }
+        });
+    }
+}
+
+async fn probe(check: &dyn HealthCheck) -> bool {
+    tokio::time::timeout(PROBE_TIMEOUT, check.check()).await
+        .is_ok_and(|status| status.is_online())
+}
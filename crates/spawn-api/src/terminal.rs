@@ -1,184 +1,262 @@
 //! Terminal WebSocket handler
 //!
-//! Pipes PTY stdin/stdout over WebSocket to xterm.js frontend
+//! Pipes PTY stdin/stdout over WebSocket to xterm.js frontend. Clients that
+//! negotiate a schema version exchange `input`/`output` messages and can
+//! resume their shell after a dropped connection; others get raw text frames
+//! and a shell that ends with the socket.
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use futures::{SinkExt, StreamExt};
+use spawn_core::{SessionKind, WsChannel, WsClientMessage, WsSequenced, WsServerMessage};
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info};
 
-use crate::AppState;
+use crate::resume::{ReplayChannel, ResumeQuery};
+use crate::{ws_protocol, AppState};
 
-/// WebSocket upgrade handler
-pub async fn ws_handler(
-    ws: WebSocketUpgrade,
-    State(_state): State<AppState>,
-) -> impl IntoResponse {
-    info!("🖥️ Terminal WebSocket connection request");
-    ws.on_upgrade(handle_socket)
+/// A shell and its buffered output, independent of any one socket
+pub struct ShellSession {
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    output: Arc<ReplayChannel>,
+    pumps: [JoinHandle<()>; 2],
+    /// Killed on drop
+    _child: Child,
+    /// Held for the life of the shell (`terminal.max_sessions`)
+    _slot: OwnedSemaphorePermit,
 }
 
-/// Handle the WebSocket connection
-async fn handle_socket(socket: WebSocket) {
-    info!("🖥️ Terminal WebSocket connected");
+impl ShellSession {
+    fn spawn(slot: OwnedSemaphorePermit) -> std::io::Result<Self> {
+        // Get user's shell
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
+        info!("🐚 Spawning shell: {}", shell);
 
-    let (mut ws_sender, mut ws_receiver) = socket.split();
+        let mut child = Command::new(&shell)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .env("TERM", "xterm-256color")
+            .env("COLORTERM", "truecolor")
+            .kill_on_drop(true)
+            .spawn()?;
 
-    // Get user's shell
-    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-    
-    info!("🐚 Spawning shell: {}", shell);
-
-    // Spawn shell process
-    let mut child = match Command::new(&shell)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .env("TERM", "xterm-256color")
-        .env("COLORTERM", "truecolor")
-        .spawn()
-    {
-        Ok(child) => child,
-        Err(e) => {
-            error!("Failed to spawn shell: {}", e);
-            let _ = ws_sender
-                .send(Message::Text(format!("Error: Failed to spawn shell: {}\r\n", e)))
-                .await;
-            return;
-        }
-    };
+        let stdin = child.stdin.take().expect("Failed to get stdin");
+        let stdout = child.stdout.take().expect("Failed to get stdout");
+        let stderr = child.stderr.take().expect("Failed to get stderr");
+        let output = Arc::new(ReplayChannel::new());
+        let pumps = [
+            pump(stdout, 4096, output.clone(), "stdout"),
+            pump(stderr, 1024, output.clone(), "stderr"),
+        ];
 
-    let mut stdin = child.stdin.take().expect("Failed to get stdin");
-    let stdout = child.stdout.take().expect("Failed to get stdout");
-    let stderr = child.stderr.take().expect("Failed to get stderr");
+        Ok(Self { stdin: tokio::sync::Mutex::new(stdin), output, pumps, _child: child, _slot: slot })
+    }
 
-    // Channel for PTY output -> WebSocket
-    let (tx, mut rx) = mpsc::channel::<String>(100);
-    let tx2 = tx.clone();
+    async fn write(&self, input: &[u8]) -> std::io::Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        stdin.write_all(input).await?;
+        stdin.flush().await
+    }
+}
 
-    // Task: Read stdout and send to channel
-    let stdout_task = tokio::spawn(async move {
-        let mut reader = BufReader::new(stdout);
-        let mut buf = vec![0u8; 4096];
-        
-        loop {
-            match tokio::io::AsyncReadExt::read(&mut reader, &mut buf).await {
-                Ok(0) => {
-                    debug!("stdout EOF");
-                    break;
-                }
-                Ok(n) => {
-                    let output = String::from_utf8_lossy(&buf[..n]).to_string();
-                    if tx.send(output).await.is_err() {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    error!("stdout read error: {}", e);
-                    break;
-                }
-            }
+impl Drop for ShellSession {
+    fn drop(&mut self) {
+        info!("🖥️ Cleaning up terminal session");
+        for pump in &self.pumps {
+            pump.abort();
         }
-    });
+    }
+}
+
+/// Copy one of the shell's output streams into its replay channel
+fn pump(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    chunk: usize,
+    output: Arc<ReplayChannel>,
+    name: &'static str,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut buf = vec![0u8; chunk];
 
-    // Task: Read stderr and send to channel
-    let stderr_task = tokio::spawn(async move {
-        let mut reader = BufReader::new(stderr);
-        let mut buf = vec![0u8; 1024];
-        
         loop {
             match tokio::io::AsyncReadExt::read(&mut reader, &mut buf).await {
                 Ok(0) => {
-                    debug!("stderr EOF");
+                    debug!("{} EOF", name);
                     break;
                 }
                 Ok(n) => {
-                    let output = String::from_utf8_lossy(&buf[..n]).to_string();
-                    if tx2.send(output).await.is_err() {
-                        break;
-                    }
+                    let data = String::from_utf8_lossy(&buf[..n]).to_string();
+                    output.publish(WsServerMessage::Output { data });
                 }
                 Err(e) => {
-                    error!("stderr read error: {}", e);
+                    error!("{} read error: {}", name, e);
                     break;
                 }
             }
         }
-    });
+    })
+}
 
-    // Task: Send PTY output to WebSocket
-    let send_task = tokio::spawn(async move {
-        while let Some(output) = rx.recv().await {
-            if ws_sender.send(Message::Text(output)).await.is_err() {
-                break;
-            }
-        }
+/// WebSocket upgrade handler
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<ResumeQuery>,
+) -> Response {
+    info!("🖥️ Terminal WebSocket connection request");
+    let resumed = query.resume.and_then(|token| {
+        let (session, generation) = state.terminal_sessions.attach(&token)?;
+        Some(Attach::Resume { token, session, generation, last_seq: query.last_seq.unwrap_or(0) })
     });
+    let attach = match resumed {
+        Some(resumed) => resumed,
+        None => match state.terminal_slots.clone().try_acquire_owned() {
+            Ok(slot) => Attach::New(slot),
+            Err(_) => return (StatusCode::SERVICE_UNAVAILABLE, "Too many open terminals").into_response(),
+        },
+    };
+    ws_protocol::offer(ws).on_upgrade(move |socket| handle_socket(socket, state, attach))
+}
 
-    // Main loop: Receive from WebSocket and write to PTY stdin
-    while let Some(msg) = ws_receiver.next().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                // Parse message - could be raw input or JSON command
-                let input = if text.starts_with('{') {
-                    // Try to parse as JSON
-                    if let Ok(cmd) = serde_json::from_str::<serde_json::Value>(&text) {
-                        if let Some(data) = cmd.get("data").and_then(|d| d.as_str()) {
-                            data.to_string()
-                        } else if let Some(input) = cmd.get("input").and_then(|i| i.as_str()) {
-                            input.to_string()
-                        } else {
-                            text
-                        }
-                    } else {
-                        text
-                    }
-                } else {
-                    text
-                };
+/// What a connection attaches to
+enum Attach {
+    Resume { token: String, session: Arc<ShellSession>, generation: u64, last_seq: u64 },
+    /// A new shell, with the slot it will occupy
+    New(OwnedSemaphorePermit),
+}
 
-                if let Err(e) = stdin.write_all(input.as_bytes()).await {
-                    error!("Failed to write to stdin: {}", e);
-                    break;
+/// Handle the WebSocket connection
+async fn handle_socket(socket: WebSocket, state: AppState, attach: Attach) {
+    let version = ws_protocol::negotiated(&socket);
+    info!(?version, "🖥️ Terminal WebSocket connected");
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+
+    let resumed = matches!(attach, Attach::Resume { .. });
+    let (session, registration, last_seq) = match attach {
+        Attach::Resume { token, session, generation, last_seq } => (session, Some((token, generation)), last_seq),
+        Attach::New(slot) => match ShellSession::spawn(slot) {
+            Ok(session) => {
+                let session = Arc::new(session);
+                // Only clients that can see the token can come back for the shell
+                let registration = version.map(|_| state.terminal_sessions.insert(session.clone()));
+                if let (Some((token, _)), Some(cluster)) = (&registration, &state.cluster) {
+                    cluster.claim(SessionKind::Terminal, token).await;
                 }
-                if let Err(e) = stdin.flush().await {
-                    error!("Failed to flush stdin: {}", e);
+                (session, registration, 0)
+            }
+            Err(e) => {
+                error!("Failed to spawn shell: {}", e);
+                let _ = ws_sender
+                    .send(Message::Text(format!("Error: Failed to spawn shell: {}\r\n", e)))
+                    .await;
+                return;
+            }
+        },
+    };
+
+    if let Some(version) = version {
+        let welcome = WsServerMessage::Welcome {
+            version,
+            channel: WsChannel::Terminal,
+            resume_token: registration.as_ref().map(|(token, _)| token.clone()),
+            resumed,
+        };
+        // A failed send ends the loop below, which still detaches
+        let _ = ws_sender.send(ws_protocol::encode(&welcome)).await;
+    }
+    let (mut output, gap_from) = session.output.attach(last_seq);
+    if let (Some(oldest), Some(_)) = (gap_from, version) {
+        let gap = WsServerMessage::error("replay_gap", format!("Output before seq {} is no longer available", oldest));
+        let _ = ws_sender.send(ws_protocol::encode(&gap)).await;
+    }
+
+    loop {
+        tokio::select! {
+            frame = output.next() => {
+                let frame = match (version, frame.message) {
+                    (Some(_), message) => ws_protocol::encode(&WsSequenced { seq: frame.seq, message }),
+                    (None, WsServerMessage::Output { data }) => Message::Text(data),
+                    // Legacy clients only understand raw output
+                    (None, _) => continue,
+                };
+                if ws_sender.send(frame).await.is_err() {
                     break;
                 }
             }
-            Ok(Message::Binary(data)) => {
-                // Raw binary input
-                if let Err(e) = stdin.write_all(&data).await {
-                    error!("Failed to write binary to stdin: {}", e);
+            incoming = ws_receiver.next() => {
+                let input = match incoming {
+                    Some(Ok(Message::Text(text))) if version.is_some() => {
+                        let reply = match serde_json::from_str::<WsClientMessage>(&text) {
+                            Ok(WsClientMessage::Input { data }) => {
+                                if let Err(e) = session.write(data.as_bytes()).await {
+                                    error!("Failed to write to stdin: {}", e);
+                                    break;
+                                }
+                                continue;
+                            }
+                            Ok(WsClientMessage::Ping) => WsServerMessage::Pong,
+                            Ok(WsClientMessage::Subscribe { .. }) => {
+                                WsServerMessage::error("unsupported", "subscribe is only valid on /ws/events")
+                            }
+                            Err(e) => WsServerMessage::error("invalid_message", e.to_string()),
+                        };
+                        if ws_sender.send(ws_protocol::encode(&reply)).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Some(Ok(Message::Text(text))) => legacy_input(text).into_bytes(),
+                    // Raw binary input
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Close(_))) | None => {
+                        info!("🖥️ Terminal WebSocket closed by client");
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        error!("WebSocket error: {}", e);
+                        break;
+                    }
+                    Some(Ok(_)) => continue,
+                };
+                if let Err(e) = session.write(&input).await {
+                    error!("Failed to write to stdin: {}", e);
                     break;
                 }
-                let _ = stdin.flush().await;
-            }
-            Ok(Message::Close(_)) => {
-                info!("🖥️ Terminal WebSocket closed by client");
-                break;
             }
-            Err(e) => {
-                error!("WebSocket error: {}", e);
-                break;
-            }
-            _ => {}
         }
     }
 
-    // Cleanup
-    info!("🖥️ Cleaning up terminal session");
-    let _ = child.kill().await;
-    stdout_task.abort();
-    stderr_task.abort();
-    send_task.abort();
+    // The shell lives on for a resuming client; otherwise dropping it here ends it
+    if let Some((token, generation)) = registration {
+        debug!("Terminal session detached; resumable for {:?}", crate::resume::RESUME_GRACE);
+        state.terminal_sessions.detach(&token, generation);
+    }
+}
+
+/// Unversioned text frames: raw input, or JSON with `data` or `input`
+fn legacy_input(text: String) -> String {
+    if !text.starts_with('{') {
+        return text;
+    }
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(cmd) => cmd.get("data")
+            .or_else(|| cmd.get("input"))
+            .and_then(|d| d.as_str())
+            .map(str::to_string)
+            .unwrap_or(text),
+        Err(_) => text,
+    }
 }
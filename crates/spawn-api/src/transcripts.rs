@@ -0,0 +1,97 @@
+//! Chat session and transcript endpoints
+//!
+//! Serves a stored chat session as a `Conversation`, or renders it as
+//! Markdown, returned inline or written into the workspace for committing
+//! alongside a PR or doc.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::transcript;
+use spawn_core::DataSubject;
+use std::path::Component;
+
+use crate::{error, spawn_error, AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct TranscriptQuery {
+    /// Only `md` is supported
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Workspace-relative path to write the transcript to instead of returning it
+    pub save: Option<String>,
+}
+
+fn default_format() -> String {
+    "md".to_string()
+}
+
+/// `GET /api/chat/sessions/:id`: the stored session as a `Conversation`
+pub async fn get_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let memory = match state.vector_memory() {
+        Ok(memory) => memory,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
+    match memory.conversation(&session_id).await {
+        Ok(conversation) if conversation.is_empty() => {
+            error(StatusCode::NOT_FOUND, format!("Session '{}' has no messages", session_id))
+        }
+        Ok(conversation) => (StatusCode::OK, Json(conversation)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `GET /api/chat/sessions/:id/transcript`
+pub async fn get_transcript(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+    Query(query): Query<TranscriptQuery>,
+) -> impl IntoResponse {
+    if !matches!(query.format.as_str(), "md" | "markdown") {
+        return error(StatusCode::BAD_REQUEST, format!("Unsupported format '{}'; use md", query.format));
+    }
+    let memory = match state.vector_memory() {
+        Ok(memory) => memory,
+        Err(e) => return spawn_error(&e, serde_json::json!({})),
+    };
+
+    let messages = match memory.export_chat(&DataSubject::Session(session_id.clone()), false).await {
+        Ok(data) => data.messages,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    if messages.is_empty() {
+        return error(StatusCode::NOT_FOUND, format!("Session '{}' has no messages", session_id));
+    }
+    let markdown = transcript::render_markdown(&session_id, &messages);
+
+    let Some(save) = query.save else {
+        return ([(header::CONTENT_TYPE, "text/markdown; charset=utf-8")], markdown).into_response();
+    };
+    let relative = std::path::Path::new(&save);
+    if relative.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return error(StatusCode::BAD_REQUEST, "save must be a path inside the workspace");
+    }
+    let path = state.workspace_root.join(relative);
+    if let Some(parent) = path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    }
+    if let Err(e) = tokio::fs::write(&path, &markdown).await {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "session_id": session_id,
+        "path": save,
+        "messages": messages.len(),
+        "bytes": markdown.len(),
+    }))).into_response()
+}
@@ -0,0 +1,154 @@
+//! Voice endpoints
+//!
+//! Audio in from the web UI is transcribed by the configured STT provider and
+//! fed into chat or mission creation; mission outcomes can be read back
+//! through the optional TTS provider.
+
+use axum::{
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Serialize;
+use spawn_core::{Mission, MissionContext, MissionId, MissionStatus};
+
+use crate::{error, AppState};
+
+/// Whisper-compatible APIs reject uploads above 25 MB
+pub const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+
+/// Input limit of OpenAI-compatible speech endpoints
+const MAX_SPEECH_CHARS: usize = 4096;
+
+#[derive(Debug, Serialize)]
+pub struct VoiceResponse {
+    pub transcript: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mission_id: Option<MissionId>,
+}
+
+/// Transcribe an audio clip and send it to chat (`mode=chat`, default) or
+/// start a mission with it as the goal (`mode=mission`).
+///
+/// Multipart fields: `audio` (file), optional `mode` and `language`.
+pub async fn chat_voice(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let Some(stt) = state.stt.clone() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Speech-to-text is not configured (set STT_API_KEY)");
+    };
+
+    let mut audio: Option<(Vec<u8>, String)> = None;
+    let mut mode = "chat".to_string();
+    let mut language: Option<String> = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "audio" => {
+                let mime = field.content_type().unwrap_or("audio/webm").to_string();
+                match field.bytes().await {
+                    Ok(bytes) => audio = Some((bytes.to_vec(), mime)),
+                    Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+                }
+            }
+            "mode" => mode = field.text().await.unwrap_or_default(),
+            "language" => language = field.text().await.ok().filter(|l| !l.is_empty()),
+            _ => {}
+        }
+    }
+
+    let Some((audio, mime)) = audio.filter(|(a, _)| !a.is_empty()) else {
+        return error(StatusCode::BAD_REQUEST, "Missing 'audio' field");
+    };
+
+    let transcript = match stt.transcribe(audio, &mime, language.as_deref()).await {
+        Ok(t) if !t.is_empty() => t,
+        Ok(_) => return error(StatusCode::UNPROCESSABLE_ENTITY, "No speech detected"),
+        Err(e) => return error(StatusCode::BAD_GATEWAY, e.to_string()),
+    };
+    tracing::info!(provider = stt.provider_name(), mode = %mode, "Voice input transcribed");
+
+    // The spoken language, when given, is also the one to answer in
+    let request = crate::locales::LocaleRequest { locale: language, ..Default::default() };
+    let locale = match crate::locales::resolve(&state, &request).await {
+        Ok(locale) => locale,
+        Err(message) => return error(StatusCode::BAD_REQUEST, message),
+    };
+
+    match mode.as_str() {
+        "mission" => {
+            let mut context = MissionContext::new().with("source", "voice");
+            context.locale = Some(locale);
+            let mission = Mission::new(&transcript).with_context(context);
+            let mission_id = mission.id.clone();
+
+            state.spawn_mission(mission).await;
+
+            (StatusCode::ACCEPTED, Json(VoiceResponse {
+                transcript,
+                response: None,
+                mission_id: Some(mission_id),
+            })).into_response()
+        }
+        "chat" => match crate::chat_reply(&state, &transcript, &locale).await {
+            Ok(response) => (StatusCode::OK, Json(VoiceResponse {
+                transcript,
+                response: Some(response),
+                mission_id: None,
+            })).into_response(),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+        other => error(StatusCode::BAD_REQUEST, format!("Unknown mode '{}' (expected chat or mission)", other)),
+    }
+}
+
+/// Spoken completion summary of a finished mission
+pub async fn mission_summary_audio(
+    State(state): State<AppState>,
+    Path(id): Path<MissionId>,
+) -> impl IntoResponse {
+    let Some(tts) = state.tts.clone() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Text-to-speech is not configured (set TTS_API_KEY)");
+    };
+
+    let mission = match state.db.get_mission(&id).await {
+        Ok(Some(m)) => m,
+        Ok(None) => return error(StatusCode::NOT_FOUND, format!("Mission '{}' not found", id)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    let text = match mission.status {
+        MissionStatus::Completed => {
+            let summary = match state.logs.completion_summary(&id).await {
+                Ok(s) => s.unwrap_or_default(),
+                Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            };
+            format!("Mission complete: {}. {}", mission.goal, summary)
+        }
+        MissionStatus::Failed => format!("Mission failed: {}.", mission.goal),
+        _ => return error(StatusCode::CONFLICT, "Mission has not finished yet"),
+    };
+    let text: String = text.chars().take(MAX_SPEECH_CHARS).collect();
+
+    match tts.synthesize(&text).await {
+        Ok(audio) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, audio.mime_type),
+                (header::CACHE_CONTROL, "private, max-age=3600".to_string()),
+            ],
+            audio.data,
+        ).into_response(),
+        Err(e) => error(StatusCode::BAD_GATEWAY, e.to_string()),
+    }
+}
@@ -0,0 +1,208 @@
+//! Workspace registry and federated search
+//!
+//! Extra repository roots can be registered next to the server's own
+//! workspace ("default") and searched together in one request.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use spawn_agents::federation::federated_search;
+use spawn_agents::templates::Template;
+use std::path::PathBuf;
+
+use crate::{error, AppState};
+
+/// Name the server's own WORKSPACE_ROOT is registered under
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterWorkspaceRequest {
+    pub name: String,
+    pub root: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InitWorkspaceRequest {
+    /// Template directory or git URL
+    pub template: String,
+    /// Where to set the workspace up; the server's workspace by default
+    pub root: Option<String>,
+    /// Also register the workspace under this name
+    pub name: Option<String>,
+    /// Replace files the workspace already has
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Run the template's missions once the files are in place
+    #[serde(default)]
+    pub start_missions: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FederatedSearchQuery {
+    pub q: String,
+    /// Comma-separated workspace names (default: all)
+    pub workspaces: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    20
+}
+
+/// Names are used in comma-separated lists, and "default" is taken
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name != DEFAULT_WORKSPACE && !name.contains(',')
+}
+
+/// The default workspace followed by all registered ones
+async fn all_workspaces(state: &AppState) -> spawn_core::Result<Vec<(String, PathBuf)>> {
+    let mut workspaces = vec![(DEFAULT_WORKSPACE.to_string(), state.workspace_root.clone())];
+    workspaces.extend(state.db.list_workspaces().await?
+        .into_iter()
+        .map(|w| (w.name, PathBuf::from(w.root))));
+    Ok(workspaces)
+}
+
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    match all_workspaces(&state).await {
+        Ok(workspaces) => {
+            let workspaces: Vec<_> = workspaces.into_iter()
+                .map(|(name, root)| serde_json::json!({ "name": name, "root": root.display().to_string() }))
+                .collect();
+            (StatusCode::OK, Json(serde_json::json!({ "workspaces": workspaces }))).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+pub async fn register(
+    State(state): State<AppState>,
+    Json(req): Json<RegisterWorkspaceRequest>,
+) -> impl IntoResponse {
+    let name = req.name.trim();
+    if !valid_name(name) {
+        return error(StatusCode::BAD_REQUEST, format!("Invalid workspace name '{}'", name));
+    }
+    let root = match std::fs::canonicalize(&req.root) {
+        Ok(root) if root.is_dir() => root,
+        _ => return error(StatusCode::BAD_REQUEST, format!("'{}' is not a directory", req.root)),
+    };
+
+    match state.db.register_workspace(name, &root.display().to_string()).await {
+        Ok(workspace) => (StatusCode::CREATED, Json(workspace)).into_response(),
+        Err(e) if e.to_string().contains("UNIQUE") => {
+            error(StatusCode::CONFLICT, "A workspace with that name or root is already registered")
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/workspaces/init` - set a workspace up from a template
+pub async fn init(
+    State(state): State<AppState>,
+    Json(req): Json<InitWorkspaceRequest>,
+) -> Response {
+    // Everything that can be rejected is, before any file is written
+    let name = req.name.as_deref().map(str::trim);
+    if let Some(name) = name.filter(|name| !valid_name(name)) {
+        return error(StatusCode::BAD_REQUEST, format!("Invalid workspace name '{}'", name));
+    }
+    let root = match req.root.as_deref() {
+        Some(root) => {
+            let path = PathBuf::from(root);
+            let escapes = path.components().any(|c| matches!(c, std::path::Component::ParentDir));
+            if !path.is_absolute() || escapes || (path.exists() && !path.is_dir()) {
+                return error(StatusCode::BAD_REQUEST, format!("'{}' is not an absolute directory path", root));
+            }
+            path
+        }
+        None => state.workspace_root.clone(),
+    };
+    if let Some(name) = name {
+        match all_workspaces(&state).await {
+            Ok(workspaces) if workspaces.iter().any(|(registered, _)| registered == name) => {
+                return error(StatusCode::CONFLICT, format!("Workspace '{}' is already registered", name));
+            }
+            Ok(_) => {}
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    let template = match Template::fetch(&req.template).await {
+        Ok(template) => template,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    let applied = match template.apply(&root, req.overwrite).await {
+        Ok(applied) => applied,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let root = std::fs::canonicalize(&root).unwrap_or(root);
+
+    if let Some(name) = name {
+        if let Err(e) = state.db.register_workspace(name, &root.display().to_string()).await {
+            return error(StatusCode::CONFLICT, format!("Files applied, but the workspace wasn't registered: {}", e));
+        }
+    }
+
+    let mut missions = Vec::new();
+    for mission in template.missions(&root) {
+        let summary = serde_json::json!({ "goal": mission.goal, "mission_id": req.start_missions.then(|| mission.id.clone()) });
+        if req.start_missions {
+            state.spawn_mission(mission).await;
+        }
+        missions.push(summary);
+    }
+    (StatusCode::CREATED, Json(serde_json::json!({
+        "template": template.manifest.name,
+        "root": root.display().to_string(),
+        "written": applied.written,
+        "skipped": applied.skipped,
+        "missions": missions,
+    }))).into_response()
+}
+
+pub async fn remove(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.db.remove_workspace(&name).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, format!("Workspace '{}' not found", name)),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Search all (or the selected) workspaces at once
+pub async fn federated(
+    State(state): State<AppState>,
+    Query(query): Query<FederatedSearchQuery>,
+) -> impl IntoResponse {
+    if query.q.trim().is_empty() {
+        return error(StatusCode::BAD_REQUEST, "Missing query");
+    }
+
+    let mut workspaces = match all_workspaces(&state).await {
+        Ok(w) => w,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    if let Some(selected) = &query.workspaces {
+        let selected: Vec<&str> = selected.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+        if let Some(unknown) = selected.iter().find(|s| !workspaces.iter().any(|(name, _)| name == *s)) {
+            return error(StatusCode::NOT_FOUND, format!("Workspace '{}' not found", unknown));
+        }
+        workspaces.retain(|(name, _)| selected.contains(&name.as_str()));
+    }
+
+    let results = federated_search(&workspaces, state.vector_memory.as_deref(), &query.q, query.limit).await;
+    (StatusCode::OK, Json(serde_json::json!({
+        "query": query.q,
+        "workspaces": workspaces.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        "total": results.len(),
+        "results": results,
+    }))).into_response()
+}
@@ -0,0 +1,23 @@
+//! WebSocket schema negotiation
+//!
+//! Clients opt into the typed message schema by offering `spawn.v<N>` in
+//! `Sec-WebSocket-Protocol`; the server picks the newest version both sides
+//! speak. Clients that offer nothing get the endpoint's legacy raw framing.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use serde::Serialize;
+use spawn_core::{parse_ws_subprotocol, ws_subprotocol, WS_PROTOCOL_VERSIONS};
+
+/// Advertise every supported version, newest first so it wins
+pub fn offer(ws: WebSocketUpgrade) -> WebSocketUpgrade {
+    ws.protocols(WS_PROTOCOL_VERSIONS.iter().map(|&v| ws_subprotocol(v)))
+}
+
+/// Schema version agreed during the upgrade; `None` for legacy clients
+pub fn negotiated(socket: &WebSocket) -> Option<u32> {
+    socket.protocol()?.to_str().ok().and_then(parse_ws_subprotocol)
+}
+
+pub fn encode(message: &impl Serialize) -> Message {
+    Message::Text(serde_json::to_string(message).unwrap_or_default())
+}
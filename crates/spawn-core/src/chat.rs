@@ -0,0 +1,364 @@
+//! Chat messages, tool calls and conversations
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::{Result, Summarizer};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    /// Providers send `null` content on assistant turns that only call tools
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub content: MessageContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Native function calls requested by the assistant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    /// On `Role::Tool` messages, the call this is the result of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// When the message was written; orders a replayed conversation
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    /// Annotations such as the model used, token counts or tool ids; kept with
+    /// the conversation but never sent to a provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// The prompt up to and including this message is the same on every
+    /// call, so providers that cache prompts (Anthropic, Gemini) may cache it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_breakpoint: bool,
+}
+
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<MessageContent, D::Error> {
+    Ok(Option::<MessageContent>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Message body: plain text, or text mixed with images for vision models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    ImageUrl {
+        url: String,
+        /// `low`, `high` or `auto` resolution hint
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+    ImageBase64 {
+        mime_type: String,
+        /// Base64-encoded image bytes
+        data: String,
+    },
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+    
+    pub fn image_url(url: impl Into<String>) -> Self {
+        Self::ImageUrl { url: url.into(), detail: None }
+    }
+    
+    pub fn image_base64(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::ImageBase64 { mime_type: mime_type.into(), data: data.into() }
+    }
+}
+
+impl MessageContent {
+    /// The text of the message, with image parts left out
+    pub fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Parts(parts) => parts.iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+    
+    pub fn has_images(&self) -> bool {
+        matches!(self, Self::Parts(parts) if parts.iter().any(|p| !matches!(p, ContentPart::Text { .. })))
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&String> for MessageContent {
+    fn from(text: &String) -> Self {
+        Self::Text(text.clone())
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        Self::Parts(parts)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
+}
+
+/// A function call requested by the model.
+///
+/// Serialized in the OpenAI wire format (`{"id", "type": "function",
+/// "function": {"name", "arguments": "<json string>"}}`) so messages can be
+/// sent back to providers unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "WireToolCall", from = "WireToolCall")]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+impl ToolCall {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        Self { id: id.into(), name: name.into(), arguments }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_type")]
+    kind: String,
+    function: WireFunction,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireFunction {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+impl From<ToolCall> for WireToolCall {
+    fn from(call: ToolCall) -> Self {
+        Self {
+            id: call.id,
+            kind: function_type(),
+            function: WireFunction {
+                name: call.name,
+                arguments: call.arguments.to_string(),
+            },
+        }
+    }
+}
+
+impl From<WireToolCall> for ToolCall {
+    fn from(wire: WireToolCall) -> Self {
+        // Models occasionally emit malformed JSON; keep the raw text rather than drop the call
+        let arguments = match wire.function.arguments.trim() {
+            "" => serde_json::json!({}),
+            raw => serde_json::from_str(raw).unwrap_or(serde_json::Value::String(raw.to_string())),
+        };
+        Self { id: wire.id, name: wire.function.name, arguments }
+    }
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: impl Into<MessageContent>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+            created_at: Utc::now(),
+            metadata: None,
+            cache_breakpoint: false,
+        }
+    }
+    
+    pub fn system(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::System, content)
+    }
+    
+    pub fn user(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::User, content)
+    }
+    
+    pub fn assistant(content: impl Into<MessageContent>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+    
+    /// Assistant turn requesting native function calls
+    pub fn assistant_tool_calls(content: impl Into<MessageContent>, tool_calls: Vec<ToolCall>) -> Self {
+        Self { tool_calls: Some(tool_calls), ..Self::new(Role::Assistant, content) }
+    }
+    
+    /// Result of a tool call, sent back to the model
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<MessageContent>) -> Self {
+        Self { tool_call_id: Some(tool_call_id.into()), ..Self::new(Role::Tool, content) }
+    }
+    
+    /// Set one metadata key, keeping any others
+    pub fn with_metadata(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or_default();
+        match &mut self.metadata {
+            Some(serde_json::Value::Object(map)) => {
+                map.insert(key.to_string(), value);
+            }
+            metadata => *metadata = Some(serde_json::json!({ key: value })),
+        }
+        self
+    }
+    
+    pub fn with_created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+    
+    pub fn with_cache_breakpoint(mut self) -> Self {
+        self.cache_breakpoint = true;
+        self
+    }
+}
+
+/// An ordered exchange of messages: a chat session, or the running prompt of
+/// a mission. Leading system messages are pinned: truncating and summarizing
+/// never remove them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conversation {
+    /// Chat session or mission id
+    pub id: String,
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+    /// Model the conversation is held with, when fixed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Conversation {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), ..Self::default() }
+    }
+    
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+    
+    pub fn with_message(mut self, message: ChatMessage) -> Self {
+        self.append(message);
+        self
+    }
+    
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        self.metadata.insert(key.into(), serde_json::to_value(value).unwrap_or_default());
+        self
+    }
+    
+    pub fn append(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+    
+    /// Add `message` after the pinned system messages, ahead of the exchange
+    pub fn insert_pinned(&mut self, message: ChatMessage) {
+        let at = self.pinned_len();
+        self.messages.insert(at, message);
+    }
+    
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+    
+    pub fn last(&self) -> Option<&ChatMessage> {
+        self.messages.last()
+    }
+    
+    /// Leading system messages, other than an earlier summary (which the
+    /// next summary takes in)
+    pub fn pinned_len(&self) -> usize {
+        self.messages.iter()
+            .take_while(|m| m.role == Role::System && m.metadata.as_ref().is_none_or(|m| m.get("summarized").is_none()))
+            .count()
+    }
+    
+    /// Keep the pinned messages and the newest `keep_last` others; returns
+    /// what was removed, oldest first
+    pub fn truncate(&mut self, keep_last: usize) -> Vec<ChatMessage> {
+        let pinned = self.pinned_len();
+        let end = self.messages.len().saturating_sub(keep_last).max(pinned);
+        self.messages.drain(pinned..end).collect()
+    }
+    
+    /// Replace everything but the pinned messages and the newest `keep_last`
+    /// with a summary of it. Returns false if there was nothing to summarize.
+    pub async fn summarize(&mut self, summarizer: &dyn Summarizer, keep_last: usize) -> Result<bool> {
+        let pinned = self.pinned_len();
+        let end = self.messages.len().saturating_sub(keep_last).max(pinned);
+        if end == pinned {
+            return Ok(false);
+        }
+        let summary = summarizer.summarize(&self.messages[pinned..end]).await?;
+        let summary = ChatMessage::system(format!("Summary of the earlier conversation:\n{}", summary))
+            .with_metadata("summarized", end - pinned);
+        self.messages.splice(pinned..end, [summary]);
+        Ok(true)
+    }
+}
+
+impl From<Conversation> for Vec<ChatMessage> {
+    fn from(conversation: Conversation) -> Self {
+        conversation.messages
+    }
+}
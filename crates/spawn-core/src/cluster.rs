@@ -0,0 +1,65 @@
+//! Sessions, locks and nodes shared across a cluster
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// State that lives in one node's memory, and so must be served by that node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    /// A running mission (cancellation, live events, snapshots)
+    Mission,
+    /// A resumable `/ws/terminal` shell, by resume token
+    Terminal,
+    /// A resumable `/ws/events` subscription, by resume token
+    Events,
+}
+
+impl SessionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionKind::Mission => "mission",
+            SessionKind::Terminal => "terminal",
+            SessionKind::Events => "events",
+        }
+    }
+}
+
+/// An advisory lock on a workspace resource, recorded in the shared database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    /// `file:<path>` or `git:<repo>`
+    pub resource: String,
+    /// Mission id or API request that holds it
+    pub holder: String,
+    /// What the holder is doing, e.g. `git_commit`
+    pub purpose: String,
+    pub acquired_at: DateTime<Utc>,
+    /// Refreshed while held; a lock past this is free for the taking
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A soft claim on a file: while it stands, writes by anyone but the owner
+/// are refused unless forced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReservation {
+    /// Relative to the workspace root
+    pub path: String,
+    /// `editor:<session>` with unsaved changes, or `mission:<id>` with
+    /// changes not yet settled
+    pub owner: String,
+    #[serde(default)]
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    /// Renewed by the owner; a reservation past this no longer counts
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A spawn-api instance sharing the database, as of its last heartbeat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterNode {
+    pub id: String,
+    /// Base URL other nodes reach it at
+    pub url: String,
+    pub heartbeat_at: DateTime<Utc>,
+}
@@ -0,0 +1,910 @@
+//! Configuration: defaults, config file and environment, in that order
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::{ModelPrice, Result, SpawnError, normalize_locale};
+
+/// Config files looked for in the working directory, in order, unless
+/// `SPAWN_CONFIG` names one
+const CONFIG_FILES: [&str; 2] = ["spawn.toml", "spawn.json"];
+
+/// Built-in values, the bottom configuration layer
+fn config_defaults() -> serde_json::Value {
+    serde_json::json!({
+        "database_url": "sqlite:spawn.db",
+        "locale": "en",
+        "server": { "host": "0.0.0.0", "port": 3000 },
+        "terminal": { "max_sessions": 10 },
+        "stt": { "api_url": "https://api.openai.com/v1", "model": "whisper-1" },
+        "tts": { "api_url": "https://api.openai.com/v1", "model": "tts-1" },
+    })
+}
+
+/// How an environment variable's text is read
+#[derive(Clone, Copy)]
+enum EnvKind {
+    Text,
+    Number,
+    Flag,
+}
+
+/// Environment variables and the config keys they set
+const ENV_KEYS: &[(&str, &str, EnvKind)] = &[
+    ("DATABASE_URL", "database_url", EnvKind::Text),
+    ("WORKSPACE_ROOT", "workspace_root", EnvKind::Text),
+    ("SPAWN_LOCALE", "locale", EnvKind::Text),
+    ("HOST", "server.host", EnvKind::Text),
+    ("PORT", "server.port", EnvKind::Number),
+    ("OPENROUTER_API_KEY", "providers.openrouter.api_key", EnvKind::Text),
+    ("OPENROUTER_REQUESTS_PER_MINUTE", "providers.openrouter.requests_per_minute", EnvKind::Number),
+    ("OPENROUTER_MAX_CONCURRENT", "providers.openrouter.max_concurrent", EnvKind::Number),
+    ("OPENROUTER_MAX_ATTEMPTS", "providers.openrouter.max_attempts", EnvKind::Number),
+    ("OPENROUTER_RETRY_BACKOFF_MS", "providers.openrouter.retry_backoff_ms", EnvKind::Number),
+    ("OPENROUTER_RETRY_MAX_BACKOFF_MS", "providers.openrouter.retry_max_backoff_ms", EnvKind::Number),
+    ("OPENROUTER_CONNECT_TIMEOUT_SECS", "providers.openrouter.connect_timeout_secs", EnvKind::Number),
+    ("OPENROUTER_READ_TIMEOUT_SECS", "providers.openrouter.read_timeout_secs", EnvKind::Number),
+    ("OPENAI_API_KEY", "providers.openai.api_key", EnvKind::Text),
+    ("OLLAMA_BASE_URL", "providers.ollama.base_url", EnvKind::Text),
+    ("OLLAMA_KEEP_ALIVE", "providers.ollama.keep_alive", EnvKind::Text),
+    ("AZURE_OPENAI_ENDPOINT", "providers.azure.base_url", EnvKind::Text),
+    ("AZURE_OPENAI_API_KEY", "providers.azure.api_key", EnvKind::Text),
+    ("AZURE_OPENAI_DEPLOYMENT", "providers.azure.deployment", EnvKind::Text),
+    ("AZURE_OPENAI_API_VERSION", "providers.azure.api_version", EnvKind::Text),
+    ("GEMINI_API_KEY", "providers.gemini.api_key", EnvKind::Text),
+    ("BEDROCK_REGION", "providers.bedrock.region", EnvKind::Text),
+    ("BEDROCK_ENDPOINT", "providers.bedrock.base_url", EnvKind::Text),
+    ("GEMINI_BASE_URL", "providers.gemini.base_url", EnvKind::Text),
+    ("RERANK_MODEL", "models.rerank", EnvKind::Text),
+    ("LLM_REQUEST_TIMEOUT_SECS", "models.request_timeout_secs", EnvKind::Number),
+    ("PROVIDER_PROBE_INTERVAL_SECS", "provider_health.interval_secs", EnvKind::Number),
+    ("PROVIDER_CIRCUIT_FAILURE_PERCENT", "provider_health.failure_rate_percent", EnvKind::Number),
+    ("PROVIDER_CIRCUIT_OPEN_SECS", "provider_health.open_secs", EnvKind::Number),
+    ("TERMINAL_MAX_SESSIONS", "terminal.max_sessions", EnvKind::Number),
+    ("STT_API_URL", "stt.api_url", EnvKind::Text),
+    ("STT_API_KEY", "stt.api_key", EnvKind::Text),
+    ("STT_MODEL", "stt.model", EnvKind::Text),
+    ("TTS_API_URL", "tts.api_url", EnvKind::Text),
+    ("TTS_API_KEY", "tts.api_key", EnvKind::Text),
+    ("TTS_MODEL", "tts.model", EnvKind::Text),
+    ("TTS_VOICE", "tts.voice", EnvKind::Text),
+    ("CHAT_RETENTION_DAYS", "retention.max_age_days", EnvKind::Number),
+    ("CHAT_MAX_MESSAGES_PER_SESSION", "retention.max_messages_per_session", EnvKind::Number),
+    ("PII_SCRUBBING", "retention.scrub_pii", EnvKind::Flag),
+    ("VERBOSE_LOG_RETENTION_DAYS", "retention.verbose_log_days", EnvKind::Number),
+    ("LOCK_ON_CONFLICT", "locks.on_conflict", EnvKind::Text),
+    ("LOCK_WAIT_SECS", "locks.wait_secs", EnvKind::Number),
+    ("LOG_BACKEND", "log_storage.backend", EnvKind::Text),
+    ("LOG_DIR", "log_storage.dir", EnvKind::Text),
+    ("LOG_SHIP_COMMAND", "log_storage.ship_command", EnvKind::Text),
+    ("MAX_CONCURRENT_MISSIONS", "scheduler.max_concurrent", EnvKind::Number),
+    ("MISSION_PREEMPTION", "scheduler.preemption", EnvKind::Flag),
+    ("MISSION_TMP_DIR", "mission_tmp.dir", EnvKind::Text),
+    ("MISSION_TMP_QUOTA_MB", "mission_tmp.quota_mb", EnvKind::Number),
+    ("MISSION_TMP_RETENTION_HOURS", "mission_tmp.retention_hours", EnvKind::Number),
+    ("RESPONSE_CACHE", "response_cache.enabled", EnvKind::Flag),
+    ("RESPONSE_CACHE_CAPACITY", "response_cache.capacity", EnvKind::Number),
+    ("RESPONSE_CACHE_PERSIST", "response_cache.persist", EnvKind::Flag),
+    ("METRICS_PROMETHEUS", "metrics.prometheus", EnvKind::Flag),
+    ("CONTEXT_WINDOW_TOKENS", "context.window_tokens", EnvKind::Number),
+    ("CONTEXT_RESERVE_TOKENS", "context.reserve_tokens", EnvKind::Number),
+    ("PROMPT_CACHING", "context.prompt_caching", EnvKind::Flag),
+    ("CONTEXT_SUMMARIZE", "context.summarize", EnvKind::Flag),
+    ("FORMAT_ON_WRITE", "format.on_write", EnvKind::Flag),
+    ("SPAWN_NODE_ID", "cluster.node_id", EnvKind::Text),
+    ("SPAWN_ADVERTISE_URL", "cluster.advertise_url", EnvKind::Text),
+    ("SPAWN_CLUSTER_AFFINITY", "cluster.affinity", EnvKind::Text),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub database_url: String,
+    /// Empty when only Ollama or Azure is configured
+    pub openrouter_api_key: String,
+    pub server_host: String,
+    pub server_port: u16,
+    /// Root for file operations; the working directory when unset
+    pub workspace_root: Option<std::path::PathBuf>,
+    /// Credentials per LLM provider, keyed by provider name (`openrouter`, `openai`, ...)
+    pub providers: HashMap<String, ProviderConfig>,
+    pub models: ModelDefaults,
+    pub terminal: TerminalLimits,
+    /// Speech-to-text provider; disabled when no API key is configured
+    pub stt: Option<SpeechConfig>,
+    /// Text-to-speech provider; disabled when no API key is configured
+    pub tts: Option<SpeechConfig>,
+    /// Pruning and scrubbing of stored conversation memory
+    pub retention: RetentionPolicy,
+    /// Multi-node mode; off unless `cluster.advertise_url` is set
+    pub cluster: Option<ClusterConfig>,
+    pub locks: LockPolicy,
+    pub scheduler: SchedulerConfig,
+    pub provider_health: ProviderHealthConfig,
+    pub log_storage: LogStorageConfig,
+    pub context: ContextConfig,
+    pub format: FormatConfig,
+    /// Prices per model id, added to (or replacing) the built-in table
+    pub pricing: std::collections::BTreeMap<String, ModelPrice>,
+    pub response_cache: ResponseCacheConfig,
+    pub metrics: MetricsConfig,
+    pub mission_tmp: MissionTmpConfig,
+    /// Language of prompts and messages for workspaces and users without
+    /// their own setting
+    pub locale: String,
+}
+
+/// Scratch directories missions get for intermediate files (`$MISSION_TMP`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MissionTmpConfig {
+    /// Where the per-mission directories go; `spawn-missions` in the
+    /// system temp directory when unset
+    pub dir: Option<std::path::PathBuf>,
+    /// A mission whose directory grows past this is stopped
+    pub quota_mb: u64,
+    /// Directories untouched for this long are removed
+    pub retention_hours: u64,
+}
+
+impl Default for MissionTmpConfig {
+    fn default() -> Self {
+        Self { dir: None, quota_mb: 512, retention_hours: 24 }
+    }
+}
+
+/// Reuse of responses to repeated deterministic (temperature 0) requests
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseCacheConfig {
+    pub enabled: bool,
+    /// Responses kept in memory, least recently used dropped first
+    pub capacity: usize,
+    /// Also keep responses in the database, so they outlive a restart
+    pub persist: bool,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self { enabled: false, capacity: 1000, persist: false }
+    }
+}
+
+/// Export of LLM usage metrics
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve them at `/metrics` in the Prometheus text format
+    pub prometheus: bool,
+}
+
+/// Formatting of files agents write. A workspace can override both fields
+/// in its own `.spawn-format.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FormatConfig {
+    /// Run written files through their language's formatter
+    pub on_write: bool,
+    /// Formatter command per file extension, reading the file on stdin and
+    /// printing it formatted; `{path}` is replaced by the file's path. Added
+    /// to (or replacing) the built-in rustfmt, prettier and black commands.
+    pub formatters: std::collections::BTreeMap<String, Vec<String>>,
+}
+
+/// How a mission step's prompt is fitted into the model's context window
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextConfig {
+    pub window_tokens: usize,
+    /// Left free for the completion
+    pub reserve_tokens: usize,
+    pub weights: ContextWeights,
+    /// Mark the end of the pinned sections as a prompt cache breakpoint, so
+    /// providers that cache prompts reuse them across steps
+    pub prompt_caching: bool,
+    /// When a mission's conversation outgrows the prompt budget, replace its
+    /// older turns with an LLM summary rather than dropping them
+    pub summarize: bool,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self { window_tokens: 32_000, reserve_tokens: 4_000, weights: ContextWeights::default(), prompt_caching: true, summarize: true }
+    }
+}
+
+impl ContextConfig {
+    /// Tokens the prompt may use
+    pub fn prompt_tokens(&self) -> usize {
+        self.window_tokens.saturating_sub(self.reserve_tokens)
+    }
+}
+
+/// Relative shares of the prompt budget per section. A section needing less
+/// than its share passes the rest on to the others.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextWeights {
+    /// System prompt and goal
+    pub system: f32,
+    /// The mission's planned steps
+    pub plan: f32,
+    /// Knowledge base chunks
+    pub retrieved: f32,
+    /// The model's turns and other conversation
+    pub recent: f32,
+    pub tool_results: f32,
+}
+
+impl Default for ContextWeights {
+    fn default() -> Self {
+        Self { system: 1.0, plan: 0.5, retrieved: 1.5, recent: 3.0, tool_results: 2.0 }
+    }
+}
+
+/// Where mission logs are kept
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogStorageConfig {
+    pub backend: LogBackend,
+    /// Directory of the `jsonl` backend, one `<mission id>.jsonl` per mission
+    pub dir: std::path::PathBuf,
+    /// Shell command run on a finished mission's log file, e.g.
+    /// `aws s3 cp {file} s3://bucket/spawn-logs/{mission_id}.jsonl`
+    pub ship_command: Option<String>,
+}
+
+impl Default for LogStorageConfig {
+    fn default() -> Self {
+        Self { backend: LogBackend::Database, dir: "logs".into(), ship_command: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogBackend {
+    /// The `mission_logs` table
+    #[default]
+    Database,
+    /// Append-only JSON lines files, keeping high-volume traces out of the database
+    Jsonl,
+}
+
+/// Background health probes of the LLM providers
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProviderHealthConfig {
+    /// Seconds between probes; 0 turns them off
+    pub interval_secs: u64,
+    /// A probe slower than this fails
+    pub timeout_secs: u64,
+    /// Percentage of failed requests and probes in the window that opens a
+    /// provider's circuit, skipping it for `open_secs`
+    pub failure_rate_percent: u32,
+    /// Requests and probes in the window before the rate counts
+    pub min_requests: u32,
+    pub window_secs: u64,
+    pub open_secs: u64,
+}
+
+impl Default for ProviderHealthConfig {
+    fn default() -> Self {
+        Self { interval_secs: 60, timeout_secs: 10, failure_rate_percent: 50, min_requests: 5, window_secs: 60, open_secs: 30 }
+    }
+}
+
+/// How many missions run at once, and whether urgent ones may bump others
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerConfig {
+    /// Missions running at once on this node; the rest queue by priority
+    pub max_concurrent: usize,
+    /// Pause a lower-priority mission at its next step boundary when a
+    /// higher-priority one is waiting
+    pub preemption: bool,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self { max_concurrent: 4, preemption: false }
+    }
+}
+
+/// What to do when a workspace lock is already held
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockPolicy {
+    pub on_conflict: LockConflict,
+    /// How long `wait` waits before giving up
+    pub wait_secs: u64,
+    /// Lifetime of a lock whose holder stops refreshing it (e.g. crashed)
+    pub ttl_secs: u64,
+}
+
+impl Default for LockPolicy {
+    fn default() -> Self {
+        Self { on_conflict: LockConflict::Wait, wait_secs: 30, ttl_secs: 120 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockConflict {
+    /// Retry until the lock frees up or `wait_secs` pass
+    #[default]
+    Wait,
+    /// Fail straight away with `SpawnError::Locked`
+    Fail,
+}
+
+/// This node's identity in a cluster of spawn-api instances sharing one database
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterConfig {
+    pub node_id: String,
+    /// Base URL other nodes use to reach this one, e.g. `http://10.0.0.5:3000`
+    pub advertise_url: String,
+    pub affinity: Affinity,
+}
+
+/// How a request for a session owned by another node reaches it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Affinity {
+    /// Relay the request (and WebSocket) through the receiving node
+    #[default]
+    Proxy,
+    /// Answer `307 Temporary Redirect` to the owner; clients must reach nodes directly
+    Redirect,
+}
+
+/// The `[cluster]` table
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClusterSection {
+    /// Defaults to the advertised URL
+    node_id: Option<String>,
+    advertise_url: Option<String>,
+    affinity: Affinity,
+}
+
+impl ClusterSection {
+    fn resolve(self) -> Option<ClusterConfig> {
+        let advertise_url = self.advertise_url.filter(|u| !u.trim().is_empty())?;
+        let advertise_url = advertise_url.trim_end_matches('/').to_string();
+        Some(ClusterConfig {
+            node_id: self.node_id.unwrap_or_else(|| advertise_url.clone()),
+            advertise_url,
+            affinity: self.affinity,
+        })
+    }
+}
+
+/// One LLM provider's credentials and limits (`[providers.<name>]`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderConfig {
+    pub api_key: Option<String>,
+    /// Override the provider's default endpoint
+    pub base_url: Option<String>,
+    pub requests_per_minute: Option<u32>,
+    pub max_concurrent: Option<usize>,
+    /// How long a local model stays loaded after a request (Ollama)
+    pub keep_alive: Option<String>,
+    /// Deployment serving models not named `azure/<deployment>` (Azure)
+    pub deployment: Option<String>,
+    /// `api-version` query parameter (Azure)
+    pub api_version: Option<String>,
+    /// AWS region (Bedrock)
+    pub region: Option<String>,
+    /// Access key; `AWS_ACCESS_KEY_ID` and friends when unset (Bedrock)
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    /// For temporary credentials (Bedrock)
+    pub session_token: Option<String>,
+    /// Block threshold per harm category, e.g. `harassment = "block_only_high"` (Gemini)
+    #[serde(default)]
+    pub safety_settings: std::collections::BTreeMap<String, String>,
+    /// Tries per request, the first included (OpenRouter)
+    pub max_attempts: Option<u32>,
+    /// Wait before the first retry, doubling after each (OpenRouter)
+    pub retry_backoff_ms: Option<u64>,
+    /// Longest wait between retries (OpenRouter)
+    pub retry_max_backoff_ms: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+    /// Longest silence while waiting for response bytes
+    pub read_timeout_secs: Option<u64>,
+}
+
+impl ProviderConfig {
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit { requests_per_minute: self.requests_per_minute, max_concurrent: self.max_concurrent }
+    }
+    
+    /// The retry policy, with defaults for whatever is unset
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(default.max_attempts).max(1),
+            initial_backoff: self.retry_backoff_ms.map(std::time::Duration::from_millis).unwrap_or(default.initial_backoff),
+            max_backoff: self.retry_max_backoff_ms.map(std::time::Duration::from_millis).unwrap_or(default.max_backoff),
+        }
+    }
+    
+    /// The HTTP timeouts, with defaults for whatever is unset
+    pub fn timeouts(&self) -> HttpTimeouts {
+        let default = HttpTimeouts::default();
+        HttpTimeouts {
+            connect: self.connect_timeout_secs.map(std::time::Duration::from_secs).unwrap_or(default.connect),
+            read: self.read_timeout_secs.map(std::time::Duration::from_secs).unwrap_or(default.read),
+        }
+    }
+}
+
+/// How hard a provider may be called; requests over the limit wait their
+/// turn instead of being sent and refused
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimit {
+    /// Sustained rate, allowing bursts of up to a minute's worth
+    pub requests_per_minute: Option<u32>,
+    /// Requests in flight at once, streams included
+    pub max_concurrent: Option<usize>,
+}
+
+/// How failed provider requests are tried again. Only transient failures
+/// (429, 5xx, connection errors) are retried, after a jittered wait that
+/// doubles each time, or as long as the provider's `Retry-After` asks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Tries per request, the first included; 1 turns retries off
+    pub max_attempts: u32,
+    pub initial_backoff: std::time::Duration,
+    pub max_backoff: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: std::time::Duration::from_millis(500),
+            max_backoff: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+}
+
+/// How long a provider's HTTP client waits. The read timeout is per read,
+/// not for the whole response, so a long stream isn't cut off while it
+/// flows; `ChatOptions::timeout_secs` bounds a whole call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HttpTimeouts {
+    pub connect: std::time::Duration,
+    pub read: std::time::Duration,
+}
+
+impl Default for HttpTimeouts {
+    fn default() -> Self {
+        Self { connect: std::time::Duration::from_secs(10), read: std::time::Duration::from_secs(300) }
+    }
+}
+
+/// Models used when a request doesn't name one; unset means the built-in default
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelDefaults {
+    /// Mission agent loop
+    pub mission: Option<String>,
+    /// `/api/chat`
+    pub chat: Option<String>,
+    /// Failed mission analysis
+    pub postmortem: Option<String>,
+    /// LLM search reranker
+    pub rerank: Option<String>,
+    /// Longest a mission step's or chat's model call may take, retries
+    /// included; unlimited when unset
+    pub request_timeout_secs: Option<u64>,
+    /// Per-task rules, ahead of the defaults above; the first match wins
+    pub routes: Vec<RoutingRule>,
+}
+
+/// What a model call is for, as far as model routing is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// A mission's first step, before any tool has run
+    Planning,
+    /// A mission's later steps
+    Execution,
+    Chat,
+    Summarization,
+    Postmortem,
+    Rerank,
+    Embedding,
+}
+
+/// Send `task` calls to `model`, optionally only while the mission has spent
+/// no more than `max_spent_usd`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingRule {
+    pub task: TaskKind,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_spent_usd: Option<f64>,
+}
+
+impl RoutingRule {
+    pub fn matches(&self, task: TaskKind, spent_usd: f64) -> bool {
+        self.task == task && self.max_spent_usd.is_none_or(|max| spent_usd <= max)
+    }
+}
+
+/// Limits on interactive terminals
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TerminalLimits {
+    /// Concurrent terminal connections
+    pub max_sessions: usize,
+}
+
+/// OpenAI-compatible audio endpoint settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpeechConfig {
+    pub api_url: String,
+    pub api_key: String,
+    pub model: String,
+    /// Voice name (text-to-speech only)
+    pub voice: Option<String>,
+}
+
+/// The `[stt]`/`[tts]` table before the API key fallback is resolved
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpeechSection {
+    api_url: String,
+    api_key: Option<String>,
+    model: String,
+    voice: Option<String>,
+}
+
+impl SpeechSection {
+    /// Enabled with its own key, or the OpenAI provider key
+    fn resolve(self, providers: &HashMap<String, ProviderConfig>) -> Option<SpeechConfig> {
+        let api_key = self.api_key.or_else(|| providers.get("openai")?.api_key.clone())?;
+        Some(SpeechConfig { api_url: self.api_url, api_key, model: self.model, voice: self.voice })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServerSection {
+    host: String,
+    port: u16,
+}
+
+/// The merged configuration document, as written in `spawn.toml`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    database_url: String,
+    workspace_root: Option<std::path::PathBuf>,
+    server: ServerSection,
+    #[serde(default)]
+    providers: HashMap<String, ProviderConfig>,
+    #[serde(default)]
+    models: ModelDefaults,
+    terminal: TerminalLimits,
+    stt: SpeechSection,
+    tts: SpeechSection,
+    #[serde(default)]
+    retention: RetentionPolicy,
+    #[serde(default)]
+    cluster: ClusterSection,
+    #[serde(default)]
+    locks: LockPolicy,
+    #[serde(default)]
+    scheduler: SchedulerConfig,
+    #[serde(default)]
+    provider_health: ProviderHealthConfig,
+    #[serde(default)]
+    log_storage: LogStorageConfig,
+    #[serde(default)]
+    context: ContextConfig,
+    #[serde(default)]
+    format: FormatConfig,
+    #[serde(default)]
+    pricing: std::collections::BTreeMap<String, ModelPrice>,
+    #[serde(default)]
+    response_cache: ResponseCacheConfig,
+    #[serde(default)]
+    metrics: MetricsConfig,
+    #[serde(default)]
+    mission_tmp: MissionTmpConfig,
+    locale: String,
+}
+
+impl Config {
+    /// Defaults, overlaid with the config file and then environment variables
+    pub fn from_env() -> Result<Self> {
+        Self::load(serde_json::Value::Null)
+    }
+    
+    /// Like `from_env`, with `overrides` (same shape as the config file)
+    /// applied on top of everything else
+    pub fn load(overrides: serde_json::Value) -> Result<Self> {
+        let mut document = config_defaults();
+        // Where each key last came from, for error messages
+        let mut sources: HashMap<String, String> = HashMap::new();
+        
+        if let Some(path) = config_file_path()? {
+            let layer = read_config_file(&path)?;
+            record_sources(&layer, "", &path.display().to_string(), &mut sources);
+            merge_config(&mut document, layer);
+        }
+        
+        for &(var, key, kind) in ENV_KEYS {
+            let Ok(raw) = std::env::var(var) else {
+                continue;
+            };
+            let value = match kind {
+                EnvKind::Text => serde_json::Value::String(raw),
+                EnvKind::Number => raw.trim().parse::<u64>().map(serde_json::Value::from).map_err(|_| {
+                    config_error(key, &format!("expected a non-negative integer, got '{}'", raw), Some(var))
+                })?,
+                EnvKind::Flag => match raw.trim().to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => serde_json::Value::Bool(true),
+                    "" | "0" | "false" | "no" | "off" => serde_json::Value::Bool(false),
+                    _ => return Err(config_error(key, &format!("expected true or false, got '{}'", raw), Some(var))),
+                },
+            };
+            let layer = key.rsplit('.').fold(value, |inner, part| serde_json::json!({ part: inner }));
+            sources.insert(key.to_string(), var.to_string());
+            merge_config(&mut document, layer);
+        }
+        
+        if !overrides.is_null() {
+            record_sources(&overrides, "", "overrides", &mut sources);
+            merge_config(&mut document, overrides);
+        }
+        
+        let file: ConfigFile = serde_path_to_error::deserialize(document).map_err(|e| {
+            let key = e.path().to_string();
+            let source = sources.get(&key).map(String::as_str);
+            config_error(&key, &e.into_inner().to_string(), source)
+        })?;
+        Self::from_file(file)
+    }
+    
+    /// Validate the merged document and resolve fallbacks
+    fn from_file(file: ConfigFile) -> Result<Self> {
+        let openrouter_api_key = match file.providers.get("openrouter")
+            .and_then(|p| p.api_key.clone())
+            .filter(|k| !k.trim().is_empty())
+        {
+            Some(key) => key,
+            // Missions run on local models, an Azure deployment, Gemini or Bedrock instead
+            None if ["ollama", "azure", "gemini", "bedrock"].iter().any(|p| file.providers.contains_key(*p)) => String::new(),
+            None => return Err(config_error(
+                "providers.openrouter.api_key",
+                "is required unless [providers.ollama], [providers.azure], [providers.gemini] or [providers.bedrock] is configured; \
+                 set OPENROUTER_API_KEY or add it to spawn.toml",
+                None,
+            )),
+        };
+        if let Some(azure) = file.providers.get("azure") {
+            for (key, value) in [("base_url", &azure.base_url), ("api_key", &azure.api_key), ("deployment", &azure.deployment)] {
+                if value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                    return Err(config_error(&format!("providers.azure.{}", key), "is required for Azure OpenAI", None));
+                }
+            }
+        }
+        if let Some(gemini) = file.providers.get("gemini") {
+            if gemini.api_key.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                return Err(config_error("providers.gemini.api_key", "is required for Gemini", None));
+            }
+        }
+        if let Some(bedrock) = file.providers.get("bedrock") {
+            if bedrock.region.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                return Err(config_error("providers.bedrock.region", "is required for Bedrock", None));
+            }
+            if bedrock.access_key_id.is_some() != bedrock.secret_access_key.is_some() {
+                return Err(config_error("providers.bedrock", "access_key_id and secret_access_key go together", None));
+            }
+        }
+        for (name, provider) in &file.providers {
+            const THRESHOLDS: [&str; 5] = ["block_none", "block_only_high", "block_medium_and_above", "block_low_and_above", "off"];
+            if let Some((category, _)) = provider.safety_settings.iter().find(|(_, t)| !THRESHOLDS.contains(&t.to_lowercase().as_str())) {
+                return Err(config_error(
+                    &format!("providers.{}.safety_settings.{}", name, category),
+                    &format!("must be one of {}", THRESHOLDS.join(", ")),
+                    None,
+                ));
+            }
+            for (key, value) in [("connect_timeout_secs", provider.connect_timeout_secs), ("read_timeout_secs", provider.read_timeout_secs)] {
+                if value == Some(0) {
+                    return Err(config_error(&format!("providers.{}.{}", name, key), "must be at least 1", None));
+                }
+            }
+        }
+        if file.models.request_timeout_secs == Some(0) {
+            return Err(config_error("models.request_timeout_secs", "must be at least 1", None));
+        }
+        if file.database_url.trim().is_empty() {
+            return Err(config_error("database_url", "must not be empty", None));
+        }
+        if file.server.port == 0 {
+            return Err(config_error("server.port", "must be between 1 and 65535", None));
+        }
+        if file.terminal.max_sessions == 0 {
+            return Err(config_error("terminal.max_sessions", "must be at least 1", None));
+        }
+        if let Some(url) = file.cluster.advertise_url.as_deref().filter(|u| !u.is_empty()) {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(config_error("cluster.advertise_url", "must be an http(s) URL", None));
+            }
+        }
+        if file.locks.ttl_secs == 0 {
+            return Err(config_error("locks.ttl_secs", "must be at least 1", None));
+        }
+        if file.provider_health.timeout_secs == 0 {
+            return Err(config_error("provider_health.timeout_secs", "must be at least 1", None));
+        }
+        if !(1..=100).contains(&file.provider_health.failure_rate_percent) {
+            return Err(config_error("provider_health.failure_rate_percent", "must be between 1 and 100", None));
+        }
+        for (key, value) in [
+            ("provider_health.min_requests", u64::from(file.provider_health.min_requests)),
+            ("provider_health.window_secs", file.provider_health.window_secs),
+            ("provider_health.open_secs", file.provider_health.open_secs),
+        ] {
+            if value == 0 {
+                return Err(config_error(key, "must be at least 1", None));
+            }
+        }
+        if file.scheduler.max_concurrent == 0 {
+            return Err(config_error("scheduler.max_concurrent", "must be at least 1", None));
+        }
+        if file.log_storage.backend == LogBackend::Jsonl && file.log_storage.dir.as_os_str().is_empty() {
+            return Err(config_error("log_storage.dir", "must not be empty with the jsonl backend", None));
+        }
+        if file.context.prompt_tokens() == 0 {
+            return Err(config_error("context.reserve_tokens", "must be less than context.window_tokens", None));
+        }
+        let weights = &file.context.weights;
+        let weights = [weights.system, weights.plan, weights.retrieved, weights.recent, weights.tool_results];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f32>() <= 0.0 {
+            return Err(config_error("context.weights", "must be non-negative and not all zero", None));
+        }
+        let Some(locale) = normalize_locale(&file.locale) else {
+            return Err(config_error("locale", &format!("'{}' is not a language tag like en or pt-BR", file.locale), None));
+        };
+        if let Some(root) = file.workspace_root.as_ref().filter(|r| !r.is_dir()) {
+            return Err(config_error("workspace_root", &format!("{} is not a directory", root.display()), None));
+        }
+        
+        let mut retention = file.retention;
+        // Zero means "no limit", as it always has for the environment variables
+        retention.max_age_days = retention.max_age_days.filter(|&n| n > 0);
+        retention.max_messages_per_session = retention.max_messages_per_session.filter(|&n| n > 0);
+        retention.verbose_log_days = retention.verbose_log_days.filter(|&n| n > 0);
+        
+        Ok(Self {
+            database_url: file.database_url,
+            openrouter_api_key,
+            server_host: file.server.host,
+            server_port: file.server.port,
+            workspace_root: file.workspace_root,
+            stt: file.stt.resolve(&file.providers),
+            tts: file.tts.resolve(&file.providers),
+            providers: file.providers,
+            models: file.models,
+            terminal: file.terminal,
+            retention,
+            cluster: file.cluster.resolve(),
+            locks: file.locks,
+            scheduler: file.scheduler,
+            provider_health: file.provider_health,
+            log_storage: LogStorageConfig {
+                ship_command: file.log_storage.ship_command.filter(|c| !c.trim().is_empty()),
+                ..file.log_storage
+            },
+            context: file.context,
+            format: file.format,
+            pricing: file.pricing,
+            response_cache: file.response_cache,
+            metrics: file.metrics,
+            mission_tmp: file.mission_tmp,
+            locale,
+        })
+    }
+    
+    /// API key configured for a provider
+    pub fn provider_key(&self, provider: &str) -> Option<&str> {
+        self.providers.get(provider)?.api_key.as_deref()
+    }
+}
+
+/// `SPAWN_CONFIG` if set (it must exist), else the first of `CONFIG_FILES` present
+fn config_file_path() -> Result<Option<std::path::PathBuf>> {
+    if let Ok(path) = std::env::var("SPAWN_CONFIG") {
+        let path = std::path::PathBuf::from(path);
+        if !path.is_file() {
+            return Err(SpawnError::Config(format!("SPAWN_CONFIG: {} does not exist", path.display())));
+        }
+        return Ok(Some(path));
+    }
+    Ok(CONFIG_FILES.iter().map(std::path::PathBuf::from).find(|p| p.is_file()))
+}
+
+/// Parse a TOML or JSON config file, by extension
+fn read_config_file(path: &std::path::Path) -> Result<serde_json::Value> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| SpawnError::Config(format!("{}: {}", path.display(), e)))?;
+    let parsed = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        _ => toml::from_str(&text).map_err(|e| e.to_string()),
+    };
+    parsed.map_err(|e| SpawnError::Config(format!("{}: {}", path.display(), e.trim_end())))
+}
+
+/// Overlay `layer` onto `base`, recursing into tables
+fn merge_config(base: &mut serde_json::Value, layer: serde_json::Value) {
+    match (base, layer) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Note `source` against every dotted key set in `layer`
+fn record_sources(layer: &serde_json::Value, prefix: &str, source: &str, sources: &mut HashMap<String, String>) {
+    let Some(table) = layer.as_object() else {
+        sources.insert(prefix.to_string(), source.to_string());
+        return;
+    };
+    for (key, value) in table {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        record_sources(value, &path, source, sources);
+    }
+}
+
+fn config_error(key: &str, problem: &str, source: Option<&str>) -> SpawnError {
+    let key = if key.is_empty() || key == "." { "(root)" } else { key };
+    match source {
+        Some(source) => SpawnError::Config(format!("{}: {} (from {})", key, problem, source)),
+        None => SpawnError::Config(format!("{}: {}", key, problem)),
+    }
+}
+
+/// Limits on stored chat history and conversation embeddings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionPolicy {
+    /// Delete conversation memory older than this many days
+    pub max_age_days: Option<u32>,
+    /// Keep only the newest N messages of each chat session
+    pub max_messages_per_session: Option<u32>,
+    /// Redact emails, phone numbers, card numbers and secrets before storage
+    pub scrub_pii: bool,
+    /// Keep the verbose tier of mission logs this many days; the concise
+    /// tier is kept
+    pub verbose_log_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Whether the cleanup job has anything to prune
+    pub fn prunes(&self) -> bool {
+        self.max_age_days.is_some() || self.max_messages_per_session.is_some()
+    }
+}
@@ -0,0 +1,53 @@
+//! Diagnostics from linters and compilers, and test coverage
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// A file/line diagnostic from a linter or compiler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Path relative to the workspace root
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub end_line: Option<u32>,
+    pub end_column: Option<u32>,
+    pub severity: Severity,
+    /// Rule or error code (e.g. `clippy::needless_return`, `E0308`, `F401`)
+    pub code: Option<String>,
+    pub message: String,
+    /// Tool that produced it (clippy, eslint, ruff, ...)
+    pub source: String,
+}
+
+
+/// Line coverage for one file, from a test run with coverage enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCoverage {
+    /// Path relative to the workspace root
+    pub file: String,
+    pub lines_total: u32,
+    pub lines_covered: u32,
+    /// Executable lines that were never hit
+    pub uncovered_lines: Vec<u32>,
+    /// Tool that produced it (llvm-cov, istanbul, coverage.py)
+    pub source: String,
+}
+
+impl FileCoverage {
+    pub fn percent(&self) -> f64 {
+        if self.lines_total == 0 {
+            100.0
+        } else {
+            self.lines_covered as f64 * 100.0 / self.lines_total as f64
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Versioned serialization of stored values
+
+use serde::{Deserialize, Serialize};
+use crate::{ChatMessage, LogEntry, Result, SpawnError};
+
+/// How a persisted value is stored: its schema version alongside it, so
+/// rows written by older releases can be upgraded on read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub payload: T,
+}
+
+/// A type that is persisted and must stay readable as it changes. When its
+/// serialized shape changes, bump `VERSION` and teach `upgrade` the step
+/// from the previous version.
+pub trait Versioned: Serialize + serde::de::DeserializeOwned {
+    const VERSION: u32;
+    
+    /// Rewrite a payload stored at `from` into the shape of `from + 1`.
+    /// Version 0 is whatever was stored before envelopes were used.
+    fn upgrade(from: u32, _payload: serde_json::Value) -> Result<serde_json::Value> {
+        Err(SpawnError::Internal(format!("No upgrade from version {} of {}", from, std::any::type_name::<Self>())))
+    }
+}
+
+/// Serialize `value` inside an envelope at its current version
+pub fn to_envelope<T: Versioned>(value: &T) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(Envelope { version: T::VERSION, payload: value })?)
+}
+
+/// Read a value stored with `to_envelope`, upgrading it if it is older.
+/// Anything that isn't an envelope is taken as a version 0 payload.
+pub fn from_envelope<T: Versioned>(stored: serde_json::Value) -> Result<T> {
+    let is_envelope = stored.as_object()
+        .is_some_and(|o| o.len() == 2 && o.get("version").is_some_and(|v| v.is_u64()) && o.contains_key("payload"));
+    let (mut version, mut payload) = if is_envelope {
+        let envelope: Envelope<serde_json::Value> = serde_json::from_value(stored)?;
+        (envelope.version, envelope.payload)
+    } else {
+        (0, stored)
+    };
+    if version > T::VERSION {
+        return Err(SpawnError::Internal(format!(
+            "{} version {} was written by a newer release (this one reads up to {})",
+            std::any::type_name::<T>(), version, T::VERSION
+        )));
+    }
+    while version < T::VERSION {
+        payload = T::upgrade(version, payload)?;
+        version += 1;
+    }
+    Ok(serde_json::from_value(payload)?)
+}
+
+/// Message arrays, as saved for step contexts
+impl Versioned for Vec<ChatMessage> {
+    const VERSION: u32 = 1;
+    
+    fn upgrade(from: u32, payload: serde_json::Value) -> Result<serde_json::Value> {
+        match from {
+            // Bare arrays from before envelopes have the version 1 shape
+            0 => Ok(payload),
+            _ => Err(SpawnError::Internal(format!("No upgrade from version {} of messages", from))),
+        }
+    }
+}
+
+impl Versioned for LogEntry {
+    const VERSION: u32 = 1;
+    
+    fn upgrade(from: u32, payload: serde_json::Value) -> Result<serde_json::Value> {
+        match from {
+            0 => Ok(payload),
+            _ => Err(SpawnError::Internal(format!("No upgrade from version {} of log entries", from))),
+        }
+    }
+}
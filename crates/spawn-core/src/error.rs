@@ -0,0 +1,162 @@
+//! The error type shared across crates
+
+use thiserror::Error;
+use crate::{MissionStatus, Service};
+
+#[derive(Error, Debug)]
+pub enum SpawnError {
+    #[error("LLM Provider Error: {0}")]
+    ProviderError(String),
+    
+    /// The provider throttled us (HTTP 429)
+    #[error("Rate limited: {message}")]
+    RateLimited { message: String, retry_after: Option<std::time::Duration> },
+    
+    /// The provider rejected our credentials (HTTP 401/403)
+    #[error("Authentication failed: {0}")]
+    AuthFailed(String),
+    
+    /// The provider couldn't be reached or failed on its side (HTTP 5xx, timeouts)
+    #[error("Provider unavailable: {0}")]
+    ProviderUnavailable(String),
+    
+    #[error("Tool Execution Error: {0}")]
+    ToolError(String),
+    
+    #[error("Orchestrator Error: {0}")]
+    OrchestrationError(String),
+    
+    #[cfg(feature = "db")]
+    #[error("Database Error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+    
+    #[cfg(feature = "db")]
+    #[error("Migration Error: {0}")]
+    MigrationError(#[from] sqlx::migrate::MigrateError),
+    
+    #[error("Serialization Error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+    
+    #[error("Internal Error: {0}")]
+    Internal(String),
+    
+    /// Invalid or missing configuration, naming the offending key
+    #[error("Config Error: {0}")]
+    Config(String),
+    
+    /// A step, token, cost or time ceiling was hit
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+    
+    /// A prompt too large for the model's context window, caught before sending
+    #[error("Context overflow: prompt of {tokens} tokens exceeds the {limit}-token limit")]
+    ContextOverflow { tokens: usize, limit: usize },
+    
+    /// The operation's cancellation token fired before it finished
+    #[error("Cancelled")]
+    Cancelled,
+    
+    /// A workspace lock is held by someone else
+    #[error("Locked: {resource} is held by {holder}")]
+    Locked { resource: String, holder: String },
+    
+    /// A file someone else has unsaved or pending changes to
+    #[error("Reserved: {path} has changes pending by {owner}")]
+    Reserved { path: String, owner: String },
+    
+    /// A mission status change its current status doesn't allow
+    #[error("Invalid mission status transition: {from:?} -> {to:?}")]
+    InvalidTransition { from: MissionStatus, to: MissionStatus },
+    
+    /// The named record doesn't exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+    
+    /// An optional service the operation depends on is down or not configured
+    #[error("Service unavailable: {0} is down or not configured")]
+    ServiceUnavailable(Service),
+}
+
+impl SpawnError {
+    /// Classify a failed provider HTTP response
+    pub fn from_status(status: u16, body: impl Into<String>, retry_after: Option<std::time::Duration>) -> Self {
+        let message = format!("API error {}: {}", status, body.into());
+        match status {
+            401 | 403 => Self::AuthFailed(message),
+            429 => Self::RateLimited { message, retry_after },
+            408 | 500..=599 => Self::ProviderUnavailable(message),
+            _ => Self::ProviderError(message),
+        }
+    }
+    
+    /// Stable machine-readable identifier, for API clients and metrics
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ProviderError(_) => "provider_error",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::AuthFailed(_) => "auth_failed",
+            Self::ProviderUnavailable(_) => "provider_unavailable",
+            Self::ToolError(_) => "tool_error",
+            Self::OrchestrationError(_) => "orchestration_error",
+            #[cfg(feature = "db")]
+            Self::DatabaseError(_) => "database_error",
+            #[cfg(feature = "db")]
+            Self::MigrationError(_) => "migration_error",
+            Self::SerializationError(_) => "serialization_error",
+            Self::Internal(_) => "internal",
+            Self::Config(_) => "config_invalid",
+            Self::BudgetExceeded(_) => "budget_exceeded",
+            Self::ContextOverflow { .. } => "context_overflow",
+            Self::Cancelled => "cancelled",
+            Self::Locked { .. } => "locked",
+            Self::Reserved { .. } => "reserved",
+            Self::InvalidTransition { .. } => "invalid_transition",
+            Self::NotFound(_) => "not_found",
+            Self::ServiceUnavailable(_) => "service_unavailable",
+        }
+    }
+    
+    /// Whether the same call may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } | Self::ProviderUnavailable(_) | Self::Locked { .. } | Self::ServiceUnavailable(_) => true,
+            #[cfg(feature = "db")]
+            Self::DatabaseError(e) => matches!(e, sqlx::Error::PoolTimedOut | sqlx::Error::Io(_)),
+            _ => false,
+        }
+    }
+    
+    /// Suggested wait before retrying, when the provider gave one
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+    
+    /// The service whose absence caused this error, for the
+    /// `missing_capability` field of API errors
+    pub fn missing_capability(&self) -> Option<Service> {
+        match self {
+            Self::ServiceUnavailable(service) => Some(*service),
+            _ => None,
+        }
+    }
+    
+    /// HTTP status an API handler should answer with
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Self::RateLimited { .. } => 429,
+            Self::ProviderUnavailable(_) | Self::ServiceUnavailable(_) => 503,
+            // Upstream failures, including our own bad provider credentials
+            Self::ProviderError(_) | Self::AuthFailed(_) => 502,
+            Self::BudgetExceeded(_) => 402,
+            Self::ContextOverflow { .. } => 413,
+            Self::NotFound(_) => 404,
+            Self::Cancelled | Self::Locked { .. } | Self::Reserved { .. } | Self::InvalidTransition { .. } => 409,
+            _ => 500,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SpawnError>;
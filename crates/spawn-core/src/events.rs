@@ -0,0 +1,102 @@
+//! Mission events and hooks
+
+use serde::{Deserialize, Serialize};
+use crate::{Citation, Mission, MissionId, MissionPriority, MissionStatus, Result, SpawnError, ToolProgress};
+
+/// Something that happened during a mission's run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MissionEvent {
+    Created { mission_id: MissionId, goal: String },
+    /// Waiting for a slot behind `position` other missions
+    Queued { mission_id: MissionId, priority: MissionPriority, position: usize },
+    /// Paused at a step boundary to give its slot to `by`
+    Preempted { mission_id: MissionId, by: MissionId },
+    /// Got a slot back after being preempted
+    Resumed { mission_id: MissionId },
+    StepStarted { mission_id: MissionId, step: usize },
+    /// A running tool reported progress
+    ToolProgress { mission_id: MissionId, tool: String, progress: ToolProgress },
+    ToolExecuted { mission_id: MissionId, tool: String, success: bool },
+    /// A tool wrote these workspace files; they are on disk when this is sent
+    FilesChanged { mission_id: MissionId, tool: String, paths: Vec<String> },
+    /// A line appended to the mission log
+    LogLine { mission_id: MissionId, agent: String, content: String },
+    Completed {
+        mission_id: MissionId,
+        summary: String,
+        /// Knowledge base chunks the final answer cited
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        citations: Vec<Citation>,
+    },
+    Failed { mission_id: MissionId, error: String },
+    Cancelled { mission_id: MissionId },
+}
+
+impl MissionEvent {
+    pub fn mission_id(&self) -> &str {
+        match self {
+            Self::Created { mission_id, .. }
+            | Self::Queued { mission_id, .. }
+            | Self::Preempted { mission_id, .. }
+            | Self::Resumed { mission_id }
+            | Self::StepStarted { mission_id, .. }
+            | Self::ToolProgress { mission_id, .. }
+            | Self::ToolExecuted { mission_id, .. }
+            | Self::FilesChanged { mission_id, .. }
+            | Self::LogLine { mission_id, .. }
+            | Self::Completed { mission_id, .. }
+            | Self::Failed { mission_id, .. }
+            | Self::Cancelled { mission_id } => mission_id,
+        }
+    }
+    
+    /// Whether this is the mission's last event
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed { .. } | Self::Failed { .. } | Self::Cancelled { .. })
+    }
+}
+
+/// Stream of mission events from the moment of subscription
+pub type EventStream = futures::stream::BoxStream<'static, MissionEvent>;
+
+/// Event bus trait - publish/subscribe for mission events
+pub trait EventBus: Send + Sync {
+    /// Deliver an event to current subscribers; never blocks
+    fn publish(&self, event: MissionEvent);
+    
+    /// Receive every event published from now on
+    fn subscribe(&self) -> EventStream;
+}
+
+/// Custom code run by the orchestrator at points in a mission's life, for
+/// validation, notifications or metrics without patching the orchestrator.
+/// Every method does nothing by default; hooks run in registration order.
+#[async_trait::async_trait]
+pub trait MissionHook: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// Once the mission has a slot, before its first step; an error fails
+    /// the mission
+    async fn on_start(&self, _mission: &Mission) -> Result<()> {
+        Ok(())
+    }
+
+    /// Before each step's LLM call; an error fails the mission
+    async fn on_step(&self, _mission: &Mission, _step: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// Before a tool runs; an error refuses the call, and the model is told why
+    async fn on_tool_call(&self, _mission: &Mission, _tool: &str, _args: &serde_json::Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// After a tool ran, with what it returned
+    async fn on_tool_result(&self, _mission: &Mission, _tool: &str, _result: &Result<serde_json::Value>) {}
+
+    /// After the mission ended as `status` (completed, failed or cancelled),
+    /// with the error it failed with
+    async fn on_complete(&self, _mission: &Mission, _status: &MissionStatus, _error: Option<&SpawnError>) {}
+}
@@ -0,0 +1,134 @@
+//! ID newtypes for missions, tasks and agents
+
+use serde::{Deserialize, Serialize};
+use crate::{Result, SpawnError};
+
+/// Declares a string ID newtype. IDs deref to `&str` and compare against
+/// strings, so lookups and formatting read as before, but a `TaskId` can no
+/// longer be passed where a `MissionId` is expected.
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[cfg_attr(feature = "db", derive(sqlx::Type), sqlx(transparent))]
+        #[serde(transparent)]
+        pub struct $name(String);
+        
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+            
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+        
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+        
+        impl std::str::FromStr for $name {
+            type Err = SpawnError;
+            
+            fn from_str(s: &str) -> Result<Self> {
+                let s = s.trim();
+                if s.is_empty() {
+                    return Err(SpawnError::Internal(format!("{} must not be empty", stringify!($name))));
+                }
+                Ok(Self(s.to_string()))
+            }
+        }
+        
+        impl std::ops::Deref for $name {
+            type Target = str;
+            
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+        
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+        
+        impl std::borrow::Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+        
+        impl From<String> for $name {
+            fn from(s: String) -> Self {
+                Self(s)
+            }
+        }
+        
+        impl From<&str> for $name {
+            fn from(s: &str) -> Self {
+                Self(s.to_string())
+            }
+        }
+        
+        impl From<&$name> for $name {
+            fn from(id: &$name) -> Self {
+                id.clone()
+            }
+        }
+        
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+        
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+        
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+        
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+    };
+}
+
+string_id! {
+    /// Identifies a mission; a UUID for missions created by the server
+    MissionId
+}
+
+string_id! {
+    /// Identifies a task within a mission's plan
+    TaskId
+}
+
+string_id! {
+    /// Identifies an agent persona; its lowercased, hyphenated name
+    AgentId
+}
+
+impl MissionId {
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
+
+impl TaskId {
+    pub fn new() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+}
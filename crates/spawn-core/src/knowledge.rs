@@ -0,0 +1,92 @@
+//! Knowledge base documents and citations
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocFormat {
+    Markdown,
+    Pdf,
+    Html,
+}
+
+impl DocFormat {
+    /// Guess the format from a file name or MIME type
+    pub fn detect(filename: &str, mime_type: Option<&str>) -> Option<Self> {
+        let ext = filename.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+        match (ext.as_deref(), mime_type) {
+            (Some("md" | "markdown" | "txt"), _) => Some(Self::Markdown),
+            (Some("pdf"), _) => Some(Self::Pdf),
+            (Some("html" | "htm"), _) => Some(Self::Html),
+            (_, Some(m)) if m.starts_with("text/markdown") || m.starts_with("text/plain") => Some(Self::Markdown),
+            (_, Some(m)) if m.starts_with("application/pdf") => Some(Self::Pdf),
+            (_, Some(m)) if m.starts_with("text/html") => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for DocFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocFormat::Markdown => write!(f, "markdown"),
+            DocFormat::Pdf => write!(f, "pdf"),
+            DocFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+/// A project document (conventions, runbooks, specs) the agent can draw on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    /// Workspace root the document belongs to
+    pub workspace: String,
+    pub title: String,
+    pub filename: String,
+    pub format: DocFormat,
+    /// Extracted plain text
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub content: String,
+    pub chunk_count: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Document {
+    pub fn new(
+        workspace: impl Into<String>,
+        title: impl Into<String>,
+        filename: impl Into<String>,
+        format: DocFormat,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            workspace: workspace.into(),
+            title: title.into(),
+            filename: filename.into(),
+            format,
+            content: content.into(),
+            chunk_count: 0,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A knowledge base chunk that a mission's answer cited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    /// Marker as it appears in the answer, e.g. `S2`
+    pub marker: String,
+    pub doc_id: String,
+    pub title: String,
+    pub filename: String,
+    /// Lines of the document the chunk came from, 1-based, when they could be located
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    /// Start of the cited chunk
+    pub excerpt: String,
+}
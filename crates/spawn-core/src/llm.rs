@@ -0,0 +1,290 @@
+//! LLM client contract: requests, responses, usage and provider health
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::{CancellationToken, ChatMessage, Result};
+
+/// Token counts reported by the provider for one completion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    /// Prompt tokens read from the provider's prompt cache, included in
+    /// `prompt_tokens`
+    pub cached_tokens: u32,
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.cached_tokens += other.cached_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// What a model charges, in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub prompt: f64,
+    pub completion: f64,
+    /// Prompt tokens read from the prompt cache; full price when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_prompt: Option<f64>,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_tokens.min(usage.prompt_tokens);
+        (f64::from(usage.prompt_tokens - cached) * self.prompt
+            + f64::from(cached) * self.cached_prompt.unwrap_or(self.prompt)
+            + f64::from(usage.completion_tokens) * self.completion) / 1_000_000.0
+    }
+}
+
+/// A complete chat completion with its metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub content: String,
+    pub usage: TokenUsage,
+    /// Why generation stopped (`stop`, `length`, `tool_calls`, ...)
+    pub finish_reason: Option<String>,
+    /// Model that actually served the request (may differ from the one asked for)
+    pub model: String,
+}
+
+/// Generation parameters for one completion; unset fields use the provider's default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChatOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Sequences that end generation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Best-effort deterministic sampling, where the provider supports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Constrain the reply to JSON; see `LlmClient::supports_json_schema`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Give up on the call after this long, retries included, with
+    /// `SpawnError::ProviderUnavailable`; never sent to the provider
+    #[serde(skip_serializing)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Shape a reply must take, as OpenAI's `response_format`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any JSON object
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    /// `[a-zA-Z0-9_-]`, up to 64 characters
+    pub name: String,
+    pub schema: serde_json::Value,
+    /// Have the provider enforce the schema exactly; it then only accepts
+    /// schemas where every property is required and no others are allowed
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl ChatOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+    
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+    
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+    
+    pub fn with_stop(mut self, stop: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.stop = stop.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+    
+    pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+        self.response_format = Some(format);
+        self
+    }
+    
+    pub fn with_timeout_secs(mut self, secs: u64) -> Self {
+        self.timeout_secs = Some(secs);
+        self
+    }
+    
+    /// Fill unset fields from `defaults`
+    pub fn or(self, defaults: &ChatOptions) -> Self {
+        Self {
+            temperature: self.temperature.or(defaults.temperature),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            top_p: self.top_p.or(defaults.top_p),
+            stop: if self.stop.is_empty() { defaults.stop.clone() } else { self.stop },
+            seed: self.seed.or(defaults.seed),
+            response_format: self.response_format.or_else(|| defaults.response_format.clone()),
+            timeout_secs: self.timeout_secs.or(defaults.timeout_secs),
+        }
+    }
+}
+
+/// One increment of a streamed completion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatDelta {
+    /// Text added since the previous delta
+    pub content: String,
+    /// Set on the final delta (`stop`, `length`, `tool_calls`, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    /// Token counts for the whole completion, on the final delta when the
+    /// provider reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// Stream of completion deltas
+pub type ChatStream = futures::stream::BoxStream<'static, Result<ChatDelta>>;
+
+/// LLM Client trait - implement for each provider
+///
+/// Every call takes a cancellation token; implementations abandon the request
+/// and return `SpawnError::Cancelled` once it fires.
+#[async_trait::async_trait]
+pub trait LlmClient: Send + Sync {
+    /// Send a chat completion request, returning usage and finish reason
+    async fn chat_with_usage(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatResponse>;
+    
+    /// Send a chat completion request
+    async fn chat(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<String> {
+        Ok(self.chat_with_usage(model, messages, options, cancel).await?.content)
+    }
+    
+    /// Stream a chat completion as deltas.
+    ///
+    /// Providers without streaming support get this default, which yields the
+    /// whole completion as a single delta. Consumers stop polling the stream
+    /// to cancel it mid-way.
+    async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        options: &ChatOptions,
+        cancel: &CancellationToken,
+    ) -> Result<ChatStream> {
+        let response = self.chat_with_usage(model, messages, options, cancel).await?;
+        let delta = ChatDelta {
+            content: response.content,
+            finish_reason: response.finish_reason.or(Some("stop".to_string())),
+            usage: Some(response.usage),
+        };
+        Ok(Box::pin(futures::stream::once(async move { Ok(delta) })))
+    }
+    
+    /// Cheap liveness probe. The default asks `model` for a one-token
+    /// completion; providers with a models or key endpoint use that instead.
+    async fn health_check(&self, model: &str, cancel: &CancellationToken) -> Result<()> {
+        let options = ChatOptions::new().with_max_tokens(1);
+        self.chat_with_usage(model, &[ChatMessage::user("ping")], &options, cancel).await?;
+        Ok(())
+    }
+    
+    /// Whether `model` can be held to a JSON schema through
+    /// `ResponseFormat::JsonSchema`; without it, callers describe the schema
+    /// in the prompt instead
+    fn supports_json_schema(&self, _model: &str) -> bool {
+        false
+    }
+    
+    /// Provider name for logging/routing
+    fn provider_name(&self) -> &str;
+}
+
+/// Whether requests go to a provider, by its recent failure rate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests go through
+    #[default]
+    Closed,
+    /// Too many recent failures; the provider is skipped
+    Open,
+    /// Cooling down is over; one trial request decides
+    HalfOpen,
+}
+
+/// Outcome of an LLM provider's latest health probe, and its circuit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    /// Unprobed providers count as healthy
+    pub healthy: bool,
+    pub checked_at: Option<DateTime<Utc>>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    /// Probes failed in a row
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub circuit: CircuitState,
+    /// Failed share of recent requests and probes
+    #[serde(default)]
+    pub failure_rate: f64,
+}
+
+impl ProviderHealth {
+    pub fn new(provider: impl Into<String>) -> Self {
+        Self {
+            provider: provider.into(),
+            healthy: true,
+            checked_at: None,
+            latency_ms: None,
+            error: None,
+            consecutive_failures: 0,
+            circuit: CircuitState::Closed,
+            failure_rate: 0.0,
+        }
+    }
+    
+    /// Passing its probes with the circuit not open
+    pub fn is_available(&self) -> bool {
+        self.healthy && self.circuit != CircuitState::Open
+    }
+}
@@ -0,0 +1,556 @@
+//! Missions and what they are made of: tasks, agents, budgets, workspaces
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::{AgentId, CapabilitySet, MissionId, Result, SpawnError, TaskId, TaskKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mission {
+    pub id: MissionId,
+    pub goal: String,
+    pub status: MissionStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub context: MissionContext,
+    /// Free-form labels, conventionally `key:value` (`project:web`, `priority:high`, `user:sam`)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Scheduling order when missions queue for a slot
+    #[serde(default)]
+    pub priority: MissionPriority,
+    /// Ceilings the run is stopped at
+    #[serde(default)]
+    pub budget: Budget,
+}
+
+impl Mission {
+    pub fn new(goal: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: MissionId::new(),
+            goal: goal.into(),
+            status: MissionStatus::Pending,
+            created_at: now,
+            updated_at: now,
+            context: MissionContext::default(),
+            tags: Vec::new(),
+            priority: MissionPriority::default(),
+            budget: Budget::default(),
+        }
+    }
+    
+    pub fn with_priority(mut self, priority: MissionPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+    
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.tags = normalize_tags(tags);
+        self
+    }
+    
+    /// Attach a context; its `tags` join the mission's own, and its `budget`
+    /// is the cost ceiling unless one is already set
+    pub fn with_context(mut self, context: MissionContext) -> Self {
+        self.tags = normalize_tags(self.tags.drain(..).chain(context.tags.iter().cloned()));
+        self.budget.max_cost_usd = self.budget.max_cost_usd.or(context.budget);
+        self.context = context;
+        self
+    }
+    
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Budget {
+            max_cost_usd: budget.max_cost_usd.or(self.budget.max_cost_usd),
+            ..budget
+        };
+        self
+    }
+    
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.budget.max_steps = Some(max_steps);
+        self
+    }
+    
+    pub fn with_deadline(mut self, deadline: DateTime<Utc>) -> Self {
+        self.budget.deadline = Some(deadline);
+        self
+    }
+}
+
+/// Spend and time ceilings for a mission; unset ones don't apply (steps fall
+/// back to the orchestrator's default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Budget {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<usize>,
+    /// Prompt plus completion tokens across all steps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+    /// Wall-clock time the mission must finish by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Seconds of running time after which the mission is asked to
+    /// summarize its progress and stop
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soft_timeout_secs: Option<u64>,
+    /// Seconds of running time after which the mission is stopped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hard_timeout_secs: Option<u64>,
+}
+
+/// What a mission has used so far, measured against its `Budget`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub steps: usize,
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// The LLM usage a past mission ran up, as history for estimating new ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissionUsage {
+    pub mission_id: MissionId,
+    pub goal: String,
+    pub steps: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Budget {
+    /// `BudgetExceeded` naming the first ceiling `usage` has reached
+    pub fn check(&self, usage: &BudgetUsage, now: DateTime<Utc>) -> Result<()> {
+        if let Some(max) = self.max_steps.filter(|max| usage.steps >= *max) {
+            return Err(SpawnError::BudgetExceeded(format!("Max steps exceeded ({})", max)));
+        }
+        if let Some(max) = self.max_tokens.filter(|max| usage.tokens >= *max) {
+            return Err(SpawnError::BudgetExceeded(format!("Token budget exceeded ({} of {})", usage.tokens, max)));
+        }
+        if let Some(max) = self.max_cost_usd.filter(|max| usage.cost_usd >= *max) {
+            return Err(SpawnError::BudgetExceeded(format!("Cost budget exceeded (${:.4} of ${:.2})", usage.cost_usd, max)));
+        }
+        if let Some(deadline) = self.deadline.filter(|deadline| now >= *deadline) {
+            return Err(SpawnError::BudgetExceeded(format!("Deadline passed ({})", deadline.to_rfc3339())));
+        }
+        Ok(())
+    }
+}
+
+/// What a mission was started with, beyond its goal
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MissionContext {
+    /// Directory the mission works in, if not the server's workspace root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<std::path::PathBuf>,
+    /// Persona the mission runs as (see `AgentRegistry`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Spend ceiling in USD; `Mission::with_context` copies it into
+    /// `Budget::max_cost_usd`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+    /// Planned steps, when the caller already broke the goal down
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<String>,
+    /// Labels supplied alongside the goal; merged into `Mission.tags`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Capabilities the mission's tools may not use (`network`, `git_push`, ...)
+    #[serde(default, skip_serializing_if = "CapabilitySet::is_empty")]
+    pub deny: CapabilitySet,
+    /// Models for this mission's calls by task, ahead of routing rules
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub models: std::collections::BTreeMap<TaskKind, String>,
+    /// Language the agent works and answers in, as a BCP 47 tag (`de`, `pt-BR`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Anything else the caller attached (`source`, `kind`, ...)
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl MissionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Typed read of an extra field; `None` if absent or of another shape
+    pub fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.extra.get(key).and_then(|v| T::deserialize(v).ok())
+    }
+    
+    pub fn set(&mut self, key: impl Into<String>, value: impl Serialize) {
+        self.extra.insert(key.into(), serde_json::to_value(value).unwrap_or_default());
+    }
+    
+    pub fn with(mut self, key: impl Into<String>, value: impl Serialize) -> Self {
+        self.set(key, value);
+        self
+    }
+    
+    pub fn with_agent(mut self, agent: impl Into<String>) -> Self {
+        self.agent = Some(agent.into());
+        self
+    }
+    
+    pub fn with_steps(mut self, steps: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.steps = steps.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Trim, lowercase and dedupe tags, dropping empty ones
+pub fn normalize_tags(tags: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let mut tags: Vec<String> = tags.into_iter()
+        .map(|t| t.into().trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Criteria for listing missions; all set fields must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MissionFilter {
+    pub status: Option<MissionStatus>,
+    /// Missions must carry every one of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Case-insensitive substring of the goal
+    pub query: Option<String>,
+}
+
+/// A named, reusable mission filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedFilter {
+    pub id: String,
+    pub name: String,
+    pub filter: MissionFilter,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An A/B test of mission settings: each new mission is assigned one variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    pub id: String,
+    pub name: String,
+    pub variants: Vec<ExperimentVariant>,
+    /// Only active experiments take new missions
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Settings a variant overrides; unset fields keep the orchestrator's defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentVariant {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// System prompt replacing the default, with `{persona}` and `{tools}`
+    /// filled in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<String>,
+    /// Share of missions relative to the other variants
+    #[serde(default = "default_variant_weight")]
+    pub weight: u32,
+}
+
+fn default_variant_weight() -> u32 {
+    1
+}
+
+/// A registered repository root that can be searched alongside the default one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    pub root: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a stored locale applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LocaleScope {
+    /// A workspace by name (`default` for the server's own)
+    Workspace,
+    User,
+}
+
+impl LocaleScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocaleScope::Workspace => "workspace",
+            LocaleScope::User => "user",
+        }
+    }
+}
+
+/// The language a workspace or user gets prompts and messages in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocaleSetting {
+    pub scope: LocaleScope,
+    pub id: String,
+    pub locale: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `tag` as a BCP 47 language tag (`pt_BR.UTF-8` -> `pt-BR`); `None` if it
+/// doesn't start with a 2-3 letter language code
+pub fn normalize_locale(tag: &str) -> Option<String> {
+    let tag = tag.trim().split(['.', '@']).next().unwrap_or_default();
+    let mut subtags = tag.split(['-', '_']);
+    let language = subtags.next()?.to_ascii_lowercase();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut normalized = language;
+    for subtag in subtags {
+        if subtag.is_empty() || subtag.len() > 8 || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        normalized.push('-');
+        match subtag.len() {
+            // Region (`BR`) and script (`Hant`) have a conventional case
+            2 => normalized.push_str(&subtag.to_ascii_uppercase()),
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                normalized.push_str(&subtag[..1].to_ascii_uppercase());
+                normalized.push_str(&subtag[1..].to_ascii_lowercase());
+            }
+            _ => normalized.push_str(&subtag.to_ascii_lowercase()),
+        }
+    }
+    Some(normalized)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissionStatus {
+    Pending,
+    /// Waiting for a free mission slot
+    Queued,
+    Running,
+    /// Preempted at a step boundary; resumes when a slot frees up
+    Paused,
+    /// Stopped until a person approves its next action
+    WaitingApproval,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl MissionStatus {
+    /// Completed, failed and cancelled missions never change status again
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+    
+    /// Whether a mission in this status may move to `next`. Setting the
+    /// current status again is allowed and changes nothing.
+    pub fn can_transition_to(&self, next: &MissionStatus) -> bool {
+        use MissionStatus::*;
+        if self == next {
+            return true;
+        }
+        match self {
+            Pending => matches!(next, Queued | Running | Failed | Cancelled),
+            Queued => matches!(next, Running | Failed | Cancelled),
+            Running => matches!(next, Paused | WaitingApproval | Completed | Failed | Cancelled),
+            Paused | WaitingApproval => matches!(next, Running | Failed | Cancelled),
+            Completed | Failed | Cancelled => false,
+        }
+    }
+}
+
+/// How urgently a mission should get a slot; higher runs first
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MissionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl MissionPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+}
+
+/// A unit of work within a mission's plan; tasks form a DAG via `depends_on`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    pub id: TaskId,
+    pub mission_id: MissionId,
+    pub title: String,
+    pub status: TaskStatus,
+    /// Tasks that must complete before this one can start
+    #[serde(default)]
+    pub depends_on: Vec<TaskId>,
+    pub assigned_agent: Option<AgentId>,
+    /// Outcome summary once finished
+    pub result: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Task {
+    pub fn new(mission_id: impl Into<MissionId>, title: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: TaskId::new(),
+            mission_id: mission_id.into(),
+            title: title.into(),
+            status: TaskStatus::Pending,
+            depends_on: Vec::new(),
+            assigned_agent: None,
+            result: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    pub fn depends_on(mut self, tasks: impl IntoIterator<Item = impl Into<TaskId>>) -> Self {
+        self.depends_on = tasks.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    pub fn assigned_to(mut self, agent: impl Into<AgentId>) -> Self {
+        self.assigned_agent = Some(agent.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    /// Not run because a dependency failed
+    Skipped,
+}
+
+impl TaskStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Skipped)
+    }
+}
+
+/// An agent persona: who the model plays, which model it runs on and which
+/// tools it may call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agent {
+    pub id: AgentId,
+    pub name: String,
+    /// What the persona is for (`architect`, `coder`, `reviewer`, ...)
+    pub role: String,
+    /// Placed ahead of the tool instructions in the mission's system prompt
+    pub system_prompt: String,
+    /// Model override; the orchestrator's default when unset
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Tools the agent may call; empty allows all of them
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+}
+
+impl Agent {
+    /// The ID is the lowercased name, so declarative definitions stay stable
+    /// across restarts
+    pub fn new(name: impl Into<String>, role: impl Into<String>, system_prompt: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            id: name.trim().to_lowercase().replace(char::is_whitespace, "-").into(),
+            name,
+            role: role.into(),
+            system_prompt: system_prompt.into(),
+            model: None,
+            allowed_tools: Vec::new(),
+        }
+    }
+    
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+    
+    pub fn with_tools(mut self, tools: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allowed_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+    
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.allowed_tools.is_empty() || self.allowed_tools.iter().any(|t| t == tool)
+    }
+}
+
+/// Why a mission failed, as determined by its post-mortem
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// A tool call errored (bad arguments, command failure, missing binary)
+    ToolError,
+    /// The agent pursued the wrong approach or looped without progress
+    BadPlan,
+    /// Ran out of steps, time or money
+    Budget,
+    /// The LLM provider was unavailable or rejected requests
+    ProviderOutage,
+    Unknown,
+}
+
+/// Structured analysis of a failed mission
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostMortem {
+    pub mission_id: MissionId,
+    pub category: FailureCategory,
+    /// What went wrong, in a sentence or two
+    pub summary: String,
+    /// What to change before retrying
+    pub remediation: String,
+    /// Model that produced the analysis, if any (heuristic otherwise)
+    pub model: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mission_status_transitions() {
+        use MissionStatus::*;
+        let all = [Pending, Queued, Running, Paused, WaitingApproval, Completed, Failed, Cancelled];
+        let allowed = [
+            (Pending, Queued), (Pending, Running), (Pending, Failed), (Pending, Cancelled),
+            (Queued, Running), (Queued, Failed), (Queued, Cancelled),
+            (Running, Paused), (Running, WaitingApproval), (Running, Completed), (Running, Failed), (Running, Cancelled),
+            (Paused, Running), (Paused, Failed), (Paused, Cancelled),
+            (WaitingApproval, Running), (WaitingApproval, Failed), (WaitingApproval, Cancelled),
+        ];
+        for from in &all {
+            for to in &all {
+                let expected = from == to || allowed.contains(&(from.clone(), to.clone()));
+                assert_eq!(from.can_transition_to(to), expected, "{:?} -> {:?}", from, to);
+            }
+        }
+        assert!(!Completed.can_transition_to(&Running));
+        assert!(!Queued.can_transition_to(&Pending));
+        assert!(!Paused.can_transition_to(&Queued));
+    }
+}
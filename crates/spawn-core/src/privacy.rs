@@ -0,0 +1,47 @@
+//! Data subjects and the audit trail
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Whose stored conversation data a data subject request covers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSubject {
+    /// One chat session
+    Session(String),
+    /// Every session stored with this `user_id`
+    User(String),
+}
+
+impl std::fmt::Display for DataSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataSubject::Session(id) => write!(f, "session:{}", id),
+            DataSubject::User(id) => write!(f, "user:{}", id),
+        }
+    }
+}
+
+/// A record of a sensitive operation (data export, erasure, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: String,
+    /// e.g. `chat_export`, `chat_delete`
+    pub action: String,
+    /// What the action applied to, e.g. `user:sam`
+    pub subject: String,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(action: impl Into<String>, subject: impl Into<String>, details: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            action: action.into(),
+            subject: subject.into(),
+            details,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! Contracts for tools, services, logs, speech and health checks
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::{Agent, CancellationToken, ChatMessage, ChatResponse, MissionId, Result, SpawnError};
+
+/// Synthesized speech
+#[derive(Debug, Clone)]
+pub struct SpeechAudio {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// One line of a mission's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub mission_id: MissionId,
+    /// Who wrote it: `assistant`, `tool`, `scheduler`, ...
+    pub agent: String,
+    /// The concise form: the action taken, a shortened tool result
+    pub content: String,
+    /// The full form, when it differs: the whole LLM response, the whole
+    /// tool output. Dropped by retention after `verbose_log_days`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbose: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LogEntry {
+    pub fn new(mission_id: impl Into<MissionId>, agent: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            mission_id: mission_id.into(),
+            agent: agent.into(),
+            content: content.into(),
+            verbose: None,
+            created_at: Utc::now(),
+        }
+    }
+    
+    /// Keep `verbose` as the full form, unless it says the same as `content`
+    pub fn with_verbose(mut self, verbose: impl Into<String>) -> Self {
+        let verbose = verbose.into();
+        self.verbose = (verbose != self.content).then_some(verbose);
+        self
+    }
+}
+
+/// Which form of the log to read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTier {
+    /// Only `content`; what the UI shows
+    #[default]
+    Concise,
+    /// `verbose` too, where it is still stored
+    Verbose,
+}
+
+/// Log store trait - where mission logs are kept
+#[async_trait::async_trait]
+pub trait LogStore: Send + Sync {
+    async fn append(&self, entry: &LogEntry) -> Result<()>;
+    
+    /// All of a mission's entries, oldest first
+    async fn list(&self, mission_id: &MissionId) -> Result<Vec<LogEntry>>;
+    
+    /// The entries in one tier; concise ones come without `verbose`
+    async fn list_tier(&self, mission_id: &MissionId, tier: LogTier) -> Result<Vec<LogEntry>> {
+        let mut entries = self.list(mission_id).await?;
+        if tier == LogTier::Concise {
+            entries.iter_mut().for_each(|e| e.verbose = None);
+        }
+        Ok(entries)
+    }
+    
+    /// Drop the verbose form of entries written before `before`, returning
+    /// how many were trimmed. Stores that can't rewrite entries keep them.
+    async fn prune_verbose(&self, _before: DateTime<Utc>) -> Result<u64> {
+        Ok(0)
+    }
+    
+    /// Summary from the mission's final `DONE:` response, if it completed
+    async fn completion_summary(&self, mission_id: &MissionId) -> Result<Option<String>> {
+        Ok(self.list(mission_id).await?
+            .into_iter()
+            .rev()
+            .filter(|e| e.agent == "assistant")
+            .find_map(|e| e.content.split_once("DONE:").map(|(_, summary)| summary.trim().to_string())))
+    }
+    
+    /// The mission has ended and nothing more will be appended; stores that
+    /// archive finished logs do it here
+    async fn finish(&self, _mission_id: &MissionId) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Backend name for status reporting
+    fn backend_name(&self) -> &str;
+}
+
+/// Durable storage for cached model responses, keyed by a hash of the request
+#[async_trait::async_trait]
+pub trait ResponseStore: Send + Sync {
+    async fn get_response(&self, key: &str) -> Result<Option<ChatResponse>>;
+    
+    async fn put_response(&self, key: &str, response: &ChatResponse) -> Result<()>;
+}
+
+/// Text-to-speech trait - implement for each synthesis provider
+#[async_trait::async_trait]
+pub trait TextToSpeech: Send + Sync {
+    /// Synthesize speech for a piece of text
+    async fn synthesize(&self, text: &str) -> Result<SpeechAudio>;
+    
+    /// Provider name for logging/routing
+    fn provider_name(&self) -> &str;
+}
+
+/// Speech-to-text trait - implement for each transcription provider
+#[async_trait::async_trait]
+pub trait SpeechToText: Send + Sync {
+    /// Transcribe an audio clip (`mime_type` e.g. `audio/webm`)
+    async fn transcribe(&self, audio: Vec<u8>, mime_type: &str, language: Option<&str>) -> Result<String>;
+    
+    /// Provider name for logging/routing
+    fn provider_name(&self) -> &str;
+}
+
+/// Agent registry trait - source of the personas missions can run as
+pub trait AgentRegistry: Send + Sync {
+    /// Look up an agent by ID or name
+    fn get(&self, id_or_name: &str) -> Option<Agent>;
+    
+    /// All registered agents
+    fn list(&self) -> Vec<Agent>;
+}
+
+/// An update from a tool that is still running
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProgress {
+    pub message: String,
+    /// Share of the work done, 0.0 to 1.0, when the tool can tell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fraction: Option<f32>,
+}
+
+impl ToolProgress {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), fraction: None }
+    }
+    
+    pub fn with_fraction(mut self, fraction: f32) -> Self {
+        self.fraction = Some(fraction.clamp(0.0, 1.0));
+        self
+    }
+}
+
+/// Where a tool reports progress. Sending never blocks; updates are dropped
+/// when nobody listens (including a `default()` sender).
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSender(Option<futures::channel::mpsc::UnboundedSender<ToolProgress>>);
+
+impl ProgressSender {
+    pub fn channel() -> (Self, futures::channel::mpsc::UnboundedReceiver<ToolProgress>) {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        (Self(Some(tx)), rx)
+    }
+    
+    pub fn send(&self, progress: ToolProgress) {
+        if let Some(tx) = &self.0 {
+            let _ = tx.unbounded_send(progress);
+        }
+    }
+    
+    pub fn message(&self, message: impl Into<String>) {
+        self.send(ToolProgress::new(message));
+    }
+}
+
+/// Something a tool may do to the host beyond computing a result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermission {
+    FilesystemRead,
+    FilesystemWrite,
+    Network,
+    ProcessSpawn,
+    GitPush,
+}
+
+impl std::fmt::Display for ToolPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ToolPermission::FilesystemRead => "filesystem_read",
+            ToolPermission::FilesystemWrite => "filesystem_write",
+            ToolPermission::Network => "network",
+            ToolPermission::ProcessSpawn => "process_spawn",
+            ToolPermission::GitPush => "git_push",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A set of `ToolPermission`s; serialized as a list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CapabilitySet(std::collections::BTreeSet<ToolPermission>);
+
+impl CapabilitySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn with(mut self, permission: ToolPermission) -> Self {
+        self.0.insert(permission);
+        self
+    }
+    
+    pub fn contains(&self, permission: ToolPermission) -> bool {
+        self.0.contains(&permission)
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    
+    pub fn iter(&self) -> impl Iterator<Item = ToolPermission> + '_ {
+        self.0.iter().copied()
+    }
+    
+    /// Permissions in both sets, e.g. what a tool needs that a mission denies
+    pub fn intersection(&self, other: &CapabilitySet) -> CapabilitySet {
+        Self(self.0.intersection(&other.0).copied().collect())
+    }
+    
+    pub fn union(&self, other: &CapabilitySet) -> CapabilitySet {
+        Self(self.0.union(&other.0).copied().collect())
+    }
+}
+
+impl FromIterator<ToolPermission> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = ToolPermission>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl std::fmt::Display for CapabilitySet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<String> = self.iter().map(|p| p.to_string()).collect();
+        f.write_str(&names.join(", "))
+    }
+}
+
+/// An optional service some endpoints and tools can't work without. While
+/// one is down they answer `SpawnError::ServiceUnavailable` up front rather
+/// than failing partway through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Service {
+    /// PostgreSQL with pgvector, behind the knowledge base and conversation memory
+    VectorStore,
+    /// terminal-app, which hosts named terminals
+    Terminal,
+    /// An LLM provider passing its health probes
+    Provider,
+}
+
+impl Service {
+    pub const ALL: [Service; 3] = [Service::VectorStore, Service::Terminal, Service::Provider];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Service::VectorStore => "vector_store",
+            Service::Terminal => "terminal",
+            Service::Provider => "provider",
+        }
+    }
+}
+
+impl std::fmt::Display for Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Which services are usable, as their latest probes found them. Services
+/// count as up until marked down.
+#[derive(Debug, Default)]
+pub struct ServiceAvailability {
+    down: std::sync::RwLock<std::collections::BTreeSet<Service>>,
+}
+
+impl ServiceAvailability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record whether `service` is up; true if that changed anything
+    pub fn set(&self, service: Service, up: bool) -> bool {
+        let mut down = self.down.write().unwrap();
+        if up { down.remove(&service) } else { down.insert(service) }
+    }
+    
+    pub fn is_up(&self, service: Service) -> bool {
+        !self.down.read().unwrap().contains(&service)
+    }
+    
+    /// Services currently down
+    pub fn down(&self) -> Vec<Service> {
+        self.down.read().unwrap().iter().copied().collect()
+    }
+    
+    /// The first of `services` that is down, if any
+    pub fn missing(&self, services: &[Service]) -> Option<Service> {
+        let down = self.down.read().unwrap();
+        services.iter().copied().find(|s| down.contains(s))
+    }
+    
+    /// `SpawnError::ServiceUnavailable` unless `service` is up
+    pub fn require(&self, service: Service) -> Result<()> {
+        if self.is_up(service) { Ok(()) } else { Err(SpawnError::ServiceUnavailable(service)) }
+    }
+}
+
+/// Whether a subsystem answered its health probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Online,
+    Offline,
+}
+
+/// Outcome of one health probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthStatus {
+    pub name: String,
+    pub status: HealthState,
+    pub latency_ms: Option<u64>,
+    /// What the subsystem reported, or why it is offline
+    pub details: Option<serde_json::Value>,
+}
+
+impl HealthStatus {
+    pub fn offline(name: impl Into<String>, error: impl std::fmt::Display) -> Self {
+        Self {
+            name: name.into(),
+            status: HealthState::Offline,
+            latency_ms: None,
+            details: Some(serde_json::json!({ "error": error.to_string() })),
+        }
+    }
+    
+    pub fn is_online(&self) -> bool {
+        self.status == HealthState::Online
+    }
+}
+
+/// A subsystem that can report whether it is usable
+#[async_trait::async_trait]
+pub trait HealthCheck: Send + Sync {
+    fn name(&self) -> &str;
+    
+    /// Reach the subsystem, returning anything worth showing next to its
+    /// status; an error means it is offline
+    async fn probe(&self) -> Result<Option<serde_json::Value>>;
+    
+    /// Probe and time it; a failed probe is reported, not returned
+    async fn check(&self) -> HealthStatus {
+        let started = std::time::Instant::now();
+        match self.probe().await {
+            Ok(details) => HealthStatus {
+                name: self.name().to_string(),
+                status: HealthState::Online,
+                latency_ms: Some(started.elapsed().as_millis() as u64),
+                details,
+            },
+            Err(e) => HealthStatus { latency_ms: Some(started.elapsed().as_millis() as u64), ..HealthStatus::offline(self.name(), e) },
+        }
+    }
+}
+
+/// Condenses part of a conversation for `Conversation::summarize`
+#[async_trait::async_trait]
+pub trait Summarizer: Send + Sync {
+    async fn summarize(&self, messages: &[ChatMessage]) -> Result<String>;
+}
+
+/// Turns text into embedding vectors for semantic search
+#[async_trait::async_trait]
+pub trait EmbeddingClient: Send + Sync {
+    /// One vector per input, in input order
+    async fn embed_batch(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    async fn embed(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        self.embed_batch(model, &[text.to_string()]).await?
+            .pop()
+            .ok_or_else(|| SpawnError::ProviderError("Empty embedding response".into()))
+    }
+}
+
+/// Tool trait - implement for each capability
+#[async_trait::async_trait]
+pub trait Tool: Send + Sync {
+    /// Tool name (used in function calling)
+    fn name(&self) -> &str;
+    
+    /// Human-readable description
+    fn description(&self) -> &str;
+    
+    /// JSON Schema for parameters
+    fn parameters(&self) -> serde_json::Value;
+    
+    /// Execute the tool with given arguments.
+    ///
+    /// Long-running tools should watch `cancel` and stop early (killing any
+    /// child processes) when it fires.
+    async fn execute(&self, args: serde_json::Value, cancel: &CancellationToken) -> Result<serde_json::Value>;
+    
+    /// `execute`, reporting progress along the way. Tools that take a while
+    /// (cloning, running tests) override this; by default nothing is reported.
+    async fn execute_with_progress(
+        &self,
+        args: serde_json::Value,
+        progress: &ProgressSender,
+        cancel: &CancellationToken,
+    ) -> Result<serde_json::Value> {
+        let _ = progress;
+        self.execute(args, cancel).await
+    }
+    
+    /// Workspace locks (`file:<path>`, `git:<repo>`) a call with these
+    /// arguments needs for its duration; read-only tools need none
+    fn locks(&self, _args: &serde_json::Value) -> Vec<String> {
+        Vec::new()
+    }
+    
+    /// What the tool may do to the host; missions that deny any of these
+    /// can't run it
+    fn capabilities(&self) -> CapabilitySet;
+    
+    /// Optional services the tool can't work without; it is withheld from
+    /// missions while any of them is down
+    fn requires(&self) -> &[Service] {
+        &[]
+    }
+    
+    /// Workspace files a successful call wrote, as named in its result, so
+    /// readers of those files can be told to reload them. Around tools that
+    /// spawn processes or write files the orchestrator also compares the
+    /// workspace, which finds the files a command changed.
+    fn written_files(&self, _result: &serde_json::Value) -> Vec<String> {
+        Vec::new()
+    }
+}
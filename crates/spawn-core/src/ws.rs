@@ -0,0 +1,75 @@
+//! The WebSocket protocol
+
+use serde::{Deserialize, Serialize};
+use crate::{MissionEvent, MissionId};
+
+/// Versions of the WebSocket message schema this build speaks, newest first
+pub const WS_PROTOCOL_VERSIONS: &[u32] = &[1];
+
+/// `Sec-WebSocket-Protocol` token for a schema version (`spawn.v1`)
+pub fn ws_subprotocol(version: u32) -> String {
+    format!("spawn.v{}", version)
+}
+
+/// Schema version named by a negotiated `Sec-WebSocket-Protocol` token
+pub fn parse_ws_subprotocol(protocol: &str) -> Option<u32> {
+    let version = protocol.trim().strip_prefix("spawn.v")?.parse().ok()?;
+    WS_PROTOCOL_VERSIONS.contains(&version).then_some(version)
+}
+
+/// Which WebSocket endpoint a connection is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsChannel {
+    Terminal,
+    Events,
+}
+
+/// Frames a client sends once a schema version is negotiated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsClientMessage {
+    /// Keystrokes or pasted text for a terminal
+    Input { data: String },
+    /// Narrow an event stream to one mission, or widen it again with `null`
+    Subscribe { mission_id: Option<MissionId> },
+    Ping,
+}
+
+/// Frames the server sends once a schema version is negotiated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+    /// Always the first frame, confirming the negotiated version
+    Welcome {
+        version: u32,
+        channel: WsChannel,
+        /// Present `?resume=<token>&last_seq=<n>` when reconnecting
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        resume_token: Option<String>,
+        /// Whether this connection picked up an earlier session
+        #[serde(default)]
+        resumed: bool,
+    },
+    /// Terminal output
+    Output { data: String },
+    Event { event: MissionEvent },
+    /// The client sent something the server couldn't act on; the connection stays open
+    Error { code: String, message: String },
+    Pong,
+}
+
+impl WsServerMessage {
+    pub fn error(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Error { code: code.into(), message: message.into() }
+    }
+}
+
+/// An `output` or `event` frame numbered within its session, so a resuming
+/// client can say what it last received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WsSequenced {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub message: WsServerMessage,
+}
@@ -0,0 +1,334 @@
+//! spawn: The agent engine, embeddable
+//!
+//! Runs missions in-process, without the HTTP server: build an [`Engine`]
+//! around an LLM client, register tools and hooks, then run missions and
+//! watch their events. The underlying crates are re-exported as `core`, `ai`
+//! and `agents` for anything the engine doesn't wrap.
+//!
+//! ```no_run
+//! use spawn::{Engine, Mission, OpenRouterClient};
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> spawn::Result<()> {
+//! let engine = Engine::builder(Arc::new(OpenRouterClient::new("sk-or-...")))
+//!     .database_url("sqlite:spawn.db?mode=rwc")
+//!     .workspace("./my-app")
+//!     .build()
+//!     .await?;
+//! let mission = engine.start(Mission::new("Add a health endpoint"));
+//! let mut events = engine.mission_events(mission.id());
+//! # use futures::StreamExt;
+//! while let Some(event) = events.next().await {
+//!     println!("{:?}", event);
+//! }
+//! mission.wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use spawn_agents as agents;
+pub use spawn_ai as ai;
+pub use spawn_core as core;
+
+pub use spawn_agents::tools::ToolRegistry;
+pub use spawn_agents::{Database, Orchestrator};
+pub use spawn_ai::{
+    AwsCredentials, AzureOpenAiClient, BedrockClient, GeminiClient, OllamaClient, OpenRouterClient, ProviderManager, RecordingClient,
+    ReplayClient,
+};
+pub use spawn_core::{
+    Budget, CancellationToken, ChatMessage, ChatOptions, ContextConfig, EventStream, LlmClient, Mission, MissionContext, MissionEvent,
+    HttpTimeouts, MissionHook, MissionId, MissionStatus, Result, SpawnError, Tool,
+};
+
+use futures::StreamExt;
+use spawn_agents::tools::{CargoTool, DepsTool, LintTool};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// Where missions are stored unless the builder names a database
+pub const DEFAULT_DATABASE_URL: &str = "sqlite:spawn.db?mode=rwc";
+
+/// Sets an [`Engine`] up; see [`Engine::builder`]
+pub struct EngineBuilder {
+    llm: Arc<dyn LlmClient>,
+    database_url: String,
+    model: Option<String>,
+    workspace: Option<PathBuf>,
+    context: Option<ContextConfig>,
+    tools: Vec<Box<dyn Tool>>,
+    hooks: Vec<Arc<dyn MissionHook>>,
+}
+
+impl EngineBuilder {
+    /// SQLite database missions, logs and usage are kept in; migrated on build
+    pub fn database_url(mut self, url: impl Into<String>) -> Self {
+        self.database_url = url.into();
+        self
+    }
+
+    /// Model missions run on unless their context names another
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Project the missions work on; adds the dependency, lint and cargo
+    /// tools for it
+    pub fn workspace(mut self, root: impl Into<PathBuf>) -> Self {
+        self.workspace = Some(root.into());
+        self
+    }
+
+    /// Context window size and how it is shared between prompt sections
+    pub fn context(mut self, config: ContextConfig) -> Self {
+        self.context = Some(config);
+        self
+    }
+
+    /// Offer `tool` to missions, next to the built-in echo and shell tools;
+    /// one with a built-in's name replaces it
+    pub fn tool(mut self, tool: Box<dyn Tool>) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Run `hook` in every mission, after the hooks added before it
+    pub fn hook(mut self, hook: Arc<dyn MissionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Connect to (and migrate) the database and put the orchestrator together
+    pub async fn build(self) -> Result<Engine> {
+        let db = Arc::new(Database::connect(&self.database_url).await?);
+        let mut tools = ToolRegistry::new();
+        if let Some(root) = &self.workspace {
+            tools.register(Box::new(DepsTool::new(root.clone())));
+            tools.register(Box::new(LintTool::new(root.clone()).with_database(db.clone())));
+            tools.register(Box::new(CargoTool::new(root.clone()).with_database(db.clone())));
+        }
+        for tool in self.tools {
+            tools.register(tool);
+        }
+
+        let mut orchestrator = Orchestrator::new(db.clone(), self.llm).with_tools(tools);
+        if let Some(model) = self.model {
+            orchestrator = orchestrator.with_model(model);
+        }
+        if let Some(config) = self.context {
+            orchestrator = orchestrator.with_context_config(config);
+        }
+        for hook in self.hooks {
+            orchestrator = orchestrator.with_hook(hook);
+        }
+        Ok(Engine { orchestrator: Arc::new(orchestrator), db, workspace: self.workspace })
+    }
+}
+
+/// The agent engine: runs missions to completion and reports on them
+#[derive(Clone)]
+pub struct Engine {
+    orchestrator: Arc<Orchestrator>,
+    db: Arc<Database>,
+    workspace: Option<PathBuf>,
+}
+
+impl Engine {
+    /// Missions' steps go to `llm`; a [`ProviderManager`] gives fallbacks
+    pub fn builder(llm: Arc<dyn LlmClient>) -> EngineBuilder {
+        EngineBuilder {
+            llm,
+            database_url: DEFAULT_DATABASE_URL.to_string(),
+            model: None,
+            workspace: None,
+            context: None,
+            tools: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Run `mission` to the end: `Ok` once it completes, its error if it
+    /// fails or is cancelled
+    pub async fn run(&self, mission: Mission) -> Result<()> {
+        self.orchestrator.run_mission(self.prepare(mission)).await
+    }
+
+    /// Run `mission` in the background
+    pub fn start(&self, mission: Mission) -> MissionHandle {
+        let mission = self.prepare(mission);
+        let id = mission.id.clone();
+        let orchestrator = self.orchestrator.clone();
+        MissionHandle { id, task: tokio::spawn(async move { orchestrator.run_mission(mission).await }) }
+    }
+
+    /// Stop a running or queued mission; false if it isn't either
+    pub fn cancel(&self, mission_id: &MissionId) -> bool {
+        self.orchestrator.cancel(mission_id)
+    }
+
+    /// A stored mission, with its current status
+    pub async fn mission(&self, mission_id: &MissionId) -> Result<Option<Mission>> {
+        self.db.get_mission(mission_id).await
+    }
+
+    /// Every mission's events from now on
+    pub fn events(&self) -> EventStream {
+        self.orchestrator.events().subscribe()
+    }
+
+    /// One mission's events from now on, ending with its last one
+    pub fn mission_events(&self, mission_id: &MissionId) -> EventStream {
+        let id = mission_id.as_str().to_string();
+        self.events()
+            .filter(move |event| futures::future::ready(event.mission_id() == id))
+            // A `None` after the last event ends the stream there
+            .flat_map(|event| {
+                let end = event.is_terminal().then_some(None);
+                futures::stream::iter(std::iter::once(Some(event)).chain(end))
+            })
+            .take_while(|event| futures::future::ready(event.is_some()))
+            .filter_map(futures::future::ready)
+            .boxed()
+    }
+
+    pub fn orchestrator(&self) -> &Arc<Orchestrator> {
+        &self.orchestrator
+    }
+
+    pub fn database(&self) -> &Arc<Database> {
+        &self.db
+    }
+
+    /// Missions without a workspace of their own work in the engine's
+    fn prepare(&self, mut mission: Mission) -> Mission {
+        if mission.context.workspace.is_none() {
+            mission.context.workspace = self.workspace.clone();
+        }
+        mission
+    }
+}
+
+/// A mission running in the background
+pub struct MissionHandle {
+    id: MissionId,
+    task: JoinHandle<Result<()>>,
+}
+
+impl MissionHandle {
+    pub fn id(&self) -> &MissionId {
+        &self.id
+    }
+
+    /// Wait for the mission to end, as [`Engine::run`] would
+    pub async fn wait(self) -> Result<()> {
+        self.task.await
+            .map_err(|e| SpawnError::Internal(format!("Mission task failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use spawn_core::{CapabilitySet, ChatResponse, TokenUsage, ToolPermission};
+    use std::sync::Mutex;
+
+    /// Gives its replies in order, keeping the last message of each prompt
+    struct ScriptedClient(Mutex<Vec<String>>, Mutex<Vec<String>>);
+
+    impl ScriptedClient {
+        fn new(replies: &[&str]) -> Self {
+            Self(Mutex::new(replies.iter().map(|r| r.to_string()).collect()), Mutex::new(Vec::new()))
+        }
+    }
+
+    #[async_trait]
+    impl LlmClient for ScriptedClient {
+        async fn chat_with_usage(&self, model: &str, messages: &[ChatMessage], _: &ChatOptions, _: &CancellationToken) -> Result<ChatResponse> {
+            if let Some(last) = messages.last() {
+                self.1.lock().unwrap().push(last.content.text());
+            }
+            Ok(ChatResponse {
+                content: self.0.lock().unwrap().remove(0),
+                usage: TokenUsage::default(),
+                finish_reason: Some("stop".into()),
+                model: model.to_string(),
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runs_mission_in_process() {
+        let path = std::env::temp_dir().join(format!("spawn-engine-{}.db", MissionId::new()));
+        let llm = ScriptedClient::new(&["TOOL: echo\nARGS: {\"message\": \"hi\"}", "DONE: echoed"]);
+        let engine = Engine::builder(Arc::new(llm))
+            .database_url(format!("sqlite:{}?mode=rwc", path.display()))
+            .build()
+            .await
+            .unwrap();
+
+        let mission = Mission::new("Echo hi");
+        let id = mission.id.clone();
+        let events = engine.mission_events(&id);
+        engine.start(mission).wait().await.unwrap();
+        let events: Vec<MissionEvent> = events.collect().await;
+        assert!(matches!(events.last(), Some(MissionEvent::Completed { summary, .. }) if summary == "echoed"));
+        assert!(events.iter().any(|e| matches!(e, MissionEvent::ToolExecuted { tool, success: true, .. } if tool == "echo")));
+        assert_eq!(engine.mission(&id).await.unwrap().unwrap().status, MissionStatus::Completed);
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// Rewrites `notes.txt` without saying so in its result
+    struct RewriteTool(PathBuf);
+
+    #[async_trait]
+    impl Tool for RewriteTool {
+        fn name(&self) -> &str { "rewrite" }
+        fn description(&self) -> &str { "Rewrite the notes" }
+        fn parameters(&self) -> serde_json::Value { serde_json::json!({ "type": "object" }) }
+        fn capabilities(&self) -> CapabilitySet { CapabilitySet::new().with(ToolPermission::FilesystemWrite) }
+
+        async fn execute(&self, _: serde_json::Value, _: &CancellationToken) -> Result<serde_json::Value> {
+            std::fs::write(self.0.join("notes.txt"), "version 2").map_err(|e| SpawnError::ToolError(e.to_string()))?;
+            Ok(serde_json::json!({ "success": true }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_see_tool_writes() {
+        let root = std::env::temp_dir().join(format!("spawn-engine-{}", MissionId::new()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("notes.txt"), "version 1").unwrap();
+        let read = format!("TOOL: shell\nARGS: {{\"command\": \"cat\", \"args\": [\"{}\"]}}", root.join("notes.txt").display());
+        let llm = Arc::new(ScriptedClient::new(&["TOOL: rewrite\nARGS: {}", &read, "DONE: rewritten"]));
+        let engine = Engine::builder(llm.clone())
+            .database_url(format!("sqlite:{}.db?mode=rwc", root.display()))
+            .workspace(&root)
+            .tool(Box::new(RewriteTool(root.clone())))
+            .build()
+            .await
+            .unwrap();
+
+        let mission = Mission::new("Rewrite the notes and read them back");
+        let events = engine.mission_events(&mission.id);
+        engine.run(mission).await.unwrap();
+        let events: Vec<MissionEvent> = events.collect().await;
+        let changed: Vec<_> = events.iter()
+            .filter_map(|e| match e {
+                MissionEvent::FilesChanged { tool, paths, .. } => Some((tool.as_str(), paths.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changed, [("rewrite", vec!["notes.txt".to_string()])]);
+        let prompts = llm.1.lock().unwrap();
+        assert!(prompts[2].contains("version 2"), "{}", prompts[2]);
+        let _ = std::fs::remove_file(root.with_extension("db"));
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
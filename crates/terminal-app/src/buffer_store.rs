@@ -0,0 +1,143 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::Row;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use terminal_code_editor::{BufferSnapshot, Cursor, EditorManager, Reconciled};
+
+/// Open editor buffers, kept in SQLite so a restart restores the session
+pub struct BufferStore {
+    pool: SqlitePool,
+}
+
+impl BufferStore {
+    /// Open the database at `url`, creating the file if it doesn't exist yet
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePool::connect_with(options).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS editor_buffers (
+                path TEXT PRIMARY KEY,
+                content_hash TEXT NOT NULL,
+                unsaved TEXT,
+                cursor_line INTEGER NOT NULL DEFAULT 0,
+                cursor_column INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn save(&self, snapshot: &BufferSnapshot) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO editor_buffers (path, content_hash, unsaved, cursor_line, cursor_column, updated_at)
+             VALUES (?, ?, ?, ?, ?, datetime('now'))
+             ON CONFLICT(path) DO UPDATE SET
+                content_hash = excluded.content_hash, unsaved = excluded.unsaved,
+                cursor_line = excluded.cursor_line, cursor_column = excluded.cursor_column,
+                updated_at = excluded.updated_at",
+        )
+        .bind(snapshot.path.to_string_lossy().as_ref())
+        .bind(&snapshot.content_hash)
+        .bind(&snapshot.unsaved)
+        .bind(snapshot.cursor.line as i64)
+        .bind(snapshot.cursor.column as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, path: &Path) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM editor_buffers WHERE path = ?")
+            .bind(path.to_string_lossy().as_ref())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn load(&self) -> Result<Vec<BufferSnapshot>, sqlx::Error> {
+        let rows = sqlx::query("SELECT path, content_hash, unsaved, cursor_line, cursor_column FROM editor_buffers ORDER BY updated_at")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| BufferSnapshot {
+                path: PathBuf::from(row.get::<String, _>("path")),
+                content_hash: row.get("content_hash"),
+                unsaved: row.get("unsaved"),
+                cursor: Cursor {
+                    line: row.get::<i64, _>("cursor_line").max(0) as usize,
+                    column: row.get::<i64, _>("cursor_column").max(0) as usize,
+                },
+            })
+            .collect())
+    }
+
+    /// Reopen every stored buffer in `editor`, reconciled against the files
+    /// on disk, and store what they became
+    pub async fn restore(&self, editor: &EditorManager) -> Result<(), sqlx::Error> {
+        for snapshot in self.load().await? {
+            match editor.restore(&snapshot).await {
+                Ok((Reconciled::Dropped, _)) => {
+                    tracing::info!(path = %snapshot.path.display(), "File is gone; not restoring its buffer");
+                    self.remove(&snapshot.path).await?;
+                }
+                Ok((reconciled, Some(buffer))) => {
+                    tracing::info!(path = %snapshot.path.display(), ?reconciled, "Restored editor buffer");
+                    if let Some(current) = editor.snapshot(buffer.id) {
+                        self.save(&current).await?;
+                    }
+                }
+                Ok((_, None)) => {}
+                Err(e) => tracing::warn!(path = %snapshot.path.display(), error = %e, "Failed to restore editor buffer"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terminal_code_editor::content_hash;
+
+    #[tokio::test]
+    async fn test_save_load_restore_round_trip() {
+        let dir = std::env::temp_dir().join(format!("buffer-store-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (kept, gone) = (dir.join("main.rs"), dir.join("gone.rs"));
+        std::fs::write(&kept, "fn main() {}\n").unwrap();
+
+        let store = BufferStore::connect("sqlite::memory:").await.unwrap();
+        let edited = BufferSnapshot {
+            path: kept.clone(),
+            content_hash: content_hash("fn main() {}\n"),
+            unsaved: Some("fn main() { run() }\n".into()),
+            cursor: Cursor { line: 0, column: 4 },
+        };
+        store.save(&edited).await.unwrap();
+        store.save(&BufferSnapshot { path: gone.clone(), content_hash: content_hash(""), unsaved: None, cursor: Cursor::default() }).await.unwrap();
+        let loaded = store.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.contains(&edited));
+
+        let editor = EditorManager::new();
+        store.restore(&editor).await.unwrap();
+        let buffers = editor.list_buffers();
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(editor.get_content(buffers[0].id).as_deref(), Some("fn main() { run() }\n"));
+        // The buffer whose file is gone is forgotten
+        assert_eq!(store.load().await.unwrap(), vec![edited]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_creates_database_file() {
+        let path = std::env::temp_dir().join(format!("buffer-store-{}.db", uuid::Uuid::new_v4().simple()));
+        BufferStore::connect(&format!("sqlite:{}", path.display())).await.unwrap();
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 use axum::{http::StatusCode, response::{IntoResponse, Response}, Json};
 use serde_json::json;
+use terminal_core::TerminalError;
 
 pub enum ApiError {
     NotFound(String),
@@ -27,7 +28,15 @@ impl IntoResponse for ApiError {
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            ApiError::Terminal(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            ApiError::Terminal(err) => {
+                let status = match err {
+                    TerminalError::SessionNotFound(_) | TerminalError::SessionNameNotFound(_) => StatusCode::NOT_FOUND,
+                    TerminalError::SessionExists(_) => StatusCode::CONFLICT,
+                    TerminalError::MissingParameter(_) | TerminalError::UnknownParameter(_) | TerminalError::UnsupportedEncoding(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, err.to_string())
+            }
             ApiError::Io(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
         };
         (status, Json(json!({ "error": message }))).into_response()
@@ -0,0 +1,29 @@
+use crate::{state::AppState, error::ApiError};
+use axum::{extract::State, Json};
+use serde::Deserialize;
+use terminal_core::{ClipboardEntry, ClipboardSource};
+
+pub async fn get(State(state): State<AppState>) -> Result<Json<ClipboardEntry>, ApiError> {
+    state.sessions.clipboard().get()
+        .map(Json)
+        .ok_or(ApiError::NotFound("Clipboard is empty".into()))
+}
+
+#[derive(Deserialize)]
+pub struct SetRequest {
+    pub text: String,
+    pub source: Option<ClipboardSource>,
+}
+
+pub async fn set(
+    State(state): State<AppState>,
+    Json(req): Json<SetRequest>,
+) -> Json<ClipboardEntry> {
+    let source = req.source.unwrap_or(ClipboardSource::Api);
+    Json(state.sessions.clipboard().set(req.text, source))
+}
+
+pub async fn clear(State(state): State<AppState>) -> Json<()> {
+    state.sessions.clipboard().clear();
+    Json(())
+}
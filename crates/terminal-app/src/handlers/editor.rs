@@ -1,9 +1,19 @@
 use crate::{state::AppState, error::ApiError};
 use axum::{extract::{Path, State}, Json};
 use serde::{Deserialize, Serialize};
-use terminal_code_editor::EditorBuffer;
+use terminal_code_editor::{Cursor, EditorBuffer};
 use uuid::Uuid;
 
+/// Write the buffer through to the buffer store, if there is one
+async fn persist(state: &AppState, id: Uuid) {
+    let (Some(store), Some(snapshot)) = (&state.buffer_store, state.editor.snapshot(id)) else {
+        return;
+    };
+    if let Err(e) = store.save(&snapshot).await {
+        tracing::warn!(path = %snapshot.path.display(), error = %e, "Failed to persist editor buffer");
+    }
+}
+
 #[derive(Deserialize)]
 pub struct OpenRequest {
     pub path: String,
@@ -14,6 +24,7 @@ pub async fn open(
     Json(req): Json<OpenRequest>,
 ) -> Result<Json<EditorBuffer>, ApiError> {
     let buffer = state.editor.open(std::path::Path::new(&req.path)).await?;
+    persist(&state, buffer.id).await;
     Ok(Json(buffer))
 }
 
@@ -32,6 +43,7 @@ pub async fn save(
     Json(req): Json<SaveRequest>,
 ) -> Result<Json<SaveResponse>, ApiError> {
     state.editor.save(req.id).await?;
+    persist(&state, req.id).await;
     Ok(Json(SaveResponse { success: true }))
 }
 
@@ -72,6 +84,7 @@ pub async fn update_buffer(
     Json(req): Json<UpdateBufferRequest>,
 ) -> Result<Json<UpdateBufferResponse>, ApiError> {
     if state.editor.set_content(id, &req.content) {
+        persist(&state, id).await;
         Ok(Json(UpdateBufferResponse { success: true }))
     } else {
         Err(ApiError::NotFound(format!("Buffer {}", id)))
@@ -87,9 +100,29 @@ pub async fn close_buffer(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<CloseBufferResponse>, ApiError> {
+    let path = state.editor.get_buffer(id).and_then(|b| b.path);
     if state.editor.close(id) {
+        if let (Some(store), Some(path)) = (&state.buffer_store, path) {
+            if let Err(e) = store.remove(&path).await {
+                tracing::warn!(path = %path.display(), error = %e, "Failed to forget editor buffer");
+            }
+        }
         Ok(Json(CloseBufferResponse { success: true }))
     } else {
         Err(ApiError::NotFound(format!("Buffer {}", id)))
     }
 }
+
+pub async fn set_cursor(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(cursor): Json<Cursor>,
+) -> Result<Json<EditorBuffer>, ApiError> {
+    if !state.editor.set_cursor(id, cursor) {
+        return Err(ApiError::NotFound(format!("Buffer {}", id)));
+    }
+    persist(&state, id).await;
+    state.editor.get_buffer(id)
+        .map(Json)
+        .ok_or(ApiError::NotFound(format!("Buffer {}", id)))
+}
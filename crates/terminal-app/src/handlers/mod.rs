@@ -2,6 +2,8 @@ pub mod terminal;
 pub mod editor;
 pub mod files;
 pub mod webrtc;
+pub mod clipboard;
+pub mod snippets;
 
 use axum::Json;
 use serde::Serialize;
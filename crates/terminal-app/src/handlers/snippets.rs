@@ -0,0 +1,88 @@
+use crate::{state::AppState, error::ApiError};
+use axum::{extract::{Path, State}, Json};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use terminal_core::Snippet;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct ListResponse {
+    pub snippets: Vec<Snippet>,
+    pub count: usize,
+}
+
+pub async fn list(State(state): State<AppState>) -> Json<ListResponse> {
+    let snippets = state.snippets.list();
+    Json(ListResponse { count: snippets.len(), snippets })
+}
+
+pub async fn get(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Snippet>, ApiError> {
+    state.snippets.get(&name)
+        .map(Json)
+        .ok_or(ApiError::NotFound(format!("Snippet '{}'", name)))
+}
+
+#[derive(Deserialize)]
+pub struct SaveRequest {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, Option<String>>,
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+pub async fn save(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(req): Json<SaveRequest>,
+) -> Result<Json<Snippet>, ApiError> {
+    if req.commands.is_empty() {
+        return Err(ApiError::BadRequest("Snippet needs at least one command".into()));
+    }
+    let snippet = Snippet {
+        name,
+        description: req.description,
+        params: req.params,
+        commands: req.commands,
+        delay_ms: req.delay_ms,
+    };
+    state.snippets.save(snippet.clone())?;
+    Ok(Json(snippet))
+}
+
+pub async fn remove(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<()>, ApiError> {
+    state.snippets.remove(&name)?
+        .ok_or(ApiError::NotFound(format!("Snippet '{}'", name)))?;
+    Ok(Json(()))
+}
+
+#[derive(Deserialize, Default)]
+pub struct RunRequest {
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct RunResponse {
+    pub commands: Vec<String>,
+}
+
+pub async fn run(
+    State(state): State<AppState>,
+    Path((id, name)): Path<(Uuid, String)>,
+    body: Option<Json<RunRequest>>,
+) -> Result<Json<RunResponse>, ApiError> {
+    let snippet = state.snippets.get(&name)
+        .ok_or(ApiError::NotFound(format!("Snippet '{}'", name)))?;
+    let req = body.map(|Json(r)| r).unwrap_or_default();
+    let commands = state.sessions.run_snippet(id, &snippet, &req.params).await?;
+    Ok(Json(RunResponse { commands }))
+}
@@ -2,7 +2,7 @@ use crate::{state::AppState, error::ApiError};
 use axum::{extract::{Path, Query, State}, Json};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, time::Duration};
-use terminal_core::{SessionConfig, TerminalSession};
+use terminal_core::{CommandMark, SessionConfig, TerminalSession};
 use uuid::Uuid;
 
 #[derive(Serialize)]
@@ -11,8 +11,17 @@ pub struct ListResponse {
     pub count: usize,
 }
 
-pub async fn list(State(state): State<AppState>) -> Json<ListResponse> {
-    let terminals = state.sessions.list_sessions().await;
+#[derive(Deserialize)]
+pub struct ListQuery {
+    /// Only the sessions this mission owns
+    pub mission_id: Option<String>,
+}
+
+pub async fn list(State(state): State<AppState>, Query(query): Query<ListQuery>) -> Json<ListResponse> {
+    let terminals = match query.mission_id {
+        Some(mission_id) => state.sessions.mission_sessions(&mission_id).await,
+        None => state.sessions.list_sessions().await,
+    };
     Json(ListResponse { count: terminals.len(), terminals })
 }
 
@@ -25,6 +34,13 @@ pub struct CreateRequest {
     pub rows: Option<u16>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Mission that owns the session
+    pub mission_id: Option<String>,
+    /// e.g. `en_US` or `de_DE.ISO-8859-1`
+    pub locale: Option<String>,
+    pub encoding: Option<String>,
+    /// OSC 133 prompt marks for bash and zsh (default on)
+    pub shell_integration: Option<bool>,
 }
 
 pub async fn create(
@@ -38,6 +54,10 @@ pub async fn create(
         cols: req.cols,
         rows: req.rows,
         env: Some(req.env),
+        mission_id: req.mission_id,
+        locale: req.locale,
+        encoding: req.encoding,
+        shell_integration: req.shell_integration,
     };
     let session = state.sessions.create_session(config).await?;
     Ok(Json(session))
@@ -113,6 +133,10 @@ fn default_timeout() -> u64 {
 #[derive(Serialize)]
 pub struct ExecWaitResponse {
     pub output: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// The shell reported the command finished (needs shell integration)
+    pub completed: bool,
     pub duration_ms: u64,
 }
 
@@ -122,9 +146,11 @@ pub async fn exec_wait(
     Json(req): Json<ExecWaitRequest>,
 ) -> Result<Json<ExecWaitResponse>, ApiError> {
     let start = std::time::Instant::now();
-    let output = state.sessions.exec_wait(id, &req.command, Duration::from_millis(req.timeout_ms)).await?;
+    let result = state.sessions.exec_wait(id, &req.command, Duration::from_millis(req.timeout_ms)).await?;
     Ok(Json(ExecWaitResponse {
-        output,
+        output: result.output,
+        exit_code: result.exit_code,
+        completed: result.completed,
         duration_ms: start.elapsed().as_millis() as u64,
     }))
 }
@@ -143,6 +169,24 @@ pub async fn write(
     Ok(Json(()))
 }
 
+#[derive(Deserialize)]
+pub struct PasteRequest {
+    /// Text to paste; defaults to the workspace clipboard
+    pub text: Option<String>,
+}
+
+pub async fn paste(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<PasteRequest>,
+) -> Result<Json<()>, ApiError> {
+    let text = req.text
+        .or_else(|| state.sessions.clipboard().text())
+        .ok_or(ApiError::BadRequest("Nothing to paste: clipboard is empty".into()))?;
+    state.sessions.paste(id, &text).await?;
+    Ok(Json(()))
+}
+
 #[derive(Deserialize)]
 pub struct ResizeRequest {
     pub cols: u16,
@@ -185,3 +229,17 @@ pub async fn flush_buffer(
     state.sessions.flush_buffer(id).await?;
     Ok(Json(()))
 }
+
+#[derive(Serialize)]
+pub struct CommandsResponse {
+    pub commands: Vec<CommandMark>,
+}
+
+/// Commands segmented by the shell's OSC 133 marks
+pub async fn commands(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommandsResponse>, ApiError> {
+    let commands = state.sessions.commands(id).await?;
+    Ok(Json(CommandsResponse { commands }))
+}
@@ -25,7 +25,7 @@ pub async fn handle_offer(
     });
 
     let answer_sdp = state.webrtc.handle_offer(peer_id, &req.sdp).await
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
     Ok(Json(OfferResponse {
         peer_id,
@@ -49,7 +49,7 @@ pub async fn handle_answer(
     Json(req): Json<AnswerRequest>,
 ) -> Result<Json<AnswerResponse>, ApiError> {
     state.webrtc.handle_answer(req.peer_id, &req.sdp).await
-        .map_err(|e| ApiError::Internal(e))?;
+        .map_err(ApiError::Internal)?;
 
     Ok(Json(AnswerResponse { success: true }))
 }
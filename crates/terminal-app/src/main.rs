@@ -1,4 +1,5 @@
 mod state;
+mod buffer_store;
 mod routes;
 mod handlers;
 mod error;
@@ -18,7 +19,7 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let state = AppState::from_env();
+    let state = AppState::from_env().await;
     let app = routes::create_router(state);
 
     let host = std::env::var("TERMINAL_HOST").unwrap_or_else(|_| "0.0.0.0".into());
@@ -14,12 +14,26 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/terminals/:id/exec", post(handlers::terminal::exec))
         .route("/api/terminals/:id/exec/wait", post(handlers::terminal::exec_wait))
         .route("/api/terminals/:id/write", post(handlers::terminal::write))
+        .route("/api/terminals/:id/paste", post(handlers::terminal::paste))
         .route("/api/terminals/:id/resize", post(handlers::terminal::resize))
         .route("/api/terminals/:id/buffer", get(handlers::terminal::get_buffer))
         .route("/api/terminals/:id/buffer", delete(handlers::terminal::flush_buffer))
+        .route("/api/terminals/:id/commands", get(handlers::terminal::commands))
+        .route("/api/terminals/:id/snippets/:name/run", post(handlers::snippets::run))
         .route("/api/terminals/by-name/:name", get(handlers::terminal::get_by_name))
         .route("/api/terminals/by-name/:name/exec", post(handlers::terminal::exec_by_name))
 
+        // SNIPPET API
+        .route("/api/snippets", get(handlers::snippets::list))
+        .route("/api/snippets/:name", get(handlers::snippets::get))
+        .route("/api/snippets/:name", put(handlers::snippets::save))
+        .route("/api/snippets/:name", delete(handlers::snippets::remove))
+
+        // CLIPBOARD API
+        .route("/api/clipboard", get(handlers::clipboard::get))
+        .route("/api/clipboard", put(handlers::clipboard::set))
+        .route("/api/clipboard", delete(handlers::clipboard::clear))
+
         // EDITOR API
         .route("/api/editor/open", post(handlers::editor::open))
         .route("/api/editor/save", post(handlers::editor::save))
@@ -27,6 +41,7 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/editor/buffers/:id", get(handlers::editor::get_buffer))
         .route("/api/editor/buffers/:id", put(handlers::editor::update_buffer))
         .route("/api/editor/buffers/:id", delete(handlers::editor::close_buffer))
+        .route("/api/editor/buffers/:id/cursor", put(handlers::editor::set_cursor))
 
         // FILE API
         .route("/api/files", get(handlers::files::list))
@@ -1,5 +1,6 @@
+use crate::buffer_store::BufferStore;
 use std::{path::PathBuf, sync::Arc};
-use terminal_core::SessionManager;
+use terminal_core::{SessionManager, SnippetStore};
 use terminal_code_editor::EditorManager;
 use terminal_file::FileManager;
 use terminal_webrtc::WebRtcManager;
@@ -10,10 +11,13 @@ pub struct AppState {
     pub editor: Arc<EditorManager>,
     pub files: Arc<FileManager>,
     pub webrtc: Arc<WebRtcManager>,
+    pub snippets: Arc<SnippetStore>,
+    /// Where open editor buffers are persisted, if `TERMINAL_DATABASE_URL` is set
+    pub buffer_store: Option<Arc<BufferStore>>,
 }
 
 impl AppState {
-    pub fn from_env() -> Self {
+    pub async fn from_env() -> Self {
         let workspace = std::env::var("TERMINAL_WORKSPACE")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("/home/spawn/spawn"));
@@ -23,9 +27,28 @@ impl AppState {
             .parse()
             .unwrap_or(10);
 
+        let editor = Arc::new(EditorManager::new());
+        let buffer_store = match std::env::var("TERMINAL_DATABASE_URL") {
+            Ok(url) => match BufferStore::connect(&url).await {
+                Ok(store) => {
+                    if let Err(e) = store.restore(&editor).await {
+                        tracing::warn!(error = %e, "Failed to restore editor buffers");
+                    }
+                    Some(Arc::new(store))
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Editor buffer store unavailable; buffers won't persist");
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+
         Self {
             sessions: Arc::new(SessionManager::new(workspace.clone(), max_sessions)),
-            editor: Arc::new(EditorManager::new()),
+            editor,
+            buffer_store,
+            snippets: Arc::new(SnippetStore::new(workspace.clone())),
             files: Arc::new(FileManager::new(workspace)),
             webrtc: Arc::new(WebRtcManager::new()),
         }
@@ -1,6 +1,7 @@
 use parking_lot::RwLock;
 use ropey::Rope;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{collections::HashMap, path::{Path, PathBuf}, sync::Arc};
 use uuid::Uuid;
 
@@ -12,6 +13,53 @@ pub struct EditorBuffer {
     pub language: Language,
     pub modified: bool,
     pub line_count: usize,
+    #[serde(default)]
+    pub cursor: Cursor,
+    /// Hash of the file as the buffer last read or saved it
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// The file changed on disk under unsaved edits; saving overwrites it
+    #[serde(default)]
+    pub stale: bool,
+}
+
+/// Zero-based cursor position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// An open buffer as persisted between restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferSnapshot {
+    pub path: PathBuf,
+    pub content_hash: String,
+    /// Unsaved content; `None` when the buffer matches the file
+    pub unsaved: Option<String>,
+    pub cursor: Cursor,
+}
+
+/// How a restored buffer was reconciled with the file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reconciled {
+    /// The file is unchanged; unsaved edits were reapplied
+    Restored,
+    /// The file changed and there were no unsaved edits, so it was reloaded
+    Reloaded,
+    /// The file changed under unsaved edits; they are kept and the buffer is
+    /// marked stale
+    Conflict,
+    /// The file is gone; its unsaved edits are kept in the buffer
+    Missing,
+    /// The file is gone and nothing was unsaved, so it wasn't reopened
+    Dropped,
+}
+
+/// Hex SHA-256 of file content
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -73,6 +121,10 @@ impl EditorManager {
         }
 
         let content = tokio::fs::read_to_string(path).await?;
+        Ok(self.insert(path, &content))
+    }
+
+    fn insert(&self, path: &Path, content: &str) -> EditorBuffer {
         let id = Uuid::new_v4();
         let name = path
             .file_name()
@@ -82,7 +134,7 @@ impl EditorManager {
             .extension()
             .map(|e| Language::from_extension(&e.to_string_lossy()))
             .unwrap_or(Language::Unknown);
-        let rope = Rope::from_str(&content);
+        let rope = Rope::from_str(content);
 
         let info = EditorBuffer {
             id,
@@ -91,13 +143,77 @@ impl EditorManager {
             language,
             modified: false,
             line_count: rope.len_lines(),
+            cursor: Cursor::default(),
+            content_hash: Some(content_hash(content)),
+            stale: false,
         };
 
         self.buffers
             .write()
             .insert(id, BufferInner { info: info.clone(), rope });
         self.path_index.write().insert(path.to_path_buf(), id);
-        Ok(info)
+        info
+    }
+
+    /// Reopen a persisted buffer, reconciling it with the file as it is now
+    pub async fn restore(&self, snapshot: &BufferSnapshot) -> Result<(Reconciled, Option<EditorBuffer>), std::io::Error> {
+        let path = snapshot.path.as_path();
+        let (reconciled, info) = match tokio::fs::read_to_string(path).await {
+            Ok(disk) => {
+                let info = self.insert(path, &disk);
+                let changed = content_hash(&disk) != snapshot.content_hash;
+                match (&snapshot.unsaved, changed) {
+                    (None, false) => (Reconciled::Restored, info),
+                    (None, true) => (Reconciled::Reloaded, info),
+                    (Some(unsaved), changed) => {
+                        self.set_content(info.id, unsaved);
+                        // Still based on the old file, so it stays stale across restarts
+                        if let Some(b) = self.buffers.write().get_mut(&info.id).filter(|_| changed) {
+                            b.info.stale = true;
+                            b.info.content_hash = Some(snapshot.content_hash.clone());
+                        }
+                        (if changed { Reconciled::Conflict } else { Reconciled::Restored }, info)
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => match &snapshot.unsaved {
+                Some(unsaved) => {
+                    let info = self.insert(path, unsaved);
+                    if let Some(b) = self.buffers.write().get_mut(&info.id) {
+                        b.info.modified = true;
+                        b.info.content_hash = None;
+                    }
+                    (Reconciled::Missing, info)
+                }
+                None => return Ok((Reconciled::Dropped, None)),
+            },
+            Err(e) => return Err(e),
+        };
+        self.set_cursor(info.id, snapshot.cursor);
+        Ok((reconciled, self.get_buffer(info.id)))
+    }
+
+    /// The buffer as it should be persisted; `None` for buffers with no file
+    /// behind them yet
+    pub fn snapshot(&self, id: Uuid) -> Option<BufferSnapshot> {
+        let buffers = self.buffers.read();
+        let b = buffers.get(&id)?;
+        Some(BufferSnapshot {
+            path: b.info.path.clone()?,
+            content_hash: b.info.content_hash.clone().unwrap_or_default(),
+            unsaved: b.info.modified.then(|| b.rope.to_string()),
+            cursor: b.info.cursor,
+        })
+    }
+
+    /// Move the cursor, clamped to the buffer's last line
+    pub fn set_cursor(&self, id: Uuid, cursor: Cursor) -> bool {
+        if let Some(b) = self.buffers.write().get_mut(&id) {
+            b.info.cursor = Cursor { line: cursor.line.min(b.info.line_count.saturating_sub(1)), ..cursor };
+            true
+        } else {
+            false
+        }
     }
 
     pub fn get_content(&self, id: Uuid) -> Option<String> {
@@ -128,9 +244,11 @@ impl EditorManager {
                 b.rope.to_string(),
             )
         };
-        tokio::fs::write(&path, content).await?;
+        tokio::fs::write(&path, &content).await?;
         if let Some(b) = self.buffers.write().get_mut(&id) {
             b.info.modified = false;
+            b.info.stale = false;
+            b.info.content_hash = Some(content_hash(&content));
         }
         Ok(())
     }
@@ -160,3 +278,45 @@ impl Default for EditorManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restore_reconciles_with_disk() {
+        let dir = std::env::temp_dir().join(format!("editor-restore-{}", Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.rs");
+        std::fs::write(&path, "fn main() {}\n").unwrap();
+        let snapshot = |unsaved: Option<&str>| BufferSnapshot {
+            path: path.clone(),
+            content_hash: content_hash("fn main() {}\n"),
+            unsaved: unsaved.map(String::from),
+            cursor: Cursor { line: 7, column: 2 },
+        };
+
+        let editor = EditorManager::new();
+        let (reconciled, buffer) = editor.restore(&snapshot(Some("fn main() { todo!() }\n"))).await.unwrap();
+        let buffer = buffer.unwrap();
+        assert_eq!(reconciled, Reconciled::Restored);
+        assert!(buffer.modified && !buffer.stale);
+        assert_eq!(buffer.cursor, Cursor { line: 1, column: 2 });
+        assert_eq!(editor.snapshot(buffer.id).unwrap().unsaved.as_deref(), Some("fn main() { todo!() }\n"));
+
+        std::fs::write(&path, "fn main() { println!() }\n").unwrap();
+        let editor = EditorManager::new();
+        assert_eq!(editor.restore(&snapshot(None)).await.unwrap().0, Reconciled::Reloaded);
+        let editor = EditorManager::new();
+        let (reconciled, buffer) = editor.restore(&snapshot(Some("edited\n"))).await.unwrap();
+        assert_eq!(reconciled, Reconciled::Conflict);
+        assert!(buffer.unwrap().stale);
+
+        std::fs::remove_file(&path).unwrap();
+        let editor = EditorManager::new();
+        assert!(matches!(editor.restore(&snapshot(None)).await.unwrap(), (Reconciled::Dropped, None)));
+        assert_eq!(editor.restore(&snapshot(Some("edited\n"))).await.unwrap().0, Reconciled::Missing);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
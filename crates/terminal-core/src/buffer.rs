@@ -1,9 +1,31 @@
+use serde::Serialize;
 use std::collections::VecDeque;
 
+/// Commands remembered for output segmentation
+const MAX_COMMANDS: usize = 1000;
+
+/// Where one command's output sits in the buffer, from OSC 133 shell
+/// integration marks. Line numbers count from the start of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CommandMark {
+    /// Commands run in the session before this one
+    pub seq: u64,
+    pub output_start: u64,
+    /// Line after the last line of output; `None` while the command runs
+    pub output_end: Option<u64>,
+    pub exit_code: Option<i32>,
+}
+
 pub struct TerminalBuffer {
     lines: VecDeque<String>,
     max_lines: usize,
     current_line: String,
+    /// Lines dropped off the front, so `lines[0]` is line number `evicted`
+    evicted: u64,
+    commands: VecDeque<CommandMark>,
+    commands_started: u64,
+    /// The shell has sent OSC 133 marks
+    shell_integration: bool,
 }
 
 impl TerminalBuffer {
@@ -12,22 +34,113 @@ impl TerminalBuffer {
             lines: VecDeque::with_capacity(max_lines),
             max_lines,
             current_line: String::new(),
+            evicted: 0,
+            commands: VecDeque::new(),
+            commands_started: 0,
+            shell_integration: false,
         }
     }
 
+    fn push_line(&mut self, line: String) {
+        self.lines.push_back(line);
+        if self.lines.len() > self.max_lines {
+            self.lines.pop_front();
+            self.evicted += 1;
+        }
+    }
+
+    /// Number of the line being written
+    pub fn line_number(&self) -> u64 {
+        self.evicted + self.lines.len() as u64
+    }
+
     pub fn push(&mut self, data: &[u8]) {
         for byte in data {
             if *byte == b'\n' {
-                self.lines.push_back(std::mem::take(&mut self.current_line));
-                if self.lines.len() > self.max_lines {
-                    self.lines.pop_front();
-                }
+                let line = std::mem::take(&mut self.current_line);
+                self.push_line(line);
             } else if *byte != b'\r' {
                 self.current_line.push(*byte as char);
             }
         }
     }
 
+    /// Append a printable character to the current line
+    pub fn print(&mut self, c: char) {
+        self.current_line.push(c);
+    }
+
+    /// Finish the current line
+    pub fn newline(&mut self) {
+        let line = std::mem::take(&mut self.current_line);
+        self.push_line(line);
+    }
+
+    /// OSC 133 A/B: a prompt or the command line is being drawn
+    pub fn mark_prompt(&mut self) {
+        self.shell_integration = true;
+    }
+
+    /// OSC 133 C: the command line was accepted and its output starts here
+    pub fn mark_command_start(&mut self) {
+        self.shell_integration = true;
+        if !self.current_line.is_empty() {
+            self.newline();
+        }
+        self.commands.push_back(CommandMark {
+            seq: self.commands_started,
+            output_start: self.line_number(),
+            output_end: None,
+            exit_code: None,
+        });
+        self.commands_started += 1;
+        if self.commands.len() > MAX_COMMANDS {
+            self.commands.pop_front();
+        }
+    }
+
+    /// OSC 133 D: the running command finished. Shells also send this before
+    /// a prompt that no command preceded; those are ignored.
+    pub fn mark_command_end(&mut self, exit_code: Option<i32>) {
+        self.shell_integration = true;
+        if self.commands.back().is_none_or(|c| c.output_end.is_some()) {
+            return;
+        }
+        if !self.current_line.is_empty() {
+            self.newline();
+        }
+        let end = self.line_number();
+        if let Some(command) = self.commands.back_mut() {
+            command.output_end = Some(end);
+            command.exit_code = exit_code;
+        }
+    }
+
+    pub fn has_shell_integration(&self) -> bool {
+        self.shell_integration
+    }
+
+    /// Commands started so far; the next one gets this `seq`
+    pub fn commands_started(&self) -> u64 {
+        self.commands_started
+    }
+
+    pub fn command(&self, seq: u64) -> Option<CommandMark> {
+        self.commands.iter().find(|c| c.seq == seq).copied()
+    }
+
+    pub fn commands(&self) -> Vec<CommandMark> {
+        self.commands.iter().copied().collect()
+    }
+
+    /// A command's output lines still in the buffer; up to the current line
+    /// while it runs
+    pub fn command_output(&self, command: &CommandMark) -> Vec<String> {
+        let start = command.output_start.saturating_sub(self.evicted) as usize;
+        let end = command.output_end.unwrap_or(self.line_number()).saturating_sub(self.evicted) as usize;
+        self.lines.iter().skip(start).take(end.saturating_sub(start)).cloned().collect()
+    }
+
     pub fn get_all(&self) -> Vec<String> {
         self.lines.iter().cloned().collect()
     }
@@ -37,6 +150,7 @@ impl TerminalBuffer {
     }
 
     pub fn clear(&mut self) {
+        self.evicted += self.lines.len() as u64;
         self.lines.clear();
         self.current_line.clear();
     }
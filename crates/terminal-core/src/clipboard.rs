@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// Where the clipboard contents came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardSource {
+    /// Copied by a program in a terminal via OSC 52
+    Terminal,
+    Editor,
+    Agent,
+    Api,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardEntry {
+    pub text: String,
+    pub source: ClipboardSource,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Server-side clipboard shared by every terminal, editor and agent tool in a workspace
+pub struct Clipboard {
+    entry: RwLock<Option<ClipboardEntry>>,
+    max_bytes: usize,
+}
+
+impl Clipboard {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entry: RwLock::new(None),
+            max_bytes,
+        }
+    }
+
+    pub fn get(&self) -> Option<ClipboardEntry> {
+        self.entry.read().clone()
+    }
+
+    pub fn text(&self) -> Option<String> {
+        self.entry.read().as_ref().map(|e| e.text.clone())
+    }
+
+    /// Store text, truncated to the size limit on a char boundary
+    pub fn set(&self, mut text: String, source: ClipboardSource) -> ClipboardEntry {
+        if text.len() > self.max_bytes {
+            let mut end = self.max_bytes;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+        }
+        let entry = ClipboardEntry { text, source, updated_at: Utc::now() };
+        *self.entry.write() = Some(entry.clone());
+        entry
+    }
+
+    pub fn clear(&self) {
+        *self.entry.write() = None;
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Self::new(1024 * 1024)
+    }
+}
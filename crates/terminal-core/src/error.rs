@@ -22,4 +22,13 @@ pub enum TerminalError {
 
     #[error("Timeout waiting for output")]
     Timeout,
+
+    #[error("Missing snippet parameter: {0}")]
+    MissingParameter(String),
+
+    #[error("Unknown snippet parameter: {0}")]
+    UnknownParameter(String),
+
+    #[error("Unsupported encoding: {0}")]
+    UnsupportedEncoding(String),
 }
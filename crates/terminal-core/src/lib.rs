@@ -1,8 +1,15 @@
 pub mod pty;
 pub mod session;
 pub mod buffer;
+pub mod clipboard;
+pub mod parser;
+pub mod snippets;
+pub mod shell_integration;
 pub mod error;
 
-pub use session::{SessionManager, TerminalSession, SessionConfig, SessionStatus};
-pub use buffer::TerminalBuffer;
+pub use session::{ExecOutput, SessionManager, TerminalSession, SessionConfig, SessionStatus};
+pub use buffer::{CommandMark, TerminalBuffer};
+pub use clipboard::{Clipboard, ClipboardEntry, ClipboardSource};
+pub use parser::{VtEvent, VtParser};
+pub use snippets::{Snippet, SnippetStore};
 pub use error::TerminalError;
@@ -0,0 +1,137 @@
+use crate::buffer::TerminalBuffer;
+use base64::Engine;
+use vte::{Params, Perform};
+
+/// Side effects of terminal output that the session has to act on
+#[derive(Debug, Clone, PartialEq)]
+pub enum VtEvent {
+    /// OSC 52 write: a program copied text to the clipboard
+    ClipboardSet(String),
+    /// OSC 52 read: a program asked for the clipboard contents
+    ClipboardQuery,
+    /// DECSET/DECRST 2004
+    BracketedPaste(bool),
+}
+
+/// Parses PTY output into plain text lines, stripping escape sequences and
+/// surfacing the ones the server cares about as [`VtEvent`]s
+pub struct VtParser {
+    parser: vte::Parser,
+}
+
+impl VtParser {
+    pub fn new() -> Self {
+        Self { parser: vte::Parser::new() }
+    }
+
+    pub fn advance(&mut self, buffer: &mut TerminalBuffer, data: &[u8]) -> Vec<VtEvent> {
+        let mut performer = Performer { buffer, events: Vec::new() };
+        for byte in data {
+            self.parser.advance(&mut performer, *byte);
+        }
+        performer.events
+    }
+}
+
+impl Default for VtParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// OSC 52 reply carrying the clipboard contents
+pub fn osc52_reply(text: &str) -> Vec<u8> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    format!("\x1b]52;c;{}\x07", encoded).into_bytes()
+}
+
+struct Performer<'a> {
+    buffer: &'a mut TerminalBuffer,
+    events: Vec<VtEvent>,
+}
+
+impl Perform for Performer<'_> {
+    fn print(&mut self, c: char) {
+        self.buffer.print(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.buffer.newline(),
+            b'\t' => self.buffer.print('\t'),
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        // OSC 133 ; <A|B|C|D> [; <exit code>]
+        if params.first() == Some(&&b"133"[..]) {
+            match params.get(1).and_then(|p| p.first()) {
+                Some(b'A' | b'B') => self.buffer.mark_prompt(),
+                Some(b'C') => self.buffer.mark_command_start(),
+                Some(b'D') => {
+                    let exit_code = params.get(2)
+                        .and_then(|code| std::str::from_utf8(code).ok())
+                        .and_then(|code| code.parse().ok());
+                    self.buffer.mark_command_end(exit_code);
+                }
+                _ => {}
+            }
+            return;
+        }
+        // OSC 52 ; <selection> ; <base64 data | ?>
+        if params.len() < 3 || params[0] != b"52" {
+            return;
+        }
+        let payload = params[2];
+        if payload == b"?" {
+            self.events.push(VtEvent::ClipboardQuery);
+        } else if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(payload) {
+            self.events.push(VtEvent::ClipboardSet(String::from_utf8_lossy(&bytes).into_owned()));
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        if intermediates != b"?" || !matches!(action, 'h' | 'l') {
+            return;
+        }
+        if params.iter().any(|p| p.first() == Some(&2004)) {
+            self.events.push(VtEvent::BracketedPaste(action == 'h'));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_escapes_and_reports_osc52() {
+        let mut buffer = TerminalBuffer::new(10);
+        let mut parser = VtParser::new();
+        let events = parser.advance(
+            &mut buffer,
+            b"\x1b[?2004h\x1b[32mok\x1b[0m\r\n\x1b]52;c;aGVsbG8=\x07\x1b]52;c;?\x1b\\",
+        );
+        assert_eq!(buffer.get_all(), vec!["ok".to_string()]);
+        assert_eq!(events, vec![
+            VtEvent::BracketedPaste(true),
+            VtEvent::ClipboardSet("hello".into()),
+            VtEvent::ClipboardQuery,
+        ]);
+    }
+
+    #[test]
+    fn segments_output_by_osc133_marks() {
+        let mut buffer = TerminalBuffer::new(10);
+        let mut parser = VtParser::new();
+        parser.advance(&mut buffer, b"\x1b]133;D;0\x07\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        parser.advance(&mut buffer, b"a.txt\r\nb.txt\r\n\x1b]133;D;2\x07\x1b]133;A\x07$ ");
+
+        assert!(buffer.has_shell_integration());
+        let command = buffer.command(0).unwrap();
+        assert_eq!(command.exit_code, Some(2));
+        assert_eq!(buffer.command_output(&command), vec!["a.txt".to_string(), "b.txt".to_string()]);
+        assert_eq!(buffer.commands_started(), 1);
+    }
+}
@@ -4,7 +4,8 @@ use std::{collections::HashMap, io::{Read, Write}, path::Path, sync::Arc};
 use tokio::sync::Mutex;
 
 pub struct PtyHandle {
-    reader: Arc<Mutex<Box<dyn Read + Send>>>,
+    /// Until a session's output pump takes it
+    reader: Mutex<Option<Box<dyn Read + Send>>>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
     pid: Option<u32>,
 }
@@ -23,18 +24,29 @@ impl PtyHandle {
         writer.write(data).map_err(TerminalError::Io)
     }
 
+    /// Read output directly; an error once `take_reader` has handed the
+    /// reader to someone else
     pub async fn read(&self, buf: &mut [u8]) -> Result<usize, TerminalError> {
         let mut reader = self.reader.lock().await;
+        let reader = reader.as_mut()
+            .ok_or_else(|| TerminalError::Pty("PTY output is already being read elsewhere".into()))?;
         reader.read(buf).map_err(TerminalError::Io)
     }
 
-    pub fn try_clone_reader(&self) -> Arc<Mutex<Box<dyn Read + Send>>> {
-        Arc::clone(&self.reader)
+    /// Sole ownership of the output, for a reader that blocks on it without
+    /// holding a lock anyone else waits for
+    pub fn take_reader(&self) -> Option<Box<dyn Read + Send>> {
+        self.reader.try_lock().ok()?.take()
+    }
+
+    pub fn try_clone_writer(&self) -> Arc<Mutex<Box<dyn Write + Send>>> {
+        Arc::clone(&self.writer)
     }
 }
 
 pub async fn spawn_pty(
     shell: &str,
+    args: &[String],
     cwd: &Path,
     cols: u16,
     rows: u16,
@@ -47,6 +59,7 @@ pub async fn spawn_pty(
         .map_err(|e| TerminalError::Pty(e.to_string()))?;
 
     let mut cmd = CommandBuilder::new(shell);
+    cmd.args(args);
     cmd.cwd(cwd);
 
     for (key, value) in env {
@@ -73,7 +86,7 @@ pub async fn spawn_pty(
     drop(child);
 
     Ok(PtyHandle {
-        reader: Arc::new(Mutex::new(reader)),
+        reader: Mutex::new(Some(reader)),
         writer: Arc::new(Mutex::new(writer)),
         pid,
     })
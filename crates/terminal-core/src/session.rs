@@ -1,10 +1,29 @@
-use crate::{pty::PtyHandle, buffer::TerminalBuffer, TerminalError};
+use crate::{
+    pty::PtyHandle,
+    buffer::{CommandMark, TerminalBuffer},
+    clipboard::{Clipboard, ClipboardSource},
+    parser::{osc52_reply, VtEvent, VtParser},
+    snippets::Snippet,
+    TerminalError,
+};
 use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
+    time::Duration,
+};
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+const BRACKETED_PASTE_START: &str = "\x1b[200~";
+const BRACKETED_PASTE_END: &str = "\x1b[201~";
+const DEFAULT_LOCALE: &str = "C";
+const DEFAULT_ENCODING: &str = "UTF-8";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalSession {
     pub id: Uuid,
@@ -16,6 +35,18 @@ pub struct TerminalSession {
     pub created_at: DateTime<Utc>,
     pub status: SessionStatus,
     pub pid: Option<u32>,
+    /// Mission that owns the session; it is closed when the mission ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mission_id: Option<String>,
+    /// `LANG`/`LC_ALL` of the shell
+    #[serde(default)]
+    pub locale: String,
+    /// Character encoding of the session's input and output
+    #[serde(default)]
+    pub encoding: String,
+    /// The shell was started with OSC 133 hooks
+    #[serde(default)]
+    pub shell_integration: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -35,6 +66,29 @@ pub struct SessionConfig {
     pub cols: Option<u16>,
     pub rows: Option<u16>,
     pub env: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub mission_id: Option<String>,
+    /// Locale name, e.g. `en_US` (default `C`); a codeset in it
+    /// (`de_DE.ISO-8859-1`) sets the encoding too
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Encoding label, e.g. `UTF-8` (default), `ISO-8859-1`, `Shift_JIS`
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// Start bash or zsh with OSC 133 prompt marks (default on)
+    #[serde(default)]
+    pub shell_integration: Option<bool>,
+}
+
+/// What `exec_wait` saw of a command
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecOutput {
+    pub output: String,
+    /// Exit status, when the shell reports it
+    pub exit_code: Option<i32>,
+    /// The shell marked the command finished; false when the wait timed out
+    /// or the shell has no integration
+    pub completed: bool,
 }
 
 pub struct SessionManager {
@@ -43,12 +97,16 @@ pub struct SessionManager {
     max_sessions: usize,
     default_shell: String,
     workspace_root: PathBuf,
+    clipboard: Arc<Clipboard>,
 }
 
 struct SessionInner {
     pub info: TerminalSession,
     pub handle: PtyHandle,
-    pub buffer: TerminalBuffer,
+    pub buffer: Arc<parking_lot::Mutex<TerminalBuffer>>,
+    /// Set while the shell has bracketed paste mode enabled
+    pub bracketed_paste: Arc<AtomicBool>,
+    pub encoding: &'static Encoding,
 }
 
 impl SessionManager {
@@ -62,9 +120,15 @@ impl SessionManager {
             max_sessions,
             default_shell,
             workspace_root,
+            clipboard: Arc::new(Clipboard::default()),
         }
     }
 
+    /// Workspace clipboard, written by OSC 52 and read by paste
+    pub fn clipboard(&self) -> Arc<Clipboard> {
+        Arc::clone(&self.clipboard)
+    }
+
     pub async fn create_session(&self, config: SessionConfig) -> Result<TerminalSession, TerminalError> {
         if self.sessions.read().await.len() >= self.max_sessions {
             return Err(TerminalError::MaxSessions);
@@ -79,9 +143,26 @@ impl SessionManager {
         let cwd = config.cwd.unwrap_or_else(|| self.workspace_root.clone());
         let cols = config.cols.unwrap_or(120);
         let rows = config.rows.unwrap_or(40);
-        let env = config.env.unwrap_or_default();
+        let mut env = config.env.unwrap_or_default();
 
-        let handle = crate::pty::spawn_pty(&shell, &cwd, cols, rows, env).await?;
+        let locale = config.locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        let (locale, label) = match locale.split_once('.') {
+            Some((name, codeset)) => (name.to_string(), codeset.to_string()),
+            None => (locale, config.encoding.unwrap_or_else(|| DEFAULT_ENCODING.to_string())),
+        };
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| TerminalError::UnsupportedEncoding(label.clone()))?;
+        let lang = format!("{}.{}", locale, label);
+        env.entry("LANG".into()).or_insert_with(|| lang.clone());
+        env.entry("LC_ALL".into()).or_insert(lang);
+
+        let args = if config.shell_integration.unwrap_or(true) {
+            crate::shell_integration::prepare(&shell, &mut env)?
+        } else {
+            None
+        };
+        let shell_integration = args.is_some();
+        let handle = crate::pty::spawn_pty(&shell, &args.unwrap_or_default(), &cwd, cols, rows, env).await?;
         let pid = handle.child_pid();
 
         let session = TerminalSession {
@@ -94,13 +175,20 @@ impl SessionManager {
             created_at: Utc::now(),
             status: SessionStatus::Running,
             pid,
+            mission_id: config.mission_id,
+            locale,
+            encoding: label,
+            shell_integration,
         };
 
         let inner = SessionInner {
             info: session.clone(),
             handle,
-            buffer: TerminalBuffer::new(10000),
+            buffer: Arc::new(parking_lot::Mutex::new(TerminalBuffer::new(10000))),
+            bracketed_paste: Arc::new(AtomicBool::new(false)),
+            encoding,
         };
+        self.spawn_output_pump(id, &inner);
 
         self.sessions.write().await.insert(id, inner);
         self.name_index.write().await.insert(config.name, id);
@@ -108,6 +196,68 @@ impl SessionManager {
         Ok(session)
     }
 
+    /// Read PTY output on a blocking thread, feeding the buffer through the
+    /// VT parser and handling OSC 52 / bracketed paste mode changes. The
+    /// thread owns the reader, so its blocking reads lock nothing.
+    fn spawn_output_pump(&self, id: Uuid, inner: &SessionInner) {
+        let Some(mut reader) = inner.handle.take_reader() else {
+            tracing::warn!(session = %id, "Terminal output already taken; not pumping it");
+            return;
+        };
+        let writer = inner.handle.try_clone_writer();
+        let buffer = Arc::clone(&inner.buffer);
+        let bracketed_paste = Arc::clone(&inner.bracketed_paste);
+        let clipboard = Arc::clone(&self.clipboard);
+        let sessions = Arc::clone(&self.sessions);
+        // The parser reads UTF-8; anything else is decoded first
+        let mut decoder = (inner.encoding != UTF_8).then(|| inner.encoding.new_decoder());
+
+        std::thread::spawn(move || {
+            let mut parser = VtParser::new();
+            let mut buf = [0u8; 8192];
+            let mut decoded = String::new();
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let output = match &mut decoder {
+                    Some(decoder) => {
+                        decoded.clear();
+                        decoded.reserve(decoder.max_utf8_buffer_length(n).unwrap_or(n * 3));
+                        let _ = decoder.decode_to_string(&buf[..n], &mut decoded, false);
+                        decoded.as_bytes()
+                    }
+                    None => &buf[..n],
+                };
+                let events = parser.advance(&mut buffer.lock(), output);
+                for event in events {
+                    match event {
+                        VtEvent::ClipboardSet(text) => {
+                            clipboard.set(text, ClipboardSource::Terminal);
+                        }
+                        VtEvent::ClipboardQuery => {
+                            // Written from its own thread: waiting on the writer
+                            // here would stop output from being drained
+                            let reply = osc52_reply(&clipboard.text().unwrap_or_default());
+                            let writer = Arc::clone(&writer);
+                            std::thread::spawn(move || {
+                                let _ = writer.blocking_lock().write_all(&reply);
+                            });
+                        }
+                        VtEvent::BracketedPaste(enabled) => {
+                            bracketed_paste.store(enabled, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+            tracing::debug!(session = %id, "Terminal output closed");
+            if let Some(session) = sessions.blocking_write().get_mut(&id) {
+                session.info.status = SessionStatus::Stopped;
+            }
+        });
+    }
+
     pub async fn get_session(&self, id: Uuid) -> Option<TerminalSession> {
         self.sessions.read().await.get(&id).map(|s| s.info.clone())
     }
@@ -127,31 +277,60 @@ impl SessionManager {
             .ok_or(TerminalError::SessionNotFound(id))?;
 
         let cmd = format!("{}\n", command);
-        session.handle.write(cmd.as_bytes()).await?;
+        let (bytes, _, _) = session.encoding.encode(&cmd);
+        session.handle.write(&bytes).await?;
         Ok(())
     }
 
-    pub async fn exec_wait(&self, id: Uuid, command: &str, timeout: Duration) -> Result<String, TerminalError> {
+    /// Run a command and wait for it to finish. With shell integration that
+    /// is the command's OSC 133 end mark and the output is exactly its own;
+    /// without, this waits out `timeout` and returns the recent output.
+    pub async fn exec_wait(&self, id: Uuid, command: &str, timeout: Duration) -> Result<ExecOutput, TerminalError> {
+        let buffer = {
+            let sessions = self.sessions.read().await;
+            let session = sessions.get(&id).ok_or(TerminalError::SessionNotFound(id))?;
+            Arc::clone(&session.buffer)
+        };
+        let seq = buffer.lock().commands_started();
         self.exec(id, command).await?;
 
         let start = std::time::Instant::now();
-        let mut output = String::new();
+        let mut output = ExecOutput { output: String::new(), exit_code: None, completed: false };
 
         while start.elapsed() < timeout {
             tokio::time::sleep(Duration::from_millis(50)).await;
 
-            let sessions = self.sessions.read().await;
-            if let Some(session) = sessions.get(&id) {
-                let new_output = session.buffer.get_recent(100);
-                if !new_output.is_empty() {
-                    output = new_output.join("\n");
+            let buffer = buffer.lock();
+            match buffer.command(seq) {
+                Some(mark) => {
+                    output.output = buffer.command_output(&mark).join("\n");
+                    if mark.output_end.is_some() {
+                        output.exit_code = mark.exit_code;
+                        output.completed = true;
+                        return Ok(output);
+                    }
+                }
+                None if !buffer.has_shell_integration() => {
+                    let recent = buffer.get_recent(100);
+                    if !recent.is_empty() {
+                        output.output = recent.join("\n");
+                    }
                 }
+                None => {}
             }
         }
 
         Ok(output)
     }
 
+    /// The commands the shell has marked, oldest first
+    pub async fn commands(&self, id: Uuid) -> Result<Vec<CommandMark>, TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions.get(&id).ok_or(TerminalError::SessionNotFound(id))?;
+        let commands = session.buffer.lock().commands();
+        Ok(commands)
+    }
+
     pub async fn write(&self, id: Uuid, data: &[u8]) -> Result<(), TerminalError> {
         let sessions = self.sessions.read().await;
         let session = sessions.get(&id)
@@ -160,6 +339,42 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Run a snippet's commands in order; returns the commands that were sent
+    pub async fn run_snippet(
+        &self,
+        id: Uuid,
+        snippet: &Snippet,
+        args: &BTreeMap<String, String>,
+    ) -> Result<Vec<String>, TerminalError> {
+        let commands = snippet.render(args)?;
+        for (i, command) in commands.iter().enumerate() {
+            if i > 0 && snippet.delay_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(snippet.delay_ms)).await;
+            }
+            self.exec(id, command).await?;
+        }
+        Ok(commands)
+    }
+
+    /// Paste text into a session, wrapped in bracketed-paste markers when the
+    /// shell has enabled them so multi-line text is not executed line by line
+    pub async fn paste(&self, id: Uuid, text: &str) -> Result<(), TerminalError> {
+        let sessions = self.sessions.read().await;
+        let session = sessions.get(&id)
+            .ok_or(TerminalError::SessionNotFound(id))?;
+
+        let data = if session.bracketed_paste.load(Ordering::Relaxed) {
+            // An embedded end marker would let pasted text escape the bracket
+            let text = text.replace(BRACKETED_PASTE_END, "");
+            format!("{}{}{}", BRACKETED_PASTE_START, text, BRACKETED_PASTE_END)
+        } else {
+            text.replace("\r\n", "\r").replace('\n', "\r")
+        };
+        let (bytes, _, _) = session.encoding.encode(&data);
+        session.handle.write(&bytes).await?;
+        Ok(())
+    }
+
     pub async fn resize(&self, id: Uuid, cols: u16, rows: u16) -> Result<(), TerminalError> {
         let mut sessions = self.sessions.write().await;
         let session = sessions.get_mut(&id)
@@ -185,11 +400,19 @@ impl SessionManager {
         self.sessions.read().await.values().map(|s| s.info.clone()).collect()
     }
 
+    /// Sessions owned by a mission
+    pub async fn mission_sessions(&self, mission_id: &str) -> Vec<TerminalSession> {
+        self.sessions.read().await.values()
+            .filter(|s| s.info.mission_id.as_deref() == Some(mission_id))
+            .map(|s| s.info.clone())
+            .collect()
+    }
+
     pub async fn flush_buffer(&self, id: Uuid) -> Result<(), TerminalError> {
         let mut sessions = self.sessions.write().await;
         let session = sessions.get_mut(&id)
             .ok_or(TerminalError::SessionNotFound(id))?;
-        session.buffer.clear();
+        session.buffer.lock().clear();
         Ok(())
     }
 
@@ -199,8 +422,8 @@ impl SessionManager {
             .ok_or(TerminalError::SessionNotFound(id))?;
 
         Ok(match lines {
-            Some(n) => session.buffer.get_recent(n),
-            None => session.buffer.get_all(),
+            Some(n) => session.buffer.lock().get_recent(n),
+            None => session.buffer.lock().get_all(),
         })
     }
 }
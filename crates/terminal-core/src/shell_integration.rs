@@ -0,0 +1,60 @@
+//! OSC 133 shell integration
+//!
+//! bash and zsh are started with a wrapper rc file that loads the user's own
+//! and then marks the prompt (`A`), the command line (`B`), the start of a
+//! command's output (`C`) and its end with the exit status (`D`). The output
+//! pump records the marks in the buffer, which is what lets `exec_wait`
+//! return as soon as a command finishes. Other shells start unchanged.
+
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+const BASH_RC: &str = r#"[ -f ~/.bashrc ] && . ~/.bashrc
+__spawn_osc133_precmd() { printf '\033]133;D;%s\007\033]133;A\007' "$?"; }
+PROMPT_COMMAND="__spawn_osc133_precmd${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+PS1="$PS1\[\033]133;B\007\]"
+PS0='\e]133;C\a'"$PS0"
+"#;
+
+const ZSH_ENV: &str = r#"[ -f "${SPAWN_USER_ZDOTDIR:-$HOME}/.zshenv" ] && . "${SPAWN_USER_ZDOTDIR:-$HOME}/.zshenv"
+"#;
+
+const ZSH_RC: &str = r#"ZDOTDIR="${SPAWN_USER_ZDOTDIR:-$HOME}"
+[ -f "$ZDOTDIR/.zshrc" ] && . "$ZDOTDIR/.zshrc"
+__spawn_osc133_precmd() { print -n "\e]133;D;$?\a\e]133;A\a"; }
+__spawn_osc133_preexec() { print -n "\e]133;C\a"; }
+precmd_functions=(__spawn_osc133_precmd $precmd_functions)
+preexec_functions+=(__spawn_osc133_preexec)
+PS1="$PS1%{"$'\e]133;B\a'"%}"
+"#;
+
+/// Arguments to start `shell` with its hooks, adding whatever it needs to
+/// `env`; `None` for shells without hooks
+pub fn prepare(shell: &str, env: &mut HashMap<String, String>) -> std::io::Result<Option<Vec<String>>> {
+    let dir = std::env::temp_dir().join("spawn-shell-integration");
+    match Path::new(shell).file_name().and_then(|n| n.to_str()) {
+        Some("bash") => {
+            let rc = write_file(&dir.join("bash"), ".bashrc", BASH_RC)?;
+            Ok(Some(vec!["--rcfile".into(), rc.to_string_lossy().into_owned()]))
+        }
+        Some("zsh") => {
+            let zdotdir = dir.join("zsh");
+            write_file(&zdotdir, ".zshenv", ZSH_ENV)?;
+            write_file(&zdotdir, ".zshrc", ZSH_RC)?;
+            if let Ok(user) = std::env::var("ZDOTDIR") {
+                env.entry("SPAWN_USER_ZDOTDIR".into()).or_insert(user);
+            }
+            env.insert("ZDOTDIR".into(), zdotdir.to_string_lossy().into_owned());
+            Ok(Some(Vec::new()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn write_file(dir: &Path, name: &str, content: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(content) {
+        std::fs::write(&path, content)?;
+    }
+    Ok(path)
+}
@@ -0,0 +1,148 @@
+use crate::TerminalError;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// A named, parameterized command sequence, e.g. "start db, run migrations,
+/// start server". Commands may reference parameters as `{{name}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Parameter names, optionally with defaults
+    #[serde(default)]
+    pub params: BTreeMap<String, Option<String>>,
+    pub commands: Vec<String>,
+    /// Pause between commands, for steps that need the previous one to settle
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+impl Snippet {
+    /// Substitute parameters into the commands. Each `{{name}}` is replaced
+    /// once, so values containing `{{...}}` come out as written; arguments or
+    /// placeholders the snippet doesn't declare are errors.
+    pub fn render(&self, args: &BTreeMap<String, String>) -> Result<Vec<String>, TerminalError> {
+        if let Some(unknown) = args.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(TerminalError::UnknownParameter(unknown.clone()));
+        }
+        let mut values = BTreeMap::new();
+        for (name, default) in &self.params {
+            let value = args.get(name).or(default.as_ref())
+                .ok_or_else(|| TerminalError::MissingParameter(name.clone()))?;
+            values.insert(name.as_str(), value.as_str());
+        }
+
+        self.commands.iter().map(|command| substitute(command, &values)).collect()
+    }
+}
+
+fn substitute(command: &str, values: &BTreeMap<&str, &str>) -> Result<String, TerminalError> {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = values.get(name).ok_or_else(|| TerminalError::UnknownParameter(name.to_string()))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Snippets for a workspace, persisted in `.spawn/snippets.json`
+pub struct SnippetStore {
+    path: PathBuf,
+    snippets: RwLock<BTreeMap<String, Snippet>>,
+}
+
+impl SnippetStore {
+    pub fn new(workspace_root: PathBuf) -> Self {
+        let path = workspace_root.join(".spawn").join("snippets.json");
+        let snippets = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<Vec<Snippet>>(&s).ok())
+            .map(|list| list.into_iter().map(|s| (s.name.clone(), s)).collect())
+            .unwrap_or_default();
+
+        Self { path, snippets: RwLock::new(snippets) }
+    }
+
+    pub fn list(&self) -> Vec<Snippet> {
+        self.snippets.read().values().cloned().collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Snippet> {
+        self.snippets.read().get(name).cloned()
+    }
+
+    pub fn save(&self, snippet: Snippet) -> Result<(), TerminalError> {
+        let mut snippets = self.snippets.write();
+        snippets.insert(snippet.name.clone(), snippet);
+        self.persist(&snippets)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<Option<Snippet>, TerminalError> {
+        let mut snippets = self.snippets.write();
+        let removed = snippets.remove(name);
+        if removed.is_some() {
+            self.persist(&snippets)?;
+        }
+        Ok(removed)
+    }
+
+    fn persist(&self, snippets: &BTreeMap<String, Snippet>) -> Result<(), TerminalError> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let list: Vec<&Snippet> = snippets.values().collect();
+        let json = serde_json::to_string_pretty(&list)
+            .map_err(|e| TerminalError::Io(std::io::Error::other(e)))?;
+        std::fs::write(&self.path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(commands: &[&str]) -> Snippet {
+        Snippet {
+            name: "dev".into(),
+            description: String::new(),
+            params: BTreeMap::from([("port".to_string(), Some("3000".to_string())), ("db".to_string(), None)]),
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            delay_ms: 0,
+        }
+    }
+
+    fn args(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_render_substitutes_once() {
+        let snippet = snippet(&["start {{db}}", "serve --port {{ port }} --db {{db}}"]);
+        assert_eq!(
+            snippet.render(&args(&[("db", "pg")])).unwrap(),
+            vec!["start pg", "serve --port 3000 --db pg"],
+        );
+        // A value that looks like a placeholder isn't expanded again
+        assert_eq!(snippet.render(&args(&[("db", "{{port}}")])).unwrap()[0], "start {{port}}");
+    }
+
+    #[test]
+    fn test_render_rejects_missing_and_unknown() {
+        let snippet = snippet(&["start {{db}}"]);
+        assert!(matches!(snippet.render(&args(&[])), Err(TerminalError::MissingParameter(p)) if p == "db"));
+        assert!(matches!(snippet.render(&args(&[("db", "pg"), ("host", "x")])), Err(TerminalError::UnknownParameter(p)) if p == "host"));
+        let undeclared = Snippet { commands: vec!["run {{env}}".into()], ..snippet };
+        assert!(matches!(undeclared.render(&args(&[("db", "pg")])), Err(TerminalError::UnknownParameter(p)) if p == "env"));
+    }
+}
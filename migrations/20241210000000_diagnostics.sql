@@ -0,0 +1,18 @@
+-- Linter / compiler diagnostics
+
+CREATE TABLE IF NOT EXISTS diagnostics (
+    id TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    file TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    col INTEGER,
+    end_line INTEGER,
+    end_col INTEGER,
+    severity TEXT NOT NULL,
+    code TEXT,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_diagnostics_file ON diagnostics(file);
+CREATE INDEX IF NOT EXISTS idx_diagnostics_source ON diagnostics(source);
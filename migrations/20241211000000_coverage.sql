@@ -0,0 +1,11 @@
+-- Per-file test coverage
+
+CREATE TABLE IF NOT EXISTS coverage (
+    file TEXT NOT NULL,
+    source TEXT NOT NULL,
+    lines_total INTEGER NOT NULL,
+    lines_covered INTEGER NOT NULL,
+    uncovered_lines TEXT NOT NULL,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (source, file)
+);
@@ -0,0 +1,17 @@
+-- Mission tags and saved filters
+
+CREATE TABLE IF NOT EXISTS mission_tags (
+    mission_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (mission_id, tag),
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_mission_tags_tag ON mission_tags(tag);
+
+CREATE TABLE IF NOT EXISTS saved_filters (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    filter TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
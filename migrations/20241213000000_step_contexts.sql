@@ -0,0 +1,12 @@
+-- Exact LLM input per orchestrator step (gzip-compressed JSON message array)
+
+CREATE TABLE IF NOT EXISTS step_contexts (
+    mission_id TEXT NOT NULL,
+    step INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    message_count INTEGER NOT NULL,
+    messages BLOB NOT NULL,
+    created_at DATETIME NOT NULL,
+    PRIMARY KEY (mission_id, step),
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
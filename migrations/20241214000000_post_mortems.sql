@@ -0,0 +1,13 @@
+-- Failure analysis for failed missions
+
+CREATE TABLE IF NOT EXISTS post_mortems (
+    mission_id TEXT PRIMARY KEY,
+    category TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    remediation TEXT NOT NULL,
+    model TEXT,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_mortems_category ON post_mortems(category);
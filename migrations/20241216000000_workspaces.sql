@@ -0,0 +1,7 @@
+-- Additional workspace roots (the server's WORKSPACE_ROOT is always "default")
+
+CREATE TABLE IF NOT EXISTS workspaces (
+    name TEXT PRIMARY KEY,
+    root TEXT NOT NULL UNIQUE,
+    created_at DATETIME NOT NULL
+);
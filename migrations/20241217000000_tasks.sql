@@ -0,0 +1,16 @@
+-- Mission plans as a DAG of tasks
+
+CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    mission_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    status TEXT NOT NULL,
+    depends_on TEXT NOT NULL DEFAULT '[]',
+    assigned_agent TEXT,
+    result TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tasks_mission ON tasks(mission_id);
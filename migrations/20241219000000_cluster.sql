@@ -0,0 +1,17 @@
+-- Cluster mode: live nodes and which node holds each in-memory session
+
+CREATE TABLE IF NOT EXISTS cluster_nodes (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    heartbeat_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS session_owners (
+    kind TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    node_id TEXT NOT NULL,
+    claimed_at DATETIME NOT NULL,
+    PRIMARY KEY (kind, session_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_owners_node ON session_owners(node_id);
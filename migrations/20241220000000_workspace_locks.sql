@@ -0,0 +1,9 @@
+-- Advisory locks on workspace resources (files, git repositories), shared by every node
+
+CREATE TABLE IF NOT EXISTS workspace_locks (
+    resource TEXT PRIMARY KEY,
+    holder TEXT NOT NULL,
+    purpose TEXT NOT NULL,
+    acquired_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL
+);
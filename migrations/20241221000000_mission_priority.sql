@@ -0,0 +1,3 @@
+-- Mission scheduling priority, stored like status (JSON string)
+
+ALTER TABLE missions ADD COLUMN priority TEXT NOT NULL DEFAULT '"normal"';
@@ -0,0 +1,8 @@
+-- Knowledge base chunks cited in completed missions' answers
+
+CREATE TABLE IF NOT EXISTS mission_citations (
+    mission_id TEXT PRIMARY KEY,
+    citations TEXT NOT NULL DEFAULT '[]',
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
@@ -0,0 +1,20 @@
+-- A/B experiments over mission settings and the variant each mission ran with
+
+CREATE TABLE IF NOT EXISTS experiments (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    variants TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS mission_experiments (
+    mission_id TEXT PRIMARY KEY,
+    experiment_id TEXT NOT NULL,
+    variant TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id),
+    FOREIGN KEY (experiment_id) REFERENCES experiments(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_mission_experiments_experiment ON mission_experiments(experiment_id);
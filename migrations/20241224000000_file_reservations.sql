@@ -0,0 +1,9 @@
+-- Soft claims on files with unsaved editor changes or unsettled mission changes
+
+CREATE TABLE IF NOT EXISTS file_reservations (
+    path TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    created_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Mission spend and time ceilings (JSON object; unset fields don't apply)
+
+ALTER TABLE missions ADD COLUMN budget TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,15 @@
+-- Responses to requests sent with an Idempotency-Key, replayed when the
+-- request is retried
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT PRIMARY KEY,
+    -- Method, path and body of the request that claimed the key
+    request_hash TEXT NOT NULL,
+    -- NULL while that request is still running
+    status INTEGER,
+    content_type TEXT,
+    response BLOB,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created ON idempotency_keys(created_at);
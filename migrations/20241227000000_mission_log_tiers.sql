@@ -0,0 +1,4 @@
+-- Full form of a log line (the whole LLM response, untruncated tool output)
+-- when it differs from the concise one in `content`. Cleared by retention.
+
+ALTER TABLE mission_logs ADD COLUMN verbose TEXT;
@@ -0,0 +1,9 @@
+-- Language of prompts and messages per workspace and per user
+
+CREATE TABLE IF NOT EXISTS locales (
+    scope TEXT NOT NULL,
+    id TEXT NOT NULL,
+    locale TEXT NOT NULL,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (scope, id)
+);
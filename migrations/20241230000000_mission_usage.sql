@@ -0,0 +1,12 @@
+-- LLM usage per mission, added to after every step; finished missions are
+-- the history new missions' cost estimates come from
+
+CREATE TABLE IF NOT EXISTS mission_usage (
+    mission_id TEXT PRIMARY KEY,
+    steps INTEGER NOT NULL DEFAULT 0,
+    prompt_tokens INTEGER NOT NULL DEFAULT 0,
+    completion_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (mission_id) REFERENCES missions(id)
+);
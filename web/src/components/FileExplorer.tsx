@@ -26,6 +26,21 @@ export default function FileExplorer() {
     fetchFiles()
   }, [])
 
+  // Files a mission's tools changed: reload the tree and any of them open
+  useEffect(() => {
+    const events = new EventSource(`${API_BASE}/api/events`)
+    events.addEventListener('files_changed', async (e) => {
+      const { paths } = JSON.parse((e as MessageEvent).data) as { paths: string[] }
+      fetchFiles()
+      const { openFiles, updateFileContent } = useAppStore.getState()
+      for (const path of paths.filter(p => openFiles.includes(p))) {
+        const res = await fetch(`${API_BASE}/api/files/${encodeURIComponent(path)}`)
+        if (res.ok) updateFileContent(path, await res.text())
+      }
+    })
+    return () => events.close()
+  }, [])
+
   const fetchFiles = async () => {
     setLoading(true)
     setError(null)